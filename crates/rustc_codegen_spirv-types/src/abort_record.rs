@@ -0,0 +1,229 @@
+//! Host-side decoding of "abort record" buffers, which the first `panic!` (or other
+//! abort) of a dispatch is written to, with `ShaderPanicStrategy::AbortRecordThenExit`
+//! (see also `CompileResult::abort_record`).
+//!
+//! The abort record buffer is a storage buffer of `u32` words, laid out as:
+//! - word `0`: number of invocations which aborted, which has to be reset to `0`
+//!   before each dispatch (only the first aborting invocation writes its record)
+//! - word `1`: scratch space, overwritten by all the other aborting invocations
+//! - words `2..`: the record, i.e. a message id (an index into `AbortRecordInfo::messages`),
+//!   followed by the words of the message arguments, and then the entry-point inputs
+//!
+//! (the header is the same as that of the shader log buffer, see `SHADER_LOG_HEADER_WORDS`).
+
+use crate::{DescriptorBinding, SHADER_LOG_HEADER_WORDS, ShaderLogFormat};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Information needed to set up, and decode, the abort record buffer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbortRecordInfo {
+    pub binding: DescriptorBinding,
+
+    /// All the messages that can be reported, indexed by their message ids.
+    pub messages: Vec<AbortMessage>,
+}
+
+/// A (static) abort site, in a specific entry-point.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AbortMessage {
+    /// Kind of abort, e.g. `"panic"`.
+    pub kind: String,
+
+    pub location: Option<AbortSrcLoc>,
+
+    /// The decoded `format_args!` of the `panic!` (with its runtime arguments).
+    pub message: ShaderLogFormat,
+
+    /// The entry-point name, followed by its inputs (if they were requested to be
+    /// recorded, with e.g. `ShaderPanicStrategy::AbortRecordThenExit::record_inputs`),
+    /// e.g. `main_cs(id = vec3(%v3u))`.
+    pub entry_point: ShaderLogFormat,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AbortSrcLoc {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for AbortSrcLoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// Abort decoded from an abort record buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbortRecord {
+    /// Number of invocations which aborted (only the first one is described by the record).
+    pub aborted_invocations: u32,
+
+    pub kind: String,
+    pub location: Option<AbortSrcLoc>,
+    pub message: String,
+    pub entry_point: String,
+}
+
+impl fmt::Display for AbortRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // NOTE `"panic"` -> `"panicked"` like in Rust, the general case being
+        // e.g. `"abort"` -> `"aborted"`.
+        match &self.kind[..] {
+            "panic" => write!(f, "panicked")?,
+            kind => write!(f, "{kind}ed")?,
+        }
+        if let Some(location) = &self.location {
+            write!(f, " at {location}")?;
+        }
+        write!(f, ":\n{}\n  in {}", self.message, self.entry_point)?;
+        if self.aborted_invocations > 1 {
+            let others = self.aborted_invocations - 1;
+            let s = if others == 1 { "" } else { "s" };
+            write!(f, "\n  (and {others} other invocation{s})")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AbortRecordError {
+    #[error("abort record buffer too small ({len} words), must have at least {required}")]
+    BufferTooSmall { len: usize, required: usize },
+    #[error("unknown message id {0} in abort record")]
+    UnknownMessage(u32),
+}
+
+impl AbortMessage {
+    /// Number of words taken up by the record (i.e. its message id and arguments).
+    pub fn record_words(&self) -> usize {
+        self.message.record_words() + self.entry_point.record_words() - 1
+    }
+}
+
+impl AbortRecordInfo {
+    /// Minimum size (in words) of the abort record buffer, to fit any of the messages.
+    pub fn buffer_words(&self) -> usize {
+        SHADER_LOG_HEADER_WORDS
+            + self
+                .messages
+                .iter()
+                .map(|message| message.record_words())
+                .max()
+                .unwrap_or(1)
+    }
+
+    /// Decode the contents of an abort record buffer (see the module-level docs for its
+    /// layout), returning `None` if no invocation aborted.
+    pub fn decode(&self, buffer: &[u32]) -> Result<Option<AbortRecord>, AbortRecordError> {
+        let required = self.buffer_words();
+        if buffer.len() < required {
+            return Err(AbortRecordError::BufferTooSmall {
+                len: buffer.len(),
+                required,
+            });
+        }
+        let aborted_invocations = buffer[0];
+        if aborted_invocations == 0 {
+            return Ok(None);
+        }
+        let record = &buffer[SHADER_LOG_HEADER_WORDS..];
+        let message_id = record[0];
+        let message = self
+            .messages
+            .get(message_id as usize)
+            .ok_or(AbortRecordError::UnknownMessage(message_id))?;
+        let (message_words, entry_point_words) =
+            record[1..message.record_words()].split_at(message.message.record_words() - 1);
+        Ok(Some(AbortRecord {
+            aborted_invocations,
+            kind: message.kind.clone(),
+            location: message.location.clone(),
+            message: message.message.format(message_words),
+            entry_point: message.entry_point.format(entry_point_words),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ShaderLogArg, ShaderLogScalar};
+
+    fn info() -> AbortRecordInfo {
+        let format = |format: &str, args: &[(ShaderLogScalar, u32)]| ShaderLogFormat {
+            format: format.to_string(),
+            args: args
+                .iter()
+                .map(|&(scalar, components)| ShaderLogArg { scalar, components })
+                .collect(),
+        };
+        AbortRecordInfo {
+            binding: DescriptorBinding {
+                descriptor_set: 0,
+                binding: 7,
+            },
+            messages: vec![
+                AbortMessage {
+                    kind: "panic".to_string(),
+                    location: None,
+                    message: format("explicit panic", &[]),
+                    entry_point: format("main_fs()", &[]),
+                },
+                AbortMessage {
+                    kind: "panic".to_string(),
+                    location: Some(AbortSrcLoc {
+                        file: "src/lib.rs".to_string(),
+                        line: 12,
+                        column: 5,
+                    }),
+                    message: format(
+                        "index out of bounds: the len is %u but the index is %u",
+                        &[(ShaderLogScalar::U32, 1), (ShaderLogScalar::U32, 1)],
+                    ),
+                    entry_point: format("main_cs(id = vec3(%v3u))", &[(ShaderLogScalar::U32, 3)]),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let info = info();
+        assert_eq!(info.buffer_words(), 2 + 6);
+
+        assert_eq!(info.decode(&[0; 8]).unwrap(), None);
+
+        let record = info
+            .decode(&[3, 0xdead, 1, 4, 9, 1, 2, 3])
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.aborted_invocations, 3);
+        assert_eq!(
+            record.to_string(),
+            "panicked at src/lib.rs:12:5:\n\
+             index out of bounds: the len is 4 but the index is 9\n  \
+             in main_cs(id = vec3(1, 2, 3))\n  \
+             (and 2 other invocations)"
+        );
+
+        let record = info.decode(&[1, 0, 0, 0, 0, 0, 0, 0]).unwrap().unwrap();
+        assert_eq!(
+            record.to_string(),
+            "panicked:\nexplicit panic\n  in main_fs()"
+        );
+
+        assert!(matches!(
+            info.decode(&[1, 0, 0]),
+            Err(AbortRecordError::BufferTooSmall {
+                len: 3,
+                required: 8
+            })
+        ));
+        assert!(matches!(
+            info.decode(&[1, 0, 2, 0, 0, 0, 0, 0]),
+            Err(AbortRecordError::UnknownMessage(2))
+        ));
+    }
+}
@@ -1,3 +1,6 @@
+use crate::{
+    AbortRecord, AbortRecordError, AbortRecordInfo, ShaderLog, ShaderLogError, ShaderLogFormat,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::Write;
@@ -34,9 +37,53 @@ impl ModuleResult {
 pub struct CompileResult {
     pub entry_points: Vec<String>,
     pub module: ModuleResult,
+
+    /// Bindings of runtime-sized descriptor arrays (e.g. `&RuntimeArray<T>` or
+    /// `&DescriptorTable<T>` entry-point parameters), across all entry-points,
+    /// which usually need `VARIABLE_DESCRIPTOR_COUNT` (and the highest binding
+    /// number in their descriptor set) when creating descriptor set layouts.
+    #[serde(default)]
+    pub variable_count_bindings: Vec<DescriptorBinding>,
+
+    /// Format strings (and argument types) of the records written to the shader log
+    /// buffer, indexed by their format ids (see [`ShaderLog::decode`]), when one was
+    /// requested (e.g. via `SpirvBuilder::shader_log_buffer`).
+    #[serde(default)]
+    pub shader_log_formats: Vec<ShaderLogFormat>,
+
+    /// Binding and message table of the abort record buffer (see [`AbortRecordInfo::decode`]),
+    /// when `ShaderPanicStrategy::AbortRecordThenExit` is used (and any entry-point can abort).
+    #[serde(default)]
+    pub abort_record: Option<AbortRecordInfo>,
+}
+
+/// A `(descriptor_set, binding)` pair, as used by `#[spirv(descriptor_set = ..., binding = ...)]`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DescriptorBinding {
+    pub descriptor_set: u32,
+    pub binding: u32,
 }
 
 impl CompileResult {
+    /// Decode the contents of the shader log buffer written to by these shaders
+    /// (see also [`ShaderLog::decode`]).
+    pub fn decode_shader_log(&self, buffer: &[u32]) -> Result<ShaderLog, ShaderLogError> {
+        ShaderLog::decode(&self.shader_log_formats, buffer)
+    }
+
+    /// Decode the contents of the abort record buffer written to by these shaders
+    /// (see also [`AbortRecordInfo::decode`]), returning `None` if no invocation aborted
+    /// (or if there is no abort record buffer at all).
+    pub fn decode_abort_record(
+        &self,
+        buffer: &[u32],
+    ) -> Result<Option<AbortRecord>, AbortRecordError> {
+        match &self.abort_record {
+            Some(info) => info.decode(buffer),
+            None => Ok(None),
+        }
+    }
+
     pub fn codegen_entry_point_strings(&self) -> String {
         let trie = Trie::create_from(self.entry_points.iter().map(|x| x as &str));
         let mut builder = String::new();
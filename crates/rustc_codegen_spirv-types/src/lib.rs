@@ -2,14 +2,22 @@
 
 pub use rspirv::spirv::Capability;
 
+mod abort_record;
 mod compile_result;
+mod link_stats;
 mod rustc_version;
+mod shader_log;
 mod target;
 mod target_spec;
+mod zombie_report;
+pub use abort_record::*;
 pub use compile_result::*;
+pub use link_stats::*;
 pub use rustc_version::*;
+pub use shader_log::*;
 pub use target::*;
 pub use target_spec::*;
+pub use zombie_report::*;
 
 // HACK(eddyb) allows downstream crates to access the correct version directly.
 pub use serde;
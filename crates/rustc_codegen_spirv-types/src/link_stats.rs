@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Statistics about one run of the Rust-GPU linker, written as JSON by the
+/// `--link-stats=FILE` codegen arg (or `SpirvBuilder::link_stats`).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LinkStats {
+    /// Wall time (in milliseconds) of the whole link, including loading the
+    /// input modules and post-link processing (`spirv-opt` and `spirv-val`).
+    pub total_wall_time_ms: f64,
+
+    /// Whether linking itself was skipped, due to a `--link-cache` hit
+    /// (in which case `passes` and `inlining` will be empty).
+    pub link_cache_hit: bool,
+
+    /// Entry-points whose output modules were reused from the `--link-cache`
+    /// (with `--module-output=multiple`), instead of being linked again.
+    pub link_cache_entry_point_hits: Vec<String>,
+
+    /// All passes, in the order they ran.
+    pub passes: Vec<LinkPassStats>,
+
+    /// Functions which were inlined into at least one caller, or which had
+    /// some calls to them left in the final module.
+    pub inlining: Vec<InliningStats>,
+
+    pub output_modules: Vec<OutputModuleStats>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkPassStats {
+    pub name: String,
+
+    /// Which output module this pass ran on, for the passes that run after the
+    /// module is split per entry-point (i.e. with `--module-output=multiple`).
+    pub output_module: Option<String>,
+
+    pub wall_time_ms: f64,
+
+    /// Module counts before and after the pass, if the pass runs on SPIR-V
+    /// (SPIR-T passes only have their timing recorded).
+    pub before: Option<ModuleCounts>,
+    pub after: Option<ModuleCounts>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleCounts {
+    /// All instructions in the module, including those in functions.
+    pub instructions: usize,
+
+    pub functions: usize,
+    pub types: usize,
+    pub constants: usize,
+    pub global_variables: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InliningStats {
+    pub callee: String,
+
+    /// Calls that were inlined, grouped by why inlining happened, which is
+    /// either a heuristic (`"inline hint"`, from `#[inline]`, or, with
+    /// `--inline-mode=size`, `"single call site"`/`"small function"`), or the
+    /// reason inlining was required to legalize the call (e.g. `"panicking"`).
+    pub inlined_calls: Vec<InlinedCallsStats>,
+
+    /// Number of calls to this function left in the module after inlining.
+    pub remaining_calls: usize,
+
+    /// Whether the function was marked `#[inline(never)]`.
+    pub dont_inline: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InlinedCallsStats {
+    pub cause: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputModuleStats {
+    /// Path of the final `.spv` file.
+    pub path: PathBuf,
+
+    pub entry_points: Vec<EntryPointStats>,
+
+    /// Counts (and size in bytes) as produced by the linker, before `spirv-opt`.
+    pub counts_before_opt: ModuleCounts,
+    pub size_before_opt: usize,
+
+    /// Size in bytes of the final module (i.e. after `spirv-opt`, if it ran).
+    pub size: usize,
+
+    /// Wall time (in milliseconds) of `spirv-opt` and `spirv-val`, if they ran
+    /// (and their results weren't reused from the `--link-cache`).
+    pub spirv_opt_wall_time_ms: Option<f64>,
+    pub spirv_val_wall_time_ms: Option<f64>,
+}
+
+/// Size estimate of one entry-point, before `spirv-opt`, based on all the
+/// functions reachable from it (which may be shared with other entry-points).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntryPointStats {
+    pub name: String,
+    pub reachable_functions: usize,
+    pub reachable_function_instructions: usize,
+}
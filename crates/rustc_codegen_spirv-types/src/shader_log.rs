@@ -0,0 +1,520 @@
+//! Host-side decoding of "shader log" buffers, which `debug_printf!`s (and `panic!` messages)
+//! can be lowered to (see `SpirvBuilder::shader_log_buffer`), instead of being left for
+//! the Vulkan Validation Layers' `debugPrintf` implementation to handle.
+//!
+//! The shader log buffer is a storage buffer of `u32` words, laid out as:
+//! - word `0`: write cursor, i.e. the number of record words appended so far
+//!   (including dropped ones), which has to be reset to `0` to reuse the buffer
+//! - word `1`: scratch space, overwritten by records which don't fit in the buffer
+//! - words `2..`: records, each one being a format id (i.e. an index into
+//!   `CompileResult::shader_log_formats`), followed by the words of its arguments
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+/// Number of words at the start of the shader log buffer, before any records.
+pub const SHADER_LOG_HEADER_WORDS: usize = 2;
+
+/// Format string (and argument types) of the records with a specific format id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ShaderLogFormat {
+    /// C-style format string, as accepted by `NonSemantic.DebugPrintf`
+    /// (i.e. `printf`, extended with e.g. `%v3f` for vectors).
+    pub format: String,
+    pub args: Vec<ShaderLogArg>,
+}
+
+/// Type of a `ShaderLogFormat` argument, which determines how many words it takes up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ShaderLogArg {
+    pub scalar: ShaderLogScalar,
+    /// Number of vector components, or `1` for scalars.
+    pub components: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShaderLogScalar {
+    U32,
+    I32,
+    F32,
+    /// 64-bit scalars take up two words (least significant word first).
+    U64,
+    I64,
+    F64,
+}
+
+impl ShaderLogScalar {
+    pub fn words(self) -> usize {
+        match self {
+            Self::U32 | Self::I32 | Self::F32 => 1,
+            Self::U64 | Self::I64 | Self::F64 => 2,
+        }
+    }
+}
+
+impl ShaderLogArg {
+    pub fn words(self) -> usize {
+        self.scalar.words() * self.components as usize
+    }
+}
+
+impl ShaderLogFormat {
+    /// Number of words taken up by each record (i.e. its format id and arguments).
+    pub fn record_words(&self) -> usize {
+        1 + self.args.iter().map(|arg| arg.words()).sum::<usize>()
+    }
+
+    /// Format the arguments of a record (`arg_words`, excluding the format id),
+    /// following the (C-style) format string, like `debugPrintf` would.
+    pub fn format(&self, arg_words: &[u32]) -> String {
+        let mut out = String::new();
+        let mut args = self.args.iter();
+        let mut arg_words = arg_words;
+        let mut rest = &self.format[..];
+        while let Some(i) = rest.find('%') {
+            out += &rest[..i];
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix('%') {
+                out.push('%');
+                rest = after;
+                continue;
+            }
+            let Some((spec, after)) = Spec::parse(rest) else {
+                // Not a (supported) specifier, print it as-is.
+                out.push('%');
+                continue;
+            };
+            let Some(&arg) = args.next().filter(|arg| arg.words() <= arg_words.len()) else {
+                out.push('%');
+                out += &rest[..rest.len() - after.len()];
+                rest = after;
+                continue;
+            };
+            let (words, remaining_words) = arg_words.split_at(arg.words());
+            arg_words = remaining_words;
+            rest = after;
+
+            for (i, component) in words.chunks(arg.scalar.words()).enumerate() {
+                if i > 0 {
+                    out += ", ";
+                }
+                let bits = match *component {
+                    [lo] => u64::from(lo),
+                    [lo, hi] => u64::from(lo) | (u64::from(hi) << 32),
+                    _ => unreachable!(),
+                };
+                spec.format(&mut out, arg.scalar, bits);
+            }
+        }
+        out += rest;
+        out
+    }
+}
+
+/// Messages decoded from a shader log buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShaderLog {
+    pub messages: Vec<String>,
+
+    /// Number of words (across all the records that didn't fit in the buffer) dropped.
+    pub dropped_words: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShaderLogError {
+    #[error(
+        "shader log buffer too small ({0} words), must have more than {SHADER_LOG_HEADER_WORDS}"
+    )]
+    BufferTooSmall(usize),
+    #[error("unknown format id {format_id} in shader log record (at word {offset})")]
+    UnknownFormat { offset: usize, format_id: u32 },
+}
+
+impl ShaderLog {
+    /// Decode the contents of a shader log buffer (see the module-level docs for its layout),
+    /// using the format table (usually `CompileResult::shader_log_formats`) of the shaders
+    /// which wrote to it.
+    pub fn decode(formats: &[ShaderLogFormat], buffer: &[u32]) -> Result<Self, ShaderLogError> {
+        if buffer.len() <= SHADER_LOG_HEADER_WORDS {
+            return Err(ShaderLogError::BufferTooSmall(buffer.len()));
+        }
+        let (header, records) = buffer.split_at(SHADER_LOG_HEADER_WORDS);
+        let written = header[0] as usize;
+        let available = written.min(records.len());
+
+        let mut log = ShaderLog::default();
+        let mut offset = 0;
+        while offset < available {
+            let format_id = records[offset];
+            let format = formats
+                .get(format_id as usize)
+                .ok_or(ShaderLogError::UnknownFormat {
+                    offset: SHADER_LOG_HEADER_WORDS + offset,
+                    format_id,
+                })?;
+            let end = offset + format.record_words();
+            if end > records.len() {
+                // Records are laid out in the order their space was reserved in,
+                // so all the records after one that didn't fit were dropped too.
+                break;
+            }
+            log.messages.push(format.format(&records[offset + 1..end]));
+            offset = end;
+        }
+        log.dropped_words = written - offset.min(written);
+        Ok(log)
+    }
+}
+
+/// Parsed `%[flags][width][.precision][l][vN]conversion` specifier (without the `%`).
+struct Spec {
+    left_align: bool,
+    zero_pad: bool,
+    plus_sign: bool,
+    space_sign: bool,
+    alternate: bool,
+    width: usize,
+    precision: Option<usize>,
+    conversion: char,
+}
+
+impl Spec {
+    /// Parse a specifier from the start of `s`, returning it and the rest of `s`.
+    fn parse(s: &str) -> Option<(Self, &str)> {
+        let mut spec = Self {
+            left_align: false,
+            zero_pad: false,
+            plus_sign: false,
+            space_sign: false,
+            alternate: false,
+            width: 0,
+            precision: None,
+            conversion: '\0',
+        };
+        let mut chars = s.char_indices().peekable();
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                '-' => spec.left_align = true,
+                '0' => spec.zero_pad = true,
+                '+' => spec.plus_sign = true,
+                ' ' => spec.space_sign = true,
+                '#' => spec.alternate = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        let digits = |chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>| {
+            let mut n = 0;
+            while let Some(d) = chars.peek().and_then(|&(_, c)| c.to_digit(10)) {
+                n = n * 10 + d as usize;
+                chars.next();
+            }
+            n
+        };
+        spec.width = digits(&mut chars);
+        if chars.next_if(|&(_, c)| c == '.').is_some() {
+            spec.precision = Some(digits(&mut chars));
+        }
+        // The argument type is already known, so the (64-bit) length modifier
+        // and vector size are only skipped here, wherever they may appear.
+        chars.next_if(|&(_, c)| c == 'l');
+        if chars.next_if(|&(_, c)| c == 'v').is_some() {
+            chars.next_if(|&(_, c)| matches!(c, '2'..='4'))?;
+        }
+        chars.next_if(|&(_, c)| c == 'l');
+        let (i, conversion) = chars.next()?;
+        if !"diuoxXfFeEgGaA".contains(conversion) {
+            return None;
+        }
+        spec.conversion = conversion;
+        let mut end = i + conversion.len_utf8();
+        // HACK `debug_printf!` also accepts `%ul` (for `u64`).
+        if conversion == 'u' && s[end..].starts_with('l') {
+            end += 1;
+        }
+        Some((spec, &s[end..]))
+    }
+
+    /// Format one scalar (of type `scalar`, with its bits in `bits`) according to `self`.
+    fn format(&self, out: &mut String, scalar: ShaderLogScalar, bits: u64) {
+        let bit_width = scalar.words() * 32;
+        let (negative, digits) = match self.conversion {
+            'd' | 'i' => {
+                // Sign-extend (the bits of) the argument, whatever its type.
+                let value = ((bits << (64 - bit_width)) as i64) >> (64 - bit_width);
+                (value < 0, value.unsigned_abs().to_string())
+            }
+            'u' => (false, bits.to_string()),
+            'o' => {
+                let digits = format!("{bits:o}");
+                (
+                    false,
+                    if self.alternate && bits != 0 {
+                        format!("0{digits}")
+                    } else {
+                        digits
+                    },
+                )
+            }
+            'x' | 'X' => {
+                let mut digits = format!("{bits:x}");
+                if self.alternate && bits != 0 {
+                    digits.insert_str(0, "0x");
+                }
+                if self.conversion == 'X' {
+                    digits.make_ascii_uppercase();
+                }
+                (false, digits)
+            }
+            _ => {
+                let value = if bit_width == 32 {
+                    f64::from(f32::from_bits(bits as u32))
+                } else {
+                    f64::from_bits(bits)
+                };
+                let mut digits = if value.is_nan() {
+                    "nan".to_string()
+                } else if value.is_infinite() {
+                    "inf".to_string()
+                } else {
+                    self.format_float_digits(value.abs())
+                };
+                if self.conversion.is_ascii_uppercase() {
+                    digits.make_ascii_uppercase();
+                }
+                (value.is_sign_negative() && !value.is_nan(), digits)
+            }
+        };
+        // Integers are only zero-padded up to the precision, when one is specified.
+        let digits = match (self.precision, self.conversion) {
+            (Some(precision), 'd' | 'i' | 'u' | 'o' | 'x' | 'X') if digits.len() < precision => {
+                format!("{}{digits}", "0".repeat(precision - digits.len()))
+            }
+            _ => digits,
+        };
+
+        let sign = if negative {
+            "-"
+        } else if self.plus_sign && !matches!(self.conversion, 'u' | 'o' | 'x' | 'X') {
+            "+"
+        } else if self.space_sign && !matches!(self.conversion, 'u' | 'o' | 'x' | 'X') {
+            " "
+        } else {
+            ""
+        };
+        let len = sign.len() + digits.len();
+        let padding = self.width.saturating_sub(len);
+        let zero_pad = self.zero_pad
+            && !self.left_align
+            && digits.starts_with(|c: char| c.is_ascii_digit())
+            && !(self.precision.is_some() && "diuoxX".contains(self.conversion));
+        if self.left_align {
+            *out += sign;
+            *out += &digits;
+            out.extend(std::iter::repeat_n(' ', padding));
+        } else if zero_pad {
+            *out += sign;
+            // Keep the `0x` prefix before the zeroes.
+            let (prefix, digits) = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+                Some(rest) => digits.split_at(digits.len() - rest.len()),
+                None => ("", &digits[..]),
+            };
+            *out += prefix;
+            out.extend(std::iter::repeat_n('0', padding));
+            *out += digits;
+        } else {
+            out.extend(std::iter::repeat_n(' ', padding));
+            *out += sign;
+            *out += &digits;
+        }
+    }
+
+    /// Format a finite non-negative float, according to the (lowercase) conversion.
+    fn format_float_digits(&self, value: f64) -> String {
+        let precision = self.precision.unwrap_or(6);
+        match self.conversion.to_ascii_lowercase() {
+            'f' => {
+                let mut s = format!("{value:.precision$}");
+                if self.alternate && precision == 0 {
+                    s.push('.');
+                }
+                s
+            }
+            'e' => format_exp(value, precision, self.alternate),
+            'g' => {
+                let precision = precision.max(1);
+                let exp = if value == 0.0 {
+                    0
+                } else {
+                    // Round first, as that may bump the exponent (e.g. `9.99` -> `1.0e1`).
+                    let s = format!("{value:.*e}", precision - 1);
+                    s[s.find('e').unwrap() + 1..].parse::<i32>().unwrap()
+                };
+                let mut s = if exp < -4 || exp >= precision as i32 {
+                    format_exp(value, precision - 1, self.alternate)
+                } else {
+                    format!("{value:.*}", (precision as i32 - 1 - exp) as usize)
+                };
+                if !self.alternate {
+                    // Remove trailing zeroes (and the decimal point, if nothing is left).
+                    let (mantissa, exp) = s.split_at(s.find('e').unwrap_or(s.len()));
+                    if mantissa.contains('.') {
+                        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+                        s = format!("{mantissa}{exp}");
+                    }
+                }
+                s
+            }
+            'a' => {
+                let bits = value.to_bits();
+                let exp = ((bits >> 52) & 0x7ff) as i32;
+                let mantissa = bits & ((1 << 52) - 1);
+                let (lead, exp) = match exp {
+                    0 if mantissa == 0 => (0, 0),
+                    0 => (0, -1022),
+                    _ => (1, exp - 1023),
+                };
+                let mut s = format!("0x{lead}");
+                let hex = format!("{mantissa:013x}");
+                let hex = hex.trim_end_matches('0');
+                if !hex.is_empty() || self.alternate {
+                    s.push('.');
+                }
+                s += hex;
+                write!(s, "p{exp:+}").unwrap();
+                s
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Format a non-negative float like C's `%e` (e.g. `1.500000e+01`).
+fn format_exp(value: f64, precision: usize, alternate: bool) -> String {
+    let s = format!("{value:.precision$e}");
+    let (mantissa, exp) = s.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let point = if alternate && precision == 0 { "." } else { "" };
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{mantissa}{point}e{sign}{:02}", exp.unsigned_abs())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(format: &str, args: &[(ShaderLogScalar, u32)], words: &[u32]) -> String {
+        ShaderLogFormat {
+            format: format.to_string(),
+            args: args
+                .iter()
+                .map(|&(scalar, components)| ShaderLogArg { scalar, components })
+                .collect(),
+        }
+        .format(words)
+    }
+
+    #[test]
+    fn scalars() {
+        use ShaderLogScalar::*;
+        assert_eq!(
+            format(
+                "%u %d %x %#X %o %5d|%-5d|%05d %+d",
+                &[(U32, 1), (I32, 1), (U32, 1), (U32, 1), (U32, 1)]
+                    .into_iter()
+                    .chain([(I32, 1); 4])
+                    .collect::<Vec<_>>(),
+                &[7, -3i32 as u32, 255, 255, 8, 42, 42, -42i32 as u32, 1],
+            ),
+            "7 -3 ff 0XFF 10    42|42   |-0042 +1"
+        );
+        assert_eq!(
+            format(
+                "%lu %lx %ld",
+                &[(U64, 1), (U64, 1), (I64, 1)],
+                &[1, 1, 0xdead_beef, 0xf00d, -2i32 as u32, !0],
+            ),
+            "4294967297 f00ddeadbeef -2"
+        );
+        assert_eq!(format("100%%", &[], &[]), "100%");
+    }
+
+    #[test]
+    fn floats() {
+        use ShaderLogScalar::*;
+        let f = |x: f32| x.to_bits();
+        assert_eq!(
+            format(
+                "%f %.2f %e %g %g %g %a",
+                &[(F32, 1); 7],
+                &[
+                    f(1.5),
+                    f(-2.125),
+                    f(1234.5),
+                    f(0.0001),
+                    f(100000.0),
+                    f(1e-5),
+                    f(1.0)
+                ],
+            ),
+            "1.500000 -2.12 1.234500e+03 0.0001 100000 1e-05 0x1p+0"
+        );
+        assert_eq!(
+            format("%f %F", &[(F32, 1); 2], &[f(f32::INFINITY), f(f32::NAN)]),
+            "inf NAN"
+        );
+        assert_eq!(format("%.1f", &[(F64, 1)], &[0, 0x3ff8_0000]), "1.5");
+    }
+
+    #[test]
+    fn vectors() {
+        use ShaderLogScalar::*;
+        let f = |x: f32| x.to_bits();
+        assert_eq!(
+            format(
+                "pos = (%v3f), id = %v2u",
+                &[(F32, 3), (U32, 2)],
+                &[f(1.0), f(2.0), f(3.0), 4, 5],
+            ),
+            "pos = (1.000000, 2.000000, 3.000000), id = 4, 5"
+        );
+    }
+
+    #[test]
+    fn decode() {
+        use ShaderLogScalar::*;
+        let formats = [
+            ShaderLogFormat {
+                format: "a %u".into(),
+                args: vec![ShaderLogArg {
+                    scalar: U32,
+                    components: 1,
+                }],
+            },
+            ShaderLogFormat {
+                format: "b %lu".into(),
+                args: vec![ShaderLogArg {
+                    scalar: U64,
+                    components: 1,
+                }],
+            },
+        ];
+        // Second record fits, third doesn't (and neither would any later ones).
+        let buffer = [8, 0xffff, 0, 1, 1, 2, 0, 1, 3];
+        assert_eq!(
+            ShaderLog::decode(&formats, &buffer).unwrap(),
+            ShaderLog {
+                messages: vec!["a 1".into(), "b 2".into()],
+                dropped_words: 3,
+            }
+        );
+        assert!(matches!(
+            ShaderLog::decode(&formats, &[2, 0, 5, 0]),
+            Err(ShaderLogError::UnknownFormat {
+                offset: 2,
+                format_id: 5
+            })
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+/// Detailed report of all "zombies" (i.e. deferred errors for instructions that
+/// can't be legally emitted) reached from entry-points, written as JSON by the
+/// `--zombie-report[=FILE]` codegen arg.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ZombieReport {
+    pub zombies: Vec<ZombieReportEntry>,
+}
+
+/// A single reported zombie, together with its full provenance chain.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ZombieReportEntry {
+    pub root_cause: ZombieRootCause,
+
+    /// Chain of uses leading to the zombie, starting closest to the root cause,
+    /// and ending at the entry-point (or other export) that requires it.
+    pub use_chain: Vec<ZombieUseFrame>,
+
+    /// Location of the first use (in `use_chain` order) that is in user code,
+    /// as opposed to library code, i.e. `core` and other standard crates, or `spirv-std`.
+    pub user_code_location: Option<SourceLocation>,
+
+    pub suggested_fix: ZombieFixCategory,
+}
+
+/// The instruction which couldn't be legally emitted, and why.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ZombieRootCause {
+    pub reason: String,
+
+    /// Kind of definition (e.g. the SPIR-V opcode) that was zombie'd, if known.
+    pub def_kind: Option<String>,
+
+    pub location: Option<SourceLocation>,
+}
+
+/// One use (or call, including inlined calls) in the provenance chain of a zombie.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ZombieUseFrame {
+    /// Human-readable description of the use, e.g. "called by `foo::bar`".
+    pub description: String,
+
+    pub location: Option<SourceLocation>,
+
+    /// Whether this frame comes from a call that was inlined (as opposed to
+    /// a call that still exists in the SPIR-V module, or some other use).
+    pub inlined: bool,
+
+    pub in_user_code: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl SourceLocation {
+    /// Heuristic for whether this location is in library code (`core`, `alloc`
+    /// and other crates shipped with the Rust toolchain, and `spirv-std`),
+    /// instead of user code.
+    pub fn is_in_library_code(&self) -> bool {
+        let file = self.file.replace('\\', "/");
+        let is_spirv_std_dir = |dir: &str| {
+            dir == "spirv-std"
+                || dir
+                    .strip_prefix("spirv-std-")
+                    .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+        };
+        file.starts_with("/rustc/")
+            || ["core", "alloc", "std", "compiler-builtins"]
+                .iter()
+                .any(|krate| file.contains(&format!("/library/{krate}/")))
+            || file.split('/').any(is_spirv_std_dir)
+    }
+}
+
+/// Broad category of fix likely to address a zombie, derived from its reason.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZombieFixCategory {
+    /// Pointer casts/offsets, or pointer-integer conversions, which logical
+    /// addressing can't express: avoid reinterpreting memory through pointers.
+    PointerCast,
+
+    /// The operation requires a SPIR-V capability (or extension) that wasn't
+    /// enabled: enable it (e.g. via `SpirvBuilder::capability`).
+    MissingCapability,
+
+    /// Constant data that can't be represented in SPIR-V: avoid the constant
+    /// (or compute the value at runtime instead).
+    UnsupportedConstant,
+
+    /// Function pointers and indirect calls: use direct calls (e.g. generics).
+    IndirectCall,
+
+    /// Operations on dynamically sized data: use fixed-size types instead.
+    DynamicallySized,
+
+    /// An intrinsic or operation that Rust-GPU doesn't (yet) support: replace
+    /// it with an alternative (e.g. from `spirv-std`).
+    UnsupportedOperation,
+
+    Other,
+}
+
+impl ZombieFixCategory {
+    pub fn from_reason(reason: &str) -> Self {
+        let any = |needles: &[&str]| needles.iter().any(|needle| reason.contains(needle));
+        if any(&[
+            "cannot cast between pointer types",
+            "cannot offset a pointer",
+            "pointers to integers",
+            "integers to pointers",
+            "integer address",
+            "OpPtrEqual",
+            "OpPtrNotEqual",
+            "pointer operator",
+        ]) {
+            Self::PointerCast
+        } else if any(&["OpCapability", "when disallowed"]) {
+            Self::MissingCapability
+        } else if any(&["constant"]) {
+            Self::UnsupportedConstant
+        } else if any(&["function pointer", "indirect call"]) {
+            Self::IndirectCall
+        } else if any(&["dynamically sized", "unsized"]) {
+            Self::DynamicallySized
+        } else if any(&["not supported", "not implemented", "unsupported"]) {
+            Self::UnsupportedOperation
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fix_category_from_reason() {
+        let cases = [
+            (
+                "cannot cast between pointer types\nfrom `*f32`\n  to `*u32`",
+                ZombieFixCategory::PointerCast,
+            ),
+            (
+                "cannot offset a pointer to an arbitrary element",
+                ZombieFixCategory::PointerCast,
+            ),
+            (
+                "cannot convert pointers to integers",
+                ZombieFixCategory::PointerCast,
+            ),
+            (
+                "`f16` type used without `OpCapability Float16`",
+                ZombieFixCategory::MissingCapability,
+            ),
+            (
+                "`u64` type used without `OpCapability Int64`",
+                ZombieFixCategory::MissingCapability,
+            ),
+            (
+                "pointer has non-null integer address",
+                ZombieFixCategory::PointerCast,
+            ),
+            (
+                "constant arrays/structs cannot contain pointers to other constants",
+                ZombieFixCategory::UnsupportedConstant,
+            ),
+            (
+                "function pointer types are not allowed",
+                ZombieFixCategory::IndirectCall,
+            ),
+            (
+                "cannot memcpy dynamically sized data",
+                ZombieFixCategory::DynamicallySized,
+            ),
+            (
+                "`ctpop` is not supported on this target",
+                ZombieFixCategory::UnsupportedOperation,
+            ),
+            ("something went wrong", ZombieFixCategory::Other),
+        ];
+        for (reason, expected) in cases {
+            assert_eq!(
+                ZombieFixCategory::from_reason(reason),
+                expected,
+                "{reason:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn library_locations() {
+        let is_library = |file: &str| {
+            SourceLocation {
+                file: file.to_string(),
+                line: 1,
+                column: 1,
+            }
+            .is_in_library_code()
+        };
+        assert!(is_library("/rustc/0123abcd/library/core/src/ptr/mod.rs"));
+        assert!(is_library(
+            "/home/user/.rustup/toolchains/nightly/lib/rustlib/src/rust/library/core/src/ptr/mod.rs"
+        ));
+        assert!(is_library(
+            "/home/user/rust-gpu/crates/spirv-std/src/arch.rs"
+        ));
+        assert!(is_library(
+            "/home/user/.cargo/registry/src/index.crates.io-0123/spirv-std-0.9.0/src/image.rs"
+        ));
+        assert!(is_library(
+            "C:\\Users\\user\\rust-gpu\\crates\\spirv-std\\src\\lib.rs"
+        ));
+
+        assert!(!is_library("src/lib.rs"));
+        assert!(!is_library("/home/user/my-shaders/src/lib.rs"));
+        assert!(!is_library("/home/user/spirv-std-shaders/src/lib.rs"));
+    }
+}
@@ -5,7 +5,7 @@ use crate::attr::{AggregatedSpirvAttributes, IntrinsicType};
 use crate::codegen_cx::CodegenCx;
 use crate::spirv_type::SpirvType;
 use itertools::Itertools;
-use rspirv::spirv::{Dim, ImageFormat, StorageClass, Word};
+use rspirv::spirv::{CooperativeMatrixUse, Dim, ImageFormat, Scope, StorageClass, Word};
 use rustc_abi::ExternAbi as Abi;
 use rustc_abi::{
     Align, BackendRepr, FieldIdx, FieldsShape, Primitive, Scalar, Size, VariantIdx, Variants,
@@ -759,6 +759,60 @@ impl fmt::Display for TyLayoutNameKey<'_> {
     }
 }
 
+trait FromScalarInt: Sized {
+    fn from_scalar_int(n: ScalarInt) -> Option<Self>;
+}
+
+impl FromScalarInt for u32 {
+    fn from_scalar_int(n: ScalarInt) -> Option<Self> {
+        Some(n.try_to_bits(Size::from_bits(32)).ok()?.try_into().unwrap())
+    }
+}
+
+impl FromScalarInt for Dim {
+    fn from_scalar_int(n: ScalarInt) -> Option<Self> {
+        Dim::from_u32(u32::from_scalar_int(n)?)
+    }
+}
+
+impl FromScalarInt for ImageFormat {
+    fn from_scalar_int(n: ScalarInt) -> Option<Self> {
+        ImageFormat::from_u32(u32::from_scalar_int(n)?)
+    }
+}
+
+impl FromScalarInt for Scope {
+    fn from_scalar_int(n: ScalarInt) -> Option<Self> {
+        Scope::from_u32(u32::from_scalar_int(n)?)
+    }
+}
+
+impl FromScalarInt for CooperativeMatrixUse {
+    fn from_scalar_int(n: ScalarInt) -> Option<Self> {
+        CooperativeMatrixUse::from_u32(u32::from_scalar_int(n)?)
+    }
+}
+
+fn const_int_value<'tcx, P: FromScalarInt>(
+    cx: &CodegenCx<'tcx>,
+    type_name: &str,
+    const_: Const<'tcx>,
+) -> Result<P, ErrorGuaranteed> {
+    let ty::Value {
+        ty: const_ty,
+        valtree: const_val,
+    } = const_.to_value();
+    assert!(const_ty.is_integral());
+    const_val
+        .try_to_scalar_int()
+        .and_then(P::from_scalar_int)
+        .ok_or_else(|| {
+            cx.tcx.dcx().err(format!(
+                "invalid value for {type_name} const generic: {const_}"
+            ))
+        })
+}
+
 fn trans_intrinsic_type<'tcx>(
     cx: &CodegenCx<'tcx>,
     span: Span,
@@ -829,53 +883,12 @@ fn trans_intrinsic_type<'tcx>(
             // let image_format: spirv::ImageFormat =
             //     type_from_variant_discriminant(cx, args.const_at(6));
 
-            trait FromScalarInt: Sized {
-                fn from_scalar_int(n: ScalarInt) -> Option<Self>;
-            }
-
-            impl FromScalarInt for u32 {
-                fn from_scalar_int(n: ScalarInt) -> Option<Self> {
-                    Some(n.try_to_bits(Size::from_bits(32)).ok()?.try_into().unwrap())
-                }
-            }
-
-            impl FromScalarInt for Dim {
-                fn from_scalar_int(n: ScalarInt) -> Option<Self> {
-                    Dim::from_u32(u32::from_scalar_int(n)?)
-                }
-            }
-
-            impl FromScalarInt for ImageFormat {
-                fn from_scalar_int(n: ScalarInt) -> Option<Self> {
-                    ImageFormat::from_u32(u32::from_scalar_int(n)?)
-                }
-            }
-
-            fn const_int_value<'tcx, P: FromScalarInt>(
-                cx: &CodegenCx<'tcx>,
-                const_: Const<'tcx>,
-            ) -> Result<P, ErrorGuaranteed> {
-                let ty::Value {
-                    ty: const_ty,
-                    valtree: const_val,
-                } = const_.to_value();
-                assert!(const_ty.is_integral());
-                const_val
-                    .try_to_scalar_int()
-                    .and_then(P::from_scalar_int)
-                    .ok_or_else(|| {
-                        cx.tcx
-                            .dcx()
-                            .err(format!("invalid value for Image const generic: {const_}"))
-                    })
-            }
-
-            let dim = const_int_value(cx, args.const_at(1))?;
-            let depth = const_int_value(cx, args.const_at(2))?;
-            let arrayed = const_int_value(cx, args.const_at(3))?;
-            let multisampled = const_int_value(cx, args.const_at(4))?;
-            let sampled = const_int_value(cx, args.const_at(5))?;
-            let image_format = const_int_value(cx, args.const_at(6))?;
+            let dim = const_int_value(cx, "Image", args.const_at(1))?;
+            let depth = const_int_value(cx, "Image", args.const_at(2))?;
+            let arrayed = const_int_value(cx, "Image", args.const_at(3))?;
+            let multisampled = const_int_value(cx, "Image", args.const_at(4))?;
+            let sampled = const_int_value(cx, "Image", args.const_at(5))?;
+            let image_format = const_int_value(cx, "Image", args.const_at(6))?;
 
             let ty = SpirvType::Image {
                 sampled_type,
@@ -899,6 +912,51 @@ fn trans_intrinsic_type<'tcx>(
             Ok(SpirvType::AccelerationStructureKhr.def(span, cx))
         }
         IntrinsicType::RayQueryKhr => Ok(SpirvType::RayQueryKhr.def(span, cx)),
+        IntrinsicType::CooperativeMatrixKhr => {
+            // see SpirvType::sizeof
+            if ty.size != Size::from_bytes(4) {
+                return Err(cx
+                    .tcx
+                    .dcx()
+                    .err("#[spirv(cooperative_matrix)] type must have size 4"));
+            }
+
+            let span = span_for_spirv_type_adt(cx, ty).unwrap_or(span);
+            let component_type = cx.layout_of(args.type_at(0)).spirv_type(span, cx);
+            match cx.lookup_type(component_type) {
+                SpirvType::Integer(..) | SpirvType::Float(_) => {}
+                other => {
+                    return Err(cx
+                        .tcx
+                        .dcx()
+                        .struct_span_err(
+                            span,
+                            "`#[spirv(cooperative_matrix)]` component type must be \
+                             an integer or float scalar",
+                        )
+                        .with_note(format!(
+                            "component type is {}",
+                            other.debug(component_type, cx)
+                        ))
+                        .emit());
+                }
+            }
+
+            let type_name = "CooperativeMatrix";
+            let scope = const_int_value(cx, type_name, args.const_at(1))?;
+            let rows = const_int_value(cx, type_name, args.const_at(2))?;
+            let columns = const_int_value(cx, type_name, args.const_at(3))?;
+            let matrix_use = const_int_value(cx, type_name, args.const_at(4))?;
+
+            Ok(SpirvType::CooperativeMatrixKhr {
+                component_type,
+                scope,
+                rows,
+                columns,
+                matrix_use,
+            }
+            .def(span, cx))
+        }
         IntrinsicType::SampledImage => {
             // see SpirvType::sizeof
             if ty.size != Size::from_bytes(4) {
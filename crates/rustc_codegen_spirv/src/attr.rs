@@ -3,6 +3,7 @@
 //! The attribute-checking parts of this try to follow `rustc_passes::check_attr`.
 
 use crate::codegen_cx::CodegenCx;
+use crate::custom_insts::RspirvUnsupportedExecutionMode;
 use crate::symbols::Symbols;
 use rspirv::spirv::{BuiltIn, ExecutionMode, ExecutionModel, StorageClass};
 use rustc_ast::{LitKind, MetaItemInner, MetaItemLit};
@@ -44,6 +45,9 @@ impl AsRef<[u32]> for ExecutionModeExtra {
 pub struct Entry {
     pub execution_model: ExecutionModel,
     pub execution_modes: Vec<(ExecutionMode, ExecutionModeExtra)>,
+    /// Execution modes `rspirv` can't represent yet, which are emitted as
+    /// custom instructions instead (see `RspirvUnsupportedExecutionMode`).
+    pub rspirv_unsupported_execution_modes: Vec<RspirvUnsupportedExecutionMode>,
     pub name: Option<Symbol>,
 }
 
@@ -52,6 +56,7 @@ impl From<ExecutionModel> for Entry {
         Self {
             execution_model,
             execution_modes: Vec::new(),
+            rspirv_unsupported_execution_modes: Vec::new(),
             name: None,
         }
     }
@@ -65,6 +70,7 @@ pub enum IntrinsicType {
     AccelerationStructureKhr,
     SampledImage,
     RayQueryKhr,
+    CooperativeMatrixKhr,
     RuntimeArray,
     TypedBuffer,
     Matrix,
@@ -97,9 +103,14 @@ pub enum SpirvAttribute {
     Location(u32),
     Flat,
     PerPrimitiveExt,
+    Patch,
     Invariant,
     InputAttachmentIndex(u32),
     SpecConstant(SpecConstant),
+    Stream(u32),
+    XfbBuffer(u32),
+    XfbStride(u32),
+    Offset(u32),
 
     // `fn`/closure attributes:
     BufferLoadIntrinsic,
@@ -135,8 +146,13 @@ pub struct AggregatedSpirvAttributes {
     pub flat: Option<Spanned<()>>,
     pub invariant: Option<Spanned<()>>,
     pub per_primitive_ext: Option<Spanned<()>>,
+    pub patch: Option<Spanned<()>>,
     pub input_attachment_index: Option<Spanned<u32>>,
     pub spec_constant: Option<Spanned<SpecConstant>>,
+    pub stream: Option<Spanned<u32>>,
+    pub xfb_buffer: Option<Spanned<u32>>,
+    pub xfb_stride: Option<Spanned<u32>>,
+    pub offset: Option<Spanned<u32>>,
 
     // `fn`/closure attributes:
     pub buffer_load_intrinsic: Option<Spanned<()>>,
@@ -227,6 +243,7 @@ impl AggregatedSpirvAttributes {
                 span,
                 "#[spirv(per_primitive_ext)]",
             ),
+            Patch => try_insert(&mut self.patch, (), span, "#[spirv(patch)]"),
             InputAttachmentIndex(value) => try_insert(
                 &mut self.input_attachment_index,
                 value,
@@ -239,6 +256,14 @@ impl AggregatedSpirvAttributes {
                 span,
                 "#[spirv(spec_constant)]",
             ),
+            Stream(value) => try_insert(&mut self.stream, value, span, "#[spirv(stream)]"),
+            XfbBuffer(value) => {
+                try_insert(&mut self.xfb_buffer, value, span, "#[spirv(xfb_buffer)]")
+            }
+            XfbStride(value) => {
+                try_insert(&mut self.xfb_stride, value, span, "#[spirv(xfb_stride)]")
+            }
+            Offset(value) => try_insert(&mut self.offset, value, span, "#[spirv(offset)]"),
             BufferLoadIntrinsic => try_insert(
                 &mut self.buffer_load_intrinsic,
                 (),
@@ -330,8 +355,13 @@ impl CheckSpirvAttrVisitor<'_> {
                 | SpirvAttribute::Flat
                 | SpirvAttribute::Invariant
                 | SpirvAttribute::PerPrimitiveExt
+                | SpirvAttribute::Patch
                 | SpirvAttribute::InputAttachmentIndex(_)
-                | SpirvAttribute::SpecConstant(_) => match target {
+                | SpirvAttribute::SpecConstant(_)
+                | SpirvAttribute::Stream(_)
+                | SpirvAttribute::XfbBuffer(_)
+                | SpirvAttribute::XfbStride(_)
+                | SpirvAttribute::Offset(_) => match target {
                     Target::Param => {
                         let parent_hir_id = self.tcx.parent_hir_id(hir_id);
                         let parent_is_entry_point = parse_attrs(self.tcx.hir_attrs(parent_hir_id))
@@ -610,6 +640,14 @@ fn parse_spirv_attr<'a>(
                 SpirvAttribute::Location(parse_attr_int_value(arg)?)
             } else if arg.has_name(sym.input_attachment_index) {
                 SpirvAttribute::InputAttachmentIndex(parse_attr_int_value(arg)?)
+            } else if arg.has_name(sym.stream) {
+                SpirvAttribute::Stream(parse_attr_int_value(arg)?)
+            } else if arg.has_name(sym.xfb_buffer) {
+                SpirvAttribute::XfbBuffer(parse_attr_int_value(arg)?)
+            } else if arg.has_name(sym.xfb_stride) {
+                SpirvAttribute::XfbStride(parse_attr_int_value(arg)?)
+            } else if arg.has_name(sym.offset) {
+                SpirvAttribute::Offset(parse_attr_int_value(arg)?)
             } else if arg.has_name(sym.spec_constant) {
                 SpirvAttribute::SpecConstant(parse_spec_constant_attr(sym, arg)?)
             } else {
@@ -817,6 +855,10 @@ fn parse_entry_attrs(
                             }
                         }
                     }
+                } else if let Some(&mode) =
+                    sym.rspirv_unsupported_execution_modes.get(&attr_name.name)
+                {
+                    entry.rspirv_unsupported_execution_modes.push(mode);
                 } else if attr_name.name == sym.entry_point_name {
                     match attr.value_str() {
                         Some(sym) => {
@@ -11,9 +11,11 @@ use crate::custom_insts::CustomInst;
 use crate::spirv_type::SpirvType;
 use itertools::Itertools;
 use rspirv::dr::{InsertPoint, Instruction, Operand};
-use rspirv::spirv::{Capability, MemoryModel, MemorySemantics, Op, Scope, StorageClass, Word};
+use rspirv::spirv::{
+    Capability, MemoryAccess, MemoryModel, MemorySemantics, Op, Scope, StorageClass, Word,
+};
 use rustc_abi::{Align, BackendRepr, Scalar, Size, WrappingRange};
-use rustc_apfloat::{Float, Round, Status, ieee};
+use rustc_apfloat::{Float, FloatConvert, Round, Status, ieee};
 use rustc_codegen_ssa::MemFlags;
 use rustc_codegen_ssa::common::{
     AtomicRmwBinOp, IntPredicate, RealPredicate, SynchronizationScope, TypeKind,
@@ -289,6 +291,12 @@ macro_rules! simple_uni_op {
     };
 }
 
+/// Exactly convert the bits of an `f16` (e.g. from a constant) to an `f64`.
+fn f16_bits_to_f64(bits: u128) -> f64 {
+    let val: ieee::Double = ieee::Half::from_bits(bits).convert(&mut false).value;
+    f64::from_bits(val.to_bits() as u64)
+}
+
 fn memset_fill_u16(b: u8) -> u16 {
     b as u16 | ((b as u16) << 8)
 }
@@ -407,6 +415,12 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 )),
             },
             SpirvType::Float(width) => match width {
+                16 => self
+                    .def_constant(
+                        ty.def(self.span(), self),
+                        SpirvConst::Scalar(memset_fill_u16(fill_byte).into()),
+                    )
+                    .def(self),
                 32 => self
                     .constant_f32(self.span(), f32::from_bits(memset_fill_u32(fill_byte)))
                     .def(self),
@@ -443,6 +457,9 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 self.fatal("cannot memset acceleration structure")
             }
             SpirvType::RayQueryKhr => self.fatal("cannot memset ray query"),
+            SpirvType::CooperativeMatrixKhr { .. } => {
+                self.fatal("cannot memset cooperative matrix")
+            }
         }
     }
 
@@ -461,6 +478,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 )),
             },
             SpirvType::Float(width) => match width {
+                16 => memset_dynamic_scalar(self, fill_var, 2, true),
                 32 => memset_dynamic_scalar(self, fill_var, 4, true),
                 64 => memset_dynamic_scalar(self, fill_var, 8, true),
                 _ => self.fatal(format!("memset on float width {width} not implemented yet")),
@@ -500,6 +518,9 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 self.fatal("cannot memset acceleration structure")
             }
             SpirvType::RayQueryKhr => self.fatal("cannot memset ray query"),
+            SpirvType::CooperativeMatrixKhr { .. } => {
+                self.fatal("cannot memset cooperative matrix")
+            }
         }
     }
 
@@ -577,6 +598,29 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
         }
     }
 
+    /// Memory operands for an `OpLoad`/`OpStore`/`OpCopyMemory`, i.e. `Aligned`
+    /// (with the alignment `rustc` provides, which accounts for e.g. `#[repr(packed)]`),
+    /// but only when the pointer may be a `PhysicalStorageBuffer` one, as that's the
+    /// only storage class requiring it.
+    ///
+    /// NOTE the storage class of pointers isn't known until the linker infers it
+    /// (see `SpirvType::Pointer`), so any pointer may end up being a
+    /// `PhysicalStorageBuffer` one, if the `PhysicalStorageBufferAddresses`
+    /// capability is enabled (and `Aligned` is harmless for other storage classes).
+    fn aligned_memory_access(&self, align: Align) -> (Option<MemoryAccess>, Option<Operand>) {
+        if self
+            .builder
+            .has_capability(Capability::PhysicalStorageBufferAddresses)
+        {
+            (
+                Some(MemoryAccess::ALIGNED),
+                Some(Operand::LiteralBit32(align.bytes().try_into().unwrap())),
+            )
+        } else {
+            (None, None)
+        }
+    }
+
     /// Convenience wrapper for `adjust_pointer_for_sized_access`, falling back
     /// on choosing `ty` as the leaf's type (and casting `ptr` to a pointer to it).
     //
@@ -1081,6 +1125,22 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
         };
         let int_width = self.cx().int_width(int_ty);
         let float_width = self.cx().float_width(float_ty);
+
+        // NOTE `f16`'s exponent range is too small for the approach
+        // below (e.g. `i32::MIN` isn't representable), but widening to `f32`
+        // is exact, and `f32` has enough range for all integer types.
+        if float_width == 16 {
+            let src_f32_ty = if self.cx.type_kind(src_ty) == TypeKind::Vector {
+                let count = self.cx.vector_length(src_ty) as u32;
+                SpirvType::simd_vector(self, self.span(), SpirvType::Float(32), count)
+            } else {
+                SpirvType::Float(32)
+            }
+            .def(self.span(), self);
+            let val = self.fpext(val, src_f32_ty);
+            return self.fptoint_sat(signed, val, dest_ty);
+        }
+
         // LLVM's fpto[su]i returns undef when the input x is infinite, NaN, or does not fit into the
         // destination integer type after rounding towards zero. This `undef` value can cause UB in
         // safe code (see issue #10184), so we implement a saturating conversion on top of it:
@@ -1825,11 +1885,12 @@ impl<'a, 'tcx> BuilderMethods<'a, 'tcx> for Builder<'a, 'tcx> {
         self.declare_func_local_var(self.type_array(self.type_i8(), size.bytes()), align)
     }
 
-    fn load(&mut self, ty: Self::Type, ptr: Self::Value, _align: Align) -> Self::Value {
+    fn load(&mut self, ty: Self::Type, ptr: Self::Value, align: Align) -> Self::Value {
         let (ptr, access_ty) = self.adjust_pointer_for_typed_access(ptr, ty);
         let loaded_val = ptr.const_fold_load(self).unwrap_or_else(|| {
+            let (memory_access, align_operand) = self.aligned_memory_access(align);
             self.emit()
-                .load(access_ty, None, ptr.def(self), None, empty())
+                .load(access_ty, None, ptr.def(self), memory_access, align_operand)
                 .unwrap()
                 .with_type(access_ty)
         });
@@ -1956,12 +2017,13 @@ impl<'a, 'tcx> BuilderMethods<'a, 'tcx> for Builder<'a, 'tcx> {
         // ignore
     }
 
-    fn store(&mut self, val: Self::Value, ptr: Self::Value, _align: Align) -> Self::Value {
+    fn store(&mut self, val: Self::Value, ptr: Self::Value, align: Align) -> Self::Value {
         let (ptr, access_ty) = self.adjust_pointer_for_typed_access(ptr, val.ty);
         let val = self.bitcast(val, access_ty);
 
+        let (memory_access, align_operand) = self.aligned_memory_access(align);
         self.emit()
-            .store(ptr.def(self), val.def(self), None, empty())
+            .store(ptr.def(self), val.def(self), memory_access, align_operand)
             .unwrap();
         // FIXME(eddyb) this is meant to be a handle the store instruction itself.
         val
@@ -2116,6 +2178,7 @@ impl<'a, 'tcx> BuilderMethods<'a, 'tcx> for Builder<'a, 'tcx> {
             {
                 // Convert the bit representation to the actual float value
                 let float_val = match src_width {
+                    16 => Some(f16_bits_to_f64(const_val)),
                     32 => Some(f32::from_bits(const_val as u32) as f64),
                     64 => Some(f64::from_bits(const_val as u64)),
                     _ => None,
@@ -2787,9 +2850,9 @@ impl<'a, 'tcx> BuilderMethods<'a, 'tcx> for Builder<'a, 'tcx> {
     fn memcpy(
         &mut self,
         dst: Self::Value,
-        _dst_align: Align,
+        dst_align: Align,
         src: Self::Value,
-        _src_align: Align,
+        src_align: Align,
         size: Self::Value,
         flags: MemFlags,
         _tt: Option<rustc_ast::expand::typetree::FncTree>,
@@ -2848,11 +2911,20 @@ impl<'a, 'tcx> BuilderMethods<'a, 'tcx> for Builder<'a, 'tcx> {
         if let Some((dst, src)) = typed_copy_dst_src {
             if let Some(const_value) = src.const_fold_load(self) {
                 trace!("storing const value");
-                self.store(const_value, dst, Align::from_bytes(0).unwrap());
+                self.store(const_value, dst, dst_align);
             } else {
                 trace!("copying memory using OpCopyMemory");
+                // NOTE a single memory operand applies to both `dst` and `src`.
+                let (memory_access, align_operand) =
+                    self.aligned_memory_access(dst_align.min(src_align));
                 self.emit()
-                    .copy_memory(dst.def(self), src.def(self), None, None, empty())
+                    .copy_memory(
+                        dst.def(self),
+                        src.def(self),
+                        memory_access,
+                        None,
+                        align_operand,
+                    )
                     .unwrap();
             }
         } else {
@@ -3378,6 +3450,9 @@ impl<'a, 'tcx> BuilderMethods<'a, 'tcx> for Builder<'a, 'tcx> {
                         let float_val = f32::from_bits(const_val as u32) as f64;
                         Some(self.constant_float(result_type, float_val))
                     }
+                    (ty::Float(FloatTy::F16), ty::Float(FloatTy::F32 | FloatTy::F64)) => {
+                        Some(self.constant_float(result_type, f16_bits_to_f64(const_val)))
+                    }
                     // No optimization for narrowing conversions or unsupported types
                     _ => None,
                 };
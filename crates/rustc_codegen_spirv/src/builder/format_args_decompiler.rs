@@ -236,6 +236,9 @@ impl<'tcx> DecodedFormatArgs<'tcx> {
                 let id_operands = inst
                     .operands
                     .iter()
+                    // NOTE memory operands (e.g. the `Aligned` of `OpLoad`s
+                    // and `OpStore`s) don't matter here, and aren't IDs.
+                    .take_while(|operand| !matches!(operand, Operand::MemoryAccess(_)))
                     .map(|operand| operand.id_ref_any())
                     .collect::<Option<SmallVec<[_; 4]>>>()?;
 
@@ -144,44 +144,44 @@ impl<'a, 'tcx> IntrinsicCallBuilderMethods<'tcx> for Builder<'a, 'tcx> {
                 result
             }
 
-            sym::sqrtf32 | sym::sqrtf64 | sym::sqrtf128 => {
+            sym::sqrtf16 | sym::sqrtf32 | sym::sqrtf64 | sym::sqrtf128 => {
                 self.gl_op(GLOp::Sqrt, ret_ty, [args[0].immediate()])
             }
-            sym::powif32 | sym::powif64 | sym::powif128 => {
+            sym::powif16 | sym::powif32 | sym::powif64 | sym::powif128 => {
                 let float = self.sitofp(args[1].immediate(), args[0].immediate().ty);
                 self.gl_op(GLOp::Pow, ret_ty, [args[0].immediate(), float])
             }
-            sym::sinf32 | sym::sinf64 | sym::sinf128 => {
+            sym::sinf16 | sym::sinf32 | sym::sinf64 | sym::sinf128 => {
                 self.gl_op(GLOp::Sin, ret_ty, [args[0].immediate()])
             }
-            sym::cosf32 | sym::cosf64 | sym::cosf128 => {
+            sym::cosf16 | sym::cosf32 | sym::cosf64 | sym::cosf128 => {
                 self.gl_op(GLOp::Cos, ret_ty, [args[0].immediate()])
             }
-            sym::powf32 | sym::powf64 | sym::powf128 => self.gl_op(
+            sym::powf16 | sym::powf32 | sym::powf64 | sym::powf128 => self.gl_op(
                 GLOp::Pow,
                 ret_ty,
                 [args[0].immediate(), args[1].immediate()],
             ),
-            sym::expf32 | sym::expf64 | sym::expf128 => {
+            sym::expf16 | sym::expf32 | sym::expf64 | sym::expf128 => {
                 self.gl_op(GLOp::Exp, ret_ty, [args[0].immediate()])
             }
-            sym::exp2f32 | sym::exp2f64 | sym::exp2f128 => {
+            sym::exp2f16 | sym::exp2f32 | sym::exp2f64 | sym::exp2f128 => {
                 self.gl_op(GLOp::Exp2, ret_ty, [args[0].immediate()])
             }
-            sym::logf32 | sym::logf64 | sym::logf128 => {
+            sym::logf16 | sym::logf32 | sym::logf64 | sym::logf128 => {
                 self.gl_op(GLOp::Log, ret_ty, [args[0].immediate()])
             }
-            sym::log2f32 | sym::log2f64 | sym::log2f128 => {
+            sym::log2f16 | sym::log2f32 | sym::log2f64 | sym::log2f128 => {
                 self.gl_op(GLOp::Log2, ret_ty, [args[0].immediate()])
             }
-            sym::log10f32 | sym::log10f64 | sym::log10f128 => {
+            sym::log10f16 | sym::log10f32 | sym::log10f64 | sym::log10f128 => {
                 // spir-v glsl doesn't have log10, so,
                 // log10(x) == (1 / ln(10)) * ln(x)
                 let mul = self.constant_float(args[0].immediate().ty, 1.0 / 10.0f64.ln());
                 let ln = self.gl_op(GLOp::Log, ret_ty, [args[0].immediate()]);
                 self.fmul(mul, ln)
             }
-            sym::fmaf32 | sym::fmaf64 | sym::fmaf128 => self.gl_op(
+            sym::fmaf16 | sym::fmaf32 | sym::fmaf64 | sym::fmaf128 => self.gl_op(
                 GLOp::Fma,
                 ret_ty,
                 [
@@ -190,37 +190,40 @@ impl<'a, 'tcx> IntrinsicCallBuilderMethods<'tcx> for Builder<'a, 'tcx> {
                     args[2].immediate(),
                 ],
             ),
-            sym::fabsf32 | sym::fabsf64 | sym::fabsf128 => {
+            sym::fabsf16 | sym::fabsf32 | sym::fabsf64 | sym::fabsf128 => {
                 self.gl_op(GLOp::FAbs, ret_ty, [args[0].immediate()])
             }
-            sym::minnumf32 | sym::minnumf64 | sym::minnumf128 => self.gl_op(
+            sym::minnumf16 | sym::minnumf32 | sym::minnumf64 | sym::minnumf128 => self.gl_op(
                 GLOp::FMin,
                 ret_ty,
                 [args[0].immediate(), args[1].immediate()],
             ),
-            sym::maxnumf32 | sym::maxnumf64 | sym::maxnumf128 => self.gl_op(
+            sym::maxnumf16 | sym::maxnumf32 | sym::maxnumf64 | sym::maxnumf128 => self.gl_op(
                 GLOp::FMax,
                 ret_ty,
                 [args[0].immediate(), args[1].immediate()],
             ),
-            sym::copysignf32 | sym::copysignf64 | sym::copysignf128 => {
+            sym::copysignf16 | sym::copysignf32 | sym::copysignf64 | sym::copysignf128 => {
                 let val = args[0].immediate();
                 let sign = args[1].immediate();
                 self.copysign(val, sign)
             }
-            sym::floorf32 | sym::floorf64 | sym::floorf128 => {
+            sym::floorf16 | sym::floorf32 | sym::floorf64 | sym::floorf128 => {
                 self.gl_op(GLOp::Floor, ret_ty, [args[0].immediate()])
             }
-            sym::ceilf32 | sym::ceilf64 | sym::ceilf128 => {
+            sym::ceilf16 | sym::ceilf32 | sym::ceilf64 | sym::ceilf128 => {
                 self.gl_op(GLOp::Ceil, ret_ty, [args[0].immediate()])
             }
-            sym::truncf32 | sym::truncf64 | sym::truncf128 => {
+            sym::truncf16 | sym::truncf32 | sym::truncf64 | sym::truncf128 => {
                 self.gl_op(GLOp::Trunc, ret_ty, [args[0].immediate()])
             }
-            sym::round_ties_even_f32 | sym::round_ties_even_f64 | sym::round_ties_even_f128 => {
+            sym::round_ties_even_f16
+            | sym::round_ties_even_f32
+            | sym::round_ties_even_f64
+            | sym::round_ties_even_f128 => {
                 self.gl_op(GLOp::RoundEven, ret_ty, [args[0].immediate()])
             }
-            sym::roundf32 | sym::roundf64 | sym::roundf128 => {
+            sym::roundf16 | sym::roundf32 | sym::roundf64 | sym::roundf128 => {
                 self.gl_op(GLOp::Round, ret_ty, [args[0].immediate()])
             }
 
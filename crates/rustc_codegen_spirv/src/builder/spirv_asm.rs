@@ -5,6 +5,7 @@ use super::Builder;
 use crate::abi::ConvSpirvType;
 use crate::builder_spirv::{SpirvValue, SpirvValueExt, SpirvValueKind};
 use crate::codegen_cx::CodegenCx;
+use crate::custom_insts::CustomOp;
 use crate::spirv_type::SpirvType;
 use rspirv::dr;
 use rspirv::grammar::{LogicalOperand, OperandKind, OperandQuantifier, reflect};
@@ -14,6 +15,7 @@ use rspirv::spirv::{
     Op, RayFlags, SelectionControl, StorageClass, Word,
 };
 use rustc_abi::{BackendRepr, Primitive};
+use rustc_apfloat::{Float as _, ieee};
 use rustc_ast::ast::{InlineAsmOptions, InlineAsmTemplatePiece};
 use rustc_codegen_ssa::mir::operand::OperandValue;
 use rustc_codegen_ssa::mir::place::PlaceRef;
@@ -150,9 +152,22 @@ impl<'a, 'tcx> AsmBuilderMethods<'tcx> for Builder<'a, 'tcx> {
             }
         }
 
+        // NOTE `{{`/`}}` escapes split the template into several `String` pieces,
+        // which have to be merged back, e.g. for `OpString`s containing braces.
+        let mut merged_template = Vec::<InlineAsmTemplatePiece>::with_capacity(template.len());
+        for piece in template {
+            match (merged_template.last_mut(), piece) {
+                (
+                    Some(InlineAsmTemplatePiece::String(prev)),
+                    InlineAsmTemplatePiece::String(asm),
+                ) => *prev.to_mut() += asm,
+                _ => merged_template.push(piece.clone()),
+            }
+        }
+
         // vec of lines, and each line is vec of tokens
         let mut tokens = vec![vec![]];
-        for piece in template {
+        for piece in &merged_template {
             match piece {
                 InlineAsmTemplatePiece::String(asm) => {
                     // We cannot use str::lines() here because we don't want the behavior of "the
@@ -438,6 +453,14 @@ impl<'cx, 'tcx> Builder<'cx, 'tcx> {
                 SpirvType::AccelerationStructureKhr.def(self.span(), self)
             }
             Op::TypeRayQueryKHR => SpirvType::RayQueryKhr.def(self.span(), self),
+            Op::TypeCooperativeMatrixKHR => {
+                self.struct_err("`OpTypeCooperativeMatrixKHR` in asm! is not supported")
+                    .with_note(
+                        "pass `CooperativeMatrix` as parameters and use `typeof{foo}` or `typeof*{foo}` (pointer to type) to resolve matrix type",
+                    )
+                    .emit();
+                return;
+            }
             Op::Variable => {
                 // OpVariable with Function storage class should be emitted inside the function,
                 // however, all other OpVariables should appear in the global scope instead.
@@ -559,9 +582,21 @@ impl<'cx, 'tcx> Builder<'cx, 'tcx> {
                 return;
             }
         };
-        let inst_class = inst_name
-            .strip_prefix("Op")
-            .and_then(|n| self.cx.instruction_table.table.get(n));
+        // NOTE `rspirv` doesn't support these instructions yet, so they're parsed
+        // like `OpLogicalNot` (which has the same operands), and then emitted as
+        // custom instructions (see `linker::rspirv_unsupported`).
+        let rspirv_unsupported_custom_op = match inst_name {
+            "OpGroupNonUniformQuadAllKHR" => Some(CustomOp::QuadAllKHR),
+            "OpGroupNonUniformQuadAnyKHR" => Some(CustomOp::QuadAnyKHR),
+            _ => None,
+        };
+        let inst_class = if rspirv_unsupported_custom_op.is_some() {
+            self.cx.instruction_table.table.get("LogicalNot")
+        } else {
+            inst_name
+                .strip_prefix("Op")
+                .and_then(|n| self.cx.instruction_table.table.get(n))
+        };
         let inst_class = if let Some(inst) = inst_class {
             inst
         } else {
@@ -583,6 +618,21 @@ impl<'cx, 'tcx> Builder<'cx, 'tcx> {
         if let Some(result_type) = instruction.result_type {
             id_to_type_map.insert(instruction.result_id.unwrap(), result_type);
         }
+        if let Some(custom_op) = rspirv_unsupported_custom_op {
+            let custom_ext_inst_set = self.ext_inst.borrow_mut().import_custom(self);
+            instruction = dr::Instruction::new(
+                Op::ExtInst,
+                instruction.result_type,
+                instruction.result_id,
+                [
+                    dr::Operand::IdRef(custom_ext_inst_set),
+                    dr::Operand::LiteralExtInstInteger(custom_op as u32),
+                ]
+                .into_iter()
+                .chain(instruction.operands)
+                .collect(),
+            );
+        }
         self.insert_inst(id_map, defined_ids, asm_block, instruction);
         if let Some(OutRegister::Place(place)) = out_register {
             self.emit()
@@ -1238,6 +1288,10 @@ impl<'cx, 'tcx> Builder<'cx, 'tcx> {
                         SpirvType::Integer(64, true) => {
                             dr::Operand::LiteralBit64(w.parse::<i64>().map_err(fmt)? as u64)
                         }
+                        SpirvType::Float(16) => {
+                            let val = w.parse::<ieee::Half>().map_err(|e| e.0)?;
+                            dr::Operand::LiteralBit32(val.to_bits() as u32)
+                        }
                         SpirvType::Float(32) => {
                             dr::Operand::LiteralBit32(w.parse::<f32>().map_err(fmt)?.to_bits())
                         }
@@ -1508,7 +1562,13 @@ impl<'cx, 'tcx> Builder<'cx, 'tcx> {
                 Err(()) => self.err(format!("unknown HostAccessQualifier {word}")),
             },
             (OperandKind::CooperativeMatrixOperands, Some(word)) => {
-                match parse_bitflags_operand(COOPERATIVE_MATRIX_OPERANDS, word) {
+                // NOTE the numeric form allows e.g. `const` operands in `asm!`.
+                match word
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(CooperativeMatrixOperands::from_bits)
+                    .or_else(|| parse_bitflags_operand(COOPERATIVE_MATRIX_OPERANDS, word))
+                {
                     Some(x) => inst
                         .operands
                         .push(dr::Operand::CooperativeMatrixOperands(x)),
@@ -1708,6 +1768,8 @@ pub const FRAGMENT_SHADING_RATE: &[(&str, FragmentShadingRate)] = &[
         FragmentShadingRate::HORIZONTAL4_PIXELS,
     ),
 ];
+// NOTE both the `rspirv` names (kept for compatibility) and the names used
+// by the SPIR-V specification (and e.g. `spirv-dis`) are accepted.
 pub const COOPERATIVE_MATRIX_OPERANDS: &[(&str, CooperativeMatrixOperands)] = &[
     ("NONE_KHR", CooperativeMatrixOperands::NONE_KHR),
     (
@@ -1730,6 +1792,27 @@ pub const COOPERATIVE_MATRIX_OPERANDS: &[(&str, CooperativeMatrixOperands)] = &[
         "SATURATING_ACCUMULATION_KHR",
         CooperativeMatrixOperands::SATURATING_ACCUMULATION_KHR,
     ),
+    ("NoneKHR", CooperativeMatrixOperands::NONE_KHR),
+    (
+        "MatrixASignedComponentsKHR",
+        CooperativeMatrixOperands::MATRIX_A_SIGNED_COMPONENTS_KHR,
+    ),
+    (
+        "MatrixBSignedComponentsKHR",
+        CooperativeMatrixOperands::MATRIX_B_SIGNED_COMPONENTS_KHR,
+    ),
+    (
+        "MatrixCSignedComponentsKHR",
+        CooperativeMatrixOperands::MATRIX_C_SIGNED_COMPONENTS_KHR,
+    ),
+    (
+        "MatrixResultSignedComponentsKHR",
+        CooperativeMatrixOperands::MATRIX_RESULT_SIGNED_COMPONENTS_KHR,
+    ),
+    (
+        "SaturatingAccumulationKHR",
+        CooperativeMatrixOperands::SATURATING_ACCUMULATION_KHR,
+    ),
 ];
 
 fn parse_bitflags_operand<T: std::ops::BitOr<Output = T> + Copy>(
@@ -8,6 +8,7 @@ use crate::spirv_type::SpirvType;
 use itertools::Itertools as _;
 use rspirv::spirv::Word;
 use rustc_abi::{self as abi, AddressSpace, Float, HasDataLayout, Integer, Primitive, Size};
+use rustc_apfloat::{Float as _, FloatConvert as _, ieee};
 use rustc_codegen_ssa::traits::{ConstCodegenMethods, MiscCodegenMethods, StaticCodegenMethods};
 use rustc_middle::mir::interpret::{AllocError, ConstAllocation, GlobalAlloc, Scalar, alloc_range};
 use rustc_middle::ty::layout::LayoutOf;
@@ -79,6 +80,12 @@ impl<'tcx> CodegenCx<'tcx> {
     pub fn constant_float(&self, ty: Word, val: f64) -> SpirvValue {
         match self.lookup_type(ty) {
             // FIXME(eddyb) use `rustc_apfloat` to support all float sizes.
+            SpirvType::Float(16) => {
+                let val: ieee::Half = ieee::Double::from_bits(val.to_bits().into())
+                    .convert(&mut false)
+                    .value;
+                self.def_constant(ty, SpirvConst::Scalar(val.to_bits()))
+            }
             SpirvType::Float(32) => {
                 self.def_constant(ty, SpirvConst::Scalar((val as f32).to_bits().into()))
             }
@@ -621,7 +628,8 @@ impl<'tcx> CodegenCx<'tcx> {
             | SpirvType::SampledImage { .. }
             | SpirvType::InterfaceBlock { .. }
             | SpirvType::AccelerationStructureKhr
-            | SpirvType::RayQueryKhr => {
+            | SpirvType::RayQueryKhr
+            | SpirvType::CooperativeMatrixKhr { .. } => {
                 let result = self.undef(ty);
                 self.zombie_no_span(
                     result.def_cx(self),
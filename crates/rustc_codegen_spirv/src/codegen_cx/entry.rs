@@ -6,13 +6,17 @@ use crate::abi::ConvSpirvType;
 use crate::attr::{AggregatedSpirvAttributes, Entry, Spanned, SpecConstant};
 use crate::builder::Builder;
 use crate::builder_spirv::{SpirvFunctionCursor, SpirvValue, SpirvValueExt};
+use crate::custom_insts::CustomInst;
 use crate::spirv_type::SpirvType;
 use rspirv::dr::Operand;
 use rspirv::spirv::{
-    BuiltIn, Capability, Decoration, Dim, ExecutionModel, FunctionControl, StorageClass, Word,
+    BuiltIn, Capability, Decoration, Dim, ExecutionMode, ExecutionModel, FunctionControl,
+    StorageClass, Word,
 };
 use rustc_abi::FieldsShape;
-use rustc_codegen_ssa::traits::{BaseTypeCodegenMethods, BuilderMethods, MiscCodegenMethods as _};
+use rustc_codegen_ssa::traits::{
+    BaseTypeCodegenMethods, BuilderMethods, ConstCodegenMethods as _, MiscCodegenMethods as _,
+};
 use rustc_data_structures::fx::FxHashMap;
 use rustc_errors::MultiSpan;
 use rustc_hir as hir;
@@ -122,15 +126,7 @@ impl<'tcx> CodegenCx<'tcx> {
             );
         }
 
-        // let execution_model = entry.execution_model;
-        let stub = self.shader_entry_stub(
-            span,
-            entry_instance,
-            fn_abi,
-            hir_params,
-            name,
-            entry.execution_model,
-        );
+        let stub = self.shader_entry_stub(span, entry_instance, fn_abi, hir_params, name, &entry);
         let mut emit = self.emit_global();
         entry
             .execution_modes
@@ -147,7 +143,7 @@ impl<'tcx> CodegenCx<'tcx> {
         entry_fn_abi: &FnAbi<'tcx, Ty<'tcx>>,
         hir_params: &[hir::Param<'tcx>],
         name: String,
-        execution_model: ExecutionModel,
+        entry: &Entry,
     ) -> SpirvFunctionCursor {
         let stub_fn = {
             let void = SpirvType::Void.def(span, self);
@@ -177,7 +173,7 @@ impl<'tcx> CodegenCx<'tcx> {
         for (entry_arg_abi, hir_param) in entry_fn_abi.args.iter().zip(hir_params) {
             bx.set_span(hir_param.span);
             self.declare_shader_interface_for_param(
-                execution_model,
+                entry,
                 entry_arg_abi,
                 hir_param,
                 &mut op_entry_point_interface_operands,
@@ -187,6 +183,23 @@ impl<'tcx> CodegenCx<'tcx> {
             );
         }
         bx.set_span(span);
+
+        // NOTE execution modes `rspirv` can't represent are emitted as
+        // custom instructions in the entry-point stub, for the linker to turn
+        // them into actual `OpExecutionMode`s (see `linker::rspirv_unsupported`).
+        if !entry.rspirv_unsupported_execution_modes.is_empty() {
+            let void = SpirvType::Void.def(span, self);
+            for &mode in &entry.rspirv_unsupported_execution_modes {
+                let mode = self.const_u32(mode as u32).def_cx(self);
+                bx.custom_inst(
+                    void,
+                    CustomInst::ExecutionMode {
+                        mode: Operand::IdRef(mode),
+                    },
+                );
+            }
+        }
+
         bx.call(
             self.get_fn(entry_instance).ty,
             None,
@@ -199,7 +212,7 @@ impl<'tcx> CodegenCx<'tcx> {
         bx.ret_void();
 
         self.emit_global().entry_point(
-            execution_model,
+            entry.execution_model,
             stub_fn.id,
             name,
             op_entry_point_interface_operands,
@@ -431,7 +444,7 @@ impl<'tcx> CodegenCx<'tcx> {
     #[allow(clippy::too_many_arguments)]
     fn declare_shader_interface_for_param(
         &self,
-        execution_model: ExecutionModel,
+        entry: &Entry,
         entry_arg_abi: &ArgAbi<'tcx, Ty<'tcx>>,
         hir_param: &hir::Param<'tcx>,
         op_entry_point_interface_operands: &mut Vec<Word>,
@@ -439,6 +452,7 @@ impl<'tcx> CodegenCx<'tcx> {
         call_args: &mut Vec<SpirvValue>,
         decoration_locations: &mut FxHashMap<StorageClass, u32>,
     ) {
+        let execution_model = entry.execution_model;
         let attrs = AggregatedSpirvAttributes::parse(self, self.tcx.hir_attrs(hir_param.hir_id));
 
         let EntryParamDeducedFromRustRefOrValue {
@@ -749,6 +763,12 @@ impl<'tcx> CodegenCx<'tcx> {
                         StorageClass::Input,
                         _,
                     ) => true,
+                    // Tessellation control per-vertex outputs, and tessellation evaluation
+                    // per-vertex inputs, are similarly arrayed (unlike per-patch ones).
+                    (ExecutionModel::TessellationControl, StorageClass::Output, _)
+                    | (ExecutionModel::TessellationEvaluation, StorageClass::Input, _) => {
+                        attrs.patch.is_none()
+                    }
                     // > if the maintenance4 feature is enabled, they are declared as OpTypeVector variables, and the
                     // > output has a Component Count value higher than that of the input but the same Component Type
                     // Irrelevant: This allows a vertex shader to output a Vec4 and a fragment shader to accept a vector
@@ -913,6 +933,112 @@ impl<'tcx> CodegenCx<'tcx> {
             );
         }
 
+        if let Some(patch) = attrs.patch {
+            match (execution_model, storage_class) {
+                (ExecutionModel::TessellationControl, Ok(StorageClass::Output))
+                | (ExecutionModel::TessellationEvaluation, Ok(StorageClass::Input)) => {}
+                _ => {
+                    self.tcx.dcx().span_err(
+                        patch.span,
+                        "`#[spirv(patch)]` is only valid on Output variables of tessellation \
+                         control shaders, or Input variables of tessellation evaluation shaders",
+                    );
+                }
+            }
+            if attrs.builtin.is_some() {
+                self.tcx.dcx().span_err(
+                    patch.span,
+                    "`#[spirv(patch)]` cannot be combined with builtins (which are \
+                     implicitly per-patch, where applicable)",
+                );
+            }
+
+            if let Ok(var_id) = var_id {
+                self.emit_global()
+                    .decorate(var_id, Decoration::Patch, std::iter::empty());
+            }
+        } else if attrs.builtin.is_none()
+            && let Ok(storage_class) = storage_class
+        {
+            self.check_tessellation_per_vertex_type(
+                entry,
+                hir_param.ty_span,
+                value_spirv_type,
+                storage_class,
+            );
+        }
+
+        if let Some(stream) = attrs.stream {
+            if execution_model != ExecutionModel::Geometry
+                || storage_class != Ok(StorageClass::Output)
+            {
+                self.tcx.dcx().span_err(
+                    stream.span,
+                    "`#[spirv(stream = ...)]` is only valid on Output variables of geometry shaders",
+                );
+            } else {
+                self.emit_global().decorate(
+                    var_id.unwrap(),
+                    Decoration::Stream,
+                    std::iter::once(Operand::LiteralBit32(stream.value)),
+                );
+            }
+        }
+        let xfb_attrs = [attrs.xfb_buffer, attrs.xfb_stride, attrs.offset];
+        if let Some(first_xfb_attr) = xfb_attrs.iter().flatten().next() {
+            let has_xfb_mode = entry
+                .execution_modes
+                .iter()
+                .any(|&(mode, _)| mode == ExecutionMode::Xfb);
+            if storage_class != Ok(StorageClass::Output) {
+                self.tcx.dcx().span_err(
+                    first_xfb_attr.span,
+                    "transform feedback attributes (`xfb_buffer`, `xfb_stride` and `offset`) \
+                     are only valid on Output variables",
+                );
+            } else if !has_xfb_mode {
+                self.tcx
+                    .dcx()
+                    .struct_span_err(
+                        first_xfb_attr.span,
+                        "transform feedback attributes require the `xfb` execution mode",
+                    )
+                    .with_help(format!(
+                        "add it to the entry-point, e.g. `#[spirv({}(xfb))]`",
+                        match execution_model {
+                            ExecutionModel::Geometry => "geometry",
+                            ExecutionModel::TessellationEvaluation => "tessellation_evaluation",
+                            _ => "vertex",
+                        }
+                    ))
+                    .emit();
+            } else if let [Some(xfb_buffer), Some(xfb_stride), Some(offset)] = xfb_attrs {
+                let var_id = var_id.unwrap();
+                let mut emit = self.emit_global();
+                emit.decorate(
+                    var_id,
+                    Decoration::XfbBuffer,
+                    std::iter::once(Operand::LiteralBit32(xfb_buffer.value)),
+                );
+                emit.decorate(
+                    var_id,
+                    Decoration::XfbStride,
+                    std::iter::once(Operand::LiteralBit32(xfb_stride.value)),
+                );
+                emit.decorate(
+                    var_id,
+                    Decoration::Offset,
+                    std::iter::once(Operand::LiteralBit32(offset.value)),
+                );
+            } else {
+                self.tcx.dcx().span_err(
+                    first_xfb_attr.span,
+                    "`#[spirv(xfb_buffer = ...)]`, `#[spirv(xfb_stride = ...)]` and \
+                     `#[spirv(offset = ...)]` must be used together",
+                );
+            }
+        }
+
         let is_subpass_input = match self.lookup_type(value_spirv_type) {
             SpirvType::Image {
                 dim: Dim::DimSubpassData,
@@ -1007,6 +1133,65 @@ impl<'tcx> CodegenCx<'tcx> {
         }
     }
 
+    /// Tessellation control per-vertex inputs and outputs, and tessellation evaluation
+    /// per-vertex inputs (i.e. the non-builtin ones not decorated with `#[spirv(patch)]`),
+    /// must be arrays with one element per vertex, and where the number of vertices in a
+    /// patch is known (through the `output_vertices` execution mode), the outputs of the
+    /// tessellation control shader (and inputs of the tessellation evaluation one) must
+    /// have exactly that many elements.
+    fn check_tessellation_per_vertex_type(
+        &self,
+        entry: &Entry,
+        span: Span,
+        value_spirv_type: Word,
+        storage_class: StorageClass,
+    ) {
+        let patch_vertices_must_match = match (entry.execution_model, storage_class) {
+            (ExecutionModel::TessellationControl, StorageClass::Input) => false,
+            (ExecutionModel::TessellationControl, StorageClass::Output)
+            | (ExecutionModel::TessellationEvaluation, StorageClass::Input) => true,
+            _ => return,
+        };
+        let what = format!(
+            "`{:?}` entry-point per-vertex `{storage_class:?}` parameter",
+            entry.execution_model
+        );
+
+        let SpirvType::Array { count, .. } = self.lookup_type(value_spirv_type) else {
+            self.tcx
+                .dcx()
+                .struct_span_err(span, format!("{what} must be an array"))
+                .with_note("each element holds the value for one vertex of the patch")
+                .with_help("use `#[spirv(patch)]` for per-patch values")
+                .emit();
+            return;
+        };
+        if !patch_vertices_must_match {
+            return;
+        }
+        let output_vertices = entry.execution_modes.iter().find_map(|(mode, extra)| {
+            (*mode == ExecutionMode::OutputVertices).then(|| extra.as_ref()[0])
+        });
+        let Some(count) = self.builder.lookup_const_scalar(count) else {
+            return;
+        };
+        if let Some(output_vertices) = output_vertices
+            && count != u128::from(output_vertices)
+        {
+            self.tcx
+                .dcx()
+                .struct_span_err(
+                    span,
+                    format!("{what} must have one element per output vertex"),
+                )
+                .with_note(format!(
+                    "`output_vertices = {output_vertices}` was specified, \
+                     but the array has {count} elements"
+                ))
+                .emit();
+        }
+    }
+
     // Booleans are only allowed in some storage classes. Error if they're in others.
     // Integers and `f64`s must be decorated with `#[spirv(flat)]`.
     fn check_for_bad_types(
@@ -20,6 +20,7 @@ use rspirv::dr::{Module, Operand};
 use rspirv::spirv::{Decoration, LinkageType, Word};
 use rustc_abi::{AddressSpace, HasDataLayout, TargetDataLayout};
 use rustc_ast::ast::{InlineAsmOptions, InlineAsmTemplatePiece};
+use rustc_codegen_spirv_types::DescriptorBinding;
 use rustc_codegen_ssa::mir::debuginfo::{FunctionDebugContext, VariableKind};
 use rustc_codegen_ssa::traits::{
     AsmCodegenMethods, BackendTypes, DebugInfoCodegenMethods, GlobalAsmOperandRef,
@@ -42,6 +43,7 @@ use std::iter::once;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Arc;
 
 pub struct CodegenCx<'tcx> {
     pub tcx: TyCtxt<'tcx>,
@@ -94,11 +96,15 @@ pub struct CodegenCx<'tcx> {
     /// This enables/disables them.
     pub i8_i16_atomics_allowed: bool,
 
-    pub codegen_args: CodegenArgs,
+    pub codegen_args: Arc<CodegenArgs>,
 }
 
 impl<'tcx> CodegenCx<'tcx> {
-    pub fn new(tcx: TyCtxt<'tcx>, codegen_unit: &'tcx CodegenUnit<'tcx>) -> Self {
+    pub fn new(
+        tcx: TyCtxt<'tcx>,
+        codegen_unit: &'tcx CodegenUnit<'tcx>,
+        codegen_args: Arc<CodegenArgs>,
+    ) -> Self {
         // Validate the target spec, as the backend doesn't control `--target`.
         let target_tuple = tcx.sess.opts.target_triple.tuple();
         let target: SpirvTarget = target_tuple.parse().unwrap_or_else(|_| {
@@ -189,8 +195,6 @@ impl<'tcx> CodegenCx<'tcx> {
                 Vec::new()
             });
 
-        let codegen_args = CodegenArgs::from_session(tcx.sess);
-
         Self {
             tcx,
             codegen_unit,
@@ -316,6 +320,10 @@ pub struct CodegenArgs {
 
     pub run_spirv_opt: bool,
 
+    /// Directory to cache the results of (expensive) linking stages in
+    /// (see `crate::link_cache` for more details).
+    pub link_cache: Option<PathBuf>,
+
     /// All options pertinent to `rustc_codegen_spirv::linker` specifically.
     //
     // FIXME(eddyb) should these be handled as `-C linker-args="..."` instead?
@@ -393,6 +401,13 @@ impl CodegenArgs {
             "Preserve unused descriptor bindings. Useful for reflection.",
         );
 
+        opts.optopt(
+            "",
+            "link-cache",
+            "cache (and reuse) the results of linking, spirv-opt and spirv-val in DIR",
+            "DIR",
+        );
+
         // Linker options.
         // FIXME(eddyb) should these be handled as `-C linker-args="..."` instead?
         {
@@ -407,6 +422,13 @@ impl CodegenArgs {
                 "no-early-report-zombies",
                 "delays reporting zombies (to allow more legalization)",
             );
+            opts.optflagopt(
+                "",
+                "zombie-report",
+                "write a detailed report of all zombies (with provenance), as JSON, to FILE \
+                 (or, without FILE, emit it as a compiler note)",
+                "FILE",
+            );
             opts.optflag(
                 "",
                 "no-infer-storage-classes",
@@ -420,12 +442,42 @@ impl CodegenArgs {
                 "enable additional SPIR-T passes (comma-separated)",
                 "PASSES",
             );
+            opts.optmulti(
+                "",
+                "keep-link-export",
+                "preserve `Export` linkage for these symbols (comma-separated), to produce a SPIR-V library",
+                "NAMES",
+            );
+            opts.optmulti(
+                "",
+                "link-spirv-lib",
+                "link in a precompiled SPIR-V module (e.g. a library from another build)",
+                "FILE",
+            );
+            opts.optopt(
+                "",
+                "inline-mode",
+                "which calls the linker inlines (beyond those required for legalization): `hints` (default) or `size`",
+                "MODE",
+            );
             opts.optopt(
                 "",
                 "abort-strategy",
                 "select a non-default abort (i.e. panic) strategy - see `spirv-builder` docs",
                 "STRATEGY",
             );
+            opts.optopt(
+                "",
+                "shader-log-buffer",
+                "lower `debug_printf!`s (and `panic!` messages) to records appended to a storage buffer at SET,BINDING",
+                "SET,BINDING",
+            );
+            opts.optopt(
+                "",
+                "abort-record-buffer",
+                "storage buffer (at SET,BINDING) for `--abort-strategy=abort-record` to write the first abort to",
+                "SET,BINDING",
+            );
 
             // NOTE(eddyb) these are debugging options that used to be env vars
             // (for more information see `docs/src/codegen-args.md`).
@@ -480,6 +532,13 @@ impl CodegenArgs {
                 "dump all instances inferred by the specializer, to FILE",
                 "FILE",
             );
+            opts.optopt(
+                "",
+                "link-stats",
+                "write per-pass timings, module counts, inlining decisions \
+                 and output module sizes, as JSON, to FILE",
+                "FILE",
+            );
         }
 
         // NOTE(eddyb) these are debugging options that used to be env vars
@@ -589,6 +648,21 @@ impl CodegenArgs {
             }
         };
 
+        let parse_descriptor_binding = |name| {
+            matches.opt_str(name).map(|s| {
+                s.split_once(',')
+                    .and_then(|(set, binding)| {
+                        Some(DescriptorBinding {
+                            descriptor_set: set.trim().parse().ok()?,
+                            binding: binding.trim().parse().ok()?,
+                        })
+                    })
+                    .ok_or(rustc_session::getopts::Fail::UnrecognizedOption(s))
+            })
+        };
+        let shader_log_buffer = parse_descriptor_binding("shader-log-buffer").transpose()?;
+        let abort_record_buffer = parse_descriptor_binding("abort-record-buffer").transpose()?;
+
         let matches_opt_path = |name| matches.opt_str(name).map(PathBuf::from);
         let matches_opt_dump_dir_path = |name| {
             matches_opt_path(name).inspect(|path| {
@@ -603,6 +677,12 @@ impl CodegenArgs {
             // FIXME(eddyb) clean up this `no-` "negation prefix" situation.
             compact_ids: !matches.opt_present("no-compact-ids"),
             early_report_zombies: !matches.opt_present("no-early-report-zombies"),
+            zombie_report: matches.opt_present("zombie-report").then(|| {
+                matches_opt_path("zombie-report").map_or(
+                    crate::linker::ZombieReportOutput::Note,
+                    crate::linker::ZombieReportOutput::File,
+                )
+            }),
             infer_storage_classes: !matches.opt_present("no-infer-storage-classes"),
             structurize: !matches.opt_present("no-structurize"),
             preserve_bindings: matches.opt_present("preserve-bindings"),
@@ -614,11 +694,25 @@ impl CodegenArgs {
                 .collect(),
 
             abort_strategy: matches.opt_str("abort-strategy"),
+            abort_record_buffer,
+            shader_log_buffer,
             module_output_type: matches.opt_get_default("module-output", Default::default())?,
+            inline_mode: matches.opt_get_default("inline-mode", Default::default())?,
 
             // FIXME(eddyb) deduplicate between `CodegenArgs` and `linker::Options`.
             spirv_metadata,
             keep_link_exports: false,
+            keep_link_export_names: matches
+                .opt_strs("keep-link-export")
+                .iter()
+                .flat_map(|s| s.split(','))
+                .map(|s| s.to_string())
+                .collect(),
+            link_spirv_libs: matches
+                .opt_strs("link-spirv-lib")
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
 
             // NOTE(eddyb) these are debugging options that used to be env vars
             // (for more information see `docs/src/codegen-args.md`).
@@ -634,6 +728,7 @@ impl CodegenArgs {
             spirt_keep_unstructured_cfg_in_dumps: matches
                 .opt_present("spirt-keep-unstructured-cfg-in-dumps"),
             specializer_dump_instances: matches_opt_path("specializer-dump-instances"),
+            link_stats: matches_opt_path("link-stats"),
         };
 
         Ok(Self {
@@ -655,6 +750,8 @@ impl CodegenArgs {
 
             run_spirv_opt,
 
+            link_cache: matches_opt_path("link-cache"),
+
             linker_opts,
 
             // NOTE(eddyb) these are debugging options that used to be env vars
@@ -775,6 +872,31 @@ impl FromStr for ModuleOutputType {
     }
 }
 
+/// Which calls the linker inlines, beyond those it *has to* inline, in order to
+/// legalize the SPIR-V (e.g. due to pointer arguments, see `linker::inline`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InlineMode {
+    /// Inline calls to `#[inline]` (and `#[inline(always)]`) functions.
+    #[default]
+    Hints,
+
+    /// Minimize code size, by ignoring `#[inline]`, and only inlining calls to
+    /// functions which are either small enough to not be worth calling, or
+    /// which have only one call site (and aren't `#[inline(never)]`).
+    Size,
+}
+
+impl FromStr for InlineMode {
+    type Err = rustc_session::getopts::Fail;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hints" => Ok(Self::Hints),
+            "size" => Ok(Self::Size),
+            v => Err(Self::Err::UnrecognizedOption(v.to_string())),
+        }
+    }
+}
+
 impl<'tcx> BackendTypes for CodegenCx<'tcx> {
     type Value = SpirvValue;
     type Metadata = ();
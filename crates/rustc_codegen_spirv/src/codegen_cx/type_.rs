@@ -207,6 +207,7 @@ impl BaseTypeCodegenMethods for CodegenCx<'_> {
             | SpirvType::SampledImage { .. }
             | SpirvType::AccelerationStructureKhr
             | SpirvType::RayQueryKhr
+            | SpirvType::CooperativeMatrixKhr { .. }
                 => TypeKind::Token,
         }
     }
@@ -199,6 +199,34 @@ def_custom_insts! {
     // invocation (format string followed by inputs) for the "message", while
     // `kind` only distinguishes broad categories like `"abort"` vs `"panic"`.
     4 => Abort { kind, ..message_debug_printf },
+
+    // [Semantic] `OpGroupNonUniformQuad{All,Any}KHR` (from `SPV_KHR_quad_control`),
+    // which `rspirv` can't represent yet, so these are only replaced with the actual
+    // instructions in the final SPIR-V binary (see `linker::rspirv_unsupported`).
+    5 => QuadAllKHR { predicate },
+    6 => QuadAnyKHR { predicate },
+
+    // [Semantic] Like `OpExecutionMode` (for the entry-point whose function contains
+    // this instruction), but for execution modes `rspirv` can't represent yet, with
+    // `mode` being an `OpConstant` holding a `RspirvUnsupportedExecutionMode`
+    // (only added to the final SPIR-V binary, like the instructions above).
+    7 => ExecutionMode { mode },
+}
+
+/// Execution modes `rspirv` can't represent yet (see `CustomInst::ExecutionMode`).
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RspirvUnsupportedExecutionMode {
+    QuadDerivativesKHR = 5088,
+    MaximallyReconvergesKHR = 6023,
+}
+
+impl RspirvUnsupportedExecutionMode {
+    pub fn decode(mode: u32) -> Option<Self> {
+        [Self::QuadDerivativesKHR, Self::MaximallyReconvergesKHR]
+            .into_iter()
+            .find(|&m| m as u32 == mode)
+    }
 }
 
 impl CustomOp {
@@ -211,7 +239,10 @@ impl CustomOp {
             | CustomOp::PushInlinedCallFrame
             | CustomOp::PopInlinedCallFrame => true,
 
-            CustomOp::Abort => false,
+            CustomOp::Abort
+            | CustomOp::QuadAllKHR
+            | CustomOp::QuadAnyKHR
+            | CustomOp::ExecutionMode => false,
         }
     }
 
@@ -223,9 +254,27 @@ impl CustomOp {
             CustomOp::SetDebugSrcLoc
             | CustomOp::ClearDebugSrcLoc
             | CustomOp::PushInlinedCallFrame
-            | CustomOp::PopInlinedCallFrame => false,
+            | CustomOp::PopInlinedCallFrame
+            | CustomOp::QuadAllKHR
+            | CustomOp::QuadAnyKHR
+            | CustomOp::ExecutionMode => false,
 
             CustomOp::Abort => true,
         }
     }
+
+    /// Returns `true` iff this `CustomOp` stands in for a SPIR-V feature that
+    /// `rspirv` can't represent yet, i.e. it's left in the linker output, to be
+    /// replaced in the final SPIR-V binary (by `linker::rspirv_unsupported`).
+    pub fn is_rspirv_unsupported(self) -> bool {
+        match self {
+            CustomOp::SetDebugSrcLoc
+            | CustomOp::ClearDebugSrcLoc
+            | CustomOp::PushInlinedCallFrame
+            | CustomOp::PopInlinedCallFrame
+            | CustomOp::Abort => false,
+
+            CustomOp::QuadAllKHR | CustomOp::QuadAnyKHR | CustomOp::ExecutionMode => true,
+        }
+    }
 }
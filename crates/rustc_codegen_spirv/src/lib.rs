@@ -130,6 +130,7 @@ mod codegen_cx;
 mod custom_decorations;
 mod custom_insts;
 mod link;
+mod link_cache;
 mod linker;
 mod spirv_type;
 mod spirv_type_constraints;
@@ -138,7 +139,7 @@ mod target;
 mod target_feature;
 
 use builder::Builder;
-use codegen_cx::CodegenCx;
+use codegen_cx::{CodegenArgs, CodegenCx};
 use maybe_pqp_cg_ssa::back::lto::{SerializedModule, ThinModule};
 use maybe_pqp_cg_ssa::back::write::{
     CodegenContext, FatLtoInput, ModuleConfig, OngoingCodegen, TargetMachineFactoryConfig,
@@ -168,7 +169,7 @@ use std::fs;
 use std::io::Cursor;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tracing::{error, warn};
 
 fn dump_mir(tcx: TyCtxt<'_>, mono_items: &[(MonoItem<'_>, MonoItemData)], path: &Path) {
@@ -186,13 +187,35 @@ fn dump_mir(tcx: TyCtxt<'_>, mono_items: &[(MonoItem<'_>, MonoItemData)], path:
     }
 }
 
-#[derive(Clone)]
-struct SpirvCodegenBackend;
+#[derive(Clone, Default)]
+struct SpirvCodegenBackend {
+    /// Parsed once (by `init`), and shared by codegen and linking.
+    codegen_args: OnceLock<Arc<CodegenArgs>>,
+}
+
+impl SpirvCodegenBackend {
+    fn codegen_args(&self) -> &Arc<CodegenArgs> {
+        self.codegen_args
+            .get()
+            .expect("`CodegenBackend::init` should've been called first")
+    }
+}
 
 impl CodegenBackend for SpirvCodegenBackend {
     fn init(&self, sess: &Session) {
         // Set up logging/tracing. See https://github.com/Rust-GPU/rust-gpu/issues/192.
         init_logging(sess);
+
+        let cg_args = self
+            .codegen_args
+            .get_or_init(|| Arc::new(CodegenArgs::from_session(sess)));
+
+        // NOTE this has to happen before `rustc` writes out `.d` files,
+        // so that Cargo can rebuild whenever any of the SPIR-V libraries change.
+        let mut file_depinfo = sess.psess.file_depinfo.borrow_mut();
+        for lib in &cg_args.linker_opts.link_spirv_libs {
+            file_depinfo.insert(Symbol::intern(&lib.to_string_lossy()));
+        }
     }
 
     fn locale_resource(&self) -> &'static str {
@@ -220,9 +243,11 @@ impl CodegenBackend for SpirvCodegenBackend {
             target_features,
             unstable_target_features,
 
-            // FIXME(eddyb) support and/or emulate `f16` and `f128`.
-            has_reliable_f16: false,
-            has_reliable_f16_math: false,
+            // NOTE `f16` maps to `OpTypeFloat 16` (requiring `Float16`,
+            // or one of the 16-bit storage capabilities for load/store only).
+            has_reliable_f16: true,
+            has_reliable_f16_math: true,
+            // FIXME support and/or emulate `f128`.
             has_reliable_f128: false,
             has_reliable_f128_math: false,
         }
@@ -240,7 +265,7 @@ impl CodegenBackend for SpirvCodegenBackend {
 
     fn codegen_crate(&self, tcx: TyCtxt<'_>) -> Box<dyn Any> {
         Box::new(maybe_pqp_cg_ssa::base::codegen_crate(
-            Self,
+            self.clone(),
             tcx,
             tcx.sess
                 .opts
@@ -273,6 +298,7 @@ impl CodegenBackend for SpirvCodegenBackend {
         let timer = sess.timer("link_crate");
         link::link(
             sess,
+            self.codegen_args(),
             &codegen_results,
             &metadata,
             outputs,
@@ -450,7 +476,7 @@ impl ExtraBackendMethods for SpirvCodegenBackend {
         // TODO: Do dep_graph stuff
         let cgu = tcx.codegen_unit(cgu_name);
 
-        let mut cx = CodegenCx::new(tcx, cgu);
+        let mut cx = CodegenCx::new(tcx, cgu, self.codegen_args().clone());
         let do_codegen = |cx: &mut CodegenCx<'tcx>| {
             let mono_items = cgu.items_in_deterministic_order(cx.tcx);
 
@@ -541,7 +567,7 @@ pub fn __rustc_codegen_backend() -> Box<dyn CodegenBackend> {
         ));
     });
 
-    Box::new(SpirvCodegenBackend)
+    Box::new(SpirvCodegenBackend::default())
 }
 
 // Set up logging/tracing. See https://github.com/Rust-GPU/rust-gpu/issues/192.
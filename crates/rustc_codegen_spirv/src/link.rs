@@ -2,17 +2,21 @@
 use crate::maybe_pqp_cg_ssa as rustc_codegen_ssa;
 
 use crate::codegen_cx::{CodegenArgs, SpirvMetadata};
+use crate::link_cache::{DiagRecorder, LinkCache};
+use crate::linker::stats::LinkStatsCollector;
 use crate::{SpirvCodegenBackend, SpirvModuleBuffer, linker};
 use ar::{Archive, GnuBuilder, Header};
 use rspirv::binary::Assemble;
 use rspirv::dr::Module;
 use rustc_ast::CRATE_NODE_ID;
 use rustc_attr_parsing::{ShouldEmit, eval_config_entry};
-use rustc_codegen_spirv_types::{CompileResult, ModuleResult};
+use rustc_codegen_spirv_types::{
+    CompileResult, DescriptorBinding, ModuleResult, OutputModuleStats,
+};
 use rustc_codegen_ssa::back::lto::{SerializedModule, ThinModule, ThinShared};
 use rustc_codegen_ssa::back::write::CodegenContext;
 use rustc_codegen_ssa::{CodegenResults, NativeLib};
-use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_errors::Diag;
 use rustc_hir::attrs::NativeLibKind;
 use rustc_metadata::{EncodedMetadata, fs::METADATA_FILENAME};
@@ -25,16 +29,18 @@ use rustc_session::config::{
 };
 use rustc_session::output::{check_file_is_writeable, invalid_output_for_target, out_filename};
 use rustc_span::Symbol;
-use std::collections::BTreeMap;
-use std::ffi::{CString, OsStr};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::{CString, OsStr, OsString};
 use std::fs::File;
 use std::io::{BufWriter, Read};
 use std::iter;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
 pub fn link(
     sess: &Session,
+    cg_args: &CodegenArgs,
     codegen_results: &CodegenResults,
     metadata: &EncodedMetadata,
     outputs: &OutputFilenames,
@@ -93,6 +99,7 @@ pub fn link(
 
                     link_exe(
                         sess,
+                        cg_args,
                         crate_type,
                         &out_filename_file_for_writing,
                         codegen_results,
@@ -156,6 +163,7 @@ fn link_rlib(
 
 fn link_exe(
     sess: &Session,
+    cg_args: &CodegenArgs,
     crate_type: CrateType,
     out_filename: &Path,
     codegen_results: &CodegenResults,
@@ -179,26 +187,52 @@ fn link_exe(
         codegen_results,
     );
 
-    let cg_args = CodegenArgs::from_session(sess);
+    let link_cache = LinkCache::new(sess, cg_args);
+    let link_stats = cg_args
+        .linker_opts
+        .link_stats
+        .as_ref()
+        .map(|_| LinkStatsCollector::new());
 
     // HACK(eddyb) this removes the `.json` in `.spv.json`, from `out_filename`.
     let out_path_spv = out_filename.with_extension("");
 
     let link_result = do_link(
         sess,
-        &cg_args,
+        cg_args,
+        link_cache.as_ref(),
+        link_stats.as_ref(),
         &objects,
         &rlibs,
         outputs,
         disambiguated_crate_name_for_dumps,
     );
+    let linker::LinkOutput {
+        result: link_result,
+        shader_log_formats,
+        abort_record,
+        warnings: _,
+    } = link_result;
     let compile_result = match link_result {
         linker::LinkResult::SingleModule(module) => {
             let entry_points = entry_points(&module);
-            post_link_single_module(sess, &cg_args, *module, &out_path_spv, None);
+            let mut variable_count_bindings = BTreeSet::new();
+            collect_variable_count_bindings(&module, &mut variable_count_bindings);
+            post_link_single_module(
+                sess,
+                cg_args,
+                link_cache.as_ref(),
+                link_stats.as_ref(),
+                *module,
+                &out_path_spv,
+                None,
+            );
             CompileResult {
                 entry_points,
                 module: ModuleResult::SingleModule(out_path_spv),
+                variable_count_bindings: variable_count_bindings.into_iter().collect(),
+                shader_log_formats,
+                abort_record,
             }
         }
         linker::LinkResult::MultipleModules {
@@ -209,15 +243,19 @@ fn link_exe(
                 std::fs::create_dir_all(&out_dir).unwrap();
             }
 
+            let mut variable_count_bindings = BTreeSet::new();
             let entry_name_to_file_path: BTreeMap<_, _> = file_stem_to_entry_name_and_module
                 .into_iter()
                 .map(|(file_stem, (entry_name, module))| {
+                    collect_variable_count_bindings(&module, &mut variable_count_bindings);
                     let mut out_file_name = file_stem;
                     out_file_name.push(".spv");
                     let out_file_path = out_dir.join(out_file_name);
                     post_link_single_module(
                         sess,
-                        &cg_args,
+                        cg_args,
+                        link_cache.as_ref(),
+                        link_stats.as_ref(),
                         module,
                         &out_file_path,
                         Some(disambiguated_crate_name_for_dumps),
@@ -228,6 +266,9 @@ fn link_exe(
             CompileResult {
                 entry_points: entry_name_to_file_path.keys().cloned().collect(),
                 module: ModuleResult::MultiModule(entry_name_to_file_path),
+                variable_count_bindings: variable_count_bindings.into_iter().collect(),
+                shader_log_formats,
+                abort_record,
             }
         }
     };
@@ -236,6 +277,14 @@ fn link_exe(
     // FIXME(eddyb) move this functionality into `rustc_codegen_spirv_types`.
     rustc_codegen_spirv_types::serde_json::to_writer(BufWriter::new(file), &compile_result)
         .unwrap();
+
+    if let Some(link_cache) = &link_cache {
+        link_cache.evict(sess);
+    }
+
+    if let (Some(link_stats), Some(path)) = (link_stats, &cg_args.linker_opts.link_stats) {
+        link_stats.write_to_file(sess, path);
+    }
 }
 
 fn entry_points(module: &rspirv::dr::Module) -> Vec<String> {
@@ -247,15 +296,105 @@ fn entry_points(module: &rspirv::dr::Module) -> Vec<String> {
         .collect()
 }
 
+/// Collect the bindings of all runtime arrays of descriptors (see also
+/// `CompileResult::variable_count_bindings`).
+fn collect_variable_count_bindings(
+    module: &rspirv::dr::Module,
+    variable_count_bindings: &mut BTreeSet<DescriptorBinding>,
+) {
+    use rspirv::dr::Operand;
+    use rspirv::spirv::{Decoration, Op, StorageClass};
+
+    let type_defs: FxHashMap<_, _> = module
+        .types_global_values
+        .iter()
+        .filter_map(|inst| Some((inst.result_id?, inst)))
+        .collect();
+    let runtime_array_vars: FxHashSet<_> = module
+        .types_global_values
+        .iter()
+        .filter(|inst| {
+            inst.class.opcode == Op::Variable
+                && matches!(
+                    inst.operands[0].unwrap_storage_class(),
+                    StorageClass::UniformConstant
+                        | StorageClass::Uniform
+                        | StorageClass::StorageBuffer
+                )
+                && type_defs
+                    .get(&inst.result_type.unwrap())
+                    .filter(|ptr_ty| ptr_ty.class.opcode == Op::TypePointer)
+                    .and_then(|ptr_ty| type_defs.get(&ptr_ty.operands[1].unwrap_id_ref()))
+                    .is_some_and(|ty| ty.class.opcode == Op::TypeRuntimeArray)
+        })
+        .map(|inst| inst.result_id.unwrap())
+        .collect();
+
+    let mut descriptor_sets = FxHashMap::default();
+    let mut bindings = FxHashMap::default();
+    for inst in &module.annotations {
+        if inst.class.opcode != Op::Decorate {
+            continue;
+        }
+        let target = inst.operands[0].unwrap_id_ref();
+        if !runtime_array_vars.contains(&target) {
+            continue;
+        }
+        match inst.operands[1..] {
+            [
+                Operand::Decoration(Decoration::DescriptorSet),
+                Operand::LiteralBit32(set),
+            ] => {
+                descriptor_sets.insert(target, set);
+            }
+            [
+                Operand::Decoration(Decoration::Binding),
+                Operand::LiteralBit32(binding),
+            ] => {
+                bindings.insert(target, binding);
+            }
+            _ => {}
+        }
+    }
+    variable_count_bindings.extend(
+        bindings
+            .into_iter()
+            .map(|(var, binding)| DescriptorBinding {
+                descriptor_set: descriptor_sets.get(&var).copied().unwrap_or(0),
+                binding,
+            }),
+    );
+}
+
 fn post_link_single_module(
     sess: &Session,
     cg_args: &CodegenArgs,
+    link_cache: Option<&LinkCache>,
+    link_stats: Option<&LinkStatsCollector>,
     module: Module,
     out_filename: &Path,
     dump_prefix: Option<&OsStr>,
 ) {
     cg_args.do_disassemble(&module);
-    let spv_binary = module.assemble();
+    let spv_binary = linker::rspirv_unsupported::lower(module.assemble());
+
+    let mut output_module_stats = link_stats.map(|_| OutputModuleStats {
+        path: out_filename.to_path_buf(),
+        entry_points: linker::stats::entry_point_stats(&module),
+        counts_before_opt: linker::stats::module_counts(&module),
+        size_before_opt: spirv_tools::binary::from_binary(&spv_binary).len(),
+        size: 0,
+        spirv_opt_wall_time_ms: None,
+        spirv_val_wall_time_ms: None,
+    });
+    let record_output_module_stats = |output_module_stats: Option<OutputModuleStats>,
+                                      spv_binary: &[u32]| {
+        if let (Some(link_stats), Some(mut output_module_stats)) = (link_stats, output_module_stats)
+        {
+            output_module_stats.size = spirv_tools::binary::from_binary(spv_binary).len();
+            link_stats.record_output_module(output_module_stats);
+        }
+    };
 
     if let Some(dir) = &cg_args.dump_post_link {
         // FIXME(eddyb) rename `filename` with `file_path` to make this less confusing.
@@ -284,21 +423,51 @@ fn post_link_single_module(
         preserve_spec_constants: false,
     };
 
+    let post_link_cache_and_key =
+        link_cache.map(|link_cache| (link_cache, link_cache.post_link_key(&spv_binary)));
+    let cached_spv_binary = post_link_cache_and_key.and_then(|(link_cache, key)| {
+        let _timer = sess.timer("link_cache_load");
+        link_cache.load_post_link(sess, key)
+    });
+    if let Some(spv_binary) = cached_spv_binary {
+        record_output_module_stats(output_module_stats, &spv_binary);
+        save_module(sess, &spv_binary, out_filename);
+        return;
+    }
+
+    // NOTE these are kept alongside the cached results, to re-emit them on reuse.
+    let warnings = DiagRecorder::default();
+
     let spv_binary = if sess.opts.optimize != OptLevel::No
         || (sess.opts.debuginfo == DebugInfo::None && cg_args.spirv_metadata == SpirvMetadata::None)
     {
         if cg_args.run_spirv_opt {
             let _timer = sess.timer("link_spirv_opt");
-            do_spirv_opt(sess, cg_args, spv_binary, out_filename, opt_options)
+            let start = Instant::now();
+            let spv_binary = do_spirv_opt(
+                sess,
+                cg_args,
+                spv_binary,
+                out_filename,
+                opt_options,
+                &warnings,
+            );
+            if let Some(output_module_stats) = &mut output_module_stats {
+                output_module_stats.spirv_opt_wall_time_ms =
+                    Some(start.elapsed().as_secs_f64() * 1000.0);
+            }
+            spv_binary
         } else {
             let reason = match (sess.opts.optimize, sess.opts.debuginfo == DebugInfo::None) {
                 (OptLevel::No, true) => "debuginfo=None".to_string(),
                 (optlevel, false) => format!("optlevel={optlevel:?}"),
                 (optlevel, true) => format!("optlevel={optlevel:?}, debuginfo=None"),
             };
-            sess.dcx().warn(format!(
+            let warning = sess.dcx().struct_warn(format!(
                 "`spirv-opt` should have ran ({reason}) but was disabled by `--no-spirv-opt`"
             ));
+            warnings.record(sess, &warning);
+            warning.emit();
             spv_binary
         }
     } else {
@@ -306,22 +475,41 @@ fn post_link_single_module(
     };
 
     if cg_args.run_spirv_val {
+        let start = Instant::now();
         do_spirv_val(sess, &spv_binary, out_filename, val_options);
+        if let Some(output_module_stats) = &mut output_module_stats {
+            output_module_stats.spirv_val_wall_time_ms =
+                Some(start.elapsed().as_secs_f64() * 1000.0);
+        }
     }
 
+    // NOTE only results which `spirv-opt`/`spirv-val` didn't error on
+    // are cached, so that any such errors keep being reported on rebuilds.
+    if let Some((link_cache, key)) = post_link_cache_and_key
+        && sess.dcx().has_errors().is_none()
     {
-        let save_modules_timer = sess.timer("link_save_modules");
-        if let Err(e) = std::fs::write(out_filename, spirv_tools::binary::from_binary(&spv_binary))
-        {
-            let mut err = sess
-                .dcx()
-                .struct_err("failed to serialize spirv-binary to disk");
-            err.note(format!("module `{}`", out_filename.display()));
-            err.note(format!("I/O error: {e:#}"));
-            err.emit();
-        }
+        let _timer = sess.timer("link_cache_store");
+        link_cache.store_post_link(
+            sess,
+            key,
+            spirv_tools::binary::from_binary(&spv_binary),
+            &warnings.into_diags(),
+        );
+    }
 
-        drop(save_modules_timer);
+    record_output_module_stats(output_module_stats, &spv_binary);
+    save_module(sess, &spv_binary, out_filename);
+}
+
+fn save_module(sess: &Session, spv_binary: &[u32], out_filename: &Path) {
+    let _timer = sess.timer("link_save_modules");
+    if let Err(e) = std::fs::write(out_filename, spirv_tools::binary::from_binary(spv_binary)) {
+        let mut err = sess
+            .dcx()
+            .struct_err("failed to serialize spirv-binary to disk");
+        err.note(format!("module `{}`", out_filename.display()));
+        err.note(format!("I/O error: {e:#}"));
+        err.emit();
     }
 }
 
@@ -331,6 +519,7 @@ fn do_spirv_opt(
     spv_binary: Vec<u32>,
     filename: &Path,
     options: spirv_tools::opt::Options,
+    warnings: &DiagRecorder,
 ) -> Vec<u32> {
     use spirv_tools::{
         error,
@@ -373,6 +562,7 @@ fn do_spirv_opt(
             };
 
             err.note(format!("module `{}`", filename.display()));
+            warnings.record(sess, &err);
             err.emit();
         },
         Some(options),
@@ -385,6 +575,7 @@ fn do_spirv_opt(
             let mut err = sess.dcx().struct_warn(e.to_string());
             err.note("spirv-opt failed, leaving as unoptimized");
             err.note(format!("module `{}`", filename.display()));
+            warnings.record(sess, &err);
             err.emit();
             spv_binary
         }
@@ -556,42 +747,122 @@ fn create_archive(files: &[&Path], metadata: &[u8], out_filename: &Path) {
 pub fn with_rspirv_loader<E>(
     f: impl FnOnce(&mut dyn rspirv::binary::Consumer) -> Result<(), E>,
 ) -> Result<rspirv::dr::Module, E> {
-    let mut loader = rspirv::dr::Loader::new();
+    let mut loader = Loader::default();
     f(&mut loader)?;
     Ok(loader.module())
 }
 
+/// Wrapper around `rspirv::dr::Loader`, which places type declarations that
+/// `rspirv` doesn't know about (see `is_unknown_type`) in the right position
+/// within `types_global_values`, instead of erroring on them.
+#[derive(Default)]
+struct Loader {
+    inner: rspirv::dr::Loader,
+
+    in_function: bool,
+    in_block: bool,
+
+    /// Length of the `types_global_values` of `inner`, tracked by replicating
+    /// the logic used by `rspirv::dr::Loader` to place instructions there.
+    types_global_values_len: usize,
+
+    /// Unknown type declarations, and the indices they should be inserted at,
+    /// in the final `types_global_values`.
+    unknown_types: Vec<(usize, rspirv::dr::Instruction)>,
+}
+
+impl Loader {
+    // FIXME remove this once `rspirv::grammar::reflect::is_type` knows
+    // about all the types we can emit.
+    fn is_unknown_type(op: rspirv::spirv::Op) -> bool {
+        matches!(op, rspirv::spirv::Op::TypeCooperativeMatrixKHR)
+    }
+
+    fn module(self) -> rspirv::dr::Module {
+        let mut module = self.inner.module();
+        for (i, inst) in self.unknown_types {
+            module.types_global_values.insert(i, inst);
+        }
+        module
+    }
+}
+
+impl rspirv::binary::Consumer for Loader {
+    fn initialize(&mut self) -> rspirv::binary::ParseAction {
+        self.inner.initialize()
+    }
+
+    fn finalize(&mut self) -> rspirv::binary::ParseAction {
+        self.inner.finalize()
+    }
+
+    fn consume_header(&mut self, header: rspirv::dr::ModuleHeader) -> rspirv::binary::ParseAction {
+        self.inner.consume_header(header)
+    }
+
+    fn consume_instruction(
+        &mut self,
+        inst: rspirv::dr::Instruction,
+    ) -> rspirv::binary::ParseAction {
+        use rspirv::grammar::reflect;
+        use rspirv::spirv::Op;
+
+        let op = inst.class.opcode;
+        if !self.in_function && Self::is_unknown_type(op) {
+            self.unknown_types.push((
+                self.types_global_values_len + self.unknown_types.len(),
+                inst,
+            ));
+            return rspirv::binary::ParseAction::Continue;
+        }
+
+        let goes_in_types_global_values = match op {
+            _ if reflect::is_location_debug(op) => !self.in_block,
+            _ if reflect::is_annotation(op) => false,
+            _ if reflect::is_type(op) || reflect::is_constant(op) => true,
+            Op::Variable | Op::Undef => !self.in_function,
+            _ => false,
+        };
+        if goes_in_types_global_values {
+            self.types_global_values_len += 1;
+        }
+        match op {
+            Op::Function => self.in_function = true,
+            Op::FunctionEnd => self.in_function = false,
+            Op::Label => self.in_block = true,
+            _ if reflect::is_block_terminator(op) => self.in_block = false,
+            _ => {}
+        }
+
+        self.inner.consume_instruction(inst)
+    }
+}
+
 /// This is the actual guts of linking: the rest of the link-related functions are just digging through rustc's
 /// shenanigans to collect all the object files we need to link.
+#[allow(clippy::too_many_arguments)]
 fn do_link(
     sess: &Session,
     cg_args: &CodegenArgs,
+    link_cache: Option<&LinkCache>,
+    link_stats: Option<&LinkStatsCollector>,
     objects: &[PathBuf],
     rlibs: &[PathBuf],
     outputs: &OutputFilenames,
     disambiguated_crate_name_for_dumps: &OsStr,
-) -> linker::LinkResult {
+) -> linker::LinkOutput {
     let load_modules_timer = sess.timer("link_load_modules");
 
-    let mut modules = Vec::new();
-    let mut add_module = |file_name: &OsStr, bytes: &[u8]| {
-        let module =
-            with_rspirv_loader(|loader| rspirv::binary::parse_bytes(bytes, loader)).unwrap();
-        if let Some(dir) = &cg_args.dump_pre_link {
-            // FIXME(eddyb) is it a good idea to re-`assemble` the `rspirv::dr`
-            // module, or should this just save the original bytes?
-            std::fs::write(
-                dir.join(file_name).with_extension("spv"),
-                spirv_tools::binary::from_binary(&module.assemble()),
-            )
-            .unwrap();
-        }
-        modules.push(module);
-    };
+    // NOTE all inputs are read (but not parsed) first, as linking can
+    // be skipped altogether if its result, for these exact inputs, is cached.
+    let mut inputs: Vec<(OsString, Vec<u8>)> = Vec::new();
 
     // `objects` are the plain obj files we need to link - usually produced by the final crate.
     for obj in objects {
-        add_module(obj.file_name().unwrap(), &std::fs::read(obj).unwrap());
+        inputs.push((
+            obj.file_name().unwrap().to_owned(),
+            std::fs::read(obj).unwrap(),
+        ));
     }
 
     // `rlibs` are archive files we've created in `create_archive`, usually produced by crates that are being
@@ -607,10 +878,74 @@ fn do_link(
                 entry.read_to_end(&mut bytes).unwrap();
 
                 let file_name = std::str::from_utf8(entry.header().identifier()).unwrap();
-                add_module(OsStr::new(file_name), &bytes);
+                inputs.push((file_name.into(), bytes));
+            }
+        }
+    }
+
+    // `link_spirv_libs` are standalone SPIR-V modules, provided by the user (e.g. via `spirv-builder`),
+    // which can be produced by other Rust-GPU builds (see `--keep-link-export`), or other compilers.
+    let num_non_lib_inputs = inputs.len();
+    for lib in &cg_args.linker_opts.link_spirv_libs {
+        match std::fs::read(lib) {
+            Ok(bytes) => inputs.push((lib.file_name().unwrap().to_owned(), bytes)),
+            Err(e) => {
+                sess.dcx()
+                    .struct_err("failed to load SPIR-V library")
+                    .with_note(format!("library `{}`", lib.display()))
+                    .with_note(format!("I/O error: {e:#}"))
+                    .emit();
+            }
+        }
+    }
+    sess.dcx().abort_if_errors();
+
+    let link_cache_and_key = link_cache
+        .filter(|_| LinkCache::can_cache_link(cg_args))
+        .map(|link_cache| {
+            let key = link_cache.link_key(inputs.iter().map(|(_, bytes)| &bytes[..]));
+            (link_cache, key)
+        });
+    if let Some((link_cache, key)) = link_cache_and_key {
+        let _timer = sess.timer("link_cache_load");
+        if let Some(link_output) =
+            link_cache.load_link_output(sess, &cg_args.linker_opts.module_output_type, key)
+        {
+            if let Some(link_stats) = link_stats {
+                link_stats.record_link_cache_hit();
+            }
+            return link_output;
+        }
+    }
+
+    let mut modules = Vec::with_capacity(inputs.len());
+    for (i, (file_name, bytes)) in inputs.into_iter().enumerate() {
+        let module = with_rspirv_loader(|loader| rspirv::binary::parse_bytes(&bytes, loader));
+        let module = match module {
+            Ok(module) => module,
+            Err(e) if i >= num_non_lib_inputs => {
+                let lib = &cg_args.linker_opts.link_spirv_libs[i - num_non_lib_inputs];
+                sess.dcx()
+                    .struct_err("failed to load SPIR-V library")
+                    .with_note(format!("library `{}`", lib.display()))
+                    .with_note(format!("SPIR-V parse error: {e}"))
+                    .emit();
+                continue;
             }
+            Err(e) => panic!("failed to parse `{}`: {e}", file_name.display()),
+        };
+        if let Some(dir) = &cg_args.dump_pre_link {
+            // FIXME(eddyb) is it a good idea to re-`assemble` the `rspirv::dr`
+            // module, or should this just save the original bytes?
+            std::fs::write(
+                dir.join(&file_name).with_extension("spv"),
+                spirv_tools::binary::from_binary(&module.assemble()),
+            )
+            .unwrap();
         }
+        modules.push(module);
     }
+    sess.dcx().abort_if_errors();
 
     drop(load_modules_timer);
 
@@ -621,9 +956,15 @@ fn do_link(
         &cg_args.linker_opts,
         outputs,
         disambiguated_crate_name_for_dumps,
+        link_stats,
+        link_cache_and_key.map(|(link_cache, _)| link_cache),
     );
 
     if let Ok(v) = link_result {
+        if let Some((link_cache, key)) = link_cache_and_key {
+            let _timer = sess.timer("link_cache_store");
+            link_cache.store_link_output(sess, key, &v);
+        }
         v
     } else {
         sess.dcx().abort_if_errors();
@@ -0,0 +1,940 @@
+//! Content-addressed cache for the (expensive) stages of linking, enabled by
+//! the `--link-cache DIR` codegen arg (see also `docs/src/codegen-args.md`).
+//!
+//! Three kinds of entries are kept in `DIR` (each entry being a directory):
+//! - `link/{hash}/`: the output module(s) of `crate::linker::link` (as `.spv`
+//!   files), keyed by the contents of all of its input modules (i.e. the
+//!   per-CGU `.spv` objects, from both the crate being linked and all of its
+//!   dependencies), alongside `shader-log-formats.json` (if non-empty), and
+//!   `abort-record.json` (if any)
+//! - `entry-point/{hash}/module.spv`: with `--module-output=multiple`, the
+//!   output module of one entry-point, keyed by the contents of everything
+//!   that entry-point (transitively) uses, after import/export linking
+//!   (see `LinkCache::entry_point_key`)
+//! - `post-link/{hash}/module.spv`: the final (optimized and validated) form
+//!   of one output module, keyed by its contents *before* `spirv-opt`
+//!   (and `spirv-val`)
+//!
+//! That is, on top of skipping linking altogether when none of its inputs
+//! changed, `--module-output=multiple` also gets incremental linking, at the
+//! granularity of entry-points: after import/export linking, the entry-points
+//! which are found in the cache are removed from the module being linked, so
+//! the rest of the linker pipeline (and `spirv-opt`/`spirv-val`) only has to
+//! process the entry-points affected by any changes (note that the contents of
+//! source files are included in the SPIR-V, as `OpSource` debuginfo, so any
+//! change to a source file affects all entry-points using code from it).
+//!
+//! Any warnings emitted while producing the contents of an entry are recorded
+//! (as `CachedDiag`s, in `diags.json`), and re-emitted whenever it's reused.
+//!
+//! All keys also include everything else that could affect the outputs,
+//! i.e. the "codegen args", target, target features, optimization level,
+//! debuginfo level, and the `rustc_codegen_spirv` dylib itself.
+//!
+//! The least recently used entries are removed whenever the total size of
+//! all entries exceeds `MAX_TOTAL_SIZE` (see `LinkCache::evict`).
+
+use crate::codegen_cx::{CodegenArgs, ModuleOutputType};
+use crate::linker::{LinkOutput, LinkResult};
+use rspirv::binary::Assemble;
+use rspirv::dr::{Instruction, Module};
+use rustc_codegen_spirv_types::serde::{Deserialize, Serialize};
+use rustc_codegen_spirv_types::serde_json;
+use rustc_data_structures::stable_hasher::StableHasher;
+use rustc_errors::{Diag, DiagMessage, EmissionGuarantee, Level, MultiSpan};
+use rustc_hashes::Hash128;
+use rustc_session::Session;
+use rustc_span::{BytePos, DUMMY_SP, Span};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+const SHADER_LOG_FORMATS_FILE_NAME: &str = "shader-log-formats.json";
+const ABORT_RECORD_FILE_NAME: &str = "abort-record.json";
+const DIAGS_FILE_NAME: &str = "diags.json";
+const MODULE_FILE_NAME: &str = "module.spv";
+
+const LINK_KIND: &str = "link";
+const ENTRY_POINT_KIND: &str = "entry-point";
+const POST_LINK_KIND: &str = "post-link";
+
+/// Once the total size of all entries exceeds this, the least recently used
+/// ones are removed (see `LinkCache::evict`).
+const MAX_TOTAL_SIZE: u64 = 1 << 30;
+
+pub struct LinkCache {
+    dir: PathBuf,
+
+    /// Hash of everything (other than the module contents) that may affect
+    /// the results cached in `dir`, shared by all cache keys.
+    config_hash: Hash128,
+
+    /// Whether any entries were written, i.e. whether `evict` has any work to do.
+    any_stored: Cell<bool>,
+}
+
+/// Content hash identifying one cache entry.
+#[derive(Copy, Clone)]
+pub struct CacheKey(Hash128);
+
+impl CacheKey {
+    fn file_name(self) -> String {
+        format!("{:032x}", self.0.as_u128())
+    }
+}
+
+/// A warning (or note) emitted while producing the contents of a cache entry,
+/// which has to be re-emitted whenever that entry is reused (see `DiagRecorder`).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rustc_codegen_spirv_types::serde")]
+pub struct CachedDiag {
+    level: CachedDiagLevel,
+    message: String,
+    span: Option<CachedSpan>,
+    children: Vec<CachedDiag>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rustc_codegen_spirv_types::serde")]
+enum CachedDiagLevel {
+    Warning,
+    Note,
+    Help,
+}
+
+/// Source location of a `CachedDiag`, independent of the `SourceMap` of the
+/// session that emitted it (lines are 1-based, columns are 0-based, in `char`s).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rustc_codegen_spirv_types::serde")]
+struct CachedSpan {
+    file: String,
+    line_start: u32,
+    col_start: u32,
+    line_end: u32,
+    col_end: u32,
+}
+
+/// Collects `CachedDiag`s, from the diagnostics passed to `record` (which should
+/// be all of the non-error diagnostics, just before they're emitted).
+#[derive(Default)]
+pub struct DiagRecorder {
+    diags: RefCell<Vec<CachedDiag>>,
+}
+
+impl DiagRecorder {
+    pub fn record<G: EmissionGuarantee>(&self, sess: &Session, diag: &Diag<'_, G>) {
+        let Some(level) = CachedDiagLevel::from_rustc(diag.level()) else {
+            return;
+        };
+        self.diags.borrow_mut().push(CachedDiag {
+            level,
+            message: message_to_string(&diag.messages),
+            span: CachedSpan::from_rustc(sess, &diag.span),
+            children: diag
+                .children
+                .iter()
+                .filter_map(|child| {
+                    Some(CachedDiag {
+                        level: CachedDiagLevel::from_rustc(child.level)?,
+                        message: message_to_string(&child.messages),
+                        span: CachedSpan::from_rustc(sess, &child.span),
+                        children: vec![],
+                    })
+                })
+                .collect(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diags.borrow().is_empty()
+    }
+
+    pub fn into_diags(self) -> Vec<CachedDiag> {
+        self.diags.into_inner()
+    }
+}
+
+fn message_to_string(messages: &[(DiagMessage, rustc_errors::Style)]) -> String {
+    messages
+        .iter()
+        .map(|(message, _)| match message {
+            DiagMessage::Str(s) | DiagMessage::Translated(s) => s.to_string(),
+            DiagMessage::FluentIdentifier(id, attr) => match attr {
+                Some(attr) => format!("{id}.{attr}"),
+                None => id.to_string(),
+            },
+        })
+        .collect()
+}
+
+impl CachedDiagLevel {
+    fn from_rustc(level: Level) -> Option<Self> {
+        match level {
+            Level::Warning | Level::ForceWarning => Some(Self::Warning),
+            Level::Note | Level::OnceNote => Some(Self::Note),
+            Level::Help | Level::OnceHelp => Some(Self::Help),
+            _ => None,
+        }
+    }
+}
+
+impl CachedSpan {
+    fn from_rustc(sess: &Session, span: &MultiSpan) -> Option<Self> {
+        let span = span.primary_span().filter(|span| !span.is_dummy())?;
+        let source_map = sess.source_map();
+        let (lo, hi) = (
+            source_map.lookup_char_pos(span.lo()),
+            source_map.lookup_char_pos(span.hi()),
+        );
+        Some(Self {
+            file: lo.file.name.prefer_local().to_string(),
+            line_start: lo.line as u32,
+            col_start: lo.col.0 as u32,
+            line_end: hi.line as u32,
+            col_end: hi.col.0 as u32,
+        })
+    }
+
+    /// Find the `SourceFile` (either already loaded, or loading it from disk)
+    /// and convert the line/column pairs back into a `Span`, if possible.
+    fn to_rustc(&self, sess: &Session) -> Option<Span> {
+        let source_map = sess.source_map();
+        let loaded_file = source_map
+            .files()
+            .iter()
+            .find(|file| file.name.prefer_local().to_string() == self.file)
+            .cloned();
+        let file = match loaded_file {
+            Some(file) => file,
+            None => source_map.load_file(Path::new(&self.file)).ok()?,
+        };
+        let line_col_to_bpos = |line: u32, col: u32| {
+            let line_idx_in_file = line.checked_sub(1)? as usize;
+            let line_contents = file.get_line(line_idx_in_file)?;
+            let col_offset: usize = line_contents
+                .chars()
+                .take(col as usize)
+                .map(char::len_utf8)
+                .sum();
+            Some(file.line_bounds(line_idx_in_file).start + BytePos(col_offset as u32))
+        };
+        Some(Span::with_root_ctxt(
+            line_col_to_bpos(self.line_start, self.col_start)?,
+            line_col_to_bpos(self.line_end, self.col_end)?,
+        ))
+    }
+}
+
+impl CachedDiag {
+    pub fn emit(&self, sess: &Session) {
+        let dcx = sess.dcx();
+        let mut diag = match self.level {
+            CachedDiagLevel::Warning => dcx.struct_warn(self.message.clone()),
+            CachedDiagLevel::Note => dcx.struct_note(self.message.clone()),
+            CachedDiagLevel::Help => dcx.struct_help(self.message.clone()),
+        };
+        let (span, location_note) = Self::span_and_location_note(sess, &self.span);
+        diag.span(span);
+        if let Some(location_note) = location_note {
+            diag.note(location_note);
+        }
+        for child in &self.children {
+            let (span, location_note) = Self::span_and_location_note(sess, &child.span);
+            let message = match location_note {
+                Some(location_note) => format!("{} ({location_note})", child.message),
+                None => child.message.clone(),
+            };
+            match child.level {
+                CachedDiagLevel::Warning => diag.span_warn(span, message),
+                CachedDiagLevel::Note => diag.span_note(span, message),
+                CachedDiagLevel::Help => diag.span_help(span, message),
+            };
+        }
+        diag.emit();
+    }
+
+    /// Get the `Span` for `span`, or failing that, a note describing its location.
+    fn span_and_location_note(
+        sess: &Session,
+        span: &Option<CachedSpan>,
+    ) -> (MultiSpan, Option<String>) {
+        let Some(span) = span else {
+            return (MultiSpan::new(), None);
+        };
+        match span.to_rustc(sess) {
+            Some(span) => (span.into(), None),
+            None => (
+                DUMMY_SP.into(),
+                Some(format!(
+                    "at {}:{}:{}",
+                    span.file,
+                    span.line_start,
+                    span.col_start + 1
+                )),
+            ),
+        }
+    }
+}
+
+fn emit_all(sess: &Session, diags: &[CachedDiag]) {
+    for diag in diags {
+        diag.emit(sess);
+    }
+}
+
+impl LinkCache {
+    pub fn new(sess: &Session, cg_args: &CodegenArgs) -> Option<Self> {
+        let dir = cg_args.link_cache.clone()?;
+
+        let mut hasher = StableHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        // NOTE the contents of the backend itself are hashed, so that rebuilding
+        // `rustc_codegen_spirv` (e.g. during development) invalidates all of
+        // its previous results, even without a version bump.
+        if let Some(backend) = &sess.opts.unstable_opts.codegen_backend {
+            backend.hash(&mut hasher);
+            fs::read(backend).ok().hash(&mut hasher);
+        }
+        sess.opts.cg.llvm_args.hash(&mut hasher);
+        sess.opts.cg.target_feature.hash(&mut hasher);
+        sess.opts.target_triple.hash(&mut hasher);
+        sess.opts.optimize.hash(&mut hasher);
+        sess.opts.debuginfo.hash(&mut hasher);
+
+        Some(Self::with_config_hash(dir, hasher.finish()))
+    }
+
+    pub(crate) fn with_config_hash(dir: PathBuf, config_hash: Hash128) -> Self {
+        Self {
+            dir,
+            config_hash,
+            any_stored: Cell::new(false),
+        }
+    }
+
+    /// Whether linking itself can be cached, which is not the case when any
+    /// of the debugging options (e.g. dumps) which require running the linker
+    /// (to observe its intermediary state) are enabled.
+    pub fn can_cache_link(cg_args: &CodegenArgs) -> bool {
+        let opts = &cg_args.linker_opts;
+        cg_args.dump_pre_link.is_none()
+            && opts.zombie_report.is_none()
+            && opts.dump_post_merge.is_none()
+            && opts.dump_pre_inline.is_none()
+            && opts.dump_post_inline.is_none()
+            && opts.dump_post_split.is_none()
+            && opts.dump_spirt_passes.is_none()
+            && opts.specializer_dump_instances.is_none()
+    }
+
+    fn key<'a>(&self, kind: &str, contents: impl IntoIterator<Item = &'a [u8]>) -> CacheKey {
+        let mut hasher = StableHasher::new();
+        self.config_hash.hash(&mut hasher);
+        kind.hash(&mut hasher);
+        for bytes in contents {
+            bytes.hash(&mut hasher);
+        }
+        CacheKey(hasher.finish())
+    }
+
+    /// Key for the result of linking `input_modules` (in the order given).
+    pub fn link_key<'a>(&self, input_modules: impl IntoIterator<Item = &'a [u8]>) -> CacheKey {
+        self.key(LINK_KIND, input_modules)
+    }
+
+    /// Key for the output module of the `entry_point` (one of the `OpEntryPoint`s
+    /// of `module`), computed from only the parts of `module` reachable from it
+    /// (plus all the module-wide instructions, e.g. `OpCapability`), after
+    /// renumbering all IDs (in order of appearance), so that any unrelated
+    /// changes to the rest of `module` don't affect the key.
+    pub fn entry_point_key(&self, module: &Module, entry_point: &Instruction) -> CacheKey {
+        let mut module = module.clone();
+        module.entry_points = vec![entry_point.clone()];
+        crate::linker::dce::dce(&mut module);
+        module.header.as_mut().unwrap().bound =
+            crate::linker::simple_passes::compact_ids(&mut module);
+        self.key(
+            ENTRY_POINT_KIND,
+            [spirv_tools::binary::from_binary(&module.assemble())],
+        )
+    }
+
+    /// Key for the optimized and validated form of the `spv_binary` module.
+    pub fn post_link_key(&self, spv_binary: &[u32]) -> CacheKey {
+        self.key(
+            POST_LINK_KIND,
+            [spirv_tools::binary::from_binary(spv_binary)],
+        )
+    }
+
+    fn entry_dir(&self, kind: &str, key: CacheKey) -> PathBuf {
+        self.dir.join(kind).join(key.file_name())
+    }
+
+    /// Get the directory of an existing entry (if any), while also marking
+    /// it as used (for `evict`), by updating the modification time of its files.
+    fn load_entry(&self, kind: &str, key: CacheKey) -> Option<PathBuf> {
+        let entry_dir = self.entry_dir(kind, key);
+        let now = SystemTime::now();
+        for dir_entry in fs::read_dir(&entry_dir).ok()? {
+            let path = dir_entry.ok()?.path();
+            // NOTE failing to update the modification time only affects `evict`.
+            if let Ok(file) = fs::File::options().append(true).open(path) {
+                let _ = file.set_modified(now);
+            }
+        }
+        Some(entry_dir)
+    }
+
+    fn load_module(path: &Path) -> Option<Module> {
+        let bytes = fs::read(path).ok()?;
+        crate::link::with_rspirv_loader(|loader| rspirv::binary::parse_bytes(bytes, loader)).ok()
+    }
+
+    /// Load an optional JSON file (i.e. `T::default()` if it doesn't exist).
+    fn load_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> Option<T> {
+        if !path.exists() {
+            return Some(T::default());
+        }
+        let json = fs::read(path).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    fn try_store_entry(
+        &self,
+        kind: &str,
+        key: CacheKey,
+        files: Vec<(impl AsRef<Path>, Vec<u8>)>,
+    ) -> io::Result<()> {
+        let entry_dir = self.entry_dir(kind, key);
+        // NOTE the entry is written to a temporary directory first, and
+        // then renamed into place, to avoid ever exposing a partial entry (e.g.
+        // to another concurrent build sharing the same cache directory).
+        let tmp_dir = entry_dir.with_extension(format!("tmp{}", std::process::id()));
+        let result = (|| {
+            fs::create_dir_all(&tmp_dir)?;
+            for (file_name, contents) in files {
+                fs::write(tmp_dir.join(file_name), contents)?;
+            }
+            if entry_dir.exists() {
+                fs::remove_dir_all(&tmp_dir)
+            } else {
+                fs::rename(&tmp_dir, &entry_dir)
+            }
+        })();
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&tmp_dir);
+        }
+        self.any_stored.set(true);
+        result
+    }
+
+    /// Load the result of linking (if cached), re-emitting any of its warnings.
+    pub fn load_link_output(
+        &self,
+        sess: &Session,
+        module_output_type: &ModuleOutputType,
+        key: CacheKey,
+    ) -> Option<LinkOutput> {
+        let link_output = self.try_load_link_output(module_output_type, key)?;
+        emit_all(sess, &link_output.warnings);
+        Some(link_output)
+    }
+
+    fn try_load_link_output(
+        &self,
+        module_output_type: &ModuleOutputType,
+        key: CacheKey,
+    ) -> Option<LinkOutput> {
+        let entry_dir = self.load_entry(LINK_KIND, key)?;
+        let shader_log_formats = Self::load_json(&entry_dir.join(SHADER_LOG_FORMATS_FILE_NAME))?;
+        let abort_record = Self::load_json(&entry_dir.join(ABORT_RECORD_FILE_NAME))?;
+        let warnings = Self::load_json(&entry_dir.join(DIAGS_FILE_NAME))?;
+
+        let result = match module_output_type {
+            ModuleOutputType::Single => LinkResult::SingleModule(Box::new(Self::load_module(
+                &entry_dir.join(MODULE_FILE_NAME),
+            )?)),
+            ModuleOutputType::Multiple => {
+                let mut file_stem_to_entry_name_and_module = BTreeMap::new();
+                for dir_entry in fs::read_dir(&entry_dir).ok()? {
+                    let path = dir_entry.ok()?.path();
+                    if path.extension() != Some("spv".as_ref()) {
+                        continue;
+                    }
+                    let module = Self::load_module(&path)?;
+                    let entry_name = module
+                        .entry_points
+                        .first()?
+                        .operands
+                        .get(2)?
+                        .unwrap_literal_string()
+                        .to_string();
+                    file_stem_to_entry_name_and_module
+                        .insert(path.file_stem()?.to_os_string(), (entry_name, module));
+                }
+                LinkResult::MultipleModules {
+                    file_stem_to_entry_name_and_module,
+                }
+            }
+        };
+        Some(LinkOutput {
+            result,
+            shader_log_formats,
+            abort_record,
+            warnings,
+        })
+    }
+
+    pub fn store_link_output(&self, sess: &Session, key: CacheKey, link_output: &LinkOutput) {
+        if let Err(e) = self.try_store_link_output(key, link_output) {
+            warn_failed_write(sess, &self.entry_dir(LINK_KIND, key), e);
+        }
+    }
+
+    fn try_store_link_output(&self, key: CacheKey, link_output: &LinkOutput) -> io::Result<()> {
+        let modules: Vec<(OsString, &Module)> = match &link_output.result {
+            LinkResult::SingleModule(module) => vec![("module".into(), &**module)],
+            LinkResult::MultipleModules {
+                file_stem_to_entry_name_and_module,
+            } => file_stem_to_entry_name_and_module
+                .iter()
+                .map(|(file_stem, (_, module))| (file_stem.clone(), module))
+                .collect(),
+        };
+
+        let mut files: Vec<(OsString, Vec<u8>)> = vec![];
+        for (file_stem, module) in modules {
+            let mut file_name = file_stem;
+            file_name.push(".spv");
+            files.push((
+                file_name,
+                spirv_tools::binary::from_binary(&module.assemble()).to_vec(),
+            ));
+        }
+        if !link_output.shader_log_formats.is_empty() {
+            files.push((
+                SHADER_LOG_FORMATS_FILE_NAME.into(),
+                serde_json::to_vec(&link_output.shader_log_formats)?,
+            ));
+        }
+        if let Some(abort_record) = &link_output.abort_record {
+            files.push((
+                ABORT_RECORD_FILE_NAME.into(),
+                serde_json::to_vec(abort_record)?,
+            ));
+        }
+        if !link_output.warnings.is_empty() {
+            files.push((
+                DIAGS_FILE_NAME.into(),
+                serde_json::to_vec(&link_output.warnings)?,
+            ));
+        }
+        self.try_store_entry(LINK_KIND, key, files)
+    }
+
+    /// Load the output module of one entry-point (if cached).
+    ///
+    /// NOTE only output modules that were produced without any warnings are
+    /// stored (see `store_entry_point`), so there's nothing to re-emit here.
+    pub fn load_entry_point(&self, key: CacheKey) -> Option<Module> {
+        let entry_dir = self.load_entry(ENTRY_POINT_KIND, key)?;
+        Self::load_module(&entry_dir.join(MODULE_FILE_NAME))
+    }
+
+    pub fn store_entry_point(&self, sess: &Session, key: CacheKey, module: &Module) {
+        let spv_bytes = spirv_tools::binary::from_binary(&module.assemble()).to_vec();
+        if let Err(e) =
+            self.try_store_entry(ENTRY_POINT_KIND, key, vec![(MODULE_FILE_NAME, spv_bytes)])
+        {
+            warn_failed_write(sess, &self.entry_dir(ENTRY_POINT_KIND, key), e);
+        }
+    }
+
+    /// Load the final form of one output module (if cached), re-emitting any
+    /// of the warnings from when it was produced.
+    pub fn load_post_link(&self, sess: &Session, key: CacheKey) -> Option<Vec<u32>> {
+        let (spv_binary, diags) = self.try_load_post_link(key)?;
+        emit_all(sess, &diags);
+        Some(spv_binary)
+    }
+
+    fn try_load_post_link(&self, key: CacheKey) -> Option<(Vec<u32>, Vec<CachedDiag>)> {
+        let entry_dir = self.load_entry(POST_LINK_KIND, key)?;
+        let bytes = fs::read(entry_dir.join(MODULE_FILE_NAME)).ok()?;
+        if bytes.len() % 4 != 0 {
+            return None;
+        }
+        let diags = Self::load_json(&entry_dir.join(DIAGS_FILE_NAME))?;
+        Some((
+            bytes
+                .chunks_exact(4)
+                .map(|word| u32::from_ne_bytes(word.try_into().unwrap()))
+                .collect(),
+            diags,
+        ))
+    }
+
+    pub fn store_post_link(
+        &self,
+        sess: &Session,
+        key: CacheKey,
+        spv_bytes: &[u8],
+        diags: &[CachedDiag],
+    ) {
+        if let Err(e) = self.try_store_post_link(key, spv_bytes, diags) {
+            warn_failed_write(sess, &self.entry_dir(POST_LINK_KIND, key), e);
+        }
+    }
+
+    fn try_store_post_link(
+        &self,
+        key: CacheKey,
+        spv_bytes: &[u8],
+        diags: &[CachedDiag],
+    ) -> io::Result<()> {
+        let mut files = vec![(MODULE_FILE_NAME, spv_bytes.to_vec())];
+        if !diags.is_empty() {
+            files.push((DIAGS_FILE_NAME, serde_json::to_vec(diags)?));
+        }
+        self.try_store_entry(POST_LINK_KIND, key, files)
+    }
+
+    /// Remove the least recently used entries (i.e. the ones with the oldest
+    /// modification times, see also `load_entry`), until the total size of
+    /// all entries is at most `MAX_TOTAL_SIZE`.
+    ///
+    /// Only does any work if any entries were stored (by this `LinkCache`).
+    pub fn evict(&self, sess: &Session) {
+        if self.any_stored.get() {
+            let _timer = sess.timer("link_cache_evict");
+            self.evict_to_max_total_size(MAX_TOTAL_SIZE);
+        }
+    }
+
+    fn evict_to_max_total_size(&self, max_total_size: u64) {
+        let mut entries = vec![];
+        for kind in [LINK_KIND, ENTRY_POINT_KIND, POST_LINK_KIND] {
+            let Ok(kind_dir_entries) = fs::read_dir(self.dir.join(kind)) else {
+                continue;
+            };
+            for entry_dir in kind_dir_entries.filter_map(|e| Some(e.ok()?.path())) {
+                // Skip the temporary directories of in-progress writes.
+                if entry_dir.extension().is_some() {
+                    continue;
+                }
+                let (mut size, mut last_used) = (0, SystemTime::UNIX_EPOCH);
+                for metadata in fs::read_dir(&entry_dir)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|e| e.ok()?.metadata().ok())
+                {
+                    size += metadata.len();
+                    last_used = last_used.max(metadata.modified().unwrap_or(last_used));
+                }
+                entries.push((last_used, size, entry_dir));
+            }
+        }
+
+        let mut total_size: u64 = entries.iter().map(|&(_, size, _)| size).sum();
+        entries.sort();
+        for (_, size, entry_dir) in entries {
+            if total_size <= max_total_size {
+                break;
+            }
+            // NOTE concurrent builds may still be reading the entry, but any
+            // partial reads are treated the same as cache misses.
+            if fs::remove_dir_all(entry_dir).is_ok() {
+                total_size -= size;
+            }
+        }
+    }
+}
+
+fn warn_failed_write(sess: &Session, path: &Path, e: io::Error) {
+    sess.dcx()
+        .struct_warn("failed to write link cache entry")
+        .with_note(format!("cache entry `{}`", path.display()))
+        .with_note(format!("I/O error: {e:#}"))
+        .emit();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rspirv::spirv;
+
+    /// Fresh (empty) cache directory, unique to `name` and the current process.
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rust-gpu-link-cache-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn config_hash(config: &str) -> Hash128 {
+        let mut hasher = StableHasher::new();
+        config.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn module(entry_name: &str) -> Module {
+        let mut b = rspirv::dr::Builder::new();
+        b.set_version(1, 3);
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        let void = b.type_void();
+        let void_fn = b.type_function(void, []);
+        let main = b
+            .begin_function(void, None, spirv::FunctionControl::NONE, void_fn)
+            .unwrap();
+        b.begin_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(spirv::ExecutionModel::GLCompute, main, entry_name, []);
+        b.execution_mode(main, spirv::ExecutionMode::LocalSize, [1, 1, 1]);
+        b.module()
+    }
+
+    fn warning(message: &str) -> CachedDiag {
+        CachedDiag {
+            level: CachedDiagLevel::Warning,
+            message: message.to_string(),
+            span: Some(CachedSpan {
+                file: "src/lib.rs".to_string(),
+                line_start: 1,
+                col_start: 0,
+                line_end: 1,
+                col_end: 4,
+            }),
+            children: vec![CachedDiag {
+                level: CachedDiagLevel::Note,
+                message: "some note".to_string(),
+                span: None,
+                children: vec![],
+            }],
+        }
+    }
+
+    #[test]
+    fn post_link_hit_and_miss() {
+        let dir = cache_dir("post_link_hit_and_miss");
+        let cache = LinkCache::with_config_hash(dir.clone(), config_hash("config"));
+
+        let a = module("a").assemble();
+        let b = module("b").assemble();
+        let key_a = cache.post_link_key(&a);
+        let key_b = cache.post_link_key(&b);
+
+        assert_eq!(cache.try_load_post_link(key_a), None);
+        cache
+            .try_store_post_link(key_a, spirv_tools::binary::from_binary(&a), &[])
+            .unwrap();
+        assert_eq!(cache.try_load_post_link(key_a), Some((a.clone(), vec![])));
+        assert_eq!(
+            cache.try_load_post_link(cache.post_link_key(&a)),
+            Some((a, vec![]))
+        );
+        assert_eq!(cache.try_load_post_link(key_b), None);
+
+        // Warnings are kept alongside the module, to be re-emitted on hits.
+        let diags = [warning("`spirv-opt` warning")];
+        cache
+            .try_store_post_link(key_b, spirv_tools::binary::from_binary(&b), &diags)
+            .unwrap();
+        assert_eq!(cache.try_load_post_link(key_b), Some((b, diags.to_vec())));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn link_output_hit_and_miss() {
+        let dir = cache_dir("link_output_hit_and_miss");
+        let cache = LinkCache::with_config_hash(dir.clone(), config_hash("config"));
+
+        let inputs: [&[u8]; 2] = [b"first input", b"second input"];
+        let key = cache.link_key(inputs);
+        assert!(
+            cache
+                .try_load_link_output(&ModuleOutputType::Single, key)
+                .is_none()
+        );
+
+        let module = module("main");
+        let link_output = LinkOutput {
+            result: LinkResult::SingleModule(Box::new(module.clone())),
+            shader_log_formats: vec![],
+            abort_record: None,
+            warnings: vec![warning("linker warning")],
+        };
+        cache.try_store_link_output(key, &link_output).unwrap();
+
+        let loaded = cache
+            .try_load_link_output(&ModuleOutputType::Single, cache.link_key(inputs))
+            .unwrap();
+        match loaded.result {
+            LinkResult::SingleModule(loaded) => assert_eq!(loaded.assemble(), module.assemble()),
+            LinkResult::MultipleModules { .. } => panic!("expected a single module"),
+        }
+        assert!(loaded.shader_log_formats.is_empty());
+        assert!(loaded.abort_record.is_none());
+        assert_eq!(loaded.warnings, link_output.warnings);
+
+        // Any change to the inputs (or even just their order) is a miss.
+        for changed_inputs in [
+            [&b"first input"[..], b"changed input"],
+            [&b"second input"[..], b"first input"],
+        ] {
+            let changed_key = cache.link_key(changed_inputs);
+            assert!(
+                cache
+                    .try_load_link_output(&ModuleOutputType::Single, changed_key)
+                    .is_none()
+            );
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn entry_point_key_ignores_unrelated_changes() {
+        let dir = cache_dir("entry_point_key_ignores_unrelated_changes");
+        let cache = LinkCache::with_config_hash(dir.clone(), config_hash("config"));
+
+        // Two entry-points, `a` (unchanged) and `b` (changed, by adding an
+        // extra, unused, function type, which also shifts all later IDs).
+        let with_b = |extra_type: bool| {
+            let mut b = rspirv::dr::Builder::new();
+            b.set_version(1, 3);
+            b.capability(spirv::Capability::Shader);
+            b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+            let void = b.type_void();
+            let void_fn = b.type_function(void, []);
+            let entry = |b: &mut rspirv::dr::Builder, name: &str| {
+                let func = b
+                    .begin_function(void, None, spirv::FunctionControl::NONE, void_fn)
+                    .unwrap();
+                b.begin_block(None).unwrap();
+                b.ret().unwrap();
+                b.end_function().unwrap();
+                b.entry_point(spirv::ExecutionModel::GLCompute, func, name, []);
+                b.execution_mode(func, spirv::ExecutionMode::LocalSize, [1, 1, 1]);
+            };
+            if extra_type {
+                let u32 = b.type_int(32, 0);
+                b.type_function(void, [u32]);
+            }
+            entry(&mut b, "b");
+            entry(&mut b, "a");
+            b.module()
+        };
+        let (before, after) = (with_b(false), with_b(true));
+        let key = |module: &Module, name: &str| {
+            let entry_point = module
+                .entry_points
+                .iter()
+                .find(|e| e.operands[2].unwrap_literal_string() == name)
+                .unwrap();
+            cache.entry_point_key(module, entry_point).0
+        };
+
+        assert_eq!(key(&before, "a"), key(&after, "a"));
+        assert_ne!(key(&before, "a"), key(&before, "b"));
+
+        let a_module = module("a");
+        let a_key = cache.entry_point_key(&before, &before.entry_points[1]);
+        assert!(cache.load_entry_point(a_key).is_none());
+        cache
+            .try_store_entry(
+                ENTRY_POINT_KIND,
+                a_key,
+                vec![(
+                    MODULE_FILE_NAME,
+                    spirv_tools::binary::from_binary(&a_module.assemble()).to_vec(),
+                )],
+            )
+            .unwrap();
+        let loaded = cache
+            .load_entry_point(cache.entry_point_key(&after, &after.entry_points[1]))
+            .unwrap();
+        assert_eq!(loaded.assemble(), a_module.assemble());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalidation() {
+        let dir = cache_dir("invalidation");
+        let cache = LinkCache::with_config_hash(dir.clone(), config_hash("config"));
+
+        let spv = module("main").assemble();
+        let key = cache.post_link_key(&spv);
+        cache
+            .try_store_post_link(key, spirv_tools::binary::from_binary(&spv), &[])
+            .unwrap();
+        assert_eq!(cache.try_load_post_link(key), Some((spv.clone(), vec![])));
+
+        // Changing the configuration (e.g. the codegen args) invalidates all entries,
+        // even when sharing the same cache directory.
+        let other_config = LinkCache::with_config_hash(dir.clone(), config_hash("other config"));
+        assert_eq!(
+            other_config.try_load_post_link(other_config.post_link_key(&spv)),
+            None
+        );
+
+        // Corrupted entries are treated as misses.
+        fs::write(
+            cache.entry_dir(POST_LINK_KIND, key).join(MODULE_FILE_NAME),
+            [1, 2, 3],
+        )
+        .unwrap();
+        assert_eq!(cache.try_load_post_link(key), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn eviction() {
+        let dir = cache_dir("eviction");
+        let cache = LinkCache::with_config_hash(dir.clone(), config_hash("config"));
+
+        let modules = ["a", "b", "c"].map(|name| module(name).assemble());
+        let keys = modules.each_ref().map(|spv| cache.post_link_key(spv));
+        for (spv, &key) in modules.iter().zip(&keys) {
+            cache
+                .try_store_post_link(key, spirv_tools::binary::from_binary(spv), &[])
+                .unwrap();
+        }
+
+        // Make `b` the least recently used, then `a` (by using it later).
+        let set_last_used = |key, secs_ago| {
+            let path = cache.entry_dir(POST_LINK_KIND, key).join(MODULE_FILE_NAME);
+            fs::File::options()
+                .append(true)
+                .open(path)
+                .unwrap()
+                .set_modified(SystemTime::now() - std::time::Duration::from_secs(secs_ago))
+                .unwrap();
+        };
+        set_last_used(keys[0], 20);
+        set_last_used(keys[1], 30);
+        set_last_used(keys[2], 10);
+        assert!(cache.load_entry(POST_LINK_KIND, keys[0]).is_some());
+
+        // Only leave room for two entries.
+        let entry_size = spirv_tools::binary::from_binary(&modules[0]).len() as u64;
+        cache.evict_to_max_total_size(2 * entry_size);
+
+        assert_eq!(cache.try_load_post_link(keys[1]), None);
+        assert!(cache.try_load_post_link(keys[0]).is_some());
+        assert!(cache.try_load_post_link(keys[2]).is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
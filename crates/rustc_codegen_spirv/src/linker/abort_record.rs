@@ -0,0 +1,218 @@
+//! Lowering of the abort markers left by the `abort-record` abort strategy (see
+//! `spirt_passes::controlflow`) to writing the first abort (of each dispatch) to an
+//! "abort record" buffer (see `--abort-record-buffer`).
+//!
+//! Each marker is assigned a message id (an index into the message table returned by
+//! the pass, which ends up in `CompileResult::abort_record`), and only the invocation
+//! which first increments the counter at the start of the buffer writes its message id
+//! and arguments (see `rustc_codegen_spirv_types::AbortRecordInfo` for the layout, and
+//! how the record is decoded on the host).
+
+use super::shader_log::{ArgKinds, Globals, WordBuffer, remove_unused_strings};
+use super::{Result, get_name, get_names};
+use rspirv::dr::{Instruction, Module, Operand};
+use rspirv::spirv::{Op, Word};
+use rustc_codegen_spirv_types::{
+    AbortMessage, AbortRecordInfo, AbortSrcLoc, DescriptorBinding, ShaderLogFormat,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexSet};
+use rustc_session::Session;
+
+/// Name of the extended instruction set used for the abort markers, which has only
+/// one instruction (`0`), with these operands:
+/// - kind (e.g. `"panic"`), file (or `""` if unknown), line and column
+/// - message format string, entry-point format string (see `AbortMessage`)
+/// - number of message arguments, followed by the message arguments,
+///   and then the entry-point inputs
+//
+// NOTE this intentionally doesn't use `custom_insts::CUSTOM_EXT_INST_SET_PREFIX`,
+// as those are all expected to be gone by the time SPIR-T passes are done.
+pub(super) const ABORT_RECORD_EXT_INST_SET: &str = "Rust.AbortRecord";
+
+/// Lower all the abort markers to writing the abort record buffer at `binding`,
+/// returning the message table for that buffer (or `None` if there are no markers).
+///
+/// Must run after the SPIR-T passes (which introduce the markers), but before
+/// interface variables are gathered.
+pub fn lower_abort_records_to_buffer(
+    sess: &Session,
+    module: &mut Module,
+    binding: Option<DescriptorBinding>,
+) -> Result<Option<AbortRecordInfo>> {
+    let marker_imports: FxHashSet<Word> = module
+        .ext_inst_imports
+        .iter()
+        .filter(|inst| inst.operands[0].unwrap_literal_string() == ABORT_RECORD_EXT_INST_SET)
+        .map(|inst| inst.result_id.unwrap())
+        .collect();
+    if marker_imports.is_empty() {
+        return Ok(None);
+    }
+    let Some(binding) = binding else {
+        return Err(sess
+            .dcx()
+            .err("`--abort-strategy=abort-record` requires `--abort-record-buffer SET,BINDING`"));
+    };
+    let is_marker = |inst: &Instruction| {
+        inst.class.opcode == Op::ExtInst
+            && marker_imports.contains(&inst.operands[0].unwrap_id_ref())
+    };
+
+    let strings: FxHashMap<Word, String> = module
+        .debug_string_source
+        .iter()
+        .filter(|inst| inst.class.opcode == Op::String)
+        .map(|inst| {
+            (
+                inst.result_id.unwrap(),
+                inst.operands[0].unwrap_literal_string().to_string(),
+            )
+        })
+        .collect();
+    let u32_consts: FxHashMap<Word, u32> = module
+        .types_global_values
+        .iter()
+        .filter(|inst| inst.class.opcode == Op::Constant)
+        .filter_map(|inst| match inst.operands[..] {
+            [Operand::LiteralBit32(x)] => Some((inst.result_id?, x)),
+            _ => None,
+        })
+        .collect();
+    let arg_kinds = ArgKinds::new(module);
+
+    // Returns the message of a marker, and its arguments (message arguments,
+    // followed by entry-point inputs), or `None` if it's malformed.
+    let decode_marker = |inst: &Instruction| {
+        let operands: Vec<_> = inst.operands[2..]
+            .iter()
+            .map(|operand| operand.unwrap_id_ref())
+            .collect();
+        let [
+            kind,
+            file,
+            line,
+            column,
+            message,
+            entry_point,
+            message_arg_count,
+            ref args @ ..,
+        ] = operands[..]
+        else {
+            return None;
+        };
+        let message_arg_count = *u32_consts.get(&message_arg_count)? as usize;
+        let format = |format: Word, args: &[Word]| {
+            Some(ShaderLogFormat {
+                format: strings.get(&format)?.clone(),
+                args: args
+                    .iter()
+                    .map(|&arg| Some(arg_kinds.arg_kind(arg)?.0))
+                    .collect::<Option<_>>()?,
+            })
+        };
+        let file = strings.get(&file)?;
+        let location = if file.is_empty() {
+            None
+        } else {
+            Some(AbortSrcLoc {
+                file: file.clone(),
+                line: *u32_consts.get(&line)?,
+                column: *u32_consts.get(&column)?,
+            })
+        };
+        let message = AbortMessage {
+            kind: strings.get(&kind)?.clone(),
+            location,
+            message: format(message, args.get(..message_arg_count)?)?,
+            entry_point: format(entry_point, &args[message_arg_count..])?,
+        };
+        Some((message, args.to_vec()))
+    };
+
+    // Collect all the messages first, to avoid adding anything if any are invalid.
+    let mut messages = FxIndexSet::default();
+    let mut errors = vec![];
+    for func in &module.functions {
+        for inst in func.all_inst_iter().filter(|inst| is_marker(inst)) {
+            match decode_marker(inst) {
+                Some((message, _)) => {
+                    messages.insert(message);
+                }
+                None => errors.push(func.def_id().unwrap()),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        let names = get_names(module);
+        let mut result = Ok(None);
+        for func in errors {
+            let mut err = sess.dcx().struct_err(format!(
+                "unsupported `panic!` (in `{}`) for the abort record buffer",
+                get_name(&names, func)
+            ));
+            err.note(
+                "only 32-bit and 64-bit scalars (or vectors of them) are supported as arguments",
+            );
+            result = Err(err.emit());
+        }
+        return result;
+    }
+
+    let mut globals = Globals::new(module);
+    let buffer = WordBuffer::declare(&mut globals, binding);
+
+    let mut new_functions = std::mem::take(&mut globals.module.functions);
+    for func in &mut new_functions {
+        for block in &mut func.blocks {
+            let mut new_insts = Vec::with_capacity(block.instructions.len());
+            for inst in std::mem::take(&mut block.instructions) {
+                if !is_marker(&inst) {
+                    new_insts.push(inst);
+                    continue;
+                }
+                let (message, args) = decode_marker(&inst).unwrap();
+                let message_id = messages.get_index_of(&message).unwrap() as u32;
+
+                // Convert all the arguments to `u32` words.
+                let mut words = vec![globals.u32_const(message_id)];
+                for arg in args {
+                    buffer.push_arg_words(
+                        &mut globals,
+                        &mut new_insts,
+                        &mut words,
+                        arg,
+                        arg_kinds.arg_kind(arg).unwrap(),
+                    );
+                }
+
+                // Count this abort, and only write the record if it's the first one.
+                let one = globals.u32_const(1);
+                let zero = globals.u32_const(0);
+                let bool_type = globals.get_or_add(Op::TypeBool, None, vec![]);
+                let aborted_before =
+                    buffer.atomic_add_to_counter(&mut globals, &mut new_insts, one);
+                let is_first = globals.emit(
+                    &mut new_insts,
+                    Op::IEqual,
+                    bool_type,
+                    vec![Operand::IdRef(aborted_before), Operand::IdRef(zero)],
+                );
+                buffer.store_words(&mut globals, &mut new_insts, zero, words, Some(is_first));
+            }
+            block.instructions = new_insts;
+        }
+    }
+    globals.module.functions = new_functions;
+    globals.finish();
+
+    // Remove the markers' extended instruction set, and any strings only they used.
+    module
+        .ext_inst_imports
+        .retain(|inst| !marker_imports.contains(&inst.result_id.unwrap()));
+    remove_unused_strings(module);
+
+    Ok(Some(AbortRecordInfo {
+        binding,
+        messages: messages.into_iter().collect(),
+    }))
+}
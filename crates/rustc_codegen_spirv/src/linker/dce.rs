@@ -27,7 +27,8 @@ pub fn collect_roots(module: &Module) -> FxIndexSet<Word> {
 
     // NOTE(eddyb) such "link exports" roots are only relevant when `Options`'s
     // `keep_link_export`s field is used to request that `Export`s are left in
-    // (primarily for unit testing - see also its doc comment).
+    // (primarily for unit testing - see also its doc comment), or when specific
+    // exports are kept via `keep_link_export_names` (for SPIR-V "libraries").
     for inst in &module.annotations {
         if inst.class.opcode == Op::Decorate
             && inst.operands[1].unwrap_decoration() == Decoration::LinkageAttributes
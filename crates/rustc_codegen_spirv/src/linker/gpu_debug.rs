@@ -0,0 +1,619 @@
+//! Lowering of the Rust-style format strings used by `debug_print!` (from `spirv-std`),
+//! to the C-style ones expected by `NonSemantic.DebugPrintf`.
+//!
+//! `debug_print!` emits a `NonSemantic.DebugPrintf` instruction, where each `{...}` from
+//! the original format string is replaced by a `%{spec}` placeholder, and its argument is
+//! a "node" (an instruction from the `Rust.spirv-std.GpuDebug` extended instruction set)
+//! describing the value being printed (see `spirv_std::debug_printf::DebugNode` for the
+//! kinds of nodes). After inlining, all nodes are available in the same function as the
+//! `NonSemantic.DebugPrintf` instruction, so they can be replaced with the scalars/vectors
+//! they contain, and the placeholders with the right specifiers for their types.
+//!
+//! Enums are printed using the names of their variants, which requires branching on
+//! the variant (with one `NonSemantic.DebugPrintf` instruction for each variant), up to
+//! `MAX_ENUM_VARIANT_COMBINATIONS`, past which only variant indices are printed.
+
+use super::{Result, get_name, get_names};
+use rspirv::dr::{Block, Function, Instruction, Module, Operand};
+use rspirv::spirv::{Op, Word};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_session::Session;
+
+/// `OpExtInstImport` "instruction set" name for the nodes built by `GpuDebug`.
+const GPU_DEBUG_EXT_INST_SET: &str = "Rust.spirv-std.GpuDebug";
+
+/// Maximum number of `NonSemantic.DebugPrintf` instructions that printing enums
+/// (by the names of their variants) can be lowered to, for a single `debug_print!`.
+const MAX_ENUM_VARIANT_COMBINATIONS: usize = 64;
+
+mod node {
+    pub const SCALAR: u32 = 1;
+    pub const VECTOR: u32 = 2;
+    pub const STRUCT: u32 = 3;
+    pub const TUPLE_STRUCT: u32 = 4;
+    pub const ARRAY: u32 = 5;
+    pub const FIELD: u32 = 6;
+    pub const NAMED_FIELD: u32 = 7;
+    pub const ENUM: u32 = 8;
+}
+
+/// Parsed `%{spec}` placeholder (see `debug_print_inner` in `spirv-std-macros`).
+#[derive(Copy, Clone)]
+struct Spec {
+    precision: Option<u32>,
+    hex: bool,
+    debug: bool,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Option<Self> {
+        let mut rest = spec;
+        let mut precision = None;
+        if let Some(digits) = rest.strip_prefix('.') {
+            let end = digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len());
+            precision = Some(digits[..end].parse().ok()?);
+            rest = &digits[end..];
+        }
+        let hex = rest.starts_with('x');
+        rest = rest.strip_prefix('x').unwrap_or(rest);
+        let debug = rest == "?";
+        (rest.is_empty() || debug).then_some(Self {
+            precision,
+            hex,
+            debug,
+        })
+    }
+}
+
+enum Piece {
+    Text(String),
+    Arg {
+        specifier: String,
+        value: Word,
+    },
+    /// Name of the current variant of `LoweredFormat::enums[i]`.
+    Enum(usize),
+}
+
+#[derive(Default)]
+struct LoweredFormat {
+    pieces: Vec<Piece>,
+    /// Variant index and variant names, for every enum being printed.
+    enums: Vec<(Word, Vec<String>)>,
+}
+
+impl LoweredFormat {
+    fn text(&mut self, text: &str) {
+        let text = text.replace('%', "%%");
+        if let Some(Piece::Text(prev)) = self.pieces.last_mut() {
+            *prev += &text;
+        } else {
+            self.pieces.push(Piece::Text(text));
+        }
+    }
+
+    /// Format string and arguments, for the given choice of variant for each enum.
+    fn format_and_args(&self, variants: &[usize]) -> (String, Vec<Word>) {
+        let mut format = String::new();
+        let mut args = vec![];
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => format += text,
+                Piece::Arg { specifier, value } => {
+                    format += specifier;
+                    args.push(*value);
+                }
+                Piece::Enum(i) => format += &self.enums[*i].1[variants[*i]].replace('%', "%%"),
+            }
+        }
+        (format, args)
+    }
+
+    /// Clone `printf`, replacing its format string and arguments, for the given choice of
+    /// variant for each enum.
+    fn printf(&self, printf: &Instruction, variants: &[usize], ids: &mut Ids<'_>) -> Instruction {
+        let (format, args) = self.format_and_args(variants);
+        let mut printf = printf.clone();
+        printf.result_id = Some(ids.next());
+        printf.operands.truncate(2);
+        printf.operands.push(Operand::IdRef(ids.string(format)));
+        printf.operands.extend(args.into_iter().map(Operand::IdRef));
+        printf
+    }
+
+    /// Generate blocks for each possible combination of enum variants (past those already
+    /// chosen in `variants`), each with their own `printf`, returning the label of the
+    /// first block (to branch to).
+    fn branch_on_enum_variants(
+        &self,
+        printf: &Instruction,
+        variants: &mut Vec<usize>,
+        merge_label: Word,
+        ids: &mut Ids<'_>,
+        new_blocks: &mut Vec<Block>,
+    ) -> Word {
+        let label = ids.next();
+        let block_idx = new_blocks.len();
+        new_blocks.push(Block {
+            label: Some(Instruction::new(Op::Label, None, Some(label), vec![])),
+            instructions: vec![],
+        });
+        new_blocks[block_idx].instructions = match self.enums.get(variants.len()) {
+            None => vec![
+                self.printf(printf, variants, ids),
+                Instruction::new(Op::Branch, None, None, vec![Operand::IdRef(merge_label)]),
+            ],
+            Some((variant_index, variant_names)) => {
+                let targets: Vec<_> = (0..variant_names.len())
+                    .map(|variant| {
+                        variants.push(variant);
+                        let target = self.branch_on_enum_variants(
+                            printf,
+                            variants,
+                            merge_label,
+                            ids,
+                            new_blocks,
+                        );
+                        variants.pop();
+                        target
+                    })
+                    .collect();
+
+                // NOTE the last variant is used as the default, as the variant index
+                // is always in bounds (being produced by `#[derive(GpuDebug)]`).
+                let (&default, cases) = targets.split_last().unwrap();
+                let mut operands = vec![Operand::IdRef(*variant_index), Operand::IdRef(default)];
+                for (variant, &target) in cases.iter().enumerate() {
+                    operands.push(Operand::LiteralBit32(variant as u32));
+                    operands.push(Operand::IdRef(target));
+                }
+                vec![Instruction::new(Op::Switch, None, None, operands)]
+            }
+        };
+        label
+    }
+}
+
+struct Ids<'a> {
+    bound: &'a mut u32,
+    /// New `OpString`s, for the lowered format strings.
+    strings: FxHashMap<String, Word>,
+}
+
+impl Ids<'_> {
+    fn next(&mut self) -> Word {
+        *self.bound += 1;
+        *self.bound - 1
+    }
+
+    fn string(&mut self, s: String) -> Word {
+        if let Some(&id) = self.strings.get(&s) {
+            return id;
+        }
+        let id = self.next();
+        self.strings.insert(s, id);
+        id
+    }
+}
+
+struct Lowerer<'a> {
+    strings: &'a FxHashMap<Word, String>,
+    type_defs: &'a FxHashMap<Word, &'a Instruction>,
+    value_types: FxHashMap<Word, Word>,
+    nodes: FxHashMap<Word, Instruction>,
+}
+
+impl Lowerer<'_> {
+    fn string(&self, id: Word) -> std::result::Result<&str, String> {
+        self.strings
+            .get(&id)
+            .map(|s| &s[..])
+            .ok_or_else(|| "expected `OpString`".to_string())
+    }
+
+    /// Returns the `NonSemantic.DebugPrintf` specifier for a scalar of type `ty`.
+    fn scalar_specifier(&self, ty: Word, spec: Spec) -> std::result::Result<String, String> {
+        let ty_def = self.type_defs[&ty];
+        let specifier =
+            match (ty_def.class.opcode, &ty_def.operands[..]) {
+                (Op::TypeInt, &[Operand::LiteralBit32(32), Operand::LiteralBit32(signedness)]) => {
+                    match (spec.hex, signedness) {
+                        (true, _) => "x",
+                        (false, 0) => "u",
+                        (false, _) => "i",
+                    }
+                }
+                (Op::TypeInt, &[Operand::LiteralBit32(64), Operand::LiteralBit32(0)]) => {
+                    if spec.hex { "lx" } else { "lu" }
+                }
+                (Op::TypeFloat, &[Operand::LiteralBit32(32), ..]) => {
+                    if spec.hex {
+                        return Err("floats cannot be printed in hexadecimal".into());
+                    }
+                    "f"
+                }
+                _ => return Err("only 32-bit scalars and `u64` can be printed".into()),
+            };
+        Ok(specifier.to_string())
+    }
+
+    fn lower_node(
+        &self,
+        lowered: &mut LoweredFormat,
+        node_id: Word,
+        spec: Spec,
+        top_level: bool,
+    ) -> std::result::Result<(), String> {
+        let node = self.nodes.get(&node_id).ok_or_else(|| {
+            "argument isn't known at compile time (`GpuDebug` values must not be \
+             e.g. stored in memory, or selected at runtime, before printing)"
+                .to_string()
+        })?;
+        let operands = &node.operands[2..];
+        let precision = spec.precision.map_or(String::new(), |p| format!(".{p}"));
+        match node.operands[1].unwrap_literal_ext_inst_integer() {
+            node::SCALAR => {
+                let value = operands[0].unwrap_id_ref();
+                let specifier = self.scalar_specifier(self.value_types[&value], spec)?;
+                let precision = if specifier == "f" { &precision[..] } else { "" };
+                lowered.pieces.push(Piece::Arg {
+                    specifier: format!("%{precision}{specifier}"),
+                    value,
+                });
+            }
+            node::VECTOR => {
+                let name = self.string(operands[0].unwrap_id_ref())?;
+                let value = operands[1].unwrap_id_ref();
+                let ty_def = self.type_defs[&self.value_types[&value]];
+                assert_eq!(ty_def.class.opcode, Op::TypeVector);
+                let elem_ty = ty_def.operands[0].unwrap_id_ref();
+                let count = ty_def.operands[1].unwrap_literal_bit32();
+                let specifier = self.scalar_specifier(elem_ty, spec)?;
+                let precision = if specifier == "f" { &precision[..] } else { "" };
+
+                let (open, close) = if spec.debug || !top_level {
+                    (format!("{name}("), ")")
+                } else {
+                    ("[".to_string(), "]")
+                };
+                lowered.text(&open);
+                lowered.pieces.push(Piece::Arg {
+                    specifier: format!("%{precision}v{count}{specifier}"),
+                    value,
+                });
+                lowered.text(close);
+            }
+            node::ENUM => {
+                let variant_index = operands[0].unwrap_id_ref();
+
+                // Avoid generating too many blocks (e.g. for arrays of enums), by only
+                // printing the variant index, past a certain number of combinations.
+                let combinations = lowered
+                    .enums
+                    .iter()
+                    .map(|(_, variant_names)| variant_names.len())
+                    .product::<usize>();
+                if combinations * (operands.len() - 1) > MAX_ENUM_VARIANT_COMBINATIONS {
+                    lowered.pieces.push(Piece::Arg {
+                        specifier: "%u".into(),
+                        value: variant_index,
+                    });
+                    return Ok(());
+                }
+
+                let variant_names = operands[1..]
+                    .iter()
+                    .map(|name| Ok(self.string(name.unwrap_id_ref())?.to_string()))
+                    .collect::<std::result::Result<_, String>>()?;
+                lowered.enums.push((variant_index, variant_names));
+                lowered.pieces.push(Piece::Enum(lowered.enums.len() - 1));
+            }
+            node::STRUCT | node::TUPLE_STRUCT | node::ARRAY | node::FIELD | node::NAMED_FIELD => {
+                // Walk the chain of fields back to its start.
+                let mut fields = vec![];
+                let mut start = node;
+                loop {
+                    let operands = &start.operands[2..];
+                    let prev = match start.operands[1].unwrap_literal_ext_inst_integer() {
+                        node::FIELD => {
+                            fields.push((None, operands[1].unwrap_id_ref()));
+                            operands[0].unwrap_id_ref()
+                        }
+                        node::NAMED_FIELD => {
+                            let name = self.string(operands[1].unwrap_id_ref())?;
+                            fields.push((Some(name), operands[2].unwrap_id_ref()));
+                            operands[0].unwrap_id_ref()
+                        }
+                        _ => break,
+                    };
+                    start = &self.nodes[&prev];
+                }
+                fields.reverse();
+
+                let kind = start.operands[1].unwrap_literal_ext_inst_integer();
+                let (open, close) = match kind {
+                    node::STRUCT => {
+                        let name = self.string(start.operands[2].unwrap_id_ref())?;
+                        if fields.is_empty() {
+                            lowered.text(name);
+                            return Ok(());
+                        }
+                        (format!("{name} {{ "), " }")
+                    }
+                    node::TUPLE_STRUCT => {
+                        let name = self.string(start.operands[2].unwrap_id_ref())?;
+                        (format!("{name}("), ")")
+                    }
+                    node::ARRAY => ("[".to_string(), "]"),
+                    _ => unreachable!(),
+                };
+                lowered.text(&open);
+                for (i, (name, field)) in fields.into_iter().enumerate() {
+                    if i > 0 {
+                        lowered.text(", ");
+                    }
+                    if let Some(name) = name {
+                        lowered.text(&format!("{name}: "));
+                    }
+                    self.lower_node(lowered, field, spec, false)?;
+                }
+                lowered.text(close);
+            }
+            other => return Err(format!("unknown `GpuDebug` node kind {other}")),
+        }
+        Ok(())
+    }
+
+    /// Parses the placeholders in `format`, and lowers their corresponding `args`.
+    fn lower_format(
+        &self,
+        format: &str,
+        args: &[Word],
+    ) -> std::result::Result<LoweredFormat, String> {
+        let mut lowered = LoweredFormat::default();
+        let mut args = args.iter();
+        let mut chars = format.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                lowered.text(&ch.to_string());
+                continue;
+            }
+            match chars.next() {
+                Some('{') => {
+                    let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let spec = Spec::parse(&spec)
+                        .ok_or_else(|| format!("invalid format spec `{spec}`"))?;
+                    let &arg = args
+                        .next()
+                        .ok_or_else(|| "missing argument for format placeholder".to_string())?;
+                    self.lower_node(&mut lowered, arg, spec, true)?;
+                }
+                // NOTE unescaping `%%` only for it to be escaped again.
+                Some('%') => lowered.text("%"),
+                _ => return Err("C-style specifiers cannot be mixed with placeholders".into()),
+            }
+        }
+        if args.next().is_some() {
+            return Err("too many arguments for format string".into());
+        }
+        Ok(lowered)
+    }
+}
+
+/// Lower all the `NonSemantic.DebugPrintf` instructions emitted by `debug_print!`, and
+/// remove all `GpuDebug` nodes (see also the module-level documentation).
+///
+/// Must run after inlining (and `mem2reg`), so that the nodes are in the same function as
+/// the `NonSemantic.DebugPrintf` instructions printing them.
+pub fn lower_gpu_debug_printf(sess: &Session, module: &mut Module) -> Result<()> {
+    let find_imports = |name: &str| -> FxHashSet<Word> {
+        module
+            .ext_inst_imports
+            .iter()
+            .filter(|inst| inst.operands[0].unwrap_literal_string() == name)
+            .map(|inst| inst.result_id.unwrap())
+            .collect()
+    };
+    let gpu_debug_imports = find_imports(GPU_DEBUG_EXT_INST_SET);
+    if gpu_debug_imports.is_empty() {
+        return Ok(());
+    }
+    let debug_printf_imports = find_imports("NonSemantic.DebugPrintf");
+
+    let strings: FxHashMap<Word, String> = module
+        .debug_string_source
+        .iter()
+        .filter(|inst| inst.class.opcode == Op::String)
+        .map(|inst| {
+            (
+                inst.result_id.unwrap(),
+                inst.operands[0].unwrap_literal_string().to_string(),
+            )
+        })
+        .collect();
+    let type_defs: FxHashMap<Word, &Instruction> = module
+        .types_global_values
+        .iter()
+        .filter_map(|inst| Some((inst.result_id?, inst)))
+        .collect();
+    let global_value_types: FxHashMap<Word, Word> = module
+        .types_global_values
+        .iter()
+        .filter_map(|inst| Some((inst.result_id?, inst.result_type?)))
+        .collect();
+
+    let is_gpu_debug_node = |inst: &Instruction| {
+        inst.class.opcode == Op::ExtInst
+            && gpu_debug_imports.contains(&inst.operands[0].unwrap_id_ref())
+    };
+    let is_debug_printf = |inst: &Instruction| {
+        inst.class.opcode == Op::ExtInst
+            && debug_printf_imports.contains(&inst.operands[0].unwrap_id_ref())
+            && inst.operands[1].unwrap_literal_ext_inst_integer() == 1
+    };
+
+    let mut ids = Ids {
+        bound: &mut module.header.as_mut().unwrap().bound,
+        strings: FxHashMap::default(),
+    };
+    let mut errors = vec![];
+    let mut node_ids = FxHashSet::default();
+    for func in &mut module.functions {
+        let all_insts = || {
+            func.parameters
+                .iter()
+                .chain(func.blocks.iter().flat_map(|b| &b.instructions))
+        };
+        let nodes: FxHashMap<_, _> = all_insts()
+            .filter(|inst| is_gpu_debug_node(inst))
+            .map(|inst| (inst.result_id.unwrap(), inst.clone()))
+            .collect();
+        if nodes.is_empty() {
+            continue;
+        }
+        node_ids.extend(nodes.keys().copied());
+        let lowerer = Lowerer {
+            strings: &strings,
+            type_defs: &type_defs,
+            value_types: global_value_types
+                .iter()
+                .map(|(&id, &ty)| (id, ty))
+                .chain(all_insts().filter_map(|inst| Some((inst.result_id?, inst.result_type?))))
+                .collect(),
+            nodes,
+        };
+
+        let mut block_idx = 0;
+        while block_idx < func.blocks.len() {
+            let mut inst_idx = 0;
+            while inst_idx < func.blocks[block_idx].instructions.len() {
+                let inst = &func.blocks[block_idx].instructions[inst_idx];
+                let format = is_debug_printf(inst)
+                    .then(|| strings.get(&inst.operands[2].unwrap_id_ref()))
+                    .flatten()
+                    .filter(|format| format.contains("%{"));
+                let Some(format) = format else {
+                    inst_idx += 1;
+                    continue;
+                };
+                let args: Vec<_> = inst.operands[3..]
+                    .iter()
+                    .map(|arg| arg.unwrap_id_ref())
+                    .collect();
+                let lowered = match lowerer.lower_format(format, &args) {
+                    Ok(lowered) => lowered,
+                    Err(err) => {
+                        errors.push((func.def_id().unwrap(), format.clone(), err));
+                        inst_idx += 1;
+                        continue;
+                    }
+                };
+                let printf = inst.clone();
+
+                if lowered.enums.is_empty() {
+                    func.blocks[block_idx].instructions[inst_idx] =
+                        lowered.printf(&printf, &[], &mut ids);
+                    inst_idx += 1;
+                    continue;
+                }
+
+                // Branch on the variant of each enum, and move the rest of the block
+                // (after the `NonSemantic.DebugPrintf` instruction) to a new block.
+                let merge_label = ids.next();
+                let mut new_blocks = vec![];
+                let root = lowered.branch_on_enum_variants(
+                    &printf,
+                    &mut vec![],
+                    merge_label,
+                    &mut ids,
+                    &mut new_blocks,
+                );
+                let block = &mut func.blocks[block_idx];
+                let original_label = block.label_id().unwrap();
+                let rest = block.instructions.split_off(inst_idx + 1);
+                block.instructions.pop();
+                block.instructions.push(Instruction::new(
+                    Op::Branch,
+                    None,
+                    None,
+                    vec![Operand::IdRef(root)],
+                ));
+                new_blocks.push(Block {
+                    label: Some(Instruction::new(Op::Label, None, Some(merge_label), vec![])),
+                    instructions: rest,
+                });
+                replace_phi_parent(func, original_label, merge_label);
+                func.blocks.splice(block_idx + 1..block_idx + 1, new_blocks);
+                break;
+            }
+            block_idx += 1;
+        }
+    }
+    let new_strings = ids.strings;
+
+    // Remove all the nodes (and their instruction set), which must now be unused.
+    for func in &mut module.functions {
+        for block in &mut func.blocks {
+            block.instructions.retain(|inst| !is_gpu_debug_node(inst));
+        }
+    }
+    module
+        .ext_inst_imports
+        .retain(|inst| !gpu_debug_imports.contains(&inst.result_id.unwrap()));
+    let mut new_strings: Vec<_> = new_strings.into_iter().collect();
+    new_strings.sort_by_key(|&(_, id)| id);
+    module
+        .debug_string_source
+        .extend(new_strings.into_iter().map(|(s, id)| {
+            Instruction::new(Op::String, None, Some(id), vec![Operand::LiteralString(s)])
+        }));
+
+    let mut has_leftover_node_uses = false;
+    for inst in module.all_inst_iter() {
+        if inst.operands.iter().any(|operand| {
+            operand
+                .id_ref_any()
+                .is_some_and(|id| node_ids.contains(&id))
+        }) {
+            has_leftover_node_uses = true;
+        }
+    }
+
+    let mut result = Ok(());
+    if !errors.is_empty() || has_leftover_node_uses {
+        let names = get_names(module);
+        for (func, format, err) in errors {
+            result = Err(sess
+                .dcx()
+                .struct_err(format!(
+                    "failed to lower `debug_print!` format string in `{}`: {err}",
+                    get_name(&names, func)
+                ))
+                .with_note(format!("format string (after expansion): {format:?}"))
+                .emit());
+        }
+        if has_leftover_node_uses {
+            result = Err(sess
+                .dcx()
+                .err("`GpuDebug` nodes can only be used as `debug_print!` arguments".to_string()));
+        }
+    }
+    result
+}
+
+fn replace_phi_parent(func: &mut Function, from: Word, to: Word) {
+    for block in &mut func.blocks {
+        for inst in &mut block.instructions {
+            if inst.class.opcode != Op::Phi {
+                continue;
+            }
+            for parent in inst.operands.iter_mut().skip(1).step_by(2) {
+                if *parent == Operand::IdRef(from) {
+                    *parent = Operand::IdRef(to);
+                }
+            }
+        }
+    }
+}
@@ -234,13 +234,20 @@ fn kill_linkage_instructions(
         .retain(|v| v.result_id.is_none_or(|v| !rewrite_rules.contains_key(&v)));
 
     // NOTE(eddyb) `Options`'s `keep_link_export`s field requests that `Export`s
-    // are left in (primarily for unit testing - see also its doc comment).
+    // are left in (primarily for unit testing - see also its doc comment),
+    // while `keep_link_export_names` does the same, but only for some names.
     let mut kept_any_linkage_decorations = false;
     module.annotations.retain(|inst| {
         !(inst.class.opcode == Op::Decorate
             && inst.operands[1].unwrap_decoration() == Decoration::LinkageAttributes
             && match inst.operands[3].unwrap_linkage_type() {
-                LinkageType::Export if opts.keep_link_exports => {
+                LinkageType::Export
+                    if opts.keep_link_exports
+                        || opts
+                            .keep_link_export_names
+                            .iter()
+                            .any(|name| name == inst.operands[2].unwrap_literal_string()) =>
+                {
                     kept_any_linkage_decorations = true;
                     false
                 }
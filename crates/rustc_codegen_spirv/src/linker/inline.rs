@@ -3,13 +3,22 @@
 //! `StorageClass::Input` pointer. Our frontend definitely allows it, though, this is like taking a
 //! `&Input<T>` in a function! So, we inline all functions that take these "illegal" pointers, then
 //! run mem2reg (see mem2reg.rs) on the result to "unwrap" the Function pointer.
+//!
+//! Pointers into module-scoped `OpVariable`s (e.g. buffers) don't always require
+//! inlining, as `param_weakening::replace_global_ptr_params_with_indices` can
+//! replace such parameters with indices, before this pass runs.
+//!
+//! Beyond legalization, which calls get inlined depends on the `InlineMode`.
 
 use super::apply_rewrite_rules;
 use super::ipo::CallGraph;
 use super::simple_passes::outgoing_edges;
+use super::stats::LinkStatsCollector;
 use super::{get_name, get_names};
+use crate::codegen_cx::InlineMode;
 use crate::custom_decorations::SpanRegenerator;
 use crate::custom_insts::{self, CustomInst, CustomOp};
+use crate::link_cache::DiagRecorder;
 use rspirv::dr::{Block, Function, Instruction, Module, ModuleHeader, Operand};
 use rspirv::spirv::{FunctionControl, Op, StorageClass, Word};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap, FxIndexSet};
@@ -26,7 +35,13 @@ fn next_id(header: &mut ModuleHeader) -> Word {
     result
 }
 
-pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
+pub fn inline(
+    sess: &Session,
+    module: &mut Module,
+    inline_mode: InlineMode,
+    link_stats: Option<&LinkStatsCollector>,
+    warnings: &DiagRecorder,
+) -> super::Result<()> {
     // This algorithm gets real sad if there's recursion - but, good news, SPIR-V bans recursion
     deny_recursion_in_module(sess, module)?;
 
@@ -44,6 +59,17 @@ pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
 
     let legal_globals = LegalGlobal::gather_from_module(module);
 
+    let mut call_counts = FxHashMap::default();
+    if inline_mode == InlineMode::Size {
+        for inst in module.all_inst_iter() {
+            if inst.class.opcode == Op::FunctionCall {
+                *call_counts
+                    .entry(inst.operands[0].unwrap_id_ref())
+                    .or_default() += 1;
+            }
+        }
+    }
+
     let header = module.header.as_mut().unwrap();
 
     // FIXME(eddyb) clippy false positive (separate `map` required for borrowck).
@@ -77,6 +103,9 @@ pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
 
         func_id_to_idx,
 
+        inline_mode,
+        call_counts,
+
         id_to_name: module
             .debug_names
             .iter()
@@ -126,6 +155,7 @@ pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
             .collect(),
 
         inlined_dont_inlines_to_cause_and_callers: FxIndexMap::default(),
+        inlined_callee_to_cause_counts: FxIndexMap::default(),
     };
 
     let mut functions: Vec<_> = mem::take(&mut module.functions)
@@ -147,9 +177,14 @@ pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
     let Inliner {
         id_to_name,
         inlined_dont_inlines_to_cause_and_callers,
+        inlined_callee_to_cause_counts,
         ..
     } = inliner;
 
+    if let Some(link_stats) = link_stats {
+        link_stats.record_inlining(module, &id_to_name, &inlined_callee_to_cause_counts);
+    }
+
     let mut span_regen = SpanRegenerator::new(sess.source_map(), module);
     for (callee_id, (cause, callers)) in inlined_dont_inlines_to_cause_and_callers {
         let callee_name = get_name(&id_to_name, callee_id);
@@ -164,7 +199,8 @@ pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
             .src_loc_for_id(callee_id)
             .and_then(|src_loc| span_regen.src_loc_to_rustc(src_loc))
             .unwrap_or_default();
-        sess.dcx()
+        let warning = sess
+            .dcx()
             .struct_span_warn(
                 callee_span,
                 format!("`#[inline(never)]` function `{callee_name}` has been inlined"),
@@ -187,8 +223,9 @@ pub fn inline(sess: &Session, module: &mut Module) -> super::Result<()> {
                     })
                     .collect::<SmallVec<[_; 5]>>()
                     .join(", ")
-            ))
-            .emit();
+            ));
+        warnings.record(sess, &warning);
+        warning.emit();
     }
 
     Ok(())
@@ -298,7 +335,12 @@ impl LegalGlobal {
             let global = match inst.class.opcode {
                 Op::TypePointer => Self::TypePointer(inst.operands[0].unwrap_storage_class()),
                 Op::Variable => Self::Variable,
-                op if rspirv::grammar::reflect::is_type(op) => Self::TypeNonPointer,
+                // NOTE `rspirv` doesn't consider `OpTypeCooperativeMatrixKHR` a type.
+                op if rspirv::grammar::reflect::is_type(op)
+                    || op == Op::TypeCooperativeMatrixKHR =>
+                {
+                    Self::TypeNonPointer
+                }
                 op if rspirv::grammar::reflect::is_constant(op) => Self::Const,
 
                 // FIXME(eddyb) should this be `unreachable!()`?
@@ -313,7 +355,8 @@ impl LegalGlobal {
                 None => matches!(global, Self::TypePointer(_) | Self::TypeNonPointer),
             };
             let legal_operands = inst.operands.iter().all(|operand| match operand {
-                Operand::IdRef(id) => matches!(
+                // NOTE `IdScope` is used by `OpTypeCooperativeMatrixKHR`.
+                Operand::IdRef(id) | Operand::IdScope(id) => matches!(
                     legal_globals.get(id),
                     Some(Self::TypeNonPointer | Self::Const)
                 ),
@@ -330,14 +373,7 @@ impl LegalGlobal {
 
     fn legal_as_fn_param_ty(&self) -> bool {
         match *self {
-            Self::TypePointer(storage_class) => matches!(
-                storage_class,
-                StorageClass::UniformConstant
-                    | StorageClass::Function
-                    | StorageClass::Private
-                    | StorageClass::Workgroup
-                    | StorageClass::AtomicCounter
-            ),
+            Self::TypePointer(storage_class) => legal_as_fn_param_storage_class(storage_class),
             Self::TypeNonPointer => true,
 
             // FIXME(eddyb) should this be an `unreachable!()`?
@@ -357,6 +393,18 @@ impl LegalGlobal {
     }
 }
 
+/// Whether pointers in `storage_class` can be kept as function parameters.
+pub(super) fn legal_as_fn_param_storage_class(storage_class: StorageClass) -> bool {
+    matches!(
+        storage_class,
+        StorageClass::UniformConstant
+            | StorageClass::Function
+            | StorageClass::Private
+            | StorageClass::Workgroup
+            | StorageClass::AtomicCounter
+    )
+}
+
 /// Helper type which encapsulates all the information about one specific call.
 #[derive(Copy, Clone)]
 struct CallSite<'a> {
@@ -364,7 +412,7 @@ struct CallSite<'a> {
     call_inst: &'a Instruction,
 }
 
-fn has_dont_inline(function: &Function) -> bool {
+pub(super) fn has_dont_inline(function: &Function) -> bool {
     let def = function.def.as_ref().unwrap();
     let control = def.operands[0].unwrap_function_control();
     control.contains(FunctionControl::DONT_INLINE)
@@ -375,7 +423,8 @@ fn has_dont_inline(function: &Function) -> bool {
 struct MustInlineToLegalize(&'static str);
 
 /// Returns `Ok(true)`/`Err(MustInlineToLegalize(_))` if `callee` should/must be
-/// inlined (either in general, or specifically from `call_site`, if provided).
+/// inlined (either in general, or specifically from `call_site`, if provided),
+/// where `Ok(_)` only reflects the `#[inline]` hint (see also `InlineMode`).
 ///
 /// The distinction made here is that `Err(MustInlineToLegalize(cause))` is
 /// very much *not* a heuristic, and inlining is *mandatory* due to `cause`
@@ -457,6 +506,58 @@ fn should_inline(
     Ok(callee_control.contains(FunctionControl::INLINE))
 }
 
+/// Decide whether to inline a call to `callee`, when not required for
+/// legalization (with `hint` indicating `#[inline]`), returning the cause.
+fn inline_heuristic(
+    inline_mode: InlineMode,
+    call_counts: &FxHashMap<Word, usize>,
+    custom_ext_inst_set_import: Word,
+    callee: &Function,
+    hint: bool,
+) -> Option<&'static str> {
+    match inline_mode {
+        InlineMode::Hints => hint.then_some("inline hint"),
+        InlineMode::Size => {
+            if has_dont_inline(callee) {
+                return None;
+            }
+            if call_counts.get(&callee.def_id().unwrap()) == Some(&1) {
+                return Some("single call site");
+            }
+            is_small_function(Some(custom_ext_inst_set_import), callee).then_some("small function")
+        }
+    }
+}
+
+/// Whether `callee` is small enough that `InlineMode::Size` always inlines it,
+/// i.e. inlining it shouldn't cost more than keeping the call would.
+pub(super) fn is_small_function(
+    custom_ext_inst_set_import: Option<Word>,
+    callee: &Function,
+) -> bool {
+    // HACK arbitrary limit, picked to be around the cost of a call
+    // (`OpFunctionCall` itself, and the callee's `OpFunction`, `OpLabel`, etc.).
+    const SIZE_MODE_MAX_INLINED_INSTS: usize = 8;
+
+    let inlined_insts = callee
+        .blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+        .filter(|inst| {
+            let is_debuginfo = match inst.class.opcode {
+                Op::Line | Op::NoLine => true,
+                Op::ExtInst => {
+                    Some(inst.operands[0].unwrap_id_ref()) == custom_ext_inst_set_import
+                        && CustomOp::decode_from_ext_inst(inst).is_debuginfo()
+                }
+                _ => false,
+            };
+            !is_debuginfo
+        })
+        .count();
+    inlined_insts <= SIZE_MODE_MAX_INLINED_INSTS
+}
+
 /// Helper error type for `Inliner`'s `functions` field, indicating a `Function`
 /// was taken out of its slot because it's being inlined.
 #[derive(Debug)]
@@ -478,6 +579,11 @@ struct Inliner<'a, 'b> {
     /// Map from each function's ID to its index in `functions`.
     func_id_to_idx: FxHashMap<Word, usize>,
 
+    inline_mode: InlineMode,
+
+    /// Number of calls to each function, before inlining (only for `InlineMode::Size`).
+    call_counts: FxHashMap<Word, usize>,
+
     /// Pre-collected `OpName`s, that can be used to find any function's name
     /// during inlining (to be able to generate debuginfo that uses names).
     id_to_name: FxHashMap<Word, &'a str>,
@@ -496,6 +602,9 @@ struct Inliner<'a, 'b> {
     legal_globals: FxHashMap<Word, LegalGlobal>,
     functions_that_may_abort: FxHashSet<Word>,
     inlined_dont_inlines_to_cause_and_callers: FxIndexMap<Word, (&'static str, FxIndexSet<Word>)>,
+
+    /// Number of inlined calls, for each callee and cause (for `--link-stats`).
+    inlined_callee_to_cause_counts: FxIndexMap<Word, FxIndexMap<&'static str, usize>>,
     // rewrite_rules: FxHashMap<Word, Word>,
 }
 
@@ -559,18 +668,24 @@ impl Inliner<'_, '_> {
                         .unwrap(),
                 )
             })
-            .find(|(_, inst, f)| {
+            .find_map(|(index, inst, f)| {
                 let call_site = CallSite {
                     caller,
                     call_inst: inst,
                 };
-                match should_inline(
+                let cause = match should_inline(
                     &self.legal_globals,
                     &self.functions_that_may_abort,
                     f,
                     call_site,
                 ) {
-                    Ok(inline) => inline,
+                    Ok(hint) => inline_heuristic(
+                        self.inline_mode,
+                        &self.call_counts,
+                        self.custom_ext_inst_set_import,
+                        f,
+                        hint,
+                    )?,
                     Err(MustInlineToLegalize(cause)) => {
                         if has_dont_inline(f) {
                             self.inlined_dont_inlines_to_cause_and_callers
@@ -579,15 +694,23 @@ impl Inliner<'_, '_> {
                                 .1
                                 .insert(caller.def_id().unwrap());
                         }
-                        true
+                        cause
                     }
-                }
+                };
+                Some((index, inst, f, cause))
             });
-        let (call_index, call_inst, callee) = match call {
+        let (call_index, call_inst, callee, cause) = match call {
             None => return false,
             Some(call) => call,
         };
 
+        *self
+            .inlined_callee_to_cause_counts
+            .entry(callee.def_id().unwrap())
+            .or_default()
+            .entry(cause)
+            .or_default() += 1;
+
         // Propagate "may abort" from callee to caller (i.e. as aborts get inlined).
         if self
             .functions_that_may_abort
@@ -816,7 +939,10 @@ impl Inliner<'_, '_> {
                                         current_debug_src_loc_inst = callsite_debug_src_loc_inst;
                                     }
                                 }
-                                CustomOp::Abort => break,
+                                CustomOp::Abort
+                                | CustomOp::QuadAllKHR
+                                | CustomOp::QuadAnyKHR
+                                | CustomOp::ExecutionMode => break,
                             }
                         }
                         Op::Variable => {}
@@ -1064,7 +1190,10 @@ impl Inliner<'_, '_> {
                             inlined_frames_depth = inlined_frames_depth.saturating_sub(1);
                             continue;
                         }
-                        CustomOp::Abort => break,
+                        CustomOp::Abort
+                        | CustomOp::QuadAllKHR
+                        | CustomOp::QuadAnyKHR
+                        | CustomOp::ExecutionMode => break,
                     }
                 }
                 Op::Variable => continue,
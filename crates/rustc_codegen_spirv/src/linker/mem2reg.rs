@@ -14,8 +14,8 @@ use super::{apply_rewrite_rules, id};
 use rspirv::dr::{Block, Function, Instruction, ModuleHeader, Operand};
 use rspirv::spirv::{Op, Word};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap};
-use rustc_middle::bug;
 use std::collections::hash_map;
+use std::iter;
 
 // HACK(eddyb) newtype instead of type alias to avoid mistakes.
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
@@ -327,10 +327,18 @@ fn split_copy_memory(
             if inst.class.opcode == Op::CopyMemory {
                 let target = inst.operands[0].id_ref_any().unwrap();
                 let source = inst.operands[1].id_ref_any().unwrap();
-                if inst.operands.len() > 2 {
-                    // TODO: Copy the memory operands to the load/store
-                    bug!("mem2reg OpCopyMemory doesn't support memory operands yet");
-                }
+                // NOTE a single set of memory operands applies to both sides,
+                // otherwise the first set is for `target`, and the second one
+                // for `source`.
+                let mut target_memory_operands = inst.operands[2..].to_vec();
+                let source_memory_operands = match target_memory_operands
+                    .iter()
+                    .skip(1)
+                    .position(|operand| matches!(operand, Operand::MemoryAccess(_)))
+                {
+                    Some(i) => target_memory_operands.split_off(i + 1),
+                    None => target_memory_operands.clone(),
+                };
                 let ty = match (var_map.get(&target), var_map.get(&source)) {
                     (None, None) => {
                         inst_index += 1;
@@ -348,7 +356,9 @@ fn split_copy_memory(
                     Op::Load,
                     Some(ty),
                     Some(temp_id),
-                    vec![Operand::IdRef(source)],
+                    iter::once(Operand::IdRef(source))
+                        .chain(source_memory_operands)
+                        .collect(),
                 );
                 inst_index += 1;
                 block.instructions.insert(
@@ -357,7 +367,10 @@ fn split_copy_memory(
                         Op::Store,
                         None,
                         None,
-                        vec![Operand::IdRef(target), Operand::IdRef(temp_id)],
+                        [Operand::IdRef(target), Operand::IdRef(temp_id)]
+                            .into_iter()
+                            .chain(target_memory_operands)
+                            .collect(),
                     ),
                 );
             }
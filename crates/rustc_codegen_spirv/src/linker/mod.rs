@@ -1,61 +1,99 @@
 #[cfg(test)]
 mod test;
 
+mod abort_record;
 pub(crate) mod dce;
 mod destructure_composites;
 mod duplicates;
 mod entry_interface;
+mod gpu_debug;
 mod import_export_link;
 mod inline;
 mod ipo;
 mod mem2reg;
 mod param_weakening;
 mod peephole_opts;
-mod simple_passes;
+pub(crate) mod rspirv_unsupported;
+mod shader_log;
+pub(crate) mod simple_passes;
 mod specializer;
 mod spirt_passes;
+pub(crate) mod stats;
 mod zombies;
 
 use std::borrow::Cow;
 
-use crate::codegen_cx::{ModuleOutputType, SpirvMetadata};
+use crate::codegen_cx::{InlineMode, ModuleOutputType, SpirvMetadata};
 use crate::custom_decorations::{CustomDecoration, SrcLocDecoration, ZombieDecoration};
 use crate::custom_insts;
+use crate::link_cache::{CachedDiag, DiagRecorder, LinkCache};
 use either::Either;
 use rspirv::binary::Assemble;
 use rspirv::dr::{Block, Module, ModuleHeader, Operand};
-use rspirv::spirv::{Op, StorageClass, Word};
+use rspirv::spirv::{AddressingModel, Op, StorageClass, Word};
+use rustc_codegen_spirv_types::{AbortRecordInfo, DescriptorBinding, ShaderLogFormat};
 use rustc_data_structures::fx::FxHashMap;
 use rustc_errors::ErrorGuaranteed;
 use rustc_session::Session;
 use rustc_session::config::OutputFilenames;
+use stats::{LinkStatsCollector, PassTimer};
 use std::cell::Cell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
 pub type Result<T> = std::result::Result<T, ErrorGuaranteed>;
 
+/// Where to write the `--zombie-report`.
+pub enum ZombieReportOutput {
+    File(PathBuf),
+    /// Emit the report as a compiler note (useful e.g. for `compiletest`).
+    Note,
+}
+
 #[derive(Default)]
 pub struct Options {
     pub compact_ids: bool,
     pub early_report_zombies: bool,
+    pub zombie_report: Option<ZombieReportOutput>,
     pub infer_storage_classes: bool,
     pub structurize: bool,
     pub preserve_bindings: bool,
     pub spirt_passes: Vec<String>,
 
     pub abort_strategy: Option<String>,
+
+    /// Binding of the buffer that the `abort-record` abort strategy writes
+    /// the first abort (of each dispatch) to.
+    pub abort_record_buffer: Option<DescriptorBinding>,
+
+    /// Lower `NonSemantic.DebugPrintf` instructions (i.e. `debug_printf!`s, and
+    /// `panic!` messages) to appending records to a storage buffer at this binding.
+    pub shader_log_buffer: Option<DescriptorBinding>,
     pub module_output_type: ModuleOutputType,
+    pub inline_mode: InlineMode,
 
     pub spirv_metadata: SpirvMetadata,
 
     /// Whether to preserve `LinkageAttributes "..." Export` decorations,
     /// even after resolving imports to exports.
     ///
-    /// **Note**: currently only used for unit testing, and not exposed elsewhere.
+    /// **Note**: currently only used for unit testing, and not exposed elsewhere
+    /// (see `keep_link_export_names` for the user-facing, per-name, version).
     pub keep_link_exports: bool,
 
+    /// Names of `LinkageAttributes "..." Export` decorations to preserve (like
+    /// `keep_link_exports`, but only for these names), which allows producing
+    /// SPIR-V "libraries", i.e. modules that can be linked into other builds.
+    ///
+    /// As Rust symbols are mangled, this is only useful for `#[no_mangle]` ones.
+    pub keep_link_export_names: Vec<String>,
+
+    /// Additional (precompiled) SPIR-V modules to link in, which can resolve
+    /// any `LinkageAttributes "..." Import` decorations left by e.g. `extern`
+    /// functions (and may come from other Rust-GPU builds, or `glslang`/DXC).
+    pub link_spirv_libs: Vec<PathBuf>,
+
     // NOTE(eddyb) these are debugging options that used to be env vars
     // (for more information see `docs/src/codegen-args.md`).
     pub dump_post_merge: Option<PathBuf>,
@@ -67,6 +105,23 @@ pub struct Options {
     pub spirt_keep_debug_sources_in_dumps: bool,
     pub spirt_keep_unstructured_cfg_in_dumps: bool,
     pub specializer_dump_instances: Option<PathBuf>,
+    pub link_stats: Option<PathBuf>,
+}
+
+pub struct LinkOutput {
+    pub result: LinkResult,
+
+    /// Format table for the shader log buffer (see `Options::shader_log_buffer`),
+    /// shared by all the output modules (as it's produced before splitting).
+    pub shader_log_formats: Vec<ShaderLogFormat>,
+
+    /// Message table for the abort record buffer (see `Options::abort_record_buffer`),
+    /// shared by all the output modules (as it's produced before splitting).
+    pub abort_record: Option<AbortRecordInfo>,
+
+    /// All the warnings emitted while linking, to re-emit if this `LinkOutput`
+    /// gets reused from the `--link-cache` (see `crate::link_cache::DiagRecorder`).
+    pub warnings: Vec<CachedDiag>,
 }
 
 pub enum LinkResult {
@@ -173,7 +228,14 @@ pub fn link(
     opts: &Options,
     outputs: &OutputFilenames,
     disambiguated_crate_name_for_dumps: &OsStr,
-) -> Result<LinkResult> {
+    link_stats: Option<&LinkStatsCollector>,
+    link_cache: Option<&LinkCache>,
+) -> Result<LinkOutput> {
+    let warnings = DiagRecorder::default();
+
+    let start_pass =
+        |name, module: Option<&Module>| PassTimer::start(sess, link_stats, name, module);
+
     // HACK(eddyb) this is defined here to allow SPIR-T pretty-printing to apply
     // to SPIR-V being dumped, outside of e.g. `--dump-spirt-passes`.
     // FIXME(eddyb) this isn't used everywhere, sadly - to find those, search
@@ -189,7 +251,7 @@ pub fn link(
         };
 
         // FIXME(eddyb) should've really been "spirt::Module::lower_from_spv_bytes".
-        let lower_from_spv_timer = sess.timer("spirt::Module::lower_from_spv_file");
+        let lower_from_spv_timer = start_pass("spirt::Module::lower_from_spv_file", None);
         let cx = std::rc::Rc::new(spirt::Context::new());
         crate::custom_insts::register_to_spirt_context(&cx);
         (
@@ -239,7 +301,7 @@ pub fn link(
     };
 
     let mut output = {
-        let _timer = sess.timer("link_merge");
+        let timer = start_pass("link_merge", None);
         // shift all the ids
         let mut bound = inputs[0].header.as_ref().unwrap().bound - 1;
         let version = inputs[0].header.as_ref().unwrap().version();
@@ -247,15 +309,28 @@ pub fn link(
         for module in inputs.iter_mut().skip(1) {
             simple_passes::shift_ids(module, bound);
             bound += module.header.as_ref().unwrap().bound - 1;
+            // NOTE older SPIR-V versions are forward-compatible, which is
+            // relevant for SPIR-V libraries (`link_spirv_libs`) not produced by
+            // Rust-GPU itself, while all Rust-GPU modules share the same version.
             let this_version = module.header.as_ref().unwrap().version();
-            if version != this_version {
+            if this_version > version {
                 return Err(sess.dcx().err(format!(
-                    "cannot link two modules with different SPIR-V versions: v{}.{} and v{}.{}",
-                    version.0, version.1, this_version.0, this_version.1
+                    "cannot link SPIR-V v{}.{} module into SPIR-V v{}.{} output",
+                    this_version.0, this_version.1, version.0, version.1
                 )));
             }
         }
 
+        // NOTE only crates built with the `PhysicalStorageBufferAddresses` capability
+        // use the `PhysicalStorageBuffer64` addressing model (see `BuilderSpirv::new`),
+        // but it has to apply to the whole output (the other crates being compatible).
+        let uses_physical_storage_buffer64 = inputs.iter().any(|module| {
+            module.memory_model.as_ref().is_some_and(|inst| {
+                inst.operands[0].unwrap_addressing_model()
+                    == AddressingModel::PhysicalStorageBuffer64
+            })
+        });
+
         // merge the binaries
         let mut output = crate::link::with_rspirv_loader(|loader| {
             for module in inputs {
@@ -272,10 +347,16 @@ pub fn link(
         })
         .unwrap();
 
+        if uses_physical_storage_buffer64 && let Some(memory_model) = &mut output.memory_model {
+            memory_model.operands[0] =
+                Operand::AddressingModel(AddressingModel::PhysicalStorageBuffer64);
+        }
+
         let mut header = ModuleHeader::new(bound + 1);
         header.set_version(version.0, version.1);
         header.generator = 0x001B_0000;
         output.header = Some(header);
+        timer.finish(Some(&output));
         output
     };
 
@@ -285,45 +366,136 @@ pub fn link(
 
     // remove duplicates (https://github.com/KhronosGroup/SPIRV-Tools/blob/e7866de4b1dc2a7e8672867caeb0bdca49f458d3/source/opt/remove_duplicates_pass.cpp)
     {
-        let _timer = sess.timer("link_remove_duplicates");
+        let timer = start_pass("link_remove_duplicates", Some(&output));
         duplicates::remove_duplicate_extensions(&mut output);
         duplicates::remove_duplicate_capabilities(&mut output);
         duplicates::remove_duplicate_ext_inst_imports(&mut output);
         duplicates::remove_duplicate_types(&mut output);
         // jb-todo: strip identical OpDecoration / OpDecorationGroups
+        timer.finish(Some(&output));
     }
 
     // find import / export pairs
     {
-        let _timer = sess.timer("link_find_pairs");
+        let timer = start_pass("link_find_pairs", Some(&output));
         import_export_link::run(opts, sess, &mut output)?;
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_dce-post-link");
+        let timer = start_pass("link_dce-post-link", Some(&output));
+        dce::dce(&mut output);
+        timer.finish(Some(&output));
+    }
+
+    let entry_names: Vec<String> = output
+        .entry_points
+        .iter()
+        .map(|entry| entry.operands[2].unwrap_literal_string().to_string())
+        .collect();
+    let file_stems = entry_point_file_stems(&entry_names);
+
+    // NOTE with `--module-output=multiple`, the output modules of entry-points
+    // found in the `--link-cache` (by the contents of everything they use, see
+    // `LinkCache::entry_point_key`) are reused, and all the passes below only
+    // see the remaining entry-points (i.e. the ones affected by any changes).
+    // This relies on the output module of an entry-point only depending on the
+    // other entry-points in ways which don't affect its behavior (e.g. unused
+    // capabilities, or inlining heuristics), which isn't the case for the
+    // `shader_log_buffer`/`abort_record_buffer` tables (shared by all modules).
+    let entry_point_cache = link_cache.filter(|_| {
+        opts.module_output_type == ModuleOutputType::Multiple
+            && opts.shader_log_buffer.is_none()
+            && opts.abort_record_buffer.is_none()
+    });
+    let mut entry_point_cache_keys = vec![];
+    let mut cached_entry_point_modules = BTreeMap::new();
+    if let Some(link_cache) = entry_point_cache {
+        let _timer = sess.timer("link_cache_load");
+        entry_point_cache_keys = output
+            .entry_points
+            .iter()
+            .map(|entry| link_cache.entry_point_key(&output, entry))
+            .collect();
+        for (i, &key) in entry_point_cache_keys.iter().enumerate() {
+            if let Some(module) = link_cache.load_entry_point(key) {
+                cached_entry_point_modules.insert(i, module);
+            }
+        }
+    }
+    // Indices (into `entry_names`) of the entry-points left in `output`.
+    let remaining_entry_points: Vec<usize> = (0..entry_names.len())
+        .filter(|i| !cached_entry_point_modules.contains_key(i))
+        .collect();
+    if !cached_entry_point_modules.is_empty() {
+        if let Some(link_stats) = link_stats {
+            for &i in cached_entry_point_modules.keys() {
+                link_stats.record_link_cache_entry_point_hit(&entry_names[i]);
+            }
+        }
+
+        if remaining_entry_points.is_empty() {
+            return Ok(LinkOutput {
+                result: LinkResult::MultipleModules {
+                    file_stem_to_entry_name_and_module: cached_entry_point_modules
+                        .into_iter()
+                        .map(|(i, module)| {
+                            (file_stems[i].clone(), (entry_names[i].clone(), module))
+                        })
+                        .collect(),
+                },
+                shader_log_formats: vec![],
+                abort_record: None,
+                warnings: vec![],
+            });
+        }
+
+        let timer = start_pass("link_remove_cached_entry_points", Some(&output));
+        let mut i = 0;
+        output.entry_points.retain(|_| {
+            let keep = !cached_entry_point_modules.contains_key(&i);
+            i += 1;
+            keep
+        });
         dce::dce(&mut output);
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_fragment_inst_check");
+        let timer = start_pass("link_fragment_inst_check", Some(&output));
         simple_passes::check_fragment_insts(sess, &output)?;
+        timer.finish(Some(&output));
+    }
+
+    {
+        let timer = start_pass("link_invocation_interlock_check", Some(&output));
+        simple_passes::check_invocation_interlock(sess, &output)?;
+        timer.finish(Some(&output));
+    }
+
+    {
+        let timer = start_pass("link_geometry_stream_check", Some(&output));
+        simple_passes::check_geometry_streams(sess, &output)?;
+        timer.finish(Some(&output));
     }
 
     // HACK(eddyb) this has to run before the `report_zombies` pass, so that
     // any zombies that are passed as call arguments, but eventually unused,
     // won't be (incorrectly) considered used.
     {
-        let _timer = sess.timer("link_remove_unused_params");
+        let timer = start_pass("link_remove_unused_params", Some(&output));
         output = param_weakening::remove_unused_params(output);
+        timer.finish(Some(&output));
     }
 
     if opts.early_report_zombies {
-        let _timer = sess.timer("link_report_zombies");
-        zombies::report_zombies(sess, &output)?;
+        let timer = start_pass("link_report_zombies", Some(&output));
+        zombies::report_zombies(sess, opts, &output)?;
+        timer.finish(Some(&output));
     }
 
     if opts.infer_storage_classes {
-        let _timer = sess.timer("specialize_generic_storage_class");
+        let timer = start_pass("specialize_generic_storage_class", Some(&output));
         // HACK(eddyb) `specializer` requires functions' blocks to be in RPO order
         // (i.e. `block_ordering_pass`) - this could be relaxed by using RPO visit
         // inside `specializer`, but this is easier.
@@ -351,16 +523,21 @@ pub fn link(
                 concrete_fallback: Operand::StorageClass(StorageClass::Function),
             },
         );
+        timer.finish(Some(&output));
     }
 
     // NOTE(eddyb) with SPIR-T, we can do `mem2reg` before inlining, too!
     {
         {
-            let _timer = sess.timer("link_dce-before-inlining");
+            let timer = start_pass("link_dce-before-inlining", Some(&output));
             dce::dce(&mut output);
+            timer.finish(Some(&output));
         }
 
-        let _timer = sess.timer("link_block_ordering_pass_and_mem2reg-before-inlining");
+        let timer = start_pass(
+            "link_block_ordering_pass_and_mem2reg-before-inlining",
+            Some(&output),
+        );
         let mut pointer_to_pointee = FxHashMap::default();
         let mut constants = FxHashMap::default();
         let mut u32 = None;
@@ -396,13 +573,17 @@ pub fn link(
             );
             destructure_composites::destructure_composites(func);
         }
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer =
-            sess.timer("link_dce-and-remove_duplicate_debuginfo-after-mem2reg-before-inlining");
+        let timer = start_pass(
+            "link_dce-and-remove_duplicate_debuginfo-after-mem2reg-before-inlining",
+            Some(&output),
+        );
         dce::dce(&mut output);
         duplicates::remove_duplicate_debuginfo(&mut output);
+        timer.finish(Some(&output));
     }
 
     // HACK(eddyb) this has to be after DCE, to not break SPIR-T w/ dead decorations.
@@ -410,9 +591,19 @@ pub fn link(
         dump_spv_and_spirt(&output, dir.join(disambiguated_crate_name_for_dumps));
     }
 
+    // NOTE this has to run after `mem2reg` (and DCE), to see through
+    // the pointers (to e.g. buffers) passed as call arguments, and it's the
+    // only thing allowing functions with such parameters to not be inlined.
     {
-        let _timer = sess.timer("link_inline");
-        inline::inline(sess, &mut output)?;
+        let timer = start_pass("link_replace_global_ptr_params_with_indices", Some(&output));
+        output = param_weakening::replace_global_ptr_params_with_indices(output, opts.inline_mode);
+        timer.finish(Some(&output));
+    }
+
+    {
+        let timer = start_pass("link_inline", Some(&output));
+        inline::inline(sess, &mut output, opts.inline_mode, link_stats, &warnings)?;
+        timer.finish(Some(&output));
     }
 
     // Fold OpLoad from Private/Function variables with constant initializers.
@@ -421,13 +612,15 @@ pub fn link(
     // This is critical for pointer-to-pointer patterns like `&&123` which would
     // otherwise generate invalid SPIR-V in Logical addressing mode.
     {
-        let _timer = sess.timer("link_fold_load_from_constant_variable");
+        let timer = start_pass("link_fold_load_from_constant_variable", Some(&output));
         peephole_opts::fold_load_from_constant_variable(&mut output);
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_dce-after-inlining");
+        let timer = start_pass("link_dce-after-inlining", Some(&output));
         dce::dce(&mut output);
+        timer.finish(Some(&output));
     }
 
     // HACK(eddyb) this has to be after DCE, to not break SPIR-T w/ dead decorations.
@@ -436,7 +629,10 @@ pub fn link(
     }
 
     {
-        let _timer = sess.timer("link_block_ordering_pass_and_mem2reg-after-inlining");
+        let timer = start_pass(
+            "link_block_ordering_pass_and_mem2reg-after-inlining",
+            Some(&output),
+        );
         let mut pointer_to_pointee = FxHashMap::default();
         let mut constants = FxHashMap::default();
         let mut u32 = None;
@@ -472,20 +668,50 @@ pub fn link(
             );
             destructure_composites::destructure_composites(func);
         }
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer =
-            sess.timer("link_dce-and-remove_duplicate_debuginfo-after-mem2reg-after-inlining");
+        let timer = start_pass(
+            "link_dce-and-remove_duplicate_debuginfo-after-mem2reg-after-inlining",
+            Some(&output),
+        );
         dce::dce(&mut output);
         duplicates::remove_duplicate_debuginfo(&mut output);
+        timer.finish(Some(&output));
+    }
+
+    {
+        let timer = start_pass("link_lower_gpu_debug_printf", Some(&output));
+        gpu_debug::lower_gpu_debug_printf(sess, &mut output)?;
+        timer.finish(Some(&output));
+    }
+
+    {
+        let timer = start_pass("link_add_descriptor_indexing_capabilities", Some(&output));
+        simple_passes::add_descriptor_indexing_capabilities(&mut output);
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_remove_non_uniform");
+        let timer = start_pass("link_remove_non_uniform", Some(&output));
         simple_passes::remove_non_uniform_decorations(sess, &mut output)?;
+        timer.finish(Some(&output));
+    }
+
+    {
+        let timer = start_pass(
+            "link_add_geometry_stream_and_xfb_capabilities",
+            Some(&output),
+        );
+        simple_passes::add_geometry_stream_and_xfb_capabilities(&mut output);
+        timer.finish(Some(&output));
     }
 
+    // HACK SPIR-T can't represent the type operand of `OpCooperativeMatrixLengthKHR`,
+    // so it's temporarily replaced with an `OpUndef` (and restored after SPIR-T passes).
+    simple_passes::cooperative_matrix_length_types_to_undefs(&mut output);
+
     // NOTE(eddyb) SPIR-T pipeline is entirely limited to this block.
     {
         let (spv_words, module_or_err, lower_from_spv_timer) =
@@ -525,10 +751,10 @@ pub fn link(
             // FIXME(eddyb) could it make sense to allow these to nest?
             assert_eq!(outer_pass_name, None);
 
-            sess.timer(pass_name)
+            start_pass(pass_name, None)
         };
-        let mut after_pass = |module: Option<&spirt::Module>, timer| {
-            drop(timer);
+        let mut after_pass = |module: Option<&spirt::Module>, timer: PassTimer<'_>| {
+            timer.finish(None);
             let pass_name = dump_guard.in_progress_pass_name.take().unwrap();
             if let Some(module) = module
                 && opts.dump_spirt_passes.is_some()
@@ -587,7 +813,7 @@ pub fn link(
 
         {
             let timer = before_pass("spirt_passes::diagnostics::report_diagnostics");
-            spirt_passes::diagnostics::report_diagnostics(sess, opts, module).map_err(
+            spirt_passes::diagnostics::report_diagnostics(sess, opts, module, &warnings).map_err(
                 |spirt_passes::diagnostics::ReportedDiagnostics {
                      rustc_errors_guarantee,
                      any_errors_were_spirt_bugs,
@@ -614,21 +840,40 @@ pub fn link(
         };
         // FIXME(eddyb) dump both SPIR-T and `spv_words` if there's an error here.
         output = {
-            let _timer = sess.timer("parse-spv_words-from-spirt");
-            crate::link::with_rspirv_loader(|loader| {
+            let timer = start_pass("parse-spv_words-from-spirt", None);
+            let output = crate::link::with_rspirv_loader(|loader| {
                 rspirv::binary::parse_words(&spv_words, loader)
             })
-            .unwrap()
+            .unwrap();
+            timer.finish(Some(&output));
+            output
         };
     }
+    simple_passes::cooperative_matrix_length_undefs_to_types(&mut output);
 
-    // Ensure that no references remain, to our custom "extended instruction set".
+    // Ensure that no references remain, to our custom "extended instruction set",
+    // other than the ones `rspirv_unsupported` lowers in the final SPIR-V binary.
     for inst in &output.ext_inst_imports {
         assert_eq!(inst.class.opcode, Op::ExtInstImport);
         let ext_inst_set = inst.operands[0].unwrap_literal_string();
         if ext_inst_set.starts_with(custom_insts::CUSTOM_EXT_INST_SET_PREFIX) {
             let expected = &custom_insts::CUSTOM_EXT_INST_SET[..];
             if ext_inst_set == expected {
+                let import_id = inst.result_id.unwrap();
+                let only_rspirv_unsupported = output
+                    .functions
+                    .iter()
+                    .flat_map(|func| func.all_inst_iter())
+                    .filter(|inst| {
+                        inst.class.opcode == Op::ExtInst
+                            && inst.operands[0].unwrap_id_ref() == import_id
+                    })
+                    .all(|inst| {
+                        custom_insts::CustomOp::decode_from_ext_inst(inst).is_rspirv_unsupported()
+                    });
+                if only_rspirv_unsupported {
+                    continue;
+                }
                 return Err(sess.dcx().err(format!(
                     "`OpExtInstImport {ext_inst_set:?}` should not have been \
                          left around after SPIR-T passes"
@@ -647,79 +892,74 @@ pub fn link(
     // multi-module, it's much simpler with SPIR-T, just replace `module.exports`
     // with a single-entry map, run `spirt::spv::lift` (or even `spirt::print`)
     // on `module`, then put back the full original `module.exports` map.
+    let shader_log_formats = if let Some(binding) = opts.shader_log_buffer {
+        let timer = start_pass("link_lower_debug_printf_to_shader_log", Some(&output));
+        let formats = shader_log::lower_debug_printf_to_shader_log(sess, &mut output, binding)?;
+        timer.finish(Some(&output));
+        formats
+    } else {
+        vec![]
+    };
+
+    let abort_record = {
+        let timer = start_pass("link_lower_abort_records_to_buffer", Some(&output));
+        let abort_record = abort_record::lower_abort_records_to_buffer(
+            sess,
+            &mut output,
+            opts.abort_record_buffer,
+        )?;
+        timer.finish(Some(&output));
+        abort_record
+    };
+
     {
-        let _timer = sess.timer("peephole_opts");
+        let timer = start_pass("peephole_opts", Some(&output));
         let types = peephole_opts::collect_types(&output);
         for func in &mut output.functions {
             peephole_opts::composite_construct(&types, func);
             peephole_opts::vector_ops(output.header.as_mut().unwrap(), &types, func);
             peephole_opts::bool_fusion(output.header.as_mut().unwrap(), &types, func);
         }
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_remove_unused_type_capabilities");
+        let timer = start_pass("link_remove_unused_type_capabilities", Some(&output));
         simple_passes::remove_unused_type_capabilities(&mut output);
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_gather_all_interface_vars_from_uses");
+        let timer = start_pass("link_gather_all_interface_vars_from_uses", Some(&output));
         entry_interface::gather_all_interface_vars_from_uses(&mut output, opts.preserve_bindings);
+        timer.finish(Some(&output));
     }
 
     if opts.spirv_metadata == SpirvMetadata::NameVariables {
-        let _timer = sess.timer("link_name_variables");
+        let timer = start_pass("link_name_variables", Some(&output));
         simple_passes::name_variables_pass(&mut output);
+        timer.finish(Some(&output));
     }
 
     {
-        let _timer = sess.timer("link_sort_globals");
+        let timer = start_pass("link_sort_globals", Some(&output));
         simple_passes::sort_globals(&mut output);
+        timer.finish(Some(&output));
     }
 
     let mut output = if opts.module_output_type == ModuleOutputType::Multiple {
-        let mut file_stem_to_entry_name_and_module = BTreeMap::new();
-        for (i, entry) in output.entry_points.iter().enumerate() {
-            let mut module = output.clone();
-            module.entry_points.clear();
-            module.entry_points.push(entry.clone());
-            let entry_name = entry.operands[2].unwrap_literal_string().to_string();
-            let mut file_stem = OsString::from(
-                sanitize_filename::sanitize_with_options(
-                    &entry_name,
-                    sanitize_filename::Options {
-                        replacement: "-",
-                        ..Default::default()
-                    },
-                )
-                .replace("--", "-"),
-            );
-            // It's always possible to find an unambiguous `file_stem`, but it
-            // may take two tries (or more, in bizzare/adversarial cases).
-            let mut disambiguator = Some(i);
-            loop {
-                use std::collections::btree_map::Entry;
-                match file_stem_to_entry_name_and_module.entry(file_stem) {
-                    Entry::Vacant(entry) => {
-                        entry.insert((entry_name, module));
-                        break;
-                    }
-                    // FIXME(eddyb) false positive: `file_stem` was moved out of,
-                    // so assigning it is necessary, but clippy doesn't know that.
-                    #[allow(clippy::assigning_clones)]
-                    Entry::Occupied(entry) => {
-                        // FIXME(eddyb) there's no way to access the owned key
-                        // passed to `BTreeMap::entry` from `OccupiedEntry`.
-                        file_stem = entry.key().clone();
-                        file_stem.push(".");
-                        match disambiguator.take() {
-                            Some(d) => file_stem.push(d.to_string()),
-                            None => file_stem.push("next"),
-                        }
-                    }
-                }
-            }
-        }
+        assert_eq!(output.entry_points.len(), remaining_entry_points.len());
+        let file_stem_to_entry_name_and_module = output
+            .entry_points
+            .iter()
+            .zip(&remaining_entry_points)
+            .map(|(entry, &i)| {
+                let mut module = output.clone();
+                module.entry_points.clear();
+                module.entry_points.push(entry.clone());
+                (file_stems[i].clone(), (entry_names[i].clone(), module))
+            })
+            .collect();
         LinkResult::MultipleModules {
             file_stem_to_entry_name_and_module,
         }
@@ -738,12 +978,17 @@ pub fn link(
         ),
     };
     for (file_stem, output) in output_module_iter {
+        let start_pass = |name, module: &Module| {
+            start_pass(name, Some(module)).for_output_module(file_stem.map(|s| s.as_os_str()))
+        };
+
         // Run DCE again, even if module_output_type == ModuleOutputType::Multiple - the first DCE ran before
         // structurization and mem2reg (for perf reasons), and mem2reg may remove references to
         // invalid types, so we need to DCE again.
         {
-            let _timer = sess.timer("link_dce-post-split");
+            let timer = start_pass("link_dce-post-split", output);
             dce::dce(output);
+            timer.finish(Some(output));
         }
 
         // HACK(eddyb) this has to be after DCE, to not break SPIR-T w/ dead decorations.
@@ -758,14 +1003,16 @@ pub fn link(
         }
 
         {
-            let _timer = sess.timer("link_remove_duplicate_debuginfo");
+            let timer = start_pass("link_remove_duplicate_debuginfo", output);
             duplicates::remove_duplicate_debuginfo(output);
+            timer.finish(Some(output));
         }
 
         if opts.compact_ids {
-            let _timer = sess.timer("link_compact_ids");
+            let timer = start_pass("link_compact_ids", output);
             // compact the ids https://github.com/KhronosGroup/SPIRV-Tools/blob/e02f178a716b0c3c803ce31b9df4088596537872/source/opt/compact_ids_pass.cpp#L43
             output.header.as_mut().unwrap().bound = simple_passes::compact_ids(output);
+            timer.finish(Some(output));
         };
 
         // FIXME(eddyb) convert these into actual `OpLine`s with a SPIR-T pass,
@@ -777,7 +1024,68 @@ pub fn link(
         ZombieDecoration::remove_all(output);
     }
 
-    Ok(output)
+    if let (
+        Some(link_cache),
+        LinkResult::MultipleModules {
+            file_stem_to_entry_name_and_module,
+        },
+    ) = (entry_point_cache, &mut output)
+    {
+        // NOTE output modules are only cached when linking didn't emit any
+        // warnings, as those can't be attributed to individual entry-points.
+        if warnings.is_empty() && sess.dcx().has_errors().is_none() {
+            let _timer = sess.timer("link_cache_store");
+            for &i in &remaining_entry_points {
+                let (_, module) = &file_stem_to_entry_name_and_module[&file_stems[i]];
+                link_cache.store_entry_point(sess, entry_point_cache_keys[i], module);
+            }
+        }
+        for (i, module) in cached_entry_point_modules {
+            file_stem_to_entry_name_and_module
+                .insert(file_stems[i].clone(), (entry_names[i].clone(), module));
+        }
+    }
+
+    Ok(LinkOutput {
+        result: output,
+        shader_log_formats,
+        abort_record,
+        warnings: warnings.into_diags(),
+    })
+}
+
+/// Compute the (unique) "file stem" of the output module for each entry-point
+/// (for `ModuleOutputType::Multiple`, see also `LinkResult::MultipleModules`).
+fn entry_point_file_stems(entry_names: &[String]) -> Vec<OsString> {
+    let mut used_file_stems = BTreeSet::new();
+    entry_names
+        .iter()
+        .enumerate()
+        .map(|(i, entry_name)| {
+            let mut file_stem = OsString::from(
+                sanitize_filename::sanitize_with_options(
+                    entry_name,
+                    sanitize_filename::Options {
+                        replacement: "-",
+                        ..Default::default()
+                    },
+                )
+                .replace("--", "-"),
+            );
+            // It's always possible to find an unambiguous `file_stem`, but it
+            // may take two tries (or more, in bizzare/adversarial cases).
+            let mut disambiguator = Some(i);
+            while used_file_stems.contains(&file_stem) {
+                file_stem.push(".");
+                match disambiguator.take() {
+                    Some(d) => file_stem.push(d.to_string()),
+                    None => file_stem.push("next"),
+                }
+            }
+            used_file_stems.insert(file_stem.clone());
+            file_stem
+        })
+        .collect()
 }
 
 /// Helper for dumping SPIR-T on drop, which allows panics to also dump,
@@ -3,12 +3,16 @@
 //! based on how those parameters are used in the function and/or what arguments
 //! get passed from callers.
 //!
+use crate::codegen_cx::InlineMode;
+use crate::custom_insts;
+use crate::linker::inline::{is_small_function, legal_as_fn_param_storage_class};
 use crate::linker::ipo::CallGraph;
 use indexmap::IndexMap;
-use rspirv::dr::{Builder, Module, Operand};
-use rspirv::spirv::{Op, Word};
-use rustc_data_structures::fx::FxHashMap;
+use rspirv::dr::{Builder, Instruction, Module, Operand};
+use rspirv::spirv::{Decoration, FunctionControl, Op, Word};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap};
 use rustc_index::bit_set::DenseBitSet as BitSet;
+use smallvec::SmallVec;
 use std::mem;
 
 pub fn remove_unused_params(module: Module) -> Module {
@@ -111,3 +115,379 @@ pub fn remove_unused_params(module: Module) -> Module {
 
     builder.module()
 }
+
+/// Pointer parameter replaced by `replace_global_ptr_params_with_indices`.
+struct ReplacedPtrParam {
+    param_idx: usize,
+    global_var: Word,
+
+    /// `OpAccessChain` indices into `global_var`, each being either a constant
+    /// (shared by all callers), or the ID of one of the `new_params`.
+    indices: SmallVec<[Word; 4]>,
+
+    new_params: SmallVec<[Instruction; 4]>,
+
+    /// Positions (in `indices`) of the `new_params`.
+    new_params_positions: SmallVec<[usize; 4]>,
+}
+
+/// Module-scoped `OpVariable` and `OpAccessChain` indices into it, which
+/// (when applied together) produce the same pointer as some pointer value.
+type GlobalPtrOrigin = (Word, SmallVec<[Word; 4]>);
+
+fn global_ptr_origin(
+    ptr: Word,
+    global_vars: &FxHashSet<Word>,
+    access_chains: &FxHashMap<Word, GlobalPtrOrigin>,
+) -> Option<GlobalPtrOrigin> {
+    if global_vars.contains(&ptr) {
+        return Some((ptr, SmallVec::new()));
+    }
+    let (base, indices) = access_chains.get(&ptr)?;
+    let (global_var, mut base_indices) = global_ptr_origin(*base, global_vars, access_chains)?;
+    base_indices.extend_from_slice(indices);
+    Some((global_var, base_indices))
+}
+
+/// Replace pointer parameters which are illegal to keep (see `inline::inline`,
+/// which would otherwise be forced to inline every call), when every caller
+/// passes a pointer into the same module-scoped `OpVariable` (e.g. a buffer),
+/// with the `OpAccessChain` indices the callers would use to produce them,
+/// allowing the callee to recompute the original pointer from its own copy
+/// of those indices (or the `OpVariable` itself, when there are no indices).
+///
+/// Functions which would get inlined anyway (i.e. `#[inline]` ones, unless the
+/// `InlineMode` is ignoring those hints, ones with a single call site, or, with
+/// `InlineMode::Size`, small enough ones) are left alone, as are entry-points
+/// and exports (which need to keep their signatures).
+pub fn replace_global_ptr_params_with_indices(
+    mut module: Module,
+    inline_mode: InlineMode,
+) -> Module {
+    let call_graph = CallGraph::collect(&module);
+
+    let custom_ext_inst_set_import = module
+        .ext_inst_imports
+        .iter()
+        .find(|inst| {
+            inst.operands[0].unwrap_literal_string() == &custom_insts::CUSTOM_EXT_INST_SET[..]
+        })
+        .map(|inst| inst.result_id.unwrap());
+
+    let mut ptr_types = FxHashMap::default();
+    let mut composite_types = FxHashMap::default();
+    let mut const_u32s = FxHashMap::default();
+    let mut global_vars = FxHashSet::default();
+    let mut id_to_type = FxHashMap::default();
+    for inst in &module.types_global_values {
+        match inst.class.opcode {
+            Op::TypePointer => {
+                ptr_types.insert(
+                    inst.result_id.unwrap(),
+                    (
+                        inst.operands[0].unwrap_storage_class(),
+                        inst.operands[1].unwrap_id_ref(),
+                    ),
+                );
+            }
+            Op::TypeStruct
+            | Op::TypeArray
+            | Op::TypeRuntimeArray
+            | Op::TypeVector
+            | Op::TypeMatrix => {
+                composite_types.insert(inst.result_id.unwrap(), inst.clone());
+            }
+            Op::Constant => {
+                if let [Operand::LiteralBit32(value)] = inst.operands[..] {
+                    const_u32s.insert(inst.result_id.unwrap(), value);
+                }
+            }
+            Op::Variable => {
+                global_vars.insert(inst.result_id.unwrap());
+            }
+            _ => {}
+        }
+        if let (Some(id), Some(ty)) = (inst.result_id, inst.result_type) {
+            id_to_type.insert(id, ty);
+        }
+    }
+
+    let exports: FxHashSet<Word> = module
+        .annotations
+        .iter()
+        .filter(|inst| {
+            inst.class.opcode == Op::Decorate
+                && inst.operands[1].unwrap_decoration() == Decoration::LinkageAttributes
+        })
+        .map(|inst| inst.operands[0].unwrap_id_ref())
+        .collect();
+
+    let mut access_chains = FxHashMap::<Word, GlobalPtrOrigin>::default();
+    let mut callee_to_calls = FxHashMap::<Word, Vec<Word>>::default();
+    let mut call_args = FxHashMap::<Word, SmallVec<[Word; 4]>>::default();
+    for inst in module
+        .functions
+        .iter()
+        .flat_map(|func| func.all_inst_iter())
+    {
+        if let (Some(id), Some(ty)) = (inst.result_id, inst.result_type) {
+            id_to_type.insert(id, ty);
+        }
+        match inst.class.opcode {
+            Op::AccessChain | Op::InBoundsAccessChain => {
+                let mut ids = inst.operands.iter().map(|operand| operand.unwrap_id_ref());
+                let base = ids.next().unwrap();
+                access_chains.insert(inst.result_id.unwrap(), (base, ids.collect()));
+            }
+            Op::FunctionCall => {
+                let call_id = inst.result_id.unwrap();
+                let mut ids = inst.operands.iter().map(|operand| operand.unwrap_id_ref());
+                callee_to_calls
+                    .entry(ids.next().unwrap())
+                    .or_default()
+                    .push(call_id);
+                call_args.insert(call_id, ids.collect());
+            }
+            _ => {}
+        }
+    }
+
+    // Callers are visited before their callees, so that any of the callers'
+    // own (replaced) pointer parameters can be seen through, in their calls.
+    let mut replaced_params_per_func_id = FxIndexMap::<Word, Vec<ReplacedPtrParam>>::default();
+    let mut replaced_args_per_call_id =
+        FxHashMap::<Word, Vec<(usize, SmallVec<[Word; 4]>)>>::default();
+    for func_idx in call_graph.post_order().into_iter().rev() {
+        if call_graph.entry_points.contains(&func_idx) {
+            continue;
+        }
+
+        let func = &module.functions[func_idx];
+        let func_id = func.def_id().unwrap();
+        let control = func.def.as_ref().unwrap().operands[0].unwrap_function_control();
+        if exports.contains(&func_id)
+            || inline_mode == InlineMode::Hints && control.contains(FunctionControl::INLINE)
+        {
+            continue;
+        }
+        let Some(calls) = callee_to_calls.get(&func_id) else {
+            continue;
+        };
+
+        // NOTE inlining a function with a single call site can't grow
+        // the module, so there's no reason to keep it out-of-line, unless it
+        // was explicitly requested (e.g. the entry-point wrapper calling the
+        // user's `fn` would otherwise always remain as a separate function).
+        // Such functions can still be seen through, by any of their callees,
+        // as long as that doesn't require access to their caller's values.
+        let see_through_only = calls.len() == 1 && !control.contains(FunctionControl::DONT_INLINE);
+        if !see_through_only
+            && inline_mode == InlineMode::Size
+            && !control.contains(FunctionControl::DONT_INLINE)
+            && is_small_function(custom_ext_inst_set_import, func)
+        {
+            continue;
+        }
+
+        let params: SmallVec<[_; 4]> = func
+            .parameters
+            .iter()
+            .map(|param| (param.result_id.unwrap(), param.result_type.unwrap()))
+            .collect();
+        for (param_idx, (param_id, param_ty)) in params.into_iter().enumerate() {
+            let illegal_ptr = ptr_types
+                .get(&param_ty)
+                .is_some_and(|&(storage_class, _)| !legal_as_fn_param_storage_class(storage_class));
+            if !illegal_ptr {
+                continue;
+            }
+
+            let Some(origins) = calls
+                .iter()
+                .map(|call_id| {
+                    let &arg = call_args[call_id].get(param_idx)?;
+                    global_ptr_origin(arg, &global_vars, &access_chains)
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            if see_through_only {
+                let [(global_var, indices)] = &origins[..] else {
+                    unreachable!()
+                };
+                if indices.iter().all(|index| const_u32s.contains_key(index)) {
+                    access_chains.insert(param_id, (*global_var, indices.clone()));
+                }
+                continue;
+            }
+
+            let Some(replaced_param) = (|| {
+                let (global_var, first_indices) = &origins[0];
+                if !origins.iter().all(|(other_global_var, indices)| {
+                    other_global_var == global_var && indices.len() == first_indices.len()
+                }) {
+                    return None;
+                }
+
+                // Each index either stays a constant (when all callers agree on
+                // it, as is required for indexing into structs), or becomes a
+                // new parameter (when all callers agree on its type, at least).
+                let mut ty = ptr_types.get(id_to_type.get(global_var)?)?.1;
+                let mut replaced_param = ReplacedPtrParam {
+                    param_idx,
+                    global_var: *global_var,
+                    indices: SmallVec::new(),
+                    new_params: SmallVec::new(),
+                    new_params_positions: SmallVec::new(),
+                };
+                for (i, &index) in first_indices.iter().enumerate() {
+                    let same_const = const_u32s.contains_key(&index)
+                        && origins.iter().all(|(_, indices)| indices[i] == index);
+                    let ty_def = composite_types.get(&ty)?;
+                    ty = match ty_def.class.opcode {
+                        Op::TypeStruct if same_const => ty_def
+                            .operands
+                            .get(const_u32s[&index] as usize)?
+                            .unwrap_id_ref(),
+                        Op::TypeArray | Op::TypeRuntimeArray | Op::TypeVector | Op::TypeMatrix => {
+                            ty_def.operands[0].unwrap_id_ref()
+                        }
+                        _ => return None,
+                    };
+                    if same_const {
+                        replaced_param.indices.push(index);
+                        continue;
+                    }
+
+                    let index_ty = *id_to_type.get(&index)?;
+                    if !origins
+                        .iter()
+                        .all(|(_, indices)| id_to_type.get(&indices[i]) == Some(&index_ty))
+                    {
+                        return None;
+                    }
+                    let new_param_id = super::id(module.header.as_mut().unwrap());
+                    replaced_param.indices.push(new_param_id);
+                    replaced_param.new_params.push(Instruction::new(
+                        Op::FunctionParameter,
+                        Some(index_ty),
+                        Some(new_param_id),
+                        vec![],
+                    ));
+                    replaced_param.new_params_positions.push(i);
+                }
+                Some(replaced_param)
+            })() else {
+                continue;
+            };
+
+            access_chains.insert(
+                param_id,
+                (replaced_param.global_var, replaced_param.indices.clone()),
+            );
+            for (&call_id, (_, indices)) in calls.iter().zip(&origins) {
+                replaced_args_per_call_id.entry(call_id).or_default().push((
+                    param_idx,
+                    replaced_param
+                        .new_params_positions
+                        .iter()
+                        .map(|&i| indices[i])
+                        .collect(),
+                ));
+            }
+            replaced_params_per_func_id
+                .entry(func_id)
+                .or_default()
+                .push(replaced_param);
+        }
+    }
+
+    if replaced_params_per_func_id.is_empty() {
+        return module;
+    }
+
+    // Decorations on the original parameters (e.g. `FuncParamAttr`) can't be
+    // kept, as they either disappear, or become `OpAccessChain`s.
+    let mut replaced_param_ids = FxHashSet::default();
+
+    let mut builder = Builder::new_from_module(module);
+    for func_idx in 0..builder.module_ref().functions.len() {
+        let func = &mut builder.module_mut().functions[func_idx];
+
+        // NOTE replacements are applied in reverse, so that earlier
+        // (argument/parameter) indices aren't affected by later replacements.
+        for inst in func.all_inst_iter_mut() {
+            if inst.class.opcode == Op::FunctionCall
+                && let Some(replaced_args) = replaced_args_per_call_id.get(&inst.result_id.unwrap())
+            {
+                for (arg_idx, indices) in replaced_args.iter().rev() {
+                    let operand_idx = 1 + arg_idx;
+                    inst.operands.splice(
+                        operand_idx..=operand_idx,
+                        indices.iter().map(|&index| Operand::IdRef(index)),
+                    );
+                }
+            }
+        }
+
+        let Some(replaced_params) = replaced_params_per_func_id.get(&func.def_id().unwrap()) else {
+            continue;
+        };
+        let mut rewrite_rules = FxHashMap::default();
+        let mut new_access_chains = vec![];
+        for replaced_param in replaced_params.iter().rev() {
+            let param_idx = replaced_param.param_idx;
+            let param = func
+                .parameters
+                .splice(
+                    param_idx..=param_idx,
+                    replaced_param.new_params.iter().cloned(),
+                )
+                .next()
+                .unwrap();
+            let param_id = param.result_id.unwrap();
+            replaced_param_ids.insert(param_id);
+            if replaced_param.indices.is_empty() {
+                rewrite_rules.insert(param_id, replaced_param.global_var);
+            } else {
+                new_access_chains.push(Instruction::new(
+                    Op::AccessChain,
+                    param.result_type,
+                    Some(param_id),
+                    [replaced_param.global_var]
+                        .into_iter()
+                        .chain(replaced_param.indices.iter().copied())
+                        .map(Operand::IdRef)
+                        .collect(),
+                ));
+            }
+        }
+        super::apply_rewrite_rules(&rewrite_rules, &mut func.blocks);
+
+        // The recomputed pointers must come after all `OpVariable`s.
+        let entry_insts = &mut func.blocks[0].instructions;
+        let insert_idx = entry_insts
+            .iter()
+            .position(|inst| inst.class.opcode != Op::Variable)
+            .unwrap_or(entry_insts.len());
+        entry_insts.splice(insert_idx..insert_idx, new_access_chains);
+
+        let return_type = func.def.as_ref().unwrap().result_type.unwrap();
+        let new_param_types: Vec<_> = func
+            .parameters
+            .iter()
+            .map(|inst| inst.result_type.unwrap())
+            .collect();
+        let new_func_type = builder.type_function(return_type, new_param_types);
+        let func = &mut builder.module_mut().functions[func_idx];
+        func.def.as_mut().unwrap().operands[1] = Operand::IdRef(new_func_type);
+    }
+
+    let mut module = builder.module();
+    module.annotations.retain(|inst| {
+        !matches!(inst.operands.first(), Some(Operand::IdRef(target)) if replaced_param_ids.contains(target))
+    });
+    module
+}
@@ -0,0 +1,178 @@
+//! Lowering of the custom instructions standing in for SPIR-V features that
+//! `rspirv` can't represent yet (see `CustomOp::is_rspirv_unsupported`), which
+//! has to happen on the final SPIR-V binary, as `rspirv` can't load the result.
+//!
+//! Currently, that's `SPV_KHR_quad_control` (`OpGroupNonUniformQuad{All,Any}KHR`,
+//! and the `QuadDerivativesKHR` execution mode), and `SPV_KHR_maximal_reconvergence`
+//! (the `MaximallyReconvergesKHR` execution mode), with their capabilities and
+//! extensions also being added here, as needed.
+//
+// FIXME remove this once `rspirv` supports all of the above.
+
+use crate::custom_insts::{self, CustomOp, RspirvUnsupportedExecutionMode};
+use rspirv::binary::Assemble;
+use rspirv::dr::{Instruction, Operand};
+use rspirv::spirv::{Op, Word};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexSet};
+
+// NOTE these are from `SPV_KHR_quad_control`.
+const OP_GROUP_NON_UNIFORM_QUAD_ALL_KHR: u32 = 5110;
+const OP_GROUP_NON_UNIFORM_QUAD_ANY_KHR: u32 = 5111;
+const CAPABILITY_QUAD_CONTROL_KHR: u32 = 5087;
+
+const SPV_KHR_QUAD_CONTROL: &str = "SPV_KHR_quad_control";
+const SPV_KHR_MAXIMAL_RECONVERGENCE: &str = "SPV_KHR_maximal_reconvergence";
+
+/// Splits the words of a SPIR-V binary (without its header) into instructions.
+fn insts(words: &[u32]) -> impl Iterator<Item = &[u32]> + Clone {
+    let mut words = words;
+    std::iter::from_fn(move || {
+        let word_count = (*words.first()? >> 16) as usize;
+        let (inst, rest) = words.split_at(word_count);
+        words = rest;
+        Some(inst)
+    })
+}
+
+fn opcode(inst: &[u32]) -> u32 {
+    inst[0] & 0xffff
+}
+
+/// Decodes the (NUL-terminated) literal string at the start of `words`.
+fn decode_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|&b| b != 0)
+        .collect();
+    String::from_utf8(bytes).unwrap()
+}
+
+/// Replace the custom instructions standing in for SPIR-V features that `rspirv`
+/// can't represent yet, with the actual SPIR-V instructions (and execution modes),
+/// also adding the capabilities and extensions they require.
+pub fn lower(spv_binary: Vec<u32>) -> Vec<u32> {
+    let (header, words) = spv_binary.split_at(5);
+
+    let Some(custom_ext_inst_set) = insts(words).find_map(|inst| {
+        (opcode(inst) == Op::ExtInstImport as u32
+            && decode_string(&inst[2..]) == custom_insts::CUSTOM_EXT_INST_SET[..])
+            .then(|| inst[1])
+    }) else {
+        return spv_binary;
+    };
+
+    let mut existing_capabilities = FxHashSet::default();
+    let mut existing_extensions = FxHashSet::default();
+    let mut new_capabilities = FxIndexSet::default();
+    let mut new_extensions = FxIndexSet::default();
+    let mut new_execution_modes = FxIndexSet::default();
+
+    // Indices of the instructions to insert the above after.
+    let mut last_capability = None;
+    let mut last_extension = None;
+    let mut last_entry_point_or_execution_mode = None;
+
+    let mut u32_constants: FxHashMap<Word, u32> = FxHashMap::default();
+    let mut current_func = None;
+    for (i, inst) in insts(words).enumerate() {
+        match opcode(inst) {
+            o if o == Op::Capability as u32 => {
+                existing_capabilities.insert(inst[1]);
+                last_capability = Some(i);
+            }
+            o if o == Op::Extension as u32 => {
+                existing_extensions.insert(decode_string(&inst[1..]));
+                last_extension = Some(i);
+            }
+            o if o == Op::EntryPoint as u32
+                || o == Op::ExecutionMode as u32
+                || o == Op::ExecutionModeId as u32 =>
+            {
+                last_entry_point_or_execution_mode = Some(i);
+            }
+            o if o == Op::Constant as u32 && inst.len() == 4 => {
+                u32_constants.insert(inst[2], inst[3]);
+            }
+            o if o == Op::Function as u32 => current_func = Some(inst[2]),
+            o if o == Op::ExtInst as u32 && inst[3] == custom_ext_inst_set => {
+                match CustomOp::decode(inst[4]) {
+                    CustomOp::QuadAllKHR | CustomOp::QuadAnyKHR => {
+                        new_capabilities.insert(CAPABILITY_QUAD_CONTROL_KHR);
+                        new_extensions.insert(SPV_KHR_QUAD_CONTROL);
+                    }
+                    CustomOp::ExecutionMode => {
+                        let mode = RspirvUnsupportedExecutionMode::decode(u32_constants[&inst[5]])
+                            .unwrap();
+                        match mode {
+                            RspirvUnsupportedExecutionMode::QuadDerivativesKHR => {
+                                new_capabilities.insert(CAPABILITY_QUAD_CONTROL_KHR);
+                                new_extensions.insert(SPV_KHR_QUAD_CONTROL);
+                            }
+                            RspirvUnsupportedExecutionMode::MaximallyReconvergesKHR => {
+                                new_extensions.insert(SPV_KHR_MAXIMAL_RECONVERGENCE);
+                            }
+                        }
+                        new_execution_modes.insert((current_func.unwrap(), mode));
+                    }
+                    custom_op => unreachable!(
+                        "`CustomOp::{custom_op:?}` should've been lowered by the linker"
+                    ),
+                }
+            }
+            _ => {}
+        }
+    }
+    new_capabilities.retain(|cap| !existing_capabilities.contains(cap));
+    new_extensions.retain(|ext| !existing_extensions.contains(*ext));
+
+    let mut lowered = Vec::with_capacity(spv_binary.len());
+    lowered.extend_from_slice(header);
+    for (i, inst) in insts(words).enumerate() {
+        match opcode(inst) {
+            o if o == Op::ExtInstImport as u32 && inst[1] == custom_ext_inst_set => {}
+            o if o == Op::ExtInst as u32 && inst[3] == custom_ext_inst_set => {
+                let quad_opcode = match CustomOp::decode(inst[4]) {
+                    CustomOp::QuadAllKHR => OP_GROUP_NON_UNIFORM_QUAD_ALL_KHR,
+                    CustomOp::QuadAnyKHR => OP_GROUP_NON_UNIFORM_QUAD_ANY_KHR,
+                    CustomOp::ExecutionMode => continue,
+                    _ => unreachable!(),
+                };
+                let (result_type, result_id, predicate) = (inst[1], inst[2], inst[5]);
+                lowered.extend([4 << 16 | quad_opcode, result_type, result_id, predicate]);
+            }
+            _ => lowered.extend_from_slice(inst),
+        }
+
+        // NOTE capabilities always come first, so there's always at least one.
+        if Some(i) == last_capability {
+            for &cap in &new_capabilities {
+                Instruction::new(Op::Capability, None, None, vec![Operand::LiteralBit32(cap)])
+                    .assemble_into(&mut lowered);
+            }
+        }
+        if Some(i) == last_extension.or(last_capability) {
+            for &ext in &new_extensions {
+                Instruction::new(
+                    Op::Extension,
+                    None,
+                    None,
+                    vec![Operand::LiteralString(ext.to_string())],
+                )
+                .assemble_into(&mut lowered);
+            }
+        }
+        if Some(i) == last_entry_point_or_execution_mode {
+            for &(func, mode) in &new_execution_modes {
+                Instruction::new(
+                    Op::ExecutionMode,
+                    None,
+                    None,
+                    vec![Operand::IdRef(func), Operand::LiteralBit32(mode as u32)],
+                )
+                .assemble_into(&mut lowered);
+            }
+        }
+    }
+    lowered
+}
@@ -0,0 +1,623 @@
+//! Lowering of `NonSemantic.DebugPrintf` instructions (i.e. `debug_printf!`s, and
+//! `panic!` messages, with the `debug-printf` abort strategy) to appending records
+//! to a "shader log" buffer (see `--shader-log-buffer`), which doesn't depend on
+//! the Vulkan Validation Layers' `debugPrintf` implementation.
+//!
+//! Each record is a format id (an index into the format table returned by the pass,
+//! which ends up in `CompileResult::shader_log_formats`), followed by the words of
+//! all the arguments, with space for it reserved by an atomic add to the cursor
+//! at the start of the buffer (see `rustc_codegen_spirv_types::ShaderLog` for
+//! the layout, and how the records are decoded on the host).
+
+use super::{Result, get_name, get_names};
+use rspirv::dr::{Instruction, Module, Operand};
+use rspirv::spirv::{Decoration, MemoryModel, MemorySemantics, Op, Scope, StorageClass, Word};
+use rustc_codegen_spirv_types::{
+    DescriptorBinding, SHADER_LOG_HEADER_WORDS, ShaderLogArg, ShaderLogFormat, ShaderLogScalar,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexSet};
+use rustc_session::Session;
+
+/// Helper for reusing (or adding) the types and constants needed by the lowering.
+pub(super) struct Globals<'a> {
+    pub(super) module: &'a mut Module,
+    pub(super) new_globals: Vec<Instruction>,
+}
+
+impl<'a> Globals<'a> {
+    pub(super) fn new(module: &'a mut Module) -> Self {
+        Self {
+            module,
+            new_globals: vec![],
+        }
+    }
+
+    fn next_id(&mut self) -> Word {
+        let header = self.module.header.as_mut().unwrap();
+        header.bound += 1;
+        header.bound - 1
+    }
+
+    /// Find an existing (undecorated) type or constant, or add a new one.
+    pub(super) fn get_or_add(
+        &mut self,
+        op: Op,
+        result_type: Option<Word>,
+        operands: Vec<Operand>,
+    ) -> Word {
+        let existing = self
+            .module
+            .types_global_values
+            .iter()
+            .chain(&self.new_globals)
+            .find(|inst| {
+                inst.class.opcode == op
+                    && inst.result_type == result_type
+                    && inst.operands == operands
+            });
+        if let Some(inst) = existing {
+            return inst.result_id.unwrap();
+        }
+        self.add(op, result_type, operands)
+    }
+
+    pub(super) fn add(
+        &mut self,
+        op: Op,
+        result_type: Option<Word>,
+        operands: Vec<Operand>,
+    ) -> Word {
+        let id = self.next_id();
+        self.new_globals
+            .push(Instruction::new(op, result_type, Some(id), operands));
+        id
+    }
+
+    /// Append a new instruction (with a result) to `insts`.
+    pub(super) fn emit(
+        &mut self,
+        insts: &mut Vec<Instruction>,
+        op: Op,
+        result_type: Word,
+        operands: Vec<Operand>,
+    ) -> Word {
+        let id = self.next_id();
+        insts.push(Instruction::new(op, Some(result_type), Some(id), operands));
+        id
+    }
+
+    pub(super) fn u32_type(&mut self) -> Word {
+        self.get_or_add(
+            Op::TypeInt,
+            None,
+            vec![Operand::LiteralBit32(32), Operand::LiteralBit32(0)],
+        )
+    }
+
+    pub(super) fn u32_const(&mut self, value: u32) -> Word {
+        let u32_type = self.u32_type();
+        self.get_or_add(
+            Op::Constant,
+            Some(u32_type),
+            vec![Operand::LiteralBit32(value)],
+        )
+    }
+
+    /// Add all the new types and constants to the module.
+    pub(super) fn finish(self) {
+        self.module.types_global_values.extend(self.new_globals);
+    }
+}
+
+/// Helper for determining how values can be written to a `WordBuffer`.
+pub(super) struct ArgKinds {
+    type_defs: FxHashMap<Word, Instruction>,
+    value_types: FxHashMap<Word, Word>,
+}
+
+impl ArgKinds {
+    pub(super) fn new(module: &Module) -> Self {
+        Self {
+            type_defs: module
+                .types_global_values
+                .iter()
+                .filter_map(|inst| Some((inst.result_id?, inst.clone())))
+                .collect(),
+            value_types: module
+                .all_inst_iter()
+                .filter_map(|inst| Some((inst.result_id?, inst.result_type?)))
+                .collect(),
+        }
+    }
+
+    fn scalar_kind(&self, ty: Word) -> Option<ShaderLogScalar> {
+        let ty_def = self.type_defs.get(&ty)?;
+        Some(match (ty_def.class.opcode, &ty_def.operands[..]) {
+            (Op::TypeInt, [Operand::LiteralBit32(32), Operand::LiteralBit32(0)]) => {
+                ShaderLogScalar::U32
+            }
+            (Op::TypeInt, [Operand::LiteralBit32(32), _]) => ShaderLogScalar::I32,
+            (Op::TypeFloat, [Operand::LiteralBit32(32), ..]) => ShaderLogScalar::F32,
+            (Op::TypeInt, [Operand::LiteralBit32(64), Operand::LiteralBit32(0)]) => {
+                ShaderLogScalar::U64
+            }
+            (Op::TypeInt, [Operand::LiteralBit32(64), _]) => ShaderLogScalar::I64,
+            (Op::TypeFloat, [Operand::LiteralBit32(64), ..]) => ShaderLogScalar::F64,
+            _ => return None,
+        })
+    }
+
+    /// Returns the kind of the argument `value`, and its scalar type (for vectors).
+    pub(super) fn arg_kind(&self, value: Word) -> Option<(ShaderLogArg, Word)> {
+        let ty = *self.value_types.get(&value)?;
+        let ty_def = self.type_defs.get(&ty)?;
+        if ty_def.class.opcode == Op::TypeVector {
+            let elem_ty = ty_def.operands[0].unwrap_id_ref();
+            let components = ty_def.operands[1].unwrap_literal_bit32();
+            let arg = ShaderLogArg {
+                scalar: self.scalar_kind(elem_ty)?,
+                components,
+            };
+            Some((arg, elem_ty))
+        } else {
+            let arg = ShaderLogArg {
+                scalar: self.scalar_kind(ty)?,
+                components: 1,
+            };
+            Some((arg, ty))
+        }
+    }
+}
+
+/// A storage buffer of `u32` words, starting with `SHADER_LOG_HEADER_WORDS` header words
+/// (an atomic counter, followed by a scratch word), used by both the shader log buffer,
+/// and the abort record buffer (see also `abort_record`).
+pub(super) struct WordBuffer {
+    var: Word,
+    word_ptr_type: Word,
+    u32_type: Word,
+    bool_type: Word,
+    zero: Word,
+}
+
+impl WordBuffer {
+    /// Declare the buffer variable (and its types/decorations) at `binding`.
+    pub(super) fn declare(globals: &mut Globals<'_>, binding: DescriptorBinding) -> Self {
+        let u32_type = globals.u32_type();
+        let bool_type = globals.get_or_add(Op::TypeBool, None, vec![]);
+        let words_type = globals.add(Op::TypeRuntimeArray, None, vec![Operand::IdRef(u32_type)]);
+        let buffer_type = globals.add(Op::TypeStruct, None, vec![Operand::IdRef(words_type)]);
+        let buffer_ptr_type = globals.get_or_add(
+            Op::TypePointer,
+            None,
+            vec![
+                Operand::StorageClass(StorageClass::StorageBuffer),
+                Operand::IdRef(buffer_type),
+            ],
+        );
+        let word_ptr_type = globals.get_or_add(
+            Op::TypePointer,
+            None,
+            vec![
+                Operand::StorageClass(StorageClass::StorageBuffer),
+                Operand::IdRef(u32_type),
+            ],
+        );
+        let var = globals.add(
+            Op::Variable,
+            Some(buffer_ptr_type),
+            vec![Operand::StorageClass(StorageClass::StorageBuffer)],
+        );
+        let zero = globals.u32_const(0);
+
+        let module = &mut *globals.module;
+        module.annotations.extend([
+            Instruction::new(
+                Op::Decorate,
+                None,
+                None,
+                vec![
+                    Operand::IdRef(words_type),
+                    Operand::Decoration(Decoration::ArrayStride),
+                    Operand::LiteralBit32(4),
+                ],
+            ),
+            Instruction::new(
+                Op::Decorate,
+                None,
+                None,
+                vec![
+                    Operand::IdRef(buffer_type),
+                    Operand::Decoration(Decoration::Block),
+                ],
+            ),
+            Instruction::new(
+                Op::MemberDecorate,
+                None,
+                None,
+                vec![
+                    Operand::IdRef(buffer_type),
+                    Operand::LiteralBit32(0),
+                    Operand::Decoration(Decoration::Offset),
+                    Operand::LiteralBit32(0),
+                ],
+            ),
+            Instruction::new(
+                Op::Decorate,
+                None,
+                None,
+                vec![
+                    Operand::IdRef(var),
+                    Operand::Decoration(Decoration::DescriptorSet),
+                    Operand::LiteralBit32(binding.descriptor_set),
+                ],
+            ),
+            Instruction::new(
+                Op::Decorate,
+                None,
+                None,
+                vec![
+                    Operand::IdRef(var),
+                    Operand::Decoration(Decoration::Binding),
+                    Operand::LiteralBit32(binding.binding),
+                ],
+            ),
+        ]);
+
+        // `StorageBuffer` was only added to core SPIR-V in 1.3.
+        let version = module.header.as_ref().unwrap().version();
+        let storage_buffer_ext = "SPV_KHR_storage_buffer_storage_class";
+        if version < (1, 3)
+            && !module
+                .extensions
+                .iter()
+                .any(|inst| inst.operands[0].unwrap_literal_string() == storage_buffer_ext)
+        {
+            module.extensions.push(Instruction::new(
+                Op::Extension,
+                None,
+                None,
+                vec![Operand::LiteralString(storage_buffer_ext.to_string())],
+            ));
+        }
+
+        Self {
+            var,
+            word_ptr_type,
+            u32_type,
+            bool_type,
+            zero,
+        }
+    }
+
+    /// Atomically add `value` to the counter (i.e. the first word), returning its old value.
+    pub(super) fn atomic_add_to_counter(
+        &self,
+        globals: &mut Globals<'_>,
+        insts: &mut Vec<Instruction>,
+        value: Word,
+    ) -> Word {
+        // NOTE `Device` scope requires an additional capability with the Vulkan memory model
+        // (where `QueueFamily` is the equivalent of the `Device` scope of other memory models).
+        let uses_vulkan_memory_model = globals
+            .module
+            .memory_model
+            .as_ref()
+            .is_some_and(|inst| inst.operands[1] == Operand::MemoryModel(MemoryModel::Vulkan));
+        let scope = globals.u32_const(if uses_vulkan_memory_model {
+            Scope::QueueFamily as u32
+        } else {
+            Scope::Device as u32
+        });
+        let relaxed = globals.u32_const(MemorySemantics::NONE.bits());
+        let counter_ptr = globals.emit(
+            insts,
+            Op::AccessChain,
+            self.word_ptr_type,
+            vec![
+                Operand::IdRef(self.var),
+                Operand::IdRef(self.zero),
+                Operand::IdRef(self.zero),
+            ],
+        );
+        globals.emit(
+            insts,
+            Op::AtomicIAdd,
+            self.u32_type,
+            vec![
+                Operand::IdRef(counter_ptr),
+                Operand::IdRef(scope),
+                Operand::IdRef(relaxed),
+                Operand::IdRef(value),
+            ],
+        )
+    }
+
+    /// Convert `arg` (of kind `kind`, see `ArgKinds::arg_kind`) to `u32` words,
+    /// appending them to `words`.
+    pub(super) fn push_arg_words(
+        &self,
+        globals: &mut Globals<'_>,
+        insts: &mut Vec<Instruction>,
+        words: &mut Vec<Word>,
+        arg: Word,
+        (kind, scalar_type): (ShaderLogArg, Word),
+    ) {
+        for i in 0..kind.components {
+            let scalar = if kind.components == 1 {
+                arg
+            } else {
+                globals.emit(
+                    insts,
+                    Op::CompositeExtract,
+                    scalar_type,
+                    vec![Operand::IdRef(arg), Operand::LiteralBit32(i)],
+                )
+            };
+            match kind.scalar {
+                ShaderLogScalar::U32 => words.push(scalar),
+                ShaderLogScalar::I32 | ShaderLogScalar::F32 => {
+                    words.push(globals.emit(
+                        insts,
+                        Op::Bitcast,
+                        self.u32_type,
+                        vec![Operand::IdRef(scalar)],
+                    ));
+                }
+                ShaderLogScalar::U64 | ShaderLogScalar::I64 | ShaderLogScalar::F64 => {
+                    let u64_type = globals.get_or_add(
+                        Op::TypeInt,
+                        None,
+                        vec![Operand::LiteralBit32(64), Operand::LiteralBit32(0)],
+                    );
+                    let shift_32 = globals.u32_const(32);
+                    let bits = if kind.scalar == ShaderLogScalar::U64 {
+                        scalar
+                    } else {
+                        globals.emit(insts, Op::Bitcast, u64_type, vec![Operand::IdRef(scalar)])
+                    };
+                    let hi = globals.emit(
+                        insts,
+                        Op::ShiftRightLogical,
+                        u64_type,
+                        vec![Operand::IdRef(bits), Operand::IdRef(shift_32)],
+                    );
+                    for half in [bits, hi] {
+                        words.push(globals.emit(
+                            insts,
+                            Op::UConvert,
+                            self.u32_type,
+                            vec![Operand::IdRef(half)],
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Store `words` starting at index `start` (after the header words), redirecting
+    /// those which don't fit in the buffer (or all of them, if `enabled` is `false`)
+    /// to the scratch word instead.
+    pub(super) fn store_words(
+        &self,
+        globals: &mut Globals<'_>,
+        insts: &mut Vec<Instruction>,
+        start: Word,
+        words: Vec<Word>,
+        enabled: Option<Word>,
+    ) {
+        let scratch_index = globals.u32_const(1);
+        let buffer_len = globals.emit(
+            insts,
+            Op::ArrayLength,
+            self.u32_type,
+            vec![Operand::IdRef(self.var), Operand::LiteralBit32(0)],
+        );
+        for (i, word) in words.into_iter().enumerate() {
+            let offset = globals.u32_const((SHADER_LOG_HEADER_WORDS + i) as u32);
+            let index = globals.emit(
+                insts,
+                Op::IAdd,
+                self.u32_type,
+                vec![Operand::IdRef(start), Operand::IdRef(offset)],
+            );
+            let mut fits = globals.emit(
+                insts,
+                Op::ULessThan,
+                self.bool_type,
+                vec![Operand::IdRef(index), Operand::IdRef(buffer_len)],
+            );
+            if let Some(enabled) = enabled {
+                fits = globals.emit(
+                    insts,
+                    Op::LogicalAnd,
+                    self.bool_type,
+                    vec![Operand::IdRef(enabled), Operand::IdRef(fits)],
+                );
+            }
+            let index = globals.emit(
+                insts,
+                Op::Select,
+                self.u32_type,
+                vec![
+                    Operand::IdRef(fits),
+                    Operand::IdRef(index),
+                    Operand::IdRef(scratch_index),
+                ],
+            );
+            let ptr = globals.emit(
+                insts,
+                Op::AccessChain,
+                self.word_ptr_type,
+                vec![
+                    Operand::IdRef(self.var),
+                    Operand::IdRef(self.zero),
+                    Operand::IdRef(index),
+                ],
+            );
+            insts.push(Instruction::new(
+                Op::Store,
+                None,
+                None,
+                vec![Operand::IdRef(ptr), Operand::IdRef(word)],
+            ));
+        }
+    }
+}
+
+/// Remove any `OpString`s which are no longer used (e.g. after removing
+/// the instructions using them as format strings).
+pub(super) fn remove_unused_strings(module: &mut Module) {
+    let used_ids: FxHashSet<Word> = module
+        .all_inst_iter()
+        .filter(|inst| inst.class.opcode != Op::String)
+        .flat_map(|inst| &inst.operands)
+        .filter_map(|operand| operand.id_ref_any())
+        .collect();
+    module.debug_string_source.retain(|inst| {
+        inst.class.opcode != Op::String || used_ids.contains(&inst.result_id.unwrap())
+    });
+}
+
+/// Lower all `NonSemantic.DebugPrintf` instructions to appending records to the
+/// shader log buffer at `binding`, returning the format table for those records.
+///
+/// Must run after the SPIR-T passes, as those may introduce `NonSemantic.DebugPrintf`
+/// instructions (e.g. to report `panic!`s), but before interface variables are gathered.
+pub fn lower_debug_printf_to_shader_log(
+    sess: &Session,
+    module: &mut Module,
+    binding: DescriptorBinding,
+) -> Result<Vec<ShaderLogFormat>> {
+    let debug_printf_imports: FxHashSet<Word> = module
+        .ext_inst_imports
+        .iter()
+        .filter(|inst| inst.operands[0].unwrap_literal_string() == "NonSemantic.DebugPrintf")
+        .map(|inst| inst.result_id.unwrap())
+        .collect();
+    if debug_printf_imports.is_empty() {
+        return Ok(vec![]);
+    }
+    let is_debug_printf = |inst: &Instruction| {
+        inst.class.opcode == Op::ExtInst
+            && debug_printf_imports.contains(&inst.operands[0].unwrap_id_ref())
+    };
+
+    let strings: FxHashMap<Word, String> = module
+        .debug_string_source
+        .iter()
+        .filter(|inst| inst.class.opcode == Op::String)
+        .map(|inst| {
+            (
+                inst.result_id.unwrap(),
+                inst.operands[0].unwrap_literal_string().to_string(),
+            )
+        })
+        .collect();
+    let arg_kinds = ArgKinds::new(module);
+
+    // Collect all the formats first, to avoid adding anything if any are invalid.
+    let mut formats = FxIndexSet::default();
+    let mut errors = vec![];
+    for func in &module.functions {
+        for inst in func.all_inst_iter().filter(|inst| is_debug_printf(inst)) {
+            let format = strings.get(&inst.operands[2].unwrap_id_ref());
+            let args: Option<Vec<_>> = inst.operands[3..]
+                .iter()
+                .map(|arg| Some(arg_kinds.arg_kind(arg.unwrap_id_ref())?.0))
+                .collect();
+            match (format, args) {
+                (Some(format), Some(args)) => {
+                    formats.insert(ShaderLogFormat {
+                        format: format.clone(),
+                        args,
+                    });
+                }
+                (format, _) => errors.push((func.def_id().unwrap(), format.cloned())),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        let names = get_names(module);
+        let mut result = Ok(vec![]);
+        for (func, format) in errors {
+            let mut err = sess.dcx().struct_err(format!(
+                "unsupported `debug_printf!` (in `{}`) for the shader log buffer",
+                get_name(&names, func)
+            ));
+            err.note(
+                "only 32-bit and 64-bit scalars (or vectors of them) are supported as arguments",
+            );
+            if let Some(format) = format {
+                err.note(format!("format string: {format:?}"));
+            }
+            result = Err(err.emit());
+        }
+        return result;
+    }
+
+    let mut globals = Globals::new(module);
+    let buffer = WordBuffer::declare(&mut globals, binding);
+
+    let mut new_functions = std::mem::take(&mut globals.module.functions);
+    for func in &mut new_functions {
+        for block in &mut func.blocks {
+            let mut new_insts = Vec::with_capacity(block.instructions.len());
+            for inst in std::mem::take(&mut block.instructions) {
+                if !is_debug_printf(&inst) {
+                    new_insts.push(inst);
+                    continue;
+                }
+                let format = ShaderLogFormat {
+                    format: strings[&inst.operands[2].unwrap_id_ref()].clone(),
+                    args: inst.operands[3..]
+                        .iter()
+                        .map(|arg| arg_kinds.arg_kind(arg.unwrap_id_ref()).unwrap().0)
+                        .collect(),
+                };
+                let format_id = formats.get_index_of(&format).unwrap() as u32;
+
+                // Convert all the arguments to `u32` words.
+                let mut words = vec![globals.u32_const(format_id)];
+                for arg in &inst.operands[3..] {
+                    let arg = arg.unwrap_id_ref();
+                    buffer.push_arg_words(
+                        &mut globals,
+                        &mut new_insts,
+                        &mut words,
+                        arg,
+                        arg_kinds.arg_kind(arg).unwrap(),
+                    );
+                }
+
+                // Reserve space for the record, and write its words, redirecting those
+                // which don't fit (in the runtime array) to the scratch word instead.
+                let len = globals.u32_const(words.len() as u32);
+                let start = buffer.atomic_add_to_counter(&mut globals, &mut new_insts, len);
+                buffer.store_words(&mut globals, &mut new_insts, start, words, None);
+            }
+            block.instructions = new_insts;
+        }
+    }
+    globals.module.functions = new_functions;
+    globals.finish();
+
+    // Remove `NonSemantic.DebugPrintf` (and its extension, if no longer needed),
+    // and any format strings that were only used by it.
+    module
+        .ext_inst_imports
+        .retain(|inst| !debug_printf_imports.contains(&inst.result_id.unwrap()));
+    let needs_non_semantic_info = module.ext_inst_imports.iter().any(|inst| {
+        inst.operands[0]
+            .unwrap_literal_string()
+            .starts_with("NonSemantic.")
+    });
+    if !needs_non_semantic_info {
+        module
+            .extensions
+            .retain(|inst| inst.operands[0].unwrap_literal_string() != "SPV_KHR_non_semantic_info");
+    }
+    remove_unused_strings(module);
+
+    Ok(formats.into_iter().collect())
+}
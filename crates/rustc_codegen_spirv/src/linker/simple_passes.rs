@@ -1,9 +1,10 @@
 use super::{get_name, get_names};
-use rspirv::dr::{Block, Function, Module};
-use rspirv::spirv::{Decoration, ExecutionModel, Op, Word};
+use rspirv::dr::{Block, Function, Instruction, Module, Operand};
+use rspirv::spirv::{Decoration, Dim, ExecutionMode, ExecutionModel, Op, StorageClass, Word};
 use rustc_codegen_spirv_types::Capability;
-use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexSet};
 use rustc_session::Session;
+use std::collections::hash_map;
 use std::iter::once;
 use std::mem::take;
 
@@ -269,6 +270,8 @@ pub fn check_fragment_insts(sess: &Session, module: &Module) -> super::Result<()
                     | Op::DPdyCoarse
                     | Op::FwidthCoarse
                     | Op::Kill
+                    | Op::BeginInvocationInterlockEXT
+                    | Op::EndInvocationInterlockEXT
             ) {
                 // These instructions are (usually) in system functions - if we get an error, allow
                 // the system function to be visited again from elsewhere to emit another error
@@ -301,6 +304,370 @@ pub fn check_fragment_insts(sess: &Session, module: &Module) -> super::Result<()
     }
 }
 
+/// Check that fragment shader interlock critical sections (`OpBeginInvocationInterlockEXT`
+/// and `OpEndInvocationInterlockEXT`, reachable from a fragment entry point) are balanced,
+/// i.e. that on every path through the entry point, each of them is executed exactly once
+/// (so they can't be used conditionally, or in a loop), and that the entry point has one
+/// of the interlock execution modes.
+///
+/// Non-fragment entry points are already rejected by `check_fragment_insts`.
+pub fn check_invocation_interlock(sess: &Session, module: &Module) -> super::Result<()> {
+    let func_id_to_idx: FxHashMap<Word, usize> = module
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(index, func)| (func.def_id().unwrap(), index))
+        .collect();
+
+    let mut summaries = vec![None; module.functions.len()];
+    let mut any_err = None;
+    for entry in &module.entry_points {
+        if entry.operands[0].unwrap_execution_model() != ExecutionModel::Fragment {
+            continue;
+        }
+        let entry_id = entry.operands[1].unwrap_id_ref();
+        let summary = summarize_interlock(
+            module,
+            &func_id_to_idx,
+            &mut summaries,
+            func_id_to_idx[&entry_id],
+        );
+        // NOTE an entry point that never returns (i.e. always aborts) is also accepted.
+        let unbalanced_note = match summary {
+            Ok(None | Some(Interlock::Untouched)) => continue,
+            Ok(Some(Interlock::BeginEnd)) => None,
+            Ok(Some(Interlock::Begin)) => Some("invocation interlock is begun without being ended"),
+            Ok(Some(Interlock::End)) => Some("invocation interlock is ended without being begun"),
+            Err(note) => Some(note),
+        };
+        let entry_name = entry.operands[2].unwrap_literal_string();
+
+        let has_interlock_mode = module.execution_modes.iter().any(|inst| {
+            inst.operands[0].unwrap_id_ref() == entry_id
+                && matches!(
+                    inst.operands[1].unwrap_execution_mode(),
+                    ExecutionMode::PixelInterlockOrderedEXT
+                        | ExecutionMode::PixelInterlockUnorderedEXT
+                        | ExecutionMode::SampleInterlockOrderedEXT
+                        | ExecutionMode::SampleInterlockUnorderedEXT
+                        | ExecutionMode::ShadingRateInterlockOrderedEXT
+                        | ExecutionMode::ShadingRateInterlockUnorderedEXT
+                )
+        });
+        if !has_interlock_mode {
+            any_err = Some(
+                sess.dcx()
+                    .struct_err(format!(
+                        "fragment entry point `{entry_name}` uses invocation interlock, \
+                         but has no interlock execution mode"
+                    ))
+                    .with_help("add one, e.g. `#[spirv(fragment(pixel_interlock_ordered_ext))]`")
+                    .emit(),
+            );
+        }
+        if let Some(note) = unbalanced_note {
+            any_err = Some(
+                sess.dcx()
+                    .struct_err(format!(
+                        "fragment entry point `{entry_name}` must begin and end invocation \
+                         interlock exactly once, on every path"
+                    ))
+                    .with_note(note)
+                    .emit(),
+            );
+        }
+    }
+    match any_err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// The effect of (a path through) a function on the invocation interlock critical section.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Interlock {
+    Untouched,
+    Begin,
+    End,
+    BeginEnd,
+}
+
+impl Interlock {
+    /// Combine the effects of two consecutive parts of a path, if they're balanced.
+    fn then(self, next: Self) -> Option<Self> {
+        Some(match (self, next) {
+            (effect, Self::Untouched) | (Self::Untouched, effect) => effect,
+            (Self::Begin, Self::End) => Self::BeginEnd,
+            _ => return None,
+        })
+    }
+}
+
+/// Compute the effect of the function at `index` on the invocation interlock critical
+/// section, which must be the same on every path through it (or `None` if it never
+/// returns), or an error note explaining why it isn't.
+fn summarize_interlock(
+    module: &Module,
+    func_id_to_idx: &FxHashMap<Word, usize>,
+    summaries: &mut Vec<Option<Result<Option<Interlock>, &'static str>>>,
+    index: usize,
+) -> Result<Option<Interlock>, &'static str> {
+    if let Some(summary) = summaries[index] {
+        return summary;
+    }
+    // Recursion is rejected later (by inlining), so it's enough to avoid getting stuck.
+    summaries[index] = Some(Ok(Some(Interlock::Untouched)));
+    let summary = summarize_interlock_uncached(module, func_id_to_idx, summaries, index);
+    summaries[index] = Some(summary);
+    summary
+}
+
+fn summarize_interlock_uncached(
+    module: &Module,
+    func_id_to_idx: &FxHashMap<Word, usize>,
+    summaries: &mut Vec<Option<Result<Option<Interlock>, &'static str>>>,
+    index: usize,
+) -> Result<Option<Interlock>, &'static str> {
+    const NOT_ONCE: &str =
+        "invocation interlock is begun or ended more than once, or ended before being begun";
+    const NOT_ON_EVERY_PATH: &str =
+        "invocation interlock is begun or ended conditionally, or in a loop";
+
+    let func = &module.functions[index];
+    let Some(entry_block) = func.blocks.first() else {
+        return Ok(Some(Interlock::Untouched));
+    };
+    let blocks: FxHashMap<Word, &Block> = func
+        .blocks
+        .iter()
+        .map(|block| (block.label_id().unwrap(), block))
+        .collect();
+
+    // The state on entry to each block, which must be the same along all incoming
+    // edges (including backedges), so each block only has to be visited once.
+    let entry_label = entry_block.label_id().unwrap();
+    let mut state_at_block = FxHashMap::default();
+    state_at_block.insert(entry_label, Interlock::Untouched);
+    let mut queue = vec![entry_label];
+    let mut state_at_return = None;
+    'blocks: while let Some(label) = queue.pop() {
+        let block = blocks[&label];
+        let mut state = state_at_block[&label];
+        for inst in &block.instructions {
+            let effect = match inst.class.opcode {
+                Op::BeginInvocationInterlockEXT => Interlock::Begin,
+                Op::EndInvocationInterlockEXT => Interlock::End,
+                Op::FunctionCall => {
+                    let callee_id = inst.operands[0].unwrap_id_ref();
+                    let Some(&callee) = func_id_to_idx.get(&callee_id) else {
+                        continue;
+                    };
+                    match summarize_interlock(module, func_id_to_idx, summaries, callee)? {
+                        Some(effect) => effect,
+                        // The rest of the block is unreachable.
+                        None => continue 'blocks,
+                    }
+                }
+                _ => continue,
+            };
+            state = state.then(effect).ok_or(NOT_ONCE)?;
+        }
+        let terminator = block.instructions.last().unwrap();
+        if matches!(terminator.class.opcode, Op::Return | Op::ReturnValue)
+            && *state_at_return.get_or_insert(state) != state
+        {
+            return Err(NOT_ON_EVERY_PATH);
+        }
+        for target in outgoing_edges(block) {
+            match state_at_block.entry(target) {
+                hash_map::Entry::Occupied(entry) => {
+                    if *entry.get() != state {
+                        return Err(NOT_ON_EVERY_PATH);
+                    }
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(state);
+                    queue.push(target);
+                }
+            }
+        }
+    }
+    Ok(state_at_return)
+}
+
+/// Check that all the outputs of each geometry entry point, that are assigned to a stream
+/// (i.e. decorated with `Stream`), are assigned to a stream that the entry point emits
+/// vertices to (through `OpEmitStreamVertex`, or `OpEmitVertex` for stream `0`).
+pub fn check_geometry_streams(sess: &Session, module: &Module) -> super::Result<()> {
+    let output_streams: FxHashMap<Word, u32> = module
+        .annotations
+        .iter()
+        .filter(|inst| {
+            inst.class.opcode == Op::Decorate
+                && inst.operands[1].unwrap_decoration() == Decoration::Stream
+        })
+        .map(|inst| {
+            (
+                inst.operands[0].unwrap_id_ref(),
+                inst.operands[2].unwrap_literal_bit32(),
+            )
+        })
+        .collect();
+    if output_streams.is_empty() {
+        return Ok(());
+    }
+
+    let constants: FxHashMap<Word, u64> = module
+        .types_global_values
+        .iter()
+        .filter(|inst| inst.class.opcode == Op::Constant)
+        .filter_map(|inst| {
+            let value = match inst.operands[0] {
+                Operand::LiteralBit32(value) => value.into(),
+                Operand::LiteralBit64(value) => value,
+                _ => return None,
+            };
+            Some((inst.result_id?, value))
+        })
+        .collect();
+    let func_id_to_idx: FxHashMap<Word, usize> = module
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(index, func)| (func.def_id().unwrap(), index))
+        .collect();
+
+    let mut names = None;
+    let mut any_err = None;
+    for entry in &module.entry_points {
+        if entry.operands[0].unwrap_execution_model() != ExecutionModel::Geometry {
+            continue;
+        }
+
+        // Collect all the streams vertices are emitted to, from all reachable functions.
+        let mut emitted_streams = FxIndexSet::default();
+        let mut has_unknown_stream = false;
+        let mut visited = FxHashSet::default();
+        let mut queue = vec![entry.operands[1].unwrap_id_ref()];
+        while let Some(func_id) = queue.pop() {
+            let Some(&func_idx) = func_id_to_idx.get(&func_id) else {
+                continue;
+            };
+            if !visited.insert(func_idx) {
+                continue;
+            }
+            for inst in module.functions[func_idx].all_inst_iter() {
+                match inst.class.opcode {
+                    Op::FunctionCall => queue.push(inst.operands[0].unwrap_id_ref()),
+                    Op::EmitVertex => {
+                        emitted_streams.insert(0);
+                    }
+                    Op::EmitStreamVertex => {
+                        match constants.get(&inst.operands[0].unwrap_id_ref()) {
+                            Some(&stream) => {
+                                emitted_streams.insert(stream);
+                            }
+                            None => has_unknown_stream = true,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if has_unknown_stream {
+            continue;
+        }
+
+        let entry_name = entry.operands[2].unwrap_literal_string();
+        for interface_var in &entry.operands[3..] {
+            let interface_var = interface_var.unwrap_id_ref();
+            let Some(&stream) = output_streams.get(&interface_var) else {
+                continue;
+            };
+            if emitted_streams.contains(&u64::from(stream)) {
+                continue;
+            }
+
+            let names = names.get_or_insert_with(|| get_names(module));
+            let mut err = sess.dcx().struct_err(format!(
+                "output `{}` of geometry entry point `{entry_name}` is assigned to stream {stream}, \
+                 but no vertices are ever emitted to that stream",
+                get_name(names, interface_var)
+            ));
+            if emitted_streams.is_empty() {
+                err.note("no vertices are emitted to any stream");
+            } else {
+                let mut emitted_streams: Vec<_> = emitted_streams.iter().collect();
+                emitted_streams.sort();
+                err.note(format!(
+                    "vertices are only emitted to {}",
+                    emitted_streams
+                        .into_iter()
+                        .map(|stream| format!("stream {stream}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            any_err = Some(err.emit());
+        }
+    }
+    match any_err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Add the `GeometryStreams` and `TransformFeedback` capabilities, if the module uses
+/// multiple geometry streams (`Stream` decorations, `OpEmitStreamVertex`, etc.) or
+/// transform feedback (the `Xfb` execution mode, `XfbBuffer`/`XfbStride` decorations).
+pub fn add_geometry_stream_and_xfb_capabilities(module: &mut Module) {
+    let mut needs_geometry_streams = false;
+    let mut needs_transform_feedback = module
+        .execution_modes
+        .iter()
+        .any(|inst| inst.operands[1].unwrap_execution_mode() == ExecutionMode::Xfb);
+    for inst in &module.annotations {
+        if inst.class.opcode != Op::Decorate {
+            continue;
+        }
+        match inst.operands[1].unwrap_decoration() {
+            Decoration::Stream => needs_geometry_streams = true,
+            Decoration::XfbBuffer | Decoration::XfbStride => needs_transform_feedback = true,
+            _ => {}
+        }
+    }
+    needs_geometry_streams |= module
+        .functions
+        .iter()
+        .flat_map(|func| func.all_inst_iter())
+        .any(|inst| {
+            matches!(
+                inst.class.opcode,
+                Op::EmitStreamVertex | Op::EndStreamPrimitive
+            )
+        });
+
+    let existing_capabilities: FxHashSet<_> = module
+        .capabilities
+        .iter()
+        .map(|inst| inst.operands[0].unwrap_capability())
+        .collect();
+    let required_capabilities = [
+        (needs_geometry_streams, Capability::GeometryStreams),
+        (needs_transform_feedback, Capability::TransformFeedback),
+    ];
+    for (needed, cap) in required_capabilities {
+        if needed && !existing_capabilities.contains(&cap) {
+            module.capabilities.push(Instruction::new(
+                Op::Capability,
+                None,
+                None,
+                vec![Operand::Capability(cap)],
+            ));
+        }
+    }
+}
+
 /// Remove type-related capabilities that are not required by any types in the module.
 ///
 /// This function specifically targets Int8, Int16, Int64, Float16, and Float64 capabilities,
@@ -365,3 +732,268 @@ pub fn remove_non_uniform_decorations(_sess: &Session, module: &mut Module) -> s
     }
     Ok(())
 }
+
+/// Add the capabilities (and extension) required by descriptor indexing, i.e.
+/// `RuntimeDescriptorArray` for runtime arrays of descriptors (e.g. `RuntimeArray`
+/// or `DescriptorTable` entry-point parameters), and the `*ArrayNonUniformIndexing`
+/// capabilities for the kinds of descriptors accessed through `NonUniform` indices.
+///
+/// `NonUniform` is also propagated from descriptor pointers to the pointers derived
+/// from them, and to the descriptors loaded from them, as e.g. image instructions
+/// require their image operand itself to be decorated.
+///
+/// Indexing descriptors with a `NonUniform` index also adds `ShaderNonUniform`, so this
+/// must run before `remove_non_uniform_decorations`, which would otherwise remove the
+/// `NonUniform` decorations when `ShaderNonUniform` wasn't explicitly enabled.
+pub fn add_descriptor_indexing_capabilities(module: &mut Module) {
+    let type_defs: FxHashMap<Word, &Instruction> = module
+        .types_global_values
+        .iter()
+        .filter_map(|inst| Some((inst.result_id?, inst)))
+        .collect();
+
+    // Returns the element type of an array of descriptors (if `ty` is one).
+    let descriptor_array_element = |storage_class, ty| {
+        let array = type_defs.get(&ty)?;
+        if !matches!(array.class.opcode, Op::TypeArray | Op::TypeRuntimeArray) {
+            return None;
+        }
+        let element = type_defs[&array.operands[0].unwrap_id_ref()];
+        let is_descriptor = match element.class.opcode {
+            Op::TypeImage
+            | Op::TypeSampler
+            | Op::TypeSampledImage
+            | Op::TypeAccelerationStructureKHR => storage_class == StorageClass::UniformConstant,
+            Op::TypeStruct => {
+                matches!(
+                    storage_class,
+                    StorageClass::Uniform | StorageClass::StorageBuffer
+                )
+            }
+            _ => false,
+        };
+        is_descriptor.then_some((array.class.opcode, element))
+    };
+
+    // Descriptor array variables, and the types of their elements.
+    let mut needs_runtime_descriptor_array = false;
+    let mut descriptor_array_vars = FxHashMap::default();
+    for inst in &module.types_global_values {
+        if inst.class.opcode != Op::Variable {
+            continue;
+        }
+        let storage_class = inst.operands[0].unwrap_storage_class();
+        let Some(ptr_ty) = type_defs.get(&inst.result_type.unwrap()) else {
+            continue;
+        };
+        if ptr_ty.class.opcode != Op::TypePointer {
+            continue;
+        }
+        if let Some((array_op, element)) =
+            descriptor_array_element(storage_class, ptr_ty.operands[1].unwrap_id_ref())
+        {
+            needs_runtime_descriptor_array |= array_op == Op::TypeRuntimeArray;
+            descriptor_array_vars.insert(inst.result_id.unwrap(), (storage_class, element));
+        }
+    }
+    if descriptor_array_vars.is_empty() {
+        return;
+    }
+
+    let mut non_uniform: FxHashSet<Word> = module
+        .annotations
+        .iter()
+        .filter(|inst| {
+            inst.class.opcode == Op::Decorate
+                && inst.operands[1].unwrap_decoration() == Decoration::NonUniform
+        })
+        .map(|inst| inst.operands[0].unwrap_id_ref())
+        .collect();
+    let originally_non_uniform = non_uniform.clone();
+
+    let mut required_capabilities = FxIndexSet::default();
+    if needs_runtime_descriptor_array {
+        required_capabilities.insert(Capability::RuntimeDescriptorArray);
+    }
+    let is_descriptor_type = |ty: Word| {
+        type_defs.get(&ty).is_some_and(|ty| {
+            matches!(
+                ty.class.opcode,
+                Op::TypeImage
+                    | Op::TypeSampler
+                    | Op::TypeSampledImage
+                    | Op::TypeAccelerationStructureKHR
+            )
+        })
+    };
+    for func in &module.functions {
+        for inst in func.all_inst_iter() {
+            let Some(result_id) = inst.result_id else {
+                continue;
+            };
+            let propagates_non_uniform = match inst.class.opcode {
+                Op::AccessChain
+                | Op::InBoundsAccessChain
+                | Op::PtrAccessChain
+                | Op::InBoundsPtrAccessChain => {
+                    let base = inst.operands[0].unwrap_id_ref();
+                    if let Some(&(storage_class, element)) = descriptor_array_vars.get(&base) {
+                        let index_is_non_uniform = inst
+                            .operands
+                            .get(1)
+                            .is_some_and(|index| non_uniform.contains(&index.unwrap_id_ref()));
+                        if index_is_non_uniform || non_uniform.contains(&result_id) {
+                            non_uniform.insert(result_id);
+                            required_capabilities.insert(Capability::ShaderNonUniform);
+                            required_capabilities
+                                .extend(non_uniform_indexing_capability(storage_class, element));
+                        }
+                    }
+                    non_uniform.contains(&base)
+                }
+                Op::CopyObject => non_uniform.contains(&inst.operands[0].unwrap_id_ref()),
+                Op::Load => {
+                    is_descriptor_type(inst.result_type.unwrap())
+                        && non_uniform.contains(&inst.operands[0].unwrap_id_ref())
+                }
+                Op::SampledImage => inst
+                    .operands
+                    .iter()
+                    .any(|operand| non_uniform.contains(&operand.unwrap_id_ref())),
+                _ => false,
+            };
+            if propagates_non_uniform {
+                non_uniform.insert(result_id);
+            }
+        }
+    }
+
+    let mut new_non_uniform: Vec<_> = non_uniform
+        .difference(&originally_non_uniform)
+        .copied()
+        .collect();
+    new_non_uniform.sort_unstable();
+    module
+        .annotations
+        .extend(new_non_uniform.into_iter().map(|id| {
+            Instruction::new(
+                Op::Decorate,
+                None,
+                None,
+                vec![
+                    Operand::IdRef(id),
+                    Operand::Decoration(Decoration::NonUniform),
+                ],
+            )
+        }));
+
+    let existing_capabilities: FxHashSet<_> = module
+        .capabilities
+        .iter()
+        .map(|inst| inst.operands[0].unwrap_capability())
+        .collect();
+    let mut added_any_capability = false;
+    for cap in required_capabilities {
+        if !existing_capabilities.contains(&cap) {
+            module.capabilities.push(Instruction::new(
+                Op::Capability,
+                None,
+                None,
+                vec![Operand::Capability(cap)],
+            ));
+            added_any_capability = true;
+        }
+    }
+
+    // NOTE `SPV_EXT_descriptor_indexing` was made core in SPIR-V 1.5.
+    let ext = "SPV_EXT_descriptor_indexing";
+    let needs_ext = module.header.as_ref().unwrap().version() < (1, 5);
+    let has_ext = module
+        .extensions
+        .iter()
+        .any(|inst| inst.operands[0].unwrap_literal_string() == ext);
+    if added_any_capability && needs_ext && !has_ext {
+        module.extensions.push(Instruction::new(
+            Op::Extension,
+            None,
+            None,
+            vec![Operand::LiteralString(ext.to_string())],
+        ));
+    }
+
+    fn non_uniform_indexing_capability(
+        storage_class: StorageClass,
+        element: &Instruction,
+    ) -> Option<Capability> {
+        Some(match element.class.opcode {
+            Op::TypeImage => {
+                let dim = element.operands[1].unwrap_dim();
+                let is_storage = element.operands[5].unwrap_literal_bit32() == 2;
+                match (dim, is_storage) {
+                    (Dim::DimSubpassData, _) => Capability::InputAttachmentArrayNonUniformIndexing,
+                    (Dim::DimBuffer, false) => {
+                        Capability::UniformTexelBufferArrayNonUniformIndexing
+                    }
+                    (Dim::DimBuffer, true) => Capability::StorageTexelBufferArrayNonUniformIndexing,
+                    (_, false) => Capability::SampledImageArrayNonUniformIndexing,
+                    (_, true) => Capability::StorageImageArrayNonUniformIndexing,
+                }
+            }
+            Op::TypeStruct if storage_class == StorageClass::Uniform => {
+                Capability::UniformBufferArrayNonUniformIndexing
+            }
+            Op::TypeStruct => Capability::StorageBufferArrayNonUniformIndexing,
+            Op::TypeSampler | Op::TypeSampledImage => {
+                Capability::SampledImageArrayNonUniformIndexing
+            }
+            // NOTE acceleration structures don't have their own capability.
+            _ => return None,
+        })
+    }
+}
+
+/// Replace the type operands of `OpCooperativeMatrixLengthKHR` with `OpUndef`s
+/// of those types, as SPIR-T doesn't support types as operands of instructions
+/// in functions (undone by `cooperative_matrix_length_undefs_to_types`).
+pub fn cooperative_matrix_length_types_to_undefs(module: &mut Module) {
+    let mut type_to_undef = FxHashMap::default();
+    let header = module.header.as_mut().unwrap();
+    let mut new_undefs = vec![];
+    for func in &mut module.functions {
+        for inst in func.all_inst_iter_mut() {
+            if inst.class.opcode != Op::CooperativeMatrixLengthKHR {
+                continue;
+            }
+            let ty = inst.operands[0].unwrap_id_ref();
+            let undef = *type_to_undef.entry(ty).or_insert_with(|| {
+                let id = header.bound;
+                header.bound += 1;
+                new_undefs.push(Instruction::new(Op::Undef, Some(ty), Some(id), vec![]));
+                id
+            });
+            inst.operands[0] = Operand::IdRef(undef);
+        }
+    }
+    module.types_global_values.extend(new_undefs);
+}
+
+/// Undo `cooperative_matrix_length_types_to_undefs`, i.e. replace `OpUndef`
+/// operands of `OpCooperativeMatrixLengthKHR` with their types.
+pub fn cooperative_matrix_length_undefs_to_types(module: &mut Module) {
+    let undef_types: FxHashMap<Word, Word> = module
+        .types_global_values
+        .iter()
+        .filter(|inst| inst.class.opcode == Op::Undef)
+        .map(|inst| (inst.result_id.unwrap(), inst.result_type.unwrap()))
+        .collect();
+    for func in &mut module.functions {
+        for inst in func.all_inst_iter_mut() {
+            if inst.class.opcode != Op::CooperativeMatrixLengthKHR {
+                continue;
+            }
+            if let Some(&ty) = undef_types.get(&inst.operands[0].unwrap_id_ref()) {
+                inst.operands[0] = Operand::IdRef(ty);
+            }
+        }
+    }
+}
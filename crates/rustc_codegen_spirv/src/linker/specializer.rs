@@ -1028,6 +1028,10 @@ struct Match<'a> {
     /// `StorageClassPat::Var(i)` (currently `i` is always `0`, aka `StorageClassPat::S`).
     storage_class_var_found: SmallIntMap<[SmallVec<[InferOperand; 2]>; 1]>,
 
+    /// `storage_class_concrete_found[..]` holds all the `InferOperand`s matched by
+    /// `StorageClassPat::Concrete(_)`, alongside the storage class they must equal.
+    storage_class_concrete_found: SmallVec<[(InferOperand, StorageClass); 1]>,
+
     /// `ty_var_found[i][..]` holds all the `InferOperand`s matched by
     /// `TyPat::Var(i)` (currently `i` is always `0`, aka `TyPat::T`).
     ty_var_found: SmallIntMap<[SmallVec<[InferOperand; 4]>; 1]>,
@@ -1048,6 +1052,7 @@ impl<'a> Match<'a> {
         let Match {
             ambiguous,
             storage_class_var_found,
+            storage_class_concrete_found,
             ty_var_found,
             index_composite_ty_var_found,
             ty_list_var_found,
@@ -1059,6 +1064,7 @@ impl<'a> Match<'a> {
                 .get_mut_or_default(i)
                 .extend(other_found);
         }
+        storage_class_concrete_found.extend(other.storage_class_concrete_found);
         for (i, other_found) in other.ty_var_found {
             ty_var_found.get_mut_or_default(i).extend(other_found);
         }
@@ -1079,6 +1085,7 @@ impl<'a> Match<'a> {
         let Match {
             ambiguous,
             storage_class_var_found,
+            storage_class_concrete_found,
             ty_var_found,
             index_composite_ty_var_found,
             ty_list_var_found,
@@ -1092,6 +1099,7 @@ impl<'a> Match<'a> {
                 .map_or(&[][..], |xs| &xs[..]);
             self_found.retain(|x| other_found.contains(x));
         }
+        storage_class_concrete_found.retain(|x| other.storage_class_concrete_found.contains(x));
         for (i, self_found) in ty_var_found {
             let other_found = other.ty_var_found.get(i).map_or(&[][..], |xs| &xs[..]);
             self_found.retain(|x| other_found.contains(x));
@@ -1137,6 +1145,7 @@ impl<'a> Match<'a> {
             let Self {
                 ambiguous,
                 storage_class_var_found,
+                storage_class_concrete_found,
                 ty_var_found,
                 index_composite_ty_var_found,
                 ty_list_var_found,
@@ -1146,6 +1155,17 @@ impl<'a> Match<'a> {
             list.entries(debug_var_found(storage_class_var_found, &move |operand| {
                 operand.display_with_infer_cx(cx)
             }));
+            list.entries(storage_class_concrete_found.iter().map(
+                move |(operand, storage_class)| {
+                    FmtBy(move |f| {
+                        write!(
+                            f,
+                            "{} = {storage_class:?}",
+                            operand.display_with_infer_cx(cx)
+                        )
+                    })
+                },
+            ));
             list.entries(debug_var_found(ty_var_found, &move |operand| {
                 operand.display_with_infer_cx(cx)
             }));
@@ -1219,6 +1239,12 @@ impl<'a, S: Specialization> InferCx<'a, S> {
                     .push(storage_class);
                 m
             }
+            &StorageClassPat::Concrete(concrete) => {
+                let mut m = Match::default();
+                m.storage_class_concrete_found
+                    .push((storage_class, concrete));
+                m
+            }
         }
     }
 
@@ -1704,7 +1730,11 @@ impl<'a, S: Specialization> InferCx<'a, S> {
             };
 
             let ty_operands_idx = match ty_opcode {
-                Op::TypeArray | Op::TypeRuntimeArray | Op::TypeVector | Op::TypeMatrix => 0,
+                Op::TypeArray
+                | Op::TypeRuntimeArray
+                | Op::TypeVector
+                | Op::TypeMatrix
+                | Op::TypeCooperativeMatrixKHR => 0,
                 Op::TypeStruct => match idx {
                     Operand::IdRef(id) => {
                         *self.specializer.int_consts.get(id).unwrap_or_else(|| {
@@ -1739,6 +1769,7 @@ impl<'a, S: Specialization> InferCx<'a, S> {
             ambiguous: _,
 
             storage_class_var_found,
+            storage_class_concrete_found,
             ty_var_found,
             index_composite_ty_var_found,
             ty_list_var_found,
@@ -1751,6 +1782,13 @@ impl<'a, S: Specialization> InferCx<'a, S> {
             }
         }
 
+        for (found, concrete) in storage_class_concrete_found {
+            self.equate_infer_operands(
+                found,
+                InferOperand::Concrete(CopyOperand::StorageClass(concrete)),
+            )?;
+        }
+
         for (i, found) in ty_var_found {
             let mut found = found.into_iter();
             if let Some(first) = found.next() {
@@ -8,6 +8,7 @@ use spirt::{
     EntityDefs, ExportKey, Exportee, Module, Type, TypeDef, TypeKind, TypeOrConst, Value, cfg, spv,
 };
 use std::fmt::Write as _;
+use std::rc::Rc;
 
 /// Replace our custom extended instruction `Abort`s with standard `OpReturn`s,
 /// but only in entry-points (and only before CFG structurization).
@@ -23,6 +24,7 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
     enum Strategy {
         Unreachable,
         DebugPrintf { inputs: bool, backtrace: bool },
+        AbortRecord { inputs: bool },
     }
     let abort_strategy = linker_options.abort_strategy.as_ref().map(|s| {
         if s == "unreachable" {
@@ -37,6 +39,12 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
                 return Strategy::DebugPrintf { inputs, backtrace };
             }
         }
+        if let Some(s) = s.strip_prefix("abort-record") {
+            let (inputs, s) = s.strip_prefix("+inputs").map_or((false, s), |s| (true, s));
+            if s.is_empty() {
+                return Strategy::AbortRecord { inputs };
+            }
+        }
         panic!("unknown `--abort-strategy={s}");
     });
 
@@ -82,7 +90,9 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
 
         let debug_printf_context_fmt_str;
         let mut debug_printf_context_inputs = SmallVec::<[_; 4]>::new();
-        if let Some(Strategy::DebugPrintf { inputs, .. }) = abort_strategy {
+        if let Some(Strategy::DebugPrintf { inputs, .. } | Strategy::AbortRecord { inputs }) =
+            abort_strategy
+        {
             let mut fmt = String::new();
 
             match entry_point_imms[..] {
@@ -261,10 +271,14 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
                     Some(Strategy::Unreachable) => {
                         terminator.kind = cfg::ControlInstKind::Unreachable;
                     }
-                    Some(Strategy::DebugPrintf {
-                        inputs: _,
-                        backtrace,
-                    }) => {
+                    Some(Strategy::DebugPrintf { .. } | Strategy::AbortRecord { .. }) => {
+                        let backtrace = matches!(
+                            abort_strategy,
+                            Some(Strategy::DebugPrintf {
+                                backtrace: true,
+                                ..
+                            })
+                        );
                         let const_kind = |v: Value| match v {
                             Value::Const(ct) => &cx[ct].kind,
                             _ => unreachable!(),
@@ -296,6 +310,40 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
                                 kind: ConstKind::SpvStringLiteralForExtInst(s),
                             })
                         };
+                        let mk_const_u32 = |x| {
+                            cx.intern(ConstDef {
+                                attrs: Default::default(),
+                                ty: cx.intern(TypeDef {
+                                    attrs: Default::default(),
+                                    kind: TypeKind::SpvInst {
+                                        spv_inst: spv::Inst {
+                                            opcode: wk.OpTypeInt,
+                                            imms: [
+                                                spv::Imm::Short(wk.LiteralInteger, 32),
+                                                spv::Imm::Short(wk.LiteralInteger, 0),
+                                            ]
+                                            .into_iter()
+                                            .collect(),
+                                        },
+                                        type_and_const_inputs: [].into_iter().collect(),
+                                    },
+                                }),
+                                kind: ConstKind::SpvInst {
+                                    spv_inst_and_const_inputs: Rc::new((
+                                        spv::Inst {
+                                            opcode: wk.OpConstant,
+                                            imms: [spv::Imm::Short(
+                                                wk.LiteralContextDependentNumber,
+                                                x,
+                                            )]
+                                            .into_iter()
+                                            .collect(),
+                                        },
+                                        [].into_iter().collect(),
+                                    )),
+                                },
+                            })
+                        };
 
                         let mut current_debug_src_loc = None;
                         let mut call_stack = SmallVec::<[_; 8]>::new();
@@ -335,7 +383,10 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
                                         current_debug_src_loc = callsite_debug_src_loc;
                                     }
                                 }
-                                CustomInst::Abort { .. } => {}
+                                CustomInst::Abort { .. }
+                                | CustomInst::QuadAllKHR { .. }
+                                | CustomInst::QuadAnyKHR { .. }
+                                | CustomInst::ExecutionMode { .. } => {}
                             }
                         }
 
@@ -347,6 +398,43 @@ pub fn convert_custom_aborts_to_unstructured_returns_in_entry_points(
                                 .map(|(&fmt_str, args)| (&cx[const_str(fmt_str)], args))
                                 .unwrap_or_default();
 
+                        // Leave the rest to `abort_record::lower_abort_records_to_buffer`,
+                        // which turns these markers into writes to the abort record buffer.
+                        if let Some(Strategy::AbortRecord { .. }) = abort_strategy {
+                            // NOTE see the FIXME in `fmt_dbg_src_loc` below for the `+ 1`.
+                            let (file, line, col) = current_debug_src_loc
+                                .map_or(("", 0, 0), |(file, line, col)| (file, line, col + 1));
+
+                            let abort_inst_def = &mut func_def_body.data_insts[abort_inst];
+                            abort_inst_def.form = cx.intern(DataInstFormDef {
+                                kind: DataInstKind::SpvExtInst {
+                                    ext_set: cx.intern(
+                                        crate::linker::abort_record::ABORT_RECORD_EXT_INST_SET,
+                                    ),
+                                    inst: 0,
+                                },
+                                output_type: cx[abort_inst_def.form].output_type,
+                            });
+                            abort_inst_def.inputs = [
+                                Value::Const(mk_const_str(const_str(abort_kind))),
+                                Value::Const(mk_const_str(cx.intern(file))),
+                                Value::Const(mk_const_u32(line)),
+                                Value::Const(mk_const_u32(col)),
+                                Value::Const(mk_const_str(cx.intern(message_debug_printf_fmt_str))),
+                                Value::Const(mk_const_str(
+                                    cx.intern(&debug_printf_context_fmt_str[..]),
+                                )),
+                                Value::Const(mk_const_u32(message_debug_printf_args.len() as u32)),
+                            ]
+                            .into_iter()
+                            .chain(message_debug_printf_args.iter().copied())
+                            .chain(debug_printf_context_inputs.iter().copied())
+                            .collect();
+
+                            // Avoid removing the instruction we just replaced.
+                            continue;
+                        }
+
                         let fmt_dbg_src_loc = |(file, line, col)| {
                             // FIXME(eddyb) figure out what is going on with
                             // these column number conventions, below is a
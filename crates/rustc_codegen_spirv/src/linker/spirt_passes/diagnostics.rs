@@ -2,6 +2,9 @@ use crate::custom_decorations::{
     CustomDecoration, SpanRegenerator, SrcLocDecoration, ZombieDecoration,
 };
 use crate::custom_insts::{self, CustomInst, CustomOp};
+use crate::link_cache::DiagRecorder;
+use crate::linker::zombies;
+use rustc_codegen_spirv_types::ZombieReport;
 use rustc_data_structures::fx::FxIndexSet;
 use rustc_errors::EmissionGuarantee;
 use rustc_session::Session;
@@ -26,12 +29,14 @@ pub(crate) fn report_diagnostics(
     sess: &Session,
     linker_options: &crate::linker::Options,
     module: &Module,
+    warnings: &DiagRecorder,
 ) -> Result<(), ReportedDiagnostics> {
     let cx = &module.cx();
 
     let mut reporter = DiagnosticReporter {
         sess,
         linker_options,
+        warnings,
 
         cx,
         custom_ext_inst_set: cx.intern(&custom_insts::CUSTOM_EXT_INST_SET[..]),
@@ -48,6 +53,10 @@ pub(crate) fn report_diagnostics(
         span_regen: SpanRegenerator::new_spirt(sess.source_map(), module),
         overall_result: Ok(()),
         any_spirt_bugs: false,
+
+        zombie_report: (linker_options.zombie_report.is_some()
+            && !linker_options.early_report_zombies)
+            .then(ZombieReport::default),
     };
     for (export_key, exportee) in &module.exports {
         assert_eq!(reporter.use_stack.len(), 0);
@@ -73,6 +82,10 @@ pub(crate) fn report_diagnostics(
         exportee.inner_visit_with(&mut reporter);
     }
 
+    if let (Some(output), Some(report)) = (&linker_options.zombie_report, &reporter.zombie_report) {
+        zombies::write_zombie_report(sess, output, report);
+    }
+
     reporter
         .overall_result
         .map_err(|rustc_errors_guarantee| ReportedDiagnostics {
@@ -146,6 +159,7 @@ fn try_decode_custom_decoration<'a, D: CustomDecoration<'a>>(
 struct DiagnosticReporter<'a> {
     sess: &'a Session,
     linker_options: &'a crate::linker::Options,
+    warnings: &'a DiagRecorder,
 
     cx: &'a Context,
 
@@ -165,6 +179,10 @@ struct DiagnosticReporter<'a> {
     span_regen: SpanRegenerator<'a>,
     overall_result: crate::linker::Result<()>,
     any_spirt_bugs: bool,
+
+    /// Only present if requested via `--zombie-report` (and zombies are
+    /// reported here, i.e. `--no-early-report-zombies` is also in effect).
+    zombie_report: Option<ZombieReport>,
 }
 
 enum UseOrigin<'a> {
@@ -309,6 +327,21 @@ impl UseOrigin<'_> {
         span_regen: &mut SpanRegenerator<'_>,
         err: &mut rustc_errors::Diag<'_, G>,
     ) {
+        let (note, span) = self.describe(cx, span_regen);
+        err.span_note(span, note);
+    }
+
+    fn is_inlined_call_frame(&self) -> bool {
+        matches!(
+            self,
+            Self::IntraFunc {
+                special_func: Some(SpecialFunc::Inlined { .. }),
+                ..
+            }
+        )
+    }
+
+    fn describe(&self, cx: &Context, span_regen: &mut SpanRegenerator<'_>) -> (String, Span) {
         let wk = &super::SpvSpecWithExtras::get().well_known;
 
         let name_from_attrs = |attrs: AttrSet, kind| {
@@ -371,7 +404,7 @@ impl UseOrigin<'_> {
         };
 
         let span = self.to_rustc_span(cx, span_regen).unwrap_or(DUMMY_SP);
-        err.span_note(span, note);
+        (note, span)
     }
 }
 
@@ -422,8 +455,30 @@ impl DiagnosticReporter<'_> {
                 .sess
                 .dcx()
                 .struct_span_err(def_span, reason.to_string());
+            let mut report_entry = self.zombie_report.is_some().then(|| {
+                zombies::new_zombie_report_entry(
+                    &reason,
+                    current_def.map(|def| match def {
+                        UseOrigin::Global { kind, .. } => kind.to_string(),
+                        UseOrigin::IntraFunc { .. } => "function".to_string(),
+                    }),
+                    zombies::span_to_report_location(self.sess, def_span),
+                )
+            });
             for use_origin in use_stack_for_def.iter().rev() {
-                use_origin.note(self.cx, &mut self.span_regen, &mut err);
+                let (note, span) = use_origin.describe(self.cx, &mut self.span_regen);
+                if let Some(report_entry) = &mut report_entry {
+                    zombies::push_zombie_use_frame(
+                        report_entry,
+                        note.clone(),
+                        zombies::span_to_report_location(self.sess, span),
+                        use_origin.is_inlined_call_frame(),
+                    );
+                }
+                err.span_note(span, note);
+            }
+            if let (Some(report), Some(report_entry)) = (&mut self.zombie_report, report_entry) {
+                report.zombies.push(report_entry);
             }
             self.overall_result = Err(err.emit());
         }
@@ -474,6 +529,8 @@ impl DiagnosticReporter<'_> {
                     for use_origin in use_stack_for_def.iter().rev() {
                         use_origin.note(self.cx, &mut self.span_regen, &mut warn);
                     }
+                    self.warnings.record(self.sess, &warn);
+                    warn.emit();
                 }
             }
             self.any_spirt_bugs = matches!(level, DiagLevel::Bug(_));
@@ -683,7 +740,10 @@ impl<'a> Visitor<'a> for DiagnosticReporter<'a> {
                                     _ => unreachable!(),
                                 }
                             }
-                            CustomInst::Abort { .. } => {}
+                            CustomInst::Abort { .. }
+                            | CustomInst::QuadAllKHR { .. }
+                            | CustomInst::QuadAnyKHR { .. }
+                            | CustomInst::ExecutionMode { .. } => {}
                         },
                     }
                 }
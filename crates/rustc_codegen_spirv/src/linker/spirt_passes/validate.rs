@@ -296,14 +296,37 @@ impl Validator<'_> {
         // HACK(eddyb) even if this seems wasteful in its allocation of
         // strings, they should only happen once each per module, and
         // also it wouldn't be hard to switch to some "small str" crate.
-        let int_or_float = |type_name: &str, cap_name: &str| {
+        let has_cap = |cap_name: &str| {
+            self.spv_spec_caps
+                .lookup(cap_name)
+                .is_some_and(|cap| self.module_spv_dialect.capabilities.contains(&cap.into()))
+        };
+        let int_or_float = |type_name: &str, width: u32, cap_name: &str| {
+            // NOTE 16-bit types can also be used for storage only (i.e.
+            // loads/stores and conversions, as enforced by `spirv-val`), with
+            // any of the `SPV_KHR_16bit_storage` capabilities.
+            let storage_only_16bit = width == 16
+                && [
+                    "StorageBuffer16BitAccess",
+                    "UniformAndStorageBuffer16BitAccess",
+                    "StoragePushConstant16",
+                    "StorageInputOutput16",
+                ]
+                .into_iter()
+                .any(has_cap);
+
             // FIXME(eddyb) find a consistent style between all the error messages
             // (mentioning `OpCapability` seems unfortunate, for example).
             match self.spv_spec_caps.lookup(cap_name).map(u32::from) {
                 None => Err(format!("`{type_name}` type unsupported in SPIR-V")),
-                Some(cap) if !self.module_spv_dialect.capabilities.contains(&cap) => Err(format!(
-                    "`{type_name}` type used without `OpCapability {cap_name}`"
-                )),
+                Some(cap)
+                    if !self.module_spv_dialect.capabilities.contains(&cap)
+                        && !storage_only_16bit =>
+                {
+                    Err(format!(
+                        "`{type_name}` type used without `OpCapability {cap_name}`"
+                    ))
+                }
                 Some(_) => Ok(()),
             }
             .map_err(|msg| Diag::err([msg.into()]))
@@ -315,13 +338,14 @@ impl Validator<'_> {
                 let signed = signedness != 0;
                 int_or_float(
                     &format!("{}{width}", if signed { "i" } else { "u" }),
+                    width,
                     &format!("Int{width}"),
                 )?;
             }
             [spv::Imm::Short(_, width)]
                 if spv_inst.opcode == self.wk.OpTypeFloat && width != 32 =>
             {
-                int_or_float(&format!("f{width}"), &format!("Float{width}"))?;
+                int_or_float(&format!("f{width}"), width, &format!("Float{width}"))?;
             }
             _ => {}
         }
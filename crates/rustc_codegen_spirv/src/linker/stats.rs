@@ -0,0 +1,222 @@
+//! Collection of the statistics written by `--link-stats=FILE` (for the exact
+//! format, see `rustc_codegen_spirv_types::LinkStats`).
+
+use super::get_name;
+use super::ipo::CallGraph;
+use rspirv::dr::Module;
+use rspirv::spirv::{Op, Word};
+use rustc_codegen_spirv_types::{
+    EntryPointStats, InlinedCallsStats, InliningStats, LinkPassStats, LinkStats, ModuleCounts,
+    OutputModuleStats,
+};
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
+use rustc_data_structures::profiling::VerboseTimingGuard;
+use rustc_session::Session;
+use std::cell::RefCell;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct LinkStatsCollector {
+    start: Instant,
+    stats: RefCell<LinkStats>,
+}
+
+fn duration_to_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl LinkStatsCollector {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            stats: RefCell::default(),
+        }
+    }
+
+    pub fn record_link_cache_hit(&self) {
+        self.stats.borrow_mut().link_cache_hit = true;
+    }
+
+    pub fn record_link_cache_entry_point_hit(&self, entry_name: &str) {
+        self.stats
+            .borrow_mut()
+            .link_cache_entry_point_hits
+            .push(entry_name.to_string());
+    }
+
+    pub fn record_output_module(&self, output_module: OutputModuleStats) {
+        self.stats.borrow_mut().output_modules.push(output_module);
+    }
+
+    /// Record the inlining decisions made by `inline::inline` (`module` being
+    /// its result, i.e. after inlining, in order to count remaining calls).
+    pub(super) fn record_inlining(
+        &self,
+        module: &Module,
+        id_to_name: &FxHashMap<Word, &str>,
+        inlined_callee_to_cause_counts: &FxIndexMap<Word, FxIndexMap<&'static str, usize>>,
+    ) {
+        let mut remaining_calls = FxIndexMap::<Word, usize>::default();
+        for inst in module.all_inst_iter() {
+            if inst.class.opcode == Op::FunctionCall {
+                *remaining_calls
+                    .entry(inst.operands[0].unwrap_id_ref())
+                    .or_default() += 1;
+            }
+        }
+
+        let dont_inline_funcs: FxHashMap<Word, bool> = module
+            .functions
+            .iter()
+            .map(|func| (func.def_id().unwrap(), super::inline::has_dont_inline(func)))
+            .collect();
+
+        let callees = inlined_callee_to_cause_counts
+            .keys()
+            .chain(remaining_calls.keys())
+            .copied()
+            .collect::<indexmap::IndexSet<_>>();
+        self.stats
+            .borrow_mut()
+            .inlining
+            .extend(callees.into_iter().map(|callee_id| {
+                InliningStats {
+                    callee: get_name(id_to_name, callee_id).into_owned(),
+                    inlined_calls: inlined_callee_to_cause_counts
+                        .get(&callee_id)
+                        .into_iter()
+                        .flatten()
+                        .map(|(&cause, &count)| InlinedCallsStats {
+                            cause: cause.to_string(),
+                            count,
+                        })
+                        .collect(),
+                    remaining_calls: remaining_calls.get(&callee_id).copied().unwrap_or(0),
+                    dont_inline: dont_inline_funcs.get(&callee_id).copied().unwrap_or(false),
+                }
+            }));
+    }
+
+    pub fn finish(self) -> LinkStats {
+        let mut stats = self.stats.into_inner();
+        stats.total_wall_time_ms = duration_to_ms(self.start.elapsed());
+        stats
+    }
+
+    pub fn write_to_file(self, sess: &Session, path: &Path) {
+        let stats = self.finish();
+        let result = std::fs::File::create(path)
+            .map(std::io::BufWriter::new)
+            .and_then(|file| {
+                rustc_codegen_spirv_types::serde_json::to_writer_pretty(file, &stats)
+                    .map_err(std::io::Error::from)
+            });
+        if let Err(e) = result {
+            sess.dcx()
+                .struct_warn("failed to write link stats")
+                .with_note(format!("file `{}`", path.display()))
+                .with_note(format!("I/O error: {e:#}"))
+                .emit();
+        }
+    }
+}
+
+pub fn module_counts(module: &Module) -> ModuleCounts {
+    let mut counts = ModuleCounts {
+        instructions: module.all_inst_iter().count(),
+        functions: module.functions.len(),
+        ..ModuleCounts::default()
+    };
+    for inst in &module.types_global_values {
+        match inst.class.opcode {
+            Op::Variable => counts.global_variables += 1,
+            Op::TypeCooperativeMatrixKHR => counts.types += 1,
+            op if rspirv::grammar::reflect::is_type(op) => counts.types += 1,
+            op if rspirv::grammar::reflect::is_constant(op) => counts.constants += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+pub fn entry_point_stats(module: &Module) -> Vec<EntryPointStats> {
+    let call_graph = CallGraph::collect(module);
+    let func_instructions: Vec<usize> = module
+        .functions
+        .iter()
+        .map(|func| func.all_inst_iter().count())
+        .collect();
+
+    module
+        .entry_points
+        .iter()
+        .zip(&call_graph.entry_points)
+        .map(|(entry, &entry_func_idx)| {
+            let mut reachable = vec![false; module.functions.len()];
+            let mut queue = vec![entry_func_idx];
+            while let Some(func_idx) = queue.pop() {
+                if !std::mem::replace(&mut reachable[func_idx], true) {
+                    queue.extend(&call_graph.callees[func_idx]);
+                }
+            }
+            let reachable_funcs = || (0..reachable.len()).filter(|&i| reachable[i]);
+            EntryPointStats {
+                name: entry.operands[2].unwrap_literal_string().to_string(),
+                reachable_functions: reachable_funcs().count(),
+                reachable_function_instructions: reachable_funcs()
+                    .map(|i| func_instructions[i])
+                    .sum(),
+            }
+        })
+        .collect()
+}
+
+/// Combination of `sess.timer(name)`, and (with `--link-stats`) recording
+/// the wall time and effects on `ModuleCounts` of the pass named `name`.
+pub struct PassTimer<'a> {
+    _timer: VerboseTimingGuard<'a>,
+    stats: Option<(&'a LinkStatsCollector, Instant, LinkPassStats)>,
+}
+
+impl<'a> PassTimer<'a> {
+    pub fn start(
+        sess: &'a Session,
+        link_stats: Option<&'a LinkStatsCollector>,
+        name: &'static str,
+        module: Option<&Module>,
+    ) -> Self {
+        Self {
+            _timer: sess.timer(name),
+            stats: link_stats.map(|link_stats| {
+                let pass_stats = LinkPassStats {
+                    name: name.to_string(),
+                    output_module: None,
+                    wall_time_ms: 0.0,
+                    before: module.map(module_counts),
+                    after: None,
+                };
+                (link_stats, Instant::now(), pass_stats)
+            }),
+        }
+    }
+
+    /// Mark this pass as running on a specific output module (only relevant
+    /// after the module is split per entry-point).
+    pub fn for_output_module(mut self, output_module: Option<&std::ffi::OsStr>) -> Self {
+        if let Some((_, _, pass_stats)) = &mut self.stats {
+            pass_stats.output_module =
+                output_module.map(|name| name.to_string_lossy().into_owned());
+        }
+        self
+    }
+
+    // NOTE this consumes `self` to ensure `sess.timer` also ends here.
+    pub fn finish(self, module: Option<&Module>) {
+        if let Some((link_stats, start, mut pass_stats)) = self.stats {
+            pass_stats.wall_time_ms = duration_to_ms(start.elapsed());
+            pass_stats.after = module.map(module_counts);
+            link_stats.stats.borrow_mut().passes.push(pass_stats);
+        }
+    }
+}
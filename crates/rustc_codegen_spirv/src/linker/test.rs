@@ -1,4 +1,7 @@
-use super::{LinkResult, link};
+use super::stats::{LinkStatsCollector, entry_point_stats, module_counts};
+use super::{LinkOutput, LinkResult, link};
+use crate::link_cache::LinkCache;
+use rspirv::binary::Assemble;
 use rspirv::dr::Module;
 use rustc_errors::registry::Registry;
 use rustc_session::CompilerIO;
@@ -71,6 +74,28 @@ fn link_with_linker_opts(
     binaries: &[&[u8]],
     opts: &crate::linker::Options,
 ) -> Result<Module, PrettyString> {
+    link_with_linker_opts_and_stats(binaries, opts, None)
+}
+
+fn link_with_linker_opts_and_stats(
+    binaries: &[&[u8]],
+    opts: &crate::linker::Options,
+    link_stats: Option<&LinkStatsCollector>,
+) -> Result<Module, PrettyString> {
+    link_with_linker_opts_stats_and_cache(binaries, opts, link_stats, None).map(|output| {
+        match output.result {
+            LinkResult::SingleModule(m) => *m,
+            LinkResult::MultipleModules { .. } => unreachable!(),
+        }
+    })
+}
+
+fn link_with_linker_opts_stats_and_cache(
+    binaries: &[&[u8]],
+    opts: &crate::linker::Options,
+    link_stats: Option<&LinkStatsCollector>,
+    link_cache: Option<&LinkCache>,
+) -> Result<LinkOutput, PrettyString> {
     let modules = binaries.iter().cloned().map(load).collect::<Vec<_>>();
 
     // A threadsafe buffer for writing.
@@ -161,6 +186,9 @@ fn link_with_linker_opts(
             // the removals in https://github.com/rust-lang/rust/pull/102992.
             sess.psess = {
                 let source_map = sess.psess.clone_source_map();
+                // NOTE the `SourceMap` would otherwise be empty, which breaks
+                // emitting diagnostics with (even dummy) spans, e.g. warnings.
+                source_map.new_source_file(FileName::Custom("linker-test".into()), String::new());
 
                 let emitter = rustc_errors::emitter::HumanEmitter::new(
                     rustc_errors::AutoStream::new(Box::new(buf), rustc_errors::ColorChoice::Never),
@@ -190,13 +218,11 @@ fn link_with_linker_opts(
                     OutputTypes::new(&[]),
                 ),
                 Default::default(),
+                link_stats,
+                link_cache,
             );
             assert_eq!(sess.dcx().has_errors(), res.as_ref().err().copied());
-            res.map(|res| match res {
-                LinkResult::SingleModule(m) => *m,
-                LinkResult::MultipleModules { .. } => unreachable!(),
-            })
-            .map_err(|_guar| ())
+            res.map_err(|_guar| ())
         })
     })
     .map_err(|_fatal| ())
@@ -303,6 +329,148 @@ fn not_a_lib_extra_exports() {
     without_header_eq(result, expect);
 }
 
+#[test]
+fn lib_named_exports() {
+    let a = assemble_spirv(
+        r#"OpCapability Linkage
+            OpMemoryModel Logical OpenCL
+            OpDecorate %1 LinkageAttributes "foo" Export
+            OpDecorate %2 LinkageAttributes "bar" Export
+            %3 = OpTypeFloat 32
+            %1 = OpVariable %3 Uniform
+            %2 = OpVariable %3 Uniform"#,
+    );
+
+    let result = link_with_linker_opts(
+        &[&a],
+        &crate::linker::Options {
+            compact_ids: true,
+            keep_link_export_names: vec!["foo".to_string()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let expect = r#"OpCapability Linkage
+        OpMemoryModel Logical OpenCL
+        OpDecorate %1 LinkageAttributes "foo" Export
+        %2 = OpTypeFloat 32
+        %1 = OpVariable %2 Uniform"#;
+    without_header_eq(result, expect);
+}
+
+/// Overwrites the SPIR-V version in the header of an assembled module.
+fn with_version(mut spirv: Vec<u8>, (major, minor): (u8, u8)) -> Vec<u8> {
+    spirv[4..8].copy_from_slice(&u32::from_le_bytes([0, minor, major, 0]).to_le_bytes());
+    spirv
+}
+
+#[test]
+fn lib_imported_func() {
+    // The `extern` function `add_one`, called by `main`, is only defined by
+    // the (precompiled, and older) SPIR-V library.
+    let a = with_version(
+        assemble_spirv(
+            r#"OpCapability Linkage
+            OpMemoryModel Logical OpenCL
+            OpDecorate %1 LinkageAttributes "add_one" Import
+            OpDecorate %2 LinkageAttributes "main" Export
+            %3 = OpTypeInt 32 0
+            %4 = OpTypeFunction %3 %3
+            %5 = OpConstant %3 41
+            %6 = OpTypeFunction %3
+            %1 = OpFunction %3 None %4
+            %7 = OpFunctionParameter %3
+            OpFunctionEnd
+            %2 = OpFunction %3 None %6
+            %8 = OpLabel
+            %9 = OpFunctionCall %3 %1 %5
+            OpReturnValue %9
+            OpFunctionEnd"#,
+        ),
+        (1, 3),
+    );
+
+    let lib = with_version(
+        assemble_spirv(
+            r#"OpCapability Linkage
+            OpMemoryModel Logical OpenCL
+            OpDecorate %1 LinkageAttributes "add_one" Export
+            %2 = OpTypeInt 32 0
+            %3 = OpTypeFunction %2 %2
+            %4 = OpConstant %2 1
+            %1 = OpFunction %2 DontInline %3
+            %5 = OpFunctionParameter %2
+            %6 = OpLabel
+            %7 = OpIAdd %2 %5 %4
+            OpReturnValue %7
+            OpFunctionEnd"#,
+        ),
+        (1, 0),
+    );
+
+    let result = link_with_linker_opts(
+        &[&a, &lib],
+        &crate::linker::Options {
+            compact_ids: true,
+            keep_link_export_names: vec!["main".to_string()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(result.header.as_ref().unwrap().version(), (1, 3));
+    let expect = r#"OpCapability Linkage
+        OpMemoryModel Logical OpenCL
+        OpDecorate %1 LinkageAttributes "main" Export
+        %2 = OpTypeInt 32 0
+        %3 = OpTypeFunction %2
+        %4 = OpTypeFunction %2 %2
+        %5 = OpConstant %2 1
+        %6 = OpConstant %2 41
+        %1 = OpFunction %2 None %3
+        %7 = OpLabel
+        %8 = OpFunctionCall %2 %9 %6
+        OpReturnValue %8
+        OpFunctionEnd
+        %9 = OpFunction %2 DontInline %4
+        %10 = OpFunctionParameter %2
+        %11 = OpLabel
+        %12 = OpIAdd %2 %10 %5
+        OpReturnValue %12
+        OpFunctionEnd"#;
+    without_header_eq(result, expect);
+}
+
+#[test]
+fn lib_newer_version() {
+    let a = with_version(
+        assemble_spirv(
+            r#"OpCapability Linkage
+            OpMemoryModel Logical OpenCL
+            OpDecorate %1 LinkageAttributes "foo" Import
+            %2 = OpTypeFloat 32
+            %1 = OpVariable %2 Uniform"#,
+        ),
+        (1, 0),
+    );
+
+    let lib = with_version(
+        assemble_spirv(
+            r#"OpCapability Linkage
+            OpMemoryModel Logical OpenCL
+            OpDecorate %1 LinkageAttributes "foo" Export
+            %2 = OpTypeFloat 32
+            %1 = OpVariable %2 Uniform"#,
+        ),
+        (1, 3),
+    );
+
+    let result = assemble_and_link(&[&a, &lib]);
+    assert_eq!(
+        result.err().as_deref(),
+        Some("error: cannot link SPIR-V v1.3 module into SPIR-V v1.0 output")
+    );
+}
+
 #[test]
 fn unresolved_symbol() {
     let a = assemble_spirv(
@@ -657,3 +825,691 @@ fn names_and_decorations() {
 
     without_header_eq(result, expect);
 }
+
+#[test]
+fn replace_global_ptr_params_with_indices() {
+    // NOTE only the index into the runtime array differs between the
+    // two calls, so the struct field index is kept as a constant in `%13`.
+    let module = load(&assemble_spirv(
+        r#"OpCapability Shader
+            OpMemoryModel Logical Simple
+            OpEntryPoint GLCompute %1 "main"
+            %2 = OpTypeVoid
+            %3 = OpTypeFunction %2
+            %4 = OpTypeInt 32 0
+            %5 = OpConstant %4 0
+            %6 = OpConstant %4 1
+            %7 = OpTypeRuntimeArray %4
+            %8 = OpTypeStruct %7
+            %9 = OpTypePointer StorageBuffer %8
+            %10 = OpTypePointer StorageBuffer %4
+            %11 = OpTypeFunction %2 %10
+            %12 = OpVariable %9 StorageBuffer
+            %13 = OpFunction %2 DontInline %11
+            %14 = OpFunctionParameter %10
+            %15 = OpLabel
+            OpStore %14 %6
+            OpReturn
+            OpFunctionEnd
+            %1 = OpFunction %2 None %3
+            %16 = OpLabel
+            %17 = OpAccessChain %10 %12 %5 %5
+            %18 = OpFunctionCall %2 %13 %17
+            %19 = OpAccessChain %10 %12 %5 %6
+            %20 = OpFunctionCall %2 %13 %19
+            OpReturn
+            OpFunctionEnd"#,
+    ));
+
+    let result = super::param_weakening::replace_global_ptr_params_with_indices(
+        module,
+        crate::codegen_cx::InlineMode::Hints,
+    );
+
+    let expect = r#"OpCapability Shader
+        OpMemoryModel Logical Simple
+        OpEntryPoint GLCompute %1 "main"
+        %2 = OpTypeVoid
+        %3 = OpTypeFunction %2
+        %4 = OpTypeInt 32 0
+        %5 = OpConstant %4 0
+        %6 = OpConstant %4 1
+        %7 = OpTypeRuntimeArray %4
+        %8 = OpTypeStruct %7
+        %9 = OpTypePointer StorageBuffer %8
+        %10 = OpTypePointer StorageBuffer %4
+        %11 = OpTypeFunction %2 %10
+        %12 = OpVariable %9 StorageBuffer
+        %22 = OpTypeFunction %2 %4
+        %13 = OpFunction %2 DontInline %22
+        %21 = OpFunctionParameter %4
+        %15 = OpLabel
+        %14 = OpAccessChain %10 %12 %5 %21
+        OpStore %14 %6
+        OpReturn
+        OpFunctionEnd
+        %1 = OpFunction %2 None %3
+        %16 = OpLabel
+        %17 = OpAccessChain %10 %12 %5 %5
+        %18 = OpFunctionCall %2 %13 %5
+        %19 = OpAccessChain %10 %12 %5 %6
+        %20 = OpFunctionCall %2 %13 %6
+        OpReturn
+        OpFunctionEnd"#;
+
+    without_header_eq(result, expect);
+}
+
+/// Common prefix of the `replace_global_ptr_params_with_indices_*` tests, with
+/// `%12` a buffer of `%4` (`u32`) elements, and `%13` the type of functions
+/// taking a pointer to one such element.
+const GLOBAL_PTR_PARAMS_PRELUDE: &str = r#"OpCapability Shader
+            OpMemoryModel Logical Simple
+            OpEntryPoint GLCompute %1 "main"
+            %2 = OpTypeVoid
+            %3 = OpTypeFunction %2
+            %4 = OpTypeInt 32 0
+            %5 = OpConstant %4 0
+            %6 = OpConstant %4 1
+            %7 = OpTypeRuntimeArray %4
+            %8 = OpTypeStruct %7
+            %9 = OpTypePointer StorageBuffer %8
+            %10 = OpTypePointer StorageBuffer %4
+            %11 = OpTypeFunction %2 %10
+            %12 = OpVariable %9 StorageBuffer"#;
+
+#[track_caller]
+fn replace_global_ptr_params_with_indices_eq(
+    inline_mode: crate::codegen_cx::InlineMode,
+    input: &str,
+    expected: &str,
+) {
+    let module = load(&assemble_spirv(input));
+    let result =
+        super::param_weakening::replace_global_ptr_params_with_indices(module, inline_mode);
+    without_header_eq(result, expected);
+}
+
+#[test]
+fn replace_global_ptr_params_with_indices_different_globals() {
+    // NOTE the pointers passed by the two calls are into different buffers,
+    // so the parameter can't be replaced with indices into either of them.
+    let module = format!(
+        r#"{GLOBAL_PTR_PARAMS_PRELUDE}
+            %13 = OpVariable %9 StorageBuffer
+            %14 = OpFunction %2 DontInline %11
+            %15 = OpFunctionParameter %10
+            %16 = OpLabel
+            OpStore %15 %6
+            OpReturn
+            OpFunctionEnd
+            %1 = OpFunction %2 None %3
+            %17 = OpLabel
+            %18 = OpAccessChain %10 %12 %5 %5
+            %19 = OpFunctionCall %2 %14 %18
+            %20 = OpAccessChain %10 %13 %5 %5
+            %21 = OpFunctionCall %2 %14 %20
+            OpReturn
+            OpFunctionEnd"#
+    );
+    replace_global_ptr_params_with_indices_eq(
+        crate::codegen_cx::InlineMode::Hints,
+        &module,
+        &module,
+    );
+}
+
+#[test]
+fn replace_global_ptr_params_with_indices_struct_index_divergence() {
+    // NOTE struct field indices must be constants, so callers disagreeing on
+    // which field they pass a pointer to prevents replacing the parameter.
+    let module = r#"OpCapability Shader
+            OpMemoryModel Logical Simple
+            OpEntryPoint GLCompute %1 "main"
+            %2 = OpTypeVoid
+            %3 = OpTypeFunction %2
+            %4 = OpTypeInt 32 0
+            %5 = OpConstant %4 0
+            %6 = OpConstant %4 1
+            %7 = OpTypeStruct %4 %4
+            %8 = OpTypePointer StorageBuffer %7
+            %9 = OpTypePointer StorageBuffer %4
+            %10 = OpTypeFunction %2 %9
+            %11 = OpVariable %8 StorageBuffer
+            %12 = OpFunction %2 DontInline %10
+            %13 = OpFunctionParameter %9
+            %14 = OpLabel
+            OpStore %13 %6
+            OpReturn
+            OpFunctionEnd
+            %1 = OpFunction %2 None %3
+            %15 = OpLabel
+            %16 = OpAccessChain %9 %11 %5
+            %17 = OpFunctionCall %2 %12 %16
+            %18 = OpAccessChain %9 %11 %6
+            %19 = OpFunctionCall %2 %12 %18
+            OpReturn
+            OpFunctionEnd"#;
+    replace_global_ptr_params_with_indices_eq(crate::codegen_cx::InlineMode::Hints, module, module);
+}
+
+#[test]
+fn replace_global_ptr_params_with_indices_see_through() {
+    // NOTE `%18` has a single call site, so it's left alone (and later inlined),
+    // but its own pointer parameter can still be seen through (along with the
+    // access chains on top of it), to replace the parameter of `%14`.
+    let module = format!(
+        r#"{GLOBAL_PTR_PARAMS_PRELUDE}
+            %13 = OpTypePointer StorageBuffer %7
+            %14 = OpFunction %2 DontInline %11
+            %15 = OpFunctionParameter %10
+            %16 = OpLabel
+            OpStore %15 %6
+            OpReturn
+            OpFunctionEnd
+            %17 = OpTypeFunction %2 %13
+            %18 = OpFunction %2 None %17
+            %19 = OpFunctionParameter %13
+            %20 = OpLabel
+            %21 = OpAccessChain %10 %19 %5
+            %22 = OpFunctionCall %2 %14 %21
+            %23 = OpAccessChain %10 %19 %6
+            %24 = OpFunctionCall %2 %14 %23
+            OpReturn
+            OpFunctionEnd
+            %1 = OpFunction %2 None %3
+            %25 = OpLabel
+            %26 = OpAccessChain %13 %12 %5
+            %27 = OpFunctionCall %2 %18 %26
+            OpReturn
+            OpFunctionEnd"#
+    );
+
+    let expect = r#"OpCapability Shader
+        OpMemoryModel Logical Simple
+        OpEntryPoint GLCompute %1 "main"
+        %2 = OpTypeVoid
+        %3 = OpTypeFunction %2
+        %4 = OpTypeInt 32 0
+        %5 = OpConstant %4 0
+        %6 = OpConstant %4 1
+        %7 = OpTypeRuntimeArray %4
+        %8 = OpTypeStruct %7
+        %9 = OpTypePointer StorageBuffer %8
+        %10 = OpTypePointer StorageBuffer %4
+        %11 = OpTypeFunction %2 %10
+        %12 = OpVariable %9 StorageBuffer
+        %13 = OpTypePointer StorageBuffer %7
+        %17 = OpTypeFunction %2 %13
+        %29 = OpTypeFunction %2 %4
+        %14 = OpFunction %2 DontInline %29
+        %28 = OpFunctionParameter %4
+        %16 = OpLabel
+        %15 = OpAccessChain %10 %12 %5 %28
+        OpStore %15 %6
+        OpReturn
+        OpFunctionEnd
+        %18 = OpFunction %2 None %17
+        %19 = OpFunctionParameter %13
+        %20 = OpLabel
+        %21 = OpAccessChain %10 %19 %5
+        %22 = OpFunctionCall %2 %14 %5
+        %23 = OpAccessChain %10 %19 %6
+        %24 = OpFunctionCall %2 %14 %6
+        OpReturn
+        OpFunctionEnd
+        %1 = OpFunction %2 None %3
+        %25 = OpLabel
+        %26 = OpAccessChain %13 %12 %5
+        %27 = OpFunctionCall %2 %18 %26
+        OpReturn
+        OpFunctionEnd"#;
+
+    replace_global_ptr_params_with_indices_eq(
+        crate::codegen_cx::InlineMode::Hints,
+        &module,
+        expect,
+    );
+}
+
+/// Module calling `%13` (with `callee_control` as its `FunctionControl`, and
+/// `callee_stores` copies of `OpStore` as its body) twice, passing pointers to
+/// different elements of the `%12` buffer, and its expected form after the
+/// pointer parameter is replaced with an index.
+fn global_ptr_params_module_and_replaced(
+    callee_control: &str,
+    callee_stores: usize,
+) -> (String, String) {
+    let stores = "OpStore %14 %6\n".repeat(callee_stores);
+    let module = format!(
+        r#"{GLOBAL_PTR_PARAMS_PRELUDE}
+            %13 = OpFunction %2 {callee_control} %11
+            %14 = OpFunctionParameter %10
+            %15 = OpLabel
+            {stores}OpReturn
+            OpFunctionEnd
+            %1 = OpFunction %2 None %3
+            %16 = OpLabel
+            %17 = OpAccessChain %10 %12 %5 %5
+            %18 = OpFunctionCall %2 %13 %17
+            %19 = OpAccessChain %10 %12 %5 %6
+            %20 = OpFunctionCall %2 %13 %19
+            OpReturn
+            OpFunctionEnd"#
+    );
+    let replaced = format!(
+        r#"{GLOBAL_PTR_PARAMS_PRELUDE}
+            %22 = OpTypeFunction %2 %4
+            %13 = OpFunction %2 {callee_control} %22
+            %21 = OpFunctionParameter %4
+            %15 = OpLabel
+            %14 = OpAccessChain %10 %12 %5 %21
+            {stores}OpReturn
+            OpFunctionEnd
+            %1 = OpFunction %2 None %3
+            %16 = OpLabel
+            %17 = OpAccessChain %10 %12 %5 %5
+            %18 = OpFunctionCall %2 %13 %5
+            %19 = OpAccessChain %10 %12 %5 %6
+            %20 = OpFunctionCall %2 %13 %6
+            OpReturn
+            OpFunctionEnd"#
+    );
+    (module, replaced)
+}
+
+#[test]
+fn replace_global_ptr_params_with_indices_inline_mode() {
+    use crate::codegen_cx::InlineMode;
+
+    // `#[inline]` functions are left alone (as they get inlined anyway), unless
+    // `InlineMode::Size` ignores the hint (for functions that aren't too small).
+    let (big_inline, big_inline_replaced) = global_ptr_params_module_and_replaced("Inline", 10);
+    replace_global_ptr_params_with_indices_eq(InlineMode::Hints, &big_inline, &big_inline);
+    replace_global_ptr_params_with_indices_eq(InlineMode::Size, &big_inline, &big_inline_replaced);
+
+    // Small functions are always inlined by `InlineMode::Size` (even without
+    // any hints), so they're also left alone, unless `#[inline(never)]`.
+    let (small, small_replaced) = global_ptr_params_module_and_replaced("None", 1);
+    replace_global_ptr_params_with_indices_eq(InlineMode::Hints, &small, &small_replaced);
+    replace_global_ptr_params_with_indices_eq(InlineMode::Size, &small, &small);
+    let (small_dont_inline, small_dont_inline_replaced) =
+        global_ptr_params_module_and_replaced("DontInline", 1);
+    replace_global_ptr_params_with_indices_eq(
+        InlineMode::Size,
+        &small_dont_inline,
+        &small_dont_inline_replaced,
+    );
+}
+
+#[test]
+fn link_stats() {
+    let a = assemble_spirv(
+        r#"OpCapability Shader
+        OpMemoryModel Logical Simple
+        OpEntryPoint GLCompute %1 "main"
+        OpExecutionMode %1 LocalSize 1 1 1
+        OpName %1 "main"
+        OpName %4 "helper"
+        OpName %6 "kept"
+        %2 = OpTypeVoid
+        %3 = OpTypeFunction %2
+        %4 = OpFunction %2 Inline %3
+        %5 = OpLabel
+        OpReturn
+        OpFunctionEnd
+        %6 = OpFunction %2 DontInline %3
+        %7 = OpLabel
+        OpReturn
+        OpFunctionEnd
+        %1 = OpFunction %2 None %3
+        %8 = OpLabel
+        %9 = OpFunctionCall %2 %4
+        %10 = OpFunctionCall %2 %6
+        OpReturn
+        OpFunctionEnd"#,
+    );
+
+    let link_stats = LinkStatsCollector::new();
+    let result = link_with_linker_opts_and_stats(
+        &[&a],
+        &crate::linker::Options::default(),
+        Some(&link_stats),
+    )
+    .unwrap();
+    let stats = link_stats.finish();
+
+    assert!(!stats.link_cache_hit);
+    assert_eq!(stats.passes[0].name, "link_merge");
+    let inline_pass = stats
+        .passes
+        .iter()
+        .find(|pass| pass.name == "link_inline")
+        .unwrap();
+    assert_eq!(inline_pass.output_module, None);
+    assert_eq!(inline_pass.before.unwrap().functions, 3);
+    assert!(inline_pass.after.is_some());
+
+    let inlining = stats
+        .inlining
+        .iter()
+        .map(|inlining| {
+            let inlined_calls = inlining
+                .inlined_calls
+                .iter()
+                .map(|calls| (calls.cause.as_str(), calls.count))
+                .collect::<Vec<_>>();
+            (
+                inlining.callee.as_str(),
+                inlined_calls,
+                inlining.remaining_calls,
+                inlining.dont_inline,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        inlining,
+        [
+            ("helper", vec![("inline hint", 1)], 0, false),
+            ("kept", vec![], 1, true),
+        ]
+    );
+
+    let counts = module_counts(&result);
+    assert_eq!(counts.functions, 2);
+    assert_eq!(counts.types, 2);
+    assert_eq!(counts.global_variables, 0);
+    assert_eq!(counts.instructions, result.all_inst_iter().count());
+
+    let entry_points = entry_point_stats(&result);
+    assert_eq!(entry_points.len(), 1);
+    assert_eq!(entry_points[0].name, "main");
+    assert_eq!(entry_points[0].reachable_functions, 2);
+    assert_eq!(
+        entry_points[0].reachable_function_instructions,
+        result
+            .functions
+            .iter()
+            .map(|func| func.all_inst_iter().count())
+            .sum::<usize>()
+    );
+}
+
+#[test]
+fn link_cache_entry_points() {
+    let dir = std::env::temp_dir().join(format!(
+        "rust-gpu-linker-test-{}-link_cache_entry_points",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let link_cache = LinkCache::with_config_hash(dir.clone(), rustc_hashes::Hash128::new(0));
+
+    // Two entry-points, `a` and `b`, which can be changed independently, and
+    // which can share a `#[inline(never)]` function that has to be inlined.
+    let module = |a_local_size: u32, b_local_size: u32, b_calls_helper: bool| {
+        let b_call = if b_calls_helper {
+            "%9 = OpFunctionCall %8 %10"
+        } else {
+            ""
+        };
+        assemble_spirv(&format!(
+            r#"OpCapability Shader
+            OpMemoryModel Logical Simple
+            OpEntryPoint GLCompute %1 "a"
+            OpEntryPoint GLCompute %2 "b"
+            OpExecutionMode %1 LocalSize {a_local_size} 1 1
+            OpExecutionMode %2 LocalSize {b_local_size} 1 1
+            OpName %10 "helper"
+            %3 = OpTypeVoid
+            %4 = OpTypeFunction %3
+            %5 = OpTypeInt 32 0
+            %6 = OpTypePointer Private %5
+            %7 = OpVariable %6 Private
+            %8 = OpTypeFunction %6
+            %10 = OpFunction %6 DontInline %8
+            %11 = OpLabel
+            OpReturnValue %7
+            OpFunctionEnd
+            %1 = OpFunction %3 None %4
+            %12 = OpLabel
+            OpReturn
+            OpFunctionEnd
+            %2 = OpFunction %3 None %4
+            %13 = OpLabel
+            {b_call}
+            OpReturn
+            OpFunctionEnd"#
+        ))
+    };
+    let opts = crate::linker::Options {
+        module_output_type: crate::codegen_cx::ModuleOutputType::Multiple,
+        ..Default::default()
+    };
+    let link = |module: &[u8]| {
+        let link_stats = LinkStatsCollector::new();
+        let output = link_with_linker_opts_stats_and_cache(
+            &[module],
+            &opts,
+            Some(&link_stats),
+            Some(&link_cache),
+        )
+        .unwrap();
+        let modules = match output.result {
+            LinkResult::SingleModule(_) => unreachable!(),
+            LinkResult::MultipleModules {
+                file_stem_to_entry_name_and_module,
+            } => file_stem_to_entry_name_and_module
+                .into_values()
+                .map(|(entry_name, module)| (entry_name, module.assemble()))
+                .collect::<std::collections::BTreeMap<_, _>>(),
+        };
+        let warnings = rustc_codegen_spirv_types::serde_json::to_string(&output.warnings).unwrap();
+        (
+            link_stats.finish().link_cache_entry_point_hits,
+            modules,
+            warnings,
+        )
+    };
+
+    let (hits, first, warnings) = link(&module(1, 1, false));
+    assert!(hits.is_empty());
+    assert_eq!(warnings, "[]");
+
+    // Only `b` changed, so only `a` can be reused.
+    let (hits, second, _) = link(&module(1, 2, false));
+    assert_eq!(hits, ["a"]);
+    assert_eq!(second["a"], first["a"]);
+    assert_ne!(second["b"], first["b"]);
+
+    // Nothing changed, so both can be reused (without running any passes).
+    let (hits, third, _) = link(&module(1, 2, false));
+    assert_eq!(hits, ["a", "b"]);
+    assert_eq!(third, second);
+
+    // Linking with warnings doesn't cache anything (as they can't be attributed
+    // to individual entry-points), but still returns them (for whole-link caching).
+    let (hits, _, warnings) = link(&module(1, 3, true));
+    assert_eq!(hits, ["a"]);
+    assert!(warnings.contains("`#[inline(never)]` function `helper` has been inlined"));
+    let (hits, _, _) = link(&module(1, 3, true));
+    assert_eq!(hits, ["a"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// NOTE `rspirv` doesn't know `OpTypeCooperativeMatrixKHR` is a type, so this
+// relies on `link::Loader` keeping it in its original position.
+const COOPERATIVE_MATRIX_LENGTH_MODULE: &str = r#"OpCapability Linkage
+            OpCapability Shader
+            OpCapability CooperativeMatrixKHR
+            OpExtension "SPV_KHR_cooperative_matrix"
+            OpMemoryModel Logical Vulkan
+            OpDecorate %1 LinkageAttributes "foo" Export
+            %2 = OpTypeInt 32 0
+            %3 = OpTypeFloat 32
+            %4 = OpConstant %2 3
+            %5 = OpConstant %2 16
+            %6 = OpConstant %2 0
+            %7 = OpTypeCooperativeMatrixKHR %3 %4 %5 %5 %6
+            %8 = OpTypePointer Function %7
+            %9 = OpTypeFunction %2
+            %1 = OpFunction %2 None %9
+            %10 = OpLabel
+            %11 = OpCooperativeMatrixLengthKHR %2 %7
+            %12 = OpCooperativeMatrixLengthKHR %2 %7
+            %13 = OpIAdd %2 %11 %12
+            OpReturnValue %13
+            OpFunctionEnd"#;
+
+#[test]
+fn cooperative_matrix_length_round_trip() {
+    let mut module = load(&assemble_spirv(COOPERATIVE_MATRIX_LENGTH_MODULE));
+
+    super::simple_passes::cooperative_matrix_length_types_to_undefs(&mut module);
+    let with_undefs = COOPERATIVE_MATRIX_LENGTH_MODULE
+        .replace(
+            "%9 = OpTypeFunction %2",
+            "%9 = OpTypeFunction %2\n            %14 = OpUndef %7",
+        )
+        .replace(
+            "OpCooperativeMatrixLengthKHR %2 %7",
+            "OpCooperativeMatrixLengthKHR %2 %14",
+        );
+    without_header_eq(module.clone(), &with_undefs);
+
+    // NOTE the `OpUndef` itself is left for DCE to remove.
+    super::simple_passes::cooperative_matrix_length_undefs_to_types(&mut module);
+    let round_tripped = COOPERATIVE_MATRIX_LENGTH_MODULE.replace(
+        "%9 = OpTypeFunction %2",
+        "%9 = OpTypeFunction %2\n            %14 = OpUndef %7",
+    );
+    without_header_eq(module, &round_tripped);
+}
+
+#[test]
+fn cooperative_matrix_length_through_spirt() {
+    let module = assemble_spirv(COOPERATIVE_MATRIX_LENGTH_MODULE);
+    let result = assemble_and_link(&[&module]).unwrap();
+
+    // NOTE the type operands of `OpCooperativeMatrixLengthKHR` must survive
+    // the SPIR-T passes (and the unused pointer type is removed by DCE).
+    let expect = r#"OpCapability Shader
+            OpCapability Linkage
+            OpCapability CooperativeMatrixKHR
+            OpExtension "SPV_KHR_cooperative_matrix"
+            OpMemoryModel Logical Vulkan
+            OpDecorate %1 LinkageAttributes "foo" Export
+            %2 = OpTypeInt 32 0
+            %3 = OpTypeFunction %2
+            %4 = OpTypeFloat 32
+            %5 = OpConstant %2 3
+            %6 = OpConstant %2 16
+            %7 = OpConstant %2 0
+            %8 = OpTypeCooperativeMatrixKHR %4 %5 %6 %6 %7
+            %1 = OpFunction %2 None %3
+            %9 = OpLabel
+            %10 = OpCooperativeMatrixLengthKHR %2 %8
+            %11 = OpCooperativeMatrixLengthKHR %2 %8
+            %12 = OpIAdd %2 %10 %11
+            OpReturnValue %12
+            OpFunctionEnd"#;
+    without_header_eq(result, expect);
+}
+
+#[test]
+fn rspirv_unsupported_lowering() {
+    use crate::custom_insts::{CUSTOM_EXT_INST_SET, CustomOp, RspirvUnsupportedExecutionMode};
+    use rspirv::dr::{Builder, Operand};
+    use rspirv::spirv::{
+        AddressingModel, Capability, ExecutionMode, ExecutionModel, FunctionControl, MemoryModel,
+    };
+    use spirv_tools::assembler::{self, Assembler};
+
+    // NOTE the input can't be assembled by `spirv-as`, as it rejects unknown
+    // `OpExtInstImport`s, so it's built with `rspirv` instead (like codegen).
+    let mut b = Builder::new();
+    b.set_version(1, 0);
+    b.capability(Capability::Shader);
+    b.memory_model(AddressingModel::Logical, MemoryModel::Simple);
+    let custom = b.ext_inst_import(&CUSTOM_EXT_INST_SET[..]);
+    let void = b.type_void();
+    let void_fn = b.type_function(void, []);
+    let bool_ty = b.type_bool();
+    let u32_ty = b.type_int(32, 0);
+    let modes = [
+        RspirvUnsupportedExecutionMode::QuadDerivativesKHR,
+        RspirvUnsupportedExecutionMode::MaximallyReconvergesKHR,
+    ]
+    .map(|mode| b.constant_bit32(u32_ty, mode as u32));
+    let true_ = b.constant_true(bool_ty);
+    let main = b
+        .begin_function(void, None, FunctionControl::NONE, void_fn)
+        .unwrap();
+    b.begin_block(None).unwrap();
+    for mode in modes {
+        let op = CustomOp::ExecutionMode as u32;
+        b.ext_inst(void, None, custom, op, [Operand::IdRef(mode)])
+            .unwrap();
+    }
+    let all = b
+        .ext_inst(
+            bool_ty,
+            None,
+            custom,
+            CustomOp::QuadAllKHR as u32,
+            [Operand::IdRef(true_)],
+        )
+        .unwrap();
+    b.ext_inst(
+        bool_ty,
+        None,
+        custom,
+        CustomOp::QuadAnyKHR as u32,
+        [Operand::IdRef(all)],
+    )
+    .unwrap();
+    b.ret().unwrap();
+    b.end_function().unwrap();
+    b.entry_point(ExecutionModel::Fragment, main, "main", []);
+    b.execution_mode(main, ExecutionMode::OriginUpperLeft, []);
+
+    let spv_binary = super::rspirv_unsupported::lower(b.module().assemble());
+
+    let actual = assembler::create(None)
+        .disassemble(
+            spv_binary,
+            assembler::DisassembleOptions {
+                no_header: true,
+                indent: false,
+                use_friendly_names: false,
+                comment: false,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+    let expect = r#"OpCapability Shader
+OpCapability QuadControlKHR
+OpExtension "SPV_KHR_quad_control"
+OpExtension "SPV_KHR_maximal_reconvergence"
+OpMemoryModel Logical Simple
+OpEntryPoint Fragment %9 "main"
+OpExecutionMode %9 OriginUpperLeft
+OpExecutionMode %9 QuadDerivativesKHR
+OpExecutionMode %9 MaximallyReconvergesKHR
+%2 = OpTypeVoid
+%3 = OpTypeFunction %2
+%4 = OpTypeBool
+%5 = OpTypeInt 32 0
+%6 = OpConstant %5 5088
+%7 = OpConstant %5 6023
+%8 = OpConstantTrue %4
+%9 = OpFunction %2 None %3
+%10 = OpLabel
+%13 = OpGroupNonUniformQuadAllKHR %4 %8
+%14 = OpGroupNonUniformQuadAnyKHR %4 %13
+OpReturn
+OpFunctionEnd
+"#;
+    pretty_assertions::assert_eq!(PrettyString(actual), PrettyString(expect.to_string()));
+}
@@ -1,10 +1,14 @@
 //! See documentation on `CodegenCx::zombie` for a description of the zombie system.
 
-use super::get_names;
+use super::{ZombieReportOutput, get_names};
 use crate::custom_decorations::{CustomDecoration, SpanRegenerator, ZombieDecoration};
 use crate::custom_insts::{self, CustomOp};
 use rspirv::dr::{Instruction, Module, Operand};
 use rspirv::spirv::{Op, Word};
+use rustc_codegen_spirv_types::{
+    SourceLocation, ZombieFixCategory, ZombieReport, ZombieReportEntry, ZombieRootCause,
+    ZombieUseFrame,
+};
 use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
 use rustc_errors::Diag;
 use rustc_session::Session;
@@ -31,9 +35,23 @@ struct ZombieUse<'a> {
     /// (both `OpLine` and `CustomInst::SetDebugSrcLoc` are supported).
     use_debug_src_loc_inst: Option<&'a Instruction>,
 
+    /// Inlined call frames (see `CustomInst::PushInlinedCallFrame`) active at
+    /// the time of the use, outermost first, which are only present in modules
+    /// that were already inlined (e.g. SPIR-V libraries from other builds).
+    inlined_call_frames: Vec<InlinedCallFrame<'a>>,
+
     origin: UseOrigin,
 }
 
+#[derive(Copy, Clone)]
+struct InlinedCallFrame<'a> {
+    /// `OpString` ID of the name of the inlined callee.
+    callee_name_id: Word,
+
+    /// Active debug "source location" instruction at the inlined call site.
+    callsite_debug_src_loc_inst: Option<&'a Instruction>,
+}
+
 enum UseOrigin {
     GlobalOperandOrResultType,
     IntraFuncOperandOrResultType { parent_func_id: Word },
@@ -97,6 +115,7 @@ impl<'a> Zombies<'a> {
                         .map(|zombie| ZombieUse {
                             used_zombie_id: zombie.id,
                             use_debug_src_loc_inst: debug_src_loc_inst,
+                            inlined_call_frames: vec![],
                             origin: UseOrigin::GlobalOperandOrResultType,
                         })
                         .collect();
@@ -123,11 +142,16 @@ impl<'a> Zombies<'a> {
 
             let mut all_zombie_uses_in_func = vec![];
             let mut debug_src_loc_inst = None;
+            let mut inlined_call_frames = vec![];
             for inst in func.all_inst_iter() {
                 match inst.class.opcode {
                     Op::Line => debug_src_loc_inst = Some(inst),
                     // NOTE(eddyb) each block starts out with cleared debuginfo.
-                    Op::Label | Op::NoLine => debug_src_loc_inst = None,
+                    Op::Label => {
+                        debug_src_loc_inst = None;
+                        inlined_call_frames.clear();
+                    }
+                    Op::NoLine => debug_src_loc_inst = None,
                     Op::ExtInst
                         if Some(inst.operands[0].unwrap_id_ref())
                             == self.custom_ext_inst_set_import =>
@@ -135,7 +159,21 @@ impl<'a> Zombies<'a> {
                         match CustomOp::decode_from_ext_inst(inst) {
                             CustomOp::SetDebugSrcLoc => debug_src_loc_inst = Some(inst),
                             CustomOp::ClearDebugSrcLoc => debug_src_loc_inst = None,
-                            _ => {}
+                            CustomOp::PushInlinedCallFrame => {
+                                inlined_call_frames.push(InlinedCallFrame {
+                                    callee_name_id: inst.operands[2].unwrap_id_ref(),
+                                    callsite_debug_src_loc_inst: debug_src_loc_inst.take(),
+                                });
+                            }
+                            CustomOp::PopInlinedCallFrame => {
+                                if let Some(frame) = inlined_call_frames.pop() {
+                                    debug_src_loc_inst = frame.callsite_debug_src_loc_inst;
+                                }
+                            }
+                            CustomOp::Abort
+                            | CustomOp::QuadAllKHR
+                            | CustomOp::QuadAnyKHR
+                            | CustomOp::ExecutionMode => {}
                         }
                     }
                     _ => {}
@@ -151,6 +189,7 @@ impl<'a> Zombies<'a> {
                         .map(|zombie| ZombieUse {
                             used_zombie_id: zombie.id,
                             use_debug_src_loc_inst: debug_src_loc_inst,
+                            inlined_call_frames: inlined_call_frames.clone(),
                             origin: UseOrigin::IntraFuncOperandOrResultType {
                                 parent_func_id: func_id,
                             },
@@ -184,6 +223,7 @@ impl<'a> Zombies<'a> {
                             ZombieUse {
                                 used_zombie_id: zombie.id,
                                 use_debug_src_loc_inst: debug_src_loc_inst,
+                                inlined_call_frames: inlined_call_frames.clone(),
                                 origin,
                             }
                         }),
@@ -199,6 +239,87 @@ impl<'a> Zombies<'a> {
     }
 }
 
+/// Source location (in the format used by `--zombie-report`) for a `Span`.
+pub(super) fn span_to_report_location(sess: &Session, span: Span) -> Option<SourceLocation> {
+    if span.is_dummy() {
+        return None;
+    }
+    let loc = sess.source_map().lookup_char_pos(span.lo());
+    Some(SourceLocation {
+        file: loc.file.name.prefer_local().to_string(),
+        line: loc.line as u32,
+        column: loc.col_display as u32 + 1,
+    })
+}
+
+/// Helper for building a `ZombieReportEntry`, alongside the diagnostic for a zombie.
+pub(super) fn new_zombie_report_entry(
+    reason: &str,
+    def_kind: Option<String>,
+    location: Option<SourceLocation>,
+) -> ZombieReportEntry {
+    ZombieReportEntry {
+        root_cause: ZombieRootCause {
+            reason: reason.to_string(),
+            def_kind,
+            location,
+        },
+        use_chain: vec![],
+        user_code_location: None,
+        suggested_fix: ZombieFixCategory::from_reason(reason),
+    }
+}
+
+/// Add a `ZombieUseFrame` to `entry`, updating `user_code_location` if needed.
+pub(super) fn push_zombie_use_frame(
+    entry: &mut ZombieReportEntry,
+    description: String,
+    location: Option<SourceLocation>,
+    inlined: bool,
+) {
+    let in_user_code = location
+        .as_ref()
+        .is_some_and(|location| !location.is_in_library_code());
+    if in_user_code && entry.user_code_location.is_none() {
+        entry.user_code_location.clone_from(&location);
+    }
+    entry.use_chain.push(ZombieUseFrame {
+        description,
+        location,
+        inlined,
+        in_user_code,
+    });
+}
+
+/// Write `report` as JSON to `path`, or emit it as a note if there's no `path`.
+pub(super) fn write_zombie_report(
+    sess: &Session,
+    output: &ZombieReportOutput,
+    report: &ZombieReport,
+) {
+    let path = match output {
+        ZombieReportOutput::File(path) => path,
+        ZombieReportOutput::Note => {
+            let json = rustc_codegen_spirv_types::serde_json::to_string_pretty(report).unwrap();
+            sess.dcx().note(format!("zombie report:\n{json}"));
+            return;
+        }
+    };
+    let result = std::fs::File::create(path)
+        .map(std::io::BufWriter::new)
+        .and_then(|file| {
+            rustc_codegen_spirv_types::serde_json::to_writer_pretty(file, report)
+                .map_err(std::io::Error::from)
+        });
+    if let Err(e) = result {
+        sess.dcx()
+            .struct_warn("failed to write zombie report")
+            .with_note(format!("file `{}`", path.display()))
+            .with_note(format!("I/O error: {e:#}"))
+            .emit();
+    }
+}
+
 struct ZombieReporter<'a> {
     sess: &'a Session,
     module: &'a Module,
@@ -222,14 +343,17 @@ impl<'a> ZombieReporter<'a> {
     // If an entry point references a zombie'd value, then the entry point would normally get removed.
     // That's an absolutely horrible experience to debug, though, so instead, create a nice error
     // message containing the stack trace of how the entry point got to the zombie value.
-    fn report_all(mut self) -> super::Result<()> {
+    fn report_all(mut self, mut report: Option<&mut ZombieReport>) -> super::Result<()> {
         let mut result = Ok(());
         // FIXME(eddyb) this loop means that every entry-point can potentially
         // list out all the leaves, but that shouldn't be a huge issue.
         for root_id in super::dce::collect_roots(self.module) {
             if let Some(zombie) = self.zombies.get_zombie_by_id(root_id) {
-                for (_, err) in self.build_errors_keyed_by_leaf_id(zombie) {
+                for (_, (err, report_entry)) in self.build_errors_keyed_by_leaf_id(zombie) {
                     result = Err(err.emit());
+                    if let Some(report) = report.as_deref_mut() {
+                        report.zombies.push(report_entry);
+                    }
                 }
             }
         }
@@ -238,7 +362,7 @@ impl<'a> ZombieReporter<'a> {
 
     fn add_use_note_to_err(
         &mut self,
-        err: &mut Diag<'a>,
+        (err, report_entry): &mut (Diag<'a>, ZombieReportEntry),
         span: Span,
         zombie: Zombie<'_>,
         zombie_use: &ZombieUse<'_>,
@@ -252,29 +376,73 @@ impl<'a> ZombieReporter<'a> {
                     |&name| format!("`{name}`"),
                 )
         };
+        let use_note = |func_desc: &str| match zombie_use.origin {
+            UseOrigin::CallCalleeOperand { .. } => format!("called by {func_desc}"),
+            _ => format!("used from within {func_desc}"),
+        };
+
+        // Notes for the inlined callees the use is in (innermost first), if any,
+        // followed by the function itself (or the global using the zombie).
+        let mut notes = vec![];
+        let mut debug_src_loc_inst = zombie_use.use_debug_src_loc_inst;
+        for frame in zombie_use.inlined_call_frames.iter().rev() {
+            let callee_name = self
+                .module
+                .debug_string_source
+                .iter()
+                .find(|inst| inst.result_id == Some(frame.callee_name_id))
+                .map(|inst| inst.operands[0].unwrap_literal_string());
+            let callee_desc = match callee_name {
+                Some("") | None => "unnamed function".to_string(),
+                Some(name) => format!("`{name}`"),
+            };
+            let note = if notes.is_empty() {
+                use_note(&callee_desc)
+            } else {
+                format!("called by {callee_desc}")
+            };
+            notes.push((note, debug_src_loc_inst, true));
+            debug_src_loc_inst = frame.callsite_debug_src_loc_inst;
+        }
         let note = match zombie_use.origin {
             UseOrigin::GlobalOperandOrResultType => {
                 format!("used by {}", id_to_name(zombie.id, "global"))
             }
-            UseOrigin::IntraFuncOperandOrResultType { parent_func_id } => {
-                format!(
-                    "used from within {}",
-                    id_to_name(parent_func_id, "function")
-                )
+            UseOrigin::IntraFuncOperandOrResultType {
+                parent_func_id: func_id,
             }
-            UseOrigin::CallCalleeOperand { caller_func_id } => {
-                format!("called by {}", id_to_name(caller_func_id, "function"))
+            | UseOrigin::CallCalleeOperand {
+                caller_func_id: func_id,
+            } => {
+                let func_desc = id_to_name(func_id, "function");
+                if notes.is_empty() {
+                    use_note(&func_desc)
+                } else {
+                    format!("called by {func_desc}")
+                }
             }
         };
-        let span = zombie_use
-            .use_debug_src_loc_inst
-            .and_then(|inst| self.span_regen.src_loc_from_debug_inst(inst))
-            .and_then(|src_loc| self.span_regen.src_loc_to_rustc(src_loc))
-            .unwrap_or(span);
-        err.span_note(span, note);
+        notes.push((note, debug_src_loc_inst, false));
+
+        for (note, debug_src_loc_inst, inlined) in notes {
+            let span = debug_src_loc_inst
+                .and_then(|inst| self.span_regen.src_loc_from_debug_inst(inst))
+                .and_then(|src_loc| self.span_regen.src_loc_to_rustc(src_loc))
+                .unwrap_or(span);
+            push_zombie_use_frame(
+                report_entry,
+                note.clone(),
+                span_to_report_location(self.sess, span),
+                inlined,
+            );
+            err.span_note(span, note);
+        }
     }
 
-    fn build_errors_keyed_by_leaf_id(&mut self, zombie: Zombie<'_>) -> FxIndexMap<Word, Diag<'a>> {
+    fn build_errors_keyed_by_leaf_id(
+        &mut self,
+        zombie: Zombie<'_>,
+    ) -> FxIndexMap<Word, (Diag<'a>, ZombieReportEntry)> {
         // FIXME(eddyb) this is a bit inefficient, compared to some kind of
         // "small map", but this is the error path, and being correct is more
         // important here - in particular, we don't want to ignore *any* leaves.
@@ -288,9 +456,23 @@ impl<'a> ZombieReporter<'a> {
         match zombie.kind {
             ZombieKind::Leaf => {
                 let reason = self.span_regen.zombie_for_id(zombie.id).unwrap().reason;
+                // NOTE this is slow, but it's only used in the error case.
+                let def_kind = self
+                    .module
+                    .global_inst_iter()
+                    .chain(self.module.functions.iter().flat_map(|f| f.all_inst_iter()))
+                    .find(|inst| inst.result_id == Some(zombie.id))
+                    .map(|inst| format!("Op{}", inst.class.opname));
                 errors_keyed_by_leaf_id.insert(
                     zombie.id,
-                    self.sess.dcx().struct_span_err(span, reason.to_string()),
+                    (
+                        self.sess.dcx().struct_span_err(span, reason.to_string()),
+                        new_zombie_report_entry(
+                            &reason,
+                            def_kind,
+                            span_to_report_location(self.sess, span),
+                        ),
+                    ),
                 );
             }
             ZombieKind::Uses(zombie_uses) => {
@@ -302,7 +484,7 @@ impl<'a> ZombieReporter<'a> {
                     for (leaf_id, err) in self.build_errors_keyed_by_leaf_id(used_zombie) {
                         use rustc_data_structures::fx::IndexEntry as Entry;
                         match errors_keyed_by_leaf_id.entry(leaf_id) {
-                            Entry::Occupied(_) => err.cancel(),
+                            Entry::Occupied(_) => err.0.cancel(),
                             Entry::Vacant(entry) => {
                                 self.add_use_note_to_err(
                                     entry.insert(err),
@@ -320,7 +502,7 @@ impl<'a> ZombieReporter<'a> {
     }
 }
 
-pub fn report_zombies(sess: &Session, module: &Module) -> super::Result<()> {
+pub fn report_zombies(sess: &Session, opts: &super::Options, module: &Module) -> super::Result<()> {
     let mut zombies = Zombies {
         // FIXME(eddyb) avoid repeating this across different passes/helpers.
         custom_ext_inst_set_import: module
@@ -339,5 +521,10 @@ pub fn report_zombies(sess: &Session, module: &Module) -> super::Result<()> {
     // Note: This is O(n^2).
     while zombies.spread(module) {}
 
-    ZombieReporter::new(sess, module, &zombies).report_all()
+    let mut report = opts.zombie_report.as_ref().map(|_| ZombieReport::default());
+    let result = ZombieReporter::new(sess, module, &zombies).report_all(report.as_mut());
+    if let (Some(output), Some(report)) = (&opts.zombie_report, &report) {
+        write_zombie_report(sess, output, report);
+    }
+    result
 }
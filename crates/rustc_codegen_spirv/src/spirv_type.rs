@@ -3,7 +3,9 @@ use crate::builder_spirv::SpirvValue;
 use crate::codegen_cx::CodegenCx;
 use indexmap::IndexSet;
 use rspirv::dr::Operand;
-use rspirv::spirv::{Decoration, Dim, ImageFormat, StorageClass, Word};
+use rspirv::spirv::{
+    CooperativeMatrixUse, Decoration, Dim, ImageFormat, Scope, StorageClass, Word,
+};
 use rustc_abi::{Align, Size};
 use rustc_data_structures::fx::FxHashMap;
 use rustc_middle::span_bug;
@@ -90,6 +92,15 @@ pub enum SpirvType<'tcx> {
 
     AccelerationStructureKhr,
     RayQueryKhr,
+
+    /// `OpTypeCooperativeMatrixKHR`, from `SPV_KHR_cooperative_matrix`.
+    CooperativeMatrixKhr {
+        component_type: Word,
+        scope: Scope,
+        rows: u32,
+        columns: u32,
+        matrix_use: CooperativeMatrixUse,
+    },
 }
 
 impl SpirvType<'_> {
@@ -193,6 +204,26 @@ impl SpirvType<'_> {
                 cx.emit_global().type_acceleration_structure_khr_id(id)
             }
             Self::RayQueryKhr => cx.emit_global().type_ray_query_khr_id(id),
+            Self::CooperativeMatrixKhr {
+                component_type,
+                scope,
+                rows,
+                columns,
+                matrix_use,
+            } => {
+                // NOTE unlike most types, these operands are constant IDs.
+                let [scope, rows, columns, matrix_use] =
+                    [scope as u32, rows, columns, matrix_use as u32]
+                        .map(|x| cx.constant_u32(def_span, x).def_cx(cx));
+                cx.emit_global().type_cooperative_matrix_khr_id(
+                    id,
+                    component_type,
+                    scope,
+                    rows,
+                    columns,
+                    matrix_use,
+                )
+            }
             Self::SampledImage { image_type } => {
                 cx.emit_global().type_sampled_image_id(id, image_type)
             }
@@ -339,6 +370,7 @@ impl SpirvType<'_> {
             Self::Image { .. }
             | Self::AccelerationStructureKhr
             | Self::RayQueryKhr
+            | Self::CooperativeMatrixKhr { .. }
             | Self::Sampler
             | Self::SampledImage { .. }
             | Self::InterfaceBlock { .. } => Size::from_bytes(4),
@@ -361,6 +393,7 @@ impl SpirvType<'_> {
             Self::Image { .. }
             | Self::AccelerationStructureKhr
             | Self::RayQueryKhr
+            | Self::CooperativeMatrixKhr { .. }
             | Self::Sampler
             | Self::SampledImage { .. }
             | Self::InterfaceBlock { .. } => Align::from_bytes(4).unwrap(),
@@ -386,7 +419,10 @@ impl SpirvType<'_> {
             ),
 
             // Always unsized types
-            Self::InterfaceBlock { .. } | Self::RayQueryKhr | Self::SampledImage { .. } => None,
+            Self::InterfaceBlock { .. }
+            | Self::RayQueryKhr
+            | Self::SampledImage { .. }
+            | Self::CooperativeMatrixKhr { .. } => None,
 
             // Descriptor types
             Self::Image { .. } | Self::AccelerationStructureKhr | Self::Sampler => None,
@@ -455,6 +491,19 @@ impl SpirvType<'_> {
             SpirvType::InterfaceBlock { inner_type } => SpirvType::InterfaceBlock { inner_type },
             SpirvType::AccelerationStructureKhr => SpirvType::AccelerationStructureKhr,
             SpirvType::RayQueryKhr => SpirvType::RayQueryKhr,
+            SpirvType::CooperativeMatrixKhr {
+                component_type,
+                scope,
+                rows,
+                columns,
+                matrix_use,
+            } => SpirvType::CooperativeMatrixKhr {
+                component_type,
+                scope,
+                rows,
+                columns,
+                matrix_use,
+            },
 
             // Only these variants have any slices to arena-allocate.
             SpirvType::Adt {
@@ -644,6 +693,21 @@ impl fmt::Debug for SpirvTypePrinter<'_, '_> {
                 .finish(),
             SpirvType::AccelerationStructureKhr => f.debug_struct("AccelerationStructure").finish(),
             SpirvType::RayQueryKhr => f.debug_struct("RayQuery").finish(),
+            SpirvType::CooperativeMatrixKhr {
+                component_type,
+                scope,
+                rows,
+                columns,
+                matrix_use,
+            } => f
+                .debug_struct("CooperativeMatrix")
+                .field("id", &self.id)
+                .field("component_type", &self.cx.debug_type(component_type))
+                .field("scope", &scope)
+                .field("rows", &rows)
+                .field("columns", &columns)
+                .field("matrix_use", &matrix_use)
+                .finish(),
         };
         {
             let mut debug_stack = DEBUG_STACK.lock().unwrap();
@@ -797,6 +861,17 @@ impl SpirvTypePrinter<'_, '_> {
             }
             SpirvType::AccelerationStructureKhr => f.write_str("AccelerationStructureKhr"),
             SpirvType::RayQueryKhr => f.write_str("RayQuery"),
+            SpirvType::CooperativeMatrixKhr {
+                component_type,
+                scope,
+                rows,
+                columns,
+                matrix_use,
+            } => {
+                f.write_str("CooperativeMatrix<")?;
+                ty(self.cx, stack, f, component_type)?;
+                write!(f, ", {scope:?}, {rows}x{columns}, {matrix_use:?}>")
+            }
         }
     }
 }
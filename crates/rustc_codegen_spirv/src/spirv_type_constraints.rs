@@ -2,7 +2,8 @@
 //! or for inference purposes.
 //!
 //! Only type/storage-class equality is currently handled here, no concrete
-//! type/storage-class constraints, nor anything involving non-type/storage-class
+//! type/storage-class constraints (with a couple of exceptions, see `TyPat::Void`
+//! and `StorageClassPat::Concrete`), nor anything involving non-type/storage-class
 //! operands. While more constraints could be supported, encoding all the possible
 //! rules for them may be challenging.
 //!
@@ -20,7 +21,7 @@
 //! If the "static representation" ends up being required (for performance reasons),
 //! the "dynamic representation" could be generated from it using associated `const`s.
 
-use rspirv::spirv::Op;
+use rspirv::spirv::{Op, StorageClass};
 
 /// Helper trait to allow macros to work uniformly across different pattern types.
 trait Pat {
@@ -38,6 +39,11 @@ pub enum StorageClassPat {
     /// identical storage classes. For convenience, these associated consts are provided:
     /// * `StorageClassPat::S` for `StorageClassPat::Var(0)`
     Var(usize),
+
+    /// One specific storage class: this is used solely for `OpConvertUToPtr`,
+    /// which can only produce `PhysicalStorageBuffer` pointers in shaders,
+    /// and `OpImageTexelPointer`, which always produces `Image` pointers.
+    Concrete(StorageClass),
 }
 
 impl Pat for StorageClassPat {
@@ -184,6 +190,13 @@ pub fn instruction_signatures(op: Op) -> Option<&'static [InstSig<'static>]> {
     // Restrict the names the `pat!` macro can take as pattern constructors.
     mod pat_ctors {
         pub const S: super::StorageClassPat = super::StorageClassPat::S;
+        #[allow(non_upper_case_globals)]
+        pub const PhysicalStorageBuffer: super::StorageClassPat =
+            super::StorageClassPat::Concrete(super::StorageClass::PhysicalStorageBuffer);
+        // Not just `Image`, to avoid conflicting with `TyPat::Image`.
+        #[allow(non_upper_case_globals)]
+        pub const ImageStorageClass: super::StorageClassPat =
+            super::StorageClassPat::Concrete(super::StorageClass::Image);
         // NOTE(eddyb) it would be really nice if we could import `TyPat::{* - Any, Var}`,
         // i.e. all but those two variants.
         pub use super::TyPat::{
@@ -329,7 +342,9 @@ pub fn instruction_signatures(op: Op) -> Option<&'static [InstSig<'static>]> {
             {S} () -> Pointer(S, _) |
             {S} (T) -> Pointer(S, T)
         },
-        Op::ImageTexelPointer => sig! { (Pointer(_, Image(T)), _, _) -> Pointer(_, T) },
+        Op::ImageTexelPointer => sig! {
+            (Pointer(_, Image(T)), _, _) -> Pointer(ImageStorageClass, T)
+        },
         Op::Load => sig! { (Pointer(_, T)) -> T },
         Op::Store => sig! { (Pointer(_, T), T) },
         Op::CopyMemory => sig! { (Pointer(_, T), Pointer(_, T)) },
@@ -424,7 +439,10 @@ pub fn instruction_signatures(op: Op) -> Option<&'static [InstSig<'static>]> {
         | Op::SConvert
         | Op::FConvert => {}
         Op::QuantizeToF16 => sig! { (T) -> T },
-        Op::ConvertPtrToU | Op::SatConvertSToU | Op::SatConvertUToS | Op::ConvertUToPtr => {}
+        Op::ConvertPtrToU | Op::SatConvertSToU | Op::SatConvertUToS => {}
+        // NOTE `OpConvertUToPtr` can produce other kinds of pointers with
+        // the `Addresses` capability, but that's only available to kernels.
+        Op::ConvertUToPtr => sig! { (_) -> Pointer(PhysicalStorageBuffer, _) },
         Op::PtrCastToGeneric | Op::GenericCastToPtr => sig! { (Pointer(_, T)) -> Pointer(_, T) },
         Op::GenericCastToPtrExplicit => sig! { {S} (Pointer(_, T)) -> Pointer(S, T) },
         Op::Bitcast => {}
@@ -776,9 +794,7 @@ pub fn instruction_signatures(op: Op) -> Option<&'static [InstSig<'static>]> {
         | Op::CooperativeMatrixMulAddNV
         | Op::CooperativeMatrixLengthNV => reserved!(SPV_NV_cooperative_matrix),
         // SPV_EXT_fragment_shader_interlock
-        Op::BeginInvocationInterlockEXT | Op::EndInvocationInterlockEXT => {
-            reserved!(SPV_EXT_fragment_shader_interlock);
-        }
+        Op::BeginInvocationInterlockEXT | Op::EndInvocationInterlockEXT => {}
         // SPV_EXT_demote_to_helper_invocation
         Op::DemoteToHelperInvocationEXT | Op::IsHelperInvocationEXT => {
             // NOTE(eddyb) we actually use these despite not being in the standard yet.
@@ -1089,7 +1105,11 @@ pub fn instruction_signatures(op: Op) -> Option<&'static [InstSig<'static>]> {
         | Op::CooperativeMatrixLoadKHR
         | Op::CooperativeMatrixStoreKHR
         | Op::CooperativeMatrixMulAddKHR
-        | Op::CooperativeMatrixLengthKHR => reserved!(SPV_KHR_cooperative_matrix),
+        | Op::CooperativeMatrixLengthKHR => {
+            // NOTE we actually use these (see `spirv_std::cooperative_matrix`),
+            // and the pointer operands of loads/stores need no extra constraints.
+            // reserved!(SPV_KHR_cooperative_matrix)
+        }
         // SPV_QCOM_image_processing
         Op::ImageSampleWeightedQCOM
         | Op::ImageBoxFilterQCOM
@@ -1,5 +1,6 @@
 use crate::attr::{IntrinsicType, SpirvAttribute};
 use crate::builder::libm_intrinsics;
+use crate::custom_insts::RspirvUnsupportedExecutionMode;
 use rspirv::spirv::{BuiltIn, ExecutionMode, ExecutionModel, StorageClass};
 use rustc_data_structures::fx::FxHashMap;
 use rustc_span::symbol::Symbol;
@@ -26,6 +27,10 @@ pub struct Symbols {
     pub binding: Symbol,
     pub location: Symbol,
     pub input_attachment_index: Symbol,
+    pub stream: Symbol,
+    pub xfb_buffer: Symbol,
+    pub xfb_stride: Symbol,
+    pub offset: Symbol,
 
     pub spec_constant: Symbol,
     pub id: Symbol,
@@ -33,6 +38,7 @@ pub struct Symbols {
 
     pub attributes: FxHashMap<Symbol, SpirvAttribute>,
     pub execution_modes: FxHashMap<Symbol, (ExecutionMode, ExecutionModeExtraDim)>,
+    pub rspirv_unsupported_execution_modes: FxHashMap<Symbol, RspirvUnsupportedExecutionMode>,
     pub libm_intrinsics: FxHashMap<Symbol, libm_intrinsics::LibmIntrinsic>,
     pub num_traits_intrinsics: FxHashMap<Symbol, libm_intrinsics::LibmIntrinsic>,
 }
@@ -328,6 +334,15 @@ const EXECUTION_MODES: &[(&str, ExecutionMode, ExecutionModeExtraDim)] = {
     ]
 };
 
+/// Execution modes that `rspirv` can't represent yet, see `RspirvUnsupportedExecutionMode`.
+const RSPIRV_UNSUPPORTED_EXECUTION_MODES: &[(&str, RspirvUnsupportedExecutionMode)] = {
+    use RspirvUnsupportedExecutionMode::*;
+    &[
+        ("quad_derivatives_khr", QuadDerivativesKHR),
+        ("maximally_reconverges_khr", MaximallyReconvergesKHR),
+    ]
+};
+
 impl Symbols {
     fn new() -> Self {
         let builtins = BUILTINS
@@ -356,10 +371,15 @@ impl Symbols {
                 "ray_query",
                 SpirvAttribute::IntrinsicType(IntrinsicType::RayQueryKhr),
             ),
+            (
+                "cooperative_matrix",
+                SpirvAttribute::IntrinsicType(IntrinsicType::CooperativeMatrixKhr),
+            ),
             ("block", SpirvAttribute::Block),
             ("flat", SpirvAttribute::Flat),
             ("invariant", SpirvAttribute::Invariant),
             ("per_primitive_ext", SpirvAttribute::PerPrimitiveExt),
+            ("patch", SpirvAttribute::Patch),
             (
                 "sampled_image",
                 SpirvAttribute::IntrinsicType(IntrinsicType::SampledImage),
@@ -405,6 +425,13 @@ impl Symbols {
             let old = execution_modes.insert(Symbol::intern(key), (mode, dim));
             assert!(old.is_none());
         }
+        let mut rspirv_unsupported_execution_modes = FxHashMap::default();
+        for &(key, mode) in RSPIRV_UNSUPPORTED_EXECUTION_MODES {
+            let key = Symbol::intern(key);
+            assert!(!execution_modes.contains_key(&key));
+            let old = rspirv_unsupported_execution_modes.insert(key, mode);
+            assert!(old.is_none());
+        }
 
         let mut libm_intrinsics = FxHashMap::default();
         for &(a, b) in libm_intrinsics::LIBM_TABLE {
@@ -433,6 +460,10 @@ impl Symbols {
             binding: Symbol::intern("binding"),
             location: Symbol::intern("location"),
             input_attachment_index: Symbol::intern("input_attachment_index"),
+            stream: Symbol::intern("stream"),
+            xfb_buffer: Symbol::intern("xfb_buffer"),
+            xfb_stride: Symbol::intern("xfb_stride"),
+            offset: Symbol::intern("offset"),
 
             spec_constant: Symbol::intern("spec_constant"),
             id: Symbol::intern("id"),
@@ -440,6 +471,7 @@ impl Symbols {
 
             attributes,
             execution_modes,
+            rspirv_unsupported_execution_modes,
             libm_intrinsics,
             num_traits_intrinsics,
         }
@@ -110,6 +110,8 @@ pub enum SpirvBuilderError {
     MissingRustcCodegenSpirvDylib,
     #[error("`rustc_codegen_spirv_location` path '{0}' is not a file")]
     RustcCodegenSpirvDylibDoesNotExist(PathBuf),
+    #[error("SPIR-V library path '{0}' is not a file")]
+    SpirvLibraryDoesNotExist(PathBuf),
     #[error("build failed")]
     BuildFailed,
     #[error(
@@ -145,6 +147,19 @@ pub enum SpirvMetadata {
     Full,
 }
 
+/// Which function calls get inlined, beyond those required to produce legal SPIR-V.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[non_exhaustive]
+pub enum InlineMode {
+    /// Inline calls to `#[inline]` functions **(default)**.
+    #[default]
+    Hints,
+    /// Minimize code size: ignore `#[inline]`, and only inline calls to small functions, or to
+    /// functions with a single call site (never to `#[inline(never)]` ones, unless required).
+    Size,
+}
+
 /// Strategy used to handle Rust `panic!`s in shaders compiled to SPIR-V.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
@@ -173,6 +188,10 @@ pub enum ShaderPanicStrategy {
     ///
     /// ---
     ///
+    /// **Note**: if none of the below is an option (e.g. when running without the
+    /// Validation Layers), see [`SpirvBuilder::shader_log_buffer`] for an alternative,
+    /// which writes the panic messages to a buffer, for the host to decode instead.
+    ///
     /// **Note**: enabling this automatically adds the `SPV_KHR_non_semantic_info`
     /// extension, as `debugPrintf` is from a "non-semantic extended instruction set".
     ///
@@ -242,6 +261,28 @@ pub enum ShaderPanicStrategy {
         print_backtrace: bool,
     },
 
+    /// Like `SilentExit`, but also writing the first panic (of each dispatch) to a
+    /// storage buffer, for the host to read back, and report as an error.
+    ///
+    /// The written "abort record" contains the source location of the `panic!`,
+    /// its message (with any runtime arguments), and the entry-point it's in
+    /// (optionally along with its inputs), and can be decoded on the host using
+    /// [`CompileResult::decode_abort_record`] (or [`AbortRecordInfo::decode`]).
+    ///
+    /// The storage buffer (of `u32` words) is added to any entry-point that can
+    /// panic, and has to be bound by the host at `descriptor_set`/`binding` (see
+    /// [`CompileResult::abort_record`] for the exact binding, and the required size),
+    /// and its first word reset to `0` before each dispatch (or draw).
+    #[cfg_attr(feature = "clap", clap(skip))]
+    AbortRecordThenExit {
+        descriptor_set: u32,
+        binding: u32,
+
+        /// Whether to also record the entry-point inputs (excluding buffers/resources),
+        /// which should uniquely identify the panicking shader invocation.
+        record_inputs: bool,
+    },
+
     /// **Warning**: this is _**unsound**_ (i.e. adds Undefined Behavior to *safe* Rust code)
     ///
     /// This option only exists for testing (hence the unfriendly name it has),
@@ -481,6 +522,9 @@ pub struct SpirvBuilder {
     /// Including metadata significantly increases binary size.
     #[cfg_attr(feature = "clap", arg(long, default_value = "none"))]
     pub spirv_metadata: SpirvMetadata,
+    /// Selects which function calls get inlined, beyond those required to produce legal SPIR-V.
+    #[cfg_attr(feature = "clap", arg(long, default_value = "hints"))]
+    pub inline_mode: InlineMode,
     /// Adds a capability to the SPIR-V module. Checking if a capability is enabled in code can be
     /// done via `#[cfg(target_feature = "TheCapability")]`.
     #[cfg_attr(feature = "clap", arg(long, value_parser=Self::parse_spirv_capability))]
@@ -514,6 +558,31 @@ pub struct SpirvBuilder {
     #[cfg_attr(feature = "clap", clap(skip))]
     pub shader_panic_strategy: ShaderPanicStrategy,
 
+    /// Lower `debug_printf!`s (and `panic!` messages) to records appended to a storage
+    /// buffer at this binding, instead of `debugPrintf` (see [`Self::shader_log_buffer`]).
+    #[cfg_attr(feature = "clap", clap(skip))]
+    pub shader_log_buffer: Option<DescriptorBinding>,
+
+    /// Precompiled SPIR-V modules to link into the shader, resolving `extern` functions
+    /// (see [`Self::link_spirv_library`]).
+    #[cfg_attr(feature = "clap", arg(long = "link-spirv-library"))]
+    pub spirv_libraries: Vec<PathBuf>,
+
+    /// Symbols to keep exported from the resulting SPIR-V module, making it usable as a
+    /// SPIR-V library (see [`Self::keep_link_export`]).
+    #[cfg_attr(feature = "clap", arg(long = "keep-link-export"))]
+    pub link_exports: Vec<String>,
+
+    /// Cache the results of linking (and of `spirv-opt`/`spirv-val`), to skip redoing that
+    /// work on rebuilds with unchanged inputs (see [`Self::link_cache`]). Defaults to false.
+    #[cfg_attr(feature = "clap", arg(long, default_value = "false"))]
+    pub link_cache: bool,
+
+    /// Write link-time statistics (per-pass timings, module counts, inlining decisions and
+    /// output module sizes) as JSON to this file (see [`Self::link_stats`]).
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub link_stats: Option<PathBuf>,
+
     /// spirv-val flags
     #[cfg_attr(feature = "clap", clap(flatten))]
     #[serde(flatten)]
@@ -549,6 +618,7 @@ impl Default for SpirvBuilder {
             deny_warnings: false,
             multimodule: false,
             spirv_metadata: SpirvMetadata::default(),
+            inline_mode: InlineMode::default(),
             capabilities: Vec::new(),
             extensions: Vec::new(),
             extra_args: Vec::new(),
@@ -557,6 +627,11 @@ impl Default for SpirvBuilder {
             toolchain_overwrite: None,
             toolchain_rustc_version: None,
             shader_panic_strategy: ShaderPanicStrategy::default(),
+            shader_log_buffer: None,
+            spirv_libraries: Vec::new(),
+            link_exports: Vec::new(),
+            link_cache: false,
+            link_stats: None,
             validator: ValidatorOptions::default(),
             optimizer: OptimizerOptions::default(),
             shader_crate_features: ShaderCrateFeatures::default(),
@@ -603,6 +678,14 @@ impl SpirvBuilder {
         self
     }
 
+    /// Selects which function calls get inlined, beyond those required to produce legal SPIR-V
+    /// (see [`InlineMode`]), e.g. [`InlineMode::Size`] to reduce the size of large shaders.
+    #[must_use]
+    pub fn inline_mode(mut self, v: InlineMode) -> Self {
+        self.inline_mode = v;
+        self
+    }
+
     /// Adds a capability to the SPIR-V module. Checking if a capability is enabled in code can be
     /// done via `#[cfg(target_feature = "TheCapability")]`.
     #[must_use]
@@ -626,6 +709,75 @@ impl SpirvBuilder {
         self
     }
 
+    /// Lower `debug_printf!`s (and `panic!` messages, with
+    /// [`ShaderPanicStrategy::DebugPrintfThenExit`]) to records appended to a storage buffer
+    /// bound at `descriptor_set`/`binding`, which the host can then decode (with
+    /// [`CompileResult::decode_shader_log`]), instead of relying on the Vulkan Validation
+    /// Layers' `debugPrintf` implementation (which e.g. `wgpu`, or release drivers, may lack).
+    ///
+    /// The buffer has to be bound for all the shaders which print anything, and consists
+    /// of `u32` words: a write cursor (reset it to `0` before reusing the buffer), a scratch
+    /// word, and then the records themselves (records which don't fit are dropped, and
+    /// counted in [`ShaderLog::dropped_words`]).
+    #[must_use]
+    pub fn shader_log_buffer(mut self, descriptor_set: u32, binding: u32) -> Self {
+        self.shader_log_buffer = Some(DescriptorBinding {
+            descriptor_set,
+            binding,
+        });
+        self
+    }
+
+    /// Link a precompiled SPIR-V module (e.g. one produced by another build using
+    /// [`Self::keep_link_export`], or by `glslang`/DXC) into the shader.
+    ///
+    /// Functions the module exports (via `LinkageAttributes "name" Export`) can be
+    /// called from the shader crate, by declaring them in an `extern` block:
+    /// ```rust,ignore
+    /// unsafe extern "C" {
+    ///     fn denoise(color: glam::Vec4) -> glam::Vec4;
+    /// }
+    /// ```
+    ///
+    /// **Note**: the SPIR-V module must not use a newer SPIR-V version than the target.
+    #[must_use]
+    pub fn link_spirv_library(mut self, path: impl AsRef<Path>) -> Self {
+        self.spirv_libraries.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Keep the `#[unsafe(no_mangle)]` function named `symbol` exported from the resulting
+    /// SPIR-V module, so that it can be used as a SPIR-V library by other builds
+    /// (see [`Self::link_spirv_library`]).
+    #[must_use]
+    pub fn keep_link_export(mut self, symbol: impl Into<String>) -> Self {
+        self.link_exports.push(symbol.into());
+        self
+    }
+
+    /// Cache the results of linking, and of running `spirv-opt`/`spirv-val` on each output
+    /// module, in the `link-cache` directory of the target dir (see [`Self::target_dir_path`]).
+    ///
+    /// Linking is skipped entirely if none of the crates being linked changed, and with
+    /// [`Self::multimodule`], it's also incremental: only the entry points affected by any
+    /// changes get linked, optimized and validated again, which can greatly speed up
+    /// rebuilds of shader crates with many entry points.
+    /// Defaults to false.
+    #[must_use]
+    pub fn link_cache(mut self, v: bool) -> Self {
+        self.link_cache = v;
+        self
+    }
+
+    /// Write link-time statistics to `path`, as JSON (see `rustc_codegen_spirv_types::LinkStats`
+    /// for the exact format), which can be used to e.g. track compile-time and shader-size
+    /// regressions in CI.
+    #[must_use]
+    pub fn link_stats(mut self, path: impl AsRef<Path>) -> Self {
+        self.link_stats = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     /// Allow store from one struct type to a different type with compatible layout and members.
     #[must_use]
     pub fn relax_struct_store(mut self, v: bool) -> Self {
@@ -921,6 +1073,22 @@ fn invoke_rustc(builder: &SpirvBuilder) -> Result<PathBuf, SpirvBuilderError> {
         env::var(name)
     };
 
+    let target_dir_path = builder
+        .target_dir_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("spirv-builder"));
+    let target_dir = if target_dir_path.is_absolute() {
+        target_dir_path
+    } else {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .current_dir(path_to_crate)
+            .exec()?;
+        metadata
+            .target_directory
+            .into_std_path_buf()
+            .join(target_dir_path)
+    };
+
     let mut llvm_args = vec![];
     if builder.multimodule {
         llvm_args.push("--module-output=multiple".to_string());
@@ -932,6 +1100,10 @@ fn invoke_rustc(builder: &SpirvBuilder) -> Result<PathBuf, SpirvBuilderError> {
         }
         SpirvMetadata::Full => llvm_args.push("--spirv-metadata=full".to_string()),
     }
+    match builder.inline_mode {
+        InlineMode::Hints => (),
+        InlineMode::Size => llvm_args.push("--inline-mode=size".to_string()),
+    }
     if builder.validator.relax_struct_store {
         llvm_args.push("--relax-struct-store".to_string());
     }
@@ -960,18 +1132,69 @@ fn invoke_rustc(builder: &SpirvBuilder) -> Result<PathBuf, SpirvBuilderError> {
             print_inputs,
             print_backtrace,
         } => {
-            target_features.push("+ext:SPV_KHR_non_semantic_info".into());
+            // NOTE the shader log buffer doesn't need `NonSemantic.DebugPrintf`
+            // to actually be supported, as it's removed during linking.
+            if builder.shader_log_buffer.is_none() {
+                target_features.push("+ext:SPV_KHR_non_semantic_info".into());
+            }
             Some(format!(
                 "debug-printf{}{}",
                 if print_inputs { "+inputs" } else { "" },
                 if print_backtrace { "+backtrace" } else { "" }
             ))
         }
+        ShaderPanicStrategy::AbortRecordThenExit {
+            descriptor_set,
+            binding,
+            record_inputs,
+        } => {
+            llvm_args.push(format!("--abort-record-buffer={descriptor_set},{binding}"));
+            Some(format!(
+                "abort-record{}",
+                if record_inputs { "+inputs" } else { "" }
+            ))
+        }
         ShaderPanicStrategy::UNSOUND_DO_NOT_USE_UndefinedBehaviorViaUnreachable => {
             Some("unreachable".into())
         }
     };
     llvm_args.extend(abort_strategy.map(|strategy| format!("--abort-strategy={strategy}")));
+    if let Some(DescriptorBinding {
+        descriptor_set,
+        binding,
+    }) = builder.shader_log_buffer
+    {
+        llvm_args.push(format!("--shader-log-buffer={descriptor_set},{binding}"));
+    }
+
+    if !builder.link_exports.is_empty() {
+        llvm_args.push(format!(
+            "--keep-link-export={}",
+            join_checking_for_separators(builder.link_exports.clone(), ",")
+        ));
+    }
+    for spirv_library in &builder.spirv_libraries {
+        // NOTE codegen args require absolute paths, as the working
+        // directory of the compiler is that of the shader crate instead.
+        if !spirv_library.is_file() {
+            return Err(SpirvBuilderError::SpirvLibraryDoesNotExist(
+                spirv_library.clone(),
+            ));
+        }
+        let spirv_library =
+            std::path::absolute(spirv_library).unwrap_or_else(|_| spirv_library.clone());
+        llvm_args.push(format!("--link-spirv-lib={}", spirv_library.display()));
+    }
+    if let Some(link_stats) = &builder.link_stats {
+        let link_stats = std::path::absolute(link_stats).unwrap_or_else(|_| link_stats.clone());
+        llvm_args.push(format!("--link-stats={}", link_stats.display()));
+    }
+    if builder.link_cache {
+        llvm_args.push(format!(
+            "--link-cache={}",
+            target_dir.join("link-cache").display()
+        ));
+    }
 
     if let Ok(extra_codegen_args) = tracked_env_var_get("RUSTGPU_CODEGEN_ARGS") {
         llvm_args.extend(extra_codegen_args.split_whitespace().map(|s| s.to_string()));
@@ -999,22 +1222,6 @@ fn invoke_rustc(builder: &SpirvBuilder) -> Result<PathBuf, SpirvBuilderError> {
         rustflags.extend(extra_rustflags.split_whitespace().map(|s| s.to_string()));
     }
 
-    let target_dir_path = builder
-        .target_dir_path
-        .clone()
-        .unwrap_or_else(|| PathBuf::from("spirv-builder"));
-    let target_dir = if target_dir_path.is_absolute() {
-        target_dir_path
-    } else {
-        let metadata = cargo_metadata::MetadataCommand::new()
-            .current_dir(path_to_crate)
-            .exec()?;
-        metadata
-            .target_directory
-            .into_std_path_buf()
-            .join(target_dir_path)
-    };
-
     let mut cargo = cargo_cmd::CargoCmd::new();
     if let Some(toolchain) = &builder.toolchain_overwrite {
         cargo.arg(format!("+{toolchain}"));
@@ -247,3 +247,267 @@ pub fn debug_printf_inner(input: DebugPrintfInput) -> TokenStream {
 
     output.into()
 }
+
+pub struct DebugPrintInput {
+    pub format_string: syn::LitStr,
+    pub positional_args: Vec<syn::Expr>,
+    pub named_args: Vec<(syn::Ident, syn::Expr)>,
+}
+
+impl syn::parse::Parse for DebugPrintInput {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::parse::Result<Self> {
+        if input.is_empty() {
+            return Ok(Self {
+                format_string: syn::LitStr::new("", input.span()),
+                positional_args: Default::default(),
+                named_args: Default::default(),
+            });
+        }
+
+        let format_string = input.parse::<syn::LitStr>()?;
+        let mut positional_args = Vec::new();
+        let mut named_args = Vec::<(syn::Ident, _)>::new();
+        while !input.is_empty() {
+            input.parse::<syn::token::Comma>()?;
+            if input.is_empty() {
+                break;
+            }
+            if input.peek(syn::Ident)
+                && input.peek2(syn::Token![=])
+                && !input.peek2(syn::Token![==])
+            {
+                let name = input.parse::<syn::Ident>()?;
+                input.parse::<syn::Token![=]>()?;
+                if named_args.iter().any(|(other, _)| *other == name) {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("duplicate argument named `{name}`"),
+                    ));
+                }
+                named_args.push((name, input.parse()?));
+            } else if !named_args.is_empty() {
+                return Err(input.error("positional arguments cannot follow named arguments"));
+            } else {
+                positional_args.push(input.parse()?);
+            }
+        }
+
+        Ok(Self {
+            format_string,
+            positional_args,
+            named_args,
+        })
+    }
+}
+
+/// Lowers a Rust-style format string (and its arguments) to a `NonSemantic.DebugPrintf`
+/// instruction, where each `{...}` is replaced with a `%{spec}` placeholder, and the
+/// corresponding argument with a `GpuDebug` node describing the value being printed.
+///
+/// The placeholders are only replaced with C-style specifiers by the linker, once the
+/// types of the values being printed are known (see `spirv_std::debug_printf::DebugNode`).
+pub fn debug_print_inner(
+    input: DebugPrintInput,
+    newline: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let DebugPrintInput {
+        format_string,
+        positional_args,
+        named_args,
+    } = input;
+    let span = format_string.span();
+
+    let mut args: Vec<_> = positional_args
+        .into_iter()
+        .chain(named_args.iter().map(|(_, expr)| expr.clone()))
+        .collect();
+    let positional_count = args.len() - named_args.len();
+    let mut named_indices: Vec<(String, usize)> = named_args
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| (name.to_string(), positional_count + i))
+        .collect();
+    let mut used = vec![false; args.len()];
+
+    // Each placeholder is `(argument index, is_debug)`.
+    let mut placeholders = Vec::new();
+    let mut lowered = String::new();
+    let mut next_positional = 0;
+    let mut chars = format_string
+        .value()
+        .chars()
+        .collect::<Vec<_>>()
+        .into_iter();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.as_slice().first() == Some(&'{') => {
+                chars.next();
+                lowered.push('{');
+            }
+            '}' if chars.as_slice().first() == Some(&'}') => {
+                chars.next();
+                lowered.push('}');
+            }
+            '}' => {
+                return Err(syn::Error::new(
+                    span,
+                    "invalid format string: unmatched `}` found (use `}}` to print a `}`)",
+                ));
+            }
+            '%' => lowered.push_str("%%"),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => placeholder.push(ch),
+                        None => {
+                            return Err(syn::Error::new(
+                                span,
+                                "invalid format string: expected `}` but string was terminated \
+                                 (use `{{` to print a `{`)",
+                            ));
+                        }
+                    }
+                }
+                let (arg, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+                let arg = arg.trim();
+
+                let index = if arg.is_empty() {
+                    next_positional += 1;
+                    next_positional - 1
+                } else if let Ok(index) = arg.parse::<usize>() {
+                    index
+                } else if let Some(&(_, index)) = named_indices.iter().find(|(name, _)| name == arg)
+                {
+                    index
+                } else {
+                    // Implicitly captured identifier (e.g. `{pos:?}`).
+                    let ident = syn::parse_str::<syn::Ident>(arg).map_err(|_err| {
+                        syn::Error::new(
+                            span,
+                            format!("invalid format string: invalid argument name `{arg}`"),
+                        )
+                    })?;
+                    let ident = syn::Ident::new(&ident.to_string(), span);
+                    args.push(syn::parse_quote!(#ident));
+                    used.push(false);
+                    named_indices.push((arg.to_string(), args.len() - 1));
+                    args.len() - 1
+                };
+                if (arg.is_empty() || arg.parse::<usize>().is_ok()) && index >= positional_count {
+                    return Err(syn::Error::new(
+                        span,
+                        format!(
+                            "invalid reference to positional argument {index} ({})",
+                            match positional_count {
+                                0 => "no arguments were given".to_string(),
+                                1 => "there is 1 argument".to_string(),
+                                n => format!("there are {n} arguments"),
+                            }
+                        ),
+                    ));
+                }
+                used[index] = true;
+
+                let unsupported = || {
+                    syn::Error::new(
+                        span,
+                        format!(
+                            "unsupported format spec `{spec}` (only `?`, `x`, `x?` and \
+                             precision, e.g. `.2`, are supported)"
+                        ),
+                    )
+                };
+                let mut rest = spec;
+                if let Some(precision) = rest.strip_prefix('.') {
+                    let digits = precision.len()
+                        - precision
+                            .trim_start_matches(|c: char| c.is_ascii_digit())
+                            .len();
+                    if digits == 0 {
+                        return Err(unsupported());
+                    }
+                    rest = &precision[digits..];
+                }
+                rest = rest.strip_prefix('x').unwrap_or(rest);
+                let is_debug = rest == "?";
+                if !(rest.is_empty() || is_debug) {
+                    return Err(unsupported());
+                }
+
+                let _ = write!(lowered, "%{{{spec}}}");
+                placeholders.push((index, is_debug));
+            }
+            ch => lowered.push(ch),
+        }
+    }
+    if newline {
+        lowered.push('\n');
+    }
+
+    if let Some(index) = used.iter().position(|&used| !used) {
+        return Err(syn::Error::new_spanned(
+            &args[index],
+            if index < positional_count {
+                "argument never used"
+            } else {
+                "named argument never used"
+            },
+        ));
+    }
+
+    let arg_idents: Vec<_> = (0..args.len())
+        .map(|i| quote::format_ident!("__arg_{}", i))
+        .collect();
+    let mut node_idents = String::new();
+    let mut nodes = Vec::new();
+    let mut input_registers = Vec::new();
+    let mut op_loads = Vec::new();
+    for (i, (index, is_debug)) in placeholders.into_iter().enumerate() {
+        let arg = &arg_idents[index];
+        let node = quote::format_ident!("__node_{}", i);
+        let ident = quote::format_ident!("_{}", i);
+
+        let _ = write!(node_idents, "%{ident} ");
+        nodes.push(if is_debug {
+            quote::quote! {
+                let #node = spirv_std::debug_printf::GpuDebug::gpu_debug_node(#arg);
+            }
+        } else {
+            quote::quote! {
+                let #node = spirv_std::debug_printf::gpu_display_node(#arg);
+            }
+        });
+        input_registers.push(quote::quote! {
+            #ident = in(reg) &#node,
+        });
+        let op_load = format!("%{ident} = OpLoad _ {{{ident}}}");
+        op_loads.push(quote::quote! {
+            #op_load,
+        });
+    }
+
+    // Escapes the '{' and '}' characters in the format string (see `debug_printf_inner`).
+    let lowered = lowered.replace('{', "{{").replace('}', "}}");
+    let op_string = format!("%string = OpString {lowered:?}");
+
+    Ok(quote::quote! {
+        match (#(&(#args),)*) {
+            (#(#arg_idents,)*) => {
+                #(#nodes)*
+                #[allow(unused_unsafe)]
+                unsafe {
+                    ::core::arch::asm!(
+                        "%void = OpTypeVoid",
+                        #op_string,
+                        "%debug_printf = OpExtInstImport \"NonSemantic.DebugPrintf\"",
+                        #(#op_loads)*
+                        concat!("%result = OpExtInst %void %debug_printf 1 %string ", #node_idents),
+                        #(#input_registers)*
+                    )
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,166 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DataEnum, DataUnion, DeriveInput, Fields, GenericParam};
+
+pub fn derive(item: TokenStream) -> syn::Result<TokenStream> {
+    // Whenever we'll properly resolve the crate symbol, replace this.
+    let spirv_std = quote!(spirv_std);
+
+    let mut item = syn::parse2::<DeriveInput>(item)?;
+    let body = match &item.data {
+        Data::Struct(data) => derive_struct(&item, &data.fields),
+        Data::Enum(data) => derive_enum(data),
+        Data::Union(DataUnion { union_token, .. }) => Err(syn::Error::new_spanned(
+            union_token,
+            "`GpuDebug` cannot be derived for unions",
+        )),
+    }?;
+
+    // Like `#[derive(Debug)]`, require all type parameters to implement `GpuDebug`.
+    for param in &mut item.generics.params {
+        if let GenericParam::Type(param) = param {
+            param
+                .bounds
+                .push(syn::parse_quote!(#spirv_std::debug_printf::GpuDebug));
+        }
+    }
+    let ident = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #spirv_std::debug_printf::GpuDebug for #ident #ty_generics #where_clause {
+            #[#spirv_std::macros::gpu_only]
+            #[inline(always)]
+            fn gpu_debug_node(&self) -> #spirv_std::debug_printf::DebugNode {
+                #body
+            }
+        }
+    })
+}
+
+/// Generates a single `asm!` block building the node chain described in the docs of
+/// `spirv_std::debug_printf::DebugNode`, starting with `first_node` (the operands of its
+/// instruction, which can refer to `strings` and `inputs` by their ids), and followed by
+/// one node for each of `fields` (with their names, for named fields).
+fn gen_node_chain(
+    strings: &[(String, String)],
+    inputs: &[(&str, TokenStream)],
+    first_node: &str,
+    fields: &[(Option<String>, TokenStream)],
+) -> TokenStream {
+    let mut asm_lines =
+        vec!["%gpu_debug = OpExtInstImport \"Rust.spirv-std.GpuDebug\"".to_string()];
+    for (id, string) in strings {
+        asm_lines.push(format!("%{id} = OpString {string:?}"));
+    }
+    let mut registers = Vec::new();
+    for (id, value) in inputs {
+        asm_lines.push(format!("%{id} = OpLoad _ {{{id}}}"));
+        let id = format_ident!("{}", id);
+        registers.push(quote!(#id = in(reg) &#value,));
+    }
+    asm_lines.push(format!(
+        "%node_0 = OpExtInst typeof*{{node}} %gpu_debug {first_node}"
+    ));
+
+    for (i, (field_name, field_node)) in fields.iter().enumerate() {
+        let field = format_ident!("field_{}", i);
+        asm_lines.push(format!("%{field} = OpLoad _ {{{field}}}"));
+        let node = match field_name {
+            Some(field_name) => {
+                asm_lines.push(format!("%{field}_name = OpString {field_name:?}"));
+                format!("7 %node_{i} %{field}_name %{field}")
+            }
+            None => format!("6 %node_{i} %{field}"),
+        };
+        asm_lines.push(format!(
+            "%node_{} = OpExtInst typeof*{{node}} %gpu_debug {node}",
+            i + 1
+        ));
+        registers.push(quote!(#field = in(reg) &#field_node,));
+    }
+    asm_lines.push(format!("OpStore {{node}} %node_{}", fields.len()));
+
+    quote! {
+        unsafe {
+            let mut node = ::core::mem::MaybeUninit::uninit();
+            ::core::arch::asm!(
+                #(#asm_lines,)*
+                node = in(reg) node.as_mut_ptr(),
+                #(#registers)*
+            );
+            node.assume_init()
+        }
+    }
+}
+
+fn derive_struct(item: &DeriveInput, fields: &Fields) -> syn::Result<TokenStream> {
+    let name = item.ident.to_string();
+    let field_node = |member: TokenStream| quote!(spirv_std::debug_printf::GpuDebug::gpu_debug_node(&self.#member));
+    Ok(match fields {
+        Fields::Named(fields) => {
+            let fields: Vec<_> = fields
+                .named
+                .iter()
+                .map(|field| {
+                    let ident = field.ident.as_ref().unwrap();
+                    let name = ident.to_string();
+                    let name = name.strip_prefix("r#").unwrap_or(&name).to_string();
+                    (Some(name), field_node(quote!(#ident)))
+                })
+                .collect();
+            gen_node_chain(&[("name".into(), name)], &[], "3 %name", &fields)
+        }
+        Fields::Unnamed(fields) => {
+            let fields: Vec<_> = (0..fields.unnamed.len())
+                .map(|i| {
+                    let index = syn::Index::from(i);
+                    (None, field_node(quote!(#index)))
+                })
+                .collect();
+            gen_node_chain(&[("name".into(), name)], &[], "4 %name", &fields)
+        }
+        Fields::Unit => gen_node_chain(&[("name".into(), name)], &[], "3 %name", &[]),
+    })
+}
+
+fn derive_enum(data: &DataEnum) -> syn::Result<TokenStream> {
+    if let Some(variant) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+        return Err(syn::Error::new_spanned(
+            variant,
+            "`GpuDebug` can only be derived for enums without fields",
+        ));
+    }
+    if data.variants.is_empty() {
+        return Ok(quote!(match *self {}));
+    }
+
+    let arms = data.variants.iter().enumerate().map(|(i, variant)| {
+        let ident = &variant.ident;
+        let i = i as u32;
+        quote!(Self::#ident => #i,)
+    });
+    let variant_names: Vec<_> = data
+        .variants
+        .iter()
+        .enumerate()
+        .map(|(i, variant)| (format!("variant_{i}"), variant.ident.to_string()))
+        .collect();
+    let variant_name_ids = variant_names
+        .iter()
+        .map(|(id, _)| format!("%{id}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let chain = gen_node_chain(
+        &variant_names,
+        &[("variant_index", quote!(variant_index))],
+        &format!("8 %variant_index {variant_name_ids}"),
+        &[],
+    );
+    Ok(quote! {
+        let variant_index: u32 = match self {
+            #(#arms)*
+        };
+        #chain
+    })
+}
@@ -71,11 +71,14 @@
 #![doc = include_str!("../README.md")]
 
 mod debug_printf;
+mod gpu_debug;
 mod image;
 mod sample_param_permutations;
 mod scalar_or_vector_composite;
 
-use crate::debug_printf::{DebugPrintfInput, debug_printf_inner};
+use crate::debug_printf::{
+    DebugPrintInput, DebugPrintfInput, debug_print_inner, debug_printf_inner,
+};
 use proc_macro::TokenStream;
 use proc_macro2::{Delimiter, Group, Ident, TokenTree};
 use quote::{ToTokens, TokenStreamExt, format_ident, quote};
@@ -243,8 +246,14 @@ pub fn spirv_recursive_for_testing(attr: TokenStream, item: TokenStream) -> Toke
 
 /// Marks a function as runnable only on the GPU, and will panic on
 /// CPU platforms.
+///
+/// With `#[gpu_only(cpu_emulation = <expr>)]`, the function instead evaluates
+/// `<expr>` on CPU platforms when `spirv-std`'s `cpu-emulation` feature is
+/// enabled, while a bare `#[gpu_only(cpu_emulation)]` keeps the original body
+/// (for functions which are only implemented in terms of other intrinsics).
 #[proc_macro_attribute]
-pub fn gpu_only(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn gpu_only(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let cpu_emulation = syn::parse_macro_input!(attr as GpuOnlyCpuEmulation);
     let syn::ItemFn {
         attrs,
         vis,
@@ -259,15 +268,43 @@ pub fn gpu_only(_attr: TokenStream, item: TokenStream) -> TokenStream {
         ..sig.clone()
     };
 
+    let cpu_emulation_body = match cpu_emulation {
+        GpuOnlyCpuEmulation::None => None,
+        GpuOnlyCpuEmulation::OriginalBody => Some(quote! { #block }),
+        GpuOnlyCpuEmulation::Expr(expr) => Some(quote! { { #expr } }),
+    };
+    let cpu_output = match cpu_emulation_body {
+        None => quote! {
+            // Don't warn on unused arguments on the CPU side.
+            #[cfg(not(target_arch="spirv"))]
+            #[allow(unused_variables)]
+            #(#attrs)* #vis #sig_cpu {
+                unimplemented!(
+                    concat!("`", stringify!(#fn_name), "` is only available on SPIR-V platforms.")
+                )
+            }
+        },
+        Some(cpu_emulation_body) => quote! {
+            // Don't warn on unused arguments on the CPU side.
+            #[cfg(all(not(target_arch="spirv"), not(feature="cpu-emulation")))]
+            #[allow(unused_variables)]
+            #(#attrs)* #vis #sig_cpu {
+                unimplemented!(
+                    concat!(
+                        "`", stringify!(#fn_name), "` is only available on SPIR-V platforms ",
+                        "(or with the `cpu-emulation` feature of `spirv-std`)."
+                    )
+                )
+            }
+
+            #[cfg(all(not(target_arch="spirv"), feature="cpu-emulation"))]
+            #[allow(unused_variables)]
+            #(#attrs)* #vis #sig_cpu #cpu_emulation_body
+        },
+    };
+
     let output = quote::quote! {
-        // Don't warn on unused arguments on the CPU side.
-        #[cfg(not(target_arch="spirv"))]
-        #[allow(unused_variables)]
-        #(#attrs)* #vis #sig_cpu {
-            unimplemented!(
-                concat!("`", stringify!(#fn_name), "` is only available on SPIR-V platforms.")
-            )
-        }
+        #cpu_output
 
         #[cfg(target_arch="spirv")]
         #(#attrs)* #vis #sig {
@@ -278,6 +315,39 @@ pub fn gpu_only(_attr: TokenStream, item: TokenStream) -> TokenStream {
     output.into()
 }
 
+/// Arguments of `#[gpu_only(...)]`, describing how the CPU version behaves
+/// with `spirv-std`'s `cpu-emulation` feature enabled.
+enum GpuOnlyCpuEmulation {
+    /// `#[gpu_only]`: always unimplemented.
+    None,
+
+    /// `#[gpu_only(cpu_emulation)]`: same body on CPU and GPU.
+    OriginalBody,
+
+    /// `#[gpu_only(cpu_emulation = <expr>)]`: evaluates `<expr>` on CPU.
+    Expr(syn::Expr),
+}
+
+impl syn::parse::Parse for GpuOnlyCpuEmulation {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Ok(Self::None);
+        }
+        let ident: Ident = input.parse()?;
+        if ident != "cpu_emulation" {
+            return Err(syn::Error::new(
+                ident.span(),
+                "expected `cpu_emulation` or `cpu_emulation = <expr>`",
+            ));
+        }
+        if input.is_empty() {
+            return Ok(Self::OriginalBody);
+        }
+        input.parse::<syn::Token![=]>()?;
+        Ok(Self::Expr(input.parse()?))
+    }
+}
+
 /// Print a formatted string using the debug printf extension.
 ///
 /// Examples:
@@ -301,6 +371,41 @@ pub fn debug_printfln(input: TokenStream) -> TokenStream {
     debug_printf_inner(input)
 }
 
+/// Print a Rust-style formatted string using the debug printf extension.
+///
+/// Unlike [`debug_printf!`], the format string uses the same syntax as `core::format_args!`
+/// (i.e. `{}`, `{:?}`, `{name}`, etc.), and there's no need to pick a specifier matching each
+/// argument: the format string is only lowered to a `NonSemantic.DebugPrintf` one at compile
+/// time, once the types of all the arguments are known.
+///
+/// Arguments printed with `{}` must implement `spirv_std::GpuDisplay`, while those printed
+/// with `{:?}` must implement `spirv_std::GpuDebug` (which can be derived for structs and
+/// fieldless enums, with `#[derive(GpuDebug)]`).
+///
+/// Examples:
+///
+/// ```rust,ignore
+/// debug_print!("uv: {uv}\n");
+/// debug_print!("pos.x: {:.2}, pos.z: {:.2}, int: {int}\n", pos.x, pos.z);
+/// debug_print!("{particle:?}\n");
+/// ```
+///
+/// See the `spirv_std::debug_printf` module for more details.
+#[proc_macro]
+pub fn debug_print(input: TokenStream) -> TokenStream {
+    debug_print_inner(syn::parse_macro_input!(input as DebugPrintInput), false)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Similar to `debug_print` but appends a newline to the format string.
+#[proc_macro]
+pub fn debug_println(input: TokenStream) -> TokenStream {
+    debug_print_inner(syn::parse_macro_input!(input as DebugPrintInput), true)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 /// Generates permutations of an `ImageWithMethods` implementation containing sampling functions
 /// that have asm instruction ending with a placeholder `$PARAMS` operand. The last parameter
 /// of each function must be named `params`, its type will be rewritten. Relevant generic
@@ -318,3 +423,10 @@ pub fn derive_scalar_or_vector_composite(item: TokenStream) -> TokenStream {
         .unwrap_or_else(syn::Error::into_compile_error)
         .into()
 }
+
+#[proc_macro_derive(GpuDebug)]
+pub fn derive_gpu_debug(item: TokenStream) -> TokenStream {
+    gpu_debug::derive(item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -14,6 +14,7 @@ mod atomics;
 mod barrier;
 mod demote_to_helper_invocation_ext;
 mod derivative;
+mod fragment_shader_interlock_ext;
 mod mesh_shading;
 mod primitive;
 mod ray_tracing;
@@ -23,6 +24,7 @@ pub use atomics::*;
 pub use barrier::*;
 pub use demote_to_helper_invocation_ext::*;
 pub use derivative::*;
+pub use fragment_shader_interlock_ext::*;
 pub use mesh_shading::*;
 pub use primitive::*;
 pub use ray_tracing::*;
@@ -209,25 +211,25 @@ unsafe fn call_glsl_op_with_ints<T: Integer, const OP: u32>(a: T, b: T) -> T {
 }
 
 /// Compute the minimum of two unsigned integers via a GLSL extended instruction.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = a.min(b))]
 pub fn unsigned_min<T: UnsignedInteger>(a: T, b: T) -> T {
     unsafe { call_glsl_op_with_ints::<_, 38>(a, b) }
 }
 
 /// Compute the maximum of two unsigned integers via a GLSL extended instruction.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = a.max(b))]
 pub fn unsigned_max<T: UnsignedInteger>(a: T, b: T) -> T {
     unsafe { call_glsl_op_with_ints::<_, 41>(a, b) }
 }
 
 /// Compute the minimum of two signed integers via a GLSL extended instruction.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = a.min(b))]
 pub fn signed_min<T: SignedInteger>(a: T, b: T) -> T {
     unsafe { call_glsl_op_with_ints::<_, 39>(a, b) }
 }
 
 /// Compute the maximum of two signed integers via a GLSL extended instruction.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = a.max(b))]
 pub fn signed_max<T: SignedInteger>(a: T, b: T) -> T {
     unsafe { call_glsl_op_with_ints::<_, 42>(a, b) }
 }
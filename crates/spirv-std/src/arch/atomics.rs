@@ -1,12 +1,14 @@
 #[cfg(target_arch = "spirv")]
 use core::arch::asm;
 
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+use crate::cpu_emulation::{BinOp, atomics as emulated};
 use crate::{Float, Integer, Number, SignedInteger, UnsignedInteger};
 
 /// Atomically load through `ptr` using the given `SEMANTICS`. All subparts of
 /// the value that is loaded are read atomically with respect to all other
 /// atomic accesses to it within `SCOPE`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = unsafe { emulated::atomic_load(ptr) })]
 #[doc(alias = "OpAtomicLoad")]
 #[inline]
 pub unsafe fn atomic_load<N: Number, const SCOPE: u32, const SEMANTICS: u32>(ptr: &N) -> N {
@@ -32,7 +34,7 @@ pub unsafe fn atomic_load<N: Number, const SCOPE: u32, const SEMANTICS: u32>(ptr
 /// Atomically store through `ptr` using the given `SEMANTICS`. All subparts of
 /// `value` are written atomically with respect to all other atomic accesses to
 /// it within `SCOPE`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = unsafe { emulated::atomic_update(ptr, |_| value); })]
 #[doc(alias = "OpAtomicStore")]
 #[inline]
 pub unsafe fn atomic_store<N: Number, const SCOPE: u32, const SEMANTICS: u32>(
@@ -62,7 +64,7 @@ pub unsafe fn atomic_store<N: Number, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = unsafe { emulated::atomic_update(ptr, |_| value) })]
 #[doc(alias = "OpAtomicExchange")]
 #[inline]
 pub unsafe fn atomic_exchange<N: Number, const SCOPE: u32, const SEMANTICS: u32>(
@@ -100,7 +102,9 @@ pub unsafe fn atomic_exchange<N: Number, const SCOPE: u32, const SEMANTICS: u32>
 ///    equaled `comparator`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = unsafe {
+    emulated::atomic_update(ptr, |old| if old == comparator { value } else { old })
+})]
 #[doc(alias = "OpAtomicCompareExchange")]
 #[inline]
 pub unsafe fn atomic_compare_exchange<
@@ -146,7 +150,9 @@ pub unsafe fn atomic_compare_exchange<
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Add, emulated::one(*ptr)) }
+)]
 #[doc(alias = "OpAtomicIIncrement")]
 #[inline]
 pub unsafe fn atomic_i_increment<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -179,7 +185,9 @@ pub unsafe fn atomic_i_increment<I: Integer, const SCOPE: u32, const SEMANTICS:
 /// 3) store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_sub(ptr, emulated::one(*ptr)) }
+)]
 #[doc(alias = "OpAtomicIDecrement")]
 #[inline]
 pub unsafe fn atomic_i_decrement<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -212,7 +220,9 @@ pub unsafe fn atomic_i_decrement<I: Integer, const SCOPE: u32, const SEMANTICS:
 /// 3) store the new value back through `ptr`.
 ///
 /// The result is the Original Value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Add, value) }
+)]
 #[doc(alias = "OpAtomicIAdd")]
 #[inline]
 pub unsafe fn atomic_i_add<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -248,7 +258,7 @@ pub unsafe fn atomic_i_add<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3) store the new value back through `ptr`.
 ///
 /// The result is the Original Value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = unsafe { emulated::atomic_sub(ptr, value) })]
 #[doc(alias = "OpAtomicISub")]
 #[inline]
 pub unsafe fn atomic_i_sub<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -285,7 +295,9 @@ pub unsafe fn atomic_i_sub<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Min, value) }
+)]
 #[doc(alias = "OpAtomicSMin")]
 #[inline]
 pub unsafe fn atomic_s_min<S: SignedInteger, const SCOPE: u32, const SEMANTICS: u32>(
@@ -322,7 +334,9 @@ pub unsafe fn atomic_s_min<S: SignedInteger, const SCOPE: u32, const SEMANTICS:
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Min, value) }
+)]
 #[doc(alias = "OpAtomicUMin")]
 #[inline]
 pub unsafe fn atomic_u_min<U: UnsignedInteger, const SCOPE: u32, const SEMANTICS: u32>(
@@ -359,7 +373,9 @@ pub unsafe fn atomic_u_min<U: UnsignedInteger, const SCOPE: u32, const SEMANTICS
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Max, value) }
+)]
 #[doc(alias = "OpAtomicSMax")]
 #[inline]
 pub unsafe fn atomic_s_max<S: SignedInteger, const SCOPE: u32, const SEMANTICS: u32>(
@@ -396,7 +412,9 @@ pub unsafe fn atomic_s_max<S: SignedInteger, const SCOPE: u32, const SEMANTICS:
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Max, value) }
+)]
 #[doc(alias = "OpAtomicUMax")]
 #[inline]
 pub unsafe fn atomic_u_max<U: UnsignedInteger, const SCOPE: u32, const SEMANTICS: u32>(
@@ -432,7 +450,9 @@ pub unsafe fn atomic_u_max<U: UnsignedInteger, const SCOPE: u32, const SEMANTICS
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::And, value) }
+)]
 #[doc(alias = "OpAtomicAnd")]
 #[inline]
 pub unsafe fn atomic_and<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -468,7 +488,9 @@ pub unsafe fn atomic_and<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Or, value) }
+)]
 #[doc(alias = "OpAtomicOr")]
 #[inline]
 pub unsafe fn atomic_or<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -504,7 +526,9 @@ pub unsafe fn atomic_or<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Xor, value) }
+)]
 #[doc(alias = "OpAtomicXor")]
 #[inline]
 pub unsafe fn atomic_xor<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
@@ -541,7 +565,9 @@ pub unsafe fn atomic_xor<I: Integer, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Min, value) }
+)]
 #[doc(alias = "OpAtomicFMinEXT")]
 #[inline]
 pub unsafe fn atomic_f_min<F: Float, const SCOPE: u32, const SEMANTICS: u32>(
@@ -578,7 +604,9 @@ pub unsafe fn atomic_f_min<F: Float, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3. Store the new value back through `ptr`.
 ///
 /// The result is the original value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Max, value) }
+)]
 #[doc(alias = "OpAtomicFMaxEXT")]
 #[inline]
 pub unsafe fn atomic_f_max<F: Float, const SCOPE: u32, const SEMANTICS: u32>(
@@ -614,7 +642,9 @@ pub unsafe fn atomic_f_max<F: Float, const SCOPE: u32, const SEMANTICS: u32>(
 /// 3) store the new value back through `ptr`.
 ///
 /// The result is the Original Value.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(
+    cpu_emulation = unsafe { emulated::atomic_binop(ptr, BinOp::Add, value) }
+)]
 #[doc(alias = "OpAtomicFAddEXT")]
 #[inline]
 pub unsafe fn atomic_f_add<F: Float, const SCOPE: u32, const SEMANTICS: u32>(
@@ -30,7 +30,7 @@ use core::arch::asm;
 /// synchronizes the `output` storage class: Writes to `output` variables
 /// performed by any invocation executed prior to a [`control_barrier`] are
 /// visible to any other invocation proceeding beyond that [`control_barrier`].
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::control_barrier(EXECUTION))]
 #[doc(alias = "OpControlBarrier")]
 #[inline]
 pub fn control_barrier<
@@ -67,7 +67,7 @@ pub fn control_barrier<
 ///
 /// To execute both a memory barrier and a control barrier,
 /// see [`control_barrier`].
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::memory_barrier())]
 #[doc(alias = "OpMemoryBarrier")]
 #[inline]
 pub fn memory_barrier<
@@ -91,7 +91,7 @@ pub fn memory_barrier<
 /// This is an exact implementation of `GroupMemoryBarrier()`.
 ///
 /// From <https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/groupmemorybarrier>
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 #[inline]
 pub fn workgroup_memory_barrier() {
     memory_barrier::<
@@ -108,7 +108,7 @@ pub fn workgroup_memory_barrier() {
 /// This is an exact implementation of `GroupMemoryBarrierWithGroupSync()`.
 ///
 /// From <https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/groupmemorybarrierwithgroupsync>
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 #[inline]
 pub fn workgroup_memory_barrier_with_group_sync() {
     control_barrier::<
@@ -126,7 +126,7 @@ pub fn workgroup_memory_barrier_with_group_sync() {
 /// This is an exact implementation of `DeviceMemoryBarrier()`.
 ///
 /// From <https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/devicememorybarrier>
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 #[inline]
 pub fn device_memory_barrier() {
     memory_barrier::<
@@ -144,7 +144,7 @@ pub fn device_memory_barrier() {
 /// This is an exact implementation of `DeviceMemoryBarrierWithGroupSync()`.
 ///
 /// From <https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/devicememorybarrierwithgroupsync>
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 #[inline]
 pub fn device_memory_barrier_with_group_sync() {
     control_barrier::<
@@ -163,7 +163,7 @@ pub fn device_memory_barrier_with_group_sync() {
 /// This is an exact implementation of `AllMemoryBarrier()`.
 ///
 /// From <https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/allmemorybarrier>
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 #[inline]
 pub fn all_memory_barrier() {
     memory_barrier::<
@@ -182,7 +182,7 @@ pub fn all_memory_barrier() {
 /// This is an exact implementation of `AllMemoryBarrierWithGroupSync()`.
 ///
 /// From <https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/allmemorybarrierwithgroupsync>
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 #[inline]
 pub fn all_memory_barrier_with_group_sync() {
     control_barrier::<
@@ -1,3 +1,5 @@
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+use crate::cpu_emulation::subgroup::{DerivativeKind, derivative};
 use crate::sealed::Sealed;
 use glam::{Vec2, Vec3, Vec3A, Vec4};
 
@@ -31,7 +33,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Dx, false))]
     #[inline]
     fn dfdx(self) -> Self {
         deriv_fn!(OpDPdx, self)
@@ -44,7 +46,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Dx, false))]
     #[inline]
     fn dfdx_fine(self) -> Self {
         deriv_fn!(OpDPdxFine, self)
@@ -59,7 +61,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Dx, true))]
     #[inline]
     fn dfdx_coarse(self) -> Self {
         deriv_fn!(OpDPdxCoarse, self)
@@ -73,7 +75,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Dy, false))]
     #[inline]
     fn dfdy(self) -> Self {
         deriv_fn!(OpDPdy, self)
@@ -86,7 +88,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Dy, false))]
     #[inline]
     fn dfdy_fine(self) -> Self {
         deriv_fn!(OpDPdyFine, self)
@@ -101,7 +103,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Dy, true))]
     #[inline]
     fn dfdy_coarse(self) -> Self {
         deriv_fn!(OpDPdyCoarse, self)
@@ -113,7 +115,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Fwidth, false))]
     #[inline]
     fn fwidth(self) -> Self {
         deriv_fn!(OpFwidth, self)
@@ -125,7 +127,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Fwidth, false))]
     #[inline]
     fn fwidth_fine(self) -> Self {
         deriv_fn!(OpFwidthFine, self)
@@ -137,7 +139,7 @@ pub unsafe trait Derivative: Sealed + Default {
     /// derivative group have executed all dynamic instances that are program-ordered before X'.
     ///
     /// This instruction is only valid in the Fragment Execution Model.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = derivative(self, DerivativeKind::Fwidth, true))]
     #[inline]
     fn fwidth_coarse(self) -> Self {
         deriv_fn!(OpFwidthCoarse, self)
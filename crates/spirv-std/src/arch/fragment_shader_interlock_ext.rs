@@ -0,0 +1,102 @@
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use core::marker::PhantomData;
+
+/// Begin a fragment shader interlock critical section, i.e. wait until all overlapping
+/// fragments ordered before this one (according to the interlock execution mode) have
+/// left theirs.
+///
+/// Prefer [`invocation_interlock`], which ends the critical section automatically.
+///
+/// - **Required Capabilities** `FragmentShaderPixelInterlockEXT`,
+///   `FragmentShaderSampleInterlockEXT` or `FragmentShaderShadingRateInterlockEXT`
+/// - **Required Extensions** `SPV_EXT_fragment_shader_interlock`
+///
+/// # Safety
+/// Must be called exactly once per fragment shader invocation, before a single call to
+/// [`end_invocation_interlock`], from a fragment entry point with one of the
+/// `*_interlock_*_ext` execution modes (e.g. `#[spirv(fragment(pixel_interlock_ordered_ext))]`),
+/// and in uniform control flow.
+#[spirv_std_macros::gpu_only]
+#[doc(alias = "OpBeginInvocationInterlockEXT")]
+#[inline]
+pub unsafe fn begin_invocation_interlock() {
+    unsafe {
+        asm!("OpBeginInvocationInterlockEXT");
+    }
+}
+
+/// End a fragment shader interlock critical section, started by [`begin_invocation_interlock`].
+///
+/// - **Required Capabilities** `FragmentShaderPixelInterlockEXT`,
+///   `FragmentShaderSampleInterlockEXT` or `FragmentShaderShadingRateInterlockEXT`
+/// - **Required Extensions** `SPV_EXT_fragment_shader_interlock`
+///
+/// # Safety
+/// Must be called exactly once per fragment shader invocation, after
+/// [`begin_invocation_interlock`], and in uniform control flow.
+#[spirv_std_macros::gpu_only]
+#[doc(alias = "OpEndInvocationInterlockEXT")]
+#[inline]
+pub unsafe fn end_invocation_interlock() {
+    unsafe {
+        asm!("OpEndInvocationInterlockEXT");
+    }
+}
+
+/// Begin a fragment shader interlock critical section, which ends when the returned
+/// guard is dropped. Accesses to memory (e.g. storage images) made while the guard is
+/// alive are ordered (or, with the `*_unordered_ext` execution modes, just made mutually
+/// exclusive) with those of other overlapping fragments, which allows e.g. programmable
+/// blending.
+///
+/// The entry point must use one of the `*_interlock_*_ext` execution modes, and must
+/// enter the critical section exactly once on every path through it, i.e. not conditionally
+/// or in a loop (both of which are checked when linking):
+/// ```no_run
+/// # use spirv_std::{Image, spirv};
+/// # use spirv_std::arch::invocation_interlock;
+/// # use spirv_std::glam::{UVec2, Vec4};
+/// #[spirv(fragment(pixel_interlock_ordered_ext))]
+/// pub fn main_fs(
+///     #[spirv(frag_coord)] frag_coord: Vec4,
+///     #[spirv(descriptor_set = 0, binding = 0)] target: &Image!(2D, format = rgba8, sampled = false),
+///     color: Vec4,
+/// ) {
+///     let coord = frag_coord.truncate().truncate().as_uvec2();
+///     let _interlock = invocation_interlock();
+///     let dst: Vec4 = target.read(coord);
+///     unsafe { target.write(coord, color.lerp(dst, 1.0 - color.w)) };
+/// }
+/// ```
+///
+/// - **Required Capabilities** `FragmentShaderPixelInterlockEXT`,
+///   `FragmentShaderSampleInterlockEXT` or `FragmentShaderShadingRateInterlockEXT`
+/// - **Required Extensions** `SPV_EXT_fragment_shader_interlock`
+#[doc(alias = "OpBeginInvocationInterlockEXT")]
+#[inline]
+pub fn invocation_interlock() -> InvocationInterlockGuard {
+    unsafe {
+        begin_invocation_interlock();
+    }
+    InvocationInterlockGuard {
+        _not_send: PhantomData,
+    }
+}
+
+/// Guard for a fragment shader interlock critical section, returned by
+/// [`invocation_interlock`], which ends the critical section when dropped.
+#[must_use = "the critical section ends as soon as the guard is dropped"]
+pub struct InvocationInterlockGuard {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for InvocationInterlockGuard {
+    #[doc(alias = "OpEndInvocationInterlockEXT")]
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            end_invocation_interlock();
+        }
+    }
+}
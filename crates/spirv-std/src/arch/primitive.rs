@@ -47,12 +47,11 @@ pub unsafe fn end_primitive() {
 #[spirv_std_macros::gpu_only]
 #[doc(alias = "OpEmitStreamVertex")]
 #[inline]
-// FIXME(eddyb) why does this require `i64` instead of `i32`?
-pub unsafe fn emit_stream_vertex<const STREAM: i64>() {
+pub unsafe fn emit_stream_vertex<const STREAM: u32>() {
     unsafe {
         asm! {
-            "%i64 = OpTypeInt 64 1",
-            "%stream = OpConstant %i64 {stream}",
+            "%u32 = OpTypeInt 32 0",
+            "%stream = OpConstant %u32 {stream}",
             "OpEmitStreamVertex %stream",
             stream = const STREAM,
         }
@@ -70,12 +69,11 @@ pub unsafe fn emit_stream_vertex<const STREAM: i64>() {
 #[spirv_std_macros::gpu_only]
 #[doc(alias = "OpEndStreamPrimitive")]
 #[inline]
-// FIXME(eddyb) why does this require `i64` instead of `i32`?
-pub unsafe fn end_stream_primitive<const STREAM: i64>() {
+pub unsafe fn end_stream_primitive<const STREAM: u32>() {
     unsafe {
         asm! {
-            "%i64 = OpTypeInt 64 1",
-            "%stream = OpConstant %i64 {stream}",
+            "%u32 = OpTypeInt 32 0",
+            "%stream = OpConstant %u32 {stream}",
             "OpEndStreamPrimitive %stream",
             stream = const STREAM,
         }
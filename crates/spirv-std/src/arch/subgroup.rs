@@ -2,6 +2,8 @@
 use crate::ScalarOrVectorTransform;
 #[cfg(target_arch = "spirv")]
 use crate::arch::{asm, barrier};
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+use crate::cpu_emulation::subgroup as emulated;
 #[cfg(target_arch = "spirv")]
 use crate::memory::{Scope, Semantics};
 use crate::{Float, Integer, ScalarComposite, ScalarOrVector, SignedInteger, UnsignedInteger};
@@ -55,7 +57,7 @@ pub enum GroupOperation {
 /// within the same subgroup.
 ///
 /// Requires Capability `GroupNonUniform`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::subgroup_barrier())]
 #[doc(alias = "subgroupBarrier")]
 #[inline]
 pub fn subgroup_barrier() {
@@ -76,7 +78,7 @@ pub fn subgroup_barrier() {
 /// invocations in the same subgroup.
 ///
 /// Requires Capability `GroupNonUniform`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::memory_barrier())]
 #[doc(alias = "subgroupMemoryBarrier")]
 #[inline]
 pub fn subgroup_memory_barrier() {
@@ -96,7 +98,7 @@ pub fn subgroup_memory_barrier() {
 /// invocation, as viewed by other invocations in the same subgroup.
 ///
 /// Requires Capability `GroupNonUniform`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::memory_barrier())]
 #[doc(alias = "subgroupMemoryBarrierBuffer")]
 #[inline]
 pub fn subgroup_memory_barrier_buffer() {
@@ -113,7 +115,7 @@ pub fn subgroup_memory_barrier_buffer() {
 /// Only available in compute shaders.
 ///
 /// Requires Capability `GroupNonUniform`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::memory_barrier())]
 #[doc(alias = "subgroupMemoryBarrierShared")]
 #[inline]
 pub fn subgroup_memory_barrier_shared() {
@@ -128,7 +130,7 @@ pub fn subgroup_memory_barrier_shared() {
 /// viewed by other invocations in the same subgroup.
 ///
 /// Requires Capability `GroupNonUniform`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = crate::cpu_emulation::memory_barrier())]
 #[doc(alias = "subgroupMemoryBarrierImage")]
 #[inline]
 pub fn subgroup_memory_barrier_image() {
@@ -145,7 +147,7 @@ pub fn subgroup_memory_barrier_image() {
 /// Execution is a Scope that identifies the group of invocations affected by this command. It must be Subgroup.
 ///
 /// Requires Capability `GroupNonUniform`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::elect())]
 #[doc(alias = "OpGroupNonUniformElect")]
 #[inline]
 pub fn subgroup_elect() -> bool {
@@ -175,7 +177,7 @@ pub fn subgroup_elect() -> bool {
 /// `predicate` must be a Boolean type.
 ///
 /// Requires Capability `GroupNonUniformVote`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::all(predicate))]
 #[doc(alias = "OpGroupNonUniformAll")]
 #[inline]
 pub fn subgroup_all(predicate: bool) -> bool {
@@ -207,7 +209,7 @@ pub fn subgroup_all(predicate: bool) -> bool {
 /// `predicate` must be a Boolean type.
 ///
 /// Requires Capability `GroupNonUniformVote`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::any(predicate))]
 #[doc(alias = "OpGroupNonUniformAny")]
 #[inline]
 pub fn subgroup_any(predicate: bool) -> bool {
@@ -239,7 +241,7 @@ pub fn subgroup_any(predicate: bool) -> bool {
 /// `value` must be a scalar or vector of floating-point type, integer type, or Boolean type. The compare operation is based on this type, and if it is a floating-point type, an ordered-and-equal compare is used.
 ///
 /// Requires Capability `GroupNonUniformVote`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::all_equal(value))]
 #[doc(alias = "OpGroupNonUniformAllEqual")]
 #[inline]
 pub fn subgroup_all_equal<T: ScalarComposite>(value: T) -> bool {
@@ -294,7 +296,7 @@ pub fn subgroup_all_equal<T: ScalarComposite>(value: T) -> bool {
 /// * Result is undefined if `id` is an inactive invocation or out of bounds
 /// * This variant with a dynamic `id` requires at least `spv1.5` or `vulkan1.2`. Alternatively, you can use
 /// [`subgroup_broadcast_const`] with a constant `id`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::broadcast(value, id))]
 #[doc(alias = "OpGroupNonUniformBroadcast")]
 #[inline]
 pub unsafe fn subgroup_broadcast<T: ScalarComposite>(value: T, id: u32) -> T {
@@ -345,7 +347,7 @@ pub unsafe fn subgroup_broadcast<T: ScalarComposite>(value: T, id: u32) -> T {
 ///
 /// # Safety
 /// * Result is undefined if `id` is an inactive invocation or out of bounds
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::broadcast(value, ID))]
 #[doc(alias = "OpGroupNonUniformBroadcast")]
 #[inline]
 pub unsafe fn subgroup_broadcast_const<T: ScalarOrVector, const ID: u32>(value: T) -> T {
@@ -385,7 +387,7 @@ pub unsafe fn subgroup_broadcast_const<T: ScalarOrVector, const ID: u32>(value:
 /// The type of `value` must be the same as Result Type.
 ///
 /// Requires Capability `GroupNonUniformBallot`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::broadcast_first(value))]
 #[doc(alias = "OpGroupNonUniformBroadcastFirst")]
 #[inline]
 pub fn subgroup_broadcast_first<T: ScalarComposite>(value: T) -> T {
@@ -425,7 +427,7 @@ pub fn subgroup_broadcast_first<T: ScalarComposite>(value: T) -> T {
 /// `predicate` must be a Boolean type.
 ///
 /// Requires Capability `GroupNonUniformBallot`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::ballot(predicate))]
 #[doc(alias = "OpGroupNonUniformBallot")]
 #[inline]
 pub fn subgroup_ballot(predicate: bool) -> SubgroupMask {
@@ -463,7 +465,7 @@ pub fn subgroup_ballot(predicate: bool) -> SubgroupMask {
 ///
 /// # Safety
 /// * `value` must be the same for all dynamic instances of this instruction
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::inverse_ballot(value))]
 #[doc(alias = "OpGroupNonUniformInverseBallot")]
 #[inline]
 pub unsafe fn subgroup_inverse_ballot(value: SubgroupMask) -> bool {
@@ -505,7 +507,7 @@ pub unsafe fn subgroup_inverse_ballot(value: SubgroupMask) -> bool {
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if `id` is out of bounds
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::ballot_bit_extract(value, index))]
 #[doc(alias = "OpGroupNonUniformBallotBitExtract")]
 #[inline]
 pub fn subgroup_ballot_bit_extract(value: SubgroupMask, index: u32) -> bool {
@@ -545,7 +547,7 @@ macro_rules! macro_subgroup_ballot_bit_count {
         /// `value` is a set of bitfields where the first invocation is represented in the lowest bit of the first vector component and the last (up to the size of the group) is the higher bit number of the last bitmask needed to represent all bits of the group invocations.
         ///
         /// Requires Capability `GroupNonUniformBallot`.
-        #[spirv_std_macros::gpu_only]
+        #[spirv_std_macros::gpu_only(cpu_emulation = emulated::ballot_bit_count(value, $group_op))]
         #[doc(alias = "OpGroupNonUniformBallotBitCount")]
         #[inline]
         pub fn $name(value: SubgroupMask) -> u32 {
@@ -595,7 +597,7 @@ macro_subgroup_ballot_bit_count!(
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if `id` is an inactive invocation or out of bounds
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::ballot_find_lsb(value))]
 #[doc(alias = "OpGroupNonUniformBallotFindLSB")]
 #[inline]
 pub fn subgroup_ballot_find_lsb(value: SubgroupMask) -> u32 {
@@ -628,7 +630,7 @@ pub fn subgroup_ballot_find_lsb(value: SubgroupMask) -> u32 {
 /// `value` is a set of bitfields where the first invocation is represented in the lowest bit of the first vector component and the last (up to the size of the group) is the higher bit number of the last bitmask needed to represent all bits of the group invocations.
 ///
 /// Requires Capability `GroupNonUniformBallot`.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::ballot_find_msb(value))]
 #[doc(alias = "OpGroupNonUniformBallotFindMSB")]
 #[inline]
 pub fn subgroup_ballot_find_msb(value: SubgroupMask) -> u32 {
@@ -667,7 +669,7 @@ pub fn subgroup_ballot_find_msb(value: SubgroupMask) -> u32 {
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if `id` is an inactive invocation or out of bounds
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::shuffle(value, id))]
 #[doc(alias = "OpGroupNonUniformShuffle")]
 #[inline]
 pub fn subgroup_shuffle<T: ScalarComposite>(value: T, id: u32) -> T {
@@ -717,7 +719,7 @@ pub fn subgroup_shuffle<T: ScalarComposite>(value: T, id: u32) -> T {
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if current invocation’s id within the group xor’ed with `mask` is an inactive invocation or out of bounds
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::shuffle_xor(value, mask))]
 #[doc(alias = "OpGroupNonUniformShuffleXor")]
 #[inline]
 pub fn subgroup_shuffle_xor<T: ScalarComposite>(value: T, mask: u32) -> T {
@@ -767,7 +769,7 @@ pub fn subgroup_shuffle_xor<T: ScalarComposite>(value: T, mask: u32) -> T {
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if `delta` is greater than the current invocation’s id within the group or if the selected lane is inactive
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::shuffle_up(value, delta))]
 #[doc(alias = "OpGroupNonUniformShuffleUp")]
 #[inline]
 pub fn subgroup_shuffle_up<T: ScalarComposite>(value: T, delta: u32) -> T {
@@ -817,7 +819,7 @@ pub fn subgroup_shuffle_up<T: ScalarComposite>(value: T, delta: u32) -> T {
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if `delta` is greater than or equal to the size of the group, or if the current invocation’s id within the group + `delta` is either an inactive invocation or greater than or equal to the size of the group.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::shuffle_down(value, delta))]
 #[doc(alias = "OpGroupNonUniformShuffleDown")]
 #[inline]
 pub fn subgroup_shuffle_down<T: ScalarComposite>(value: T, delta: u32) -> T {
@@ -853,7 +855,7 @@ pub fn subgroup_shuffle_down<T: ScalarComposite>(value: T, delta: u32) -> T {
 macro_rules! macro_subgroup_op {
     ($scalar:ty, $asm_op:literal, $($name:ident, $group_op:expr),+; $docs:literal) => { $(
         #[doc = $docs]
-        #[spirv_std_macros::gpu_only]
+        #[spirv_std_macros::gpu_only(cpu_emulation = emulated::group_op($asm_op, $group_op, value))]
         #[doc(alias = $asm_op)]
         #[inline]
         pub fn $name<I: ScalarOrVector<Scalar = $scalar>>(
@@ -881,7 +883,9 @@ macro_rules! macro_subgroup_op {
 macro_rules! macro_subgroup_op_clustered {
     ($scalar:ty, $asm_op:literal, $name:ident; $docs:literal) => {
         #[doc = $docs]
-        #[spirv_std_macros::gpu_only]
+        #[spirv_std_macros::gpu_only(
+                    cpu_emulation = emulated::group_op_clustered($asm_op, CLUSTER_SIZE, value)
+                )]
         #[doc(alias = $asm_op)]
         #[inline]
         pub unsafe fn $name<const CLUSTER_SIZE: u32, I: ScalarOrVector<Scalar = $scalar>>(
@@ -1453,7 +1457,7 @@ Requires Capability `GroupNonUniformArithmetic` and `GroupNonUniformClustered`.
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if the value of `index` is greater than or equal to 4, or refers to an inactive invocation
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::quad_broadcast(value, index))]
 #[doc(alias = "OpGroupNonUniformQuadBroadcast")]
 #[inline]
 pub fn subgroup_quad_broadcast<T: ScalarComposite>(value: T, index: u32) -> T {
@@ -1545,7 +1549,7 @@ pub enum QuadDirection {
 /// # Safety
 /// * This function is safe
 /// * Result is undefined if an active invocation reads `value` from an inactive invocation
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::quad_swap(value, DIRECTION))]
 #[doc(alias = "OpGroupNonUniformQuadSwap")]
 #[inline]
 pub fn subgroup_quad_swap<const DIRECTION: u32, T: ScalarComposite>(value: T) -> T {
@@ -1575,3 +1579,59 @@ pub fn subgroup_quad_swap<const DIRECTION: u32, T: ScalarComposite>(value: T) ->
 
     value.transform(&mut Transform::<DIRECTION>)
 }
+
+/// Evaluates a `predicate` for all active invocations in the quad, resulting in true if `predicate` evaluates to true for all active invocations in the quad, otherwise the result is false.
+///
+/// Result Type must be a Boolean type.
+///
+/// `predicate` must be a Boolean type.
+///
+/// Requires Capability `QuadControlKHR`, which (along with the `SPV_KHR_quad_control`
+/// extension) is added automatically.
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::quad_all(predicate))]
+#[doc(alias = "OpGroupNonUniformQuadAllKHR")]
+#[inline]
+pub fn subgroup_quad_all(predicate: bool) -> bool {
+    let mut result = false;
+
+    unsafe {
+        asm! {
+            "%bool = OpTypeBool",
+            "%predicate = OpLoad _ {predicate}",
+            "%result = OpGroupNonUniformQuadAllKHR %bool %predicate",
+            "OpStore {result} %result",
+            predicate = in(reg) &predicate,
+            result = in(reg) &mut result,
+        }
+    }
+
+    result
+}
+
+/// Evaluates a `predicate` for all active invocations in the quad, resulting in true if `predicate` evaluates to true for any active invocation in the quad, otherwise the result is false.
+///
+/// Result Type must be a Boolean type.
+///
+/// `predicate` must be a Boolean type.
+///
+/// Requires Capability `QuadControlKHR`, which (along with the `SPV_KHR_quad_control`
+/// extension) is added automatically.
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::quad_any(predicate))]
+#[doc(alias = "OpGroupNonUniformQuadAnyKHR")]
+#[inline]
+pub fn subgroup_quad_any(predicate: bool) -> bool {
+    let mut result = false;
+
+    unsafe {
+        asm! {
+            "%bool = OpTypeBool",
+            "%predicate = OpLoad _ {predicate}",
+            "%result = OpGroupNonUniformQuadAnyKHR %bool %predicate",
+            "OpStore {result} %result",
+            predicate = in(reg) &predicate,
+            result = in(reg) &mut result,
+        }
+    }
+
+    result
+}
@@ -0,0 +1,603 @@
+//! Cooperative matrices, from `SPV_KHR_cooperative_matrix`.
+//!
+//! A [`CooperativeMatrix`] is a matrix whose storage and computations are spread across all
+//! invocations within a `SCOPE` (typically a subgroup), which must all execute the same
+//! operations on it, in uniform control flow. This maps to e.g. tensor cores on hardware
+//! supporting it, and is mostly interesting for matrix multiplication (see
+//! [`CooperativeMatrix::mul_add`]).
+//!
+//! Using cooperative matrices requires the `CooperativeMatrixKHR` capability and the
+//! `SPV_KHR_cooperative_matrix` extension, and the supported combinations of component types,
+//! scopes and sizes must be queried from the device (with
+//! `vkGetPhysicalDeviceCooperativeMatrixPropertiesKHR`), e.g.:
+//! ```text
+//! SpirvBuilder::new(...)
+//!     .capability(Capability::CooperativeMatrixKHR)
+//!     .extension("SPV_KHR_cooperative_matrix")
+//! ```
+//!
+//! # Example
+//! ```no_run
+//! # use spirv_std::cooperative_matrix::*;
+//! # use spirv_std::glam::UVec3;
+//! # use spirv_std::spirv;
+//! #[spirv(compute(threads(32)))]
+//! pub fn main_cs(
+//!     #[spirv(workgroup_id)] id: UVec3,
+//!     #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] a: &[f32],
+//!     #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] b: &[f32],
+//!     #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] c: &mut [f32],
+//! ) {
+//!     let offset = id.x as usize * 16 * 16;
+//!     let layout = CooperativeMatrixLayout::RowMajor;
+//!     let a = SubgroupMatrixA::<f32, 16, 16>::load(a, offset, 16, layout);
+//!     let b = SubgroupMatrixB::<f32, 16, 16>::load(b, offset, 16, layout);
+//!     let acc = SubgroupMatrixAccumulator::<f32, 16, 16>::splat(0.0);
+//!     acc.mul_add(a, b).store(c, offset, 16, layout);
+//! }
+//! ```
+
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use core::marker::PhantomData;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::Scalar;
+use crate::memory::Scope;
+
+/// How a [`CooperativeMatrix`] is going to be used, which is part of its type
+/// (i.e. the `USE` const generic parameter is one of these, `as u32`).
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CooperativeMatrixUse {
+    /// The `A` (i.e. left-hand side) operand of [`CooperativeMatrix::mul_add`].
+    MatrixA = 0,
+    /// The `B` (i.e. right-hand side) operand of [`CooperativeMatrix::mul_add`].
+    MatrixB = 1,
+    /// The accumulator (and result) of [`CooperativeMatrix::mul_add`].
+    MatrixAccumulator = 2,
+}
+
+/// How the elements of a [`CooperativeMatrix`] are laid out in memory, when loading or storing it.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CooperativeMatrixLayout {
+    /// Consecutive elements of a row are adjacent in memory, and rows are `stride` elements apart.
+    RowMajor = 0,
+    /// Consecutive elements of a column are adjacent in memory, and columns are `stride` elements
+    /// apart.
+    ColumnMajor = 1,
+}
+
+impl CooperativeMatrixLayout {
+    /// The number of elements accessed, starting from the first one, when loading or storing a
+    /// `rows`x`columns` matrix with this layout, i.e. the minimum length of the memory region.
+    #[inline]
+    pub const fn required_len(self, rows: u32, columns: u32, stride: u32) -> usize {
+        let (major, minor) = match self {
+            Self::RowMajor => (rows, columns),
+            Self::ColumnMajor => (columns, rows),
+        };
+        if major == 0 || minor == 0 {
+            0
+        } else {
+            (major as usize - 1) * stride as usize + minor as usize
+        }
+    }
+}
+
+/// A `ROWS`x`COLS` matrix of `T`s, spread across all invocations within `SCOPE` (a
+/// [`Scope`] `as u32`), for the `USE` (a [`CooperativeMatrixUse`] `as u32`) operand of
+/// [`CooperativeMatrix::mul_add`].
+///
+/// See the [module-level documentation](self) for more details, and the type aliases
+/// (e.g. [`SubgroupMatrixA`]) for the more common use of subgroup scope.
+#[spirv(cooperative_matrix)]
+// HACK(eddyb) avoids "transparent newtype of `_anti_zst_padding`" misinterpretation.
+#[repr(C)]
+pub struct CooperativeMatrix<T, const SCOPE: u32, const ROWS: u32, const COLS: u32, const USE: u32>
+{
+    // HACK(eddyb) avoids the layout becoming ZST (and being elided in one way
+    // or another, before `#[spirv(cooperative_matrix)]` can special-case it).
+    _anti_zst_padding: core::mem::MaybeUninit<u32>,
+    _marker: PhantomData<T>,
+}
+
+// NOTE manual impls to avoid requiring `T: Clone`/`T: Copy`.
+impl<T, const SCOPE: u32, const ROWS: u32, const COLS: u32, const USE: u32> Clone
+    for CooperativeMatrix<T, SCOPE, ROWS, COLS, USE>
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const SCOPE: u32, const ROWS: u32, const COLS: u32, const USE: u32> Copy
+    for CooperativeMatrix<T, SCOPE, ROWS, COLS, USE>
+{
+}
+
+/// A subgroup-scoped `A` operand of [`CooperativeMatrix::mul_add`], with `M` rows and `K` columns.
+pub type SubgroupMatrixA<T, const M: u32, const K: u32> = CooperativeMatrix<
+    T,
+    { Scope::Subgroup as u32 },
+    M,
+    K,
+    { CooperativeMatrixUse::MatrixA as u32 },
+>;
+
+/// A subgroup-scoped `B` operand of [`CooperativeMatrix::mul_add`], with `K` rows and `N` columns.
+pub type SubgroupMatrixB<T, const K: u32, const N: u32> = CooperativeMatrix<
+    T,
+    { Scope::Subgroup as u32 },
+    K,
+    N,
+    { CooperativeMatrixUse::MatrixB as u32 },
+>;
+
+/// A subgroup-scoped accumulator of [`CooperativeMatrix::mul_add`], with `M` rows and `N` columns.
+pub type SubgroupMatrixAccumulator<T, const M: u32, const N: u32> = CooperativeMatrix<
+    T,
+    { Scope::Subgroup as u32 },
+    M,
+    N,
+    { CooperativeMatrixUse::MatrixAccumulator as u32 },
+>;
+
+/// Scalar types which can be the components of a [`CooperativeMatrix`].
+///
+/// # Safety
+/// Must only be implemented for integer and floating-point scalar types.
+pub unsafe trait CooperativeMatrixComponent: Scalar {
+    /// Whether this is a signed integer type, which affects the interpretation
+    /// of the components by [`CooperativeMatrix::mul_add`].
+    const SIGNED: bool;
+}
+
+macro_rules! impl_component {
+    ($($ty:ty: $signed:literal),+ $(,)?) => {$(
+        unsafe impl CooperativeMatrixComponent for $ty {
+            const SIGNED: bool = $signed;
+        }
+    )+};
+}
+
+impl_component! {
+    u8: false, u16: false, u32: false, u64: false,
+    i8: true, i16: true, i32: true, i64: true,
+    f32: false, f64: false,
+}
+#[cfg(feature = "f16")]
+impl_component!(f16: false);
+
+#[cfg(target_arch = "spirv")]
+macro_rules! load_store_asm {
+    (load $layout:literal, $ptr:expr, $stride:expr, $result_slot:expr) => {
+        asm! {
+            "%u32 = OpTypeInt 32 0",
+            concat!("%layout = OpConstant %u32 ", $layout),
+            "%result = OpCooperativeMatrixLoadKHR typeof*{result_slot} {ptr} %layout {stride}",
+            "OpStore {result_slot} %result",
+            ptr = in(reg) $ptr,
+            stride = in(reg) $stride,
+            result_slot = in(reg) $result_slot,
+        }
+    };
+    (store $layout:literal, $ptr:expr, $stride:expr, $this:expr) => {
+        asm! {
+            "%u32 = OpTypeInt 32 0",
+            concat!("%layout = OpConstant %u32 ", $layout),
+            "%this = OpLoad _ {this}",
+            "OpCooperativeMatrixStoreKHR {ptr} %this %layout {stride}",
+            ptr = in(reg) $ptr,
+            stride = in(reg) $stride,
+            this = in(reg) $this,
+        }
+    };
+}
+
+impl<
+    T: CooperativeMatrixComponent,
+    const SCOPE: u32,
+    const ROWS: u32,
+    const COLS: u32,
+    const USE: u32,
+> CooperativeMatrix<T, SCOPE, ROWS, COLS, USE>
+{
+    /// Loads a matrix from `data`, starting at the element `offset`, with rows (or columns, for
+    /// [`CooperativeMatrixLayout::ColumnMajor`]) `stride` elements apart.
+    ///
+    /// `data` must be a storage buffer (for workgroup arrays, see [`Self::load_array`]).
+    ///
+    /// # Panics
+    /// Panics if the matrix doesn't fit within `data` (see
+    /// [`CooperativeMatrixLayout::required_len`]).
+    #[inline]
+    pub fn load(data: &[T], offset: usize, stride: u32, layout: CooperativeMatrixLayout) -> Self {
+        Self::assert_in_bounds(data.len(), offset, stride, layout);
+        unsafe { Self::load_unchecked(&data[offset], stride, layout) }
+    }
+
+    /// Loads a matrix from the array `data`, like [`Self::load`].
+    ///
+    /// This is mostly useful for workgroup (i.e. shared memory) arrays, which
+    /// can't be turned into slices.
+    ///
+    /// # Panics
+    /// Panics if the matrix doesn't fit within `data` (see
+    /// [`CooperativeMatrixLayout::required_len`]).
+    #[inline]
+    pub fn load_array<const N: usize>(
+        data: &[T; N],
+        offset: usize,
+        stride: u32,
+        layout: CooperativeMatrixLayout,
+    ) -> Self {
+        Self::assert_in_bounds(N, offset, stride, layout);
+        unsafe { Self::load_unchecked(&data[offset], stride, layout) }
+    }
+
+    /// Loads a matrix starting at `ptr`, with rows (or columns, for
+    /// [`CooperativeMatrixLayout::ColumnMajor`]) `stride` elements apart.
+    ///
+    /// # Safety
+    /// `ptr` must point into a storage buffer, or a workgroup (i.e. shared memory) array,
+    /// with at least [`CooperativeMatrixLayout::required_len`] elements starting at `ptr`.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpCooperativeMatrixLoadKHR")]
+    #[inline]
+    pub unsafe fn load_unchecked(ptr: &T, stride: u32, layout: CooperativeMatrixLayout) -> Self {
+        unsafe {
+            let mut result_slot = core::mem::MaybeUninit::uninit();
+            match layout {
+                CooperativeMatrixLayout::RowMajor => {
+                    load_store_asm!(load "0", ptr, stride, result_slot.as_mut_ptr());
+                }
+                CooperativeMatrixLayout::ColumnMajor => {
+                    load_store_asm!(load "1", ptr, stride, result_slot.as_mut_ptr());
+                }
+            }
+            result_slot.assume_init()
+        }
+    }
+
+    /// Stores the matrix into `data`, starting at the element `offset`, with rows (or columns, for
+    /// [`CooperativeMatrixLayout::ColumnMajor`]) `stride` elements apart.
+    ///
+    /// `data` must be a storage buffer (for workgroup arrays, see [`Self::store_array`]).
+    ///
+    /// # Panics
+    /// Panics if the matrix doesn't fit within `data` (see
+    /// [`CooperativeMatrixLayout::required_len`]).
+    #[inline]
+    pub fn store(
+        self,
+        data: &mut [T],
+        offset: usize,
+        stride: u32,
+        layout: CooperativeMatrixLayout,
+    ) {
+        Self::assert_in_bounds(data.len(), offset, stride, layout);
+        unsafe { self.store_unchecked(&mut data[offset], stride, layout) }
+    }
+
+    /// Stores the matrix into the array `data`, like [`Self::store`].
+    ///
+    /// This is mostly useful for workgroup (i.e. shared memory) arrays, which
+    /// can't be turned into slices.
+    ///
+    /// # Panics
+    /// Panics if the matrix doesn't fit within `data` (see
+    /// [`CooperativeMatrixLayout::required_len`]).
+    #[inline]
+    pub fn store_array<const N: usize>(
+        self,
+        data: &mut [T; N],
+        offset: usize,
+        stride: u32,
+        layout: CooperativeMatrixLayout,
+    ) {
+        Self::assert_in_bounds(N, offset, stride, layout);
+        unsafe { self.store_unchecked(&mut data[offset], stride, layout) }
+    }
+
+    #[inline]
+    fn assert_in_bounds(len: usize, offset: usize, stride: u32, layout: CooperativeMatrixLayout) {
+        if offset >= len || len - offset < layout.required_len(ROWS, COLS, stride) {
+            panic!("cooperative matrix out of bounds");
+        }
+    }
+
+    /// Stores the matrix starting at `ptr`, with rows (or columns, for
+    /// [`CooperativeMatrixLayout::ColumnMajor`]) `stride` elements apart.
+    ///
+    /// # Safety
+    /// `ptr` must point into a storage buffer, or a workgroup (i.e. shared memory) array,
+    /// with at least [`CooperativeMatrixLayout::required_len`] elements starting at `ptr`.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpCooperativeMatrixStoreKHR")]
+    #[inline]
+    pub unsafe fn store_unchecked(self, ptr: &mut T, stride: u32, layout: CooperativeMatrixLayout) {
+        unsafe {
+            match layout {
+                CooperativeMatrixLayout::RowMajor => {
+                    load_store_asm!(store "0", ptr, stride, &self);
+                }
+                CooperativeMatrixLayout::ColumnMajor => {
+                    load_store_asm!(store "1", ptr, stride, &self);
+                }
+            }
+        }
+    }
+
+    /// Creates a matrix with all elements set to `value`.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpCompositeConstruct")]
+    #[inline]
+    pub fn splat(value: T) -> Self {
+        unsafe {
+            let mut result_slot = core::mem::MaybeUninit::uninit();
+            asm! {
+                "%value = OpLoad _ {value}",
+                "%result = OpCompositeConstruct typeof*{result_slot} %value",
+                "OpStore {result_slot} %result",
+                value = in(reg) &value,
+                result_slot = in(reg) result_slot.as_mut_ptr(),
+            }
+            result_slot.assume_init()
+        }
+    }
+
+    /// The number of elements of the matrix owned by the current invocation, which can be
+    /// accessed with [`Self::get`] and [`Self::set`].
+    ///
+    /// Which elements of the matrix these are is implementation-defined, so this is mostly useful
+    /// for element-wise operations which don't depend on the position of each element.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpCooperativeMatrixLengthKHR")]
+    #[inline]
+    pub fn length(&self) -> u32 {
+        unsafe {
+            let mut result = 0;
+            asm! {
+                "%u32 = OpTypeInt 32 0",
+                "%result = OpCooperativeMatrixLengthKHR %u32 typeof*{this}",
+                "OpStore {result} %result",
+                this = in(reg) self,
+                result = in(reg) &mut result,
+            }
+            result
+        }
+    }
+
+    /// Returns the `index`-th element owned by the current invocation (see [`Self::length`]).
+    ///
+    /// # Panics
+    /// Panics if `index >= self.length()`.
+    #[inline]
+    pub fn get(&self, index: u32) -> T {
+        if index >= self.length() {
+            panic!("cooperative matrix element index out of bounds");
+        }
+        unsafe { self.get_unchecked(index) }
+    }
+
+    /// Returns the `index`-th element owned by the current invocation (see [`Self::length`]).
+    ///
+    /// # Safety
+    /// `index` must be less than `self.length()`.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpAccessChain")]
+    #[inline]
+    pub unsafe fn get_unchecked(&self, index: u32) -> T {
+        unsafe {
+            let mut result = T::default();
+            asm! {
+                "%element_ptr = OpAccessChain typeof{result} {this} {index}",
+                "%element = OpLoad _ %element_ptr",
+                "OpStore {result} %element",
+                this = in(reg) self,
+                index = in(reg) index,
+                result = in(reg) &mut result,
+            }
+            result
+        }
+    }
+
+    /// Sets the `index`-th element owned by the current invocation (see [`Self::length`]).
+    ///
+    /// # Panics
+    /// Panics if `index >= self.length()`.
+    #[inline]
+    pub fn set(&mut self, index: u32, value: T) {
+        if index >= self.length() {
+            panic!("cooperative matrix element index out of bounds");
+        }
+        unsafe { self.set_unchecked(index, value) }
+    }
+
+    /// Sets the `index`-th element owned by the current invocation (see [`Self::length`]).
+    ///
+    /// # Safety
+    /// `index` must be less than `self.length()`.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpAccessChain")]
+    #[inline]
+    pub unsafe fn set_unchecked(&mut self, index: u32, value: T) {
+        unsafe {
+            asm! {
+                "%element_ptr = OpAccessChain typeof{value} {this} {index}",
+                "%value = OpLoad _ {value}",
+                "OpStore %element_ptr %value",
+                this = in(reg) self,
+                index = in(reg) index,
+                value = in(reg) &value,
+            }
+        }
+    }
+
+    /// Applies `f` to each element owned by the current invocation (see [`Self::length`]).
+    #[inline]
+    #[must_use]
+    pub fn map(mut self, mut f: impl FnMut(T) -> T) -> Self {
+        let mut i = 0;
+        while i < self.length() {
+            unsafe { self.set_unchecked(i, f(self.get_unchecked(i))) };
+            i += 1;
+        }
+        self
+    }
+}
+
+impl<T: CooperativeMatrixComponent, const SCOPE: u32, const ROWS: u32, const COLS: u32>
+    CooperativeMatrix<T, SCOPE, ROWS, COLS, { CooperativeMatrixUse::MatrixAccumulator as u32 }>
+{
+    /// Returns `a * b + self`, i.e. the (linear algebra) product of `a` and `b`, accumulated
+    /// with `self`.
+    ///
+    /// Signed integer components are sign-extended, and unsigned ones zero-extended,
+    /// when their width differs from that of `T`.
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpCooperativeMatrixMulAddKHR")]
+    #[inline]
+    #[must_use]
+    pub fn mul_add<A: CooperativeMatrixComponent, B: CooperativeMatrixComponent, const K: u32>(
+        self,
+        a: CooperativeMatrix<A, SCOPE, ROWS, K, { CooperativeMatrixUse::MatrixA as u32 }>,
+        b: CooperativeMatrix<B, SCOPE, K, COLS, { CooperativeMatrixUse::MatrixB as u32 }>,
+    ) -> Self {
+        unsafe {
+            let mut result_slot = core::mem::MaybeUninit::uninit();
+            asm! {
+                "%a = OpLoad _ {a}",
+                "%b = OpLoad _ {b}",
+                "%c = OpLoad _ {c}",
+                "%result = OpCooperativeMatrixMulAddKHR typeof*{result_slot} %a %b %c {operands}",
+                "OpStore {result_slot} %result",
+                a = in(reg) &a,
+                b = in(reg) &b,
+                c = in(reg) &self,
+                result_slot = in(reg) result_slot.as_mut_ptr(),
+                // `MatrixASignedComponentsKHR | MatrixBSignedComponentsKHR |
+                //  MatrixCSignedComponentsKHR | MatrixResultSignedComponentsKHR`, as needed.
+                operands = const (A::SIGNED as u32)
+                    | (B::SIGNED as u32) << 1
+                    | (T::SIGNED as u32) << 2
+                    | (T::SIGNED as u32) << 3,
+            }
+            result_slot.assume_init()
+        }
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($trait:ident $method:ident = $op:literal for $($ty:ty),+) => {$(
+        impl<const SCOPE: u32, const ROWS: u32, const COLS: u32, const USE: u32> $trait
+            for CooperativeMatrix<$ty, SCOPE, ROWS, COLS, USE>
+        {
+            type Output = Self;
+            #[spirv_std_macros::gpu_only]
+            #[doc(alias = $op)]
+            #[inline]
+            fn $method(self, rhs: Self) -> Self {
+                unsafe {
+                    let mut result_slot = core::mem::MaybeUninit::uninit();
+                    asm! {
+                        "%lhs = OpLoad _ {lhs}",
+                        "%rhs = OpLoad _ {rhs}",
+                        concat!("%result = ", $op, " typeof*{result_slot} %lhs %rhs"),
+                        "OpStore {result_slot} %result",
+                        lhs = in(reg) &self,
+                        rhs = in(reg) &rhs,
+                        result_slot = in(reg) result_slot.as_mut_ptr(),
+                    }
+                    result_slot.assume_init()
+                }
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_neg {
+    ($op:literal for $($ty:ty),+) => {$(
+        impl<const SCOPE: u32, const ROWS: u32, const COLS: u32, const USE: u32> Neg
+            for CooperativeMatrix<$ty, SCOPE, ROWS, COLS, USE>
+        {
+            type Output = Self;
+            #[spirv_std_macros::gpu_only]
+            #[doc(alias = $op)]
+            #[inline]
+            fn neg(self) -> Self {
+                unsafe {
+                    let mut result_slot = core::mem::MaybeUninit::uninit();
+                    asm! {
+                        "%this = OpLoad _ {this}",
+                        concat!("%result = ", $op, " typeof*{result_slot} %this"),
+                        "OpStore {result_slot} %result",
+                        this = in(reg) &self,
+                        result_slot = in(reg) result_slot.as_mut_ptr(),
+                    }
+                    result_slot.assume_init()
+                }
+            }
+        }
+    )+};
+}
+
+// NOTE element-wise multiplication isn't allowed by `SPV_KHR_cooperative_matrix`,
+// only scaling by a scalar (see the `Mul<T>` impl below) and `mul_add`.
+macro_rules! impl_ops {
+    (float: $($ty:ty),+) => {
+        impl_binary_op!(Add add = "OpFAdd" for $($ty),+);
+        impl_binary_op!(Sub sub = "OpFSub" for $($ty),+);
+        impl_binary_op!(Div div = "OpFDiv" for $($ty),+);
+        impl_neg!("OpFNegate" for $($ty),+);
+    };
+    (signed: $($ty:ty),+) => {
+        impl_binary_op!(Add add = "OpIAdd" for $($ty),+);
+        impl_binary_op!(Sub sub = "OpISub" for $($ty),+);
+        impl_binary_op!(Div div = "OpSDiv" for $($ty),+);
+        impl_neg!("OpSNegate" for $($ty),+);
+    };
+    (unsigned: $($ty:ty),+) => {
+        impl_binary_op!(Add add = "OpIAdd" for $($ty),+);
+        impl_binary_op!(Sub sub = "OpISub" for $($ty),+);
+        impl_binary_op!(Div div = "OpUDiv" for $($ty),+);
+    };
+}
+
+impl_ops!(float: f32, f64);
+#[cfg(feature = "f16")]
+impl_ops!(float: f16);
+impl_ops!(signed: i8, i16, i32, i64);
+impl_ops!(unsigned: u8, u16, u32, u64);
+
+impl<
+    T: CooperativeMatrixComponent,
+    const SCOPE: u32,
+    const ROWS: u32,
+    const COLS: u32,
+    const USE: u32,
+> Mul<T> for CooperativeMatrix<T, SCOPE, ROWS, COLS, USE>
+{
+    type Output = Self;
+    #[spirv_std_macros::gpu_only]
+    #[doc(alias = "OpMatrixTimesScalar")]
+    #[inline]
+    fn mul(self, rhs: T) -> Self {
+        unsafe {
+            let mut result_slot = core::mem::MaybeUninit::uninit();
+            asm! {
+                "%this = OpLoad _ {this}",
+                "%rhs = OpLoad _ {rhs}",
+                "%result = OpMatrixTimesScalar typeof*{result_slot} %this %rhs",
+                "OpStore {result_slot} %result",
+                this = in(reg) &self,
+                rhs = in(reg) &rhs,
+                result_slot = in(reg) result_slot.as_mut_ptr(),
+            }
+            result_slot.assume_init()
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Emulation of atomic instructions, using (address-striped) locks.
+
+use super::scalar::{BinOp, ScalarValue};
+use crate::Scalar;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+static LOCKS: [Mutex<()>; 64] = [const { Mutex::new(()) }; 64];
+
+fn lock<T>(ptr: *const T) -> MutexGuard<'static, ()> {
+    LOCKS[(ptr as usize / 8) % LOCKS.len()]
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Atomically replace `*ptr` with `f(*ptr)`, returning the original value.
+///
+/// # Safety
+/// `ptr` must be valid for reads and writes, and only ever be accessed
+/// through these functions, while other invocations may access it.
+pub(crate) unsafe fn atomic_update<S: Scalar>(ptr: *mut S, f: impl FnOnce(S) -> S) -> S {
+    let _guard = lock(ptr);
+    unsafe {
+        let old = ptr.read_volatile();
+        ptr.write_volatile(f(old));
+        old
+    }
+}
+
+/// # Safety
+/// See `atomic_update`.
+pub(crate) unsafe fn atomic_load<S: Scalar>(ptr: *const S) -> S {
+    let _guard = lock(ptr);
+    unsafe { ptr.read_volatile() }
+}
+
+/// # Safety
+/// See `atomic_update`.
+pub(crate) unsafe fn atomic_binop<S: Scalar>(ptr: *mut S, op: BinOp, value: S) -> S {
+    unsafe {
+        atomic_update(ptr, |old| {
+            op.apply(
+                ScalarValue::from_scalar(old),
+                ScalarValue::from_scalar(value),
+            )
+            .to_scalar()
+        })
+    }
+}
+
+/// # Safety
+/// See `atomic_update`.
+pub(crate) unsafe fn atomic_sub<S: Scalar>(ptr: *mut S, value: S) -> S {
+    // Wrapping subtraction, as the addition of the two's complement negation.
+    let value = ScalarValue::from_scalar(value);
+    let negated = BinOp::Add.apply(
+        BinOp::Xor.apply(value, BinOp::And.identity(value)),
+        value.with_f64(1.0),
+    );
+    unsafe { atomic_binop(ptr, BinOp::Add, negated.to_scalar()) }
+}
+
+/// Integer `1` of the same type as `like`.
+pub(crate) fn one<S: Scalar>(like: S) -> S {
+    ScalarValue::from_scalar(like).with_f64(1.0).to_scalar()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{ComputeDispatch, Shared};
+    use crate::arch::*;
+    use crate::memory::{Scope, Semantics};
+    use glam::UVec3;
+
+    const DEVICE: u32 = Scope::Device as u32;
+    const NONE: u32 = Semantics::NONE.bits();
+
+    #[test]
+    fn add_from_every_invocation() {
+        let counters = Shared::new([0u32, 1000]);
+        ComputeDispatch::new(UVec3::new(4, 1, 1), UVec3::new(64, 1, 1)).run(|invocation| {
+            let counters = unsafe { counters.get_mut() };
+            let id = invocation.global_invocation_id.x;
+            unsafe {
+                atomic_i_add::<_, DEVICE, NONE>(&mut counters[0], id);
+                atomic_i_sub::<_, DEVICE, NONE>(&mut counters[1], 1);
+            }
+        });
+        assert_eq!(counters.into_inner(), [(0..256).sum(), 1000 - 256]);
+    }
+
+    #[test]
+    fn add_from_many_threads() {
+        let mut counter = 0u64;
+        let counter_ptr = &raw mut counter as usize;
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        unsafe {
+                            atomic_i_increment::<_, DEVICE, NONE>(&mut *(counter_ptr as *mut u64));
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(counter, 8000);
+    }
+
+    #[test]
+    fn min_max_and_bitwise() {
+        let (mut u, mut s, mut f, mut bits) = (10u32, -5i32, 1.5f32, 0b1100u8);
+        unsafe {
+            assert_eq!(atomic_u_min::<_, DEVICE, NONE>(&mut u, 3), 10);
+            assert_eq!(atomic_u_max::<_, DEVICE, NONE>(&mut u, 2), 3);
+            assert_eq!(atomic_s_min::<_, DEVICE, NONE>(&mut s, -7), -5);
+            assert_eq!(atomic_s_max::<_, DEVICE, NONE>(&mut s, -6), -7);
+            assert_eq!(atomic_f_max::<_, DEVICE, NONE>(&mut f, 2.5), 1.5);
+            assert_eq!(atomic_f_add::<_, DEVICE, NONE>(&mut f, 0.5), 2.5);
+            assert_eq!(atomic_and::<_, DEVICE, NONE>(&mut bits, 0b1010), 0b1100);
+            assert_eq!(atomic_or::<_, DEVICE, NONE>(&mut bits, 0b0001), 0b1000);
+            assert_eq!(atomic_xor::<_, DEVICE, NONE>(&mut bits, 0b1111), 0b1001);
+            assert_eq!(atomic_i_decrement::<_, DEVICE, NONE>(&mut s), -6);
+        }
+        assert_eq!((u, s, f, bits), (3, -7, 3.0, 0b0110));
+    }
+
+    #[test]
+    fn wrapping_sub() {
+        let (mut u, mut i) = (1u8, i64::MIN);
+        unsafe {
+            atomic_i_sub::<_, DEVICE, NONE>(&mut u, 2);
+            atomic_i_sub::<_, DEVICE, NONE>(&mut i, 1);
+        }
+        assert_eq!((u, i), (u8::MAX, i64::MAX));
+    }
+
+    #[test]
+    fn exchange_and_compare_exchange() {
+        let mut x = 1u32;
+        unsafe {
+            assert_eq!(atomic_exchange::<_, DEVICE, NONE>(&mut x, 2), 1);
+            assert_eq!(
+                atomic_compare_exchange::<_, DEVICE, NONE, NONE>(&mut x, 3, 1),
+                2
+            );
+            assert_eq!(atomic_load::<_, DEVICE, NONE>(&x), 2);
+            assert_eq!(
+                atomic_compare_exchange::<_, DEVICE, NONE, NONE>(&mut x, 3, 2),
+                2
+            );
+            let old = atomic_load::<_, DEVICE, NONE>(&x);
+            atomic_store::<_, DEVICE, NONE>(&mut x, old + 1);
+        }
+        assert_eq!(x, 4);
+    }
+}
@@ -0,0 +1,656 @@
+//! Running shader invocations as cooperatively scheduled coroutines.
+
+use crate::memory::Scope;
+use core::any::TypeId;
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::ptr;
+use corosensei::stack::DefaultStack;
+use corosensei::{CoroutineResult, ScopedCoroutine, Yielder};
+use glam::{UVec2, UVec3, Vec4};
+use std::format;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::vec::Vec;
+
+/// Memory shared between invocations (e.g. workgroup memory, or the contents
+/// of a storage buffer), which shaders expect to access through `&mut`.
+///
+/// This is the host equivalent of e.g. a `#[spirv(workgroup)] &mut [u32; N]`
+/// or `#[spirv(storage_buffer, ...)] &mut [u32]` entry-point parameter.
+#[derive(Default)]
+pub struct Shared<T: ?Sized>(UnsafeCell<T>);
+
+// SAFETY: accesses from multiple invocations are governed by `get_mut`'s
+// safety requirements, same as on a GPU.
+unsafe impl<T: ?Sized + Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    /// Wrap `value`, to make it accessible from all invocations.
+    pub fn new(value: T) -> Self {
+        Self(UnsafeCell::new(value))
+    }
+
+    /// Unwrap the value, after all invocations are done with it.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ?Sized> Shared<T> {
+    /// Get a mutable reference to the value, e.g. to pass to a shader.
+    ///
+    /// # Safety
+    /// Like on a GPU, invocations must not access the same memory at the same
+    /// time (unless all those accesses are reads, or done through atomics),
+    /// and should instead synchronize using e.g. barriers.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_mut(&self) -> &mut T {
+        unsafe { &mut *self.0.get() }
+    }
+
+    /// Get a mutable reference to the value, when not shared with invocations.
+    pub fn get_mut_exclusive(&mut self) -> &mut T {
+        self.0.get_mut()
+    }
+}
+
+/// Why an invocation suspended its execution (see `run_group`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Wait {
+    /// Waiting for all (remaining) invocations in the workgroup.
+    Workgroup,
+
+    /// Waiting for all (remaining) invocations in the subgroup.
+    Subgroup,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum InvocationState {
+    Ready,
+    Waiting(Wait),
+    Done,
+}
+
+/// A value published by a subgroup lane, for the other lanes to read.
+#[derive(Copy, Clone)]
+struct Slot {
+    op: &'static str,
+    type_id: TypeId,
+    value: *const (),
+}
+
+struct GroupState {
+    subgroup_size: u32,
+
+    /// The `Yielder` of every invocation's coroutine, set when it first runs.
+    yielders: Vec<Cell<*const Yielder<(), Wait>>>,
+
+    /// The value published by every invocation (see `subgroup_exchange`).
+    slots: RefCell<Vec<Option<Slot>>>,
+}
+
+struct InvocationContext {
+    group: Rc<GroupState>,
+    index: usize,
+}
+
+impl InvocationContext {
+    fn subgroup_id(&self) -> usize {
+        self.index / self.group.subgroup_size as usize
+    }
+
+    fn lane(&self) -> u32 {
+        (self.index % self.group.subgroup_size as usize) as u32
+    }
+}
+
+std::thread_local! {
+    /// The invocation currently running on this thread (if any).
+    static CONTEXT: RefCell<Option<InvocationContext>> = const { RefCell::new(None) };
+
+    /// Stacks of finished coroutines, kept around for reuse by later ones.
+    static STACKS: RefCell<Vec<DefaultStack>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `shader` once per element of `invocations`, each as its own coroutine,
+/// grouped into subgroups of (at most) `subgroup_size` consecutive invocations,
+/// and return their results (in the same order as `invocations`).
+///
+/// All coroutines run on the current thread, one at a time, each until it
+/// either returns, or waits on a barrier (which includes subgroup operations),
+/// with the barrier being released once all the invocations (still running)
+/// in its workgroup (or subgroup) are waiting on it.
+fn run_group<I: Copy, R>(
+    invocations: &[I],
+    subgroup_size: u32,
+    name: impl Fn(&I) -> std::string::String,
+    shader: &impl Fn(I) -> R,
+) -> Vec<R> {
+    let group = Rc::new(GroupState {
+        subgroup_size,
+        yielders: invocations.iter().map(|_| Cell::new(ptr::null())).collect(),
+        slots: RefCell::new(std::vec![None; invocations.len()]),
+    });
+
+    let mut coroutines: Vec<_> = invocations
+        .iter()
+        .enumerate()
+        .map(|(i, &invocation)| {
+            let group = group.clone();
+            let stack = STACKS
+                .with(|stacks| stacks.borrow_mut().pop())
+                .unwrap_or_default();
+            ScopedCoroutine::with_stack(stack, move |yielder: &Yielder<(), Wait>, ()| {
+                group.yielders[i].set(yielder);
+                shader(invocation)
+            })
+        })
+        .collect();
+    let mut states = std::vec![InvocationState::Ready; invocations.len()];
+    let mut results: Vec<Option<R>> = invocations.iter().map(|_| None).collect();
+
+    loop {
+        for (i, coroutine) in coroutines.iter_mut().enumerate() {
+            if states[i] != InvocationState::Ready {
+                continue;
+            }
+            let previous_context = CONTEXT.with(|context| {
+                context.replace(Some(InvocationContext {
+                    group: group.clone(),
+                    index: i,
+                }))
+            });
+            let result = panic::catch_unwind(AssertUnwindSafe(|| coroutine.resume(())));
+            CONTEXT.with(|context| context.replace(previous_context));
+            match result {
+                Ok(CoroutineResult::Yield(wait)) => states[i] = InvocationState::Waiting(wait),
+                Ok(CoroutineResult::Return(result)) => {
+                    results[i] = Some(result);
+                    states[i] = InvocationState::Done;
+                }
+                Err(payload) => {
+                    std::eprintln!(
+                        "note: the panic above happened in {}",
+                        name(&invocations[i])
+                    );
+                    // NOTE dropping the other (suspended) coroutines unwinds
+                    // their stacks, before propagating the original panic.
+                    drop(coroutines);
+                    panic::resume_unwind(payload);
+                }
+            }
+        }
+
+        if states.iter().all(|&state| state == InvocationState::Done) {
+            break;
+        }
+
+        // Every invocation is now either done, or waiting on a barrier, so any
+        // barrier with all of its (remaining) invocations waiting is released.
+        let mut released = false;
+        for subgroup_states in states.chunks_mut(subgroup_size as usize) {
+            released |= release_barrier(subgroup_states, Wait::Subgroup);
+        }
+        if !released {
+            released = release_barrier(&mut states, Wait::Workgroup);
+        }
+        if !released {
+            drop(coroutines);
+            panic!(
+                "deadlock: invocations are waiting on different barriers \
+                 (e.g. some on subgroup operations, and others on workgroup barriers)"
+            );
+        }
+    }
+
+    STACKS.with(|stacks| {
+        stacks
+            .borrow_mut()
+            .extend(coroutines.into_iter().map(ScopedCoroutine::into_stack));
+    });
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+/// Release the barrier `wait` if all the (remaining) invocations in `states`
+/// are waiting on it, returning whether it was released.
+fn release_barrier(states: &mut [InvocationState], wait: Wait) -> bool {
+    let mut remaining = states
+        .iter()
+        .filter(|&&state| state != InvocationState::Done);
+    if remaining.clone().next().is_none()
+        || !remaining.all(|&state| state == InvocationState::Waiting(wait))
+    {
+        return false;
+    }
+    for state in states {
+        if *state != InvocationState::Done {
+            *state = InvocationState::Ready;
+        }
+    }
+    true
+}
+
+fn with_context<R>(f: impl FnOnce(Option<&InvocationContext>) -> R) -> R {
+    CONTEXT.with(|context| f(context.borrow().as_ref()))
+}
+
+/// Suspend the current invocation until `wait` is satisfied (see `run_group`).
+fn wait(wait: Wait) {
+    let yielder = with_context(|context| context.map(|c| c.group.yielders[c.index].get()));
+    if let Some(yielder) = yielder {
+        // SAFETY: the `Yielder` belongs to the coroutine currently running,
+        // which this is being called from.
+        unsafe { (*yielder).suspend(wait) };
+    }
+}
+
+/// Wait for all (remaining) invocations in the workgroup.
+pub(crate) fn workgroup_barrier() {
+    wait(Wait::Workgroup);
+}
+
+/// Wait for all (remaining) invocations in the subgroup.
+pub(crate) fn subgroup_barrier() {
+    wait(Wait::Subgroup);
+}
+
+/// The current invocation's lane within its subgroup, and the subgroup size.
+///
+/// Outside of a dispatch, the current thread acts as a subgroup of one.
+pub(crate) fn subgroup_lane_and_size() -> (u32, u32) {
+    with_context(|context| context.map_or((0, 1), |c| (c.lane(), c.group.subgroup_size)))
+}
+
+/// Result of `subgroup_exchange`.
+pub(crate) struct Exchange<T> {
+    /// The current invocation's lane within its subgroup.
+    pub lane: u32,
+
+    /// The values from every lane in the subgroup (`None` for inactive lanes),
+    /// extended with `None` up to the subgroup size.
+    pub values: Vec<Option<T>>,
+}
+
+impl<T: Copy> Exchange<T> {
+    /// The value from `lane`, if it's active (and in bounds).
+    pub fn get(&self, lane: u32) -> Option<T> {
+        self.values.get(lane as usize).copied().flatten()
+    }
+
+    /// The values from active lanes, with their lane index.
+    pub fn active(&self) -> impl Iterator<Item = (u32, T)> + '_ {
+        (0..)
+            .zip(&self.values)
+            .filter_map(|(lane, value)| Some((lane, (*value)?)))
+    }
+}
+
+/// Make `value` visible to all active invocations in the current subgroup,
+/// which must all be executing the same `op`, and collect their own values.
+///
+/// Invocations are only considered inactive once they've returned, i.e. all
+/// subgroup operations must be executed in subgroup-uniform control flow.
+pub(crate) fn subgroup_exchange<T: Copy + 'static>(op: &'static str, value: T) -> Exchange<T> {
+    let context = with_context(|context| {
+        context.map(|c| (c.group.clone(), c.index, c.subgroup_id(), c.lane()))
+    });
+    let Some((group, index, subgroup_id, lane)) = context else {
+        return Exchange {
+            lane: 0,
+            values: std::vec![Some(value)],
+        };
+    };
+    let subgroup_size = group.subgroup_size as usize;
+
+    group.slots.borrow_mut()[index] = Some(Slot {
+        op,
+        type_id: TypeId::of::<T>(),
+        value: (&raw const value).cast(),
+    });
+    subgroup_barrier();
+    let mut values: Vec<_> = group
+        .slots
+        .borrow()
+        .chunks(subgroup_size)
+        .nth(subgroup_id)
+        .unwrap()
+        .iter()
+        .map(|slot| {
+            let slot = (*slot)?;
+            if slot.op != op || slot.type_id != TypeId::of::<T>() {
+                panic!(
+                    "divergent subgroup operations (`{op}` vs `{}`) \
+                     are not supported by `cpu_emulation`",
+                    slot.op
+                );
+            }
+            // SAFETY: the owner of `slot` is waiting for us on the barrier
+            // below, and checked above to have published a `T` value.
+            Some(unsafe { *slot.value.cast::<T>() })
+        })
+        .collect();
+    values.resize(subgroup_size, None);
+    subgroup_barrier();
+    group.slots.borrow_mut()[index] = None;
+
+    Exchange { lane, values }
+}
+
+/// Built-in inputs of a compute shader invocation, run by [`ComputeDispatch`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ComputeInvocation {
+    /// `#[spirv(global_invocation_id)]`
+    pub global_invocation_id: UVec3,
+    /// `#[spirv(local_invocation_id)]`
+    pub local_invocation_id: UVec3,
+    /// `#[spirv(local_invocation_index)]`
+    pub local_invocation_index: u32,
+    /// `#[spirv(workgroup_id)]`
+    pub workgroup_id: UVec3,
+    /// `#[spirv(num_workgroups)]`
+    pub num_workgroups: UVec3,
+    /// `#[spirv(subgroup_id)]`
+    pub subgroup_id: u32,
+    /// `#[spirv(num_subgroups)]`
+    pub num_subgroups: u32,
+    /// `#[spirv(subgroup_size)]`
+    pub subgroup_size: u32,
+    /// `#[spirv(subgroup_local_invocation_id)]`
+    pub subgroup_local_invocation_id: u32,
+}
+
+/// Emulated dispatch of a compute shader.
+///
+/// Workgroups are executed one after another, with every invocation in a
+/// workgroup running as its own coroutine (on the current thread), switching
+/// between them at barriers and subgroup operations, so that those behave like
+/// they would on a GPU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ComputeDispatch {
+    num_workgroups: UVec3,
+    workgroup_size: UVec3,
+    subgroup_size: u32,
+}
+
+impl ComputeDispatch {
+    /// Dispatch `num_workgroups` workgroups, of `workgroup_size` invocations
+    /// each (i.e. the `#[spirv(compute(threads(...)))]` of the shader).
+    pub fn new(num_workgroups: UVec3, workgroup_size: UVec3) -> Self {
+        Self {
+            num_workgroups,
+            workgroup_size,
+            subgroup_size: 32,
+        }
+    }
+
+    /// Set the subgroup size (a power of two, at most 128), defaults to 32.
+    #[must_use]
+    pub fn subgroup_size(mut self, subgroup_size: u32) -> Self {
+        assert!(
+            subgroup_size.is_power_of_two() && subgroup_size <= 128,
+            "subgroup size must be a power of two, at most 128"
+        );
+        self.subgroup_size = subgroup_size;
+        self
+    }
+
+    /// Run `shader` for every invocation.
+    pub fn run(&self, shader: impl Fn(ComputeInvocation)) {
+        self.run_with_workgroup_memory(|| (), |invocation, _| shader(invocation));
+    }
+
+    /// Run `shader` for every invocation, with workgroup memory created by
+    /// `init` for every workgroup, and shared by all of its invocations.
+    pub fn run_with_workgroup_memory<W>(
+        &self,
+        init: impl Fn() -> W,
+        shader: impl Fn(ComputeInvocation, &Shared<W>),
+    ) {
+        let [sx, sy, sz] = self.workgroup_size.to_array();
+        let workgroup_len = sx * sy * sz;
+        let num_subgroups = workgroup_len.div_ceil(self.subgroup_size);
+        let [nx, ny, nz] = self.num_workgroups.to_array();
+        for workgroup_id in (0..nz)
+            .flat_map(|z| (0..ny).flat_map(move |y| (0..nx).map(move |x| UVec3::new(x, y, z))))
+        {
+            let invocations: Vec<_> = (0..workgroup_len)
+                .map(|index| {
+                    let local_invocation_id =
+                        UVec3::new(index % sx, index / sx % sy, index / (sx * sy));
+                    ComputeInvocation {
+                        global_invocation_id: workgroup_id * self.workgroup_size
+                            + local_invocation_id,
+                        local_invocation_id,
+                        local_invocation_index: index,
+                        workgroup_id,
+                        num_workgroups: self.num_workgroups,
+                        subgroup_id: index / self.subgroup_size,
+                        num_subgroups,
+                        subgroup_size: self.subgroup_size,
+                        subgroup_local_invocation_id: index % self.subgroup_size,
+                    }
+                })
+                .collect();
+            let workgroup_memory = Shared::new(init());
+            run_group(
+                &invocations,
+                self.subgroup_size,
+                |invocation| format!("invocation {}", invocation.global_invocation_id),
+                &|invocation| shader(invocation, &workgroup_memory),
+            );
+        }
+    }
+}
+
+/// Built-in inputs of a fragment shader invocation, run by [`FragmentDispatch`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FragmentInvocation {
+    /// `#[spirv(frag_coord)]`, at the center of the pixel.
+    pub frag_coord: Vec4,
+    /// The pixel being shaded (i.e. the integer part of `frag_coord.xy()`).
+    pub pixel: UVec2,
+    /// `#[spirv(helper_invocation)]`, i.e. whether this invocation only exists
+    /// to complete a 2x2 quad (for derivatives), at the edges of the target.
+    pub helper_invocation: bool,
+}
+
+/// Emulated draw of a fragment shader, covering a whole render target.
+///
+/// Every 2x2 quad of pixels is executed as its own subgroup, with a coroutine
+/// per invocation, so that derivatives and quad operations work as on a GPU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FragmentDispatch {
+    size: UVec2,
+}
+
+impl FragmentDispatch {
+    /// Shade every pixel of a `size.x` by `size.y` render target.
+    pub fn new(size: UVec2) -> Self {
+        Self { size }
+    }
+
+    /// Run `shader` for every pixel, returning its outputs in row-major order.
+    pub fn run<T>(&self, shader: impl Fn(FragmentInvocation) -> T) -> Vec<T> {
+        let mut outputs: Vec<Option<T>> = (0..self.size.x * self.size.y).map(|_| None).collect();
+        for quad_y in (0..self.size.y).step_by(2) {
+            for quad_x in (0..self.size.x).step_by(2) {
+                // Quad indices are 0 (top-left), 1 (top-right), 2 (bottom-left)
+                // and 3 (bottom-right), i.e. `(y & 1) * 2 + (x & 1)`.
+                let invocations: Vec<_> = (0..4)
+                    .map(|quad_index| {
+                        let pixel = UVec2::new(quad_x + (quad_index & 1), quad_y + quad_index / 2);
+                        FragmentInvocation {
+                            frag_coord: Vec4::new(
+                                pixel.x as f32 + 0.5,
+                                pixel.y as f32 + 0.5,
+                                0.0,
+                                1.0,
+                            ),
+                            pixel,
+                            helper_invocation: pixel.x >= self.size.x || pixel.y >= self.size.y,
+                        }
+                    })
+                    .collect();
+                let quad_outputs = run_group(
+                    &invocations,
+                    4,
+                    |invocation| format!("fragment {}", invocation.pixel),
+                    &shader,
+                );
+                for (invocation, output) in invocations.iter().zip(quad_outputs) {
+                    if !invocation.helper_invocation {
+                        let UVec2 { x, y } = invocation.pixel;
+                        outputs[(y * self.size.x + x) as usize] = Some(output);
+                    }
+                }
+            }
+        }
+        outputs.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+/// Emulate `OpControlBarrier`, with an `execution` `Scope`.
+pub(crate) fn control_barrier(execution: u32) {
+    if execution == Scope::Subgroup as u32 {
+        subgroup_barrier();
+    } else if execution != Scope::Invocation as u32 {
+        // NOTE scopes larger than `Workgroup` can't be waited on (other
+        // workgroups may not have even started yet), but are also not valid
+        // as the execution scope of a barrier in e.g. Vulkan.
+        workgroup_barrier();
+    }
+    memory_barrier();
+}
+
+/// Emulate `OpMemoryBarrier`.
+pub(crate) fn memory_barrier() {
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arch::workgroup_memory_barrier_with_group_sync;
+
+    #[test]
+    fn workgroup_barrier_orders_accesses() {
+        let output = Shared::new(std::vec![0; 64]);
+        ComputeDispatch::new(UVec3::new(1, 1, 1), UVec3::new(64, 1, 1))
+            .subgroup_size(16)
+            .run_with_workgroup_memory(
+                || [0; 64],
+                |invocation, scratch| {
+                    let index = invocation.local_invocation_index as usize;
+                    unsafe { scratch.get_mut()[index] = index * 10 };
+                    workgroup_memory_barrier_with_group_sync();
+                    // Read a value written by an invocation in another subgroup.
+                    let value = unsafe { scratch.get_mut()[63 - index] };
+                    unsafe { output.get_mut()[index] = value };
+                },
+            );
+        let expected: Vec<_> = (0..64).map(|index| (63 - index) * 10).collect();
+        assert_eq!(output.into_inner(), expected);
+    }
+
+    #[test]
+    fn returned_invocations_dont_block_barriers() {
+        let output = Shared::new(std::vec![0; 8]);
+        ComputeDispatch::new(UVec3::new(1, 1, 1), UVec3::new(8, 1, 1)).run_with_workgroup_memory(
+            || 0,
+            |invocation, counter| {
+                let index = invocation.local_invocation_index;
+                if index % 2 == 0 {
+                    return;
+                }
+                unsafe { *counter.get_mut() += 1 };
+                workgroup_memory_barrier_with_group_sync();
+                unsafe { output.get_mut()[index as usize] = *counter.get_mut() };
+            },
+        );
+        assert_eq!(output.into_inner(), [0, 4, 0, 4, 0, 4, 0, 4]);
+    }
+
+    #[test]
+    fn builtins_of_every_workgroup() {
+        let num_workgroups = UVec3::new(3, 2, 2);
+        let workgroup_size = UVec3::new(4, 2, 1);
+        let seen = Shared::new(std::vec![false; 3 * 2 * 2 * 4 * 2]);
+        ComputeDispatch::new(num_workgroups, workgroup_size)
+            .subgroup_size(4)
+            .run(|invocation| {
+                assert_eq!(invocation.num_workgroups, num_workgroups);
+                assert_eq!(invocation.num_subgroups, 2);
+                assert_eq!(
+                    invocation.global_invocation_id,
+                    invocation.workgroup_id * workgroup_size + invocation.local_invocation_id
+                );
+                assert_eq!(
+                    invocation.subgroup_id * 4 + invocation.subgroup_local_invocation_id,
+                    invocation.local_invocation_index
+                );
+                let UVec3 { x, y, z } = invocation.global_invocation_id;
+                let index = (x + 12 * (y + 4 * z)) as usize;
+                let seen = unsafe { &mut seen.get_mut()[index] };
+                assert!(
+                    !*seen,
+                    "invocation {} ran twice",
+                    invocation.global_invocation_id
+                );
+                *seen = true;
+            });
+        assert!(seen.into_inner().into_iter().all(|seen| seen));
+    }
+
+    #[test]
+    fn fragment_outputs_and_helpers() {
+        let outputs = FragmentDispatch::new(UVec2::new(3, 3)).run(|invocation| {
+            assert_eq!(
+                invocation.helper_invocation,
+                invocation.pixel.x >= 3 || invocation.pixel.y >= 3
+            );
+            assert_eq!(
+                invocation.frag_coord,
+                Vec4::new(
+                    invocation.pixel.x as f32 + 0.5,
+                    invocation.pixel.y as f32 + 0.5,
+                    0.0,
+                    1.0
+                )
+            );
+            invocation.pixel
+        });
+        let expected: Vec<_> = (0..3)
+            .flat_map(|y| (0..3).map(move |x| UVec2::new(x, y)))
+            .collect();
+        assert_eq!(outputs, expected);
+    }
+
+    #[test]
+    #[should_panic = "invocation 5 failed"]
+    fn panics_propagate() {
+        ComputeDispatch::new(UVec3::new(1, 1, 1), UVec3::new(8, 1, 1)).run(|invocation| {
+            workgroup_memory_barrier_with_group_sync();
+            let index = invocation.local_invocation_index;
+            assert_ne!(index, 5, "invocation {index} failed");
+        });
+    }
+
+    #[test]
+    #[should_panic = "deadlock"]
+    fn mismatched_barriers_deadlock() {
+        ComputeDispatch::new(UVec3::new(1, 1, 1), UVec3::new(2, 1, 1)).run(|invocation| {
+            if invocation.local_invocation_index == 0 {
+                subgroup_barrier();
+            } else {
+                workgroup_barrier();
+            }
+        });
+    }
+
+    #[test]
+    fn barriers_outside_dispatch_are_noops() {
+        workgroup_barrier();
+        subgroup_barrier();
+        assert_eq!(subgroup_lane_and_size(), (0, 1));
+    }
+}
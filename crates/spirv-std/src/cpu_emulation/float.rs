@@ -0,0 +1,142 @@
+//! Emulation of the `GLSL.std.450` packing instructions used by `crate::float`.
+//!
+//! Where the GLSL spec leaves the rounding up to the implementation (i.e. for
+//! `f16` conversions, and `round` in the `snorm`/`unorm` packing), this rounds
+//! to nearest, with ties to even.
+
+use glam::{Vec2, Vec4};
+
+/// `value >> shift`, rounded to nearest (with ties to even).
+fn shift_right_round(value: u32, shift: u32) -> u32 {
+    let (result, rest) = (value >> shift, value & ((1 << shift) - 1));
+    let halfway = 1 << (shift - 1);
+    if rest > halfway || (rest == halfway && result & 1 != 0) {
+        result + 1
+    } else {
+        result
+    }
+}
+
+/// The bits of the `f16` nearest to `x`, with too large values becoming
+/// infinite, and NaNs staying NaNs (keeping their sign and top payload bits).
+fn f32_to_f16_bits(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exp == 0xff {
+        let nan = if mantissa != 0 {
+            0x200 | (mantissa >> 13) as u16
+        } else {
+            0
+        };
+        return sign | 0x7c00 | nan;
+    }
+
+    // The exponent, rebiased for `f16`, which is `<= 0` for `f16` subnormals.
+    let exp = exp - 127 + 15;
+    if exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+    let abs = if exp > 0 {
+        // Rounding can carry into the exponent (up to infinity), as intended.
+        ((exp as u32) << 10) + shift_right_round(mantissa, 13)
+    } else {
+        // Subnormal (including `f32` subnormals, which all round to zero), in
+        // units of `2^-24`, with the implicit leading bit made explicit.
+        let shift = (14 - exp) as u32;
+        if shift > 24 {
+            0
+        } else {
+            shift_right_round(mantissa | 0x80_0000, shift)
+        }
+    };
+    sign | abs as u16
+}
+
+/// The `f32` with the same value as the `f16` with the given `bits`.
+fn f16_bits_to_f32(bits: u16) -> f32 {
+    let sign = u32::from(bits & 0x8000) << 16;
+    let exp = u32::from(bits >> 10) & 0x1f;
+    let mantissa = u32::from(bits & 0x3ff);
+    let abs = match exp {
+        // Subnormal (or zero), i.e. `mantissa * 2^-24`, which is exact.
+        0 => (mantissa as f32 * f32::from_bits(0x3380_0000)).to_bits(),
+        0x1f => 0x7f80_0000 | (mantissa << 13),
+        _ => ((exp + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(sign | abs)
+}
+
+pub(crate) fn vec2_to_f16x2(vec: Vec2) -> u32 {
+    u32::from(f32_to_f16_bits(vec.x)) | (u32::from(f32_to_f16_bits(vec.y)) << 16)
+}
+
+pub(crate) fn f16x2_to_vec2(int: u32) -> Vec2 {
+    Vec2::new(
+        f16_bits_to_f32(int as u16),
+        f16_bits_to_f32((int >> 16) as u16),
+    )
+}
+
+/// Pack `components` into `BITS`-bit fields (the first one in the lowest bits),
+/// scaled (after clamping to `min..=1`) by the largest `snorm`/`unorm` value.
+fn pack<const BITS: u32>(components: &[f32], min: f32) -> u32 {
+    let max = if min < 0.0 {
+        ((1 << (BITS - 1)) - 1) as f32
+    } else {
+        ((1 << BITS) - 1) as f32
+    };
+    components.iter().enumerate().fold(0, |packed, (i, &c)| {
+        // NOTE NaNs become `0`, like with Rust's saturating float casts.
+        let field = (c.clamp(min, 1.0) * max).round_ties_even() as i32 as u32;
+        packed | ((field & ((1 << BITS) - 1)) << (i as u32 * BITS))
+    })
+}
+
+/// Unpack `N` `BITS`-bit fields of `int` (the first one in the lowest bits),
+/// the inverse of `pack` (with `signed` for `snorm`).
+fn unpack<const BITS: u32, const N: usize>(int: u32, signed: bool) -> [f32; N] {
+    core::array::from_fn(|i| {
+        let field = (int >> (i as u32 * BITS)) & ((1 << BITS) - 1);
+        if signed {
+            // Sign-extend the field, and clamp, as the lowest value is out of range.
+            let field = ((field << (32 - BITS)) as i32) >> (32 - BITS);
+            (field as f32 / ((1 << (BITS - 1)) - 1) as f32).clamp(-1.0, 1.0)
+        } else {
+            field as f32 / ((1 << BITS) - 1) as f32
+        }
+    })
+}
+
+pub(crate) fn vec4_to_u8x4_snorm(vec: Vec4) -> u32 {
+    pack::<8>(&vec.to_array(), -1.0)
+}
+
+pub(crate) fn vec4_to_u8x4_unorm(vec: Vec4) -> u32 {
+    pack::<8>(&vec.to_array(), 0.0)
+}
+
+pub(crate) fn vec2_to_u16x2_snorm(vec: Vec2) -> u32 {
+    pack::<16>(&vec.to_array(), -1.0)
+}
+
+pub(crate) fn vec2_to_u16x2_unorm(vec: Vec2) -> u32 {
+    pack::<16>(&vec.to_array(), 0.0)
+}
+
+pub(crate) fn u8x4_to_vec4_snorm(int: u32) -> Vec4 {
+    Vec4::from_array(unpack::<8, 4>(int, true))
+}
+
+pub(crate) fn u8x4_to_vec4_unorm(int: u32) -> Vec4 {
+    Vec4::from_array(unpack::<8, 4>(int, false))
+}
+
+pub(crate) fn u16x2_to_vec2_snorm(int: u32) -> Vec2 {
+    Vec2::from_array(unpack::<16, 2>(int, true))
+}
+
+pub(crate) fn u16x2_to_vec2_unorm(int: u32) -> Vec2 {
+    Vec2::from_array(unpack::<16, 2>(int, false))
+}
@@ -0,0 +1,519 @@
+//! Host-side images and samplers, backing emulated `Image`s and `Sampler`s.
+
+use super::scalar::{ScalarValue, components, set_components};
+use crate::image::Image;
+use crate::image::{Dimensionality, SampleType};
+use crate::{Sampler, Scalar, ScalarOrVector};
+use glam::UVec3;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::vec::Vec;
+
+struct ImageLevel {
+    extent: UVec3,
+    texels: Vec<[f64; 4]>,
+}
+
+type ImageData = RwLock<Vec<ImageLevel>>;
+
+/// How texels are filtered, when sampling through a [`HostSampler`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Use the nearest texel.
+    #[default]
+    Nearest,
+    /// Linearly interpolate between the nearest texels.
+    Linear,
+}
+
+/// How out of bounds texel coordinates are handled, when sampling through a
+/// [`HostSampler`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AddressMode {
+    /// Use the texel at the nearest edge.
+    #[default]
+    ClampToEdge,
+    /// Wrap around, tiling the image.
+    Repeat,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct SamplerDesc {
+    filter: Filter,
+    address_mode: AddressMode,
+}
+
+// NOTE handles are never reused, so that any `Image`s or `Sampler`s
+// outliving their `HostImage`/`HostSampler` can't alias newer ones.
+struct Registry {
+    images: Vec<Option<Arc<ImageData>>>,
+    samplers: Vec<Option<SamplerDesc>>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    images: Vec::new(),
+    samplers: Vec::new(),
+});
+
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn image_data(handle: u32) -> Arc<ImageData> {
+    registry()
+        .images
+        .get(handle as usize)
+        .cloned()
+        .flatten()
+        .expect("`Image` used after its `HostImage` was dropped")
+}
+
+fn sampler_desc(handle: u32) -> SamplerDesc {
+    registry()
+        .samplers
+        .get(handle as usize)
+        .copied()
+        .flatten()
+        .expect("`Sampler` used after its `HostSampler` was dropped")
+}
+
+fn texel_from<V: ScalarOrVector>(texel: &V) -> [f64; 4] {
+    let mut result = [0.0, 0.0, 0.0, 1.0];
+    for (dst, src) in result
+        .iter_mut()
+        .zip(components::<V::Scalar, V>(texel, V::N.get()))
+    {
+        *dst = src.to_f64();
+    }
+    result
+}
+
+/// Image data owned by the host, which emulated [`Image`] methods operate on,
+/// through the (handle-like) [`Image`]s returned by [`HostImage::image`].
+///
+/// Texels are stored as 4 `f64` components, with missing components filled in
+/// with `0` (or `1`, for alpha), and converted to the `Image`'s sampled type
+/// (saturating when out of range) when accessed.
+pub struct HostImage {
+    handle: u32,
+    data: Arc<ImageData>,
+}
+
+impl HostImage {
+    /// Create an image of `extent` (width, height and depth) texels, stored in
+    /// `texels` in row-major order (i.e. `texels[x + width * (y + height * z)]`).
+    ///
+    /// For arrayed images, the layers take the place of the dimension after the
+    /// last one (e.g. depth, for 2D arrays). Unused dimensions must be `1`.
+    pub fn new<V: ScalarOrVector>(extent: UVec3, texels: &[V]) -> Self {
+        let data = Arc::new(RwLock::new(Vec::new()));
+        let mut registry = registry();
+        let handle = registry.images.len() as u32;
+        registry.images.push(Some(data.clone()));
+        drop(registry);
+        Self { handle, data }.with_mip_level(extent, texels)
+    }
+
+    /// Add the next mipmap level (see [`HostImage::new`] for the arguments).
+    #[must_use]
+    pub fn with_mip_level<V: ScalarOrVector>(self, extent: UVec3, texels: &[V]) -> Self {
+        assert_eq!(
+            texels.len(),
+            extent.element_product() as usize,
+            "texel count doesn't match the image extent"
+        );
+        self.data
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(ImageLevel {
+                extent,
+                texels: texels.iter().map(texel_from).collect(),
+            });
+        self
+    }
+
+    /// Get an [`Image`] referring to this image, e.g. to pass to a shader.
+    pub fn image<
+        SampledType: SampleType<FORMAT, COMPONENTS>,
+        const DIM: u32,
+        const DEPTH: u32,
+        const ARRAYED: u32,
+        const MULTISAMPLED: u32,
+        const SAMPLED: u32,
+        const FORMAT: u32,
+        const COMPONENTS: u32,
+    >(
+        &self,
+    ) -> Image<SampledType, DIM, DEPTH, ARRAYED, MULTISAMPLED, SAMPLED, FORMAT, COMPONENTS> {
+        Image::from_cpu_emulation_handle(self.handle)
+    }
+
+    /// Read back all the texels of a mipmap level (e.g. after a shader wrote
+    /// to the image), in the same order as [`HostImage::new`] takes them.
+    pub fn read_texels<V: ScalarOrVector>(&self, level: u32) -> Vec<V> {
+        let levels = self.data.read().unwrap_or_else(PoisonError::into_inner);
+        let like = ScalarValue::from_scalar(V::Scalar::default());
+        levels[level as usize]
+            .texels
+            .iter()
+            .map(|texel| {
+                let mut value = V::default();
+                set_components::<V::Scalar, V>(
+                    &mut value,
+                    texel[..V::N.get()].iter().map(|&x| like.with_f64(x)),
+                );
+                value
+            })
+            .collect()
+    }
+}
+
+impl Drop for HostImage {
+    fn drop(&mut self) {
+        registry().images[self.handle as usize] = None;
+    }
+}
+
+/// Sampler state owned by the host, used by emulated [`Image`] sampling,
+/// through the (handle-like) [`Sampler`] returned by [`HostSampler::sampler`].
+pub struct HostSampler {
+    handle: u32,
+}
+
+impl HostSampler {
+    /// Create a sampler using `filter` for both magnification and minification
+    /// (mipmap levels are always selected by rounding to the nearest level).
+    pub fn new(filter: Filter, address_mode: AddressMode) -> Self {
+        let mut registry = registry();
+        let handle = registry.samplers.len() as u32;
+        registry.samplers.push(Some(SamplerDesc {
+            filter,
+            address_mode,
+        }));
+        Self { handle }
+    }
+
+    /// Get a [`Sampler`] referring to this sampler, e.g. to pass to a shader.
+    pub fn sampler(&self) -> Sampler {
+        Sampler::from_cpu_emulation_handle(self.handle)
+    }
+}
+
+impl Drop for HostSampler {
+    fn drop(&mut self) {
+        registry().samplers[self.handle as usize] = None;
+    }
+}
+
+/// Number of coordinates (excluding the array layer) for an image `DIM`.
+fn dims(dim: u32) -> usize {
+    match dim {
+        d if d == Dimensionality::OneD as u32 || d == Dimensionality::Buffer as u32 => 1,
+        d if d == Dimensionality::TwoD as u32
+            || d == Dimensionality::Rect as u32
+            || d == Dimensionality::SubpassData as u32 =>
+        {
+            2
+        }
+        d if d == Dimensionality::ThreeD as u32 => 3,
+        _ => unimplemented!("cube images are not supported by `cpu_emulation`"),
+    }
+}
+
+/// Read the coordinates (`dims(dim)`, plus one for the array layer if arrayed)
+/// out of a scalar or vector coordinate with components of type `S`.
+fn coordinates<S: Scalar, C>(dim: u32, arrayed: u32, coordinate: &C) -> ([f64; 3], usize) {
+    let dims = dims(dim);
+    let mut result = [0.0; 3];
+    for (dst, src) in result
+        .iter_mut()
+        .zip(components::<S, C>(coordinate, dims + arrayed as usize))
+    {
+        *dst = src.to_f64();
+    }
+    (result, dims)
+}
+
+/// Zero value of the scalar type used for texels of an image of `SampledType`,
+/// which is `f32`/`f64` for floats, and 32-bit integers otherwise.
+fn texel_scalar<SampledType: Scalar>() -> ScalarValue {
+    match ScalarValue::from_scalar(SampledType::default()) {
+        ScalarValue::F32(_) => ScalarValue::F32(0.0),
+        ScalarValue::F64(_) => ScalarValue::F64(0.0),
+        ScalarValue::I8(_) | ScalarValue::I16(_) | ScalarValue::I32(_) | ScalarValue::I64(_) => {
+            ScalarValue::I32(0)
+        }
+        _ => ScalarValue::U32(0),
+    }
+}
+
+/// Convert a texel to `V` (a vector of the `texel_scalar` of `SampledType`).
+fn texel_to<SampledType: Scalar, V: Default>(texel: [f64; 4]) -> V {
+    let like = texel_scalar::<SampledType>();
+    let components = texel.into_iter().map(|x| like.with_f64(x));
+    let mut value = V::default();
+    match like {
+        ScalarValue::F32(_) => set_components::<f32, V>(&mut value, components),
+        ScalarValue::F64(_) => set_components::<f64, V>(&mut value, components),
+        ScalarValue::I32(_) => set_components::<i32, V>(&mut value, components),
+        _ => set_components::<u32, V>(&mut value, components),
+    }
+    value
+}
+
+/// Convert `n` components of `V` (a scalar or vector of the `texel_scalar`
+/// of `SampledType`) to a texel.
+fn texel_from_components<SampledType: Scalar, V>(value: &V, n: usize) -> [f64; 4] {
+    let mut texel = [0.0, 0.0, 0.0, 1.0];
+    let components: Vec<_> = match texel_scalar::<SampledType>() {
+        ScalarValue::F32(_) => components::<f32, V>(value, n).collect(),
+        ScalarValue::F64(_) => components::<f64, V>(value, n).collect(),
+        ScalarValue::I32(_) => components::<i32, V>(value, n).collect(),
+        _ => components::<u32, V>(value, n).collect(),
+    };
+    for (dst, src) in texel.iter_mut().zip(components) {
+        *dst = src.to_f64();
+    }
+    texel
+}
+
+impl ImageLevel {
+    /// Index of the texel at `coords`, if in bounds.
+    fn index(&self, coords: [i64; 3]) -> Option<usize> {
+        let extent = self.extent.to_array().map(i64::from);
+        if (0..3).any(|i| !(0..extent[i]).contains(&coords[i])) {
+            return None;
+        }
+        Some((coords[0] + extent[0] * (coords[1] + extent[1] * coords[2])) as usize)
+    }
+}
+
+fn level_index(levels: &[ImageLevel], lod: f64) -> usize {
+    (lod.round().max(0.0) as usize).min(levels.len() - 1)
+}
+
+/// Emulate `OpImageFetch`/`OpImageRead`, returning a `Vec4` of `SampledType`.
+pub(crate) fn read<SampledType: Scalar, I: Scalar, V4: Default, C>(
+    image: u32,
+    dim: u32,
+    arrayed: u32,
+    coordinate: &C,
+    lod: u32,
+) -> V4 {
+    let (coords, _) = coordinates::<I, C>(dim, arrayed, coordinate);
+    let data = image_data(image);
+    let levels = data.read().unwrap_or_else(PoisonError::into_inner);
+    // NOTE out of bounds reads return zero, like with `robustImageAccess`.
+    let texel = levels
+        .get(lod as usize)
+        .and_then(|level| Some(level.texels[level.index(coords.map(|x| x as i64))?]))
+        .unwrap_or([0.0; 4]);
+    texel_to::<SampledType, V4>(texel)
+}
+
+/// Emulate `OpImageWrite`, of a texel with `n` components.
+pub(crate) fn write<SampledType: Scalar, I: Scalar, C, T>(
+    image: u32,
+    dim: u32,
+    arrayed: u32,
+    coordinate: &C,
+    texel: &T,
+    n: u32,
+) {
+    let (coords, _) = coordinates::<I, C>(dim, arrayed, coordinate);
+    let texel = texel_from_components::<SampledType, T>(texel, n as usize);
+    let data = image_data(image);
+    let mut levels = data.write().unwrap_or_else(PoisonError::into_inner);
+    let level = &mut levels[0];
+    if let Some(index) = level.index(coords.map(|x| x as i64)) {
+        level.texels[index] = texel;
+    }
+}
+
+/// Emulate `OpImageSample*Lod`, returning a `Vec4` of `SampledType`.
+///
+/// Implicit level of detail (i.e. `sample`, without `_by_lod`) uses a `lod` of
+/// `0` (plus any bias), i.e. derivatives aren't taken into account.
+pub(crate) fn sample<SampledType: Scalar, F: Scalar, V4: Default, C>(
+    image: u32,
+    sampler: u32,
+    dim: u32,
+    arrayed: u32,
+    coordinate: &C,
+    lod: f32,
+) -> V4 {
+    let (coords, dims) = coordinates::<F, C>(dim, arrayed, coordinate);
+    let sampler = sampler_desc(sampler);
+    let data = image_data(image);
+    let levels = data.read().unwrap_or_else(PoisonError::into_inner);
+    let level = &levels[level_index(&levels, lod.into())];
+    let extent = level.extent.to_array().map(i64::from);
+
+    let address = |i: usize, x: i64| match sampler.address_mode {
+        AddressMode::ClampToEdge => x.clamp(0, extent[i] - 1),
+        AddressMode::Repeat => x.rem_euclid(extent[i]),
+    };
+    let normalized = dim != Dimensionality::Rect as u32;
+    // Texel-space coordinates (and the array layer, which is never filtered).
+    let mut texel_coords = [0.0; 3];
+    for i in 0..dims {
+        texel_coords[i] = if normalized {
+            coords[i] * extent[i] as f64
+        } else {
+            coords[i]
+        };
+    }
+    if arrayed != 0 {
+        texel_coords[dims] = coords[dims].round().clamp(0.0, (extent[dims] - 1) as f64) + 0.5;
+    }
+
+    let fetch = |texel: [i64; 3]| level.texels[level.index(texel).unwrap()];
+    match sampler.filter {
+        Filter::Nearest => {
+            let texel = core::array::from_fn(|i| {
+                let x = texel_coords[i].floor() as i64;
+                if i < dims { address(i, x) } else { x }
+            });
+            texel_to::<SampledType, V4>(fetch(texel))
+        }
+        Filter::Linear => {
+            // Sum over the `2^dims` nearest texels, weighted by distance.
+            let mut result = [0.0; 4];
+            for corner in 0..(1 << dims) {
+                let mut weight = 1.0;
+                let texel = core::array::from_fn(|i| {
+                    if i >= dims {
+                        return texel_coords[i].floor() as i64;
+                    }
+                    let x = texel_coords[i] - 0.5;
+                    let (x0, t) = (x.floor(), x - x.floor());
+                    let upper = corner & (1 << i) != 0;
+                    weight *= if upper { t } else { 1.0 - t };
+                    address(i, x0 as i64 + i64::from(upper))
+                });
+                for (acc, x) in result.iter_mut().zip(fetch(texel)) {
+                    *acc += weight * x;
+                }
+            }
+            texel_to::<SampledType, V4>(result)
+        }
+    }
+}
+
+/// Emulate `OpImageQuerySize`/`OpImageQuerySizeLod`.
+pub(crate) fn query_size<Size: Default>(image: u32, dim: u32, arrayed: u32, lod: u32) -> Size {
+    let data = image_data(image);
+    let levels = data.read().unwrap_or_else(PoisonError::into_inner);
+    let extent = levels[lod as usize].extent.to_array();
+    let mut size = Size::default();
+    set_components::<u32, Size>(
+        &mut size,
+        extent[..dims(dim) + arrayed as usize]
+            .iter()
+            .map(|&x| ScalarValue::U32(x)),
+    );
+    size
+}
+
+/// Emulate `OpImageQueryLevels`.
+pub(crate) fn query_levels(image: u32) -> u32 {
+    let data = image_data(image);
+    data.read().unwrap_or_else(PoisonError::into_inner).len() as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::image::{Image2d, Image2dArray, Image2dU, StorageImage2d};
+    use glam::{IVec2, UVec2, Vec2, Vec3, Vec4};
+
+    /// A 2x2 image with texels `0`, `1`, `2` and `3` (in the red component).
+    fn image_2x2() -> HostImage {
+        HostImage::new(UVec3::new(2, 2, 1), &[0.0f32, 1.0, 2.0, 3.0])
+    }
+
+    #[test]
+    fn fetch_and_query() {
+        let host = image_2x2().with_mip_level(UVec3::new(1, 1, 1), &[Vec2::new(5.0, 6.0)]);
+        let image: Image2d = host.image();
+        assert_eq!(image.fetch(IVec2::new(1, 0)), Vec4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(image.fetch(UVec2::new(0, 1)), Vec4::new(2.0, 0.0, 0.0, 1.0));
+        assert_eq!(
+            image.fetch_with_lod(IVec2::ZERO, 1),
+            Vec4::new(5.0, 6.0, 0.0, 1.0)
+        );
+        assert_eq!(image.query_levels(), 2);
+        assert_eq!(image.query_size_lod::<UVec2>(0), UVec2::new(2, 2));
+        assert_eq!(image.query_size_lod::<UVec2>(1), UVec2::new(1, 1));
+    }
+
+    #[test]
+    fn out_of_bounds_reads_zero() {
+        let host = image_2x2();
+        let image: Image2d = host.image();
+        assert_eq!(image.fetch(IVec2::new(2, 0)), Vec4::ZERO);
+        assert_eq!(image.fetch(IVec2::new(0, -1)), Vec4::ZERO);
+        assert_eq!(image.fetch_with_lod(IVec2::ZERO, 1), Vec4::ZERO);
+    }
+
+    #[test]
+    fn integer_texels_saturate() {
+        let host = HostImage::new(UVec3::new(2, 1, 1), &[-1.0f32, 7.9]);
+        let image: Image2dU = host.image();
+        assert_eq!(image.fetch(IVec2::new(0, 0)).x, 0);
+        assert_eq!(image.fetch(IVec2::new(1, 0)).x, 7);
+    }
+
+    #[test]
+    fn sample_nearest_and_linear() {
+        let host = image_2x2();
+        let image: Image2d = host.image();
+        let nearest = HostSampler::new(Filter::Nearest, AddressMode::ClampToEdge);
+        let linear = HostSampler::new(Filter::Linear, AddressMode::ClampToEdge);
+        let repeat = HostSampler::new(Filter::Nearest, AddressMode::Repeat);
+        let sample =
+            |sampler: &HostSampler, x, y| image.sample(sampler.sampler(), Vec2::new(x, y)).x;
+
+        assert_eq!(sample(&nearest, 0.75, 0.25), 1.0);
+        assert_eq!(sample(&nearest, 1.5, 0.25), 1.0);
+        assert_eq!(sample(&repeat, 1.25, 0.75), 2.0);
+        assert_eq!(sample(&linear, 0.5, 0.5), 1.5);
+        assert_eq!(sample(&linear, 0.5, 0.25), 0.5);
+        assert_eq!(sample(&linear, 0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn sample_array_layer() {
+        let host = HostImage::new(UVec3::new(1, 1, 2), &[1.0f32, 2.0]);
+        let image: Image2dArray = host.image();
+        let sampler = HostSampler::new(Filter::Linear, AddressMode::Repeat);
+        let sample = |layer| {
+            image
+                .sample(sampler.sampler(), Vec3::new(0.5, 0.5, layer))
+                .x
+        };
+        assert_eq!(sample(0.0), 1.0);
+        assert_eq!(sample(1.2), 2.0);
+        assert_eq!(sample(5.0), 2.0);
+    }
+
+    #[test]
+    fn write_then_read() {
+        let host = HostImage::new(UVec3::new(2, 2, 1), &[Vec4::ZERO; 4]);
+        let image: StorageImage2d = host.image();
+        unsafe {
+            image.write(UVec2::new(1, 1), Vec4::new(1.0, 2.0, 3.0, 4.0));
+            // Out of bounds writes are discarded.
+            image.write(UVec2::new(2, 0), Vec4::ONE);
+        }
+        assert_eq!(image.read(UVec2::new(1, 1)), Vec4::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(
+            host.read_texels::<Vec4>(0),
+            [
+                Vec4::ZERO,
+                Vec4::ZERO,
+                Vec4::ZERO,
+                Vec4::new(1.0, 2.0, 3.0, 4.0)
+            ]
+        );
+    }
+}
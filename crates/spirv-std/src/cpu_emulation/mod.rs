@@ -0,0 +1,75 @@
+//! Emulation of GPU-only intrinsics on the CPU, for testing shader code with a
+//! plain `cargo test` (enabled by the `cpu-emulation` feature).
+//!
+//! Shaders are run by [`ComputeDispatch`] and [`FragmentDispatch`], which
+//! execute every invocation as its own (stackful) coroutine, cooperatively
+//! scheduled on the current thread (i.e. switching between invocations only
+//! when they wait on barriers, subgroup operations, or derivatives), so that
+//! those all behave like on a GPU, while [`HostImage`] and [`HostSampler`]
+//! provide the data behind [`Image`]s and [`Sampler`]s, for fetching,
+//! sampling, reading and writing texels.
+//!
+//! ```
+//! use spirv_std::arch::{subgroup_i_add, workgroup_memory_barrier_with_group_sync};
+//! use spirv_std::cpu_emulation::{ComputeDispatch, Shared};
+//! use spirv_std::glam::UVec3;
+//!
+//! // A shader summing up its input, one workgroup of 64 invocations at a time.
+//! fn sum(id: UVec3, input: &[u32], output: &mut [u32], partial_sums: &mut [u32; 2]) {
+//!     let subgroup_sum = subgroup_i_add(input[id.x as usize]);
+//!     if id.x % 32 == 0 {
+//!         partial_sums[(id.x % 64 / 32) as usize] = subgroup_sum;
+//!     }
+//!     workgroup_memory_barrier_with_group_sync();
+//!     if id.x % 64 == 0 {
+//!         output[(id.x / 64) as usize] = partial_sums[0] + partial_sums[1];
+//!     }
+//! }
+//!
+//! let input: Vec<u32> = (0..128).collect();
+//! let output = Shared::new(vec![0; 2]);
+//! ComputeDispatch::new(UVec3::new(2, 1, 1), UVec3::new(64, 1, 1))
+//!     .subgroup_size(32)
+//!     .run_with_workgroup_memory(
+//!         || [0; 2],
+//!         |invocation, partial_sums| unsafe {
+//!             sum(
+//!                 invocation.global_invocation_id,
+//!                 &input,
+//!                 output.get_mut(),
+//!                 partial_sums.get_mut(),
+//!             )
+//!         },
+//!     );
+//! assert_eq!(output.into_inner(), [(0..64).sum::<u32>(), (64..128).sum()]);
+//! ```
+//!
+//! Some limitations apply, compared to real GPUs:
+//! * invocations are only considered inactive (e.g. for [`subgroup_ballot`])
+//!   after returning, so subgroup operations must be used in control flow that
+//!   is uniform across the (remaining) invocations of the subgroup
+//! * images can't be cubemaps, and sampling uses the nearest mipmap level,
+//!   with an implicit level of detail of `0` (i.e. ignoring derivatives)
+//! * atomics are implemented using locks, so they're only atomic with respect
+//!   to other (emulated) atomic operations
+//! * `f16` isn't supported, neither as a subgroup/atomic operand, nor for the
+//!   `GLSL.std.450` math in `spirv_std::half`
+//!
+//! [`Image`]: crate::Image
+//! [`Sampler`]: crate::Sampler
+//! [`subgroup_ballot`]: crate::arch::subgroup_ballot
+
+pub(crate) mod atomics;
+mod executor;
+pub(crate) mod float;
+pub(crate) mod image;
+mod scalar;
+pub(crate) mod subgroup;
+
+pub use executor::{
+    ComputeDispatch, ComputeInvocation, FragmentDispatch, FragmentInvocation, Shared,
+};
+pub use image::{AddressMode, Filter, HostImage, HostSampler};
+
+pub(crate) use executor::{control_barrier, memory_barrier, subgroup_barrier};
+pub(crate) use scalar::BinOp;
@@ -0,0 +1,275 @@
+//! Dynamically-typed scalars, for implementing generic intrinsics on the CPU.
+
+use crate::{Scalar, ScalarComposite, ScalarOrVector, ScalarOrVectorTransform};
+use core::any::Any;
+use core::mem::size_of;
+use std::vec::Vec;
+
+/// The value of any [`Scalar`] type.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub(crate) enum ScalarValue {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+macro_rules! for_each_scalar {
+    ($m:ident) => {
+        $m! {
+            Bool(bool), U8(u8), U16(u16), U32(u32), U64(u64),
+            I8(i8), I16(i16), I32(i32), I64(i64), F32(f32), F64(f64)
+        }
+    };
+}
+
+impl ScalarValue {
+    pub(crate) fn from_scalar<S: Scalar>(value: S) -> Self {
+        let value: &dyn Any = &value;
+        macro_rules! downcast {
+            ($($variant:ident($ty:ty)),+) => {
+                $(if let Some(&value) = value.downcast_ref::<$ty>() {
+                    return Self::$variant(value);
+                })+
+            };
+        }
+        for_each_scalar!(downcast);
+        unreachable!(
+            "unsupported `Scalar` type `{}`",
+            core::any::type_name::<S>()
+        )
+    }
+
+    /// Convert back to a [`Scalar`] type, which must be of the same type.
+    pub(crate) fn to_scalar<S: Scalar>(self) -> S {
+        macro_rules! downcast {
+            ($($variant:ident($ty:ty)),+) => {
+                match self {
+                    $(Self::$variant(value) => (&value as &dyn Any).downcast_ref::<S>().copied(),)+
+                }
+            };
+        }
+        for_each_scalar!(downcast).unwrap_or_else(|| {
+            unreachable!("`{self:?}` is not a `{}`", core::any::type_name::<S>())
+        })
+    }
+
+    /// Convert (numerically) to a `f64`, with `bool`s mapped to `0.0`/`1.0`.
+    pub(crate) fn to_f64(self) -> f64 {
+        match self {
+            Self::Bool(value) => f64::from(u8::from(value)),
+            Self::U8(value) => value.into(),
+            Self::U16(value) => value.into(),
+            Self::U32(value) => value.into(),
+            Self::U64(value) => value as f64,
+            Self::I8(value) => value.into(),
+            Self::I16(value) => value.into(),
+            Self::I32(value) => value.into(),
+            Self::I64(value) => value as f64,
+            Self::F32(value) => value.into(),
+            Self::F64(value) => value,
+        }
+    }
+
+    /// Convert (numerically, saturating) `value` to the same type as `self`.
+    pub(crate) fn with_f64(self, value: f64) -> Self {
+        match self {
+            Self::Bool(_) => Self::Bool(value != 0.0),
+            Self::U8(_) => Self::U8(value as _),
+            Self::U16(_) => Self::U16(value as _),
+            Self::U32(_) => Self::U32(value as _),
+            Self::U64(_) => Self::U64(value as _),
+            Self::I8(_) => Self::I8(value as _),
+            Self::I16(_) => Self::I16(value as _),
+            Self::I32(_) => Self::I32(value as _),
+            Self::I64(_) => Self::I64(value as _),
+            Self::F32(_) => Self::F32(value as _),
+            Self::F64(_) => Self::F64(value),
+        }
+    }
+}
+
+/// Binary operation of a group reduction/scan, or an atomic instruction,
+/// with its integer signedness (or float-ness) implied by the operands.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum BinOp {
+    Add,
+    Mul,
+    Min,
+    Max,
+    And,
+    Or,
+    Xor,
+}
+
+impl BinOp {
+    /// Get the `BinOp` of a `OpGroupNonUniform*` instruction (e.g. `FAdd`).
+    pub(crate) fn from_group_op_name(name: &str) -> Self {
+        match name.strip_prefix("OpGroupNonUniform").unwrap_or(name) {
+            "IAdd" | "FAdd" => Self::Add,
+            "IMul" | "FMul" => Self::Mul,
+            "SMin" | "UMin" | "FMin" => Self::Min,
+            "SMax" | "UMax" | "FMax" => Self::Max,
+            "BitwiseAnd" | "LogicalAnd" => Self::And,
+            "BitwiseOr" | "LogicalOr" => Self::Or,
+            "BitwiseXor" | "LogicalXor" => Self::Xor,
+            _ => unreachable!("unknown group operation `{name}`"),
+        }
+    }
+
+    pub(crate) fn apply(self, a: ScalarValue, b: ScalarValue) -> ScalarValue {
+        use ScalarValue::{Bool, F32, F64, I8, I16, I32, I64, U8, U16, U32, U64};
+        macro_rules! int {
+            ($variant:ident, $a:ident, $b:ident) => {
+                $variant(match self {
+                    Self::Add => $a.wrapping_add($b),
+                    Self::Mul => $a.wrapping_mul($b),
+                    Self::Min => $a.min($b),
+                    Self::Max => $a.max($b),
+                    Self::And => $a & $b,
+                    Self::Or => $a | $b,
+                    Self::Xor => $a ^ $b,
+                })
+            };
+        }
+        macro_rules! float {
+            ($variant:ident, $a:ident, $b:ident) => {
+                $variant(match self {
+                    Self::Add => $a + $b,
+                    Self::Mul => $a * $b,
+                    Self::Min => $a.min($b),
+                    Self::Max => $a.max($b),
+                    Self::And | Self::Or | Self::Xor => {
+                        unreachable!("`{self:?}` on floats")
+                    }
+                })
+            };
+        }
+        match (a, b) {
+            (Bool(a), Bool(b)) => Bool(match self {
+                Self::And | Self::Min => a & b,
+                Self::Or | Self::Max => a | b,
+                Self::Xor => a ^ b,
+                Self::Add | Self::Mul => unreachable!("`{self:?}` on booleans"),
+            }),
+            (U8(a), U8(b)) => int!(U8, a, b),
+            (U16(a), U16(b)) => int!(U16, a, b),
+            (U32(a), U32(b)) => int!(U32, a, b),
+            (U64(a), U64(b)) => int!(U64, a, b),
+            (I8(a), I8(b)) => int!(I8, a, b),
+            (I16(a), I16(b)) => int!(I16, a, b),
+            (I32(a), I32(b)) => int!(I32, a, b),
+            (I64(a), I64(b)) => int!(I64, a, b),
+            (F32(a), F32(b)) => float!(F32, a, b),
+            (F64(a), F64(b)) => float!(F64, a, b),
+            _ => unreachable!("mismatched operand types `{a:?}` and `{b:?}`"),
+        }
+    }
+
+    /// The identity `I` of this operation (i.e. `op(I, x) == x`), of the same
+    /// type as `like`.
+    pub(crate) fn identity(self, like: ScalarValue) -> ScalarValue {
+        use ScalarValue::{Bool, F32, F64, I8, I16, I32, I64, U8, U16, U32, U64};
+        macro_rules! int {
+            ($variant:ident($ty:ty)) => {
+                $variant(match self {
+                    Self::Add | Self::Or | Self::Xor => 0,
+                    Self::Mul => 1,
+                    Self::Min => <$ty>::MAX,
+                    Self::Max => <$ty>::MIN,
+                    Self::And => !0,
+                })
+            };
+        }
+        macro_rules! float {
+            ($variant:ident($ty:ty)) => {
+                $variant(match self {
+                    Self::Add => 0.0,
+                    Self::Mul => 1.0,
+                    Self::Min => <$ty>::INFINITY,
+                    Self::Max => <$ty>::NEG_INFINITY,
+                    Self::And | Self::Or | Self::Xor => {
+                        unreachable!("`{self:?}` on floats")
+                    }
+                })
+            };
+        }
+        match like {
+            Bool(_) => Bool(matches!(self, Self::And | Self::Min)),
+            U8(_) => int!(U8(u8)),
+            U16(_) => int!(U16(u16)),
+            U32(_) => int!(U32(u32)),
+            U64(_) => int!(U64(u64)),
+            I8(_) => int!(I8(i8)),
+            I16(_) => int!(I16(i16)),
+            I32(_) => int!(I32(i32)),
+            I64(_) => int!(I64(i64)),
+            F32(_) => float!(F32(f32)),
+            F64(_) => float!(F64(f64)),
+        }
+    }
+}
+
+// NOTE the functions below assume vectors are laid out like arrays of
+// their scalar components, which is the case for all `glam` vector types
+// (and is also how `#[rust_gpu::vector::v1]` structs are expected to look).
+
+/// Get the first `n` components of a scalar or vector (or other value with the
+/// same layout as an array of at least `n` scalars of type `S`).
+pub(crate) fn components<S: Scalar, V>(value: &V, n: usize) -> impl Iterator<Item = ScalarValue> {
+    assert!(n * size_of::<S>() <= size_of::<V>());
+    let ptr = (value as *const V).cast::<S>();
+    // SAFETY: checked above to be in bounds.
+    (0..n).map(move |i| ScalarValue::from_scalar(unsafe { ptr.add(i).read_unaligned() }))
+}
+
+/// Write `components` (of type `S`) into the start of `value`, the opposite
+/// of `components`.
+pub(crate) fn set_components<S: Scalar, V>(
+    value: &mut V,
+    components: impl IntoIterator<Item = ScalarValue>,
+) {
+    let ptr = (value as *mut V).cast::<S>();
+    for (i, component) in components.into_iter().enumerate() {
+        assert!((i + 1) * size_of::<S>() <= size_of::<V>());
+        // SAFETY: checked above to be in bounds.
+        unsafe { ptr.add(i).write_unaligned(component.to_scalar()) }
+    }
+}
+
+/// Apply `f` to each component of `value`.
+pub(crate) fn map_components<V: ScalarOrVector>(
+    value: V,
+    mut f: impl FnMut(usize, ScalarValue) -> ScalarValue,
+) -> V {
+    let mut result = V::default();
+    set_components::<V::Scalar, V>(
+        &mut result,
+        components::<V::Scalar, V>(&value, V::N.get())
+            .enumerate()
+            .map(|(i, x)| f(i, x)),
+    );
+    result
+}
+
+/// Get all the scalar components of a composite, in order.
+pub(crate) fn flatten<T: ScalarComposite>(value: T) -> Vec<ScalarValue> {
+    struct Flatten(Vec<ScalarValue>);
+    impl ScalarOrVectorTransform for Flatten {
+        fn transform<T: ScalarOrVector>(&mut self, value: T) -> T {
+            self.0
+                .extend(components::<T::Scalar, T>(&value, T::N.get()));
+            value
+        }
+    }
+    let mut flatten = Flatten(Vec::new());
+    value.transform(&mut flatten);
+    flatten.0
+}
@@ -0,0 +1,425 @@
+//! Emulation of subgroup (and quad) operations, and derivatives.
+
+use super::executor::{Exchange, subgroup_exchange, subgroup_lane_and_size};
+use super::scalar::{BinOp, ScalarValue, components, flatten, map_components};
+use crate::ScalarOrVector;
+use crate::arch::{GroupOperation, SubgroupMask};
+use core::mem::size_of;
+use std::vec::Vec;
+
+pub(crate) fn elect() -> bool {
+    let exchange = subgroup_exchange("OpGroupNonUniformElect", ());
+    exchange.active().next().map(|(lane, ())| lane) == Some(exchange.lane)
+}
+
+pub(crate) fn all(predicate: bool) -> bool {
+    let exchange = subgroup_exchange("OpGroupNonUniformAll", predicate);
+    exchange.active().all(|(_, predicate)| predicate)
+}
+
+pub(crate) fn any(predicate: bool) -> bool {
+    let exchange = subgroup_exchange("OpGroupNonUniformAny", predicate);
+    exchange.active().any(|(_, predicate)| predicate)
+}
+
+pub(crate) fn all_equal<T: crate::ScalarComposite>(value: T) -> bool {
+    let exchange = subgroup_exchange("OpGroupNonUniformAllEqual", value);
+    let value = flatten(value);
+    exchange.active().all(|(_, other)| flatten(other) == value)
+}
+
+/// `values[lane]`, or `value` itself (for undefined results, e.g. reading
+/// from an inactive or out of bounds lane).
+fn read_lane<T: Copy>(exchange: &Exchange<T>, value: T, lane: u32) -> T {
+    exchange.get(lane).unwrap_or(value)
+}
+
+pub(crate) fn broadcast<T: Copy + 'static>(value: T, id: u32) -> T {
+    read_lane(
+        &subgroup_exchange("OpGroupNonUniformBroadcast", value),
+        value,
+        id,
+    )
+}
+
+pub(crate) fn broadcast_first<T: Copy + 'static>(value: T) -> T {
+    let exchange = subgroup_exchange("OpGroupNonUniformBroadcastFirst", value);
+    exchange.active().next().map_or(value, |(_, value)| value)
+}
+
+pub(crate) fn ballot(predicate: bool) -> SubgroupMask {
+    let exchange = subgroup_exchange("OpGroupNonUniformBallot", predicate);
+    let mut mask = [0; 4];
+    for (lane, predicate) in exchange.active() {
+        if predicate {
+            mask[lane as usize / 32] |= 1 << (lane % 32);
+        }
+    }
+    SubgroupMask::from_array(mask)
+}
+
+fn mask_bit(mask: SubgroupMask, index: u32) -> bool {
+    index < 128 && mask[index as usize / 32] & (1 << (index % 32)) != 0
+}
+
+pub(crate) fn inverse_ballot(value: SubgroupMask) -> bool {
+    mask_bit(value, subgroup_lane_and_size().0)
+}
+
+pub(crate) fn ballot_bit_extract(value: SubgroupMask, index: u32) -> bool {
+    mask_bit(value, index)
+}
+
+pub(crate) fn ballot_bit_count(value: SubgroupMask, group_op: GroupOperation) -> u32 {
+    let (lane, size) = subgroup_lane_and_size();
+    let end = match group_op {
+        GroupOperation::Reduce => size,
+        GroupOperation::InclusiveScan => lane + 1,
+        GroupOperation::ExclusiveScan => lane,
+        _ => unreachable!("`{group_op:?}` is not supported by `OpGroupNonUniformBallotBitCount`"),
+    };
+    (0..end).filter(|&i| mask_bit(value, i)).count() as u32
+}
+
+pub(crate) fn ballot_find_lsb(value: SubgroupMask) -> u32 {
+    let size = subgroup_lane_and_size().1;
+    (0..size).find(|&i| mask_bit(value, i)).unwrap_or(u32::MAX)
+}
+
+pub(crate) fn ballot_find_msb(value: SubgroupMask) -> u32 {
+    let size = subgroup_lane_and_size().1;
+    (0..size)
+        .rev()
+        .find(|&i| mask_bit(value, i))
+        .unwrap_or(u32::MAX)
+}
+
+pub(crate) fn shuffle<T: Copy + 'static>(value: T, id: u32) -> T {
+    read_lane(
+        &subgroup_exchange("OpGroupNonUniformShuffle", value),
+        value,
+        id,
+    )
+}
+
+pub(crate) fn shuffle_xor<T: Copy + 'static>(value: T, mask: u32) -> T {
+    let exchange = subgroup_exchange("OpGroupNonUniformShuffleXor", value);
+    read_lane(&exchange, value, exchange.lane ^ mask)
+}
+
+pub(crate) fn shuffle_up<T: Copy + 'static>(value: T, delta: u32) -> T {
+    let exchange = subgroup_exchange("OpGroupNonUniformShuffleUp", value);
+    match exchange.lane.checked_sub(delta) {
+        Some(lane) => read_lane(&exchange, value, lane),
+        None => value,
+    }
+}
+
+pub(crate) fn shuffle_down<T: Copy + 'static>(value: T, delta: u32) -> T {
+    let exchange = subgroup_exchange("OpGroupNonUniformShuffleDown", value);
+    read_lane(&exchange, value, exchange.lane.saturating_add(delta))
+}
+
+/// Reduce the values (of active lanes) in `lanes`, component-wise.
+fn reduce<V: ScalarOrVector>(
+    op: BinOp,
+    exchange: &Exchange<V>,
+    value: V,
+    lanes: impl Iterator<Item = u32>,
+) -> V {
+    let values: Vec<Vec<ScalarValue>> = lanes
+        .filter_map(|lane| exchange.get(lane))
+        .map(|value| components::<V::Scalar, V>(&value, V::N.get()).collect())
+        .collect();
+    map_components(value, |i, component| {
+        values
+            .iter()
+            .fold(op.identity(component), |acc, value| op.apply(acc, value[i]))
+    })
+}
+
+/// Emulate a `OpGroupNonUniform*` arithmetic instruction (e.g. `FAdd`).
+pub(crate) fn group_op<V: ScalarOrVector>(
+    op_name: &'static str,
+    group_op: GroupOperation,
+    value: V,
+) -> V {
+    let op = BinOp::from_group_op_name(op_name);
+    let exchange = subgroup_exchange(op_name, value);
+    let lane = exchange.lane;
+    match group_op {
+        GroupOperation::Reduce => reduce(op, &exchange, value, 0..exchange.values.len() as u32),
+        GroupOperation::InclusiveScan => reduce(op, &exchange, value, 0..=lane),
+        GroupOperation::ExclusiveScan => reduce(op, &exchange, value, 0..lane),
+        _ => unreachable!("`{group_op:?}` is not supported by `{op_name}`"),
+    }
+}
+
+/// Emulate a `OpGroupNonUniform*` arithmetic instruction (e.g. `FAdd`), with
+/// a `ClusteredReduce` operation.
+pub(crate) fn group_op_clustered<V: ScalarOrVector>(
+    op_name: &'static str,
+    cluster_size: u32,
+    value: V,
+) -> V {
+    let op = BinOp::from_group_op_name(op_name);
+    let exchange = subgroup_exchange(op_name, value);
+    let cluster_start = exchange.lane & !(cluster_size - 1);
+    reduce(
+        op,
+        &exchange,
+        value,
+        cluster_start..cluster_start + cluster_size,
+    )
+}
+
+pub(crate) fn quad_broadcast<T: Copy + 'static>(value: T, index: u32) -> T {
+    let exchange = subgroup_exchange("OpGroupNonUniformQuadBroadcast", value);
+    read_lane(&exchange, value, (exchange.lane & !3) + index)
+}
+
+pub(crate) fn quad_swap<T: Copy + 'static>(value: T, direction: u32) -> T {
+    let exchange = subgroup_exchange("OpGroupNonUniformQuadSwap", value);
+    // Horizontal (0), vertical (1) and diagonal (2) swaps flip the lowest,
+    // second-lowest or both bits, of the quad index, respectively.
+    read_lane(&exchange, value, exchange.lane ^ (direction + 1))
+}
+
+pub(crate) fn quad_all(predicate: bool) -> bool {
+    let exchange = subgroup_exchange("OpGroupNonUniformQuadAllKHR", predicate);
+    let quad = exchange.lane & !3;
+    exchange
+        .active()
+        .filter(|&(lane, _)| lane & !3 == quad)
+        .all(|(_, predicate)| predicate)
+}
+
+pub(crate) fn quad_any(predicate: bool) -> bool {
+    let exchange = subgroup_exchange("OpGroupNonUniformQuadAnyKHR", predicate);
+    let quad = exchange.lane & !3;
+    exchange
+        .active()
+        .filter(|&(lane, _)| lane & !3 == quad)
+        .any(|(_, predicate)| predicate)
+}
+
+/// The kind of derivative computed by `derivative`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DerivativeKind {
+    Dx,
+    Dy,
+    Fwidth,
+}
+
+/// Emulate `OpDPdx*`/`OpDPdy*`/`OpFwidth*`, by differencing the values of the
+/// quad (every 4 consecutive subgroup lanes, with quad indices as described in
+/// `FragmentDispatch`), and treating missing neighbors as constant.
+///
+/// `T` must be (laid out like) an array of (at most 4) `f32`s.
+pub(crate) fn derivative<T: Default>(value: T, kind: DerivativeKind, coarse: bool) -> T {
+    let n = size_of::<T>() / size_of::<f32>();
+    assert!(n <= 4 && size_of::<T>().is_multiple_of(size_of::<f32>()));
+    let mut components = [0.0f32; 4];
+    // SAFETY: `T` has the layout of `[f32; n]`, as checked above.
+    unsafe {
+        core::ptr::copy_nonoverlapping(
+            (&raw const value).cast::<f32>(),
+            components.as_mut_ptr(),
+            n,
+        );
+    }
+
+    let exchange = subgroup_exchange("OpDPdx/OpDPdy/OpFwidth", components);
+    let quad = exchange.lane & !3;
+    let quad_index = exchange.lane & 3;
+    let get = |quad_index: u32| exchange.get(quad + quad_index).unwrap_or(components);
+    let diff = |from: u32, to: u32| {
+        let (from, to) = (get(from), get(to));
+        core::array::from_fn::<f32, 4, _>(|i| to[i] - from[i])
+    };
+    let (row, column) = if coarse {
+        (0, 0)
+    } else {
+        (quad_index & 2, quad_index & 1)
+    };
+    let dx = || diff(row, row + 1);
+    let dy = || diff(column, column + 2);
+    let result = match kind {
+        DerivativeKind::Dx => dx(),
+        DerivativeKind::Dy => dy(),
+        DerivativeKind::Fwidth => {
+            let (dx, dy) = (dx(), dy());
+            core::array::from_fn(|i| dx[i].abs() + dy[i].abs())
+        }
+    };
+
+    let mut value = T::default();
+    // SAFETY: `T` has the layout of `[f32; n]`, as checked above.
+    unsafe {
+        core::ptr::copy_nonoverlapping(result.as_ptr(), (&raw mut value).cast::<f32>(), n);
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{ComputeDispatch, FragmentDispatch, Shared};
+    use crate::arch::*;
+    use glam::{UVec2, UVec3, Vec2};
+    use std::vec::Vec;
+
+    /// Run `shader` on a single workgroup of `len` invocations, in subgroups of
+    /// `subgroup_size`, collecting its result for every invocation.
+    fn run<T: Copy + Default>(len: u32, subgroup_size: u32, shader: impl Fn(u32) -> T) -> Vec<T> {
+        let outputs = Shared::new(std::vec![T::default(); len as usize]);
+        ComputeDispatch::new(UVec3::ONE, UVec3::new(len, 1, 1))
+            .subgroup_size(subgroup_size)
+            .run(|invocation| {
+                let index = invocation.local_invocation_index;
+                let output = shader(index);
+                unsafe { outputs.get_mut()[index as usize] = output };
+            });
+        outputs.into_inner()
+    }
+
+    #[test]
+    fn elect_all_any() {
+        assert_eq!(
+            run(8, 4, |_| subgroup_elect()),
+            [true, false, false, false, true, false, false, false]
+        );
+        assert_eq!(
+            run(8, 4, |i| subgroup_all(i != 1)),
+            [false, false, false, false, true, true, true, true]
+        );
+        assert_eq!(
+            run(8, 4, |i| subgroup_any(i == 1)),
+            [true, true, true, true, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn ballot_ignores_returned_invocations() {
+        let masks = Shared::new(std::vec![SubgroupMask::ZERO; 40]);
+        ComputeDispatch::new(UVec3::ONE, UVec3::new(40, 1, 1))
+            .subgroup_size(64)
+            .run(|invocation| {
+                let index = invocation.local_invocation_index;
+                if index == 3 {
+                    return;
+                }
+                let mask = subgroup_ballot(index % 2 == 1 || index >= 32);
+                unsafe { masks.get_mut()[index as usize] = mask };
+            });
+        let masks = masks.into_inner();
+        let expected = SubgroupMask::new(0xaaaa_aaa2, 0xff, 0, 0);
+        assert!(
+            masks
+                .iter()
+                .enumerate()
+                .all(|(i, &mask)| i == 3 || mask == expected)
+        );
+    }
+
+    #[test]
+    fn i_add_reduce_and_scans() {
+        assert_eq!(
+            run(8, 4, |i| subgroup_i_add(i + 1)),
+            [10, 10, 10, 10, 26, 26, 26, 26]
+        );
+        assert_eq!(
+            run(8, 4, |i| subgroup_inclusive_i_add(i + 1)),
+            [1, 3, 6, 10, 5, 11, 18, 26]
+        );
+        assert_eq!(
+            run(8, 4, |i| subgroup_exclusive_i_add(i + 1)),
+            [0, 1, 3, 6, 0, 5, 11, 18]
+        );
+        assert_eq!(
+            run(8, 8, |i| unsafe { subgroup_clustered_i_add::<2, _>(i + 1) }),
+            [3, 3, 7, 7, 11, 11, 15, 15]
+        );
+        assert_eq!(
+            run(4, 4, |i| subgroup_f_add(Vec2::new(i as f32, 1.0))),
+            [Vec2::new(6.0, 4.0); 4]
+        );
+    }
+
+    #[test]
+    fn shuffles_and_broadcasts() {
+        assert_eq!(
+            run(8, 8, |i| subgroup_shuffle_xor(i * 10, 3)),
+            [30, 20, 10, 0, 70, 60, 50, 40]
+        );
+        assert_eq!(
+            run(8, 4, |i| unsafe { subgroup_broadcast(i * 10, 2) }),
+            [20, 20, 20, 20, 60, 60, 60, 60]
+        );
+        assert_eq!(
+            run(8, 8, |i| subgroup_quad_broadcast(i, 3)),
+            [3, 3, 3, 3, 7, 7, 7, 7]
+        );
+        assert_eq!(
+            run(
+                4,
+                4,
+                subgroup_quad_swap::<{ QuadDirection::Diagonal as u32 }, u32>
+            ),
+            [3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn quad_all_any() {
+        assert_eq!(
+            run(8, 8, |i| subgroup_quad_all(i != 1)),
+            [false, false, false, false, true, true, true, true]
+        );
+        assert_eq!(
+            run(8, 8, |i| subgroup_quad_any(i == 5)),
+            [false, false, false, false, true, true, true, true]
+        );
+    }
+
+    #[test]
+    fn subgroup_ops_outside_dispatch() {
+        assert!(subgroup_elect());
+        assert_eq!(subgroup_i_add(5u32), 5);
+        assert_eq!(subgroup_exclusive_i_add(5u32), 0);
+        assert_eq!(subgroup_ballot(true), SubgroupMask::new(1, 0, 0, 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn divergent_subgroup_ops() {
+        run(4, 4, |i| {
+            if i == 0 {
+                subgroup_any(true)
+            } else {
+                subgroup_all(true)
+            }
+        });
+    }
+
+    #[test]
+    fn quad_swaps_and_derivatives() {
+        // A 3x3 target is covered by 2x2 quads, with helper invocations (past
+        // the right and bottom edges) only contributing to derivatives.
+        let outputs = FragmentDispatch::new(UVec2::new(3, 3)).run(|invocation| {
+            let coord = invocation.frag_coord.truncate().truncate();
+            let value = coord.x * coord.x + 10.0 * coord.y;
+            let horizontal =
+                subgroup_quad_swap::<{ QuadDirection::Horizontal as u32 }, _>(invocation.pixel);
+            (horizontal, value.dfdx(), value.dfdy(), coord.fwidth())
+        });
+        for (i, (horizontal, dx, dy, fwidth)) in outputs.into_iter().enumerate() {
+            let pixel = UVec2::new(i as u32 % 3, i as u32 / 3);
+            assert_eq!(horizontal, UVec2::new(pixel.x ^ 1, pixel.y));
+            // `x * x` differenced between the two columns of the quad.
+            let x0 = (pixel.x & !1) as f32 + 0.5;
+            assert_eq!(dx, (x0 + 1.0) * (x0 + 1.0) - x0 * x0);
+            assert_eq!(dy, 10.0);
+            assert_eq!(fwidth, Vec2::ONE);
+        }
+    }
+}
@@ -1,4 +1,36 @@
 //! support functions for debug printf
+//!
+//! Besides the C-style [`debug_printf!`](crate::debug_printf!) macros, values can also be printed
+//! with Rust-style format strings, through [`debug_print!`](crate::debug_print!) and
+//! [`debug_println!`](crate::debug_println!):
+//! ```no_run
+//! # use spirv_std::glam::Vec3;
+//! # use spirv_std::{GpuDebug, debug_println, spirv};
+//! #[derive(Copy, Clone, GpuDebug)]
+//! pub struct Particle {
+//!     pub pos: Vec3,
+//!     pub id: u32,
+//! }
+//!
+//! #[spirv(compute(threads(64)))]
+//! pub fn main_cs(#[spirv(storage_buffer, descriptor_set = 0, binding = 0)] particles: &[Particle]) {
+//!     let particle = particles[0];
+//!     // Prints e.g. `Particle { pos: Vec3(1.000000, 2.000000, 3.000000), id: 7 } 7`.
+//!     debug_println!("{particle:?} {}", particle.id);
+//! }
+//! ```
+//!
+//! Format strings are lowered to `NonSemantic.DebugPrintf` ones at compile time, once the types
+//! of all the arguments are known, so there's no need to pick a specifier matching each argument.
+//! Only `{}` (for types implementing [`GpuDisplay`]), `{:?}` (for types implementing
+//! [`GpuDebug`]), `{:x}`/`{:x?}` (hexadecimal integers) and precision (e.g. `{:.2}`) are supported.
+//!
+//! As with the C-style macros, the `SPV_KHR_non_semantic_info` extension must be enabled.
+
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+#[cfg(target_arch = "spirv")]
+use core::mem::MaybeUninit;
 
 use crate::{Scalar, Vector};
 
@@ -11,3 +43,204 @@ pub fn assert_is_type<T>(ty: T) -> T {
 pub fn assert_is_vector<TY: Scalar, V: Vector<TY, SIZE>, const SIZE: usize>(vec: V) -> V {
     vec
 }
+
+/// Types which can be printed with `{:?}`, in [`debug_print!`](crate::debug_print!) and
+/// [`debug_println!`](crate::debug_println!) format strings.
+///
+/// This is implemented for 32-bit scalars, `u64`, `usize`, `bool`, [`glam`] vectors (of `f32`,
+/// `u32` or `i32`) and arrays, and can be derived (with `#[derive(GpuDebug)]`) for structs
+/// whose fields all implement `GpuDebug`, and for enums without fields (which are printed
+/// using the name of their variant). Like `core::fmt::Debug`, structs are printed along with
+/// the names of their fields, e.g. `Particle { pos: Vec3(1.000000, 2.000000, 3.000000), id: 7 }`.
+pub trait GpuDebug {
+    #[doc(hidden)]
+    fn gpu_debug_node(&self) -> DebugNode;
+}
+
+/// Types which can be printed with `{}`, in [`debug_print!`](crate::debug_print!) and
+/// [`debug_println!`](crate::debug_println!) format strings.
+///
+/// Only scalars, `bool`s and [`glam`] vectors (which are printed as e.g. `[1.000000, 2.000000]`)
+/// implement `GpuDisplay`.
+pub trait GpuDisplay: GpuDebug {}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn gpu_display_node<T: GpuDisplay + ?Sized>(value: &T) -> DebugNode {
+    value.gpu_debug_node()
+}
+
+/// Opaque compile-time description of a value being printed, which only exists until the
+/// format string (and arguments) of `debug_print!` are lowered, after inlining.
+///
+/// Each kind of node is an instruction of the `Rust.spirv-std.GpuDebug` extended instruction
+/// set (see also the `gpu_debug` module of the linker, which consumes them):
+/// 1. `Scalar %value` (for 32-bit scalars and `u64`s)
+/// 2. `Vector %name %value` (for vectors, with the name of the vector type)
+/// 3. `Struct %name` (structs with named fields, followed by `NamedField`s)
+/// 4. `TupleStruct %name` (tuple structs, followed by `Field`s)
+/// 5. `Array` (followed by `Field`s)
+/// 6. `Field %prev %field_node`
+/// 7. `NamedField %prev %field_name %field_node`
+/// 8. `Enum %variant_index %variant_name_0 %variant_name_1 ...`
+#[doc(hidden)]
+#[derive(Copy, Clone)]
+#[repr(transparent)]
+pub struct DebugNode {
+    _node: u32,
+}
+
+impl DebugNode {
+    #[doc(hidden)]
+    #[spirv_std_macros::gpu_only]
+    #[inline(always)]
+    pub fn scalar<T: Scalar>(value: T) -> Self {
+        unsafe {
+            let mut node = MaybeUninit::uninit();
+            asm! {
+                "%gpu_debug = OpExtInstImport \"Rust.spirv-std.GpuDebug\"",
+                "%value = OpLoad _ {value}",
+                "%node = OpExtInst typeof*{node} %gpu_debug 1 %value",
+                "OpStore {node} %node",
+                value = in(reg) &value,
+                node = in(reg) node.as_mut_ptr(),
+            }
+            node.assume_init()
+        }
+    }
+
+    #[doc(hidden)]
+    #[spirv_std_macros::gpu_only]
+    #[inline(always)]
+    pub fn array() -> Self {
+        unsafe {
+            let mut node = MaybeUninit::uninit();
+            asm! {
+                "%gpu_debug = OpExtInstImport \"Rust.spirv-std.GpuDebug\"",
+                "%node = OpExtInst typeof*{node} %gpu_debug 5",
+                "OpStore {node} %node",
+                node = in(reg) node.as_mut_ptr(),
+            }
+            node.assume_init()
+        }
+    }
+
+    #[doc(hidden)]
+    #[spirv_std_macros::gpu_only]
+    #[inline(always)]
+    pub fn field(self, field: Self) -> Self {
+        unsafe {
+            let mut node = MaybeUninit::uninit();
+            asm! {
+                "%gpu_debug = OpExtInstImport \"Rust.spirv-std.GpuDebug\"",
+                "%prev = OpLoad _ {prev}",
+                "%field = OpLoad _ {field}",
+                "%node = OpExtInst typeof*{node} %gpu_debug 6 %prev %field",
+                "OpStore {node} %node",
+                prev = in(reg) &self,
+                field = in(reg) &field,
+                node = in(reg) node.as_mut_ptr(),
+            }
+            node.assume_init()
+        }
+    }
+}
+
+macro_rules! impl_gpu_debug_scalar {
+    ($($ty:ty => $scalar:ty),+ $(,)?) => {$(
+        impl GpuDebug for $ty {
+            #[inline(always)]
+            fn gpu_debug_node(&self) -> DebugNode {
+                DebugNode::scalar(*self as $scalar)
+            }
+        }
+        impl GpuDisplay for $ty {}
+    )+};
+}
+
+impl_gpu_debug_scalar! {
+    u32 => u32,
+    i32 => i32,
+    f32 => f32,
+    u64 => u64,
+    usize => u32,
+}
+
+impl GpuDebug for bool {
+    #[spirv_std_macros::gpu_only]
+    #[inline(always)]
+    fn gpu_debug_node(&self) -> DebugNode {
+        unsafe {
+            let variant_index = *self as u32;
+            let mut node = MaybeUninit::uninit();
+            asm! {
+                "%gpu_debug = OpExtInstImport \"Rust.spirv-std.GpuDebug\"",
+                "%false = OpString \"false\"",
+                "%true = OpString \"true\"",
+                "%variant_index = OpLoad _ {variant_index}",
+                "%node = OpExtInst typeof*{node} %gpu_debug 8 %variant_index %false %true",
+                "OpStore {node} %node",
+                variant_index = in(reg) &variant_index,
+                node = in(reg) node.as_mut_ptr(),
+            }
+            node.assume_init()
+        }
+    }
+}
+impl GpuDisplay for bool {}
+
+macro_rules! impl_gpu_debug_vector {
+    ($($ty:ident),+ $(,)?) => {$(
+        impl GpuDebug for glam::$ty {
+            #[spirv_std_macros::gpu_only]
+            #[inline(always)]
+            fn gpu_debug_node(&self) -> DebugNode {
+                unsafe {
+                    let mut node = MaybeUninit::uninit();
+                    asm! {
+                        "%gpu_debug = OpExtInstImport \"Rust.spirv-std.GpuDebug\"",
+                        concat!("%name = OpString \"", stringify!($ty), "\""),
+                        "%value = OpLoad _ {value}",
+                        "%node = OpExtInst typeof*{node} %gpu_debug 2 %name %value",
+                        "OpStore {node} %node",
+                        value = in(reg) self,
+                        node = in(reg) node.as_mut_ptr(),
+                    }
+                    node.assume_init()
+                }
+            }
+        }
+        impl GpuDisplay for glam::$ty {}
+    )+};
+}
+
+impl_gpu_debug_vector! {
+    Vec2, Vec3, Vec3A, Vec4,
+    UVec2, UVec3, UVec4,
+    IVec2, IVec3, IVec4,
+}
+
+// NOTE arrays are unrolled (instead of using a loop), as the whole
+// description of the printed value has to be known at compile time.
+macro_rules! impl_gpu_debug_array {
+    ([$($i:literal)*] $n:literal $($rest:literal)*) => {
+        impl<T: GpuDebug> GpuDebug for [T; $n] {
+            #[inline(always)]
+            fn gpu_debug_node(&self) -> DebugNode {
+                DebugNode::array()$(.field(self[$i].gpu_debug_node()))*
+            }
+        }
+        impl_gpu_debug_array!([$($i)* $n] $($rest)*);
+    };
+    ([$($i:literal)*]) => {};
+}
+
+impl_gpu_debug_array!([] 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16);
+
+impl<T: GpuDebug + ?Sized> GpuDebug for &T {
+    #[inline(always)]
+    fn gpu_debug_node(&self) -> DebugNode {
+        (**self).gpu_debug_node()
+    }
+}
+impl<T: GpuDisplay + ?Sized> GpuDisplay for &T {}
@@ -0,0 +1,198 @@
+//! Bindless descriptor tables, from `SPV_EXT_descriptor_indexing`.
+//!
+//! A [`DescriptorTable`] is a runtime-sized array of descriptors (images, samplers, storage
+//! buffers or acceleration structures), bound to a single `(descriptor_set, binding)` pair,
+//! which is indexed with either a [`Uniform`] or a [`NonUniform`] index. The latter must be
+//! used whenever the index may differ between invocations (e.g. when it comes from per-draw
+//! or per-material data).
+//!
+//! The capabilities required by descriptor tables (`RuntimeDescriptorArray`, and for
+//! [`NonUniform`] indices, `ShaderNonUniform` and the `*ArrayNonUniformIndexing` capability
+//! specific to each kind of descriptor that is indexed), and `SPV_EXT_descriptor_indexing`
+//! (before SPIR-V 1.5), are enabled automatically. The bindings of all descriptor tables
+//! are also listed in `CompileResult::variable_count_bindings`, as they will usually need the
+//! `VARIABLE_DESCRIPTOR_COUNT` binding flag on the host.
+//!
+//! # Example
+//! ```no_run
+//! # use spirv_std::descriptor_table::*;
+//! # use spirv_std::glam::{Vec2, Vec4};
+//! # use spirv_std::{Image, Sampler, spirv};
+//! pub struct Material {
+//!     pub texture: u32,
+//!     pub tint: Vec4,
+//! }
+//!
+//! #[spirv(fragment)]
+//! pub fn main_fs(
+//!     uv: Vec2,
+//!     #[spirv(flat)] material: u32,
+//!     #[spirv(descriptor_set = 0, binding = 0)] sampler: &Sampler,
+//!     #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] materials: &[Material],
+//!     #[spirv(descriptor_set = 1, binding = 0)] textures: &DescriptorTable<
+//!         Image!(2D, type=f32, sampled),
+//!     >,
+//!     output: &mut Vec4,
+//! ) {
+//!     let material = &materials[material as usize];
+//!     let texture = unsafe { textures.get(NonUniform(material.texture)) };
+//!     let color: Vec4 = texture.sample(*sampler, uv);
+//!     *output = color * material.tint;
+//! }
+//! ```
+
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use core::marker::PhantomData;
+
+use crate::image::{Image, SampleType, SampledImage};
+use crate::ray_tracing::AccelerationStructure;
+use crate::{Sampler, TypedBuffer};
+
+/// Runtime-sized array of descriptors, for "bindless" resource binding.
+///
+/// Like [`RuntimeArray`](crate::RuntimeArray), the length of a descriptor table isn't known
+/// in the shader, so accessing one is always `unsafe`, but unlike `RuntimeArray`, the index
+/// has to explicitly be either [`Uniform`] or [`NonUniform`], and only [`Descriptor`]s can be
+/// placed in a descriptor table.
+///
+/// Examples (of entry-point parameters):
+/// - `#[spirv(descriptor_set = 0, binding = 0)] textures: &DescriptorTable<Image!(2D, type=f32, sampled)>`
+/// - `#[spirv(descriptor_set = 0, binding = 1)] samplers: &DescriptorTable<Sampler>`
+/// - `#[spirv(storage_buffer, descriptor_set = 0, binding = 2)] buffers: &mut DescriptorTable<TypedBuffer<[u32]>>`
+/// - `#[spirv(descriptor_set = 0, binding = 3)] scenes: &DescriptorTable<AccelerationStructure>`
+#[spirv(runtime_array)]
+// HACK(eddyb) avoids "transparent newtype of `_anti_zst_padding`" misinterpretation.
+#[repr(C)]
+// HACK(eddyb) false positive due to `rustc` not understanding e.g. entry-points.
+#[allow(dead_code)]
+pub struct DescriptorTable<T: Descriptor> {
+    // HACK(eddyb) avoids the layout becoming ZST (and being elided in one way
+    // or another, before `#[spirv(runtime_array)]` can special-case it).
+    _anti_zst_padding: core::mem::MaybeUninit<u32>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Descriptor> DescriptorTable<T> {
+    /// Get the descriptor at `index`.
+    ///
+    /// # Safety
+    /// Bounds checking is not performed, and `index` must be less than the number of
+    /// descriptors bound to this table (and those descriptors must have been written).
+    /// If `index` is [`Uniform`], it must also be dynamically uniform.
+    #[inline]
+    pub unsafe fn get(&self, index: impl DescriptorIndex) -> &T {
+        unsafe { index.access_chain(self) }
+    }
+
+    /// Get the descriptor at `index`, mutably (e.g. to write to a storage buffer).
+    ///
+    /// # Safety
+    /// Bounds checking is not performed, and `index` must be less than the number of
+    /// descriptors bound to this table (and those descriptors must have been written).
+    /// If `index` is [`Uniform`], it must also be dynamically uniform.
+    #[inline]
+    pub unsafe fn get_mut(&mut self, index: impl DescriptorIndex) -> &mut T {
+        unsafe { index.access_chain_mut(self) }
+    }
+}
+
+/// Types which can be placed in a [`DescriptorTable`].
+///
+/// # Safety
+/// Must only be implemented for types which are SPIR-V descriptors (i.e. that are lowered
+/// to opaque handle types, or to interface blocks).
+pub unsafe trait Descriptor {}
+
+unsafe impl<
+    SampledType: SampleType<FORMAT, COMPONENTS>,
+    const DIM: u32,
+    const DEPTH: u32,
+    const ARRAYED: u32,
+    const MULTISAMPLED: u32,
+    const SAMPLED: u32,
+    const FORMAT: u32,
+    const COMPONENTS: u32,
+> Descriptor
+    for Image<SampledType, DIM, DEPTH, ARRAYED, MULTISAMPLED, SAMPLED, FORMAT, COMPONENTS>
+{
+}
+unsafe impl<I: Descriptor> Descriptor for SampledImage<I> {}
+unsafe impl Descriptor for Sampler {}
+unsafe impl Descriptor for AccelerationStructure {}
+unsafe impl<T: ?Sized> Descriptor for TypedBuffer<T> {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An index into a [`DescriptorTable`], either [`Uniform`] or [`NonUniform`].
+pub trait DescriptorIndex: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    unsafe fn access_chain<T: Descriptor>(self, table: &DescriptorTable<T>) -> &T;
+
+    #[doc(hidden)]
+    unsafe fn access_chain_mut<T: Descriptor>(self, table: &mut DescriptorTable<T>) -> &mut T;
+}
+
+/// Dynamically uniform index into a [`DescriptorTable`], i.e. one that is the same for all
+/// invocations (within e.g. a draw or dispatch).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Uniform(pub u32);
+
+/// Index into a [`DescriptorTable`] that may differ between invocations.
+///
+/// Accesses through such an index are decorated with `NonUniform`, which requires the
+/// `ShaderNonUniform` capability (see the [module-level documentation](self)).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NonUniform(pub u32);
+
+macro_rules! impl_descriptor_index {
+    ($($index:ident => [$($decorations:literal),*]),+ $(,)?) => {$(
+        impl sealed::Sealed for $index {}
+
+        impl DescriptorIndex for $index {
+            #[spirv_std_macros::gpu_only]
+            unsafe fn access_chain<T: Descriptor>(self, table: &DescriptorTable<T>) -> &T {
+                unsafe {
+                    let mut result_slot = core::mem::MaybeUninit::uninit();
+                    asm! {
+                        $($decorations,)*
+                        "%index = OpLoad _ {index}",
+                        "%result = OpAccessChain typeof*{result_slot} {table} %index",
+                        "OpStore {result_slot} %result",
+                        result_slot = in(reg) result_slot.as_mut_ptr(),
+                        table = in(reg) table,
+                        index = in(reg) &self.0,
+                    }
+                    result_slot.assume_init()
+                }
+            }
+
+            #[spirv_std_macros::gpu_only]
+            unsafe fn access_chain_mut<T: Descriptor>(
+                self,
+                table: &mut DescriptorTable<T>,
+            ) -> &mut T {
+                unsafe {
+                    let mut result_slot = core::mem::MaybeUninit::uninit();
+                    asm! {
+                        $($decorations,)*
+                        "%index = OpLoad _ {index}",
+                        "%result = OpAccessChain typeof*{result_slot} {table} %index",
+                        "OpStore {result_slot} %result",
+                        result_slot = in(reg) result_slot.as_mut_ptr(),
+                        table = in(reg) table,
+                        index = in(reg) &self.0,
+                    }
+                    result_slot.assume_init()
+                }
+            }
+        }
+    )+};
+}
+
+impl_descriptor_index! {
+    Uniform => [],
+    NonUniform => ["OpDecorate %index NonUniform", "OpDecorate %result NonUniform"],
+}
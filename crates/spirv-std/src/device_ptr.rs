@@ -0,0 +1,336 @@
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use core::marker::PhantomData;
+use core::mem;
+
+/// Typed pointer into device memory, obtained from a buffer device address
+/// (i.e. `vkGetBufferDeviceAddress`), and accessed through SPIR-V
+/// `PhysicalStorageBuffer` pointers.
+///
+/// A `DevicePtr<T>` has the same layout as a `u64` address, so it can be
+/// passed around in push constants, uniform and storage buffers, and even
+/// stored in device memory itself (e.g. for linked data structures).
+///
+/// Using `DevicePtr` from a shader requires the `PhysicalStorageBufferAddresses`
+/// and `Int64` capabilities, and the `SPV_KHR_physical_storage_buffer` extension
+/// (when targeting SPIR-V older than 1.5), e.g.:
+/// ```text
+/// SpirvBuilder::new(...)
+///     .capability(Capability::PhysicalStorageBufferAddresses)
+///     .capability(Capability::Int64)
+///     .extension("SPV_KHR_physical_storage_buffer")
+/// ```
+///
+/// # Example
+/// ```no_run
+/// # use spirv_std::{DevicePtr, spirv};
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// pub struct PushConstants {
+///     positions: DevicePtr<[f32; 4]>,
+/// }
+///
+/// #[spirv(vertex)]
+/// pub fn main_vs(
+///     #[spirv(push_constant)] constants: &PushConstants,
+///     #[spirv(vertex_index)] vertex_index: u32,
+///     #[spirv(position)] position: &mut spirv_std::glam::Vec4,
+/// ) {
+///     let p = unsafe { constants.positions.add(vertex_index as usize).read() };
+///     *position = p.into();
+/// }
+/// ```
+#[repr(transparent)]
+pub struct DevicePtr<T> {
+    addr: u64,
+    _phantom: PhantomData<*mut T>,
+}
+
+// NOTE manual impls to avoid requiring `T: Clone`/`T: Copy`/etc.
+impl<T> Clone for DevicePtr<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DevicePtr<T> {}
+
+impl<T> PartialEq for DevicePtr<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.addr == other.addr
+    }
+}
+
+impl<T> Eq for DevicePtr<T> {}
+
+impl<T> core::fmt::Debug for DevicePtr<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "DevicePtr({:#x})", self.addr)
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: 'static> bytemuck::Zeroable for DevicePtr<T> {}
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: 'static> bytemuck::Pod for DevicePtr<T> {}
+
+impl<T> DevicePtr<T> {
+    /// The null device address.
+    pub const NULL: Self = Self::from_addr_unchecked(0);
+
+    /// Creates a `DevicePtr` from a device address, returning `None` if the
+    /// address is null, or isn't suitably aligned for `T`.
+    #[inline]
+    pub fn new(addr: u64) -> Option<Self> {
+        let ptr = Self::from_addr_unchecked(addr);
+        (!ptr.is_null() && ptr.is_aligned()).then_some(ptr)
+    }
+
+    /// Creates a `DevicePtr` from a device address, without checking it.
+    ///
+    /// This is always safe, as accessing the memory it points to is `unsafe`.
+    #[inline]
+    pub const fn from_addr_unchecked(addr: u64) -> Self {
+        Self {
+            addr,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the device address this pointer points to.
+    #[inline]
+    pub const fn addr(self) -> u64 {
+        self.addr
+    }
+
+    /// Returns `true` if this pointer is null.
+    #[inline]
+    pub const fn is_null(self) -> bool {
+        self.addr == 0
+    }
+
+    /// Returns `true` if this pointer is suitably aligned for `T`.
+    #[inline]
+    pub const fn is_aligned(self) -> bool {
+        self.addr & (mem::align_of::<T>() as u64 - 1) == 0
+    }
+
+    /// Casts to a pointer of another type, keeping the same address.
+    #[inline]
+    pub const fn cast<U>(self) -> DevicePtr<U> {
+        DevicePtr::from_addr_unchecked(self.addr)
+    }
+
+    /// Offsets the pointer by `count` elements of type `T` (i.e. `count * size_of::<T>()` bytes).
+    #[inline]
+    #[must_use]
+    pub const fn add(self, count: usize) -> Self {
+        self.byte_add(count as u64 * mem::size_of::<T>() as u64)
+    }
+
+    /// Offsets the pointer by `count` elements of type `T`, which may be negative.
+    #[inline]
+    #[must_use]
+    pub const fn offset(self, count: isize) -> Self {
+        Self::from_addr_unchecked(
+            self.addr
+                .wrapping_add_signed(count as i64 * mem::size_of::<T>() as i64),
+        )
+    }
+
+    /// Offsets the pointer by `bytes` bytes, without regard for alignment.
+    #[inline]
+    #[must_use]
+    pub const fn byte_add(self, bytes: u64) -> Self {
+        Self::from_addr_unchecked(self.addr.wrapping_add(bytes))
+    }
+
+    /// Returns a shared reference to the pointee.
+    ///
+    /// Loads through the returned reference are emitted with an `Aligned`
+    /// memory operand (see also [`DevicePtr::is_aligned`]).
+    ///
+    /// # Safety
+    /// The address must be non-null, aligned for `T`, and point to a valid `T`
+    /// within a buffer created with `VK_BUFFER_USAGE_SHADER_DEVICE_ADDRESS_BIT`,
+    /// which must not be written to (by any invocation) for the lifetime `'a`.
+    #[spirv_std_macros::gpu_only]
+    #[inline]
+    pub unsafe fn as_ref<'a>(self) -> &'a T {
+        unsafe {
+            let mut result_slot = mem::MaybeUninit::uninit();
+            asm! {
+                "%addr = OpLoad _ {addr}",
+                "%result = OpConvertUToPtr typeof*{result_slot} %addr",
+                "OpStore {result_slot} %result",
+                addr = in(reg) &self.addr,
+                result_slot = in(reg) result_slot.as_mut_ptr(),
+            }
+            result_slot.assume_init()
+        }
+    }
+
+    /// Returns a mutable reference to the pointee.
+    ///
+    /// # Safety
+    /// Same as [`DevicePtr::as_ref`], except that the pointee must not be
+    /// accessed through any other pointer for the lifetime `'a`.
+    #[spirv_std_macros::gpu_only]
+    #[inline]
+    pub unsafe fn as_mut<'a>(self) -> &'a mut T {
+        unsafe {
+            let mut result_slot = mem::MaybeUninit::uninit();
+            asm! {
+                "%addr = OpLoad _ {addr}",
+                "%result = OpConvertUToPtr typeof*{result_slot} %addr",
+                "OpStore {result_slot} %result",
+                addr = in(reg) &self.addr,
+                result_slot = in(reg) result_slot.as_mut_ptr(),
+            }
+            result_slot.assume_init()
+        }
+    }
+
+    /// Reads the value pointed to.
+    ///
+    /// # Safety
+    /// The address must be non-null, aligned for `T`, and point to a valid `T`.
+    #[inline]
+    pub unsafe fn read(self) -> T
+    where
+        T: Copy,
+    {
+        unsafe { *self.as_ref() }
+    }
+
+    /// Overwrites the value pointed to with `value`.
+    ///
+    /// # Safety
+    /// The address must be non-null, aligned for `T`, and point to memory
+    /// valid for writes of a `T`.
+    #[inline]
+    pub unsafe fn write(self, value: T) {
+        unsafe {
+            *self.as_mut() = value;
+        }
+    }
+}
+
+/// Bounds-carrying slice of device memory, i.e. a [`DevicePtr`] with a length.
+///
+/// Like [`DevicePtr`], it can be passed around in push constants and buffers,
+/// and has the same requirements when used in a shader.
+#[repr(C)]
+pub struct DeviceSlice<T> {
+    ptr: DevicePtr<T>,
+    len: u32,
+    // NOTE explicit padding, to avoid implicit padding bytes on the CPU.
+    _padding: u32,
+}
+
+impl<T> Clone for DeviceSlice<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DeviceSlice<T> {}
+
+impl<T> core::fmt::Debug for DeviceSlice<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeviceSlice")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: 'static> bytemuck::Zeroable for DeviceSlice<T> {}
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: 'static> bytemuck::Pod for DeviceSlice<T> {}
+
+impl<T> DeviceSlice<T> {
+    /// An empty slice, pointing to the null device address.
+    pub const EMPTY: Self = Self::new(DevicePtr::NULL, 0);
+
+    /// Creates a slice of `len` elements, starting at `ptr`.
+    #[inline]
+    pub const fn new(ptr: DevicePtr<T>, len: u32) -> Self {
+        Self {
+            ptr,
+            len,
+            _padding: 0,
+        }
+    }
+
+    /// Returns a pointer to the first element.
+    #[inline]
+    pub const fn as_ptr(self) -> DevicePtr<T> {
+        self.ptr
+    }
+
+    /// Returns the number of elements in the slice.
+    #[inline]
+    pub const fn len(self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns `true` if the slice has no elements.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a pointer to the element at `index`, or `None` if out of bounds.
+    #[inline]
+    pub fn get(self, index: usize) -> Option<DevicePtr<T>> {
+        (index < self.len()).then(|| self.ptr.add(index))
+    }
+
+    /// Returns the subslice starting at `start`, with (up to) `len` elements,
+    /// clamped to the bounds of `self`.
+    #[inline]
+    #[must_use]
+    pub fn subslice(self, start: usize, len: usize) -> Self {
+        let start = start.min(self.len());
+        let len = len.min(self.len() - start);
+        Self::new(self.ptr.add(start), len as u32)
+    }
+
+    /// Reads the element at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Safety
+    /// The slice must point to `self.len()` valid, aligned `T`s.
+    #[inline]
+    pub unsafe fn read(self, index: usize) -> T
+    where
+        T: Copy,
+    {
+        match self.get(index) {
+            Some(ptr) => unsafe { ptr.read() },
+            None => panic!("index out of bounds"),
+        }
+    }
+
+    /// Writes `value` to the element at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Safety
+    /// The slice must point to memory valid for writes of `self.len()` aligned `T`s.
+    #[inline]
+    pub unsafe fn write(self, index: usize, value: T) {
+        match self.get(index) {
+            Some(ptr) => unsafe { ptr.write(value) },
+            None => panic!("index out of bounds"),
+        }
+    }
+}
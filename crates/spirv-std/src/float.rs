@@ -1,12 +1,14 @@
 //! Traits and helper functions related to floats.
 
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+use crate::cpu_emulation::float as emulated;
 #[cfg(target_arch = "spirv")]
 use core::arch::asm;
 use glam::{Vec2, Vec4};
 
 /// Converts two f32 values (floats) into two f16 values (halfs). The result is a u32, with the low
 /// 16 bits being the first f16, and the high 16 bits being the second f16.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::vec2_to_f16x2(vec))]
 pub fn vec2_to_f16x2(vec: Vec2) -> u32 {
     let result;
     unsafe {
@@ -25,7 +27,7 @@ pub fn vec2_to_f16x2(vec: Vec2) -> u32 {
 
 /// Converts two f16 values (halfs) into two f32 values (floats). The parameter is a u32, with the
 /// low 16 bits being the first f16, and the high 16 bits being the second f16.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::f16x2_to_vec2(int))]
 pub fn f16x2_to_vec2(int: u32) -> Vec2 {
     let mut result = Default::default();
     unsafe {
@@ -43,14 +45,14 @@ pub fn f16x2_to_vec2(int: u32) -> Vec2 {
 
 /// Converts an f32 (float) into an f16 (half). The result is a u32, not a u16, due to GPU support
 /// for u16 not being universal - the upper 16 bits will always be zero.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 pub fn f32_to_f16(float: f32) -> u32 {
     vec2_to_f16x2(glam::Vec2::new(float, 0.))
 }
 
 /// Converts an f16 (half) into an f32 (float). The parameter is a u32, due to GPU support for u16
 /// not being universal - the upper 16 bits are ignored.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation)]
 pub fn f16_to_f32(packed: u32) -> f32 {
     f16x2_to_vec2(packed).x
 }
@@ -58,7 +60,7 @@ pub fn f16_to_f32(packed: u32) -> f32 {
 /// Packs a vec4 into 4 8-bit signed integers. See
 /// [PackSnorm4x8](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for exact
 /// semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::vec4_to_u8x4_snorm(vec))]
 pub fn vec4_to_u8x4_snorm(vec: Vec4) -> u32 {
     let result;
     unsafe {
@@ -78,7 +80,7 @@ pub fn vec4_to_u8x4_snorm(vec: Vec4) -> u32 {
 /// Packs a vec4 into 4 8-bit unsigned integers. See
 /// [PackUnorm4x8](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for exact
 /// semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::vec4_to_u8x4_unorm(vec))]
 pub fn vec4_to_u8x4_unorm(vec: Vec4) -> u32 {
     let result;
     unsafe {
@@ -98,7 +100,7 @@ pub fn vec4_to_u8x4_unorm(vec: Vec4) -> u32 {
 /// Packs a vec2 into 2 16-bit signed integers. See
 /// [PackSnorm2x16](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for exact
 /// semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::vec2_to_u16x2_snorm(vec))]
 pub fn vec2_to_u16x2_snorm(vec: Vec2) -> u32 {
     let result;
     unsafe {
@@ -118,7 +120,7 @@ pub fn vec2_to_u16x2_snorm(vec: Vec2) -> u32 {
 /// Packs a vec2 into 2 16-bit unsigned integers. See
 /// [PackUnorm2x16](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for exact
 /// semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::vec2_to_u16x2_unorm(vec))]
 pub fn vec2_to_u16x2_unorm(vec: Vec2) -> u32 {
     let result;
     unsafe {
@@ -138,7 +140,7 @@ pub fn vec2_to_u16x2_unorm(vec: Vec2) -> u32 {
 /// Unpacks 4 8-bit signed integers into a vec4. See
 /// [UnpackSnorm4x8](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for exact
 /// semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::u8x4_to_vec4_snorm(int))]
 pub fn u8x4_to_vec4_snorm(int: u32) -> Vec4 {
     let mut result = Default::default();
     unsafe {
@@ -157,7 +159,7 @@ pub fn u8x4_to_vec4_snorm(int: u32) -> Vec4 {
 /// Unpacks 4 8-bit unsigned integers into a vec4. See
 /// [UnpackSnorm4x8](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for exact
 /// semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::u8x4_to_vec4_unorm(int))]
 pub fn u8x4_to_vec4_unorm(int: u32) -> Vec4 {
     let mut result = Default::default();
     unsafe {
@@ -176,7 +178,7 @@ pub fn u8x4_to_vec4_unorm(int: u32) -> Vec4 {
 /// Unpacks 2 16-bit signed integers into a vec2. See
 /// [UnpackSnorm2x16](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for
 /// exact semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::u16x2_to_vec2_snorm(int))]
 pub fn u16x2_to_vec2_snorm(int: u32) -> Vec2 {
     let mut result = Default::default();
     unsafe {
@@ -195,7 +197,7 @@ pub fn u16x2_to_vec2_snorm(int: u32) -> Vec2 {
 /// Unpacks 2 16-bit unsigned integers into a vec2. See
 /// [UnpackUnorm2x16](https://www.khronos.org/registry/SPIR-V/specs/1.0/GLSL.std.450.html) for
 /// exact semantics.
-#[spirv_std_macros::gpu_only]
+#[spirv_std_macros::gpu_only(cpu_emulation = emulated::u16x2_to_vec2_unorm(int))]
 pub fn u16x2_to_vec2_unorm(int: u32) -> Vec2 {
     let mut result = Default::default();
     unsafe {
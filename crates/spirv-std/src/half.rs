@@ -0,0 +1,314 @@
+//! Half-precision (`f16`) vector types and math, requiring the `f16` feature.
+//!
+//! Shaders using arithmetic on `f16` (including the types in this module) need the `Float16`
+//! capability, e.g. `SpirvBuilder::capability(Capability::Float16)`. Only loading and storing
+//! `f16` values from buffers is possible with just one of the 16-bit storage capabilities
+//! (e.g. `StorageBuffer16BitAccess`, from `SPV_KHR_16bit_storage`).
+//!
+//! Half-precision is mostly interesting on mobile GPUs, where it can double ALU throughput and
+//! halve register pressure, at the cost of only having ~3 decimal digits of precision.
+
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use glam::{Vec2, Vec3, Vec4};
+
+macro_rules! f16_vector {
+    ($(
+        $(#[$attr:meta])*
+        $name:ident($f32_vec:ident) { $($field:ident),+ } = $n:literal;
+    )+) => {$(
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug, Default, PartialEq)]
+        #[cfg_attr(target_arch = "spirv", rust_gpu::vector::v1)]
+        pub struct $name {
+            $(#[allow(missing_docs)] pub $field: f16,)+
+        }
+
+        impl $name {
+            /// All components set to `0.0`.
+            pub const ZERO: Self = Self::splat(0.0);
+            /// All components set to `1.0`.
+            pub const ONE: Self = Self::splat(1.0);
+
+            /// Creates a new vector from its components.
+            #[inline]
+            pub const fn new($($field: f16),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            /// Creates a vector with all components set to `v`.
+            #[inline]
+            pub const fn splat(v: f16) -> Self {
+                Self { $($field: v),+ }
+            }
+
+            /// Returns the components as an array.
+            #[inline]
+            pub const fn to_array(self) -> [f16; $n] {
+                [$(self.$field),+]
+            }
+
+            /// Returns the dot product of `self` and `rhs`.
+            #[inline]
+            pub fn dot(self, rhs: Self) -> f16 {
+                let mut sum = 0.0;
+                $(sum += self.$field * rhs.$field;)+
+                sum
+            }
+
+            /// Converts to a single-precision vector.
+            #[inline]
+            pub fn as_vec(self) -> $f32_vec {
+                $f32_vec::new($(self.$field as f32),+)
+            }
+        }
+
+        impl From<[f16; $n]> for $name {
+            #[inline]
+            fn from([$($field),+]: [f16; $n]) -> Self {
+                Self { $($field),+ }
+            }
+        }
+
+        impl From<$name> for [f16; $n] {
+            #[inline]
+            fn from(v: $name) -> Self {
+                v.to_array()
+            }
+        }
+
+        impl From<$f32_vec> for $name {
+            #[inline]
+            fn from(v: $f32_vec) -> Self {
+                Self { $($field: v.$field as f16),+ }
+            }
+        }
+
+        impl From<$name> for $f32_vec {
+            #[inline]
+            fn from(v: $name) -> Self {
+                v.as_vec()
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+            #[inline]
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        f16_vector!(@binop $name { $($field),+ } Add add AddAssign add_assign +);
+        f16_vector!(@binop $name { $($field),+ } Sub sub SubAssign sub_assign -);
+        f16_vector!(@binop $name { $($field),+ } Mul mul MulAssign mul_assign *);
+        f16_vector!(@binop $name { $($field),+ } Div div DivAssign div_assign /);
+    )+};
+
+    (@binop $name:ident { $($field:ident),+ }
+        $trait:ident $method:ident $assign_trait:ident $assign_method:ident $op:tt
+    ) => {
+        impl $trait for $name {
+            type Output = Self;
+            #[inline]
+            fn $method(self, rhs: Self) -> Self {
+                Self { $($field: self.$field $op rhs.$field),+ }
+            }
+        }
+
+        impl $trait<f16> for $name {
+            type Output = Self;
+            #[inline]
+            fn $method(self, rhs: f16) -> Self {
+                Self { $($field: self.$field $op rhs),+ }
+            }
+        }
+
+        impl $trait<$name> for f16 {
+            type Output = $name;
+            #[inline]
+            fn $method(self, rhs: $name) -> $name {
+                $name { $($field: self $op rhs.$field),+ }
+            }
+        }
+
+        impl $assign_trait for $name {
+            #[inline]
+            fn $assign_method(&mut self, rhs: Self) {
+                *self = *self $op rhs;
+            }
+        }
+
+        impl $assign_trait<f16> for $name {
+            #[inline]
+            fn $assign_method(&mut self, rhs: f16) {
+                *self = *self $op rhs;
+            }
+        }
+    };
+}
+
+f16_vector! {
+    /// A 2-dimensional vector of `f16`s, emitted as `OpTypeVector %half 2`.
+    #[repr(C, align(4))]
+    F16Vec2(Vec2) { x, y } = 2;
+
+    /// A 3-dimensional vector of `f16`s, emitted as `OpTypeVector %half 3`.
+    #[repr(C)]
+    F16Vec3(Vec3) { x, y, z } = 3;
+
+    /// A 4-dimensional vector of `f16`s, emitted as `OpTypeVector %half 4`.
+    #[repr(C, align(8))]
+    F16Vec4(Vec4) { x, y, z, w } = 4;
+}
+
+/// `GLSL.std.450` math on `f16` scalars and vectors, applied componentwise.
+///
+/// `core` only provides a few of these for `f16` (and some of those, like `f16::abs`, are
+/// implemented with bit manipulation, requiring the `Int16` capability as well), so prefer
+/// calling them through this trait, e.g. `F16Math::abs(x)`.
+pub trait F16Math: Copy + crate::sealed::Sealed {
+    /// `FAbs`: the absolute value.
+    fn abs(self) -> Self;
+    /// `FSign`: `1.0`, `0.0` or `-1.0`, depending on the sign.
+    fn signum(self) -> Self;
+    /// `Floor`: the nearest whole number less than or equal to `self`.
+    fn floor(self) -> Self;
+    /// `Ceil`: the nearest whole number greater than or equal to `self`.
+    fn ceil(self) -> Self;
+    /// `Fract`: `self - self.floor()`.
+    fn fract(self) -> Self;
+    /// `Sqrt`: the square root.
+    fn sqrt(self) -> Self;
+    /// `InverseSqrt`: `1.0 / self.sqrt()`.
+    fn inverse_sqrt(self) -> Self;
+    /// `Sin`: the sine, in radians.
+    fn sin(self) -> Self;
+    /// `Cos`: the cosine, in radians.
+    fn cos(self) -> Self;
+    /// `Tan`: the tangent, in radians.
+    fn tan(self) -> Self;
+    /// `Asin`: the arcsine, in radians.
+    fn asin(self) -> Self;
+    /// `Acos`: the arccosine, in radians.
+    fn acos(self) -> Self;
+    /// `Atan`: the arctangent, in radians.
+    fn atan(self) -> Self;
+    /// `Exp`: `e^self`.
+    fn exp(self) -> Self;
+    /// `Exp2`: `2^self`.
+    fn exp2(self) -> Self;
+    /// `Log`: the natural logarithm.
+    fn ln(self) -> Self;
+    /// `Log2`: the base 2 logarithm.
+    fn log2(self) -> Self;
+    /// `Atan2`: the arctangent of `self / other`, in radians, using both signs for the quadrant.
+    fn atan2(self, other: Self) -> Self;
+    /// `Pow`: `self^n`.
+    fn powf(self, n: Self) -> Self;
+    /// `FMin`: the minimum of `self` and `other`.
+    fn min(self, other: Self) -> Self;
+    /// `FMax`: the maximum of `self` and `other`.
+    fn max(self, other: Self) -> Self;
+    /// `FClamp`: `self.max(min).min(max)`.
+    fn clamp(self, min: Self, max: Self) -> Self;
+    /// `FMix`: the linear blend `self * (1.0 - t) + other * t`.
+    fn lerp(self, other: Self, t: Self) -> Self;
+    /// `Fma`: `self * a + b`, potentially fused.
+    fn mul_add(self, a: Self, b: Self) -> Self;
+}
+
+/// The `GLSL.std.450` instructions used by [`F16Math`] (with the same names and
+/// numbers as in `rspirv::spirv::GLOp`).
+#[cfg(target_arch = "spirv")]
+#[derive(Copy, Clone)]
+#[repr(u32)]
+enum GLOp {
+    FAbs = 4,
+    FSign = 6,
+    Floor = 8,
+    Ceil = 9,
+    Fract = 10,
+    Sin = 13,
+    Cos = 14,
+    Tan = 15,
+    Asin = 16,
+    Acos = 17,
+    Atan = 18,
+    Atan2 = 25,
+    Pow = 26,
+    Exp = 27,
+    Log = 28,
+    Exp2 = 29,
+    Log2 = 30,
+    Sqrt = 31,
+    InverseSqrt = 32,
+    FMin = 37,
+    FMax = 40,
+    FClamp = 43,
+    FMix = 46,
+    Fma = 50,
+}
+
+macro_rules! glsl_ext_inst {
+    ($($name:ident($($arg:ident),*) = $inst:ident;)+) => {$(
+        #[crate::macros::gpu_only]
+        #[inline]
+        fn $name(self $(, $arg: Self)*) -> Self {
+            let mut result = Self::default();
+            unsafe {
+                asm!(
+                    "%glsl = OpExtInstImport \"GLSL.std.450\"",
+                    "%self = OpLoad _ {this}",
+                    $(concat!("%", stringify!($arg), " = OpLoad _ {", stringify!($arg), "}"),)*
+                    concat!(
+                        "%result = OpExtInst typeof*{result} %glsl {inst} %self",
+                        $(" %", stringify!($arg),)*
+                    ),
+                    "OpStore {result} %result",
+                    inst = const GLOp::$inst as u32,
+                    this = in(reg) &self,
+                    $($arg = in(reg) &$arg,)*
+                    result = in(reg) &mut result,
+                );
+            }
+            result
+        }
+    )+};
+}
+
+macro_rules! impl_f16_math {
+    ($($ty:ty),+) => {$(
+        impl F16Math for $ty {
+            glsl_ext_inst! {
+                abs() = FAbs;
+                signum() = FSign;
+                floor() = Floor;
+                ceil() = Ceil;
+                fract() = Fract;
+                sin() = Sin;
+                cos() = Cos;
+                tan() = Tan;
+                asin() = Asin;
+                acos() = Acos;
+                atan() = Atan;
+                atan2(other) = Atan2;
+                powf(n) = Pow;
+                exp() = Exp;
+                ln() = Log;
+                exp2() = Exp2;
+                log2() = Log2;
+                sqrt() = Sqrt;
+                inverse_sqrt() = InverseSqrt;
+                min(other) = FMin;
+                max(other) = FMax;
+                clamp(min, max) = FClamp;
+                lerp(other, t) = FMix;
+                mul_add(a, b) = Fma;
+            }
+        }
+    )+};
+}
+
+impl_f16_math!(f16, F16Vec2, F16Vec3, F16Vec4);
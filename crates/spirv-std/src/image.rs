@@ -1,7 +1,9 @@
 //! Image types
 
-pub use self::params::{ImageCoordinate, ImageCoordinateSubpassData, ImageSizeQuery, SampleType};
-#[cfg(target_arch = "spirv")]
+pub use self::params::{
+    ImageAtomicType, ImageCoordinate, ImageCoordinateSubpassData, ImageSizeQuery, SampleType,
+};
+#[cfg(any(target_arch = "spirv", feature = "cpu-emulation"))]
 use crate::VectorTruncateInto;
 pub use crate::macros::Image;
 use crate::{Float, Integer, Sampler};
@@ -113,6 +115,86 @@ pub struct Image<
     _marker: core::marker::PhantomData<SampledType>,
 }
 
+/// Texel buffer read through a sampler-less `OpImageFetch` (`samplerBuffer`/`textureBuffer`
+/// in GLSL, `Buffer<T>` in HLSL), holding `COMPONENTS`-component texels of the scalar type `T`.
+///
+/// Texels are read with [`Image::load`], and the number of texels queried with [`Image::len`].
+/// For a storage texel buffer, see [`StorageTexelBuffer`].
+pub type UniformTexelBuffer<T = f32, const COMPONENTS: u32 = 4> = Image<
+    T,
+    { Dimensionality::Buffer as u32 },
+    { ImageDepth::Unknown as u32 },
+    { Arrayed::False as u32 },
+    { Multisampled::False as u32 },
+    { Sampled::Yes as u32 },
+    { ImageFormat::Unknown as u32 },
+    COMPONENTS,
+>;
+
+/// Texel buffer which can be both read and written (`imageBuffer` in GLSL, `RWBuffer<T>` in
+/// HLSL), holding `COMPONENTS`-component texels of the scalar type `T`.
+///
+/// Texels are read with [`Image::load`], written with [`Image::store`], and the number of
+/// texels queried with [`Image::len`].
+///
+/// As the format is unknown, the `StorageImageReadWithoutFormat` and/or
+/// `StorageImageWriteWithoutFormat` capabilities are required. For a texel buffer with a known
+/// format, use e.g. `Image!(buffer, format=rgba32f, sampled=false)` instead.
+pub type StorageTexelBuffer<T = f32, const COMPONENTS: u32 = 4> = Image<
+    T,
+    { Dimensionality::Buffer as u32 },
+    { ImageDepth::Unknown as u32 },
+    { Arrayed::False as u32 },
+    { Multisampled::False as u32 },
+    { Sampled::No as u32 },
+    { ImageFormat::Unknown as u32 },
+    COMPONENTS,
+>;
+
+/// Returns `true` if all the texels accessed by the sparse image operation which returned
+/// `residency_code` (e.g. [`Image::sparse_sample`]) were resident (i.e. backed by memory),
+/// or `false` if any of them weren't (in which case the texel values are undefined).
+///
+/// Requires Capability `SparseResidency`.
+#[crate::macros::gpu_only]
+#[doc(alias = "OpImageSparseTexelsResident")]
+#[inline]
+pub fn sparse_texels_resident(residency_code: u32) -> bool {
+    let mut result = false;
+    unsafe {
+        asm! {
+            "%bool = OpTypeBool",
+            "%residency_code = OpLoad _ {residency_code}",
+            "%result = OpImageSparseTexelsResident %bool %residency_code",
+            "OpStore {result} %result",
+            residency_code = in(reg) &residency_code,
+            result = in(reg) &mut result,
+        }
+    }
+    result
+}
+
+// HACK sparse image instructions return an `OpTypeStruct`, which can't
+// be declared in `asm!`, so it has to come from a Rust type (and `#[repr(C)]`
+// keeps the residency code as the first member, as required by SPIR-V).
+#[cfg(target_arch = "spirv")]
+#[derive(Default)]
+#[repr(C)]
+struct SparseResidencyResult<T> {
+    residency_code: u32,
+    value: T,
+}
+
+#[cfg(target_arch = "spirv")]
+impl<T> SparseResidencyResult<T> {
+    fn truncate_into<R>(self) -> (u32, R)
+    where
+        T: VectorTruncateInto<R>,
+    {
+        (self.residency_code, self.value.truncate_into())
+    }
+}
+
 impl<
     SampledType: SampleType<FORMAT, COMPONENTS>,
     const DIM: u32,
@@ -134,7 +216,7 @@ impl<
     >
 {
     /// Fetch a single texel with a sampler set at compile time
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation)]
     #[doc(alias = "OpImageFetch")]
     #[inline]
     pub fn fetch<I>(
@@ -150,7 +232,7 @@ impl<
     /// Fetch a single texel at a mipmap `lod` with a sampler set at compile time
     ///
     /// `lod` is also known as `level` in WGSL's `textureLoad`
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_read(&coordinate, lod))]
     #[doc(alias = "OpImageFetch")]
     #[inline]
     pub fn fetch_with_lod<I>(
@@ -163,6 +245,41 @@ impl<
     {
         self.fetch_with(coordinate, sample_with::lod(lod))
     }
+
+    /// Sparse version of [`Image::fetch`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the texel.
+    ///
+    /// Requires Capability `SparseResidency`.
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseFetch")]
+    #[inline]
+    pub fn sparse_fetch<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        I: Integer,
+    {
+        self.sparse_fetch_with_lod(coordinate, 0)
+    }
+
+    /// Sparse version of [`Image::fetch_with_lod`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the texel.
+    ///
+    /// Requires Capability `SparseResidency`.
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseFetch")]
+    #[inline]
+    pub fn sparse_fetch_with_lod<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        lod: u32,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        I: Integer,
+    {
+        self.sparse_fetch_with(coordinate, sample_with::lod(lod))
+    }
 }
 
 impl<
@@ -225,7 +342,7 @@ impl<
     }
 
     /// Sample texels at `coord` from the image using `sampler`.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_sample(sampler, &coord, 0.0))]
     #[inline]
     pub fn sample<F>(
         &self,
@@ -260,7 +377,7 @@ impl<
 
     /// Sample texels at `coord` from the image using `sampler`, after adding the input bias to the
     /// implicit level of detail.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_sample(sampler, &coord, bias))]
     #[inline]
     pub fn sample_bias<F>(
         &self,
@@ -297,7 +414,7 @@ impl<
     }
 
     /// Fetch a single texel with a sampler set at compile time
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_sample(sampler, &coordinate, lod))]
     #[doc(alias = "OpImageSampleExplicitLod")]
     /// Sample the image at a coordinate by a lod
     #[inline]
@@ -494,203 +611,285 @@ impl<
         }
         result
     }
-}
 
-impl<
-    SampledType: SampleType<FORMAT, COMPONENTS>,
-    const DIM: u32,
-    const DEPTH: u32,
-    const SAMPLED: u32,
-    const FORMAT: u32,
-    const COMPONENTS: u32,
->
-    Image<
-        SampledType,
-        DIM,
-        DEPTH,
-        { Arrayed::False as u32 },
-        { Multisampled::False as u32 },
-        SAMPLED,
-        FORMAT,
-        COMPONENTS,
-    >
-{
-    /// Sample the image with a project coordinate
+    /// Sparse version of [`Image::gather`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
-    #[doc(alias = "OpImageSampleProjImplicitLod")]
+    #[doc(alias = "OpImageSparseGather")]
     #[inline]
-    pub fn sample_with_project_coordinate<F>(
+    pub fn sparse_gather<F>(
         &self,
         sampler: Sampler,
-        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
-    ) -> SampledType::SampleResult
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+        component: u32,
+    ) -> (u32, SampledType::Vec4)
     where
+        Self: HasGather,
         F: Float,
     {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
         unsafe {
-            let mut result = SampledType::Vec4::default();
-            asm!(
+            asm! {
                 "OpDecorate %image NonUniform",
                 "OpDecorate %sampler NonUniform",
                 "OpDecorate %sampledImage NonUniform",
                 "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
                 "%image = OpLoad _ {this}",
                 "%sampler = OpLoad _ {sampler}",
-                "%project_coordinate = OpLoad _ {project_coordinate}",
-                "%sampledImage = OpSampledImage _ %image %sampler",
-                "%result = OpImageSampleProjImplicitLod typeof*{result} %sampledImage %project_coordinate",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseGather typeof*{result} %sampledImage %coordinate {component}",
                 "OpStore {result} %result",
                 result = in(reg) &mut result,
                 this = in(reg) self,
                 sampler = in(reg) &sampler,
-                project_coordinate = in(reg) &project_coordinate,
-            );
-            result.truncate_into()
+                coordinate = in(reg) &coordinate,
+                component = in(reg) component,
+            }
         }
+        (result.residency_code, result.value)
     }
 
+    /// Sparse version of [`Image::sample`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
-    #[doc(alias = "OpImageSampleProjExplicitLod")]
-    /// Sample the image with a project coordinate by a lod
+    #[doc(alias = "OpImageSparseSampleImplicitLod")]
     #[inline]
-    pub fn sample_with_project_coordinate_by_lod<F>(
+    pub fn sparse_sample<F>(
         &self,
         sampler: Sampler,
-        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleImplicitLod typeof*{result} %sampledImage %coordinate",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coordinate = in(reg) &coordinate,
+            }
+        }
+        result.truncate_into()
+    }
+
+    /// Sparse version of [`Image::sample_bias`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseSampleImplicitLod")]
+    #[inline]
+    pub fn sparse_sample_bias<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+        bias: f32,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%bias = OpLoad _ {bias}",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleImplicitLod typeof*{result} %sampledImage %coordinate Bias %bias",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coordinate = in(reg) &coordinate,
+                bias = in(reg) &bias,
+            }
+        }
+        result.truncate_into()
+    }
+
+    /// Sparse version of [`Image::sample_by_lod`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseSampleExplicitLod")]
+    #[inline]
+    pub fn sparse_sample_by_lod<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
         lod: f32,
-    ) -> SampledType::Vec4
+    ) -> (u32, SampledType::SampleResult)
     where
         F: Float,
     {
-        let mut result = Default::default();
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
         unsafe {
-            asm!(
+            asm! {
                 "OpDecorate %image NonUniform",
                 "OpDecorate %sampler NonUniform",
                 "OpDecorate %sampledImage NonUniform",
                 "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
                 "%image = OpLoad _ {this}",
                 "%sampler = OpLoad _ {sampler}",
-                "%project_coordinate = OpLoad _ {project_coordinate}",
+                "%coordinate = OpLoad _ {coordinate}",
                 "%lod = OpLoad _ {lod}",
-                "%sampledImage = OpSampledImage _ %image %sampler",
-                "%result = OpImageSampleProjExplicitLod typeof*{result} %sampledImage %project_coordinate Lod %lod",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleExplicitLod typeof*{result} %sampledImage %coordinate Lod %lod",
                 "OpStore {result} %result",
                 result = in(reg) &mut result,
                 this = in(reg) self,
                 sampler = in(reg) &sampler,
-                project_coordinate = in(reg) &project_coordinate,
-                lod = in(reg) &lod
-            );
+                coordinate = in(reg) &coordinate,
+                lod = in(reg) &lod,
+            }
         }
-        result
+        result.truncate_into()
     }
 
+    /// Sparse version of [`Image::sample_by_gradient`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
-    #[doc(alias = "OpImageSampleProjExplicitLod")]
-    /// Sample the image with a project coordinate based on a gradient formed by (dx, dy). Specifically, ([du/dx, dv/dx], [du/dy, dv/dy])
+    #[doc(alias = "OpImageSparseSampleExplicitLod")]
     #[inline]
-    pub fn sample_with_project_coordinate_by_gradient<F>(
+    pub fn sparse_sample_by_gradient<F>(
         &self,
         sampler: Sampler,
-        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
         gradient_dx: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
         gradient_dy: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
-    ) -> SampledType::Vec4
+    ) -> (u32, SampledType::SampleResult)
     where
         F: Float,
     {
-        let mut result = Default::default();
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
         unsafe {
-            asm!(
+            asm! {
                 "OpDecorate %image NonUniform",
                 "OpDecorate %sampler NonUniform",
                 "OpDecorate %sampledImage NonUniform",
                 "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
                 "%image = OpLoad _ {this}",
                 "%sampler = OpLoad _ {sampler}",
-                "%project_coordinate = OpLoad _ {project_coordinate}",
+                "%coordinate = OpLoad _ {coordinate}",
                 "%gradient_dx = OpLoad _ {gradient_dx}",
                 "%gradient_dy = OpLoad _ {gradient_dy}",
-                "%sampledImage = OpSampledImage _ %image %sampler",
-                "%result = OpImageSampleProjExplicitLod typeof*{result} %sampledImage %project_coordinate Grad %gradient_dx %gradient_dy",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleExplicitLod typeof*{result} %sampledImage %coordinate Grad %gradient_dx %gradient_dy",
                 "OpStore {result} %result",
                 result = in(reg) &mut result,
                 this = in(reg) self,
                 sampler = in(reg) &sampler,
-                project_coordinate = in(reg) &project_coordinate,
+                coordinate = in(reg) &coordinate,
                 gradient_dx = in(reg) &gradient_dx,
                 gradient_dy = in(reg) &gradient_dy,
-            );
+            }
         }
-        result
+        result.truncate_into()
     }
 
+    /// Sparse version of [`Image::sample_depth_reference`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
-    #[doc(alias = "OpImageSampleProjDrefImplicitLod")]
-    /// Sample the image's depth reference with the project coordinate
+    #[doc(alias = "OpImageSparseSampleDrefImplicitLod")]
     #[inline]
-    pub fn sample_depth_reference_with_project_coordinate<F>(
+    pub fn sparse_sample_depth_reference<F>(
         &self,
         sampler: Sampler,
-        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
         depth_reference: f32,
-    ) -> SampledType
+    ) -> (u32, SampledType)
     where
         F: Float,
     {
-        let mut result = Default::default();
+        let mut result = SparseResidencyResult::<SampledType>::default();
         unsafe {
-            asm!(
+            asm! {
                 "OpDecorate %image NonUniform",
                 "OpDecorate %sampler NonUniform",
                 "OpDecorate %sampledImage NonUniform",
                 "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
                 "%image = OpLoad _ {this}",
                 "%sampler = OpLoad _ {sampler}",
-                "%project_coordinate = OpLoad _ {project_coordinate}",
-                "%depth_reference = OpLoad _ {depth_reference}", // not required to do this way, but done for consistency
-                "%sampledImage = OpSampledImage _ %image %sampler",
-                "%result = OpImageSampleProjDrefImplicitLod typeof*{result} %sampledImage %project_coordinate %depth_reference",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%depth_reference = OpLoad _ {depth_reference}",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleDrefImplicitLod typeof*{result} %sampledImage %coordinate %depth_reference",
                 "OpStore {result} %result",
                 result = in(reg) &mut result,
                 this = in(reg) self,
                 sampler = in(reg) &sampler,
-                project_coordinate = in(reg) &project_coordinate,
+                coordinate = in(reg) &coordinate,
                 depth_reference = in(reg) &depth_reference,
-            );
+            }
         }
-        result
+        (result.residency_code, result.value)
     }
 
+    /// Sparse version of [`Image::sample_depth_reference_by_lod`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
-    #[doc(alias = "OpImageSampleProjDrefExplicitLod")]
-    /// Sample the image's depth reference with the project coordinate based on an explicit lod
+    #[doc(alias = "OpImageSparseSampleDrefExplicitLod")]
     #[inline]
-    pub fn sample_depth_reference_with_project_coordinate_by_lod<F>(
+    pub fn sparse_sample_depth_reference_by_lod<F>(
         &self,
         sampler: Sampler,
-        coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
         depth_reference: f32,
         lod: f32,
-    ) -> SampledType
+    ) -> (u32, SampledType)
     where
         F: Float,
     {
-        let mut result = Default::default();
+        let mut result = SparseResidencyResult::<SampledType>::default();
         unsafe {
-            asm!(
+            asm! {
                 "OpDecorate %image NonUniform",
                 "OpDecorate %sampler NonUniform",
                 "OpDecorate %sampledImage NonUniform",
                 "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
                 "%image = OpLoad _ {this}",
                 "%sampler = OpLoad _ {sampler}",
                 "%coordinate = OpLoad _ {coordinate}",
                 "%depth_reference = OpLoad _ {depth_reference}",
                 "%lod = OpLoad _ {lod}",
-                "%sampledImage = OpSampledImage _ %image %sampler",
-                "%result = OpImageSampleProjDrefExplicitLod typeof*{result} %sampledImage %coordinate %depth_reference Lod %lod",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleDrefExplicitLod typeof*{result} %sampledImage %coordinate %depth_reference Lod %lod",
                 "OpStore {result} %result",
                 result = in(reg) &mut result,
                 this = in(reg) self,
@@ -698,42 +897,45 @@ impl<
                 coordinate = in(reg) &coordinate,
                 depth_reference = in(reg) &depth_reference,
                 lod = in(reg) &lod,
-            )
+            }
         }
-        result
+        (result.residency_code, result.value)
     }
 
+    /// Sparse version of [`Image::sample_depth_reference_by_gradient`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the result.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
-    #[doc(alias = "OpImageSampleProjDrefExplicitLod")]
-    /// Sample the image's depth reference with the project coordinate based on a gradient formed by (dx, dy).
-    /// Specifically, ([du/dx, dv/dx], [du/dy, dv/dy])
+    #[doc(alias = "OpImageSparseSampleDrefExplicitLod")]
     #[inline]
-    pub fn sample_depth_reference_with_project_coordinate_by_gradient<F>(
+    pub fn sparse_sample_depth_reference_by_gradient<F>(
         &self,
         sampler: Sampler,
-        coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
         depth_reference: f32,
         gradient_dx: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
         gradient_dy: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
-    ) -> SampledType
+    ) -> (u32, SampledType)
     where
         F: Float,
     {
-        let mut result = Default::default();
+        let mut result = SparseResidencyResult::<SampledType>::default();
         unsafe {
-            asm!(
+            asm! {
                 "OpDecorate %image NonUniform",
                 "OpDecorate %sampler NonUniform",
                 "OpDecorate %sampledImage NonUniform",
                 "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
                 "%image = OpLoad _ {this}",
                 "%sampler = OpLoad _ {sampler}",
                 "%coordinate = OpLoad _ {coordinate}",
                 "%depth_reference = OpLoad _ {depth_reference}",
                 "%gradient_dx = OpLoad _ {gradient_dx}",
                 "%gradient_dy = OpLoad _ {gradient_dy}",
-                "%sampledImage = OpSampledImage _ %image %sampler",
-                "%result = OpImageSampleProjDrefExplicitLod typeof*{result} %sampledImage %coordinate %depth_reference Grad %gradient_dx %gradient_dy",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSampleDrefExplicitLod typeof*{result} %sampledImage %coordinate %depth_reference Grad %gradient_dx %gradient_dy",
                 "OpStore {result} %result",
                 result = in(reg) &mut result,
                 this = in(reg) self,
@@ -742,9 +944,9 @@ impl<
                 depth_reference = in(reg) &depth_reference,
                 gradient_dx = in(reg) &gradient_dx,
                 gradient_dy = in(reg) &gradient_dy,
-            );
+            }
         }
-        result
+        (result.residency_code, result.value)
     }
 }
 
@@ -752,7 +954,259 @@ impl<
     SampledType: SampleType<FORMAT, COMPONENTS>,
     const DIM: u32,
     const DEPTH: u32,
-    const ARRAYED: u32,
+    const SAMPLED: u32,
+    const FORMAT: u32,
+    const COMPONENTS: u32,
+>
+    Image<
+        SampledType,
+        DIM,
+        DEPTH,
+        { Arrayed::False as u32 },
+        { Multisampled::False as u32 },
+        SAMPLED,
+        FORMAT,
+        COMPONENTS,
+    >
+{
+    /// Sample the image with a project coordinate
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSampleProjImplicitLod")]
+    #[inline]
+    pub fn sample_with_project_coordinate<F>(
+        &self,
+        sampler: Sampler,
+        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+    ) -> SampledType::SampleResult
+    where
+        F: Float,
+    {
+        unsafe {
+            let mut result = SampledType::Vec4::default();
+            asm!(
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%project_coordinate = OpLoad _ {project_coordinate}",
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSampleProjImplicitLod typeof*{result} %sampledImage %project_coordinate",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                project_coordinate = in(reg) &project_coordinate,
+            );
+            result.truncate_into()
+        }
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSampleProjExplicitLod")]
+    /// Sample the image with a project coordinate by a lod
+    #[inline]
+    pub fn sample_with_project_coordinate_by_lod<F>(
+        &self,
+        sampler: Sampler,
+        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        lod: f32,
+    ) -> SampledType::Vec4
+    where
+        F: Float,
+    {
+        let mut result = Default::default();
+        unsafe {
+            asm!(
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%project_coordinate = OpLoad _ {project_coordinate}",
+                "%lod = OpLoad _ {lod}",
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSampleProjExplicitLod typeof*{result} %sampledImage %project_coordinate Lod %lod",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                project_coordinate = in(reg) &project_coordinate,
+                lod = in(reg) &lod
+            );
+        }
+        result
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSampleProjExplicitLod")]
+    /// Sample the image with a project coordinate based on a gradient formed by (dx, dy). Specifically, ([du/dx, dv/dx], [du/dy, dv/dy])
+    #[inline]
+    pub fn sample_with_project_coordinate_by_gradient<F>(
+        &self,
+        sampler: Sampler,
+        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        gradient_dx: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
+        gradient_dy: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
+    ) -> SampledType::Vec4
+    where
+        F: Float,
+    {
+        let mut result = Default::default();
+        unsafe {
+            asm!(
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%project_coordinate = OpLoad _ {project_coordinate}",
+                "%gradient_dx = OpLoad _ {gradient_dx}",
+                "%gradient_dy = OpLoad _ {gradient_dy}",
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSampleProjExplicitLod typeof*{result} %sampledImage %project_coordinate Grad %gradient_dx %gradient_dy",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                project_coordinate = in(reg) &project_coordinate,
+                gradient_dx = in(reg) &gradient_dx,
+                gradient_dy = in(reg) &gradient_dy,
+            );
+        }
+        result
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSampleProjDrefImplicitLod")]
+    /// Sample the image's depth reference with the project coordinate
+    #[inline]
+    pub fn sample_depth_reference_with_project_coordinate<F>(
+        &self,
+        sampler: Sampler,
+        project_coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        depth_reference: f32,
+    ) -> SampledType
+    where
+        F: Float,
+    {
+        let mut result = Default::default();
+        unsafe {
+            asm!(
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%project_coordinate = OpLoad _ {project_coordinate}",
+                "%depth_reference = OpLoad _ {depth_reference}", // not required to do this way, but done for consistency
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSampleProjDrefImplicitLod typeof*{result} %sampledImage %project_coordinate %depth_reference",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                project_coordinate = in(reg) &project_coordinate,
+                depth_reference = in(reg) &depth_reference,
+            );
+        }
+        result
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSampleProjDrefExplicitLod")]
+    /// Sample the image's depth reference with the project coordinate based on an explicit lod
+    #[inline]
+    pub fn sample_depth_reference_with_project_coordinate_by_lod<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        depth_reference: f32,
+        lod: f32,
+    ) -> SampledType
+    where
+        F: Float,
+    {
+        let mut result = Default::default();
+        unsafe {
+            asm!(
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%depth_reference = OpLoad _ {depth_reference}",
+                "%lod = OpLoad _ {lod}",
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSampleProjDrefExplicitLod typeof*{result} %sampledImage %coordinate %depth_reference Lod %lod",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coordinate = in(reg) &coordinate,
+                depth_reference = in(reg) &depth_reference,
+                lod = in(reg) &lod,
+            )
+        }
+        result
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSampleProjDrefExplicitLod")]
+    /// Sample the image's depth reference with the project coordinate based on a gradient formed by (dx, dy).
+    /// Specifically, ([du/dx, dv/dx], [du/dy, dv/dy])
+    #[inline]
+    pub fn sample_depth_reference_with_project_coordinate_by_gradient<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, { Arrayed::True as u32 }>,
+        depth_reference: f32,
+        gradient_dx: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
+        gradient_dy: impl ImageCoordinate<F, DIM, { Arrayed::False as u32 }>,
+    ) -> SampledType
+    where
+        F: Float,
+    {
+        let mut result = Default::default();
+        unsafe {
+            asm!(
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%depth_reference = OpLoad _ {depth_reference}",
+                "%gradient_dx = OpLoad _ {gradient_dx}",
+                "%gradient_dy = OpLoad _ {gradient_dy}",
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSampleProjDrefExplicitLod typeof*{result} %sampledImage %coordinate %depth_reference Grad %gradient_dx %gradient_dy",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coordinate = in(reg) &coordinate,
+                depth_reference = in(reg) &depth_reference,
+                gradient_dx = in(reg) &gradient_dx,
+                gradient_dy = in(reg) &gradient_dy,
+            );
+        }
+        result
+    }
+}
+
+impl<
+    SampledType: SampleType<FORMAT, COMPONENTS>,
+    const DIM: u32,
+    const DEPTH: u32,
+    const ARRAYED: u32,
     const MULTISAMPLED: u32,
     const FORMAT: u32,
     const COMPONENTS: u32,
@@ -769,7 +1223,7 @@ impl<
     >
 {
     /// Read a texel from an image without a sampler.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_read(&coordinate, 0))]
     #[doc(alias = "OpImageRead")]
     #[inline]
     pub fn read<I>(
@@ -798,8 +1252,41 @@ impl<
         result.truncate_into()
     }
 
-    /// Write a texel to an image without a sampler.
+    /// Sparse version of [`Image::read`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the texel.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseRead")]
+    #[inline]
+    pub fn sparse_read<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        I: Integer,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%result = OpImageSparseRead typeof*{result} %image %coordinate",
+                "OpStore {result} %result",
+                this = in(reg) self,
+                coordinate = in(reg) &coordinate,
+                result = in(reg) &mut result,
+            }
+        }
+
+        result.truncate_into()
+    }
+
+    /// Write a texel to an image without a sampler.
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_write(&coordinate, &texels))]
     #[doc(alias = "OpImageWrite")]
     #[inline]
     pub unsafe fn write<I>(
@@ -845,7 +1332,7 @@ impl<
     >
 {
     /// Read a texel from an image without a sampler.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_read(&coordinate, 0))]
     #[doc(alias = "OpImageRead")]
     #[inline]
     pub fn read<I>(
@@ -874,8 +1361,41 @@ impl<
         result.truncate_into()
     }
 
-    /// Write a texel to an image without a sampler.
+    /// Sparse version of [`Image::read`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the texel.
+    ///
+    /// Requires Capability `SparseResidency`.
     #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseRead")]
+    #[inline]
+    pub fn sparse_read<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        I: Integer,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%result = OpImageSparseRead typeof*{result} %image %coordinate",
+                "OpStore {result} %result",
+                this = in(reg) self,
+                coordinate = in(reg) &coordinate,
+                result = in(reg) &mut result,
+            }
+        }
+
+        result.truncate_into()
+    }
+
+    /// Write a texel to an image without a sampler.
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_write(&coordinate, &texels))]
     #[doc(alias = "OpImageWrite")]
     #[inline]
     pub unsafe fn write<I>(
@@ -900,6 +1420,342 @@ impl<
     }
 }
 
+impl<
+    SampledType: SampleType<FORMAT, COMPONENTS> + ImageAtomicType<FORMAT>,
+    const DIM: u32,
+    const DEPTH: u32,
+    const ARRAYED: u32,
+    const FORMAT: u32,
+    const COMPONENTS: u32,
+>
+    Image<
+        SampledType,
+        DIM,
+        DEPTH,
+        ARRAYED,
+        { Multisampled::False as u32 },
+        { Sampled::No as u32 },
+        FORMAT,
+        COMPONENTS,
+    >
+{
+    /// Get a pointer to the texel at `coordinate`, which can only be used with the atomic
+    /// functions in [`crate::arch`] (e.g. [`crate::arch::atomic_u_min`]).
+    ///
+    /// # Safety
+    /// The returned pointer must not be loaded from or stored to directly, and must only
+    /// be accessed through atomic operations (for which the usual atomics safety rules apply).
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageTexelPointer")]
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn texel_pointer<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+    ) -> &mut SampledType
+    where
+        I: Integer,
+    {
+        unsafe {
+            let mut result_slot = core::mem::MaybeUninit::uninit();
+            asm! {
+                "OpDecorate %result NonUniform",
+                "%u32 = OpTypeInt 32 0",
+                "%sample = OpConstant %u32 0",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%result = OpImageTexelPointer typeof*{result_slot} {this} %coordinate %sample",
+                "OpStore {result_slot} %result",
+                this = in(reg) self,
+                coordinate = in(reg) &coordinate,
+                result_slot = in(reg) result_slot.as_mut_ptr(),
+            }
+            result_slot.assume_init()
+        }
+    }
+
+    /// Atomically add `value` to the texel at `coordinate`, returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_i_add`] (or [`crate::arch::atomic_f_add`] for `f32` images).
+    #[doc(alias = "OpAtomicIAdd")]
+    #[doc(alias = "OpAtomicFAddEXT")]
+    #[inline]
+    pub unsafe fn atomic_add<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+    {
+        unsafe {
+            SampledType::atomic_add::<SCOPE, SEMANTICS>(self.texel_pointer(coordinate), value)
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with the minimum of it and `value`,
+    /// returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_u_min`] (or its signed/floating-point counterparts).
+    #[doc(alias = "OpAtomicUMin")]
+    #[doc(alias = "OpAtomicSMin")]
+    #[doc(alias = "OpAtomicFMinEXT")]
+    #[inline]
+    pub unsafe fn atomic_min<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+    {
+        unsafe {
+            SampledType::atomic_min::<SCOPE, SEMANTICS>(self.texel_pointer(coordinate), value)
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with the maximum of it and `value`,
+    /// returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_u_max`] (or its signed/floating-point counterparts).
+    #[doc(alias = "OpAtomicUMax")]
+    #[doc(alias = "OpAtomicSMax")]
+    #[doc(alias = "OpAtomicFMaxEXT")]
+    #[inline]
+    pub unsafe fn atomic_max<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+    {
+        unsafe {
+            SampledType::atomic_max::<SCOPE, SEMANTICS>(self.texel_pointer(coordinate), value)
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with its bitwise AND with `value`,
+    /// returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_and`].
+    #[doc(alias = "OpAtomicAnd")]
+    #[inline]
+    pub unsafe fn atomic_and<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+        SampledType: Integer,
+    {
+        unsafe {
+            crate::arch::atomic_and::<_, SCOPE, SEMANTICS>(self.texel_pointer(coordinate), value)
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with its bitwise OR with `value`,
+    /// returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_or`].
+    #[doc(alias = "OpAtomicOr")]
+    #[inline]
+    pub unsafe fn atomic_or<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+        SampledType: Integer,
+    {
+        unsafe {
+            crate::arch::atomic_or::<_, SCOPE, SEMANTICS>(self.texel_pointer(coordinate), value)
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with its bitwise XOR with `value`,
+    /// returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_xor`].
+    #[doc(alias = "OpAtomicXor")]
+    #[inline]
+    pub unsafe fn atomic_xor<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+        SampledType: Integer,
+    {
+        unsafe {
+            crate::arch::atomic_xor::<_, SCOPE, SEMANTICS>(self.texel_pointer(coordinate), value)
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with `value`, returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_exchange`].
+    #[doc(alias = "OpAtomicExchange")]
+    #[inline]
+    pub unsafe fn atomic_exchange<I, const SCOPE: u32, const SEMANTICS: u32>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+    {
+        unsafe {
+            crate::arch::atomic_exchange::<_, SCOPE, SEMANTICS>(
+                self.texel_pointer(coordinate),
+                value,
+            )
+        }
+    }
+
+    /// Atomically replace the texel at `coordinate` with `value`, only if it's equal to
+    /// `comparator`, returning the original texel.
+    ///
+    /// # Safety
+    /// See [`crate::arch::atomic_compare_exchange`].
+    #[doc(alias = "OpAtomicCompareExchange")]
+    #[inline]
+    pub unsafe fn atomic_compare_exchange<
+        I,
+        const SCOPE: u32,
+        const EQUAL: u32,
+        const UNEQUAL: u32,
+    >(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        value: SampledType,
+        comparator: SampledType,
+    ) -> SampledType
+    where
+        I: Integer,
+        SampledType: Integer,
+    {
+        unsafe {
+            crate::arch::atomic_compare_exchange::<_, SCOPE, EQUAL, UNEQUAL>(
+                self.texel_pointer(coordinate),
+                value,
+                comparator,
+            )
+        }
+    }
+}
+
+impl<
+    SampledType: SampleType<FORMAT, COMPONENTS>,
+    const DEPTH: u32,
+    const FORMAT: u32,
+    const COMPONENTS: u32,
+>
+    Image<
+        SampledType,
+        { Dimensionality::Buffer as u32 },
+        DEPTH,
+        { Arrayed::False as u32 },
+        { Multisampled::False as u32 },
+        { Sampled::Yes as u32 },
+        FORMAT,
+        COMPONENTS,
+    >
+{
+    /// Load the texel at `index` from this (uniform) texel buffer.
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_read(&index, 0))]
+    #[doc(alias = "OpImageFetch")]
+    #[inline]
+    pub fn load(&self, index: u32) -> SampledType::SampleResult {
+        let mut result = SampledType::Vec4::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%index = OpLoad _ {index}",
+                "%result = OpImageFetch typeof*{result} %image %index",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                index = in(reg) &index,
+            }
+        }
+        result.truncate_into()
+    }
+
+    /// Query the number of texels in this (uniform) texel buffer.
+    #[doc(alias = "OpImageQuerySize")]
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.query_size()
+    }
+
+    /// Returns `true` if this (uniform) texel buffer has no texels.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<
+    SampledType: SampleType<FORMAT, COMPONENTS>,
+    const DEPTH: u32,
+    const FORMAT: u32,
+    const COMPONENTS: u32,
+>
+    Image<
+        SampledType,
+        { Dimensionality::Buffer as u32 },
+        DEPTH,
+        { Arrayed::False as u32 },
+        { Multisampled::False as u32 },
+        { Sampled::No as u32 },
+        FORMAT,
+        COMPONENTS,
+    >
+{
+    /// Load the texel at `index` from this (storage) texel buffer.
+    #[doc(alias = "OpImageRead")]
+    #[inline]
+    pub fn load(&self, index: u32) -> SampledType::SampleResult {
+        self.read(index)
+    }
+
+    /// Store `texel` at `index` into this (storage) texel buffer.
+    ///
+    /// # Safety
+    /// This writes through a shared reference, so the caller must ensure that no other
+    /// invocation is accessing the same texel concurrently.
+    #[doc(alias = "OpImageWrite")]
+    #[inline]
+    pub unsafe fn store(&self, index: u32, texel: SampledType::SampleResult) {
+        unsafe { self.write(index, texel) }
+    }
+
+    /// Query the number of texels in this (storage) texel buffer.
+    #[doc(alias = "OpImageQuerySize")]
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.query_size()
+    }
+
+    /// Returns `true` if this (storage) texel buffer has no texels.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 impl<
     SampledType: SampleType<FORMAT, COMPONENTS>,
     const DEPTH: u32,
@@ -963,7 +1819,7 @@ impl<
 > Image<SampledType, DIM, DEPTH, ARRAYED, MULTISAMPLED, SAMPLED, FORMAT, COMPONENTS>
 {
     /// Query the number of mipmap levels.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_query_levels())]
     #[doc(alias = "OpImageQueryLevels")]
     #[inline]
     pub fn query_levels(&self) -> u32
@@ -1029,7 +1885,7 @@ impl<
     }
 
     /// Query the dimensions of Image, with no level of detail.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_query_size(0))]
     #[doc(alias = "OpImageQuerySize")]
     #[inline]
     pub fn query_size<Size: ImageSizeQuery<u32, DIM, ARRAYED> + Default>(&self) -> Size
@@ -1073,7 +1929,7 @@ impl<
     >
 {
     /// Query the dimensions of Image at a specific level of detail.
-    #[crate::macros::gpu_only]
+    #[crate::macros::gpu_only(cpu_emulation = self.cpu_emulation_query_size(lod))]
     #[doc(alias = "OpImageQuerySizeLod")]
     #[inline]
     pub fn query_size_lod<Size: ImageSizeQuery<u32, DIM, ARRAYED> + Default>(
@@ -1232,6 +2088,71 @@ impl<
         result.truncate_into()
     }
 
+    /// Sparse version of [`SampledImage::sample`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the texel.
+    ///
+    /// Requires Capability `SparseResidency`.
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseSampleImplicitLod")]
+    #[inline]
+    pub fn sparse_sample<F>(
+        &self,
+        coord: impl ImageCoordinate<F, DIM, ARRAYED>,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%sampledImage = OpLoad _ {this}",
+                "%coord = OpLoad _ {coord}",
+                "%result = OpImageSparseSampleImplicitLod typeof*{result} %sampledImage %coord",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                coord = in(reg) &coord,
+            }
+        }
+        result.truncate_into()
+    }
+
+    /// Sparse version of [`SampledImage::sample_by_lod`], returning a residency code (see
+    /// [`sparse_texels_resident`]) along with the texel.
+    ///
+    /// Requires Capability `SparseResidency`.
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseSampleExplicitLod")]
+    #[inline]
+    pub fn sparse_sample_by_lod<F>(
+        &self,
+        coord: impl ImageCoordinate<F, DIM, ARRAYED>,
+        lod: f32,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%sampledImage = OpLoad _ {this}",
+                "%coord = OpLoad _ {coord}",
+                "%lod = OpLoad _ {lod}",
+                "%result = OpImageSparseSampleExplicitLod typeof*{result} %sampledImage %coord Lod %lod",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                coord = in(reg) &coord,
+                lod = in(reg) &lod,
+            }
+        }
+        result.truncate_into()
+    }
+
     /// Query the dimensions of the image at the specified level of detail.
     #[crate::macros::gpu_only]
     #[doc(alias = "OpImageQuerySizeLod")]
@@ -1404,6 +2325,55 @@ pub trait ImageWithMethods<
     ) -> SampledType
     where
         F: Float;
+
+    /// Sparse version of [`ImageWithMethods::fetch_with`], returning a residency code
+    /// (see [`sparse_texels_resident`]) along with the texel.
+    #[doc(alias = "OpImageSparseFetch")]
+    fn sparse_fetch_with<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        params: Params,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        I: Integer;
+
+    /// Sparse version of [`ImageWithMethods::gather_with`], returning a residency code
+    /// (see [`sparse_texels_resident`]) along with the texels.
+    #[doc(alias = "OpImageSparseGather")]
+    fn sparse_gather_with<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+        component: u32,
+        params: Params,
+    ) -> (u32, SampledType::Vec4)
+    where
+        Self: HasGather,
+        F: Float;
+
+    /// Sparse version of [`ImageWithMethods::sample_with`], returning a residency code
+    /// (see [`sparse_texels_resident`]) along with the texel.
+    fn sparse_sample_with<F>(
+        &self,
+        sampler: Sampler,
+        coord: impl ImageCoordinate<F, DIM, ARRAYED>,
+        params: Params,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        F: Float;
+
+    /// Sparse version of [`ImageWithMethods::sample_depth_reference_with`], returning a
+    /// residency code (see [`sparse_texels_resident`]) along with the result.
+    #[doc(alias = "OpImageSparseSampleDrefImplicitLod")]
+    fn sparse_sample_depth_reference_with<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+        depth_reference: f32,
+        params: Params,
+    ) -> (u32, SampledType)
+    where
+        F: Float;
 }
 
 #[crate::macros::gen_sample_param_permutations]
@@ -1641,6 +2611,143 @@ impl<
         }
         result
     }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseFetch")]
+    #[inline]
+    fn sparse_fetch_with<I>(
+        &self,
+        coordinate: impl ImageCoordinate<I, DIM, ARRAYED>,
+        params: SampleParams,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        I: Integer,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%result = OpImageSparseFetch typeof*{result} %image %coordinate $PARAMS",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                coordinate = in(reg) &coordinate,
+            }
+        }
+        result.truncate_into()
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseGather")]
+    #[inline]
+    fn sparse_gather_with<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+        component: u32,
+        params: SampleParams,
+    ) -> (u32, SampledType::Vec4)
+    where
+        Self: HasGather,
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseGather typeof*{result} %sampledImage %coordinate {component} $PARAMS",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coordinate = in(reg) &coordinate,
+                component = in(reg) component,
+            }
+        }
+        (result.residency_code, result.value)
+    }
+
+    #[crate::macros::gpu_only]
+    #[inline]
+    fn sparse_sample_with<F>(
+        &self,
+        sampler: Sampler,
+        coord: impl ImageCoordinate<F, DIM, ARRAYED>,
+        params: SampleParams,
+    ) -> (u32, SampledType::SampleResult)
+    where
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType::Vec4>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%typeSampledImage = OpTypeSampledImage typeof*{this}",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coord = OpLoad _ {coord}",
+                "%sampledImage = OpSampledImage %typeSampledImage %image %sampler",
+                "%result = OpImageSparseSample$LOD typeof*{result} %sampledImage %coord $PARAMS",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coord = in(reg) &coord,
+            }
+        }
+        result.truncate_into()
+    }
+
+    #[crate::macros::gpu_only]
+    #[doc(alias = "OpImageSparseSampleDrefImplicitLod")]
+    #[inline]
+    fn sparse_sample_depth_reference_with<F>(
+        &self,
+        sampler: Sampler,
+        coordinate: impl ImageCoordinate<F, DIM, ARRAYED>,
+        depth_reference: f32,
+        params: SampleParams,
+    ) -> (u32, SampledType)
+    where
+        F: Float,
+    {
+        let mut result = SparseResidencyResult::<SampledType>::default();
+        unsafe {
+            asm! {
+                "OpDecorate %image NonUniform",
+                "OpDecorate %sampler NonUniform",
+                "OpDecorate %sampledImage NonUniform",
+                "OpDecorate %result NonUniform",
+                "%image = OpLoad _ {this}",
+                "%sampler = OpLoad _ {sampler}",
+                "%coordinate = OpLoad _ {coordinate}",
+                "%depth_reference = OpLoad _ {depth_reference}",
+                "%sampledImage = OpSampledImage _ %image %sampler",
+                "%result = OpImageSparseSampleDref$LOD typeof*{result} %sampledImage %coordinate %depth_reference $PARAMS",
+                "OpStore {result} %result",
+                result = in(reg) &mut result,
+                this = in(reg) self,
+                sampler = in(reg) &sampler,
+                coordinate = in(reg) &coordinate,
+                depth_reference = in(reg) &depth_reference,
+            }
+        }
+        (result.residency_code, result.value)
+    }
 }
 
 /// This is a marker trait to represent the constraints on `OpImageGather` too complex to be
@@ -2166,3 +3273,84 @@ impl<
     >
 {
 }
+
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+impl<
+    SampledType: SampleType<FORMAT, COMPONENTS>,
+    const DIM: u32,
+    const DEPTH: u32,
+    const ARRAYED: u32,
+    const MULTISAMPLED: u32,
+    const SAMPLED: u32,
+    const FORMAT: u32,
+    const COMPONENTS: u32,
+> Image<SampledType, DIM, DEPTH, ARRAYED, MULTISAMPLED, SAMPLED, FORMAT, COMPONENTS>
+{
+    // NOTE on the CPU, the padding holds a `HostImage` handle instead.
+    pub(crate) fn from_cpu_emulation_handle(handle: u32) -> Self {
+        Self {
+            _anti_zst_padding: core::mem::MaybeUninit::new(handle),
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    pub(crate) fn cpu_emulation_handle(&self) -> u32 {
+        // SAFETY: images can only be created on the CPU through `HostImage`.
+        unsafe { self._anti_zst_padding.assume_init() }
+    }
+
+    fn cpu_emulation_read<I: Integer>(
+        &self,
+        coordinate: &impl ImageCoordinate<I, DIM, ARRAYED>,
+        lod: u32,
+    ) -> SampledType::SampleResult {
+        crate::cpu_emulation::image::read::<SampledType, I, SampledType::Vec4, _>(
+            self.cpu_emulation_handle(),
+            DIM,
+            ARRAYED,
+            coordinate,
+            lod,
+        )
+        .truncate_into()
+    }
+
+    fn cpu_emulation_write<I: Integer>(
+        &self,
+        coordinate: &impl ImageCoordinate<I, DIM, ARRAYED>,
+        texels: &SampledType::SampleResult,
+    ) {
+        crate::cpu_emulation::image::write::<SampledType, I, _, _>(
+            self.cpu_emulation_handle(),
+            DIM,
+            ARRAYED,
+            coordinate,
+            texels,
+            COMPONENTS,
+        );
+    }
+
+    fn cpu_emulation_sample<F: Float>(
+        &self,
+        sampler: Sampler,
+        coordinate: &impl ImageCoordinate<F, DIM, ARRAYED>,
+        lod: f32,
+    ) -> SampledType::SampleResult {
+        crate::cpu_emulation::image::sample::<SampledType, F, SampledType::Vec4, _>(
+            self.cpu_emulation_handle(),
+            sampler.cpu_emulation_handle(),
+            DIM,
+            ARRAYED,
+            coordinate,
+            lod,
+        )
+        .truncate_into()
+    }
+
+    fn cpu_emulation_query_levels(&self) -> u32 {
+        crate::cpu_emulation::image::query_levels(self.cpu_emulation_handle())
+    }
+
+    fn cpu_emulation_query_size<Size: Default>(&self, lod: u32) -> Size {
+        crate::cpu_emulation::image::query_size(self.cpu_emulation_handle(), DIM, ARRAYED, lod)
+    }
+}
@@ -1,5 +1,5 @@
 use super::{Arrayed, Dimensionality, ImageFormat};
-use crate::{Integer, Scalar, Vector, VectorTruncateInto};
+use crate::{Integer, Number, Scalar, Vector, VectorTruncateInto};
 
 /// Marker trait for arguments that accept single scalar values or vectors
 /// of scalars. Defines 2-, 3- and 4-component vector types based on the sample type.
@@ -116,6 +116,77 @@ sample_type_impls! {
     R64i: 1*i64 => (i32, glam::IVec2, glam::IVec3, glam::IVec4),
 }
 
+/// Marker trait for the sample types of storage images whose texels can be accessed
+/// atomically (see [`Image::texel_pointer`](super::Image::texel_pointer)), for the image
+/// formats that allow it.
+///
+/// 64-bit integer atomics require Capability `Int64ImageEXT` (and `Int64Atomics`), and
+/// floating-point atomics require the `AtomicFloat32AddEXT`/`AtomicFloat32MinMaxEXT`
+/// capabilities (from `SPV_EXT_shader_atomic_float_add`/`SPV_EXT_shader_atomic_float_min_max`).
+pub trait ImageAtomicType<const FORMAT: u32>: Number {
+    #[doc(hidden)]
+    unsafe fn atomic_add<const SCOPE: u32, const SEMANTICS: u32>(
+        ptr: &mut Self,
+        value: Self,
+    ) -> Self;
+
+    #[doc(hidden)]
+    unsafe fn atomic_min<const SCOPE: u32, const SEMANTICS: u32>(
+        ptr: &mut Self,
+        value: Self,
+    ) -> Self;
+
+    #[doc(hidden)]
+    unsafe fn atomic_max<const SCOPE: u32, const SEMANTICS: u32>(
+        ptr: &mut Self,
+        value: Self,
+    ) -> Self;
+}
+
+/// Helper macro to implement `ImageAtomicType` of various formats for various scalar types.
+macro_rules! image_atomic_type_impls {
+    ($($fmt:ident : $s:ty => ($add:ident, $min:ident, $max:ident)),+ $(,)?) => {$(
+        impl ImageAtomicType<{ ImageFormat::$fmt as u32 }> for $s {
+            #[inline]
+            unsafe fn atomic_add<const SCOPE: u32, const SEMANTICS: u32>(
+                ptr: &mut Self,
+                value: Self,
+            ) -> Self {
+                unsafe { crate::arch::$add::<_, SCOPE, SEMANTICS>(ptr, value) }
+            }
+
+            #[inline]
+            unsafe fn atomic_min<const SCOPE: u32, const SEMANTICS: u32>(
+                ptr: &mut Self,
+                value: Self,
+            ) -> Self {
+                unsafe { crate::arch::$min::<_, SCOPE, SEMANTICS>(ptr, value) }
+            }
+
+            #[inline]
+            unsafe fn atomic_max<const SCOPE: u32, const SEMANTICS: u32>(
+                ptr: &mut Self,
+                value: Self,
+            ) -> Self {
+                unsafe { crate::arch::$max::<_, SCOPE, SEMANTICS>(ptr, value) }
+            }
+        }
+    )+};
+}
+
+image_atomic_type_impls! {
+    Unknown: u32 => (atomic_i_add, atomic_u_min, atomic_u_max),
+    Unknown: i32 => (atomic_i_add, atomic_s_min, atomic_s_max),
+    Unknown: u64 => (atomic_i_add, atomic_u_min, atomic_u_max),
+    Unknown: i64 => (atomic_i_add, atomic_s_min, atomic_s_max),
+    Unknown: f32 => (atomic_f_add, atomic_f_min, atomic_f_max),
+    R32ui: u32 => (atomic_i_add, atomic_u_min, atomic_u_max),
+    R32i: i32 => (atomic_i_add, atomic_s_min, atomic_s_max),
+    R64ui: u64 => (atomic_i_add, atomic_u_min, atomic_u_max),
+    R64i: i64 => (atomic_i_add, atomic_s_min, atomic_s_max),
+    R32f: f32 => (atomic_f_add, atomic_f_min, atomic_f_max),
+}
+
 /// Marker trait for arguments that accept a coordinate for an [`crate::Image`].
 pub trait ImageCoordinate<T, const DIM: u32, const ARRAYED: u32> {}
 
@@ -4,6 +4,7 @@
     allow(internal_features),
     feature(asm_experimental_arch, core_intrinsics, lang_items, repr_simd)
 )]
+#![cfg_attr(feature = "f16", feature(f16))]
 // FIXME(eddyb) update/review these lints.
 //
 // BEGIN - Embark standard lints v0.4
@@ -86,14 +87,25 @@
 /// Public re-export of the `spirv-std-macros` crate.
 #[macro_use]
 pub extern crate spirv_std_macros as macros;
+pub use macros::GpuDebug;
 pub use macros::ScalarComposite;
 pub use macros::spirv;
-pub use macros::{debug_printf, debug_printfln};
+pub use macros::{debug_print, debug_printf, debug_printfln, debug_println};
+
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+extern crate std;
 
 pub mod arch;
 pub mod byte_addressable_buffer;
+pub mod cooperative_matrix;
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+pub mod cpu_emulation;
 pub mod debug_printf;
+pub mod descriptor_table;
+mod device_ptr;
 pub mod float;
+#[cfg(feature = "f16")]
+pub mod half;
 pub mod image;
 pub mod indirect_command;
 pub mod matrix;
@@ -109,6 +121,9 @@ mod vector;
 pub use self::sampler::Sampler;
 pub use crate::macros::Image;
 pub use byte_addressable_buffer::ByteAddressableBuffer;
+pub use debug_printf::{GpuDebug, GpuDisplay};
+pub use descriptor_table::DescriptorTable;
+pub use device_ptr::*;
 pub use num_traits;
 pub use runtime_array::*;
 pub use scalar::*;
@@ -9,3 +9,18 @@ pub struct Sampler {
     // or another, before `#[spirv(sampler)]` can special-case it).
     _anti_zst_padding: core::mem::MaybeUninit<u32>,
 }
+
+#[cfg(all(not(target_arch = "spirv"), feature = "cpu-emulation"))]
+impl Sampler {
+    // NOTE on the CPU, the padding holds a `HostSampler` handle instead.
+    pub(crate) fn from_cpu_emulation_handle(handle: u32) -> Self {
+        Self {
+            _anti_zst_padding: core::mem::MaybeUninit::new(handle),
+        }
+    }
+
+    pub(crate) fn cpu_emulation_handle(&self) -> u32 {
+        // SAFETY: samplers can only be created on the CPU through `HostSampler`.
+        unsafe { self._anti_zst_padding.assume_init() }
+    }
+}
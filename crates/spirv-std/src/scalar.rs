@@ -5,7 +5,7 @@ use crate::{ScalarComposite, ScalarOrVector, ScalarOrVectorTransform};
 use core::num::NonZeroUsize;
 
 /// Abstract trait representing a SPIR-V scalar type, which includes:
-/// * Floating-point type: f32, f64
+/// * Floating-point type: f16 (with the `f16` feature), f32, f64
 /// * Integer type: u8, u16, u32, u64, i8, i16, i32, i64
 /// * Boolean type: bool
 ///
@@ -118,6 +118,12 @@ impl_scalar! {
     impl Scalar for bool;
 }
 
+// NOTE `f16` can't implement `Float`, as `num_traits` has no support for it.
+#[cfg(feature = "f16")]
+impl_scalar! {
+    impl Number for f16;
+}
+
 /// used by `ScalarOrVector` derive when working with enums
 #[inline]
 pub fn assert_is_integer<T: Integer>() {}
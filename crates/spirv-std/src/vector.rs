@@ -91,6 +91,13 @@ impl_vector! {
     glam::BVec4: [bool; 4];
 }
 
+#[cfg(feature = "f16")]
+impl_vector! {
+    crate::half::F16Vec2: [f16; 2];
+    crate::half::F16Vec3: [f16; 3];
+    crate::half::F16Vec4: [f16; 4];
+}
+
 /// Trait that implements slicing of a vector into a scalar or vector of lower dimensions, by
 /// ignoring the higher dimensions
 pub trait VectorTruncateInto<T> {
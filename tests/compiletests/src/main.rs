@@ -2,11 +2,14 @@ use clap::Parser;
 use itertools::Itertools as _;
 use rustc_codegen_spirv_types::{SpirvTarget, TargetSpecVersion, query_rustc_version};
 use std::ffi::OsString;
+use std::time::SystemTime;
 use std::{
     env, io,
     path::{Path, PathBuf},
 };
 
+mod validate;
+
 #[derive(Parser)]
 #[command(bin_name = "cargo compiletest")]
 struct Opt {
@@ -14,9 +17,15 @@ struct Opt {
     #[arg(long)]
     bless: bool,
 
-    /// The environment to compile to the SPIR-V tests.
-    #[arg(long, default_value = "vulkan1.2")]
-    target_env: String,
+    /// The environment to compile to the SPIR-V tests (defaults to `vulkan1.2`,
+    /// or to all SPIR-V and Vulkan environments with `--validate`).
+    #[arg(long)]
+    target_env: Option<String>,
+
+    /// Also validate (with `spirv-val`) and round-trip (through SPIR-T) the
+    /// modules built by all passing tests, and report which environments pass.
+    #[arg(long)]
+    validate: bool,
 
     /// Only run tests that match these filters.
     #[arg(name = "FILTER")]
@@ -24,8 +33,12 @@ struct Opt {
 }
 
 impl Opt {
-    pub fn environments(&self) -> impl Iterator<Item = &str> {
-        self.target_env.split(',')
+    pub fn environments(&self) -> Vec<&str> {
+        match &self.target_env {
+            Some(target_env) => target_env.split(',').collect(),
+            None if self.validate => validate::ALL_ENVS.to_vec(),
+            None => vec!["vulkan1.2"],
+        }
     }
 }
 
@@ -79,7 +92,9 @@ fn main() {
         codegen_backend_path,
     };
 
-    runner.run_mode("ui");
+    if !runner.run_mode("ui") {
+        std::process::exit(1);
+    }
 }
 
 struct Runner {
@@ -93,8 +108,11 @@ struct Runner {
 impl Runner {
     /// Runs the given `mode` on the directory that matches that name, using the
     /// backend provided by `codegen_backend_path`.
+    ///
+    /// Returns `false` if any environment failed `--validate` (without it,
+    /// failing tests panic instead).
     #[allow(clippy::string_add)]
-    fn run_mode(&self, mode: &'static str) {
+    fn run_mode(&self, mode: &'static str) -> bool {
         /// RUSTFLAGS passed to all test files.
         fn test_rustc_flags(
             codegen_backend_path: &Path,
@@ -137,9 +155,11 @@ impl Runner {
             extra_flags: "",
         }];
 
+        let mut reports = vec![];
         for (env, variation) in self
             .opt
             .environments()
+            .into_iter()
             .flat_map(|env| VARIATIONS.iter().map(move |variation| (env, variation)))
         {
             // HACK(eddyb) in order to allow *some* tests to have separate output
@@ -173,7 +193,7 @@ impl Runner {
             flags += variation.extra_flags;
 
             let config = compiletest::Config {
-                stage_id,
+                stage_id: stage_id.clone(),
                 target_rustcflags: Some(flags),
                 mode: mode.parse().expect("Invalid mode"),
                 target: self.target_spec_json(&target).into_string().unwrap(),
@@ -186,8 +206,25 @@ impl Runner {
             // FIXME(eddyb) do we need this? shouldn't `compiletest` be independent?
             config.clean_rmeta();
 
-            compiletest::run_tests(&config);
+            if !self.opt.validate {
+                compiletest::run_tests(&config);
+                continue;
+            }
+
+            // Keep going after failing tests, to check every environment.
+            let started = SystemTime::now();
+            let tests_passed = std::panic::catch_unwind(|| compiletest::run_tests(&config)).is_ok();
+            reports.push(validate::check_env(
+                env,
+                &stage_id,
+                &config.src_base,
+                &config.build_base,
+                started,
+                tests_passed,
+            ));
         }
+
+        !self.opt.validate || validate::print_matrix(&reports)
     }
 
     /// Runs the processes needed to build `spirv-std` & other deps.
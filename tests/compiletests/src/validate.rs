@@ -0,0 +1,292 @@
+//! The `--validate` mode, which checks the SPIR-V modules built by passing
+//! tests, beyond what the tests themselves check (i.e. their stderr).
+//!
+//! Every module (of a `build-pass` test) is validated with `spirv-val` (with
+//! the target environment it was built for), and round-tripped through SPIR-T,
+//! i.e. lowered to SPIR-T, structurized, and lifted back to SPIR-V (like the
+//! linker does), which has to be valid as well, and a fixpoint (i.e.
+//! round-tripping it again has to produce the same global instructions, up to
+//! IDs, as structurizing an already structured CFG can still change its shape,
+//! and so renumber everything).
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+use std::{fmt, fs, io};
+
+/// All the SPIR-V and Vulkan environments listed in `platform-support.md`,
+/// tested by default in `--validate` mode.
+pub const ALL_ENVS: &[&str] = &[
+    "spv1.0",
+    "spv1.1",
+    "spv1.2",
+    "spv1.3",
+    "spv1.4",
+    "spv1.5",
+    "spv1.6",
+    "vulkan1.0",
+    "vulkan1.1",
+    "vulkan1.1spv1.4",
+    "vulkan1.2",
+    "vulkan1.3",
+    "vulkan1.4",
+];
+
+/// What went wrong with a module.
+pub enum Failure {
+    /// The module failed `spirv-val`.
+    Invalid(String),
+    /// The module couldn't be round-tripped through SPIR-T, or the result
+    /// wasn't valid, or not a fixpoint.
+    RoundTrip(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(err) => write!(f, "spirv-val failed: {err}"),
+            Self::RoundTrip(err) => write!(f, "SPIR-T round-trip failed: {err}"),
+        }
+    }
+}
+
+/// The results of one environment (i.e. one column of the matrix).
+pub struct EnvReport {
+    pub stage_id: String,
+    /// Whether the tests themselves passed.
+    pub tests_passed: bool,
+    /// The number of modules checked.
+    pub modules: usize,
+    pub failures: Vec<(PathBuf, Failure)>,
+}
+
+impl EnvReport {
+    fn invalid_count(&self) -> usize {
+        (self.failures.iter())
+            .filter(|(_, failure)| matches!(failure, Failure::Invalid(_)))
+            .count()
+    }
+
+    fn round_trip_count(&self) -> usize {
+        self.failures.len() - self.invalid_count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.tests_passed && self.failures.is_empty()
+    }
+}
+
+/// Checks all the modules built (since `built_after`) by the `stage_id` run of
+/// the tests in `src_base`, whose outputs are in `build_base`.
+pub fn check_env(
+    env: &str,
+    stage_id: &str,
+    src_base: &Path,
+    build_base: &Path,
+    built_after: SystemTime,
+    tests_passed: bool,
+) -> EnvReport {
+    let mut report = EnvReport {
+        stage_id: stage_id.to_string(),
+        tests_passed,
+        modules: 0,
+        failures: vec![],
+    };
+    let mut modules = vec![];
+    find_modules(build_base, stage_id, built_after, &mut modules).unwrap();
+    modules.sort();
+    for module in modules {
+        // NOTE `compiletest` names outputs `<test>.<stage_id>`, in the same
+        // directory (relative to `build_base`) as the test (relative to `src_base`).
+        let file_name = module.file_name().unwrap().to_str().unwrap();
+        let test_name = file_name.strip_suffix(&format!(".{stage_id}")).unwrap();
+        let test_path = module
+            .strip_prefix(build_base)
+            .unwrap()
+            .with_file_name(format!("{test_name}.rs"));
+        let test_path = src_base.join(test_path);
+
+        // `build-fail` tests can still leave (usually invalid) modules behind,
+        // and with revisions, only the last one's module is left, so only
+        // tests which always pass are checked.
+        let source = fs::read_to_string(&test_path).unwrap_or_default();
+        let header = |directive| {
+            source
+                .lines()
+                .any(|line| line.starts_with("//") && line.trim_end().ends_with(directive))
+        };
+        if !header("build-pass") || header("build-fail") {
+            continue;
+        }
+
+        report.modules += 1;
+        if let Err(failure) = check_module(env, &module, &source) {
+            report.failures.push((test_path, failure));
+        }
+    }
+    report
+}
+
+/// Collects the (extensionless) modules built for `stage_id` in `dir`.
+fn find_modules(
+    dir: &Path,
+    stage_id: &str,
+    built_after: SystemTime,
+    modules: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let suffix = format!(".{stage_id}");
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            find_modules(&path, stage_id, built_after, modules)?;
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(&suffix))
+            // Outputs of earlier runs (or of tests which failed to build this
+            // time) are left around, and have to be skipped.
+            && metadata.modified()? >= built_after
+        {
+            modules.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn check_module(env: &str, module: &Path, test_source: &str) -> Result<(), Failure> {
+    let bytes = fs::read(module).map_err(|e| Failure::Invalid(e.to_string()))?;
+    let words = spirv_tools::binary::to_binary(&bytes)
+        .map_err(|e| Failure::Invalid(e.to_string()))?
+        .to_vec();
+
+    let options = validator_options(test_source);
+    validate(env, &words, &options).map_err(Failure::Invalid)?;
+
+    let lifted = round_trip(bytes).map_err(Failure::RoundTrip)?;
+    validate(env, &lifted, &options)
+        .map_err(|e| Failure::RoundTrip(format!("invalid after lifting: {e}")))?;
+    let relifted = round_trip(spirv_tools::binary::from_binary(&lifted).to_vec())
+        .map_err(|e| Failure::RoundTrip(format!("failed on the lifted module: {e}")))?;
+    validate(env, &relifted, &options)
+        .map_err(|e| Failure::RoundTrip(format!("invalid after lifting twice: {e}")))?;
+    let (globals, relifted_globals) = (globals(&lifted), globals(&relifted));
+    if relifted_globals != globals {
+        let i = globals
+            .iter()
+            .zip(&relifted_globals)
+            .position(|(a, b)| a != b)
+            .unwrap_or(globals.len().min(relifted_globals.len()));
+        return Err(Failure::RoundTrip(format!(
+            "not a fixpoint (global instruction #{i} changed from {:?} to {:?}, \
+             as `(opcode, word count)`)",
+            globals.get(i),
+            relifted_globals.get(i),
+        )));
+    }
+    Ok(())
+}
+
+/// The opcodes and word counts of the instructions in `module` before its
+/// first function, i.e. all the global instructions (`OpCapability`,
+/// `OpEntryPoint`, types, etc.), which is enough to compare them up to IDs.
+fn globals(module: &[u32]) -> Vec<(u32, u32)> {
+    const HEADER_LEN: usize = 5;
+    const OP_FUNCTION: u32 = 54;
+
+    let mut globals = vec![];
+    let mut i = HEADER_LEN;
+    while let Some(&first_word) = module.get(i) {
+        let (opcode, word_count) = (first_word & 0xffff, first_word >> 16);
+        if opcode == OP_FUNCTION {
+            break;
+        }
+        globals.push((opcode, word_count));
+        i += word_count.max(1) as usize;
+    }
+    globals
+}
+
+fn validate(
+    env: &str,
+    words: &[u32],
+    options: &spirv_tools::val::ValidatorOptions,
+) -> Result<(), String> {
+    use spirv_tools::val::{self, Validator};
+
+    let validator = val::create(env.parse().ok());
+    validator
+        .validate(words, Some(options.clone()))
+        .map_err(|e| e.to_string())
+}
+
+fn round_trip(spv_bytes: Vec<u8>) -> Result<Vec<u32>, String> {
+    let cx = Rc::new(spirt::Context::new());
+    let mut module = spirt::Module::lower_from_spv_bytes(cx, spv_bytes)
+        .map_err(|e| format!("failed to lower: {e}"))?;
+    spirt::passes::legalize::structurize_func_cfgs(&mut module);
+    let emitter = module
+        .lift_to_spv_module_emitter()
+        .map_err(|e| format!("failed to lift: {e}"))?;
+    Ok(emitter.words)
+}
+
+/// The validator options a test was built with (see `link.rs` in
+/// `rustc_codegen_spirv`), from the `--*-layout` etc. flags among its
+/// `compile-flags`.
+fn validator_options(test_source: &str) -> spirv_tools::val::ValidatorOptions {
+    let mut options = spirv_tools::val::ValidatorOptions::default();
+    let flags = test_source
+        .lines()
+        .filter_map(|line| line.split_once("compile-flags:"))
+        .flat_map(|(_, flags)| flags.split_whitespace());
+    for flag in flags {
+        match flag.trim_start_matches("llvm-args=") {
+            "--relax-struct-store" => options.relax_struct_store = true,
+            "--relax-logical-pointer" => options.relax_logical_pointer = true,
+            "--relax-block-layout" => options.relax_block_layout = Some(true),
+            "--uniform-buffer-standard-layout" => options.uniform_buffer_standard_layout = true,
+            "--scalar-block-layout" => options.scalar_block_layout = true,
+            "--skip-block-layout" => options.skip_block_layout = true,
+            _ => {}
+        }
+    }
+    options
+}
+
+/// Prints the pass/fail matrix (and the failures), returning whether all
+/// environments passed.
+pub fn print_matrix(reports: &[EnvReport]) -> bool {
+    for report in reports {
+        for (test_path, failure) in &report.failures {
+            println!("[{}] {}: {failure}", report.stage_id, test_path.display());
+        }
+    }
+
+    let width = reports
+        .iter()
+        .map(|report| report.stage_id.len())
+        .max()
+        .unwrap_or(0)
+        .max("env".len());
+    println!();
+    println!(
+        "{:width$}  {:>6}  {:>7}  {:>9}  {:>10}  result",
+        "env", "tests", "modules", "spirv-val", "round-trip"
+    );
+    for report in reports {
+        println!(
+            "{:width$}  {:>6}  {:>7}  {:>9}  {:>10}  {}",
+            report.stage_id,
+            if report.tests_passed { "ok" } else { "FAILED" },
+            report.modules,
+            format!("{} bad", report.invalid_count()),
+            format!("{} bad", report.round_trip_count()),
+            if report.passed() { "pass" } else { "FAIL" },
+        );
+    }
+    println!();
+
+    reports.iter().all(EnvReport::passed)
+}
@@ -0,0 +1,28 @@
+// Test that the abort record buffer works with different kinds of `panic!`s,
+// and entry-point inputs (including vectors).
+
+// build-pass
+// compile-flags: -C llvm-args=--abort-strategy=abort-record+inputs
+// compile-flags: -C llvm-args=--abort-record-buffer=0,0
+
+use spirv_std::glam::{UVec3, Vec4};
+use spirv_std::spirv;
+
+#[spirv(compute(threads(64)))]
+pub fn main_cs(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] data: &mut [u32],
+) {
+    let i = id.x as usize;
+    if data[i] == 0 {
+        panic!("zero at %u");
+    }
+    assert!(data[i] < 100, "too large");
+    data[i] /= data[0];
+}
+
+#[spirv(fragment)]
+pub fn main_fs(frag_coord: Vec4, #[spirv(flat)] i: u32, output: &mut Vec4) {
+    let array = [frag_coord; 4];
+    *output = array[i as usize];
+}
@@ -0,0 +1,22 @@
+// build-pass
+// compile-flags: -C target-feature=+CooperativeMatrixKHR,+VulkanMemoryModel,+ext:SPV_KHR_cooperative_matrix,+ext:SPV_KHR_vulkan_memory_model
+
+use spirv_std::cooperative_matrix::*;
+use spirv_std::glam::UVec3;
+use spirv_std::spirv;
+
+#[spirv(compute(threads(32)))]
+pub fn main(
+    #[spirv(workgroup_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] a: &[f32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] b: &[f32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] c: &mut [f32],
+) {
+    let offset = id.x as usize * 16 * 16;
+    let a = SubgroupMatrixA::<f32, 16, 16>::load(a, offset, 16, CooperativeMatrixLayout::RowMajor);
+    let b =
+        SubgroupMatrixB::<f32, 16, 16>::load(b, offset, 16, CooperativeMatrixLayout::ColumnMajor);
+    let acc = SubgroupMatrixAccumulator::<f32, 16, 16>::splat(0.0);
+    acc.mul_add(a, b)
+        .store(c, offset, 16, CooperativeMatrixLayout::RowMajor);
+}
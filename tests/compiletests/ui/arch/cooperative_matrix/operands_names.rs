@@ -0,0 +1,64 @@
+// build-pass
+// compile-flags: -C target-feature=+CooperativeMatrixKHR,+VulkanMemoryModel,+ext:SPV_KHR_cooperative_matrix,+ext:SPV_KHR_vulkan_memory_model
+
+// `asm!` accepts both the `rspirv` names (e.g. `MATRIX_A_SIGNED_COMPONENTS_KHR`)
+// and the SPIR-V specification names (e.g. `MatrixASignedComponentsKHR`)
+// of `CooperativeMatrixOperands`, including mixed together.
+
+use core::arch::asm;
+use spirv_std::cooperative_matrix::*;
+use spirv_std::spirv;
+
+type A = SubgroupMatrixA<i32, 16, 16>;
+type B = SubgroupMatrixB<i32, 16, 16>;
+type Acc = SubgroupMatrixAccumulator<i32, 16, 16>;
+
+fn mul_add_signed(a: &A, b: &B, c: &Acc) -> Acc {
+    unsafe {
+        let mut result_slot = core::mem::MaybeUninit::uninit();
+        asm! {
+            "%a = OpLoad _ {a}",
+            "%b = OpLoad _ {b}",
+            "%c = OpLoad _ {c}",
+            "%result = OpCooperativeMatrixMulAddKHR typeof*{result_slot} %a %b %c MATRIX_A_SIGNED_COMPONENTS_KHR|MATRIX_B_SIGNED_COMPONENTS_KHR|MatrixCSignedComponentsKHR|MatrixResultSignedComponentsKHR",
+            "OpStore {result_slot} %result",
+            a = in(reg) a,
+            b = in(reg) b,
+            c = in(reg) c,
+            result_slot = in(reg) result_slot.as_mut_ptr(),
+        }
+        result_slot.assume_init()
+    }
+}
+
+fn mul_add_none(a: &A, b: &B, c: &Acc) -> Acc {
+    unsafe {
+        let mut result_slot = core::mem::MaybeUninit::uninit();
+        asm! {
+            "%a = OpLoad _ {a}",
+            "%b = OpLoad _ {b}",
+            "%c = OpLoad _ {c}",
+            "%result = OpCooperativeMatrixMulAddKHR typeof*{result_slot} %a %b %c NONE_KHR",
+            "OpStore {result_slot} %result",
+            a = in(reg) a,
+            b = in(reg) b,
+            c = in(reg) c,
+            result_slot = in(reg) result_slot.as_mut_ptr(),
+        }
+        result_slot.assume_init()
+    }
+}
+
+#[spirv(compute(threads(32)))]
+pub fn main(
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] a: &[i32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] b: &[i32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] c: &mut [i32],
+) {
+    let layout = CooperativeMatrixLayout::RowMajor;
+    let a = A::load(a, 0, 16, layout);
+    let b = B::load(b, 0, 16, layout);
+    let acc = Acc::load(c, 0, 16, layout);
+    let acc = mul_add_signed(&a, &b, &acc);
+    mul_add_none(&a, &b, &acc).store(c, 0, 16, layout);
+}
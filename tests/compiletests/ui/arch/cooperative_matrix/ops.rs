@@ -0,0 +1,36 @@
+// build-pass
+// compile-flags: -C target-feature=+CooperativeMatrixKHR,+VulkanMemoryModel,+ext:SPV_KHR_cooperative_matrix,+ext:SPV_KHR_vulkan_memory_model
+
+use spirv_std::cooperative_matrix::*;
+use spirv_std::spirv;
+
+type Mat = SubgroupMatrixAccumulator<f32, 16, 16>;
+type IMat = SubgroupMatrixAccumulator<i32, 16, 16>;
+type UMat = SubgroupMatrixAccumulator<u32, 16, 16>;
+
+#[spirv(compute(threads(32)))]
+pub fn main(
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] floats: &mut [f32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] ints: &mut [i32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] uints: &mut [u32],
+    #[spirv(workgroup)] shared: &mut [f32; 256],
+) {
+    let layout = CooperativeMatrixLayout::RowMajor;
+
+    let x = Mat::load(floats, 0, 16, layout);
+    let y = Mat::splat(2.0);
+    let mut z = -(x + y - x / y) * 0.5;
+    if z.length() > 0 {
+        let first = z.get(0);
+        z.set(0, first + 1.0);
+    }
+    z.store_array(shared, 0, 16, CooperativeMatrixLayout::ColumnMajor);
+    let w = Mat::load_array(shared, 0, 16, CooperativeMatrixLayout::ColumnMajor);
+    w.map(|e| e * e).store(floats, 0, 16, layout);
+
+    let i = IMat::load(ints, 0, 16, layout);
+    (-(i + i - i / IMat::splat(3)) * 2).store(ints, 0, 16, layout);
+
+    let u = UMat::load(uints, 0, 16, layout);
+    ((u + u - u / UMat::splat(3)) * 2).store(uints, 0, 16, layout);
+}
@@ -0,0 +1,62 @@
+// build-pass
+// compile-flags: -Ctarget-feature=+Int64,+ext:SPV_KHR_non_semantic_info
+
+use spirv_std::glam::{IVec4, UVec3, Vec2, Vec3A, Vec4};
+use spirv_std::{GpuDebug, debug_print, debug_println, spirv};
+
+#[derive(Copy, Clone, GpuDebug)]
+pub struct Unit;
+
+#[derive(Copy, Clone, GpuDebug)]
+pub struct Pair<T>(T, T);
+
+#[derive(Copy, Clone, GpuDebug)]
+#[repr(u32)]
+pub enum Shape {
+    Circle,
+    Square,
+    Triangle,
+}
+
+#[derive(Copy, Clone, GpuDebug)]
+pub struct Instance {
+    r#type: Shape,
+    corners: [Pair<f32>; 2],
+    color: Vec4,
+    flags: UVec3,
+}
+
+#[spirv(fragment)]
+pub fn main(
+    uv: Vec2,
+    #[spirv(flat)] index: u32,
+    #[spirv(flat)] offset: IVec4,
+    #[spirv(flat)] address: u64,
+    color: Vec4,
+) {
+    let instance = &Instance {
+        r#type: if index == 0 {
+            Shape::Circle
+        } else if index == 1 {
+            Shape::Square
+        } else {
+            Shape::Triangle
+        },
+        corners: [Pair(uv.x, uv.y), Pair(uv.y, uv.x)],
+        color,
+        flags: UVec3::splat(index),
+    };
+    debug_print!();
+    debug_println!();
+    debug_println!("Hello World");
+    debug_println!("Hello World",);
+    debug_println!(r#"Hello "World""#);
+    debug_println!("{{}} {{{}}} 100%", index);
+    debug_println!("{} {:?} {0:x} {1:x?}", index, offset);
+    debug_println!("{uv} {uv:?} {uv:.3} {:.1?}", Vec3A::splat(1.0));
+    debug_println!("{a} {b:?} {a:?}", a = uv.x, b = index == 0);
+    debug_println!("{address} {address:x} {}", index as usize);
+    debug_println!("{instance:?}");
+    debug_println!("{:?} {:?}", instance.corners, [instance.r#type; 4]);
+    debug_println!("{:?}", Pair(Unit, Unit));
+}
@@ -0,0 +1,32 @@
+// build-fail
+// compile-flags: -Ctarget-feature=+ext:SPV_KHR_non_semantic_info
+// normalize-stderr-test "\S*/crates/spirv-std/src/" -> "$$SPIRV_STD_SRC/"
+
+use spirv_std::glam::Vec2;
+use spirv_std::{GpuDebug, debug_println, spirv};
+
+#[derive(Copy, Clone, GpuDebug)]
+pub struct Point {
+    pos: Vec2,
+}
+
+#[derive(Copy, Clone, GpuDebug)]
+pub enum WithFields {
+    A(u32),
+}
+
+pub struct NotDebug;
+
+#[spirv(fragment)]
+pub fn main(uv: Vec2) {
+    let point = Point { pos: uv };
+    debug_println!("{");
+    debug_println!("}");
+    debug_println!("{:>8}", uv.x);
+    debug_println!("{} {}", uv.x);
+    debug_println!("{}", uv.x, uv.y);
+    debug_println!("{0}", uv.x, named = uv.y);
+    debug_println!("{uv.x}");
+    debug_println!("{point}");
+    debug_println!("{:?}", NotDebug);
+}
@@ -0,0 +1,37 @@
+// build-pass
+// compile-flags: -C target-feature=+FragmentShaderPixelInterlockEXT,+StorageImageReadWithoutFormat,+StorageImageWriteWithoutFormat,+ext:SPV_EXT_fragment_shader_interlock
+
+use spirv_std::arch::invocation_interlock;
+use spirv_std::glam::Vec4;
+use spirv_std::{Image, spirv};
+
+#[spirv(fragment(pixel_interlock_ordered_ext))]
+pub fn main(
+    #[spirv(frag_coord)] frag_coord: Vec4,
+    #[spirv(descriptor_set = 0, binding = 0)] target: &Image!(2D, type=f32, sampled=false),
+    color: Vec4,
+) {
+    let coord = frag_coord.truncate().truncate().as_uvec2();
+    let _interlock = invocation_interlock();
+    let dst: Vec4 = target.read(coord);
+    unsafe {
+        target.write(coord, color.lerp(dst, 1.0 - color.w));
+    }
+}
+
+#[spirv(fragment(pixel_interlock_ordered_ext))]
+pub fn early_return(
+    #[spirv(frag_coord)] frag_coord: Vec4,
+    #[spirv(descriptor_set = 0, binding = 0)] target: &Image!(2D, type=f32, sampled=false),
+    color: Vec4,
+) {
+    let coord = frag_coord.truncate().truncate().as_uvec2();
+    let _interlock = invocation_interlock();
+    let dst: Vec4 = target.read(coord);
+    if dst.w >= 1.0 {
+        return;
+    }
+    unsafe {
+        target.write(coord, color.lerp(dst, 1.0 - color.w));
+    }
+}
@@ -0,0 +1,11 @@
+// build-fail
+// compile-flags: -C target-feature=+FragmentShaderPixelInterlockEXT,+ext:SPV_EXT_fragment_shader_interlock
+
+use spirv_std::arch::invocation_interlock;
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(output: &mut f32) {
+    let _interlock = invocation_interlock();
+    *output = 1.0;
+}
@@ -0,0 +1,10 @@
+// build-fail
+// compile-flags: -C target-feature=+FragmentShaderPixelInterlockEXT,+ext:SPV_EXT_fragment_shader_interlock
+
+use spirv_std::arch::invocation_interlock;
+use spirv_std::spirv;
+
+#[spirv(compute(threads(1)))]
+pub fn main() {
+    let _interlock = invocation_interlock();
+}
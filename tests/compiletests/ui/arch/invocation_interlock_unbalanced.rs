@@ -0,0 +1,39 @@
+// build-fail
+// compile-flags: -C target-feature=+FragmentShaderPixelInterlockEXT,+ext:SPV_EXT_fragment_shader_interlock
+
+use spirv_std::arch::{begin_invocation_interlock, invocation_interlock};
+use spirv_std::spirv;
+
+#[spirv(fragment(pixel_interlock_unordered_ext))]
+pub fn twice(output: &mut f32) {
+    {
+        let _interlock = invocation_interlock();
+        *output = 1.0;
+    }
+    let _interlock = invocation_interlock();
+    *output = 2.0;
+}
+
+#[spirv(fragment(pixel_interlock_unordered_ext))]
+pub fn unterminated(output: &mut f32) {
+    unsafe {
+        begin_invocation_interlock();
+    }
+    *output = 1.0;
+}
+
+#[spirv(fragment(pixel_interlock_unordered_ext))]
+pub fn conditional(#[spirv(flat)] input: u32, output: &mut f32) {
+    if input > 0 {
+        let _interlock = invocation_interlock();
+        *output = 1.0;
+    }
+}
+
+#[spirv(fragment(pixel_interlock_unordered_ext))]
+pub fn in_loop(#[spirv(flat)] input: u32, output: &mut f32) {
+    for i in 0..input {
+        let _interlock = invocation_interlock();
+        *output += i as f32;
+    }
+}
@@ -0,0 +1,23 @@
+// Test lowering `debug_printf!`s with 64-bit (and vector) arguments to the shader log buffer.
+
+// build-pass
+// compile-flags: -C target-feature=+Int64
+// compile-flags: -C llvm-args=--shader-log-buffer=1,0
+
+use spirv_std::glam::{IVec3, UVec4, Vec4};
+use spirv_std::macros::{debug_printf, debug_printfln};
+use spirv_std::spirv;
+
+#[spirv(compute(threads(1)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: spirv_std::glam::UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] data: &[u64],
+) {
+    let x = data[id.x as usize];
+    let y = x as f32 * 0.5;
+    unsafe {
+        debug_printf!("no arguments");
+        debug_printfln!("%lu %lx %u", x, x, id.x);
+        debug_printfln!("%v4f %v4u %v3d", Vec4::splat(y), UVec4::splat(id.y), IVec3::ZERO);
+    }
+}
@@ -0,0 +1,18 @@
+// build-pass
+// compile-flags: -C llvm-args=--disassemble-fn=subgroup_quad_all_any::subgroup_quad_all_any
+// normalize-stderr-test "OpLine .*\n" -> ""
+
+// NOTE `rspirv` can't represent `OpGroupNonUniformQuad{All,Any}KHR` yet, so
+// the disassembly shows the custom instructions standing in for them, which
+// only get replaced in the final SPIR-V binary (that `spirv-val` then checks).
+
+use spirv_std::spirv;
+
+fn subgroup_quad_all_any(predicate: bool) -> bool {
+    spirv_std::arch::subgroup_quad_all(predicate) | spirv_std::arch::subgroup_quad_any(predicate)
+}
+
+#[spirv(fragment)]
+pub fn main(#[spirv(flat)] predicate: u32, output: &mut u32) {
+    *output = subgroup_quad_all_any(predicate != 0) as u32;
+}
@@ -0,0 +1,26 @@
+#![crate_name = "abort_record_buffer"]
+
+// Test that `panic!`s get lowered to writing the first one (of each dispatch)
+// to the abort record buffer, with `--abort-strategy=abort-record+inputs`.
+
+// build-pass
+// compile-flags: -C llvm-args=--abort-strategy=abort-record+inputs
+// compile-flags: -C llvm-args=--abort-record-buffer=1,2
+// compile-flags: -C llvm-args=--disassemble
+// normalize-stderr-test "; (SPIR-V|Generator: rspirv|Version: 1\.\d+|Bound: \d+)\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+// normalize-stderr-test "\S*/lib/rustlib/" -> "$$SYSROOT/lib/rustlib/"
+// normalize-stderr-test "\S*/crates/spirv-std/src/" -> "$$SPIRV_STD_SRC/"
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(#[spirv(flat)] i: u32, output: &mut f32) {
+    let array = [0.0, 1.0, 2.0, 3.0];
+    *output = array[i as usize];
+}
@@ -0,0 +1,30 @@
+// Test that `f16` literals in `asm!` are rounded directly to `f16` (i.e. not
+// rounded to `f64` first, which would round `1 + 2^-11 + 10^-21` down to the
+// halfway point between `1.0` and `1.0 + 2^-10`, and then down again to `1.0`).
+
+// build-pass
+// compile-flags: -C target-feature=+Float16
+// compile-flags: -C llvm-args=--disassemble-globals
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+use core::arch::asm;
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(output: &mut f32) {
+    unsafe {
+        asm!(
+            "%f16 = OpTypeFloat 16",
+            "%x = OpConstant %f16 1.000488281250000000001",
+            "%y = OpFConvert typeof*{output} %x",
+            "OpStore {output} %y",
+            output = in(reg) output,
+        );
+    }
+}
@@ -0,0 +1,21 @@
+// build-pass
+// compile-flags: -C llvm-args=--disassemble-fn=buffer_ref_arg_not_inlined::add_one
+
+// Pointers into buffers don't force inlining, as long as all callers pass
+// pointers into the same buffer (see `replace_global_ptr_params_with_indices`).
+
+use spirv_std::spirv;
+
+#[inline(never)]
+fn add_one(x: &mut u32) {
+    *x += 1;
+}
+
+#[spirv(compute(threads(64)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: spirv_std::glam::UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] buf: &mut [u32; 64],
+) {
+    add_one(&mut buf[(id.x % 64) as usize]);
+    add_one(&mut buf[0]);
+}
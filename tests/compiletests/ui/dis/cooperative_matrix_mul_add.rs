@@ -0,0 +1,32 @@
+// build-pass
+// compile-flags: -C target-feature=+CooperativeMatrixKHR,+VulkanMemoryModel,+ext:SPV_KHR_cooperative_matrix,+ext:SPV_KHR_vulkan_memory_model
+// compile-flags: -C llvm-args=--disassemble-fn=cooperative_matrix_mul_add::mul_add
+// normalize-stderr-test "OpLine .*\n" -> ""
+
+// `CooperativeMatrix::mul_add` must only set the signedness operands for
+// signed integer components (and emit just the one `asm!` block needed).
+
+use spirv_std::cooperative_matrix::*;
+use spirv_std::spirv;
+
+#[inline(never)]
+fn mul_add(
+    a: SubgroupMatrixA<i32, 16, 16>,
+    b: SubgroupMatrixB<u32, 16, 16>,
+    c: SubgroupMatrixAccumulator<i32, 16, 16>,
+) -> SubgroupMatrixAccumulator<i32, 16, 16> {
+    c.mul_add(a, b)
+}
+
+#[spirv(compute(threads(32)))]
+pub fn main(
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] a: &[i32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] b: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] c: &mut [i32],
+) {
+    let layout = CooperativeMatrixLayout::RowMajor;
+    let a = SubgroupMatrixA::load(a, 0, 16, layout);
+    let b = SubgroupMatrixB::load(b, 0, 16, layout);
+    let acc = SubgroupMatrixAccumulator::load(c, 0, 16, layout);
+    mul_add(a, b, acc).store(c, 0, 16, layout);
+}
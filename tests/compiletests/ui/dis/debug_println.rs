@@ -0,0 +1,54 @@
+#![crate_name = "debug_println"]
+
+// Test that `debug_println!` format strings get lowered to `debugPrintf` ones.
+
+// build-pass
+// compile-flags: -C target-feature=+ext:SPV_KHR_non_semantic_info
+// compile-flags: -C llvm-args=--disassemble
+// normalize-stderr-test "; (SPIR-V|Generator: rspirv|Version: 1\.\d+|Bound: \d+)\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+// normalize-stderr-test "OpLine .*\n" -> ""
+// normalize-stderr-test "\S*/crates/spirv-std/src/" -> "$$SPIRV_STD_SRC/"
+
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+use spirv_std::glam::{UVec2, Vec3};
+use spirv_std::{GpuDebug, debug_println, spirv};
+
+#[derive(Copy, Clone, GpuDebug)]
+pub enum Kind {
+    Spark,
+    Smoke,
+}
+
+#[derive(Copy, Clone, GpuDebug)]
+pub struct Id(u32);
+
+#[derive(Copy, Clone, GpuDebug)]
+pub struct Particle {
+    pos: Vec3,
+    id: Id,
+    kind: Kind,
+    lifetimes: [f32; 2],
+}
+
+#[spirv(fragment)]
+pub fn main(
+    #[spirv(flat)] id: u32,
+    pos: Vec3,
+    #[spirv(flat)] tile: UVec2,
+    #[spirv(flat)] is_smoke: u32,
+) {
+    let particle = Particle {
+        pos,
+        id: Id(id),
+        kind: if is_smoke != 0 { Kind::Smoke } else { Kind::Spark },
+        lifetimes: [1.0, 2.5],
+    };
+    debug_println!("100% {id} {id:x} {tile} {tile:?} {:.2}", pos.x);
+    debug_println!("{particle:?} {{{}}}", is_smoke != 0);
+}
@@ -0,0 +1,31 @@
+#![crate_name = "descriptor_table_non_uniform_inferred"]
+
+// build-pass
+// compile-flags: -C llvm-args=--disassemble-globals
+// normalize-stderr-test "%[0-9]+ = OpString .*\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+// only-vulkan1.2
+
+use spirv_std::descriptor_table::NonUniform;
+use spirv_std::spirv;
+use spirv_std::{DescriptorTable, Image, Sampler};
+
+#[spirv(fragment)]
+pub fn main(
+    #[spirv(flat)] index: u32,
+    #[spirv(descriptor_set = 0, binding = 0)] sampler: &Sampler,
+    #[spirv(descriptor_set = 0, binding = 1)] textures: &DescriptorTable<
+        Image!(2D, type=f32, sampled),
+    >,
+    output: &mut glam::Vec4,
+) {
+    let texture = unsafe { textures.get(NonUniform(index)) };
+    *output = texture.sample(*sampler, glam::Vec2::new(0.0, 1.0));
+}
@@ -0,0 +1,26 @@
+// build-pass
+// compile-flags: -C target-feature=+PhysicalStorageBufferAddresses,+Int64,+ext:SPV_KHR_physical_storage_buffer
+// compile-flags: -C llvm-args=--disassemble-fn=device_ptr_aligned::copy_pair
+// normalize-stderr-test "OpLine .*\n" -> ""
+
+// Accesses through `DevicePtr`s must use `Aligned` memory operands.
+
+use spirv_std::glam::Vec2;
+use spirv_std::{DevicePtr, spirv};
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Pair {
+    a: u64,
+    b: Vec2,
+}
+
+#[inline(never)]
+fn copy_pair(src: DevicePtr<Pair>, dst: DevicePtr<Vec2>) {
+    unsafe { dst.write(src.read().b) }
+}
+
+#[spirv(compute(threads(1)))]
+pub fn main(#[spirv(push_constant)] ptrs: &(DevicePtr<Pair>, DevicePtr<Vec2>)) {
+    copy_pair(ptrs.0, ptrs.1);
+}
@@ -0,0 +1,26 @@
+// build-pass
+// compile-flags: -C target-feature=+PhysicalStorageBufferAddresses,+Int64,+ext:SPV_KHR_physical_storage_buffer
+// compile-flags: -C llvm-args=--disassemble-fn=device_ptr_packed::read_packed
+// normalize-stderr-test "OpLine .*\n" -> ""
+
+// Accesses through `DevicePtr`s to `#[repr(packed)]` types must not assume the
+// alignment of their fields (i.e. `Packed` below is only aligned to 1 byte).
+
+use spirv_std::{DevicePtr, spirv};
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct Packed {
+    a: u32,
+    b: u32,
+}
+
+#[inline(never)]
+fn read_packed(src: DevicePtr<Packed>, dst: DevicePtr<u32>) {
+    unsafe { dst.write(src.read().b) }
+}
+
+#[spirv(compute(threads(1)))]
+pub fn main(#[spirv(push_constant)] ptrs: &(DevicePtr<Packed>, DevicePtr<u32>)) {
+    read_packed(ptrs.0, ptrs.1);
+}
@@ -0,0 +1,26 @@
+#![crate_name = "geometry_streams_capability"]
+
+// Tests that `emit_stream_vertex` and `end_stream_primitive` (with non-zero streams)
+// automatically add the `GeometryStreams` capability.
+
+// build-pass
+// compile-flags: -C target-feature=+Geometry
+// compile-flags: -C llvm-args=--disassemble-globals
+// normalize-stderr-test "%[0-9]+ = OpString .*\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+use spirv_std::spirv;
+
+#[spirv(geometry(input_lines = 2, output_points = 2))]
+pub fn main() {
+    unsafe {
+        spirv_std::arch::emit_stream_vertex::<2>();
+        spirv_std::arch::end_stream_primitive::<2>();
+    };
+}
@@ -0,0 +1,33 @@
+#![crate_name = "image_atomic_add"]
+
+// Tests that image atomics go through an `Image` storage class texel pointer.
+
+// build-pass
+// compile-flags: -C llvm-args=--disassemble-globals
+// normalize-stderr-test "%[0-9]+ = OpString .*\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+// only-vulkan1.2
+
+use spirv_std::memory::{Scope, Semantics};
+use spirv_std::spirv;
+use spirv_std::{Image, image::ImageFormat};
+
+#[spirv(compute(threads(64)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: glam::UVec3,
+    #[spirv(descriptor_set = 0, binding = 0)] image: &Image!(2D, format = r32ui, sampled = false),
+) {
+    unsafe {
+        image.atomic_add::<_, { Scope::Workgroup as u32 }, { Semantics::NONE.bits() }>(
+            id.truncate(),
+            1,
+        );
+    }
+}
@@ -0,0 +1,31 @@
+#![crate_name = "shader_log_buffer"]
+
+// Test that `debug_printf!`s (and `panic!` messages) get lowered to records
+// appended to the shader log buffer, instead of `NonSemantic.DebugPrintf`.
+
+// build-pass
+// compile-flags: -C llvm-args=--abort-strategy=debug-printf
+// compile-flags: -C llvm-args=--shader-log-buffer=0,3
+// compile-flags: -C llvm-args=--disassemble
+// normalize-stderr-test "; (SPIR-V|Generator: rspirv|Version: 1\.\d+|Bound: \d+)\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+// normalize-stderr-test "\S*/lib/rustlib/" -> "$$SYSROOT/lib/rustlib/"
+// normalize-stderr-test "\S*/crates/spirv-std/src/" -> "$$SPIRV_STD_SRC/"
+// HACK(eddyb) `compiletest` handles `ui\dis\`, but not `ui\\dis\\`, on Windows.
+// normalize-stderr-test "ui/dis/" -> "$$DIR/"
+
+use spirv_std::glam::Vec2;
+use spirv_std::macros::debug_printfln;
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(#[spirv(flat)] i: u32, #[spirv(flat)] x: i32, uv: Vec2) {
+    unsafe {
+        debug_printfln!("i = %u, x = %d, uv = %v2f", i, x, uv);
+    }
+    let array = [0, 1, 2, 3];
+    let _ = array[i as usize];
+}
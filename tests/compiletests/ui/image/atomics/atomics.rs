@@ -0,0 +1,41 @@
+// Test image atomics (`OpImageTexelPointer` + `OpAtomic*`)
+// build-pass
+
+use spirv_std::glam::*;
+use spirv_std::memory::{Scope, Semantics};
+use spirv_std::spirv;
+use spirv_std::{Image, image::ImageFormat};
+
+const SCOPE: u32 = Scope::Workgroup as u32;
+const SEMANTICS: u32 = Semantics::NONE.bits();
+
+#[spirv(compute(threads(8, 8)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(descriptor_set = 0, binding = 0)] counts: &Image!(2D, format = r32ui, sampled = false),
+    #[spirv(descriptor_set = 0, binding = 1)] depths: &Image!(2D, format = r32i, sampled = false),
+    #[spirv(descriptor_set = 0, binding = 2)] layers: &Image!(
+        2D,
+        format = r32ui,
+        sampled = false,
+        arrayed
+    ),
+) {
+    let coord = id.truncate();
+    unsafe {
+        let old = counts.atomic_add::<_, SCOPE, SEMANTICS>(coord, 1);
+        counts.atomic_max::<_, SCOPE, SEMANTICS>(coord, old);
+        counts.atomic_min::<_, SCOPE, SEMANTICS>(coord, 4);
+        counts.atomic_and::<_, SCOPE, SEMANTICS>(coord, 0xff);
+        counts.atomic_or::<_, SCOPE, SEMANTICS>(coord, 0x100);
+        counts.atomic_xor::<_, SCOPE, SEMANTICS>(coord, 0x10);
+        counts.atomic_exchange::<_, SCOPE, SEMANTICS>(coord, 7);
+        counts.atomic_compare_exchange::<_, SCOPE, SEMANTICS, SEMANTICS>(coord, 8, 7);
+
+        depths.atomic_min::<_, SCOPE, SEMANTICS>(coord.as_ivec2(), -1);
+        depths.atomic_max::<_, SCOPE, SEMANTICS>(coord.as_ivec2(), 1);
+
+        let texel = layers.texel_pointer(id);
+        spirv_std::arch::atomic_i_increment::<_, SCOPE, SEMANTICS>(texel);
+    }
+}
@@ -0,0 +1,25 @@
+// Test floating-point image atomics
+// build-pass
+// compile-flags: -C target-feature=+AtomicFloat32AddEXT,+AtomicFloat32MinMaxEXT,+ext:SPV_EXT_shader_atomic_float_add,+ext:SPV_EXT_shader_atomic_float_min_max
+
+use spirv_std::glam::*;
+use spirv_std::memory::{Scope, Semantics};
+use spirv_std::spirv;
+use spirv_std::{Image, image::ImageFormat};
+
+const SCOPE: u32 = Scope::Workgroup as u32;
+const SEMANTICS: u32 = Semantics::NONE.bits();
+
+#[spirv(compute(threads(8, 8)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(descriptor_set = 0, binding = 0)] image: &Image!(2D, format = r32f, sampled = false),
+) {
+    let coord = id.truncate();
+    unsafe {
+        image.atomic_add::<_, SCOPE, SEMANTICS>(coord, 1.0);
+        image.atomic_min::<_, SCOPE, SEMANTICS>(coord, 0.5);
+        image.atomic_max::<_, SCOPE, SEMANTICS>(coord, 0.25);
+        image.atomic_exchange::<_, SCOPE, SEMANTICS>(coord, 0.0);
+    }
+}
@@ -0,0 +1,41 @@
+// Test `OpImageSparseFetch` and `OpImageSparseRead`
+// build-pass
+// compile-flags: -C target-feature=+SparseResidency,+StorageImageReadWithoutFormat
+
+use spirv_std::image::{ImageWithMethods, sample_with, sparse_texels_resident};
+use spirv_std::spirv;
+use spirv_std::{Image, Sampler};
+
+#[spirv(fragment)]
+pub fn main(
+    #[spirv(descriptor_set = 0, binding = 0)] image: &Image!(2D, type=f32, sampled),
+    #[spirv(descriptor_set = 0, binding = 1)] storage_image: &Image!(2D, type=f32, sampled=false),
+    #[spirv(descriptor_set = 0, binding = 2)] sampler: &Sampler,
+    output: &mut glam::Vec4,
+) {
+    let coords = glam::IVec2::new(0, 1);
+    let (code0, r0) = image.sparse_fetch(coords);
+    let (code1, r1) = image.sparse_fetch_with_lod(coords, 1);
+    let (code2, r2) = storage_image.sparse_read(coords);
+    // FIXME get `f32` to be automatically inferred instead of the `f64` default.
+    let (code3, r3) = image.sparse_sample_with(
+        *sampler,
+        glam::Vec2::new(0.5, 0.5),
+        sample_with::bias(1.0f32),
+    );
+    let (code4, r4) = image.sparse_sample_with(
+        *sampler,
+        glam::Vec2::new(0.5, 0.5),
+        sample_with::grad(glam::Vec2::new(0.5, 0.5), glam::Vec2::new(0.5, 0.5)),
+    );
+    *output = if sparse_texels_resident(code0)
+        && sparse_texels_resident(code1)
+        && sparse_texels_resident(code2)
+        && sparse_texels_resident(code3)
+        && sparse_texels_resident(code4)
+    {
+        r0 + r1 + r2 + r3 + r4
+    } else {
+        glam::Vec4::ZERO
+    };
+}
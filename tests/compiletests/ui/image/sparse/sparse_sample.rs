@@ -0,0 +1,49 @@
+// Test `OpImageSparseSample*`, `OpImageSparseGather` and `OpImageSparseTexelsResident`
+// build-pass
+// compile-flags: -C target-feature=+SparseResidency
+
+use spirv_std::image::{SampledImage, sparse_texels_resident};
+use spirv_std::spirv;
+use spirv_std::{Image, Sampler};
+
+#[spirv(fragment)]
+pub fn main(
+    #[spirv(descriptor_set = 0, binding = 0)] image: &Image!(2D, type=f32, sampled),
+    #[spirv(descriptor_set = 0, binding = 1)] depth_image: &Image!(2D, type=f32, sampled, depth),
+    #[spirv(descriptor_set = 0, binding = 2)] sampled_image: &SampledImage<
+        Image!(2D, type=f32, sampled),
+    >,
+    #[spirv(descriptor_set = 0, binding = 3)] sampler: &Sampler,
+    output: &mut glam::Vec4,
+    depth_output: &mut f32,
+) {
+    let v2 = glam::Vec2::new(0.0, 1.0);
+    let (code0, r0) = image.sparse_sample(*sampler, v2);
+    let (code1, r1) = image.sparse_sample_bias(*sampler, v2, 1.0);
+    let (code2, r2) = image.sparse_sample_by_lod(*sampler, v2, 2.0);
+    let (code3, r3) = image.sparse_sample_by_gradient(*sampler, v2, v2, v2);
+    let (code4, r4) = image.sparse_gather(*sampler, v2, 0);
+    let (code5, r5) = unsafe { sampled_image.sparse_sample(v2) };
+    let (code6, r6) = unsafe { sampled_image.sparse_sample_by_lod(v2, 1.0) };
+    let resident = sparse_texels_resident(code0)
+        && sparse_texels_resident(code1)
+        && sparse_texels_resident(code2)
+        && sparse_texels_resident(code3)
+        && sparse_texels_resident(code4)
+        && sparse_texels_resident(code5)
+        && sparse_texels_resident(code6);
+    *output = if resident {
+        r0 + r1 + r2 + r3 + r4 + r5 + r6
+    } else {
+        glam::Vec4::ZERO
+    };
+
+    let (code0, d0) = depth_image.sparse_sample_depth_reference(*sampler, v2, 0.5);
+    let (code1, d1) = depth_image.sparse_sample_depth_reference_by_lod(*sampler, v2, 0.5, 1.0);
+    let (code2, d2) = depth_image.sparse_sample_depth_reference_by_gradient(*sampler, v2, 0.5, v2, v2);
+    *depth_output = if sparse_texels_resident(code0 | code1 | code2) {
+        d0 + d1 + d2
+    } else {
+        0.0
+    };
+}
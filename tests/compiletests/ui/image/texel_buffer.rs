@@ -0,0 +1,24 @@
+// Test `UniformTexelBuffer` and `StorageTexelBuffer`
+// build-pass
+// compile-flags: -C target-feature=+SampledBuffer,+ImageBuffer,+ImageQuery,+StorageImageReadWithoutFormat,+StorageImageWriteWithoutFormat
+
+use spirv_std::image::{StorageTexelBuffer, UniformTexelBuffer};
+use spirv_std::{Image, spirv};
+
+#[spirv(compute(threads(64)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: glam::UVec3,
+    #[spirv(descriptor_set = 0, binding = 0)] input: &UniformTexelBuffer<f32>,
+    #[spirv(descriptor_set = 0, binding = 1)] indices: &UniformTexelBuffer<u32, 1>,
+    #[spirv(descriptor_set = 0, binding = 2)] output: &StorageTexelBuffer<f32>,
+    #[spirv(descriptor_set = 0, binding = 3)] counts: &Image!(buffer, format=r32ui, sampled=false),
+) {
+    if id.x >= input.len() || output.is_empty() {
+        return;
+    }
+    let texel = input.load(indices.load(id.x));
+    unsafe {
+        output.store(id.x, texel + output.load(id.x));
+        counts.store(id.x, counts.load(id.x) + 1);
+    }
+}
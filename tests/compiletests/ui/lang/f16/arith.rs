@@ -0,0 +1,29 @@
+// Test `f16` arithmetic, conversions and math functions with `Float16`.
+
+// build-pass
+// compile-flags: -C target-feature=+Float16
+
+#![feature(f16, core_float_math)]
+
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(input: f32, #[spirv(flat)] input_int: i32, output: &mut f32) {
+    let x = input as f16;
+    let y = input_int as f16;
+
+    let mut acc = x * y + 1.5;
+    acc -= x / 3.0;
+    acc = -acc;
+    if acc < y {
+        acc += 0.25;
+    }
+
+    // NOTE `f16::{abs,copysign}` are implemented with bitwise ops on
+    // `u16`, so they would also require `Int16`.
+    acc += x.floor() + x.ceil() + x.round() + x.trunc();
+    acc += x.sqrt() + x.mul_add(y, acc) + x.powi(3);
+    acc += x.min(y) + x.max(y);
+
+    *output = acc as f32 + (acc as i32) as f32;
+}
@@ -0,0 +1,12 @@
+// Test that `f16` requires `Float16` (or 16-bit storage) to be enabled.
+
+// build-fail
+
+#![feature(f16)]
+
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(input: f32, output: &mut f32) {
+    *output = (input as f16 * 2.0) as f32;
+}
@@ -0,0 +1,20 @@
+// Test storage-only `f16` (without `Float16`), via `SPV_KHR_16bit_storage`.
+
+// build-pass
+// compile-flags: -C target-feature=+StorageBuffer16BitAccess,+ext:SPV_KHR_16bit_storage
+
+#![feature(f16)]
+
+use spirv_std::glam::UVec3;
+use spirv_std::spirv;
+
+#[spirv(compute(threads(64)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] input: &[f16],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] output: &mut [f16],
+) {
+    let i = id.x as usize;
+    let x = input[i] as f32;
+    output[i] = (x * x) as f16;
+}
@@ -0,0 +1,25 @@
+// Test `spirv_std::half` vector types and `GLSL.std.450` math on `f16`.
+
+// build-pass
+// compile-flags: -C target-feature=+Float16
+
+#![feature(f16)]
+
+use spirv_std::glam::{Vec2, Vec4};
+use spirv_std::half::{F16Math, F16Vec2, F16Vec3, F16Vec4};
+use spirv_std::spirv;
+
+#[spirv(fragment)]
+pub fn main(uv: Vec2, color: Vec4, output: &mut Vec4) {
+    let uv = F16Vec2::from(uv);
+    let color = F16Vec4::from(color);
+
+    let s = F16Math::abs(uv.x.sin()) + F16Math::powf(uv.y, 2.0);
+    let n = F16Vec3::new(uv.x, uv.y, s);
+    let lit = n.dot(F16Vec3::splat(0.5)).clamp(0.0, 1.0);
+
+    let mixed = color.lerp(F16Vec4::ONE, F16Vec4::splat(lit));
+    let shaded = (mixed * lit + F16Vec4::splat(0.1)).sqrt().min(F16Vec4::ONE);
+
+    *output = shaded.into();
+}
@@ -0,0 +1,22 @@
+// Test the `--zombie-report` output (emitted as a note, without a `FILE`), for
+// a zombie in user code, which is only reached through a call.
+
+// build-fail
+// compile-flags: -C llvm-args=--zombie-report
+
+use spirv_std::spirv;
+
+#[derive(Clone, Copy)]
+struct Position(u32);
+
+fn use_cmp(cmp: fn(&Position) -> u32) {
+    let a = Position(0);
+    let b = Position(1);
+
+    let _ = if cmp(&a) <= cmp(&b) { a } else { b };
+}
+
+#[spirv(compute(threads(1)))]
+pub fn main() {
+    use_cmp(|p| p.0);
+}
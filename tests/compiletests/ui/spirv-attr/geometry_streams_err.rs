@@ -0,0 +1,22 @@
+// Tests that outputs can only be assigned to streams which vertices are emitted to.
+// build-fail
+// compile-flags: -Ctarget-feature=+Geometry
+
+use spirv_std::arch::emit_stream_vertex;
+use spirv_std::glam::*;
+use spirv_std::spirv;
+
+#[spirv(geometry(input_points = 2, output_points = 2))]
+pub fn main(
+    #[spirv(stream = 0)] on_stream_0: &mut Vec4,
+    #[spirv(stream = 1)] on_stream_1: &mut Vec4,
+    #[spirv(stream = 2)] on_stream_2: &mut Vec4,
+) {
+    unsafe {
+        emit_stream_vertex::<0>();
+        emit_stream_vertex::<2>();
+    }
+}
+
+#[spirv(geometry(input_points = 2, output_points = 2))]
+pub fn never_emits(#[spirv(stream = 3)] on_stream_3: &mut Vec4) {}
@@ -0,0 +1,36 @@
+// Tests multi-stream geometry outputs captured with transform feedback, and that
+// the `GeometryStreams` and `TransformFeedback` capabilities are added automatically.
+// build-pass
+// compile-flags: -Ctarget-feature=+Geometry
+// compile-flags: -C llvm-args=--disassemble-globals
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpLine .*\n" -> ""
+// normalize-stderr-test "%\d+ = OpString .*\n" -> ""
+// normalize-stderr-test "; .*\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpExtension .SPV_KHR_vulkan_memory_model.\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+// only-vulkan1.2
+
+use spirv_std::arch::{emit_stream_vertex, end_stream_primitive};
+use spirv_std::glam::*;
+use spirv_std::spirv;
+
+#[spirv(geometry(input_points = 2, output_points = 2, xfb))]
+pub fn main(
+    particle_in: [Vec4; 1],
+    #[spirv(position, stream = 0)] position: &mut Vec4,
+    #[spirv(stream = 1, xfb_buffer = 0, xfb_stride = 32, offset = 0)] captured_position: &mut Vec4,
+    #[spirv(stream = 1, xfb_buffer = 0, xfb_stride = 32, offset = 16)] captured_velocity: &mut Vec4,
+) {
+    unsafe {
+        *position = particle_in[0];
+        emit_stream_vertex::<0>();
+        end_stream_primitive::<0>();
+
+        *captured_position = particle_in[0];
+        *captured_velocity = Vec4::ZERO;
+        emit_stream_vertex::<1>();
+        end_stream_primitive::<1>();
+    }
+}
@@ -0,0 +1,21 @@
+// Tests that cooperative matrix type lowering fails correctly
+// build-fail
+// compile-flags: -C target-feature=+CooperativeMatrixKHR,+ext:SPV_KHR_cooperative_matrix
+
+use core::marker::PhantomData;
+use spirv_std::spirv;
+
+#[spirv(cooperative_matrix)]
+pub struct _CooperativeMatrix<T, const SCOPE: u32, const ROWS: u32, const COLS: u32, const USE: u32>
+{
+    _anti_zst_padding: core::mem::MaybeUninit<u32>,
+    _marker: PhantomData<T>,
+}
+
+#[spirv(compute(threads(32)))]
+pub fn _entry(
+    #[spirv(workgroup)] _vector: &mut _CooperativeMatrix<glam::Vec2, 3, 16, 16, 2>,
+    #[spirv(workgroup)] _scope: &mut _CooperativeMatrix<f32, 42, 16, 16, 2>,
+    #[spirv(workgroup)] _use: &mut _CooperativeMatrix<f32, 3, 16, 16, 42>,
+) {
+}
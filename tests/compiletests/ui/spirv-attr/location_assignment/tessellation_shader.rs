@@ -0,0 +1,56 @@
+// build-pass
+// compile-flags: -Ctarget-feature=+Tessellation
+// compile-flags: -C llvm-args=--disassemble-globals
+// normalize-stderr-test "OpSource .*\n" -> ""
+// normalize-stderr-test "OpLine .*\n" -> ""
+// normalize-stderr-test "%\d+ = OpString .*\n" -> ""
+// normalize-stderr-test "; .*\n" -> ""
+// normalize-stderr-test "OpCapability VulkanMemoryModel\n" -> ""
+// normalize-stderr-test "OpMemoryModel Logical Vulkan" -> "OpMemoryModel Logical Simple"
+// ignore-spv1.0
+// ignore-spv1.1
+// ignore-spv1.2
+// ignore-spv1.3
+// ignore-vulkan1.0
+// ignore-vulkan1.1
+
+use spirv_std::glam::*;
+use spirv_std::spirv;
+
+#[spirv(tessellation_control(output_vertices = 3))]
+pub fn main_tcs(
+    #[spirv(invocation_id)] invocation_id: u32,
+    // location 0
+    color_in: [Vec4; 32],
+    // location 0
+    color_out: &mut [Vec4; 3],
+    // location 1
+    #[spirv(patch)] center_out: &mut Vec4,
+    #[spirv(tess_level_inner)] tess_level_inner: &mut [f32; 2],
+    #[spirv(tess_level_outer)] tess_level_outer: &mut [f32; 4],
+) {
+    let i = invocation_id as usize;
+    if i < 3 {
+        color_out[i] = color_in[i];
+    }
+    if i == 0 {
+        *center_out = (color_in[0] + color_in[1] + color_in[2]) / 3.0;
+        *tess_level_inner = [4.0; 2];
+        *tess_level_outer = [4.0; 4];
+    }
+}
+
+#[spirv(tessellation_evaluation(triangles, spacing_equal, vertex_order_ccw, output_vertices = 3))]
+pub fn main_tes(
+    #[spirv(tess_coord)] tess_coord: Vec3,
+    // location 0
+    color_in: [Vec4; 3],
+    // location 1
+    #[spirv(patch)] center_in: Vec4,
+    #[spirv(position)] position: &mut Vec4,
+) {
+    *position = center_in
+        + color_in[0] * tess_coord.x
+        + color_in[1] * tess_coord.y
+        + color_in[2] * tess_coord.z;
+}
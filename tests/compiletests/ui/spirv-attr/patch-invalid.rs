@@ -0,0 +1,16 @@
+// Tests that `#[spirv(patch)]` is only accepted on tessellation control
+// outputs and tessellation evaluation inputs.
+// build-fail
+// compile-flags: -Ctarget-feature=+Tessellation
+
+use spirv_std::glam::*;
+use spirv_std::spirv;
+
+#[spirv(vertex)]
+pub fn main_vs(#[spirv(patch)] out: &mut Vec4) {}
+
+#[spirv(tessellation_control(output_vertices = 3))]
+pub fn main_tcs(#[spirv(patch)] input: Vec4) {}
+
+#[spirv(tessellation_evaluation(triangles))]
+pub fn main_tes(#[spirv(patch)] out: &mut Vec4) {}
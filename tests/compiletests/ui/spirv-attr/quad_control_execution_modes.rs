@@ -0,0 +1,17 @@
+// build-pass
+
+// NOTE `rspirv` can't represent these execution modes yet, so they're emitted
+// as custom instructions, which only get turned into actual `OpExecutionMode`s
+// (along with their capabilities/extensions) in the final SPIR-V binary.
+
+use spirv_std::spirv;
+
+#[spirv(fragment(quad_derivatives_khr, maximally_reconverges_khr))]
+pub fn main(#[spirv(flat)] predicate: u32, output: &mut u32) {
+    *output = spirv_std::arch::subgroup_quad_any(predicate != 0) as u32;
+}
+
+#[spirv(fragment(maximally_reconverges_khr))]
+pub fn maximally_reconverges(output: &mut u32) {
+    *output = 1;
+}
@@ -0,0 +1,19 @@
+// Tests that per-vertex tessellation interface variables are arrays, with
+// one element per vertex where `output_vertices` is known.
+// build-fail
+// compile-flags: -Ctarget-feature=+Tessellation
+
+use spirv_std::glam::*;
+use spirv_std::spirv;
+
+#[spirv(tessellation_control(output_vertices = 3))]
+pub fn main_tcs(
+    not_array_in: Vec4,
+    not_array_out: &mut Vec4,
+    wrong_len_out: &mut [Vec4; 4],
+    #[spirv(patch)] patch_out: &mut [Vec4; 4],
+) {
+}
+
+#[spirv(tessellation_evaluation(triangles, output_vertices = 3))]
+pub fn main_tes(not_array_in: Vec4, wrong_len_in: [Vec4; 4], any_len_out: &mut Vec4) {}
@@ -0,0 +1,16 @@
+// Tests invalid uses of the `stream` and transform feedback attributes.
+// build-fail
+
+use spirv_std::glam::*;
+use spirv_std::spirv;
+
+#[spirv(vertex(xfb))]
+pub fn main_vs(
+    #[spirv(stream = 0)] not_geometry: &mut Vec4,
+    #[spirv(xfb_buffer = 0, xfb_stride = 16, offset = 0)] input: Vec4,
+    #[spirv(xfb_buffer = 0, offset = 0)] missing_stride: &mut Vec4,
+) {
+}
+
+#[spirv(vertex)]
+pub fn no_xfb_mode(#[spirv(xfb_buffer = 0, xfb_stride = 16, offset = 0)] output: &mut Vec4) {}
@@ -0,0 +1,35 @@
+// build-pass
+// compile-flags: -C target-feature=+ShaderNonUniform,+ext:SPV_EXT_descriptor_indexing
+
+use glam::{UVec2, Vec2, Vec4};
+use spirv_std::descriptor_table::{NonUniform, Uniform};
+use spirv_std::spirv;
+use spirv_std::{DescriptorTable, Image, Sampler, TypedBuffer};
+
+#[spirv(fragment)]
+pub fn main(
+    #[spirv(flat)] index: u32,
+    #[spirv(push_constant)] uniform_index: &u32,
+    #[spirv(descriptor_set = 0, binding = 0)] samplers: &DescriptorTable<Sampler>,
+    #[spirv(descriptor_set = 0, binding = 1)] textures: &DescriptorTable<
+        Image!(2D, type=f32, sampled),
+    >,
+    #[spirv(descriptor_set = 0, binding = 2)] storage_images: &DescriptorTable<
+        Image!(2D, format=rgba32f, sampled=false),
+    >,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 3)] buffers: &mut DescriptorTable<
+        TypedBuffer<[Vec4]>,
+    >,
+    output: &mut Vec4,
+) {
+    let uv = Vec2::new(0.0, 1.0);
+    unsafe {
+        let sampler = samplers.get(Uniform(*uniform_index));
+        let r1: Vec4 = textures.get(NonUniform(index)).sample(*sampler, uv);
+        let r2: Vec4 = textures.get(Uniform(*uniform_index)).sample(*sampler, uv);
+        let r3: Vec4 = storage_images.get(NonUniform(index)).read(UVec2::new(1, 2));
+        let buffer = buffers.get_mut(NonUniform(index));
+        buffer[0] = r1 + r2 + r3;
+        *output = buffers.get(Uniform(*uniform_index))[1];
+    }
+}
@@ -0,0 +1,28 @@
+// build-pass
+// compile-flags: -C target-feature=+RayQueryKHR,+ShaderNonUniform,+ext:SPV_KHR_ray_query,+ext:SPV_EXT_descriptor_indexing
+
+use glam::Vec3;
+use spirv_std::descriptor_table::NonUniform;
+use spirv_std::ray_tracing::{AccelerationStructure, RayFlags, RayQuery};
+use spirv_std::spirv;
+use spirv_std::DescriptorTable;
+
+#[spirv(fragment)]
+pub fn main(
+    #[spirv(flat)] index: u32,
+    #[spirv(descriptor_set = 0, binding = 0)] scenes: &DescriptorTable<AccelerationStructure>,
+) {
+    unsafe {
+        spirv_std::ray_query!(let mut ray_query);
+
+        ray_query.initialize(
+            scenes.get(NonUniform(index)),
+            RayFlags::NONE,
+            0,
+            Vec3::new(1.0, 2.0, 3.0),
+            0.5,
+            Vec3::new(3.0, 2.0, 1.0),
+            1.0,
+        );
+    }
+}
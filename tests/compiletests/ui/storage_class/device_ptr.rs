@@ -0,0 +1,57 @@
+// build-pass
+// compile-flags: -C target-feature=+PhysicalStorageBufferAddresses,+Int64,+ext:SPV_KHR_physical_storage_buffer
+
+use spirv_std::glam::{UVec3, Vec4};
+use spirv_std::{DevicePtr, DeviceSlice, spirv};
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Particle {
+    position: Vec4,
+    velocity: Vec4,
+    age: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PushConstants {
+    particles: DeviceSlice<Particle>,
+    alive_count: DevicePtr<u32>,
+    // Pointers can be stored in device memory too.
+    indirect_scale: DevicePtr<DevicePtr<f32>>,
+}
+
+#[spirv(compute(threads(64)))]
+pub fn main(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(push_constant)] constants: &PushConstants,
+) {
+    let Some(particle) = constants.particles.get(id.x as usize) else {
+        return;
+    };
+
+    let scale_ptr = unsafe { constants.indirect_scale.read() };
+    let scale = if scale_ptr.is_null() {
+        1.0
+    } else {
+        unsafe { scale_ptr.read() }
+    };
+
+    let particle = unsafe { particle.as_mut() };
+    particle.position += particle.velocity * scale;
+    particle.age += 1.0;
+
+    if particle.age < 100.0 {
+        unsafe {
+            *constants.alive_count.as_mut() += 1;
+        }
+    }
+
+    // Neighbouring element, via pointer arithmetic.
+    if id.x > 0 {
+        let prev = constants.particles.as_ptr().add(id.x as usize - 1);
+        let prev_age = unsafe { prev.cast::<f32>().byte_add(32).read() };
+        unsafe { constants.particles.write(id.x as usize, constants.particles.read(0)) };
+        let _ = prev_age;
+    }
+}
@@ -0,0 +1,8 @@
+// build-fail
+
+use spirv_std::{DevicePtr, spirv};
+
+#[spirv(compute(threads(1)))]
+pub fn main(#[spirv(push_constant)] ptr: &DevicePtr<u32>) {
+    unsafe { ptr.write(0) };
+}
@@ -1,6 +1,6 @@
 #![allow(clippy::unimplemented)]
 
-use difftest::config::OutputType;
+use difftest::config::{ImageFormat, OutputType, RecordField, RecordLayout, ScalarType, Tolerance};
 use std::marker::PhantomData;
 
 /// Represents the magnitude of a difference between two values
@@ -37,6 +37,23 @@ pub trait DifferenceDisplay {
 
     /// Write human-readable output to a file
     fn write_human_readable(&self, output: &[u8], path: &std::path::Path) -> std::io::Result<()>;
+
+    /// File extension used for the human-readable output
+    fn human_readable_extension(&self) -> &'static str {
+        "txt"
+    }
+
+    /// Write an image visualizing where two outputs differ, if supported,
+    /// returning whether it was written
+    fn write_diff_image(
+        &self,
+        _output1: &[u8],
+        _output2: &[u8],
+        _epsilon: Option<f32>,
+        _path: &std::path::Path,
+    ) -> std::io::Result<bool> {
+        Ok(false)
+    }
 }
 
 /// A single difference between two values
@@ -471,26 +488,544 @@ impl<T: NumericType> DifferenceDisplay for NumericDiffer<T> {
 pub type F32Differ = NumericDiffer<f32>;
 pub type U32Differ = NumericDiffer<u32>;
 
-impl From<OutputType> for Box<dyn OutputDiffer + Send + Sync> {
-    fn from(output_type: OutputType) -> Self {
-        match output_type {
+/// Differ for images, comparing them pixel by pixel (with the epsilon applying
+/// to each channel)
+pub struct ImageDiffer {
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+}
+
+impl ImageDiffer {
+    pub fn new(width: u32, height: u32, format: ImageFormat) -> Self {
+        Self {
+            width,
+            height,
+            format,
+        }
+    }
+
+    fn pixels<'a>(&self, output: &'a [u8]) -> impl Iterator<Item = Vec<f32>> + 'a {
+        let format = self.format;
+        output
+            .chunks_exact(format.bytes_per_pixel())
+            .map(move |pixel| format.decode_pixel(pixel))
+    }
+
+    fn format_pixel(pixel: &[f32]) -> String {
+        let channels: Vec<String> = pixel.iter().map(|c| format!("{c:.4}")).collect();
+        format!("({})", channels.join(", "))
+    }
+
+    /// Largest difference between any channel of the two pixels.
+    fn pixel_diff(pixel1: &[f32], pixel2: &[f32]) -> f32 {
+        pixel1
+            .iter()
+            .zip(pixel2)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, |max, diff| {
+                if diff.is_nan() {
+                    f32::NAN
+                } else {
+                    max.max(diff)
+                }
+            })
+    }
+
+    /// Convert an output to 8-bit RGBA, clamping float channels to `0.0..=1.0`.
+    fn to_rgba8(&self, output: &[u8]) -> Vec<u8> {
+        let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        self.pixels(output)
+            .flat_map(|pixel| match *pixel.as_slice() {
+                [r, g, b, a] => [to_u8(r), to_u8(g), to_u8(b), to_u8(a)],
+                [v] => [to_u8(v), to_u8(v), to_u8(v), 255],
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    fn write_png(&self, rgba: &[u8], path: &std::path::Path) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(rgba))
+            .map_err(std::io::Error::other)
+    }
+}
+
+impl OutputDiffer for ImageDiffer {
+    fn compare(&self, output1: &[u8], output2: &[u8], epsilon: Option<f32>) -> Vec<Difference> {
+        if output1.len() != output2.len() {
+            return vec![Difference {
+                index: 0,
+                value1: format!("{} bytes", output1.len()),
+                value2: format!("{} bytes", output2.len()),
+                absolute_diff: DiffMagnitude::Numeric(0.0),
+                relative_diff: DiffMagnitude::Incomparable,
+            }];
+        }
+
+        let threshold = epsilon.unwrap_or(0.0);
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        self.pixels(output1)
+            .zip(self.pixels(output2))
+            .enumerate()
+            .filter_map(|(i, (pixel1, pixel2))| {
+                let bytes = i * bytes_per_pixel..(i + 1) * bytes_per_pixel;
+                if output1[bytes.clone()] == output2[bytes] {
+                    return None;
+                }
+                let diff = Self::pixel_diff(&pixel1, &pixel2);
+                (diff.is_nan() || diff > threshold).then(|| Difference {
+                    index: i,
+                    value1: Self::format_pixel(&pixel1),
+                    value2: Self::format_pixel(&pixel2),
+                    absolute_diff: DiffMagnitude::Numeric(diff.into()),
+                    relative_diff: DiffMagnitude::Incomparable,
+                })
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "Image"
+    }
+}
+
+impl DifferenceDisplay for ImageDiffer {
+    fn format_table(&self, diffs: &[Difference], pkg1: &str, pkg2: &str) -> String {
+        use tabled::settings::{Alignment, Modify, Style, object::Rows};
+
+        let width = self.width.max(1) as usize;
+        let mut builder = tabled::builder::Builder::default();
+        builder.push_record(vec!["x", "y", pkg1, pkg2, "Δ max"]);
+        for d in diffs.iter().take(10) {
+            let abs_str = match &d.absolute_diff {
+                DiffMagnitude::Numeric(val) => format!("{val:.3e}"),
+                DiffMagnitude::Incomparable => "N/A".to_string(),
+            };
+            builder.push_record(vec![
+                (d.index % width).to_string(),
+                (d.index / width).to_string(),
+                d.value1.clone(),
+                d.value2.clone(),
+                abs_str,
+            ]);
+        }
+
+        let mut table = builder.build();
+        table
+            .with(Style::modern())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+
+        let mut result = table.to_string();
+
+        if diffs.len() > 10 {
+            let last_line_width = result.lines().last().map_or(0, |l| l.chars().count());
+            result.push_str(&format!(
+                "\n{:>width$}",
+                format!("... {} more differences", diffs.len() - 10),
+                width = last_line_width
+            ));
+        }
+
+        result
+    }
+
+    fn format_report(
+        &self,
+        diffs: &[Difference],
+        pkg1: &str,
+        pkg2: &str,
+        _epsilon: Option<f32>,
+    ) -> String {
+        let total = self.width as usize * self.height as usize;
+        let mut report = format!(
+            "Differing pixels: {} of {} ({}x{} {:?})\n\n",
+            diffs.len(),
+            total,
+            self.width,
+            self.height,
+            self.format
+        );
+        report.push_str(&self.format_table(diffs, pkg1, pkg2));
+        report
+    }
+
+    fn write_human_readable(&self, output: &[u8], path: &std::path::Path) -> std::io::Result<()> {
+        // For images, write them as a PNG
+        self.write_png(&self.to_rgba8(output), path)
+    }
+
+    fn human_readable_extension(&self) -> &'static str {
+        "png"
+    }
+
+    fn write_diff_image(
+        &self,
+        output1: &[u8],
+        output2: &[u8],
+        epsilon: Option<f32>,
+        path: &std::path::Path,
+    ) -> std::io::Result<bool> {
+        if output1.len() != output2.len() {
+            return Ok(false);
+        }
+
+        // Heatmap of the differences: pixels within epsilon are shown as a dimmed
+        // grayscale version of the first image, and the others in red, brighter
+        // the larger the difference.
+        let threshold = epsilon.unwrap_or(0.0);
+        let diffs: Vec<f32> = self
+            .pixels(output1)
+            .zip(self.pixels(output2))
+            .map(|(pixel1, pixel2)| Self::pixel_diff(&pixel1, &pixel2))
+            .collect();
+        let max_diff = diffs
+            .iter()
+            .copied()
+            .filter(|diff| !diff.is_nan())
+            .fold(0.0, f32::max);
+        let rgba: Vec<u8> = self
+            .to_rgba8(output1)
+            .chunks_exact(4)
+            .zip(diffs)
+            .flat_map(|(pixel, diff)| {
+                if diff.is_nan() || diff > threshold {
+                    let t = if diff.is_nan() || max_diff == 0.0 {
+                        1.0
+                    } else {
+                        diff / max_diff
+                    };
+                    [128 + (127.0 * t).round() as u8, 0, 0, 255]
+                } else {
+                    let luma =
+                        (u16::from(pixel[0]) + u16::from(pixel[1]) + u16::from(pixel[2])) / 3;
+                    let dimmed = (luma / 4) as u8;
+                    [dimmed, dimmed, dimmed, 255]
+                }
+            })
+            .collect();
+        self.write_png(&rgba, path)?;
+        Ok(true)
+    }
+}
+
+/// Differ for arrays of records, comparing each component of each field with
+/// the field's own tolerance (and ignoring padding)
+pub struct RecordDiffer {
+    layout: RecordLayout,
+}
+
+impl RecordDiffer {
+    pub fn new(layout: RecordLayout) -> Self {
+        Self { layout }
+    }
+
+    /// Differences between all the record fields in both outputs, with the
+    /// `index` of each being the byte offset of the differing component.
+    pub fn diff_records(
+        layout: &RecordLayout,
+        output1: &[u8],
+        output2: &[u8],
+        epsilon: Option<f32>,
+    ) -> Vec<Difference> {
+        if output1.len() != output2.len() {
+            return vec![Difference {
+                index: 0,
+                value1: format!("{} bytes", output1.len()),
+                value2: format!("{} bytes", output2.len()),
+                absolute_diff: DiffMagnitude::Numeric(0.0),
+                relative_diff: DiffMagnitude::Incomparable,
+            }];
+        }
+
+        let mut differences = Vec::new();
+        for (offset, field) in Self::components(layout, output1.len()) {
+            let ty = field.ty;
+            let tolerance = field.tolerance.unwrap_or(Tolerance {
+                abs: epsilon.filter(|_| ty.is_float()).map(f64::from),
+                ..Default::default()
+            });
+            let bits1 = read_bits(ty, &output1[offset..]);
+            let bits2 = read_bits(ty, &output2[offset..]);
+            if scalars_match(ty, bits1, bits2, tolerance) {
+                continue;
+            }
+
+            let (v1, v2) = (scalar_as_f64(ty, bits1), scalar_as_f64(ty, bits2));
+            let diff = scalar_abs_diff(ty, bits1, bits2);
+            let max_abs = f64::max(v1.abs(), v2.abs());
+            differences.push(Difference {
+                index: offset,
+                value1: format_scalar(ty, bits1),
+                value2: format_scalar(ty, bits2),
+                absolute_diff: if diff.is_finite() {
+                    DiffMagnitude::Numeric(diff)
+                } else {
+                    DiffMagnitude::Incomparable
+                },
+                relative_diff: if diff.is_finite() && max_abs > 1e-10 {
+                    DiffMagnitude::Numeric(diff / max_abs)
+                } else {
+                    DiffMagnitude::Incomparable
+                },
+            });
+        }
+        differences
+    }
+
+    /// Byte offset (and field) of every field component that fits in an output
+    /// of `len` bytes.
+    fn components(
+        layout: &RecordLayout,
+        len: usize,
+    ) -> impl Iterator<Item = (usize, &RecordField)> {
+        let size = layout.size as usize;
+        (0..len.div_ceil(size)).flat_map(move |record| {
+            layout.fields.iter().flat_map(move |field| {
+                (0..field.count).filter_map(move |i| {
+                    let offset =
+                        record * size + field.offset as usize + (i * field.ty.size()) as usize;
+                    (offset + field.ty.size() as usize <= len).then_some((offset, field))
+                })
+            })
+        })
+    }
+
+    /// Path to the component at byte `offset`, e.g. `record[17].normal.y`.
+    fn component_path(&self, offset: usize) -> String {
+        let size = self.layout.size as usize;
+        let (record, offset) = (offset / size, (offset % size) as u32);
+        let component = self.layout.fields.iter().find_map(|field| {
+            let len = field.count * field.ty.size();
+            (field.offset..field.offset + len)
+                .contains(&offset)
+                .then(|| field.component_name((offset - field.offset) / field.ty.size()))
+        });
+        match component {
+            Some(component) => format!("record[{record}].{component}"),
+            None => format!("record[{record}]+{offset}"),
+        }
+    }
+}
+
+fn read_bits(ty: ScalarType, bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    let size = ty.size() as usize;
+    buf[..size].copy_from_slice(&bytes[..size]);
+    u64::from_le_bytes(buf)
+}
+
+fn scalar_as_f64(ty: ScalarType, bits: u64) -> f64 {
+    match ty {
+        ScalarType::F32 => f32::from_bits(bits as u32).into(),
+        ScalarType::F64 => f64::from_bits(bits),
+        ScalarType::U32 | ScalarType::U64 => bits as f64,
+        ScalarType::I32 => (bits as u32 as i32).into(),
+        ScalarType::I64 => bits as i64 as f64,
+    }
+}
+
+fn scalar_as_i128(ty: ScalarType, bits: u64) -> i128 {
+    match ty {
+        ScalarType::I32 => (bits as u32 as i32).into(),
+        ScalarType::I64 => (bits as i64).into(),
+        _ => bits.into(),
+    }
+}
+
+fn scalar_abs_diff(ty: ScalarType, bits1: u64, bits2: u64) -> f64 {
+    if ty.is_float() {
+        (scalar_as_f64(ty, bits1) - scalar_as_f64(ty, bits2)).abs()
+    } else {
+        scalar_as_i128(ty, bits1).abs_diff(scalar_as_i128(ty, bits2)) as f64
+    }
+}
+
+/// Distance between two floats in units in the last place, i.e. how many
+/// representable values apart they are.
+fn ulp_distance(ty: ScalarType, bits1: u64, bits2: u64) -> u64 {
+    // Map the sign-magnitude bit patterns to monotonically increasing integers.
+    let ordered = |bits: u64| -> i128 {
+        let (magnitude, negative) = match ty {
+            ScalarType::F32 => (bits & 0x7fff_ffff, bits & 0x8000_0000 != 0),
+            _ => (bits & !(1 << 63), bits & (1 << 63) != 0),
+        };
+        if negative {
+            -i128::from(magnitude)
+        } else {
+            i128::from(magnitude)
+        }
+    };
+    ordered(bits1)
+        .abs_diff(ordered(bits2))
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+fn scalars_match(ty: ScalarType, bits1: u64, bits2: u64, tolerance: Tolerance) -> bool {
+    if bits1 == bits2 {
+        return true;
+    }
+    let (v1, v2) = (scalar_as_f64(ty, bits1), scalar_as_f64(ty, bits2));
+    if ty.is_float() && v1.is_nan() && v2.is_nan() {
+        return true;
+    }
+    let diff = scalar_abs_diff(ty, bits1, bits2);
+    tolerance.abs.is_some_and(|abs| diff <= abs)
+        || tolerance
+            .rel
+            .is_some_and(|rel| diff <= rel * f64::max(v1.abs(), v2.abs()))
+        || (ty.is_float()
+            && !v1.is_nan()
+            && !v2.is_nan()
+            && tolerance
+                .ulps
+                .is_some_and(|ulps| ulp_distance(ty, bits1, bits2) <= ulps))
+}
+
+fn format_scalar(ty: ScalarType, bits: u64) -> String {
+    match ty {
+        ScalarType::F32 => format!("{:.9}", f32::from_bits(bits as u32)),
+        ScalarType::F64 => format!("{:.17}", f64::from_bits(bits)),
+        _ => scalar_as_i128(ty, bits).to_string(),
+    }
+}
+
+impl OutputDiffer for RecordDiffer {
+    fn compare(&self, output1: &[u8], output2: &[u8], epsilon: Option<f32>) -> Vec<Difference> {
+        Self::diff_records(&self.layout, output1, output2, epsilon)
+    }
+
+    fn name(&self) -> &'static str {
+        "Records"
+    }
+}
+
+impl DifferenceDisplay for RecordDiffer {
+    fn format_table(&self, diffs: &[Difference], pkg1: &str, pkg2: &str) -> String {
+        use tabled::settings::{Alignment, Modify, Style, object::Rows};
+
+        let mut builder = tabled::builder::Builder::default();
+        builder.push_record(vec!["Field", pkg1, pkg2, "Δ abs", "Δ %"]);
+        for d in diffs.iter().take(10) {
+            let abs_str = match &d.absolute_diff {
+                DiffMagnitude::Numeric(val) => format!("{val:.3e}"),
+                DiffMagnitude::Incomparable => "N/A".to_string(),
+            };
+            let rel_str = match &d.relative_diff {
+                DiffMagnitude::Numeric(val) => format!("{:.2}%", val * 100.0),
+                DiffMagnitude::Incomparable => "N/A".to_string(),
+            };
+            builder.push_record(vec![
+                self.component_path(d.index),
+                d.value1.clone(),
+                d.value2.clone(),
+                abs_str,
+                rel_str,
+            ]);
+        }
+
+        let mut table = builder.build();
+        table
+            .with(Style::modern())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+
+        let mut result = table.to_string();
+
+        if diffs.len() > 10 {
+            let last_line_width = result.lines().last().map_or(0, |l| l.chars().count());
+            result.push_str(&format!(
+                "\n{:>width$}",
+                format!("... {} more differences", diffs.len() - 10),
+                width = last_line_width
+            ));
+        }
+
+        result
+    }
+
+    fn format_report(
+        &self,
+        diffs: &[Difference],
+        pkg1: &str,
+        pkg2: &str,
+        _epsilon: Option<f32>,
+    ) -> String {
+        self.format_table(diffs, pkg1, pkg2)
+    }
+
+    fn write_human_readable(&self, output: &[u8], path: &std::path::Path) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut file = std::fs::File::create(path)?;
+
+        // One line per record, e.g. `record[0]: pos = (1.0, 2.0), id = 3`.
+        let size = self.layout.size as usize;
+        for (record, bytes) in output.chunks(size).enumerate() {
+            let fields: Vec<String> = self
+                .layout
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    let components: Vec<String> = (0..field.count)
+                        .map(|i| (field.offset + i * field.ty.size()) as usize)
+                        .filter(|&offset| offset + field.ty.size() as usize <= bytes.len())
+                        .map(|offset| {
+                            format_scalar(field.ty, read_bits(field.ty, &bytes[offset..]))
+                        })
+                        .collect();
+                    match components.len() {
+                        0 => None,
+                        1 if field.count == 1 => {
+                            Some(format!("{} = {}", field.name, components[0]))
+                        }
+                        _ => Some(format!("{} = ({})", field.name, components.join(", "))),
+                    }
+                })
+                .collect();
+            writeln!(file, "record[{record}]: {}", fields.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&OutputType> for Box<dyn OutputDiffer + Send + Sync> {
+    fn from(output_type: &OutputType) -> Self {
+        match *output_type {
             OutputType::Raw => Box::new(RawDiffer),
             OutputType::F32 => Box::new(F32Differ::default()),
             OutputType::F64 => unimplemented!("F64Differ not implemented yet"),
             OutputType::U32 => Box::new(U32Differ::default()),
             OutputType::I32 => unimplemented!("I32Differ not implemented yet"),
+            OutputType::Image {
+                width,
+                height,
+                format,
+            } => Box::new(ImageDiffer::new(width, height, format)),
+            OutputType::Records(ref layout) => Box::new(RecordDiffer::new(layout.clone())),
         }
     }
 }
 
-impl From<OutputType> for Box<dyn DifferenceDisplay + Send + Sync> {
-    fn from(output_type: OutputType) -> Self {
-        match output_type {
+impl From<&OutputType> for Box<dyn DifferenceDisplay + Send + Sync> {
+    fn from(output_type: &OutputType) -> Self {
+        match *output_type {
             OutputType::Raw => Box::new(RawDiffer),
             OutputType::F32 => Box::new(F32Differ::default()),
             OutputType::F64 => unimplemented!("F64Differ not implemented yet"),
             OutputType::U32 => Box::new(U32Differ::default()),
             OutputType::I32 => unimplemented!("I32Differ not implemented yet"),
+            OutputType::Image {
+                width,
+                height,
+                format,
+            } => Box::new(ImageDiffer::new(width, height, format)),
+            OutputType::Records(ref layout) => Box::new(RecordDiffer::new(layout.clone())),
         }
     }
 }
@@ -667,4 +1202,229 @@ mod tests {
             DiffMagnitude::Numeric(_) => panic!("Expected incomparable"),
         }
     }
+
+    #[test]
+    fn test_image_differ_with_epsilon() {
+        let differ = ImageDiffer::new(2, 2, ImageFormat::Rgba8Unorm);
+        let image1 = [[0u8, 0, 0, 255], [255, 0, 0, 255], [0, 255, 0, 255], [0; 4]];
+        let mut image2 = image1;
+        image2[1][0] = 254; // barely different
+        image2[2][1] = 0; // very different
+        let bytes1 = bytemuck::cast_slice(&image1);
+        let bytes2 = bytemuck::cast_slice(&image2);
+
+        // With epsilon = 0.01, only the pixel at (0, 1) should be reported
+        let diffs = differ.compare(bytes1, bytes2, Some(0.01));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].index, 2);
+        match &diffs[0].absolute_diff {
+            DiffMagnitude::Numeric(val) => assert_eq!(*val, 1.0),
+            DiffMagnitude::Incomparable => panic!("Expected numeric difference"),
+        }
+        let table = differ.format_table(&diffs, "foo", "bar");
+        assert!(table.contains("(0.0000, 1.0000, 0.0000, 1.0000)"));
+
+        // Without epsilon, both differences should be reported
+        let diffs = differ.compare(bytes1, bytes2, None);
+        assert_eq!(diffs.len(), 2);
+    }
+
+    #[test]
+    fn test_image_differ_float_nan() {
+        let differ = ImageDiffer::new(2, 1, ImageFormat::R32Float);
+        let bytes1 = bytemuck::cast_slice(&[0.5f32, 1.0]);
+        let bytes2 = bytemuck::cast_slice(&[0.5f32, f32::NAN]);
+
+        let diffs = differ.compare(bytes1, bytes2, Some(0.1));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].index, 1);
+    }
+
+    #[test]
+    fn test_image_differ_writes_pngs() {
+        let differ = ImageDiffer::new(2, 1, ImageFormat::Rgba8Unorm);
+        let bytes1 = [0u8, 0, 0, 255, 255, 255, 255, 255];
+        let bytes2 = [0u8, 0, 0, 255, 0, 0, 0, 255];
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("output.png");
+        differ.write_human_readable(&bytes1, &path).unwrap();
+        let decoder =
+            png::Decoder::new(std::io::BufReader::new(std::fs::File::open(&path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(buf, bytes1);
+
+        let diff_path = dir.path().join("output.diff.png");
+        assert!(
+            differ
+                .write_diff_image(&bytes1, &bytes2, None, &diff_path)
+                .unwrap()
+        );
+        let decoder = png::Decoder::new(std::io::BufReader::new(
+            std::fs::File::open(&diff_path).unwrap(),
+        ));
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut buf).unwrap();
+        // The first pixel matches (and is black), the second one is fully red
+        assert_eq!(buf, [0, 0, 0, 255, 255, 0, 0, 255]);
+    }
+
+    fn particle_layout() -> RecordLayout {
+        // struct Particle { position: Vec3, id: u32, normal: Vec3, _pad: u32 }
+        RecordLayout::new(
+            32,
+            vec![
+                RecordField::new("position", 0, ScalarType::F32)
+                    .vector(3)
+                    .with_tolerance(Tolerance::abs(0.001)),
+                RecordField::new("id", 12, ScalarType::U32),
+                RecordField::new("normal", 16, ScalarType::F32)
+                    .vector(3)
+                    .with_tolerance(Tolerance::ulps(2)),
+            ],
+        )
+    }
+
+    fn particle(position: [f32; 3], id: u32, normal: [f32; 3], pad: u32) -> [u32; 8] {
+        [
+            position[0].to_bits(),
+            position[1].to_bits(),
+            position[2].to_bits(),
+            id,
+            normal[0].to_bits(),
+            normal[1].to_bits(),
+            normal[2].to_bits(),
+            pad,
+        ]
+    }
+
+    #[test]
+    fn test_record_differ_per_field_tolerance() {
+        let differ = RecordDiffer::new(particle_layout());
+        let y = 0.5f32;
+        let y_3_ulps = f32::from_bits(y.to_bits() + 3);
+        let data1 = [
+            particle([1.0, 2.0, 3.0], 7, [0.0, y, 1.0], 0),
+            particle([4.0, 5.0, 6.0], 8, [0.0, y, 1.0], 0),
+        ];
+        let data2 = [
+            // Position within tolerance, padding differs (and is ignored)
+            particle([1.0005, 2.0, 3.0], 7, [0.0, y, 1.0], 0xdead),
+            // `id` and `normal.y` differ
+            particle([4.0, 5.0, 6.0], 9, [0.0, y_3_ulps, 1.0], 0),
+        ];
+        let bytes1 = bytemuck::cast_slice(&data1);
+        let bytes2 = bytemuck::cast_slice(&data2);
+
+        let diffs = differ.compare(bytes1, bytes2, None);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].index, 32 + 12);
+        assert_eq!(diffs[0].value1, "8");
+        assert_eq!(diffs[0].value2, "9");
+        assert_eq!(diffs[1].index, 32 + 20);
+
+        let table = differ.format_table(&diffs, "foo", "bar");
+        assert!(table.contains("record[1].id"));
+        assert!(table.contains("record[1].normal.y"));
+
+        // Within 3 ULPs, `normal.y` matches too
+        let mut layout = particle_layout();
+        layout.fields[2].tolerance = Some(Tolerance::ulps(3));
+        let diffs = RecordDiffer::diff_records(&layout, bytes1, bytes2, None);
+        assert_eq!(diffs.len(), 1);
+    }
+
+    #[test]
+    fn test_record_differ_epsilon_fallback() {
+        let layout = RecordLayout::new(
+            8,
+            vec![
+                RecordField::new("value", 0, ScalarType::F32),
+                RecordField::new("count", 4, ScalarType::I32),
+            ],
+        );
+        let data1 = [1.0f32.to_bits(), 5];
+        let bytes1 = bytemuck::cast_slice(&data1);
+        let data2 = [1.01f32.to_bits(), 5];
+        let bytes2 = bytemuck::cast_slice(&data2);
+
+        // Fields without a tolerance use the epsilon, if any
+        assert_eq!(
+            RecordDiffer::diff_records(&layout, bytes1, bytes2, None).len(),
+            1
+        );
+        assert!(RecordDiffer::diff_records(&layout, bytes1, bytes2, Some(0.1)).is_empty());
+
+        // But integers still have to match exactly
+        let data3 = [1.0f32.to_bits(), (-5i32) as u32];
+        let bytes3 = bytemuck::cast_slice(&data3);
+        let diffs = RecordDiffer::diff_records(&layout, bytes1, bytes3, Some(100.0));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].value2, "-5");
+        match &diffs[0].absolute_diff {
+            DiffMagnitude::Numeric(val) => assert_eq!(*val, 10.0),
+            DiffMagnitude::Incomparable => panic!("Expected numeric difference"),
+        }
+    }
+
+    #[test]
+    fn test_record_differ_relative_tolerance() {
+        let layout = RecordLayout::new(
+            8,
+            vec![
+                RecordField::new("energy", 0, ScalarType::F64).with_tolerance(Tolerance::rel(1e-3)),
+            ],
+        );
+        let bytes1 = bytemuck::cast_slice(&[1000.0f64]);
+        let bytes2 = bytemuck::cast_slice(&[1000.5f64]);
+        let bytes3 = bytemuck::cast_slice(&[1002.0f64]);
+
+        assert!(RecordDiffer::diff_records(&layout, bytes1, bytes2, None).is_empty());
+        assert_eq!(
+            RecordDiffer::diff_records(&layout, bytes1, bytes3, None).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_record_differ_human_readable() {
+        let differ = RecordDiffer::new(particle_layout());
+        let data = [particle([1.0, 2.0, 3.0], 7, [0.0, 1.0, 0.0], 0)];
+        let file = tempfile::NamedTempFile::new().unwrap();
+        differ
+            .write_human_readable(bytemuck::cast_slice(&data), file.path())
+            .unwrap();
+        let text = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(
+            text,
+            "record[0]: position = (1.000000000, 2.000000000, 3.000000000), id = 7, \
+             normal = (0.000000000, 1.000000000, 0.000000000)\n"
+        );
+    }
+
+    #[test]
+    fn test_ulp_distance() {
+        let bits = |x: f32| u64::from(x.to_bits());
+        assert_eq!(ulp_distance(ScalarType::F32, bits(1.0), bits(1.0)), 0);
+        assert_eq!(ulp_distance(ScalarType::F32, bits(0.0), bits(-0.0)), 0);
+        assert_eq!(
+            ulp_distance(
+                ScalarType::F32,
+                bits(-f32::from_bits(1)),
+                bits(f32::from_bits(1))
+            ),
+            2
+        );
+        assert_eq!(
+            ulp_distance(
+                ScalarType::F64,
+                1.0f64.to_bits(),
+                1.0f64.next_up().to_bits()
+            ),
+            1
+        );
+    }
 }
@@ -1,9 +1,9 @@
 use bytesize::ByteSize;
-use difftest::config::{OutputType, TestMetadata};
+use difftest::config::{ComputeBackendKind, OutputType, TestMetadata};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
-    fs,
+    env, fs,
     io::Write,
     path::{Component, Path, PathBuf},
     process::Command,
@@ -12,7 +12,7 @@ use tempfile::NamedTempFile;
 use thiserror::Error;
 use tracing::{debug, error, info, trace};
 
-use crate::differ::{DiffMagnitude, Difference, DifferenceDisplay, OutputDiffer};
+use crate::differ::{DiffMagnitude, Difference, DifferenceDisplay, OutputDiffer, RecordDiffer};
 
 #[derive(Debug, Error)]
 pub enum RunnerError {
@@ -58,6 +58,7 @@ pub type RunnerResult<T> = std::result::Result<T, RunnerError>;
 pub struct HarnessConfig {
     pub output_path: PathBuf,
     pub metadata_path: PathBuf,
+    pub compute_backend: ComputeBackendKind,
 }
 
 #[derive(Deserialize)]
@@ -121,7 +122,12 @@ impl ErrorReport {
         &mut self,
         groups: &HashMap<Vec<u8>, Vec<&PackageOutput>>,
         pkg_outputs: &[PackageOutput],
+        human_readable_extension: &str,
     ) {
+        let label = match human_readable_extension {
+            "png" => "Image:",
+            _ => "Text:",
+        };
         if groups.len() <= 5 {
             for (output_bytes, group) in groups {
                 let names: Vec<&str> = group.iter().map(|po| po.pkg_name.as_str()).collect();
@@ -131,9 +137,9 @@ impl ErrorReport {
                     group[0].temp_path.display(),
                     ByteSize::b(output_bytes.len() as u64)
                 ));
-                let text_path = group[0].temp_path.with_extension("txt");
+                let text_path = group[0].temp_path.with_extension(human_readable_extension);
                 self.lines
-                    .push(format!("  → Text: {}", text_path.display()));
+                    .push(format!("  → {label} {}", text_path.display()));
                 self.lines.push("".to_string());
             }
         } else {
@@ -144,14 +150,19 @@ impl ErrorReport {
                     po.temp_path.display(),
                     ByteSize::b(po.output.len() as u64)
                 ));
-                let text_path = po.temp_path.with_extension("txt");
+                let text_path = po.temp_path.with_extension(human_readable_extension);
                 self.lines
-                    .push(format!("  → Text: {}", text_path.display()));
+                    .push(format!("  → {label} {}", text_path.display()));
                 self.lines.push("".to_string());
             }
         }
     }
 
+    fn add_diff_image(&mut self, path: &Path) {
+        self.lines.push(format!("→ Diff: {}", path.display()));
+        self.lines.push("".to_string());
+    }
+
     fn add_comparison_table(&mut self, table: String) {
         self.lines.push(table);
     }
@@ -223,6 +234,12 @@ impl Runner {
 
     pub fn run_test_case(&self, test_case: &Path) -> RunnerResult<()> {
         trace!("Starting test case: {}", test_case.display());
+        let compute_backend = match env::var("DIFFTEST_COMPUTE_BACKEND") {
+            Ok(backend) => backend
+                .parse()
+                .map_err(|e: anyhow::Error| RunnerError::Config { msg: e.to_string() })?,
+            Err(_) => ComputeBackendKind::default(),
+        };
         let packages = self.collect_packages(test_case)?;
         debug!(
             "Found {} package(s) in test case {}",
@@ -277,6 +294,7 @@ impl Runner {
             let config = HarnessConfig {
                 output_path: temp_output_path.clone(),
                 metadata_path: temp_metadata_path.clone(),
+                compute_backend,
             };
             let config_json = serde_json::to_string(&config)
                 .map_err(|e| RunnerError::Config { msg: e.to_string() })?;
@@ -352,9 +370,17 @@ impl Runner {
                                 );
                             }
 
+                            if let OutputType::Records(layout) = &metadata.output_type {
+                                layout.validate().map_err(|e| RunnerError::Config {
+                                    msg: format!(
+                                        "Invalid record layout for package '{pkg_name}': {e}"
+                                    ),
+                                })?;
+                            }
+
                             if output_type.is_none() {
                                 output_type = Some(metadata.output_type);
-                            } else if output_type != Some(metadata.output_type) {
+                            } else if output_type.as_ref() != Some(&metadata.output_type) {
                                 error!("Inconsistent output types across packages");
                                 return Err(RunnerError::Config {
                                     msg: format!(
@@ -406,14 +432,16 @@ impl Runner {
         }
 
         let output_type = output_type.unwrap_or_default();
-        let groups = self.group_outputs(&pkg_outputs, epsilon, output_type);
+        let groups = self.group_outputs(&pkg_outputs, epsilon, &output_type);
         if groups.len() > 1 {
-            let differ: Box<dyn OutputDiffer + Send + Sync> = output_type.into();
-            let display: Box<dyn DifferenceDisplay + Send + Sync> = output_type.into();
+            let differ: Box<dyn OutputDiffer + Send + Sync> = (&output_type).into();
+            let display: Box<dyn DifferenceDisplay + Send + Sync> = (&output_type).into();
 
             // Write human-readable outputs
             for po in &pkg_outputs {
-                let text_path = po.temp_path.with_extension("txt");
+                let text_path = po
+                    .temp_path
+                    .with_extension(display.human_readable_extension());
                 if let Err(e) = display.write_human_readable(&po.output, &text_path) {
                     debug!("Failed to write human-readable output: {}", e);
                 } else {
@@ -423,7 +451,7 @@ impl Runner {
 
             // Generate detailed error report
             let details =
-                self.format_error(&pkg_outputs, epsilon, output_type, &*differ, &*display);
+                self.format_error(&pkg_outputs, epsilon, &output_type, &*differ, &*display);
             self.keep_temp_files(&mut temp_files);
             return Err(RunnerError::DifferingOutput(details));
         }
@@ -453,12 +481,16 @@ impl Runner {
         &self,
         pkg_outputs: &'a [PackageOutput],
         epsilon: Option<f32>,
-        output_type: OutputType,
+        output_type: &OutputType,
     ) -> HashMap<Vec<u8>, Vec<&'a PackageOutput>> {
         let mut groups: HashMap<Vec<u8>, Vec<&'a PackageOutput>> = HashMap::new();
 
-        // If no epsilon specified or type is Raw with epsilon 0, use exact byte comparison
-        if epsilon.is_none() || (epsilon == Some(0.0) && output_type == OutputType::Raw) {
+        // If no epsilon specified (and there are no per-field tolerances) or type is Raw
+        // with epsilon 0, use exact byte comparison
+        let has_tolerances = matches!(output_type, OutputType::Records(_));
+        if (epsilon.is_none() && !has_tolerances)
+            || (epsilon == Some(0.0) && *output_type == OutputType::Raw)
+        {
             for po in pkg_outputs {
                 groups.entry(po.output.clone()).or_default().push(po);
             }
@@ -489,13 +521,13 @@ impl Runner {
         output1: &[u8],
         output2: &[u8],
         epsilon: Option<f32>,
-        output_type: OutputType,
+        output_type: &OutputType,
     ) -> bool {
         if output1.len() != output2.len() {
             return false;
         }
 
-        match output_type {
+        match *output_type {
             OutputType::Raw => output1 == output2,
             OutputType::F32 => {
                 if !output1.len().is_multiple_of(4) {
@@ -565,6 +597,30 @@ impl Runner {
                 // For integer types, epsilon doesn't make sense, so exact match
                 output1 == output2
             }
+            OutputType::Image { format, .. } => {
+                let bytes_per_pixel = format.bytes_per_pixel();
+                if !output1.len().is_multiple_of(bytes_per_pixel) {
+                    return false;
+                }
+
+                match epsilon {
+                    None => output1 == output2, // Exact comparison if no epsilon
+                    Some(eps) => output1
+                        .chunks_exact(bytes_per_pixel)
+                        .zip(output2.chunks_exact(bytes_per_pixel))
+                        .all(|(pixel1, pixel2)| {
+                            pixel1 == pixel2
+                                || format
+                                    .decode_pixel(pixel1)
+                                    .iter()
+                                    .zip(format.decode_pixel(pixel2))
+                                    .all(|(a, b)| (a - b).abs() <= eps)
+                        }),
+                }
+            }
+            OutputType::Records(ref layout) => {
+                RecordDiffer::diff_records(layout, output1, output2, epsilon).is_empty()
+            }
         }
     }
 
@@ -572,7 +628,7 @@ impl Runner {
         &self,
         pkg_outputs: &[PackageOutput],
         epsilon: Option<f32>,
-        output_type: OutputType,
+        output_type: &OutputType,
         differ: &dyn OutputDiffer,
         display: &dyn DifferenceDisplay,
     ) -> String {
@@ -605,7 +661,7 @@ impl Runner {
         }
 
         // Format output files
-        report.add_output_files(&groups, pkg_outputs);
+        report.add_output_files(&groups, pkg_outputs, display.human_readable_extension());
 
         // Add detailed comparison if applicable
         if groups.len() == 2 && pkg_outputs.len() == 2 {
@@ -627,6 +683,22 @@ impl Runner {
             report.add_summary_line(&differences);
         }
 
+        // Add a visualization of the differences, if the output type supports it
+        if groups.len() == 2 {
+            let (po1, po2) = if pkg_outputs.len() == 2 {
+                (&pkg_outputs[0], &pkg_outputs[1])
+            } else {
+                let group_vec: Vec<_> = groups.values().collect();
+                (group_vec[0][0], group_vec[1][0])
+            };
+            let diff_path = po1.temp_path.with_extension("diff.png");
+            match display.write_diff_image(&po1.output, &po2.output, epsilon, &diff_path) {
+                Ok(true) => report.add_diff_image(&diff_path),
+                Ok(false) => {}
+                Err(e) => debug!("Failed to write diff image: {}", e),
+            }
+        }
+
         report.build()
     }
 
@@ -736,7 +808,7 @@ pub fn forward_features(cmd: &mut Command) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use difftest::config::OutputType;
+    use difftest::config::{ImageFormat, OutputType};
     use std::{fs, io::Write, path::Path, path::PathBuf};
     use tempfile::{NamedTempFile, tempdir};
 
@@ -756,7 +828,7 @@ mod tests {
         let pkg3 = dummy_package_output("baz", "/path/to/baz", b"hello", "tmp3");
         let outputs = vec![pkg1, pkg2, pkg3];
         let runner = Runner::new(PathBuf::from("dummy_base"));
-        let groups = runner.group_outputs(&outputs, None, OutputType::Raw);
+        let groups = runner.group_outputs(&outputs, None, &OutputType::Raw);
         assert_eq!(groups.len(), 2);
     }
 
@@ -864,7 +936,7 @@ mod tests {
             b"hello",
             b"hello",
             None,
-            OutputType::Raw
+            &OutputType::Raw
         ));
 
         // Different content should not match
@@ -872,7 +944,7 @@ mod tests {
             b"hello",
             b"world",
             None,
-            OutputType::Raw
+            &OutputType::Raw
         ));
     }
 
@@ -891,7 +963,7 @@ mod tests {
             bytes1,
             bytes2,
             None,
-            OutputType::F32
+            &OutputType::F32
         ));
 
         // Should match with sufficient epsilon
@@ -899,7 +971,7 @@ mod tests {
             bytes1,
             bytes2,
             Some(0.0001),
-            OutputType::F32
+            &OutputType::F32
         ));
 
         // Should not match with too small epsilon
@@ -907,7 +979,7 @@ mod tests {
             bytes1,
             bytes2,
             Some(0.000001),
-            OutputType::F32
+            &OutputType::F32
         ));
     }
 
@@ -926,7 +998,7 @@ mod tests {
             bytes1,
             bytes2,
             None,
-            OutputType::F64
+            &OutputType::F64
         ));
 
         // Should match with sufficient epsilon
@@ -934,7 +1006,7 @@ mod tests {
             bytes1,
             bytes2,
             Some(0.0001),
-            OutputType::F64
+            &OutputType::F64
         ));
 
         // Should not match with too small epsilon
@@ -942,7 +1014,7 @@ mod tests {
             bytes1,
             bytes2,
             Some(0.000001),
-            OutputType::F64
+            &OutputType::F64
         ));
     }
 
@@ -965,11 +1037,11 @@ mod tests {
         let outputs = vec![pkg1, pkg2, pkg3];
 
         // Without epsilon, val1 and val2 should be in different groups
-        let groups = runner.group_outputs(&outputs, None, OutputType::F32);
+        let groups = runner.group_outputs(&outputs, None, &OutputType::F32);
         assert_eq!(groups.len(), 3);
 
         // With epsilon, val1 and val2 should be in the same group
-        let groups_with_epsilon = runner.group_outputs(&outputs, Some(0.0001), OutputType::F32);
+        let groups_with_epsilon = runner.group_outputs(&outputs, Some(0.0001), &OutputType::F32);
         assert_eq!(groups_with_epsilon.len(), 2);
     }
 
@@ -1032,4 +1104,60 @@ mod tests {
             _ => panic!("Wrong error type"),
         }
     }
+
+    #[test]
+    fn test_outputs_match_with_epsilon_image() {
+        let output_type = OutputType::Image {
+            width: 2,
+            height: 1,
+            format: ImageFormat::Rgba8Unorm,
+        };
+        let bytes1 = [0u8, 0, 0, 255, 100, 100, 100, 255];
+        let bytes2 = [0u8, 0, 0, 255, 101, 100, 100, 255];
+
+        // Should not match without epsilon
+        assert!(!Runner::outputs_match(&bytes1, &bytes2, None, &output_type));
+
+        // Should match with an epsilon larger than 1/255
+        assert!(Runner::outputs_match(
+            &bytes1,
+            &bytes2,
+            Some(0.005),
+            &output_type
+        ));
+
+        // Should not match with too small epsilon
+        assert!(!Runner::outputs_match(
+            &bytes1,
+            &bytes2,
+            Some(0.001),
+            &output_type
+        ));
+    }
+
+    #[test]
+    fn test_outputs_match_records() {
+        use difftest::config::{RecordField, RecordLayout, ScalarType, Tolerance};
+
+        let output_type = OutputType::Records(RecordLayout::new(
+            8,
+            vec![
+                RecordField::new("value", 0, ScalarType::F32).with_tolerance(Tolerance::abs(0.01)),
+            ],
+        ));
+        let data1 = [1.0f32.to_bits(), 0];
+        let bytes1 = bytemuck::cast_slice(&data1);
+        let data2 = [1.001f32.to_bits(), 1];
+        let bytes2 = bytemuck::cast_slice(&data2);
+
+        // Per-field tolerances apply (and padding is ignored) even without epsilon
+        assert!(Runner::outputs_match(bytes1, bytes2, None, &output_type));
+
+        let runner = Runner::new(PathBuf::from("dummy_base"));
+        let outputs = vec![
+            dummy_package_output("foo", "/path/to/foo", bytes1, "tmp1"),
+            dummy_package_output("bar", "/path/to/bar", bytes2, "tmp2"),
+        ];
+        assert_eq!(runner.group_outputs(&outputs, None, &output_type).len(), 1);
+    }
 }
@@ -7,6 +7,35 @@ use std::{fs, path::Path};
 pub struct Config {
     pub output_path: std::path::PathBuf,
     pub metadata_path: std::path::PathBuf,
+
+    /// Which backend compute tests should run on.
+    #[serde(default)]
+    pub compute_backend: ComputeBackendKind,
+}
+
+/// Selects the backend the compute test helpers (e.g. `WgpuComputeTest`) run on,
+/// which the harness takes from the `DIFFTEST_COMPUTE_BACKEND` environment variable.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ComputeBackendKind {
+    /// The backend each test was written for (e.g. `wgpu` or `ash`), on a real GPU
+    #[default]
+    Gpu,
+    /// The SPIR-V interpreter (`InterpreterBackend`), which doesn't need a GPU,
+    /// with tests of non-SPIR-V shaders (e.g. WGSL) being skipped
+    Interpreter,
+}
+
+impl std::str::FromStr for ComputeBackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "gpu" => Ok(Self::Gpu),
+            "interpreter" => Ok(Self::Interpreter),
+            _ => anyhow::bail!("unknown compute backend `{s}` (expected `gpu` or `interpreter`)"),
+        }
+    }
 }
 
 impl Config {
@@ -44,7 +73,7 @@ pub struct TestMetadata {
 }
 
 /// Specifies how test output data should be interpreted for comparison
-#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum OutputType {
     /// Exact byte-for-byte comparison (default)
@@ -58,6 +87,210 @@ pub enum OutputType {
     U32,
     /// Interpret as array of 32-bit signed integers
     I32,
+    /// Interpret as a framebuffer (e.g. written by `WgpuRenderTest`), with
+    /// tightly packed rows, enables per-pixel epsilon comparison
+    Image {
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+    },
+    /// Interpret as an array of records (i.e. structs) with the given layout,
+    /// enables per-field tolerances
+    Records(RecordLayout),
+}
+
+/// Layout of each record in `OutputType::Records` outputs
+///
+/// Bytes not covered by any field (e.g. padding) are ignored when comparing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecordLayout {
+    /// Size of each record in bytes (i.e. the array stride), including padding
+    pub size: u32,
+    pub fields: Vec<RecordField>,
+}
+
+/// A (scalar or vector) field of a record
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecordField {
+    pub name: String,
+    /// Offset of the field in bytes, from the start of the record
+    pub offset: u32,
+    #[serde(rename = "type")]
+    pub ty: ScalarType,
+    /// Number of components, with vector fields (`count` of 2 to 4) having them
+    /// named `x`, `y`, `z` and `w`, and larger ones indexed instead
+    #[serde(default = "RecordField::default_count")]
+    pub count: u32,
+    /// When `None`, the metadata's `epsilon` is used as an absolute tolerance
+    /// (for floats), or values have to match exactly if that isn't set either
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<Tolerance>,
+}
+
+/// Type of a record field's components
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalarType {
+    F32,
+    F64,
+    U32,
+    I32,
+    U64,
+    I64,
+}
+
+/// How much a value may differ, with a value matching if it's within any of
+/// the tolerances that are set (and only if it's identical when none are)
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Maximum absolute difference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abs: Option<f64>,
+    /// Maximum difference relative to the larger (in magnitude) of the two values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rel: Option<f64>,
+    /// Maximum distance in units in the last place (floats only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ulps: Option<u64>,
+}
+
+impl ScalarType {
+    pub fn size(self) -> u32 {
+        match self {
+            Self::F32 | Self::U32 | Self::I32 => 4,
+            Self::F64 | Self::U64 | Self::I64 => 8,
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+}
+
+impl RecordLayout {
+    pub fn new(size: u32, fields: Vec<RecordField>) -> Self {
+        Self { size, fields }
+    }
+
+    /// Check that the record size is non-zero, and that all fields fit in it.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.size == 0 {
+            anyhow::bail!("record size must be non-zero");
+        }
+        for field in &self.fields {
+            if field.count == 0 {
+                anyhow::bail!("field `{}` must have at least one component", field.name);
+            }
+            let end = u64::from(field.offset) + u64::from(field.count) * u64::from(field.ty.size());
+            if end > u64::from(self.size) {
+                anyhow::bail!(
+                    "field `{}` (bytes {}..{end}) doesn't fit in the {} byte record",
+                    field.name,
+                    field.offset,
+                    self.size
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RecordField {
+    pub fn new(name: impl Into<String>, offset: u32, ty: ScalarType) -> Self {
+        Self {
+            name: name.into(),
+            offset,
+            ty,
+            count: Self::default_count(),
+            tolerance: None,
+        }
+    }
+
+    fn default_count() -> u32 {
+        1
+    }
+
+    /// Make this a vector (or array) field with `count` components.
+    pub fn vector(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Name of the `i`-th component (e.g. `normal.y` or `weights[5]`).
+    pub fn component_name(&self, i: u32) -> String {
+        match self.count {
+            1 => self.name.clone(),
+            2..=4 => format!("{}.{}", self.name, ["x", "y", "z", "w"][i as usize]),
+            _ => format!("{}[{i}]", self.name),
+        }
+    }
+}
+
+impl Tolerance {
+    pub fn abs(abs: f64) -> Self {
+        Self {
+            abs: Some(abs),
+            ..Default::default()
+        }
+    }
+
+    pub fn rel(rel: f64) -> Self {
+        Self {
+            rel: Some(rel),
+            ..Default::default()
+        }
+    }
+
+    pub fn ulps(ulps: u64) -> Self {
+        Self {
+            ulps: Some(ulps),
+            ..Default::default()
+        }
+    }
+}
+
+/// Pixel format of `OutputType::Image` outputs (and render targets)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+    /// 8-bit normalized RGBA, compared as values in `0.0..=1.0`
+    Rgba8Unorm,
+    /// 32-bit float RGBA
+    Rgba32Float,
+    /// A single 32-bit float channel
+    R32Float,
+}
+
+impl ImageFormat {
+    pub fn channels(self) -> usize {
+        match self {
+            Self::Rgba8Unorm | Self::Rgba32Float => 4,
+            Self::R32Float => 1,
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba8Unorm | Self::R32Float => 4,
+            Self::Rgba32Float => 16,
+        }
+    }
+
+    /// Decode one pixel (of `bytes_per_pixel` bytes) into its channel values.
+    pub fn decode_pixel(self, bytes: &[u8]) -> Vec<f32> {
+        match self {
+            Self::Rgba8Unorm => bytes.iter().map(|&b| f32::from(b) / 255.0).collect(),
+            Self::Rgba32Float | Self::R32Float => bytes
+                .chunks_exact(4)
+                .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect(),
+        }
+    }
 }
 
 impl TestMetadata {
@@ -95,6 +328,27 @@ impl TestMetadata {
         }
     }
 
+    /// Create metadata for a `width`x`height` image, with an optional per-channel epsilon
+    pub fn image(width: u32, height: u32, format: ImageFormat, epsilon: Option<f32>) -> Self {
+        Self {
+            output_type: OutputType::Image {
+                width,
+                height,
+                format,
+            },
+            epsilon,
+            ..Default::default()
+        }
+    }
+
+    /// Create metadata for an array of records with the given layout
+    pub fn records(layout: RecordLayout) -> Self {
+        Self {
+            output_type: OutputType::Records(layout),
+            ..Default::default()
+        }
+    }
+
     /// Create metadata for raw hex values
     pub fn raw() -> Self {
         Self {
@@ -7,6 +7,8 @@ pub mod scaffold;
 
 #[cfg(not(target_arch = "spirv"))]
 pub use spirv_builder;
+#[cfg(not(target_arch = "spirv"))]
+pub use wgpu;
 
 /// Macro to round a f32 value for cross-platform compatibility in floating-point
 /// operations. This helps ensure difftest results are consistent across different
@@ -24,7 +26,7 @@ macro_rules! compat_round {
 
 #[cfg(test)]
 mod tests {
-    use super::config::Config;
+    use super::config::{ComputeBackendKind, Config};
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -37,5 +39,67 @@ mod tests {
         let config = Config::from_path(tmp.path()).unwrap();
         assert_eq!(config.output_path.to_str().unwrap(), "/tmp/output.txt");
         assert_eq!(config.metadata_path.to_str().unwrap(), "/tmp/metadata.json");
+        assert_eq!(config.compute_backend, ComputeBackendKind::Gpu);
+    }
+
+    #[test]
+    fn test_config_compute_backend() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let config_json = r#"{
+            "output_path": "/tmp/output.txt",
+            "metadata_path": "/tmp/metadata.json",
+            "compute_backend": "interpreter"
+        }"#;
+        write!(tmp, "{config_json}").unwrap();
+        let config = Config::from_path(tmp.path()).unwrap();
+        assert_eq!(config.compute_backend, ComputeBackendKind::Interpreter);
+        assert_eq!(
+            "interpreter".parse::<ComputeBackendKind>().unwrap(),
+            ComputeBackendKind::Interpreter
+        );
+        assert!("cpu".parse::<ComputeBackendKind>().is_err());
+    }
+
+    #[test]
+    fn test_record_layout_metadata() {
+        use super::config::{OutputType, ScalarType, TestMetadata, Tolerance};
+
+        let metadata_json = r#"{
+            "output_type": { "records": {
+                "size": 32,
+                "fields": [
+                    { "name": "position", "offset": 0, "type": "f32", "count": 3,
+                      "tolerance": { "abs": 1e-5, "ulps": 4 } },
+                    { "name": "id", "offset": 12, "type": "u32" }
+                ]
+            } }
+        }"#;
+        let metadata: TestMetadata = serde_json::from_str(metadata_json).unwrap();
+        let OutputType::Records(layout) = &metadata.output_type else {
+            panic!("expected records, found {:?}", metadata.output_type);
+        };
+        layout.validate().unwrap();
+        assert_eq!(layout.fields[0].ty, ScalarType::F32);
+        assert_eq!(layout.fields[0].component_name(1), "position.y");
+        assert_eq!(
+            layout.fields[0].tolerance,
+            Some(Tolerance {
+                abs: Some(1e-5),
+                rel: None,
+                ulps: Some(4),
+            })
+        );
+        assert_eq!(layout.fields[1].count, 1);
+        assert_eq!(layout.fields[1].tolerance, None);
+
+        // Round-trips through the metadata file format
+        let roundtrip: TestMetadata =
+            serde_json::from_str(&serde_json::to_string(&metadata).unwrap()).unwrap();
+        assert_eq!(roundtrip.output_type, metadata.output_type);
+
+        // Fields have to fit in the record
+        let mut layout = layout.clone();
+        layout.size = 12;
+        assert!(layout.validate().is_err());
     }
 }
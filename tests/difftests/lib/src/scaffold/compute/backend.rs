@@ -1,6 +1,8 @@
-use crate::config::Config;
+use super::interpreter::InterpreterBackend;
+use crate::config::{ComputeBackendKind, Config};
 use crate::scaffold::shader::SpirvShader;
 use anyhow::Result;
+use std::marker::PhantomData;
 
 /// Configuration for a GPU buffer
 #[derive(Clone)]
@@ -64,8 +66,11 @@ pub trait ComputeBackend: Sized {
 }
 
 /// A compute test that can run on any backend
+///
+/// The backend is only initialized when the test is run, and not at all if the
+/// interpreter is selected instead (see `ComputeBackendKind`).
 pub struct ComputeTest<B: ComputeBackend> {
-    backend: B,
+    backend: PhantomData<B>,
     spirv_bytes: Vec<u8>,
     entry_point: String,
     dispatch: [u32; 3],
@@ -80,7 +85,7 @@ impl<B: ComputeBackend> ComputeTest<B> {
         buffers: Vec<BufferConfig>,
     ) -> Result<Self> {
         Ok(Self {
-            backend: B::init()?,
+            backend: PhantomData,
             spirv_bytes,
             entry_point,
             dispatch,
@@ -89,7 +94,11 @@ impl<B: ComputeBackend> ComputeTest<B> {
     }
 
     pub fn run(self) -> Result<Vec<Vec<u8>>> {
-        self.backend.run_compute(
+        self.run_on(&B::init()?)
+    }
+
+    fn run_on(self, backend: &impl ComputeBackend) -> Result<Vec<Vec<u8>>> {
+        backend.run_compute(
             &self.spirv_bytes,
             &self.entry_point,
             self.dispatch,
@@ -99,7 +108,10 @@ impl<B: ComputeBackend> ComputeTest<B> {
 
     pub fn run_test(self, config: &Config) -> Result<()> {
         let buffers = self.buffers.clone();
-        let outputs = self.run()?;
+        let outputs = match config.compute_backend {
+            ComputeBackendKind::Gpu => self.run()?,
+            ComputeBackendKind::Interpreter => self.run_on(&InterpreterBackend::init()?)?,
+        };
         // Write the first storage buffer output to the file
         for (output, buffer_config) in outputs.iter().zip(&buffers) {
             if matches!(buffer_config.usage, BufferUsage::Storage) && !output.is_empty() {
@@ -112,8 +124,10 @@ impl<B: ComputeBackend> ComputeTest<B> {
 }
 
 /// A compute test that can run on any backend using a shader object
+///
+/// Like `ComputeTest`, the backend is only initialized when the test is run.
 pub struct ComputeShaderTest<B: ComputeBackend, S: SpirvShader> {
-    backend: B,
+    backend: PhantomData<B>,
     shader: S,
     dispatch: [u32; 3],
     buffers: Vec<BufferConfig>,
@@ -122,7 +136,7 @@ pub struct ComputeShaderTest<B: ComputeBackend, S: SpirvShader> {
 impl<B: ComputeBackend, S: SpirvShader> ComputeShaderTest<B, S> {
     pub fn new(shader: S, dispatch: [u32; 3], buffers: Vec<BufferConfig>) -> Result<Self> {
         Ok(Self {
-            backend: B::init()?,
+            backend: PhantomData,
             shader,
             dispatch,
             buffers,
@@ -130,13 +144,19 @@ impl<B: ComputeBackend, S: SpirvShader> ComputeShaderTest<B, S> {
     }
 
     pub fn run(self) -> Result<Vec<Vec<u8>>> {
-        self.backend
-            .run_compute_shader(&self.shader, self.dispatch, self.buffers)
+        self.run_on(&B::init()?)
+    }
+
+    fn run_on(self, backend: &impl ComputeBackend) -> Result<Vec<Vec<u8>>> {
+        backend.run_compute_shader(&self.shader, self.dispatch, self.buffers)
     }
 
     pub fn run_test(self, config: &Config) -> Result<()> {
         let buffers = self.buffers.clone();
-        let outputs = self.run()?;
+        let outputs = match config.compute_backend {
+            ComputeBackendKind::Gpu => self.run()?,
+            ComputeBackendKind::Interpreter => self.run_on(&InterpreterBackend::init()?)?,
+        };
         // Write the first storage buffer output to the file
         for (output, buffer_config) in outputs.iter().zip(&buffers) {
             if matches!(buffer_config.usage, BufferUsage::Storage) && !output.is_empty() {
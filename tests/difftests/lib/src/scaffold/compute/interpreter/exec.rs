@@ -0,0 +1,1115 @@
+//! Execution of a whole dispatch, one workgroup at a time, with the invocations
+//! of each workgroup interleaved according to a `Schedule`.
+
+use super::memory::{Memory, Pointer, Value};
+use super::module::{EntryPoint, Module, Type};
+use super::ops;
+use crate::scaffold::compute::backend::{BufferConfig, BufferUsage};
+use anyhow::{Context, Result, bail};
+use rspirv::dr::{Instruction, Operand};
+use rspirv::spirv::{BuiltIn, GLOp, GroupOperation, Op, Scope, StorageClass, Word};
+use std::collections::{BTreeMap, HashMap};
+
+/// Maximum number of instructions executed by a single dispatch, to turn
+/// infinite loops (e.g. spinning on a value no other invocation will write)
+/// into errors.
+const MAX_STEPS: u64 = 1 << 28;
+
+/// Order in which workgroups, and the invocations in each workgroup, are run.
+///
+/// Correct (i.e. data race free) shaders produce the same results for all
+/// schedules, so running the same dispatch with several schedules, and comparing
+/// the results, can find missing barriers (or atomics).
+#[derive(Copy, Clone, Debug)]
+pub(super) enum Schedule {
+    /// Workgroups (and invocations) in order, with each invocation running
+    /// until it reaches a barrier (or subgroup operation), or exits.
+    InOrder,
+
+    /// Like `InOrder`, but with workgroups (and invocations) in reverse order.
+    Reversed,
+
+    /// Workgroups in a random order, with invocations randomly interleaved,
+    /// a few instructions at a time (using the given seed).
+    Random(u64),
+}
+
+/// Small (`xorshift64*`) PRNG, to keep `Schedule::Random` deterministic.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum State {
+    Runnable,
+    /// Waiting at an `OpControlBarrier` with `Workgroup` execution scope.
+    WorkgroupBarrier,
+    /// Waiting at a subgroup operation (or `OpControlBarrier` with `Subgroup`
+    /// execution scope), until the rest of the subgroup is also blocked.
+    Subgroup,
+    Done,
+}
+
+struct Invocation {
+    local_index: u32,
+    /// Pointers to all the global variables accessible by this invocation.
+    vars: HashMap<Word, Value>,
+    frames: Vec<Frame>,
+    state: State,
+}
+
+struct Frame {
+    func: Word,
+    block: usize,
+    inst: usize,
+    values: HashMap<Word, Value>,
+    /// Result of the `OpFunctionCall` (in the caller) which pushed this frame.
+    call_result: Option<Word>,
+}
+
+pub(super) struct Dispatch<'a> {
+    module: &'a Module,
+    entry: &'a EntryPoint,
+    num_workgroups: [u32; 3],
+    subgroup_size: u32,
+    memory: Memory,
+    /// Memory regions of the buffers (in binding order), and their usage.
+    buffers: Vec<(usize, BufferUsage)>,
+    /// Pointers to the variables shared by all invocations (buffers and push constants).
+    shared_vars: HashMap<Word, Value>,
+    steps: u64,
+}
+
+impl<'a> Dispatch<'a> {
+    pub fn new(
+        module: &'a Module,
+        entry: &'a EntryPoint,
+        num_workgroups: [u32; 3],
+        subgroup_size: u32,
+        buffers: &[BufferConfig],
+        push_constants: &[u8],
+    ) -> Result<Self> {
+        let mut memory = Memory::default();
+        let buffers: Vec<_> = buffers
+            .iter()
+            .map(|config| {
+                let mut data = config.initial_data.clone().unwrap_or_default();
+                data.resize(config.size as usize, 0);
+                (memory.alloc(data), config.usage)
+            })
+            .collect();
+
+        let mut shared_vars = HashMap::new();
+        for var in &module.global_vars {
+            let ptr = match var.storage_class {
+                StorageClass::StorageBuffer | StorageClass::Uniform => {
+                    let (set, binding) = var
+                        .binding
+                        .with_context(|| format!("buffer %{} has no binding", var.id))?;
+                    match buffers.get(binding as usize) {
+                        Some(&(region, _)) if set == 0 => Pointer {
+                            region,
+                            offset: 0,
+                            pointee: var.pointee,
+                        },
+                        _ => {
+                            bail!("no buffer provided for descriptor set {set}, binding {binding}")
+                        }
+                    }
+                }
+                StorageClass::PushConstant => {
+                    let mut data = push_constants.to_vec();
+                    data.resize(data.len().max(module.size_of(var.pointee)?), 0);
+                    Pointer {
+                        region: memory.alloc(data),
+                        offset: 0,
+                        pointee: var.pointee,
+                    }
+                }
+                // Allocated for each workgroup/invocation.
+                StorageClass::Workgroup
+                | StorageClass::Private
+                | StorageClass::Input
+                | StorageClass::Output => continue,
+                StorageClass::UniformConstant => {
+                    bail!(
+                        "images, samplers and acceleration structures are not supported by the interpreter"
+                    )
+                }
+                storage_class => bail!("unsupported `{storage_class:?}` global variable"),
+            };
+            shared_vars.insert(var.id, Value::Pointer(ptr));
+        }
+
+        Ok(Self {
+            module,
+            entry,
+            num_workgroups,
+            subgroup_size,
+            memory,
+            buffers,
+            shared_vars,
+            steps: 0,
+        })
+    }
+
+    /// Run all the workgroups, returning the contents of every storage buffer
+    /// (and an empty `Vec` for every uniform buffer).
+    pub fn run(mut self, schedule: Schedule) -> Result<Vec<Vec<u8>>> {
+        let [x, y, z] = self.num_workgroups;
+        let mut workgroups: Vec<[u32; 3]> = (0..z)
+            .flat_map(|k| (0..y).flat_map(move |j| (0..x).map(move |i| [i, j, k])))
+            .collect();
+        let mut rng = None;
+        match schedule {
+            Schedule::InOrder => {}
+            Schedule::Reversed => workgroups.reverse(),
+            Schedule::Random(seed) => {
+                let rng = rng.insert(Rng::new(seed));
+                for i in (1..workgroups.len()).rev() {
+                    workgroups.swap(i, rng.below(i + 1));
+                }
+            }
+        }
+        for workgroup_id in workgroups {
+            self.run_workgroup(workgroup_id, schedule, rng.as_mut())
+                .with_context(|| format!("in workgroup {workgroup_id:?}"))?;
+        }
+
+        Ok(self
+            .buffers
+            .iter()
+            .map(|&(region, usage)| match usage {
+                BufferUsage::Storage | BufferUsage::StorageReadOnly => {
+                    self.memory.regions[region].clone()
+                }
+                BufferUsage::Uniform => vec![],
+            })
+            .collect())
+    }
+
+    fn run_workgroup(
+        &mut self,
+        workgroup_id: [u32; 3],
+        schedule: Schedule,
+        mut rng: Option<&mut Rng>,
+    ) -> Result<()> {
+        let module = self.module;
+        let [lx, ly, lz] = self.entry.local_size;
+
+        let mut workgroup_vars = self.shared_vars.clone();
+        for var in &module.global_vars {
+            if var.storage_class == StorageClass::Workgroup {
+                let ptr = self.alloc_global_var(var.pointee, var.initializer)?;
+                workgroup_vars.insert(var.id, Value::Pointer(ptr));
+            }
+        }
+
+        let mut invocations = vec![];
+        for local_index in 0..lx * ly * lz {
+            let mut vars = workgroup_vars.clone();
+            for var in &module.global_vars {
+                let ptr = match (var.storage_class, var.builtin) {
+                    (StorageClass::Input, Some(builtin)) => {
+                        let value =
+                            self.builtin_value(builtin, var.pointee, workgroup_id, local_index)?;
+                        self.memory.alloc_var(module, var.pointee, Some(&value))?
+                    }
+                    (StorageClass::Private | StorageClass::Output, _) => {
+                        self.alloc_global_var(var.pointee, var.initializer)?
+                    }
+                    // NOTE: non-built-in inputs can only be used by other
+                    // (non-compute) entry-points in the same module.
+                    _ => continue,
+                };
+                vars.insert(var.id, Value::Pointer(ptr));
+            }
+            invocations.push(Invocation {
+                local_index,
+                vars,
+                frames: vec![Frame {
+                    func: self.entry.func,
+                    block: 0,
+                    inst: 0,
+                    values: HashMap::new(),
+                    call_result: None,
+                }],
+                state: State::Runnable,
+            });
+        }
+
+        loop {
+            let runnable: Vec<usize> = (0..invocations.len())
+                .filter(|&i| invocations[i].state == State::Runnable)
+                .collect();
+            let (next, max_steps) = match (schedule, rng.as_deref_mut()) {
+                (Schedule::Random(_), Some(rng)) if !runnable.is_empty() => {
+                    (Some(runnable[rng.below(runnable.len())]), 1 + rng.below(16))
+                }
+                (Schedule::Reversed, _) => (runnable.last().copied(), usize::MAX),
+                _ => (runnable.first().copied(), usize::MAX),
+            };
+            if let Some(i) = next {
+                let invocation = &mut invocations[i];
+                for _ in 0..max_steps {
+                    if invocation.state != State::Runnable {
+                        break;
+                    }
+                    self.step(invocation)
+                        .with_context(|| format!("in invocation {}", invocation.local_index))?;
+                }
+                continue;
+            }
+
+            // Nothing can make progress without synchronizing invocations.
+            if invocations.iter().all(|inv| inv.state == State::Done) {
+                return Ok(());
+            }
+
+            // Subgroup operations are performed by all the invocations (of a
+            // subgroup) which reached them (i.e. the "active" invocations).
+            let mut subgroup_ops: BTreeMap<_, Vec<usize>> = BTreeMap::new();
+            for (i, inv) in invocations.iter().enumerate() {
+                if inv.state == State::Subgroup {
+                    let frame = inv.frames.last().unwrap();
+                    let key = (
+                        inv.local_index / self.subgroup_size,
+                        inv.frames.len(),
+                        frame.func,
+                        frame.block,
+                        frame.inst,
+                    );
+                    subgroup_ops.entry(key).or_default().push(i);
+                }
+            }
+            if !subgroup_ops.is_empty() {
+                for members in subgroup_ops.values() {
+                    self.subgroup_op(&mut invocations, members)?;
+                }
+                continue;
+            }
+
+            // Only workgroup barriers are left.
+            if invocations.iter().any(|inv| inv.state == State::Done) {
+                bail!(
+                    "some invocations exited without reaching the \
+                     `OpControlBarrier` the others are waiting on"
+                );
+            }
+            for inv in &mut invocations {
+                inv.state = State::Runnable;
+                inv.frames.last_mut().unwrap().inst += 1;
+            }
+        }
+    }
+
+    fn alloc_global_var(&mut self, ty: Word, initializer: Option<Word>) -> Result<Pointer> {
+        let init = initializer
+            .map(|id| {
+                self.module
+                    .constants
+                    .get(&id)
+                    .with_context(|| format!("non-constant initializer %{id}"))
+            })
+            .transpose()?;
+        self.memory.alloc_var(self.module, ty, init)
+    }
+
+    fn builtin_value(
+        &self,
+        builtin: BuiltIn,
+        ty: Word,
+        workgroup_id: [u32; 3],
+        local_index: u32,
+    ) -> Result<Value> {
+        let [lx, ly, lz] = self.entry.local_size;
+        let local_id = [
+            local_index % lx,
+            local_index / lx % ly,
+            local_index / (lx * ly),
+        ];
+        let workgroup_size = lx * ly * lz;
+        let lane = local_index % self.subgroup_size;
+        let mask = |f: fn(u32, u32) -> bool| -> Vec<u32> {
+            (0..4)
+                .map(|word| {
+                    (0..32)
+                        .filter(|bit| {
+                            let i = word * 32 + bit;
+                            i < self.subgroup_size && f(i, lane)
+                        })
+                        .fold(0, |mask, bit| mask | (1 << bit))
+                })
+                .collect()
+        };
+        let words = match builtin {
+            BuiltIn::GlobalInvocationId => (0..3)
+                .map(|i| workgroup_id[i] * self.entry.local_size[i] + local_id[i])
+                .collect(),
+            BuiltIn::LocalInvocationId => local_id.to_vec(),
+            BuiltIn::WorkgroupId => workgroup_id.to_vec(),
+            BuiltIn::NumWorkgroups => self.num_workgroups.to_vec(),
+            BuiltIn::WorkgroupSize => self.entry.local_size.to_vec(),
+            BuiltIn::LocalInvocationIndex => vec![local_index],
+            BuiltIn::SubgroupSize => vec![self.subgroup_size],
+            BuiltIn::SubgroupLocalInvocationId => vec![lane],
+            BuiltIn::SubgroupId => vec![local_index / self.subgroup_size],
+            BuiltIn::NumSubgroups => vec![workgroup_size.div_ceil(self.subgroup_size)],
+            BuiltIn::SubgroupEqMask => mask(|i, lane| i == lane),
+            BuiltIn::SubgroupGeMask => mask(|i, lane| i >= lane),
+            BuiltIn::SubgroupGtMask => mask(|i, lane| i > lane),
+            BuiltIn::SubgroupLeMask => mask(|i, lane| i <= lane),
+            BuiltIn::SubgroupLtMask => mask(|i, lane| i < lane),
+            _ => bail!("unsupported built-in `{builtin:?}`"),
+        };
+        Ok(match self.module.ty(ty)? {
+            Type::Vector { .. } => {
+                Value::Composite(words.into_iter().map(|w| Value::Scalar(w.into())).collect())
+            }
+            _ => Value::Scalar(words[0].into()),
+        })
+    }
+
+    fn current_inst(&self, inv: &Invocation) -> Result<&'a Instruction> {
+        let frame = inv.frames.last().unwrap();
+        self.module.functions[&frame.func].blocks[frame.block]
+            .insts
+            .get(frame.inst)
+            .context("block without a terminator")
+    }
+
+    fn value(&self, inv: &Invocation, id: Word) -> Result<Value> {
+        inv.frames
+            .last()
+            .unwrap()
+            .values
+            .get(&id)
+            .or_else(|| inv.vars.get(&id))
+            .or_else(|| self.module.constants.get(&id))
+            .cloned()
+            .with_context(|| format!("unknown value %{id}"))
+    }
+
+    /// Value of the `i`-th operand of `inst` (which must be an id).
+    fn operand(&self, inv: &Invocation, inst: &Instruction, i: usize) -> Result<Value> {
+        let id = inst.operands[i]
+            .id_ref_any()
+            .with_context(|| format!("expected id operand, found {}", inst.operands[i]))?;
+        self.value(inv, id)
+    }
+
+    fn operands(&self, inv: &Invocation, operands: &[Operand]) -> Result<Vec<Value>> {
+        operands
+            .iter()
+            .map(|operand| match *operand {
+                Operand::LiteralBit32(x) => Ok(Value::Scalar(x.into())),
+                Operand::LiteralBit64(x) => Ok(Value::Scalar(x)),
+                _ => self.value(
+                    inv,
+                    operand
+                        .id_ref_any()
+                        .with_context(|| format!("unsupported operand {operand}"))?,
+                ),
+            })
+            .collect()
+    }
+
+    fn scope(&self, inv: &Invocation, inst: &Instruction) -> Result<u32> {
+        Ok(self.operand(inv, inst, 0)?.bits() as u32)
+    }
+
+    /// Execute one instruction of `inv` (or block it, if it needs to synchronize
+    /// with other invocations first).
+    fn step(&mut self, inv: &mut Invocation) -> Result<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            bail!("exceeded the limit of {MAX_STEPS} executed instructions");
+        }
+
+        let module = self.module;
+        let inst = self.current_inst(inv)?;
+        let op = inst.class.opcode;
+        let result_ty = inst.result_type;
+        let operand = |i: usize| self.operand(inv, inst, i);
+        let literal = |i: usize| inst.operands[i].unwrap_literal_bit32();
+        let operand_scalar = || module.scalar(module.value_type(inst.operands[0].unwrap_id_ref())?);
+
+        let result = match op {
+            Op::Nop
+            | Op::Line
+            | Op::NoLine
+            | Op::SelectionMerge
+            | Op::LoopMerge
+            | Op::MemoryBarrier => None,
+
+            Op::Branch => return self.branch(inv, inst.operands[0].unwrap_id_ref()),
+            Op::BranchConditional => {
+                let target = if operand(0)?.bits() != 0 { 1 } else { 2 };
+                return self.branch(inv, inst.operands[target].unwrap_id_ref());
+            }
+            Op::Switch => {
+                let selector = operand(0)?.bits();
+                let mut target = inst.operands[1].unwrap_id_ref();
+                for case in inst.operands[2..].chunks(2) {
+                    let value = match case[0] {
+                        Operand::LiteralBit32(x) => x.into(),
+                        Operand::LiteralBit64(x) => x,
+                        ref operand => bail!("unsupported `OpSwitch` literal {operand}"),
+                    };
+                    // NOTE the selector is zero-extended, but literals narrower than
+                    // 32 bits aren't supported by rust-gpu anyway.
+                    if value == selector {
+                        target = case[1].unwrap_id_ref();
+                        break;
+                    }
+                }
+                return self.branch(inv, target);
+            }
+            Op::Return => return Self::ret(inv, None),
+            Op::ReturnValue => {
+                let value = operand(0)?;
+                return Self::ret(inv, Some(value));
+            }
+            Op::Kill | Op::TerminateInvocation => {
+                inv.state = State::Done;
+                return Ok(());
+            }
+            Op::Unreachable => bail!("reached `OpUnreachable`"),
+            Op::FunctionCall => {
+                let func = inst.operands[0].unwrap_id_ref();
+                let args = self.operands(inv, &inst.operands[1..])?;
+                let callee = module
+                    .functions
+                    .get(&func)
+                    .with_context(|| format!("unknown function %{func}"))?;
+                inv.frames.push(Frame {
+                    func,
+                    block: 0,
+                    inst: 0,
+                    values: callee.params.iter().copied().zip(args).collect(),
+                    call_result: inst.result_id,
+                });
+                return Ok(());
+            }
+
+            Op::ControlBarrier => {
+                inv.state = match self.scope(inv, inst)? {
+                    s if s == Scope::Workgroup as u32 => State::WorkgroupBarrier,
+                    s if s == Scope::Subgroup as u32 => State::Subgroup,
+                    s => bail!("unsupported `OpControlBarrier` execution scope {s}"),
+                };
+                return Ok(());
+            }
+            _ if is_subgroup_op(op) => {
+                if self.scope(inv, inst)? != Scope::Subgroup as u32 {
+                    bail!("`Op{op:?}` is only supported with `Subgroup` scope");
+                }
+                inv.state = State::Subgroup;
+                return Ok(());
+            }
+
+            Op::Variable => {
+                let ty = module.pointee_type(result_ty.unwrap())?;
+                let init = inst.operands.get(1).map(|_| operand(1)).transpose()?;
+                Some(Value::Pointer(self.memory.alloc_var(
+                    module,
+                    ty,
+                    init.as_ref(),
+                )?))
+            }
+            Op::Load | Op::AtomicLoad => Some(self.memory.load(module, operand(0)?.pointer()?)?),
+            Op::Store | Op::AtomicStore => {
+                let ptr = operand(0)?.pointer()?;
+                let value = operand(if op == Op::Store { 1 } else { 3 })?;
+                self.memory.store(module, ptr, &value)?;
+                None
+            }
+            Op::CopyMemory => {
+                let (dst, src) = (operand(0)?.pointer()?, operand(1)?.pointer()?);
+                let value = self.memory.load(module, src)?;
+                self.memory.store(module, dst, &value)?;
+                None
+            }
+            Op::AccessChain
+            | Op::InBoundsAccessChain
+            | Op::PtrAccessChain
+            | Op::InBoundsPtrAccessChain => {
+                let base = inst.operands[0].unwrap_id_ref();
+                let mut ptr = self.value(inv, base)?.pointer()?;
+                let mut indices = self.operands(inv, &inst.operands[1..])?.into_iter();
+                if matches!(op, Op::PtrAccessChain | Op::InBoundsPtrAccessChain) {
+                    let element = indices.next().unwrap().bits() as i64;
+                    let stride = module
+                        .array_stride(module.value_type(base)?)
+                        .or_else(|_| module.size_of(ptr.pointee))?;
+                    ptr.offset = ptr
+                        .offset
+                        .wrapping_add_signed(element as isize * stride as isize);
+                }
+                for index in indices {
+                    ptr = Memory::element_ptr(module, ptr, index.bits())?;
+                }
+                // The pointee type is taken from the result type, in case the base
+                // pointer had a different (but equivalent) pointee type.
+                ptr.pointee = module.pointee_type(result_ty.unwrap())?;
+                Some(Value::Pointer(ptr))
+            }
+            Op::ArrayLength => Some(Value::Scalar(self.memory.array_length(
+                module,
+                operand(0)?.pointer()?,
+                literal(1),
+            )?)),
+
+            Op::CompositeConstruct => {
+                let parts = self.operands(inv, &inst.operands)?;
+                let components = match module.ty(result_ty.unwrap())? {
+                    // Vectors can be constructed from smaller vectors.
+                    Type::Vector { .. } => parts
+                        .into_iter()
+                        .flat_map(|part| match part {
+                            Value::Composite(components) => components,
+                            scalar => vec![scalar],
+                        })
+                        .collect(),
+                    _ => parts,
+                };
+                Some(Value::Composite(components))
+            }
+            Op::CompositeExtract => {
+                let mut value = operand(0)?;
+                for i in 1..inst.operands.len() {
+                    value = value.components()[literal(i) as usize].clone();
+                }
+                Some(value)
+            }
+            Op::CompositeInsert => {
+                let object = operand(0)?;
+                let mut composite = operand(1)?;
+                let mut target = &mut composite;
+                for i in 2..inst.operands.len() {
+                    target = match target {
+                        Value::Composite(components) => &mut components[literal(i) as usize],
+                        _ => bail!("`OpCompositeInsert` into non-composite"),
+                    };
+                }
+                *target = object;
+                Some(composite)
+            }
+            Op::VectorShuffle => {
+                let (a, b) = (operand(0)?, operand(1)?);
+                let concat: Vec<_> = a.components().iter().chain(b.components()).collect();
+                Some(Value::Composite(
+                    (2..inst.operands.len())
+                        .map(|i| match literal(i) {
+                            0xffff_ffff => Value::Scalar(0),
+                            j => concat[j as usize].clone(),
+                        })
+                        .collect(),
+                ))
+            }
+            Op::VectorExtractDynamic => {
+                let (vector, index) = (operand(0)?, operand(1)?.bits());
+                Some(
+                    vector
+                        .components()
+                        .get(index as usize)
+                        .cloned()
+                        .with_context(|| format!("vector index {index} out of bounds"))?,
+                )
+            }
+            Op::VectorInsertDynamic => {
+                let (mut vector, component, index) = (operand(0)?, operand(1)?, operand(2)?);
+                match &mut vector {
+                    Value::Composite(components) if (index.bits() as usize) < components.len() => {
+                        components[index.bits() as usize] = component;
+                    }
+                    _ => bail!("vector index {} out of bounds", index.bits()),
+                }
+                Some(vector)
+            }
+            Op::CopyObject | Op::CopyLogical => Some(operand(0)?),
+            Op::Bitcast => Some(match operand(0)? {
+                Value::Pointer(ptr) => Value::Pointer(Pointer {
+                    pointee: module.pointee_type(result_ty.unwrap())?,
+                    ..ptr
+                }),
+                value => {
+                    let result_ty = result_ty.unwrap();
+                    let to_vector = matches!(module.ty(result_ty)?, Type::Vector { .. });
+                    ops::bitcast(
+                        value,
+                        operand_scalar()?,
+                        module.scalar(result_ty)?,
+                        to_vector,
+                    )
+                }
+            }),
+            Op::Undef => Some(module.zero_value(result_ty.unwrap())?),
+            Op::Select => {
+                let (cond, a, b) = (operand(0)?, operand(1)?, operand(2)?);
+                Some(match cond {
+                    Value::Scalar(cond) => {
+                        if cond != 0 {
+                            a
+                        } else {
+                            b
+                        }
+                    }
+                    cond => ops::componentwise(&[cond, a, b], &mut |args| {
+                        Ok(if args[0] != 0 { args[1] } else { args[2] })
+                    })?,
+                })
+            }
+            Op::Any | Op::All => {
+                let vector = operand(0)?;
+                let mut components = vector.components().iter().map(|c| c.bits() != 0);
+                let result = if op == Op::Any {
+                    components.any(|c| c)
+                } else {
+                    components.all(|c| c)
+                };
+                Some(Value::Scalar(result.into()))
+            }
+            Op::Dot
+            | Op::VectorTimesScalar
+            | Op::MatrixTimesScalar
+            | Op::VectorTimesMatrix
+            | Op::MatrixTimesVector
+            | Op::MatrixTimesMatrix
+            | Op::Transpose
+            | Op::OuterProduct => {
+                let args = self.operands(inv, &inst.operands)?;
+                Some(ops::linear_algebra_op(
+                    op,
+                    module.scalar(component_type(module, result_ty.unwrap())?)?,
+                    &args,
+                )?)
+            }
+            Op::IAddCarry | Op::ISubBorrow | Op::UMulExtended | Op::SMulExtended => {
+                let args = self.operands(inv, &inst.operands)?;
+                let ty = operand_scalar()?;
+                let half = |second: bool| {
+                    ops::componentwise(&args, &mut |args| {
+                        let (low, high) = ops::extended_op(op, ty, args[0], args[1])?;
+                        Ok(if second { high } else { low })
+                    })
+                };
+                Some(Value::Composite(vec![half(false)?, half(true)?]))
+            }
+
+            Op::ExtInst => {
+                let set = inst.operands[0].unwrap_id_ref();
+                let Operand::LiteralExtInstInteger(ext_op) = inst.operands[1] else {
+                    bail!("malformed `OpExtInst`");
+                };
+                match module.ext_inst_imports.get(&set).map(|s| &s[..]) {
+                    Some("GLSL.std.450") => {
+                        let glsl_op = GLOp::from_u32(ext_op).with_context(|| {
+                            format!("unknown `GLSL.std.450` instruction {ext_op}")
+                        })?;
+                        let args = self.operands(inv, &inst.operands[2..])?;
+                        let ty =
+                            module.scalar(module.value_type(inst.operands[2].unwrap_id_ref())?)?;
+                        let result = module.scalar(component_type(module, result_ty.unwrap())?)?;
+                        Some(ops::glsl_op(glsl_op, ty, result, &args)?)
+                    }
+                    Some(set) if set.starts_with("NonSemantic.") => None,
+                    set => bail!("unsupported extended instruction set {set:?}"),
+                }
+            }
+
+            Op::AtomicExchange
+            | Op::AtomicCompareExchange
+            | Op::AtomicIIncrement
+            | Op::AtomicIDecrement
+            | Op::AtomicIAdd
+            | Op::AtomicISub
+            | Op::AtomicSMin
+            | Op::AtomicUMin
+            | Op::AtomicSMax
+            | Op::AtomicUMax
+            | Op::AtomicAnd
+            | Op::AtomicOr
+            | Op::AtomicXor
+            | Op::AtomicFAddEXT
+            | Op::AtomicFMinEXT
+            | Op::AtomicFMaxEXT => {
+                // NOTE invocations don't run concurrently, so atomics are just
+                // loads followed by stores.
+                let ptr = operand(0)?.pointer()?;
+                let old = self.memory.load(module, ptr)?;
+                let ty = module.scalar(ptr.pointee)?;
+                let bits = old.bits();
+                let glsl = |glsl_op: GLOp| -> Result<u64> {
+                    let args = [old.clone(), operand(3)?];
+                    Ok(ops::glsl_op(glsl_op, ty, ty, &args)?.bits())
+                };
+                let scalar_op = |op: Op, value: u64| ops::scalar_op(op, ty, ty, &[bits, value]);
+                let new = match op {
+                    Op::AtomicExchange => operand(3)?.bits(),
+                    Op::AtomicCompareExchange => {
+                        let (value, comparator) = (operand(4)?.bits(), operand(5)?.bits());
+                        if bits == comparator { value } else { bits }
+                    }
+                    Op::AtomicIIncrement => scalar_op(Op::IAdd, 1)?,
+                    Op::AtomicIDecrement => scalar_op(Op::ISub, 1)?,
+                    Op::AtomicIAdd => scalar_op(Op::IAdd, operand(3)?.bits())?,
+                    Op::AtomicISub => scalar_op(Op::ISub, operand(3)?.bits())?,
+                    Op::AtomicAnd => scalar_op(Op::BitwiseAnd, operand(3)?.bits())?,
+                    Op::AtomicOr => scalar_op(Op::BitwiseOr, operand(3)?.bits())?,
+                    Op::AtomicXor => scalar_op(Op::BitwiseXor, operand(3)?.bits())?,
+                    Op::AtomicFAddEXT => scalar_op(Op::FAdd, operand(3)?.bits())?,
+                    Op::AtomicSMin => glsl(GLOp::SMin)?,
+                    Op::AtomicUMin => glsl(GLOp::UMin)?,
+                    Op::AtomicSMax => glsl(GLOp::SMax)?,
+                    Op::AtomicUMax => glsl(GLOp::UMax)?,
+                    Op::AtomicFMinEXT => glsl(GLOp::FMin)?,
+                    _ => glsl(GLOp::FMax)?,
+                };
+                self.memory.store(module, ptr, &Value::Scalar(new))?;
+                Some(old)
+            }
+
+            _ => {
+                let args = self.operands(inv, &inst.operands)?;
+                let ty = operand_scalar()?;
+                let result = module.scalar(result_ty.context("instruction without a result")?)?;
+                Some(ops::componentwise(&args, &mut |args| {
+                    ops::scalar_op(op, ty, result, args)
+                })?)
+            }
+        };
+
+        let frame = inv.frames.last_mut().unwrap();
+        if let (Some(id), Some(result)) = (inst.result_id, result) {
+            frame.values.insert(id, result);
+        }
+        frame.inst += 1;
+        Ok(())
+    }
+
+    fn branch(&self, inv: &mut Invocation, target: Word) -> Result<()> {
+        let frame = inv.frames.last().unwrap();
+        let func = &self.module.functions[&frame.func];
+        let from = func.blocks[frame.block].label;
+        let to = *func
+            .block_indices
+            .get(&target)
+            .with_context(|| format!("unknown block %{target}"))?;
+
+        // All the `OpPhi`s at the start of the target block are evaluated together.
+        let phis: Vec<_> = func.blocks[to]
+            .insts
+            .iter()
+            .take_while(|inst| inst.class.opcode == Op::Phi)
+            .map(|phi| {
+                let incoming = phi
+                    .operands
+                    .chunks(2)
+                    .find(|pair| pair[1].unwrap_id_ref() == from)
+                    .with_context(|| format!("`OpPhi` has no value for block %{from}"))?;
+                Ok((
+                    phi.result_id.unwrap(),
+                    self.value(inv, incoming[0].unwrap_id_ref())?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        let frame = inv.frames.last_mut().unwrap();
+        frame.block = to;
+        frame.inst = phis.len();
+        frame.values.extend(phis);
+        Ok(())
+    }
+
+    fn ret(inv: &mut Invocation, value: Option<Value>) -> Result<()> {
+        let frame = inv.frames.pop().unwrap();
+        match inv.frames.last_mut() {
+            Some(caller) => {
+                if let (Some(id), Some(value)) = (frame.call_result, value) {
+                    caller.values.insert(id, value);
+                }
+                caller.inst += 1;
+            }
+            None => inv.state = State::Done,
+        }
+        Ok(())
+    }
+
+    /// Perform the subgroup operation (or barrier) all of `members` are blocked on.
+    fn subgroup_op(&mut self, invocations: &mut [Invocation], members: &[usize]) -> Result<()> {
+        let module = self.module;
+        let inst = self.current_inst(&invocations[members[0]])?;
+        let op = inst.class.opcode;
+        let lanes: Vec<u32> = members
+            .iter()
+            .map(|&i| invocations[i].local_index % self.subgroup_size)
+            .collect();
+        let operand = |i: usize| -> Result<Vec<Value>> {
+            members
+                .iter()
+                .map(|&m| self.operand(&invocations[m], inst, i))
+                .collect()
+        };
+        // Value of the member with the given `lane` (falling back to `own`, which
+        // is as good as any value, if that lane is inactive).
+        let from_lane = |values: &[Value], lane: u64, own: usize| {
+            lanes
+                .iter()
+                .position(|&l| u64::from(l) == lane)
+                .map_or_else(|| values[own].clone(), |j| values[j].clone())
+        };
+        let scalar = |x: u64| Value::Scalar(x);
+        let ballot_mask = |value: &Value| -> u128 {
+            value
+                .components()
+                .iter()
+                .enumerate()
+                .fold(0, |mask, (i, word)| {
+                    mask | (u128::from(word.bits() as u32) << (i * 32))
+                })
+        };
+        let group_operation = |i: usize| match inst.operands[i] {
+            Operand::GroupOperation(group_op) => Ok(group_op),
+            ref operand => bail!("expected group operation, found {operand}"),
+        };
+        // Which lanes' values are combined, for the member in `lane`.
+        let group_filter = |group_op: GroupOperation, cluster_size: u32| {
+            move |lane: u32, other: u32| match group_op {
+                GroupOperation::Reduce => true,
+                GroupOperation::InclusiveScan => other <= lane,
+                GroupOperation::ExclusiveScan => other < lane,
+                GroupOperation::ClusteredReduce => other / cluster_size == lane / cluster_size,
+                _ => false,
+            }
+        };
+
+        let n = members.len();
+        let results: Option<Vec<Value>> = match op {
+            Op::ControlBarrier => None,
+            Op::GroupNonUniformElect => Some((0..n).map(|j| scalar((j == 0).into())).collect()),
+            Op::GroupNonUniformAll | Op::GroupNonUniformAny => {
+                let predicates = operand(1)?;
+                let mut predicates = predicates.iter().map(|p| p.bits() != 0);
+                let result = if op == Op::GroupNonUniformAll {
+                    predicates.all(|p| p)
+                } else {
+                    predicates.any(|p| p)
+                };
+                Some(vec![scalar(result.into()); n])
+            }
+            Op::GroupNonUniformAllEqual => {
+                let values = operand(1)?;
+                let result = values.iter().all(|v| *v == values[0]);
+                Some(vec![scalar(result.into()); n])
+            }
+            Op::GroupNonUniformBroadcast => {
+                let (values, id) = (operand(1)?, operand(2)?);
+                Some(
+                    (0..n)
+                        .map(|j| from_lane(&values, id[j].bits(), j))
+                        .collect(),
+                )
+            }
+            Op::GroupNonUniformBroadcastFirst => Some(vec![operand(1)?[0].clone(); n]),
+            Op::GroupNonUniformBallot => {
+                let predicates = operand(1)?;
+                let mask = lanes
+                    .iter()
+                    .zip(&predicates)
+                    .filter(|(_, p)| p.bits() != 0)
+                    .fold(0u128, |mask, (&lane, _)| mask | (1 << lane));
+                let words = (0..4).map(|i| scalar(((mask >> (i * 32)) as u32).into()));
+                Some(vec![Value::Composite(words.collect()); n])
+            }
+            Op::GroupNonUniformInverseBallot => {
+                let values = operand(1)?;
+                Some(
+                    (0..n)
+                        .map(|j| scalar(((ballot_mask(&values[j]) >> lanes[j]) & 1) as u64))
+                        .collect(),
+                )
+            }
+            Op::GroupNonUniformBallotBitExtract => {
+                let (values, index) = (operand(1)?, operand(2)?);
+                Some(
+                    (0..n)
+                        .map(|j| {
+                            scalar(
+                                ((ballot_mask(&values[j]) >> (index[j].bits() & 127)) & 1) as u64,
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            Op::GroupNonUniformBallotBitCount => {
+                let filter = group_filter(group_operation(1)?, 1);
+                let values = operand(2)?;
+                Some(
+                    (0..n)
+                        .map(|j| {
+                            let mask = ballot_mask(&values[j]);
+                            let count = (0..self.subgroup_size)
+                                .filter(|&bit| (mask >> bit) & 1 != 0 && filter(lanes[j], bit))
+                                .count();
+                            scalar(count as u64)
+                        })
+                        .collect(),
+                )
+            }
+            Op::GroupNonUniformBallotFindLSB | Op::GroupNonUniformBallotFindMSB => {
+                let values = operand(1)?;
+                Some(
+                    values
+                        .iter()
+                        .map(|value| {
+                            let mask = ballot_mask(value);
+                            let bit = if op == Op::GroupNonUniformBallotFindLSB {
+                                mask.trailing_zeros()
+                            } else {
+                                127u32.wrapping_sub(mask.leading_zeros())
+                            };
+                            scalar(bit.into())
+                        })
+                        .collect(),
+                )
+            }
+            Op::GroupNonUniformShuffle
+            | Op::GroupNonUniformShuffleXor
+            | Op::GroupNonUniformShuffleUp
+            | Op::GroupNonUniformShuffleDown => {
+                let (values, arg) = (operand(1)?, operand(2)?);
+                Some(
+                    (0..n)
+                        .map(|j| {
+                            let (lane, arg) = (u64::from(lanes[j]), arg[j].bits());
+                            let source = match op {
+                                Op::GroupNonUniformShuffle => arg,
+                                Op::GroupNonUniformShuffleXor => lane ^ arg,
+                                Op::GroupNonUniformShuffleUp => lane.wrapping_sub(arg),
+                                _ => lane + arg,
+                            };
+                            from_lane(&values, source, j)
+                        })
+                        .collect(),
+                )
+            }
+            Op::GroupNonUniformIAdd
+            | Op::GroupNonUniformFAdd
+            | Op::GroupNonUniformIMul
+            | Op::GroupNonUniformFMul
+            | Op::GroupNonUniformSMin
+            | Op::GroupNonUniformUMin
+            | Op::GroupNonUniformFMin
+            | Op::GroupNonUniformSMax
+            | Op::GroupNonUniformUMax
+            | Op::GroupNonUniformFMax
+            | Op::GroupNonUniformBitwiseAnd
+            | Op::GroupNonUniformBitwiseOr
+            | Op::GroupNonUniformBitwiseXor
+            | Op::GroupNonUniformLogicalAnd
+            | Op::GroupNonUniformLogicalOr
+            | Op::GroupNonUniformLogicalXor => {
+                let group_op = group_operation(1)?;
+                let cluster_size = match inst.operands.get(3) {
+                    Some(_) => operand(3)?[0].bits() as u32,
+                    None => 1,
+                };
+                let filter = group_filter(group_op, cluster_size);
+                let values = operand(2)?;
+                let ty = module.scalar(result_ty_of(inst)?)?;
+                let results = (0..n)
+                    .map(|j| {
+                        let identity = ops::componentwise(&values[j..=j], &mut |_| {
+                            ops::group_op_identity(op, ty)
+                        })?;
+                        (0..n).filter(|&k| filter(lanes[j], lanes[k])).try_fold(
+                            identity,
+                            |acc, k| {
+                                ops::componentwise(&[acc, values[k].clone()], &mut |args| {
+                                    ops::group_op(op, ty, args[0], args[1])
+                                })
+                            },
+                        )
+                    })
+                    .collect::<Result<_>>()?;
+                Some(results)
+            }
+            _ => bail!("unsupported subgroup operation `Op{op:?}`"),
+        };
+
+        for (j, &m) in members.iter().enumerate() {
+            let inv = &mut invocations[m];
+            let frame = inv.frames.last_mut().unwrap();
+            if let (Some(id), Some(results)) = (inst.result_id, &results) {
+                frame.values.insert(id, results[j].clone());
+            }
+            frame.inst += 1;
+            inv.state = State::Runnable;
+        }
+        Ok(())
+    }
+}
+
+fn result_ty_of(inst: &Instruction) -> Result<Word> {
+    inst.result_type
+        .with_context(|| format!("`Op{:?}` without a result type", inst.class.opcode))
+}
+
+/// The scalar (or vector) type of the components of `ty` (i.e. the column type,
+/// for matrices, and `ty` itself otherwise).
+fn component_type(module: &Module, ty: Word) -> Result<Word> {
+    Ok(match *module.ty(ty)? {
+        Type::Matrix { column, .. } => column,
+        _ => ty,
+    })
+}
+
+fn is_subgroup_op(op: Op) -> bool {
+    matches!(
+        op,
+        Op::GroupNonUniformElect
+            | Op::GroupNonUniformAll
+            | Op::GroupNonUniformAny
+            | Op::GroupNonUniformAllEqual
+            | Op::GroupNonUniformBroadcast
+            | Op::GroupNonUniformBroadcastFirst
+            | Op::GroupNonUniformBallot
+            | Op::GroupNonUniformInverseBallot
+            | Op::GroupNonUniformBallotBitExtract
+            | Op::GroupNonUniformBallotBitCount
+            | Op::GroupNonUniformBallotFindLSB
+            | Op::GroupNonUniformBallotFindMSB
+            | Op::GroupNonUniformShuffle
+            | Op::GroupNonUniformShuffleXor
+            | Op::GroupNonUniformShuffleUp
+            | Op::GroupNonUniformShuffleDown
+            | Op::GroupNonUniformIAdd
+            | Op::GroupNonUniformFAdd
+            | Op::GroupNonUniformIMul
+            | Op::GroupNonUniformFMul
+            | Op::GroupNonUniformSMin
+            | Op::GroupNonUniformUMin
+            | Op::GroupNonUniformFMin
+            | Op::GroupNonUniformSMax
+            | Op::GroupNonUniformUMax
+            | Op::GroupNonUniformFMax
+            | Op::GroupNonUniformBitwiseAnd
+            | Op::GroupNonUniformBitwiseOr
+            | Op::GroupNonUniformBitwiseXor
+            | Op::GroupNonUniformLogicalAnd
+            | Op::GroupNonUniformLogicalOr
+            | Op::GroupNonUniformLogicalXor
+    )
+}
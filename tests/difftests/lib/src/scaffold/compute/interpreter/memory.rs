@@ -0,0 +1,213 @@
+//! Values, and the (byte-addressed) memory they can be loaded from and stored to.
+
+use super::module::{Module, Type};
+use anyhow::{Context, Result, bail};
+use rspirv::spirv::Word;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Value {
+    /// Booleans (`0` or `1`), integers and floats, as their (zero-extended) bits.
+    Scalar(u64),
+
+    /// Vectors, matrices, arrays and structs.
+    Composite(Vec<Value>),
+
+    Pointer(Pointer),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(super) struct Pointer {
+    pub region: usize,
+    pub offset: usize,
+    /// Type of the value being pointed to.
+    pub pointee: Word,
+}
+
+impl Value {
+    pub fn bits(&self) -> u64 {
+        match *self {
+            Value::Scalar(bits) => bits,
+            _ => panic!("expected scalar, found {self:?}"),
+        }
+    }
+
+    pub fn components(&self) -> &[Value] {
+        match self {
+            Value::Composite(components) => components,
+            _ => panic!("expected composite, found {self:?}"),
+        }
+    }
+
+    pub fn pointer(&self) -> Result<Pointer> {
+        match *self {
+            Value::Pointer(ptr) => Ok(ptr),
+            _ => bail!("expected pointer, found {self:?}"),
+        }
+    }
+}
+
+/// All the memory accessible by a dispatch, as separate regions (one for each
+/// buffer, and then each variable of any other storage class).
+#[derive(Default)]
+pub(super) struct Memory {
+    pub regions: Vec<Vec<u8>>,
+}
+
+impl Memory {
+    pub fn alloc(&mut self, data: Vec<u8>) -> usize {
+        self.regions.push(data);
+        self.regions.len() - 1
+    }
+
+    /// Allocate a region for a variable of type `ty`, with an optional initializer.
+    pub fn alloc_var(
+        &mut self,
+        module: &Module,
+        ty: Word,
+        init: Option<&Value>,
+    ) -> Result<Pointer> {
+        let region = self.alloc(vec![0; module.size_of(ty)?]);
+        let ptr = Pointer {
+            region,
+            offset: 0,
+            pointee: ty,
+        };
+        if let Some(init) = init {
+            self.store(module, ptr, init)?;
+        }
+        Ok(ptr)
+    }
+
+    fn bytes(&self, ptr: Pointer, len: usize) -> Result<&[u8]> {
+        self.regions[ptr.region]
+            .get(ptr.offset..ptr.offset + len)
+            .with_context(|| format!("out of bounds load from {ptr:?}"))
+    }
+
+    fn bytes_mut(&mut self, ptr: Pointer, len: usize) -> Result<&mut [u8]> {
+        self.regions[ptr.region]
+            .get_mut(ptr.offset..ptr.offset + len)
+            .with_context(|| format!("out of bounds store to {ptr:?}"))
+    }
+
+    pub fn load(&self, module: &Module, ptr: Pointer) -> Result<Value> {
+        let at = |offset: usize, pointee: Word| Pointer {
+            region: ptr.region,
+            offset: ptr.offset + offset,
+            pointee,
+        };
+        Ok(match *module.ty(ptr.pointee)? {
+            Type::Bool => Value::Scalar((self.load_bits(ptr, 4)? != 0).into()),
+            Type::Int { width, .. } | Type::Float { width } => {
+                Value::Scalar(self.load_bits(ptr, width as usize / 8)?)
+            }
+            Type::Vector { elem, count } | Type::Array { elem, len: count } => {
+                let stride = module.array_stride(ptr.pointee)?;
+                Value::Composite(
+                    (0..count as usize)
+                        .map(|i| self.load(module, at(i * stride, elem)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Type::Matrix { column, count } => {
+                let stride = module.array_stride(ptr.pointee)?;
+                Value::Composite(
+                    (0..count as usize)
+                        .map(|i| self.load(module, at(i * stride, column)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Type::Struct { ref members } => Value::Composite(
+                members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &member)| {
+                        let offset = module.member_offset(ptr.pointee, i as u32)?;
+                        self.load(module, at(offset, member))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            ref ty => bail!("unsupported load of {ty:?}"),
+        })
+    }
+
+    pub fn store(&mut self, module: &Module, ptr: Pointer, value: &Value) -> Result<()> {
+        let at = |offset: usize, pointee: Word| Pointer {
+            region: ptr.region,
+            offset: ptr.offset + offset,
+            pointee,
+        };
+        match (module.ty(ptr.pointee)?, value) {
+            (Type::Bool, &Value::Scalar(bits)) => self.store_bits(ptr, 4, bits),
+            (&Type::Int { width, .. } | &Type::Float { width }, &Value::Scalar(bits)) => {
+                self.store_bits(ptr, width as usize / 8, bits)
+            }
+            (
+                &Type::Vector { elem, .. }
+                | &Type::Array { elem, .. }
+                | &Type::Matrix { column: elem, .. },
+                Value::Composite(components),
+            ) => {
+                let stride = module.array_stride(ptr.pointee)?;
+                for (i, component) in components.iter().enumerate() {
+                    self.store(module, at(i * stride, elem), component)?;
+                }
+                Ok(())
+            }
+            (Type::Struct { members }, Value::Composite(components)) => {
+                for (i, (&member, component)) in members.iter().zip(components).enumerate() {
+                    let offset = module.member_offset(ptr.pointee, i as u32)?;
+                    self.store(module, at(offset, member), component)?;
+                }
+                Ok(())
+            }
+            (ty, value) => bail!("unsupported store of {value:?} to {ty:?}"),
+        }
+    }
+
+    fn load_bits(&self, ptr: Pointer, len: usize) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes[..len].copy_from_slice(self.bytes(ptr, len)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn store_bits(&mut self, ptr: Pointer, len: usize, bits: u64) -> Result<()> {
+        self.bytes_mut(ptr, len)?
+            .copy_from_slice(&bits.to_le_bytes()[..len]);
+        Ok(())
+    }
+
+    /// Pointer to the `index`-th element (or member, for structs) of the value
+    /// pointed to by `ptr`.
+    pub fn element_ptr(module: &Module, ptr: Pointer, index: u64) -> Result<Pointer> {
+        let (offset, pointee) = match *module.ty(ptr.pointee)? {
+            Type::Struct { ref members } => (
+                module.member_offset(ptr.pointee, index as u32)?,
+                *members
+                    .get(index as usize)
+                    .with_context(|| format!("no member {index} in %{}", ptr.pointee))?,
+            ),
+            Type::Vector { elem, .. }
+            | Type::Array { elem, .. }
+            | Type::RuntimeArray { elem }
+            | Type::Matrix { column: elem, .. } => {
+                (index as usize * module.array_stride(ptr.pointee)?, elem)
+            }
+            ref ty => bail!("can't index into {ty:?}"),
+        };
+        Ok(Pointer {
+            region: ptr.region,
+            offset: ptr.offset + offset,
+            pointee,
+        })
+    }
+
+    /// Number of elements in the runtime array `member` of the struct pointed to by `ptr`.
+    pub fn array_length(&self, module: &Module, ptr: Pointer, member: u32) -> Result<u64> {
+        let array = Self::element_ptr(module, ptr, member.into())?;
+        let len = self.regions[array.region]
+            .len()
+            .saturating_sub(array.offset);
+        Ok((len / module.array_stride(array.pointee)?) as u64)
+    }
+}
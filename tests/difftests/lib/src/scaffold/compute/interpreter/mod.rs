@@ -0,0 +1,342 @@
+//! Pure-Rust SPIR-V interpreter, as a `ComputeBackend` which doesn't need a GPU
+//! (or any Vulkan driver), to run difftests on any machine.
+//!
+//! Only what's needed for compute shaders is supported, i.e. storage/uniform
+//! buffers, push constants, workgroup memory, barriers, atomics and subgroup
+//! operations (with a configurable subgroup size).
+//!
+//! Invocations don't actually run concurrently, but are interleaved according to
+//! a few different schedules (see `Schedule`), and any difference between their
+//! results is reported as an error, as it's likely caused by a data race.
+
+mod exec;
+mod memory;
+mod module;
+mod ops;
+
+use super::backend::{BufferConfig, ComputeBackend};
+use anyhow::{Result, bail, ensure};
+use exec::{Dispatch, Schedule};
+use module::Module;
+
+pub struct InterpreterBackend {
+    subgroup_size: u32,
+    schedules: u32,
+}
+
+impl Default for InterpreterBackend {
+    fn default() -> Self {
+        Self {
+            subgroup_size: 32,
+            schedules: 3,
+        }
+    }
+}
+
+impl InterpreterBackend {
+    /// Set the subgroup size, which must be a power of two between `1` and `128`
+    /// (defaults to `32`).
+    pub fn with_subgroup_size(mut self, subgroup_size: u32) -> Self {
+        assert!(
+            subgroup_size.is_power_of_two() && subgroup_size <= 128,
+            "unsupported subgroup size {subgroup_size}"
+        );
+        self.subgroup_size = subgroup_size;
+        self
+    }
+
+    /// Set the number of different schedules each dispatch is run with: the
+    /// first two run invocations in order, and in reverse order, and the rest
+    /// interleave them randomly (defaults to `3`).
+    pub fn with_schedules(mut self, schedules: u32) -> Self {
+        assert!(schedules >= 1, "at least one schedule is required");
+        self.schedules = schedules;
+        self
+    }
+
+    /// Like `ComputeBackend::run_compute`, but with push constants.
+    pub fn run_compute_with_push_constants(
+        &self,
+        spirv_bytes: &[u8],
+        entry_point: &str,
+        dispatch: [u32; 3],
+        buffers: Vec<BufferConfig>,
+        push_constants: &[u8],
+    ) -> Result<Vec<Vec<u8>>> {
+        let module = Module::load(spirv_bytes)?;
+        let entry = module.entry_point(entry_point)?;
+
+        let mut first_results: Option<(Schedule, Vec<Vec<u8>>)> = None;
+        for i in 0..self.schedules {
+            let schedule = match i {
+                0 => Schedule::InOrder,
+                1 => Schedule::Reversed,
+                _ => Schedule::Random(i.into()),
+            };
+            let results = Dispatch::new(
+                &module,
+                entry,
+                dispatch,
+                self.subgroup_size,
+                &buffers,
+                push_constants,
+            )?
+            .run(schedule)?;
+
+            let Some((first_schedule, first_results)) = &first_results else {
+                first_results = Some((schedule, results));
+                continue;
+            };
+            for (binding, (a, b)) in first_results.iter().zip(&results).enumerate() {
+                if let Some(offset) = a.iter().zip(b).position(|(a, b)| a != b) {
+                    bail!(
+                        "results differ between schedules ({first_schedule:?} vs {schedule:?}), \
+                         likely due to a data race: buffer {binding}, byte offset {offset} \
+                         ({:#04x} vs {:#04x})",
+                        a[offset],
+                        b[offset]
+                    );
+                }
+            }
+        }
+        Ok(first_results.unwrap().1)
+    }
+}
+
+impl ComputeBackend for InterpreterBackend {
+    fn init() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn run_compute(
+        &self,
+        spirv_bytes: &[u8],
+        entry_point: &str,
+        dispatch: [u32; 3],
+        buffers: Vec<BufferConfig>,
+    ) -> Result<Vec<Vec<u8>>> {
+        ensure!(
+            dispatch.iter().all(|&n| n > 0),
+            "dispatch size must be non-zero"
+        );
+        self.run_compute_with_push_constants(spirv_bytes, entry_point, dispatch, buffers, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rspirv::binary::Assemble;
+    use rspirv::dr::{Builder, Operand};
+    use rspirv::spirv::{
+        AddressingModel, BuiltIn, Capability, Decoration, ExecutionMode, ExecutionModel,
+        FunctionControl, GroupOperation, MemoryModel, MemorySemantics, Scope, StorageClass, Word,
+    };
+
+    const LOCAL_SIZE: u32 = 8;
+
+    /// Ids available to the entry-point body built by `compute_shader`.
+    struct Ids {
+        u32: Word,
+        local_id: Word,
+        global_id: Word,
+        output: Word,
+        shared: Word,
+    }
+
+    impl Ids {
+        fn constant(&self, b: &mut Builder, value: u32) -> Word {
+            b.constant_bit32(self.u32, value)
+        }
+
+        fn store_output(&self, b: &mut Builder, index: Word, value: Word) {
+            let ptr_ty = b.type_pointer(None, StorageClass::StorageBuffer, self.u32);
+            let zero = self.constant(b, 0);
+            let ptr = b
+                .access_chain(ptr_ty, None, self.output, [zero, index])
+                .unwrap();
+            b.store(ptr, value, None, []).unwrap();
+        }
+
+        fn shared_ptr(&self, b: &mut Builder, index: Word) -> Word {
+            let ptr_ty = b.type_pointer(None, StorageClass::Workgroup, self.u32);
+            b.access_chain(ptr_ty, None, self.shared, [index]).unwrap()
+        }
+    }
+
+    /// Build a compute shader (with `LOCAL_SIZE` invocations per workgroup), which
+    /// has a `u32` storage buffer at binding `0`, and a `[u32; LOCAL_SIZE]` array
+    /// in workgroup memory, with `body` emitting its (single block) entry-point.
+    fn compute_shader(body: impl FnOnce(&mut Builder, &Ids)) -> Vec<u8> {
+        let mut b = Builder::new();
+        b.set_version(1, 3);
+        b.capability(Capability::Shader);
+        b.capability(Capability::GroupNonUniformArithmetic);
+        b.memory_model(AddressingModel::Logical, MemoryModel::GLSL450);
+
+        let void = b.type_void();
+        let u32 = b.type_int(32, 0);
+        let uvec3 = b.type_vector(u32, 3);
+        let array = b.type_runtime_array(u32);
+        b.decorate(array, Decoration::ArrayStride, [Operand::LiteralBit32(4)]);
+        let buffer = b.type_struct([array]);
+        b.decorate(buffer, Decoration::Block, []);
+        b.member_decorate(buffer, 0, Decoration::Offset, [Operand::LiteralBit32(0)]);
+        let buffer_ptr = b.type_pointer(None, StorageClass::StorageBuffer, buffer);
+        let output = b.variable(buffer_ptr, None, StorageClass::StorageBuffer, None);
+        b.decorate(
+            output,
+            Decoration::DescriptorSet,
+            [Operand::LiteralBit32(0)],
+        );
+        b.decorate(output, Decoration::Binding, [Operand::LiteralBit32(0)]);
+
+        let input_ptr = b.type_pointer(None, StorageClass::Input, uvec3);
+        let mut builtins = vec![];
+        for builtin in [BuiltIn::LocalInvocationId, BuiltIn::GlobalInvocationId] {
+            let var = b.variable(input_ptr, None, StorageClass::Input, None);
+            b.decorate(var, Decoration::BuiltIn, [Operand::BuiltIn(builtin)]);
+            builtins.push(var);
+        }
+
+        let local_size = b.constant_bit32(u32, LOCAL_SIZE);
+        let shared_array = b.type_array(u32, local_size);
+        let shared_ptr = b.type_pointer(None, StorageClass::Workgroup, shared_array);
+        let shared = b.variable(shared_ptr, None, StorageClass::Workgroup, None);
+
+        let func_ty = b.type_function(void, []);
+        let main = b
+            .begin_function(void, None, FunctionControl::NONE, func_ty)
+            .unwrap();
+        b.begin_block(None).unwrap();
+        let [local_id, global_id] = [builtins[0], builtins[1]].map(|var| {
+            let id = b.load(uvec3, None, var, None, []).unwrap();
+            b.composite_extract(u32, None, id, [0]).unwrap()
+        });
+        body(
+            &mut b,
+            &Ids {
+                u32,
+                local_id,
+                global_id,
+                output,
+                shared,
+            },
+        );
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        b.entry_point(
+            ExecutionModel::GLCompute,
+            main,
+            "main",
+            [output, builtins[0], builtins[1], shared],
+        );
+        b.execution_mode(main, ExecutionMode::LocalSize, [LOCAL_SIZE, 1, 1]);
+        bytemuck::cast_slice(&b.module().assemble()).to_vec()
+    }
+
+    fn run(backend: &InterpreterBackend, spirv: &[u8], workgroups: u32) -> Result<Vec<u32>> {
+        let len = (workgroups * LOCAL_SIZE) as usize;
+        let outputs = backend.run_compute(
+            spirv,
+            "main",
+            [workgroups, 1, 1],
+            vec![BufferConfig::writeback(len * 4)],
+        )?;
+        Ok(bytemuck::cast_slice(&outputs[0]).to_vec())
+    }
+
+    #[test]
+    fn global_invocation_id() {
+        let spirv = compute_shader(|b, ids| {
+            let three = ids.constant(b, 3);
+            let value = b.i_mul(ids.u32, None, ids.global_id, three).unwrap();
+            ids.store_output(b, ids.global_id, value);
+        });
+        let output = run(&InterpreterBackend::default(), &spirv, 3).unwrap();
+        assert_eq!(output, (0..24).map(|i| i * 3).collect::<Vec<_>>());
+    }
+
+    /// Each invocation writes its global id to workgroup memory, and then reads
+    /// the value written by the next invocation (which needs a barrier in between).
+    fn rotate_through_workgroup_memory(barrier: bool) -> Vec<u8> {
+        compute_shader(|b, ids| {
+            let ptr = ids.shared_ptr(b, ids.local_id);
+            b.store(ptr, ids.global_id, None, []).unwrap();
+            if barrier {
+                let workgroup = ids.constant(b, Scope::Workgroup as u32);
+                let semantics = ids.constant(
+                    b,
+                    (MemorySemantics::ACQUIRE_RELEASE | MemorySemantics::WORKGROUP_MEMORY).bits(),
+                );
+                b.control_barrier(workgroup, workgroup, semantics).unwrap();
+            }
+            let one = ids.constant(b, 1);
+            let local_size = ids.constant(b, LOCAL_SIZE);
+            let next = b.i_add(ids.u32, None, ids.local_id, one).unwrap();
+            let next = b.u_mod(ids.u32, None, next, local_size).unwrap();
+            let ptr = ids.shared_ptr(b, next);
+            let value = b.load(ids.u32, None, ptr, None, []).unwrap();
+            ids.store_output(b, ids.global_id, value);
+        })
+    }
+
+    #[test]
+    fn workgroup_barrier() {
+        let backend = InterpreterBackend::default().with_schedules(5);
+        let output = run(&backend, &rotate_through_workgroup_memory(true), 2).unwrap();
+        let expected: Vec<u32> = (0..2 * LOCAL_SIZE)
+            .map(|i| i - i % LOCAL_SIZE + (i + 1) % LOCAL_SIZE)
+            .collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn data_race() {
+        let err = run(
+            &InterpreterBackend::default(),
+            &rotate_through_workgroup_memory(false),
+            1,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("data race"), "{err}");
+    }
+
+    #[test]
+    fn subgroup_arithmetic() {
+        for (group_op, expected) in [
+            (GroupOperation::Reduce, [6, 6, 6, 6, 22, 22, 22, 22]),
+            (GroupOperation::InclusiveScan, [0, 1, 3, 6, 4, 9, 15, 22]),
+            (GroupOperation::ExclusiveScan, [0, 0, 1, 3, 0, 4, 9, 15]),
+        ] {
+            let spirv = compute_shader(|b, ids| {
+                let subgroup = ids.constant(b, Scope::Subgroup as u32);
+                let value = b
+                    .group_non_uniform_i_add(ids.u32, None, subgroup, group_op, ids.local_id, None)
+                    .unwrap();
+                ids.store_output(b, ids.global_id, value);
+            });
+            let backend = InterpreterBackend::default().with_subgroup_size(4);
+            assert_eq!(run(&backend, &spirv, 1).unwrap(), expected, "{group_op:?}");
+        }
+    }
+
+    #[test]
+    fn atomics() {
+        let spirv = compute_shader(|b, ids| {
+            let zero = ids.constant(b, 0);
+            let ptr_ty = b.type_pointer(None, StorageClass::StorageBuffer, ids.u32);
+            let counter = b
+                .access_chain(ptr_ty, None, ids.output, [zero, zero])
+                .unwrap();
+            let device = ids.constant(b, Scope::Device as u32);
+            let relaxed = ids.constant(b, 0);
+            let one = ids.constant(b, 1);
+            b.atomic_i_add(ids.u32, None, counter, device, relaxed, one)
+                .unwrap();
+        });
+        let output = run(&InterpreterBackend::default(), &spirv, 3).unwrap();
+        assert_eq!(output[0], 3 * LOCAL_SIZE);
+    }
+}
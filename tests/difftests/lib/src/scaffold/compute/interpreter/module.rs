@@ -0,0 +1,474 @@
+//! Loading of SPIR-V modules into the (mostly pre-indexed) form used by the interpreter.
+
+use super::memory::Value;
+use anyhow::{Context, Result, bail};
+use rspirv::dr::{self, Instruction, Operand};
+use rspirv::spirv::{BuiltIn, Decoration, ExecutionMode, ExecutionModel, Op, StorageClass, Word};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub(super) enum Type {
+    Void,
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { elem: Word, count: u32 },
+    Matrix { column: Word, count: u32 },
+    Array { elem: Word, len: u32 },
+    RuntimeArray { elem: Word },
+    Struct { members: Vec<Word> },
+    Pointer { pointee: Word },
+    Function,
+}
+
+/// Scalar type (of a scalar, or the components of a vector), which determines
+/// how the bits of `Value::Scalar`s are interpreted.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(super) enum Scalar {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+}
+
+pub(super) struct GlobalVar {
+    pub id: Word,
+    pub storage_class: StorageClass,
+    pub pointee: Word,
+    pub builtin: Option<BuiltIn>,
+    pub binding: Option<(u32, u32)>,
+    pub initializer: Option<Word>,
+}
+
+pub(super) struct Block {
+    pub label: Word,
+    pub insts: Vec<Instruction>,
+}
+
+pub(super) struct Function {
+    pub params: Vec<Word>,
+    pub blocks: Vec<Block>,
+    pub block_indices: HashMap<Word, usize>,
+}
+
+pub(super) struct EntryPoint {
+    pub func: Word,
+    pub local_size: [u32; 3],
+}
+
+pub(super) struct Module {
+    pub types: HashMap<Word, Type>,
+    pub constants: HashMap<Word, Value>,
+    pub global_vars: Vec<GlobalVar>,
+    pub functions: HashMap<Word, Function>,
+    /// Result type of every instruction which has one.
+    pub value_types: HashMap<Word, Word>,
+    pub ext_inst_imports: HashMap<Word, String>,
+
+    member_offsets: HashMap<(Word, u32), u32>,
+    array_strides: HashMap<Word, u32>,
+    entry_points: HashMap<String, EntryPoint>,
+}
+
+impl Module {
+    pub fn load(spirv_bytes: &[u8]) -> Result<Self> {
+        if !spirv_bytes.len().is_multiple_of(4) {
+            bail!("SPIR-V binary length is not a multiple of 4");
+        }
+        let module = rspirv::dr::load_bytes(spirv_bytes)
+            .map_err(|e| anyhow::anyhow!("failed to parse SPIR-V: {e}"))?;
+
+        let mut this = Self {
+            types: HashMap::new(),
+            constants: HashMap::new(),
+            global_vars: vec![],
+            functions: HashMap::new(),
+            value_types: HashMap::new(),
+            ext_inst_imports: HashMap::new(),
+            member_offsets: HashMap::new(),
+            array_strides: HashMap::new(),
+            entry_points: HashMap::new(),
+        };
+
+        for inst in module.all_inst_iter() {
+            if let (Some(id), Some(ty)) = (inst.result_id, inst.result_type) {
+                this.value_types.insert(id, ty);
+            }
+        }
+        for inst in &module.ext_inst_imports {
+            this.ext_inst_imports.insert(
+                inst.result_id.unwrap(),
+                inst.operands[0].unwrap_literal_string().to_string(),
+            );
+        }
+
+        let mut builtins = HashMap::new();
+        let mut descriptor_sets = HashMap::new();
+        let mut bindings = HashMap::new();
+        for inst in &module.annotations {
+            match (inst.class.opcode, &inst.operands[..]) {
+                (
+                    Op::Decorate,
+                    &[
+                        Operand::IdRef(target),
+                        Operand::Decoration(decoration),
+                        ref args @ ..,
+                    ],
+                ) => match (decoration, args) {
+                    (Decoration::BuiltIn, &[Operand::BuiltIn(builtin)]) => {
+                        builtins.insert(target, builtin);
+                    }
+                    (Decoration::DescriptorSet, &[Operand::LiteralBit32(set)]) => {
+                        descriptor_sets.insert(target, set);
+                    }
+                    (Decoration::Binding, &[Operand::LiteralBit32(binding)]) => {
+                        bindings.insert(target, binding);
+                    }
+                    (Decoration::ArrayStride, &[Operand::LiteralBit32(stride)]) => {
+                        this.array_strides.insert(target, stride);
+                    }
+                    _ => {}
+                },
+                (
+                    Op::MemberDecorate,
+                    &[
+                        Operand::IdRef(target),
+                        Operand::LiteralBit32(member),
+                        Operand::Decoration(Decoration::Offset),
+                        Operand::LiteralBit32(offset),
+                    ],
+                ) => {
+                    this.member_offsets.insert((target, member), offset);
+                }
+                _ => {}
+            }
+        }
+
+        for inst in &module.types_global_values {
+            this.load_global(inst, &builtins, &descriptor_sets, &bindings)?;
+        }
+
+        for func in &module.functions {
+            let id = func.def_id().context("function without an id")?;
+            this.functions.insert(id, Self::load_function(func)?);
+        }
+
+        for inst in &module.entry_points {
+            let (model, func, name) = match &inst.operands[..] {
+                [
+                    Operand::ExecutionModel(model),
+                    Operand::IdRef(func),
+                    Operand::LiteralString(name),
+                    ..,
+                ] => (*model, *func, name.clone()),
+                _ => bail!("malformed `OpEntryPoint`"),
+            };
+            if model != ExecutionModel::GLCompute {
+                continue;
+            }
+            let mut local_size = None;
+            for mode in &module.execution_modes {
+                let (target, args) = match &mode.operands[..] {
+                    [
+                        Operand::IdRef(target),
+                        Operand::ExecutionMode(mode),
+                        args @ ..,
+                    ] if *target == func => (*mode, args),
+                    _ => continue,
+                };
+                let args: Vec<u32> = match target {
+                    ExecutionMode::LocalSize => {
+                        args.iter().map(|arg| arg.unwrap_literal_bit32()).collect()
+                    }
+                    ExecutionMode::LocalSizeId => args
+                        .iter()
+                        .map(|arg| this.const_u32(arg.unwrap_id_ref()))
+                        .collect::<Result<_>>()?,
+                    _ => continue,
+                };
+                local_size = Some([args[0], args[1], args[2]]);
+            }
+            // A `WorkgroupSize` built-in constant overrides the execution mode.
+            for (&id, &builtin) in &builtins {
+                if builtin == BuiltIn::WorkgroupSize
+                    && let Some(Value::Composite(size)) = this.constants.get(&id)
+                {
+                    let size = size.iter().map(|c| c.bits() as u32).collect::<Vec<_>>();
+                    local_size = Some([size[0], size[1], size[2]]);
+                }
+            }
+            this.entry_points.insert(
+                name.clone(),
+                EntryPoint {
+                    func,
+                    local_size: local_size
+                        .with_context(|| format!("entry-point `{name}` has no workgroup size"))?,
+                },
+            );
+        }
+
+        Ok(this)
+    }
+
+    fn load_global(
+        &mut self,
+        inst: &Instruction,
+        builtins: &HashMap<Word, BuiltIn>,
+        descriptor_sets: &HashMap<Word, u32>,
+        bindings: &HashMap<Word, u32>,
+    ) -> Result<()> {
+        let id = match inst.result_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let id_operand = |i: usize| inst.operands[i].unwrap_id_ref();
+        let ty = match inst.class.opcode {
+            Op::TypeVoid => Type::Void,
+            Op::TypeBool => Type::Bool,
+            Op::TypeInt => Type::Int {
+                width: inst.operands[0].unwrap_literal_bit32(),
+                signed: inst.operands[1].unwrap_literal_bit32() != 0,
+            },
+            Op::TypeFloat => Type::Float {
+                width: inst.operands[0].unwrap_literal_bit32(),
+            },
+            Op::TypeVector => Type::Vector {
+                elem: id_operand(0),
+                count: inst.operands[1].unwrap_literal_bit32(),
+            },
+            Op::TypeMatrix => Type::Matrix {
+                column: id_operand(0),
+                count: inst.operands[1].unwrap_literal_bit32(),
+            },
+            Op::TypeArray => Type::Array {
+                elem: id_operand(0),
+                len: self.const_u32(id_operand(1))?,
+            },
+            Op::TypeRuntimeArray => Type::RuntimeArray {
+                elem: id_operand(0),
+            },
+            Op::TypeStruct => Type::Struct {
+                members: (0..inst.operands.len()).map(id_operand).collect(),
+            },
+            Op::TypePointer | Op::TypeForwardPointer => Type::Pointer {
+                pointee: id_operand(1),
+            },
+            Op::TypeFunction => Type::Function,
+            // Only used by unsupported (i.e. image, sampler and ray tracing) variables.
+            Op::TypeImage
+            | Op::TypeSampler
+            | Op::TypeSampledImage
+            | Op::TypeAccelerationStructureKHR
+            | Op::TypeRayQueryKHR => return Ok(()),
+
+            Op::Variable => {
+                self.global_vars.push(GlobalVar {
+                    id,
+                    storage_class: inst.operands[0].unwrap_storage_class(),
+                    pointee: self.pointee_type(inst.result_type.unwrap())?,
+                    builtin: builtins.get(&id).copied(),
+                    binding: descriptor_sets
+                        .get(&id)
+                        .zip(bindings.get(&id))
+                        .map(|(&set, &binding)| (set, binding)),
+                    initializer: inst.operands.get(1).map(|init| init.unwrap_id_ref()),
+                });
+                return Ok(());
+            }
+
+            _ => {
+                let value = self.load_constant(inst)?;
+                self.constants.insert(id, value);
+                return Ok(());
+            }
+        };
+        self.types.insert(id, ty);
+        Ok(())
+    }
+
+    fn load_constant(&self, inst: &Instruction) -> Result<Value> {
+        let ty = inst.result_type.context("constant without a type")?;
+        Ok(match inst.class.opcode {
+            Op::Constant | Op::SpecConstant => match inst.operands[0] {
+                Operand::LiteralBit32(x) => Value::Scalar(x.into()),
+                Operand::LiteralBit64(x) => Value::Scalar(x),
+                ref operand => bail!("unsupported constant operand {operand:?}"),
+            },
+            Op::ConstantTrue | Op::SpecConstantTrue => Value::Scalar(1),
+            Op::ConstantFalse | Op::SpecConstantFalse => Value::Scalar(0),
+            Op::ConstantComposite | Op::SpecConstantComposite => Value::Composite(
+                inst.operands
+                    .iter()
+                    .map(|operand| {
+                        let id = operand.unwrap_id_ref();
+                        self.constants
+                            .get(&id)
+                            .cloned()
+                            .with_context(|| format!("unknown constant %{id}"))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            // NOTE undefined values are deterministically zero, like `OpConstantNull`.
+            Op::ConstantNull | Op::Undef => self.zero_value(ty)?,
+            op => bail!("unsupported global instruction `Op{op:?}`"),
+        })
+    }
+
+    fn load_function(func: &dr::Function) -> Result<Function> {
+        let blocks: Vec<Block> = func
+            .blocks
+            .iter()
+            .map(|block| {
+                Ok(Block {
+                    label: block.label_id().context("block without a label")?,
+                    insts: block.instructions.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Function {
+            params: func
+                .parameters
+                .iter()
+                .map(|param| param.result_id.unwrap())
+                .collect(),
+            block_indices: blocks
+                .iter()
+                .enumerate()
+                .map(|(i, block)| (block.label, i))
+                .collect(),
+            blocks,
+        })
+    }
+
+    pub fn entry_point(&self, name: &str) -> Result<&EntryPoint> {
+        self.entry_points
+            .get(name)
+            .with_context(|| format!("no compute entry-point named `{name}`"))
+    }
+
+    pub fn ty(&self, ty: Word) -> Result<&Type> {
+        self.types
+            .get(&ty)
+            .with_context(|| format!("unknown type %{ty}"))
+    }
+
+    pub fn value_type(&self, id: Word) -> Result<Word> {
+        self.value_types
+            .get(&id)
+            .copied()
+            .with_context(|| format!("unknown type for %{id}"))
+    }
+
+    pub fn pointee_type(&self, ptr_ty: Word) -> Result<Word> {
+        match self.ty(ptr_ty)? {
+            &Type::Pointer { pointee } => Ok(pointee),
+            _ => bail!("%{ptr_ty} is not a pointer type"),
+        }
+    }
+
+    /// The scalar type of `ty`, or of its components (for vectors).
+    pub fn scalar(&self, ty: Word) -> Result<Scalar> {
+        Ok(match *self.ty(ty)? {
+            Type::Bool => Scalar::Bool,
+            Type::Int { width, signed } => Scalar::Int { width, signed },
+            Type::Float { width } => Scalar::Float { width },
+            Type::Vector { elem, .. } => return self.scalar(elem),
+            _ => bail!("%{ty} is not a scalar or vector type"),
+        })
+    }
+
+    fn const_u32(&self, id: Word) -> Result<u32> {
+        match self.constants.get(&id) {
+            Some(value) => Ok(value.bits() as u32),
+            None => bail!("%{id} is not a constant"),
+        }
+    }
+
+    pub fn zero_value(&self, ty: Word) -> Result<Value> {
+        Ok(match *self.ty(ty)? {
+            Type::Bool | Type::Int { .. } | Type::Float { .. } => Value::Scalar(0),
+            Type::Vector { elem, count } | Type::Array { elem, len: count } => {
+                Value::Composite(vec![self.zero_value(elem)?; count as usize])
+            }
+            Type::Matrix { column, count } => {
+                Value::Composite(vec![self.zero_value(column)?; count as usize])
+            }
+            Type::Struct { ref members } => Value::Composite(
+                members
+                    .iter()
+                    .map(|&member| self.zero_value(member))
+                    .collect::<Result<_>>()?,
+            ),
+            ref ty => bail!("no zero value for {ty:?}"),
+        })
+    }
+
+    /// Size (in bytes) of a value of type `ty` in memory (`0` for runtime arrays).
+    pub fn size_of(&self, ty: Word) -> Result<usize> {
+        Ok(match *self.ty(ty)? {
+            Type::Bool => 4,
+            Type::Int { width, .. } | Type::Float { width } => width as usize / 8,
+            Type::Vector { elem, count } => self.size_of(elem)? * count as usize,
+            Type::Matrix { column, count } => self.size_of(column)? * count as usize,
+            Type::Array { len, .. } => self.array_stride(ty)? * len as usize,
+            Type::RuntimeArray { .. } => 0,
+            Type::Struct { ref members } => match members.len() {
+                0 => 0,
+                n => {
+                    let last = n as u32 - 1;
+                    let end = self.member_offset(ty, last)? + self.size_of(members[n - 1])?;
+                    end.next_multiple_of(self.align_of(ty)?)
+                }
+            },
+            ref ty => bail!("{ty:?} can't be stored in memory"),
+        })
+    }
+
+    /// Alignment used for types without explicit layout decorations.
+    fn align_of(&self, ty: Word) -> Result<usize> {
+        Ok(match *self.ty(ty)? {
+            Type::Vector { elem, .. } | Type::Array { elem, .. } | Type::RuntimeArray { elem } => {
+                self.align_of(elem)?
+            }
+            Type::Matrix { column, .. } => self.align_of(column)?,
+            Type::Struct { ref members } => members
+                .iter()
+                .map(|&member| self.align_of(member))
+                .try_fold(1, |a, b| Ok::<_, anyhow::Error>(a.max(b?)))?,
+            _ => self.size_of(ty)?.max(1),
+        })
+    }
+
+    pub fn member_offset(&self, struct_ty: Word, member: u32) -> Result<usize> {
+        if let Some(&offset) = self.member_offsets.get(&(struct_ty, member)) {
+            return Ok(offset as usize);
+        }
+        let Type::Struct { members } = self.ty(struct_ty)? else {
+            bail!("%{struct_ty} is not a struct type");
+        };
+        // Without `Offset` decorations, lay out members sequentially.
+        let mut offset = 0usize;
+        for (i, &member_ty) in members.iter().enumerate() {
+            offset = offset.next_multiple_of(self.align_of(member_ty)?);
+            if i == member as usize {
+                return Ok(offset);
+            }
+            offset += self.size_of(member_ty)?;
+        }
+        bail!("%{struct_ty} has no member {member}")
+    }
+
+    /// Distance (in bytes) between elements of an array type `ty`
+    /// (or between components/columns of vectors/matrices).
+    pub fn array_stride(&self, ty: Word) -> Result<usize> {
+        if let Some(&stride) = self.array_strides.get(&ty) {
+            return Ok(stride as usize);
+        }
+        match *self.ty(ty)? {
+            Type::Array { elem, .. } | Type::RuntimeArray { elem } | Type::Vector { elem, .. } => {
+                Ok(self.size_of(elem)?.next_multiple_of(self.align_of(elem)?))
+            }
+            Type::Matrix { column, .. } => self.size_of(column),
+            ref ty => bail!("{ty:?} is not an array type"),
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! Arithmetic on scalars (applied component-wise to vectors), including the
+//! `GLSL.std.450` extended instructions.
+
+use super::memory::Value;
+use super::module::Scalar;
+use anyhow::{Result, bail};
+use rspirv::spirv::{GLOp, Op};
+
+/// Apply `f` to the scalar arguments, or component-wise (for vector arguments,
+/// with any scalar arguments being used for every component).
+pub(super) fn componentwise(
+    args: &[Value],
+    f: &mut impl FnMut(&[u64]) -> Result<u64>,
+) -> Result<Value> {
+    let count = args.iter().find_map(|arg| match arg {
+        Value::Composite(components) => Some(components.len()),
+        _ => None,
+    });
+    match count {
+        None => Ok(Value::Scalar(f(&args
+            .iter()
+            .map(|arg| arg.bits())
+            .collect::<Vec<_>>())?)),
+        Some(count) => Ok(Value::Composite(
+            (0..count)
+                .map(|i| {
+                    let args = args
+                        .iter()
+                        .map(|arg| match arg {
+                            Value::Composite(components) => components[i].clone(),
+                            scalar => scalar.clone(),
+                        })
+                        .collect::<Vec<_>>();
+                    componentwise(&args, f)
+                })
+                .collect::<Result<_>>()?,
+        )),
+    }
+}
+
+fn width(ty: Scalar) -> u32 {
+    match ty {
+        Scalar::Bool => 1,
+        Scalar::Int { width, .. } | Scalar::Float { width } => width,
+    }
+}
+
+fn truncate(bits: u64, width: u32) -> u64 {
+    if width >= 64 {
+        bits
+    } else {
+        bits & ((1 << width) - 1)
+    }
+}
+
+fn sext(bits: u64, width: u32) -> i64 {
+    let shift = 64 - width;
+    ((bits << shift) as i64) >> shift
+}
+
+pub(super) fn to_f64(bits: u64, width: u32) -> Result<f64> {
+    Ok(match width {
+        16 => f16_to_f32(bits as u16).into(),
+        32 => f32::from_bits(bits as u32).into(),
+        64 => f64::from_bits(bits),
+        _ => bail!("unsupported float width {width}"),
+    })
+}
+
+pub(super) fn from_f64(x: f64, width: u32) -> Result<u64> {
+    Ok(match width {
+        16 => f32_to_f16(x as f32).into(),
+        32 => (x as f32).to_bits().into(),
+        64 => x.to_bits(),
+        _ => bail!("unsupported float width {width}"),
+    })
+}
+
+fn f16_to_f32(h: u16) -> f32 {
+    let sign = u32::from(h >> 15) << 31;
+    let exp = u32::from((h >> 10) & 0x1f);
+    let mantissa = u32::from(h & 0x3ff);
+    let bits = match (exp, mantissa) {
+        (0, 0) => sign,
+        // Subnormals are exactly representable as (normal) `f32`s.
+        (0, _) => {
+            let x = mantissa as f32 * 2f32.powi(-24);
+            return if sign != 0 { -x } else { x };
+        }
+        (0x1f, _) => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exp + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+fn f32_to_f16(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let abs = x.abs();
+    if abs.is_nan() {
+        return sign | 0x7e00;
+    }
+    if abs >= 65520.0 {
+        return sign | 0x7c00;
+    }
+    if abs < 2f32.powi(-14) {
+        // Subnormal (or zero), rounded to the nearest multiple of `2^-24`.
+        return sign | (abs * 2f32.powi(24)).round_ties_even() as u16;
+    }
+    // Round the mantissa to 10 bits (to nearest, ties to even).
+    let abs_bits = abs.to_bits();
+    let round = 0xfff + ((abs_bits >> 13) & 1);
+    let rounded = abs_bits + round;
+    let exp = (rounded >> 23) - 127 + 15;
+    sign | ((exp << 10) | ((rounded >> 13) & 0x3ff)) as u16
+}
+
+/// Reinterpret the bits of a scalar or vector `value` (with `from` components)
+/// as `to` components, possibly changing the number of components.
+pub(super) fn bitcast(value: Value, from: Scalar, to: Scalar, to_vector: bool) -> Value {
+    let (from_width, to_width) = (width(from), width(to));
+    if from_width == to_width {
+        return value;
+    }
+    let components = match value {
+        Value::Composite(components) => components,
+        scalar => vec![scalar],
+    };
+    let bits = components.iter().enumerate().fold(0u128, |bits, (i, c)| {
+        bits | (u128::from(c.bits()) << (i as u32 * from_width))
+    });
+    let count = components.len() as u32 * from_width / to_width;
+    let mut components: Vec<_> = (0..count)
+        .map(|i| Value::Scalar(truncate((bits >> (i * to_width)) as u64, to_width)))
+        .collect();
+    if to_vector {
+        Value::Composite(components)
+    } else {
+        components.pop().unwrap()
+    }
+}
+
+/// Evaluate a (component-wise) arithmetic, logic, comparison or conversion
+/// instruction on scalars, with `ty` being the type of the (first) operand.
+pub(super) fn scalar_op(op: Op, ty: Scalar, result: Scalar, args: &[u64]) -> Result<u64> {
+    let w = width(ty);
+    let rw = width(result);
+    let int = |bits: u64| truncate(bits, rw);
+    let s = |i: usize| sext(args[i], w);
+    let u = |i: usize| truncate(args[i], w);
+    let f = |i: usize| to_f64(args[i], w);
+    let float = |x: f64| from_f64(x, rw);
+    let bool = |b: bool| u64::from(b);
+    let unordered = || -> Result<bool> { Ok(f(0)?.is_nan() || f(1)?.is_nan()) };
+
+    Ok(match op {
+        Op::IAdd => int(u(0).wrapping_add(u(1))),
+        Op::ISub => int(u(0).wrapping_sub(u(1))),
+        Op::IMul => int(u(0).wrapping_mul(u(1))),
+        Op::UDiv => int(u(0).checked_div(u(1)).unwrap_or(0)),
+        Op::SDiv => int(s(0).checked_div(s(1)).unwrap_or(0) as u64),
+        Op::UMod => int(u(0).checked_rem(u(1)).unwrap_or(0)),
+        Op::SRem => int(s(0).checked_rem(s(1)).unwrap_or(0) as u64),
+        Op::SMod => int(match s(0).checked_rem(s(1)) {
+            Some(r) if r != 0 && (r < 0) != (s(1) < 0) => (r + s(1)) as u64,
+            r => r.unwrap_or(0) as u64,
+        }),
+        Op::SNegate => int(s(0).wrapping_neg() as u64),
+        Op::Not => int(!u(0)),
+        Op::BitwiseAnd => u(0) & u(1),
+        Op::BitwiseOr => u(0) | u(1),
+        Op::BitwiseXor => u(0) ^ u(1),
+        // NOTE the shift amount is taken modulo the width, even though it's
+        // undefined behavior (in SPIR-V) to shift by more than that.
+        Op::ShiftLeftLogical => int(u(0) << (args[1] % u64::from(w))),
+        Op::ShiftRightLogical => int(u(0) >> (args[1] % u64::from(w))),
+        Op::ShiftRightArithmetic => int((s(0) >> (args[1] % u64::from(w))) as u64),
+        Op::BitCount => u(0).count_ones().into(),
+        Op::BitReverse => int(u(0).reverse_bits() >> (64 - w)),
+        Op::BitFieldInsert => {
+            let (offset, count) = (args[2] as u32, args[3] as u32);
+            let mask = truncate(u64::MAX, count) << offset;
+            int((u(0) & !mask) | ((args[1] << offset) & mask))
+        }
+        Op::BitFieldUExtract | Op::BitFieldSExtract => {
+            let (offset, count) = (args[1] as u32, args[2] as u32);
+            if count == 0 {
+                0
+            } else {
+                let field = truncate(u(0) >> offset, count);
+                if op == Op::BitFieldSExtract {
+                    int(sext(field, count) as u64)
+                } else {
+                    field
+                }
+            }
+        }
+
+        Op::FAdd => float(f(0)? + f(1)?)?,
+        Op::FSub => float(f(0)? - f(1)?)?,
+        Op::FMul => float(f(0)? * f(1)?)?,
+        Op::FDiv => float(f(0)? / f(1)?)?,
+        Op::FRem => float(f(0)? % f(1)?)?,
+        Op::FMod => {
+            let (x, y) = (f(0)?, f(1)?);
+            float(x - y * (x / y).floor())?
+        }
+        Op::FNegate => float(-f(0)?)?,
+
+        Op::IEqual | Op::LogicalEqual => bool(u(0) == u(1)),
+        Op::INotEqual | Op::LogicalNotEqual => bool(u(0) != u(1)),
+        Op::UGreaterThan => bool(u(0) > u(1)),
+        Op::UGreaterThanEqual => bool(u(0) >= u(1)),
+        Op::ULessThan => bool(u(0) < u(1)),
+        Op::ULessThanEqual => bool(u(0) <= u(1)),
+        Op::SGreaterThan => bool(s(0) > s(1)),
+        Op::SGreaterThanEqual => bool(s(0) >= s(1)),
+        Op::SLessThan => bool(s(0) < s(1)),
+        Op::SLessThanEqual => bool(s(0) <= s(1)),
+        Op::LogicalAnd => bool(u(0) != 0 && u(1) != 0),
+        Op::LogicalOr => bool(u(0) != 0 || u(1) != 0),
+        Op::LogicalNot => bool(u(0) == 0),
+
+        Op::FOrdEqual => bool(f(0)? == f(1)?),
+        Op::FOrdNotEqual => bool(!unordered()? && f(0)? != f(1)?),
+        Op::FOrdLessThan => bool(f(0)? < f(1)?),
+        Op::FOrdGreaterThan => bool(f(0)? > f(1)?),
+        Op::FOrdLessThanEqual => bool(f(0)? <= f(1)?),
+        Op::FOrdGreaterThanEqual => bool(f(0)? >= f(1)?),
+        Op::FUnordEqual => bool(unordered()? || f(0)? == f(1)?),
+        Op::FUnordNotEqual => bool(f(0)? != f(1)?),
+        Op::FUnordLessThan => bool(unordered()? || f(0)? < f(1)?),
+        Op::FUnordGreaterThan => bool(unordered()? || f(0)? > f(1)?),
+        Op::FUnordLessThanEqual => bool(unordered()? || f(0)? <= f(1)?),
+        Op::FUnordGreaterThanEqual => bool(unordered()? || f(0)? >= f(1)?),
+        Op::IsNan => bool(f(0)?.is_nan()),
+        Op::IsInf => bool(f(0)?.is_infinite()),
+
+        Op::UConvert => u(0),
+        Op::SConvert => int(s(0) as u64),
+        Op::FConvert => float(f(0)?)?,
+        Op::ConvertUToF => float(u(0) as f64)?,
+        Op::ConvertSToF => float(s(0) as f64)?,
+        Op::ConvertFToU => int(f(0)? as u64),
+        Op::ConvertFToS => int(f(0)? as i64 as u64),
+
+        _ => bail!("unsupported instruction `Op{op:?}`"),
+    })
+}
+
+/// Evaluate a `GLSL.std.450` extended instruction, with `ty` being the type
+/// of the (first) operand.
+pub(super) fn glsl_op(op: GLOp, ty: Scalar, result: Scalar, args: &[Value]) -> Result<Value> {
+    let w = width(ty);
+
+    // Instructions which aren't component-wise.
+    match op {
+        GLOp::Length
+        | GLOp::Distance
+        | GLOp::Normalize
+        | GLOp::Cross
+        | GLOp::Reflect
+        | GLOp::FaceForward => {
+            let vectors = args
+                .iter()
+                .map(|arg| match arg {
+                    Value::Composite(components) => components
+                        .iter()
+                        .map(|c| to_f64(c.bits(), w))
+                        .collect::<Result<Vec<_>>>(),
+                    scalar => Ok(vec![to_f64(scalar.bits(), w)?]),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+            let to_value = |v: Vec<f64>| -> Result<Value> {
+                let mut components = v
+                    .into_iter()
+                    .map(|x| Ok(Value::Scalar(from_f64(x, w)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(if matches!(args[0], Value::Composite(_)) {
+                    Value::Composite(components)
+                } else {
+                    components.pop().unwrap()
+                })
+            };
+            let scalar = |x: f64| Ok(Value::Scalar(from_f64(x, w)?));
+            let (a, b) = (&vectors[0], vectors.get(1));
+            return match op {
+                GLOp::Length => scalar(dot(a, a).sqrt()),
+                GLOp::Distance => {
+                    let d: Vec<_> = a.iter().zip(b.unwrap()).map(|(a, b)| a - b).collect();
+                    scalar(dot(&d, &d).sqrt())
+                }
+                GLOp::Normalize => {
+                    let len = dot(a, a).sqrt();
+                    to_value(a.iter().map(|x| x / len).collect())
+                }
+                GLOp::Cross => {
+                    let b = b.unwrap();
+                    to_value(vec![
+                        a[1] * b[2] - b[1] * a[2],
+                        a[2] * b[0] - b[2] * a[0],
+                        a[0] * b[1] - b[0] * a[1],
+                    ])
+                }
+                GLOp::Reflect => {
+                    let n = b.unwrap();
+                    let d = dot(n, a);
+                    to_value(a.iter().zip(n).map(|(i, n)| i - 2.0 * d * n).collect())
+                }
+                _ => {
+                    let (i, nref) = (b.unwrap(), &vectors[2]);
+                    let sign = if dot(nref, i) < 0.0 { 1.0 } else { -1.0 };
+                    to_value(a.iter().map(|n| sign * n).collect())
+                }
+            };
+        }
+        GLOp::PackHalf2x16 => {
+            let c = args[0].components();
+            let half = |i: usize| -> Result<u64> {
+                Ok(f32_to_f16(to_f64(c[i].bits(), 32)? as f32).into())
+            };
+            return Ok(Value::Scalar(half(0)? | (half(1)? << 16)));
+        }
+        GLOp::UnpackHalf2x16 => {
+            let bits = args[0].bits();
+            let half = |shift: u32| -> Result<Value> {
+                let x = f16_to_f32((bits >> shift) as u16);
+                Ok(Value::Scalar(from_f64(x.into(), 32)?))
+            };
+            return Ok(Value::Composite(vec![half(0)?, half(16)?]));
+        }
+        GLOp::PackUnorm4x8 | GLOp::PackSnorm4x8 | GLOp::PackUnorm2x16 | GLOp::PackSnorm2x16 => {
+            let signed = matches!(op, GLOp::PackSnorm4x8 | GLOp::PackSnorm2x16);
+            let bits = if matches!(op, GLOp::PackUnorm4x8 | GLOp::PackSnorm4x8) {
+                8
+            } else {
+                16
+            };
+            let mask: u32 = (1 << bits) - 1;
+            let mut packed = 0;
+            for (i, c) in args[0].components().iter().enumerate() {
+                let x = to_f64(c.bits(), 32)?;
+                let field = if signed {
+                    (x.clamp(-1.0, 1.0) * f64::from(mask >> 1)).round_ties_even() as i64 as u64
+                } else {
+                    (x.clamp(0.0, 1.0) * f64::from(mask)).round_ties_even() as u64
+                };
+                packed |= (field & u64::from(mask)) << (i as u32 * bits);
+            }
+            return Ok(Value::Scalar(packed));
+        }
+        GLOp::UnpackUnorm4x8
+        | GLOp::UnpackSnorm4x8
+        | GLOp::UnpackUnorm2x16
+        | GLOp::UnpackSnorm2x16 => {
+            let signed = matches!(op, GLOp::UnpackSnorm4x8 | GLOp::UnpackSnorm2x16);
+            let (bits, count) = if matches!(op, GLOp::UnpackUnorm4x8 | GLOp::UnpackSnorm4x8) {
+                (8, 4)
+            } else {
+                (16, 2)
+            };
+            let packed = args[0].bits();
+            return Ok(Value::Composite(
+                (0..count)
+                    .map(|i| {
+                        let field = truncate(packed >> (i * bits), bits);
+                        let mask = (1u64 << bits) - 1;
+                        let x = if signed {
+                            (sext(field, bits) as f64 / (mask >> 1) as f64).max(-1.0)
+                        } else {
+                            field as f64 / mask as f64
+                        };
+                        Ok(Value::Scalar(from_f64(x, 32)?))
+                    })
+                    .collect::<Result<_>>()?,
+            ));
+        }
+        _ => {}
+    }
+
+    let rw = width(result);
+    componentwise(args, &mut |args| {
+        let f = |i: usize| to_f64(args[i], w);
+        let s = |i: usize| sext(args[i], w);
+        let u = |i: usize| truncate(args[i], w);
+        let float = |x: f64| from_f64(x, rw);
+        let int = |x: i64| Ok(truncate(x as u64, rw));
+        match op {
+            GLOp::Round => float(f(0)?.round()),
+            GLOp::RoundEven => float(f(0)?.round_ties_even()),
+            GLOp::Trunc => float(f(0)?.trunc()),
+            GLOp::FAbs => float(f(0)?.abs()),
+            GLOp::SAbs => int(s(0).wrapping_abs()),
+            GLOp::FSign => float(match f(0)? {
+                x if x > 0.0 => 1.0,
+                x if x < 0.0 => -1.0,
+                x => x,
+            }),
+            GLOp::SSign => int(s(0).signum()),
+            GLOp::Floor => float(f(0)?.floor()),
+            GLOp::Ceil => float(f(0)?.ceil()),
+            GLOp::Fract => float(f(0)? - f(0)?.floor()),
+            GLOp::Radians => float(f(0)?.to_radians()),
+            GLOp::Degrees => float(f(0)?.to_degrees()),
+            GLOp::Sin => float(f(0)?.sin()),
+            GLOp::Cos => float(f(0)?.cos()),
+            GLOp::Tan => float(f(0)?.tan()),
+            GLOp::Asin => float(f(0)?.asin()),
+            GLOp::Acos => float(f(0)?.acos()),
+            GLOp::Atan => float(f(0)?.atan()),
+            GLOp::Sinh => float(f(0)?.sinh()),
+            GLOp::Cosh => float(f(0)?.cosh()),
+            GLOp::Tanh => float(f(0)?.tanh()),
+            GLOp::Asinh => float(f(0)?.asinh()),
+            GLOp::Acosh => float(f(0)?.acosh()),
+            GLOp::Atanh => float(f(0)?.atanh()),
+            GLOp::Atan2 => float(f(0)?.atan2(f(1)?)),
+            GLOp::Pow => float(f(0)?.powf(f(1)?)),
+            GLOp::Exp => float(f(0)?.exp()),
+            GLOp::Log => float(f(0)?.ln()),
+            GLOp::Exp2 => float(f(0)?.exp2()),
+            GLOp::Log2 => float(f(0)?.log2()),
+            GLOp::Sqrt => float(f(0)?.sqrt()),
+            GLOp::InverseSqrt => float(1.0 / f(0)?.sqrt()),
+            GLOp::FMin | GLOp::NMin => float(f(0)?.min(f(1)?)),
+            GLOp::FMax | GLOp::NMax => float(f(0)?.max(f(1)?)),
+            GLOp::FClamp | GLOp::NClamp => float(f(0)?.max(f(1)?).min(f(2)?)),
+            GLOp::UMin => Ok(u(0).min(u(1))),
+            GLOp::UMax => Ok(u(0).max(u(1))),
+            GLOp::UClamp => Ok(u(0).max(u(1)).min(u(2))),
+            GLOp::SMin => int(s(0).min(s(1))),
+            GLOp::SMax => int(s(0).max(s(1))),
+            GLOp::SClamp => int(s(0).max(s(1)).min(s(2))),
+            GLOp::FMix => float(f(0)? * (1.0 - f(2)?) + f(1)? * f(2)?),
+            GLOp::Step => float(if f(1)? < f(0)? { 0.0 } else { 1.0 }),
+            GLOp::SmoothStep => {
+                let t = ((f(2)? - f(0)?) / (f(1)? - f(0)?)).clamp(0.0, 1.0);
+                float(t * t * (3.0 - 2.0 * t))
+            }
+            GLOp::Fma => float(f(0)?.mul_add(f(1)?, f(2)?)),
+            GLOp::Ldexp => float(f(0)? * 2f64.powi(sext(args[1], 32) as i32)),
+            GLOp::FindILsb => int(match u(0) {
+                0 => -1,
+                x => x.trailing_zeros().into(),
+            }),
+            GLOp::FindUMsb => int(match u(0) {
+                0 => -1,
+                x => (63 - x.leading_zeros()).into(),
+            }),
+            GLOp::FindSMsb => int(match s(0) {
+                0 | -1 => -1,
+                x if x < 0 => (63 - (!x).leading_zeros()).into(),
+                x => (63 - x.leading_zeros()).into(),
+            }),
+            _ => bail!("unsupported `GLSL.std.450` instruction `{op:?}`"),
+        }
+    })
+}
+
+/// Evaluate the scalar operation of a `OpGroupNonUniform*` arithmetic instruction.
+pub(super) fn group_op(op: Op, ty: Scalar, a: u64, b: u64) -> Result<u64> {
+    let glsl = |op: GLOp| -> Result<u64> {
+        Ok(glsl_op(op, ty, ty, &[Value::Scalar(a), Value::Scalar(b)])?.bits())
+    };
+    match op {
+        Op::GroupNonUniformIAdd => scalar_op(Op::IAdd, ty, ty, &[a, b]),
+        Op::GroupNonUniformFAdd => scalar_op(Op::FAdd, ty, ty, &[a, b]),
+        Op::GroupNonUniformIMul => scalar_op(Op::IMul, ty, ty, &[a, b]),
+        Op::GroupNonUniformFMul => scalar_op(Op::FMul, ty, ty, &[a, b]),
+        Op::GroupNonUniformSMin => glsl(GLOp::SMin),
+        Op::GroupNonUniformUMin => glsl(GLOp::UMin),
+        Op::GroupNonUniformFMin => glsl(GLOp::FMin),
+        Op::GroupNonUniformSMax => glsl(GLOp::SMax),
+        Op::GroupNonUniformUMax => glsl(GLOp::UMax),
+        Op::GroupNonUniformFMax => glsl(GLOp::FMax),
+        Op::GroupNonUniformBitwiseAnd | Op::GroupNonUniformLogicalAnd => Ok(a & b),
+        Op::GroupNonUniformBitwiseOr | Op::GroupNonUniformLogicalOr => Ok(a | b),
+        Op::GroupNonUniformBitwiseXor | Op::GroupNonUniformLogicalXor => Ok(a ^ b),
+        _ => bail!("unsupported instruction `Op{op:?}`"),
+    }
+}
+
+/// Identity value of a `OpGroupNonUniform*` arithmetic instruction (used for
+/// exclusive scans).
+pub(super) fn group_op_identity(op: Op, ty: Scalar) -> Result<u64> {
+    let w = width(ty);
+    Ok(match op {
+        Op::GroupNonUniformIAdd
+        | Op::GroupNonUniformUMax
+        | Op::GroupNonUniformBitwiseOr
+        | Op::GroupNonUniformBitwiseXor
+        | Op::GroupNonUniformLogicalOr
+        | Op::GroupNonUniformLogicalXor => 0,
+        Op::GroupNonUniformIMul => 1,
+        Op::GroupNonUniformFAdd => from_f64(0.0, w)?,
+        Op::GroupNonUniformFMul => from_f64(1.0, w)?,
+        Op::GroupNonUniformFMin => from_f64(f64::INFINITY, w)?,
+        Op::GroupNonUniformFMax => from_f64(f64::NEG_INFINITY, w)?,
+        Op::GroupNonUniformSMin => truncate(u64::MAX, w - 1),
+        Op::GroupNonUniformSMax => truncate(1 << (w - 1), w),
+        Op::GroupNonUniformUMin | Op::GroupNonUniformBitwiseAnd | Op::GroupNonUniformLogicalAnd => {
+            truncate(u64::MAX, w)
+        }
+        _ => bail!("unsupported instruction `Op{op:?}`"),
+    })
+}
+
+/// Evaluate one of the integer instructions with two results (e.g. `OpIAddCarry`),
+/// on scalars.
+pub(super) fn extended_op(op: Op, ty: Scalar, a: u64, b: u64) -> Result<(u64, u64)> {
+    let w = width(ty);
+    let (ua, ub) = (truncate(a, w), truncate(b, w));
+    Ok(match op {
+        Op::IAddCarry => {
+            let sum = u128::from(ua) + u128::from(ub);
+            (truncate(sum as u64, w), (sum >> w) as u64)
+        }
+        Op::ISubBorrow => (truncate(ua.wrapping_sub(ub), w), u64::from(ua < ub)),
+        Op::UMulExtended => {
+            let product = u128::from(ua) * u128::from(ub);
+            (
+                truncate(product as u64, w),
+                truncate((product >> w) as u64, w),
+            )
+        }
+        Op::SMulExtended => {
+            let product = i128::from(sext(a, w)) * i128::from(sext(b, w));
+            (
+                truncate(product as u64, w),
+                truncate((product >> w) as u64, w),
+            )
+        }
+        _ => bail!("unsupported instruction `Op{op:?}`"),
+    })
+}
+
+/// Evaluate a floating-point vector/matrix instruction (e.g. `OpDot`), with `ty`
+/// being the scalar type of the components.
+pub(super) fn linear_algebra_op(op: Op, ty: Scalar, args: &[Value]) -> Result<Value> {
+    let w = width(ty);
+    let vector = |value: &Value| -> Result<Vec<f64>> {
+        value
+            .components()
+            .iter()
+            .map(|c| to_f64(c.bits(), w))
+            .collect()
+    };
+    let matrix = |value: &Value| -> Result<Vec<Vec<f64>>> {
+        value.components().iter().map(vector).collect()
+    };
+    let to_vector = |v: Vec<f64>| -> Result<Value> {
+        Ok(Value::Composite(
+            v.into_iter()
+                .map(|x| Ok(Value::Scalar(from_f64(x, w)?)))
+                .collect::<Result<_>>()?,
+        ))
+    };
+    let to_matrix = |m: Vec<Vec<f64>>| -> Result<Value> {
+        Ok(Value::Composite(
+            m.into_iter().map(to_vector).collect::<Result<_>>()?,
+        ))
+    };
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+    let transpose = |m: &[Vec<f64>]| -> Vec<Vec<f64>> {
+        (0..m[0].len())
+            .map(|row| m.iter().map(|column| column[row]).collect())
+            .collect()
+    };
+    // Matrix-vector product, with the matrix given as its rows.
+    let rows_times_vector =
+        |rows: &[Vec<f64>], v: &[f64]| -> Vec<f64> { rows.iter().map(|row| dot(row, v)).collect() };
+
+    match op {
+        Op::Dot => Ok(Value::Scalar(from_f64(
+            dot(&vector(&args[0])?, &vector(&args[1])?),
+            w,
+        )?)),
+        Op::VectorTimesScalar => {
+            let s = to_f64(args[1].bits(), w)?;
+            to_vector(vector(&args[0])?.iter().map(|x| x * s).collect())
+        }
+        Op::MatrixTimesScalar => {
+            let s = to_f64(args[1].bits(), w)?;
+            to_matrix(
+                matrix(&args[0])?
+                    .iter()
+                    .map(|column| column.iter().map(|x| x * s).collect())
+                    .collect(),
+            )
+        }
+        Op::VectorTimesMatrix => {
+            // NOTE the columns of the matrix are the rows of its transpose.
+            to_vector(rows_times_vector(&matrix(&args[1])?, &vector(&args[0])?))
+        }
+        Op::MatrixTimesVector => to_vector(rows_times_vector(
+            &transpose(&matrix(&args[0])?),
+            &vector(&args[1])?,
+        )),
+        Op::MatrixTimesMatrix => {
+            let rows = transpose(&matrix(&args[0])?);
+            to_matrix(
+                matrix(&args[1])?
+                    .iter()
+                    .map(|column| rows_times_vector(&rows, column))
+                    .collect(),
+            )
+        }
+        Op::Transpose => to_matrix(transpose(&matrix(&args[0])?)),
+        Op::OuterProduct => {
+            let (a, b) = (vector(&args[0])?, vector(&args[1])?);
+            to_matrix(
+                b.iter()
+                    .map(|y| a.iter().map(|x| x * y).collect())
+                    .collect(),
+            )
+        }
+        _ => bail!("unsupported instruction `Op{op:?}`"),
+    }
+}
@@ -1,10 +1,12 @@
 mod ash;
 mod backend;
+mod interpreter;
 mod wgpu;
 
 pub use crate::scaffold::shader::*;
 pub use ash::AshBackend;
 pub use backend::{BufferConfig, BufferUsage, ComputeBackend, ComputeShaderTest, ComputeTest};
+pub use interpreter::InterpreterBackend;
 pub use wgpu::{
     WgpuBackend, WgpuComputeTest, WgpuComputeTestMultiBuffer, WgpuComputeTestPushConstants,
 };
@@ -1,5 +1,7 @@
 use super::backend::{self, ComputeBackend};
-use crate::config::Config;
+use super::interpreter::InterpreterBackend;
+use crate::config::{ComputeBackendKind, Config};
+use crate::scaffold::Skip;
 use crate::scaffold::shader::RustComputeShader;
 use crate::scaffold::shader::WgpuShader;
 use crate::scaffold::shader::WgslComputeShader;
@@ -12,6 +14,36 @@ use wgpu::{ExperimentalFeatures, PipelineCompilationOptions, util::DeviceExt};
 pub type BufferConfig = backend::BufferConfig;
 pub type BufferUsage = backend::BufferUsage;
 
+/// Runs `shader` on the `InterpreterBackend` (for `ComputeBackendKind::Interpreter`),
+/// writing the first storage buffer output to the file, or skips the test if the
+/// shader isn't available as SPIR-V (e.g. WGSL shaders).
+fn run_test_on_interpreter<S: WgpuShader>(
+    config: &Config,
+    shader: &S,
+    dispatch: [u32; 3],
+    buffers: Vec<BufferConfig>,
+    push_constants: &[u8],
+) -> anyhow::Result<()> {
+    let Some(shader) = shader.spirv_shader() else {
+        return Skip::new("only SPIR-V shaders can run on the interpreter").run_test(config);
+    };
+    let (spirv_bytes, entry_point) = shader.spirv_bytes()?;
+    let outputs = InterpreterBackend::default().run_compute_with_push_constants(
+        &spirv_bytes,
+        &entry_point,
+        dispatch,
+        buffers.clone(),
+        push_constants,
+    )?;
+    for (output, buffer_config) in outputs.iter().zip(&buffers) {
+        if buffer_config.usage == BufferUsage::Storage && !output.is_empty() {
+            config.write_result(output)?;
+            return Ok(());
+        }
+    }
+    anyhow::bail!("No storage buffer output found")
+}
+
 /// Compute test that is generic over the shader type.
 pub struct WgpuComputeTest<S> {
     shader: S,
@@ -51,7 +83,9 @@ where
         Self::init_with_features(wgpu::Features::empty())
     }
 
-    fn init_with_features(features: wgpu::Features) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+    pub(crate) fn init_with_features(
+        features: wgpu::Features,
+    ) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
         block_on(async {
             let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
                 #[cfg(target_os = "linux")]
@@ -218,6 +252,10 @@ where
 
     /// Runs the compute shader with no input and writes the output to a file.
     pub fn run_test(self, config: &Config) -> anyhow::Result<()> {
+        if config.compute_backend == ComputeBackendKind::Interpreter {
+            let buffers = vec![BufferConfig::writeback(self.output_bytes as usize)];
+            return run_test_on_interpreter(config, &self.shader, self.dispatch, buffers, &[]);
+        }
         let output = self.run()?;
         config.write_result(&output)?;
         Ok(())
@@ -228,6 +266,17 @@ where
     where
         I: Sized + Pod,
     {
+        if config.compute_backend == ComputeBackendKind::Interpreter {
+            let buffers = vec![
+                BufferConfig {
+                    size: size_of::<I>() as u64,
+                    usage: BufferUsage::Uniform,
+                    initial_data: Some(bytemuck::bytes_of(&input).to_vec()),
+                },
+                BufferConfig::writeback(self.output_bytes as usize),
+            ];
+            return run_test_on_interpreter(config, &self.shader, self.dispatch, buffers, &[]);
+        }
         let output = self.run_with_input(input)?;
         config.write_result(&output)?;
         Ok(())
@@ -561,6 +610,9 @@ where
 
     pub fn run_test(self, config: &Config) -> anyhow::Result<()> {
         let buffers = self.buffers.clone();
+        if config.compute_backend == ComputeBackendKind::Interpreter {
+            return run_test_on_interpreter(config, &self.shader, self.dispatch, buffers, &[]);
+        }
         let outputs = self.run()?;
         // Write the first storage buffer output to the file.
         for (output, buffer_config) in outputs.iter().zip(&buffers) {
@@ -770,6 +822,15 @@ where
 
     pub fn run_test(self, config: &Config) -> anyhow::Result<()> {
         let buffers = self.buffers.clone();
+        if config.compute_backend == ComputeBackendKind::Interpreter {
+            return run_test_on_interpreter(
+                config,
+                &self.shader,
+                self.dispatch,
+                buffers,
+                &self.push_constants_data,
+            );
+        }
         let results = self.run()?;
         // Write first storage buffer output to file.
         for (data, buffer_config) in results.iter().zip(&buffers) {
@@ -1,4 +1,5 @@
 pub mod compute;
+pub mod render;
 pub mod shader;
 pub mod skip;
 
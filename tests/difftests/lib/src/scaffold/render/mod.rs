@@ -0,0 +1,5 @@
+mod wgpu;
+
+pub use crate::config::ImageFormat;
+pub use crate::scaffold::shader::{RustRenderShader, WgpuRenderShader, WgslRenderShader};
+pub use wgpu::{TextureConfig, VertexBufferConfig, WgpuRenderTest};
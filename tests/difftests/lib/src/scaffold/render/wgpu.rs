@@ -0,0 +1,351 @@
+use crate::config::{ComputeBackendKind, Config, ImageFormat, TestMetadata};
+use crate::scaffold::Skip;
+use crate::scaffold::compute::{RustComputeShader, WgpuComputeTest};
+use crate::scaffold::shader::WgpuRenderShader;
+use anyhow::Context;
+use futures::executor::block_on;
+use wgpu::util::DeviceExt;
+
+fn texture_format(format: ImageFormat) -> wgpu::TextureFormat {
+    match format {
+        ImageFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+        ImageFormat::Rgba32Float => wgpu::TextureFormat::Rgba32Float,
+        ImageFormat::R32Float => wgpu::TextureFormat::R32Float,
+    }
+}
+
+/// Configuration for a vertex buffer, bound to the vertex buffer slot matching
+/// its position in the order they were added.
+#[derive(Clone)]
+pub struct VertexBufferConfig {
+    pub data: Vec<u8>,
+    pub stride: u64,
+    pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl VertexBufferConfig {
+    /// One vertex per element of `vertices`, with `attributes` describing its fields
+    /// (e.g. using `wgpu::vertex_attr_array!`).
+    pub fn new<A: bytemuck::NoUninit>(
+        vertices: &[A],
+        attributes: impl Into<Vec<wgpu::VertexAttribute>>,
+    ) -> Self {
+        Self {
+            data: bytemuck::cast_slice(vertices).to_vec(),
+            stride: size_of::<A>() as u64,
+            attributes: attributes.into(),
+        }
+    }
+}
+
+/// Configuration for a 2D texture, and the sampler used with it.
+///
+/// The `i`-th texture added is bound at `descriptor_set = 0, binding = 2 * i`,
+/// and its sampler at `descriptor_set = 0, binding = 2 * i + 1`.
+#[derive(Clone)]
+pub struct TextureConfig {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    pub data: Vec<u8>,
+    pub filter: wgpu::FilterMode,
+}
+
+impl TextureConfig {
+    pub fn new<A: bytemuck::NoUninit>(
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+        texels: &[A],
+    ) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            data: bytemuck::cast_slice(texels).to_vec(),
+            filter: wgpu::FilterMode::Nearest,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+/// Render test that draws with a vertex and fragment shader pair into an
+/// offscreen target, and outputs the resulting image.
+pub struct WgpuRenderTest<S> {
+    shader: S,
+    size: [u32; 2],
+    format: ImageFormat,
+    vertex_count: u32,
+    topology: wgpu::PrimitiveTopology,
+    vertex_buffers: Vec<VertexBufferConfig>,
+    textures: Vec<TextureConfig>,
+    epsilon: Option<f32>,
+}
+
+impl<S> WgpuRenderTest<S>
+where
+    S: WgpuRenderShader,
+{
+    /// Draws `vertex_count` vertices (as a triangle list, by default) into a
+    /// `size[0]`x`size[1]` target cleared to transparent black.
+    pub fn new(shader: S, size: [u32; 2], format: ImageFormat, vertex_count: u32) -> Self {
+        Self {
+            shader,
+            size,
+            format,
+            vertex_count,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            vertex_buffers: Vec::new(),
+            textures: Vec::new(),
+            epsilon: None,
+        }
+    }
+
+    pub fn with_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_vertex_buffer(mut self, vertex_buffer: VertexBufferConfig) -> Self {
+        self.vertex_buffers.push(vertex_buffer);
+        self
+    }
+
+    pub fn with_texture(mut self, texture: TextureConfig) -> Self {
+        self.textures.push(texture);
+        self
+    }
+
+    /// Maximum allowed per-channel difference when comparing the output image
+    /// (with `Rgba8Unorm` channels normalized to `0.0..=1.0`).
+    pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    /// Renders, returning the image with tightly packed rows.
+    pub fn run(self) -> anyhow::Result<Vec<u8>> {
+        let (device, queue) =
+            WgpuComputeTest::<RustComputeShader>::init_with_features(wgpu::Features::empty())?;
+        let (module, vertex_entry_point, fragment_entry_point) =
+            self.shader.create_module(&device)?;
+        let [width, height] = self.size;
+        let format = texture_format(self.format);
+
+        let vertex_buffer_layouts: Vec<_> = self
+            .vertex_buffers
+            .iter()
+            .map(|vertex_buffer| wgpu::VertexBufferLayout {
+                array_stride: vertex_buffer.stride,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &vertex_buffer.attributes,
+            })
+            .collect();
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some(&vertex_entry_point),
+                compilation_options: Default::default(),
+                buffers: &vertex_buffer_layouts,
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: self.topology,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some(&fragment_entry_point),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            multiview: None,
+            cache: None,
+        });
+
+        // Create vertex buffers.
+        let gpu_vertex_buffers: Vec<_> = self
+            .vertex_buffers
+            .iter()
+            .enumerate()
+            .map(|(i, vertex_buffer)| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&format!("Vertex Buffer {i}")),
+                    contents: &vertex_buffer.data,
+                    usage: wgpu::BufferUsages::VERTEX,
+                })
+            })
+            .collect();
+
+        // Create textures and samplers.
+        let mut texture_views = Vec::new();
+        let mut samplers = Vec::new();
+        for (i, texture_config) in self.textures.iter().enumerate() {
+            let texture = device.create_texture_with_data(
+                &queue,
+                &wgpu::TextureDescriptor {
+                    label: Some(&format!("Texture {i}")),
+                    size: wgpu::Extent3d {
+                        width: texture_config.width,
+                        height: texture_config.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: texture_format(texture_config.format),
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &texture_config.data,
+            );
+            texture_views.push(texture.create_view(&Default::default()));
+            samplers.push(device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(&format!("Sampler {i}")),
+                mag_filter: texture_config.filter,
+                min_filter: texture_config.filter,
+                ..Default::default()
+            }));
+        }
+
+        // Build the bind group (only if there's anything to bind, as otherwise
+        // the pipeline has no bind group layouts).
+        let bind_group = if self.textures.is_empty() {
+            None
+        } else {
+            let bind_entries: Vec<_> = texture_views
+                .iter()
+                .zip(&samplers)
+                .enumerate()
+                .flat_map(|(i, (view, sampler))| {
+                    [
+                        wgpu::BindGroupEntry {
+                            binding: 2 * i as u32,
+                            resource: wgpu::BindingResource::TextureView(view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2 * i as u32 + 1,
+                            resource: wgpu::BindingResource::Sampler(sampler),
+                        },
+                    ]
+                })
+                .collect();
+            Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &bind_entries,
+                label: Some("Render Bind Group"),
+            }))
+        };
+
+        // Create the render target.
+        let target_size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Target"),
+            size: target_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&Default::default());
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target_view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipeline);
+            if let Some(bind_group) = &bind_group {
+                pass.set_bind_group(0, bind_group, &[]);
+            }
+            for (slot, vertex_buffer) in gpu_vertex_buffers.iter().enumerate() {
+                pass.set_vertex_buffer(slot as u32, vertex_buffer.slice(..));
+            }
+            pass.draw(0..self.vertex_count, 0..1);
+        }
+
+        // Copy the render target into a staging buffer, whose rows have to be
+        // padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+        let bytes_per_row = width as usize * self.format.bytes_per_pixel();
+        let padded_bytes_per_row =
+            bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Staging Buffer"),
+            size: (padded_bytes_per_row * height as usize) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            target.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            target_size,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = staging_buffer.slice(..);
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |res| {
+            let _ = sender.send(res);
+        });
+        device.poll(wgpu::PollType::wait_indefinitely())?;
+        block_on(receiver)
+            .context("mapping canceled")?
+            .context("mapping failed")?;
+        let data = buffer_slice
+            .get_mapped_range()
+            .chunks_exact(padded_bytes_per_row)
+            .flat_map(|row| &row[..bytes_per_row])
+            .copied()
+            .collect();
+        staging_buffer.unmap();
+        Ok(data)
+    }
+
+    /// Renders and writes the image to a file, along with metadata describing it.
+    pub fn run_test(self, config: &Config) -> anyhow::Result<()> {
+        if config.compute_backend == ComputeBackendKind::Interpreter {
+            return Skip::new("render tests can't run on the interpreter").run_test(config);
+        }
+        let [width, height] = self.size;
+        let metadata = TestMetadata::image(width, height, self.format, self.epsilon);
+        let output = self.run()?;
+        config.write_result(&output)?;
+        config.write_metadata(&metadata)?;
+        Ok(())
+    }
+}
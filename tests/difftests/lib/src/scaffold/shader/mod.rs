@@ -1,8 +1,8 @@
 mod rust_gpu_shader;
 mod wgsl_shader;
 
-pub use rust_gpu_shader::RustComputeShader;
-pub use wgsl_shader::WgslComputeShader;
+pub use rust_gpu_shader::{RustComputeShader, RustRenderShader};
+pub use wgsl_shader::{WgslComputeShader, WgslRenderShader};
 
 /// Trait for shaders that can provide SPIRV bytes.
 pub trait SpirvShader {
@@ -17,4 +17,20 @@ pub trait WgpuShader {
         &self,
         device: &wgpu::Device,
     ) -> anyhow::Result<(wgpu::ShaderModule, Option<String>)>;
+
+    /// Returns this shader as a `SpirvShader`, if it has SPIR-V available
+    /// (needed to run it with `InterpreterBackend`).
+    fn spirv_shader(&self) -> Option<&dyn SpirvShader> {
+        None
+    }
+}
+
+/// Trait for vertex and fragment shader pairs that can create wgpu modules.
+pub trait WgpuRenderShader {
+    /// Creates a wgpu shader module containing both stages, returning it along
+    /// with the vertex and fragment entry point names.
+    fn create_module(
+        &self,
+        device: &wgpu::Device,
+    ) -> anyhow::Result<(wgpu::ShaderModule, String, String)>;
 }
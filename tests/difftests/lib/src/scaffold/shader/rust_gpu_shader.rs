@@ -1,8 +1,8 @@
-use crate::scaffold::shader::{SpirvShader, WgpuShader};
+use crate::scaffold::shader::{SpirvShader, WgpuRenderShader, WgpuShader};
 use anyhow::Context;
 use spirv_builder::{ModuleResult, SpirvBuilder};
 use std::borrow::Cow;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{env, fs};
 
 /// A compute shader written in Rust compiled with spirv-builder.
@@ -33,37 +33,72 @@ impl RustComputeShader {
         self.capabilities.push(capability);
         self
     }
+
+    /// Builds the shader, returning the SPIR-V bytes and the names of all of
+    /// its entry points (unlike `SpirvShader::spirv_bytes`, which only allows
+    /// shaders with exactly one entry point).
+    pub fn spirv_bytes_with_entry_points(&self) -> anyhow::Result<(Vec<u8>, Vec<String>)> {
+        build_spirv(&self.path, &self.target, &self.capabilities)
+    }
 }
 
-impl SpirvShader for RustComputeShader {
-    fn spirv_bytes(&self) -> anyhow::Result<(Vec<u8>, String)> {
-        let mut builder = SpirvBuilder::new(&self.path, &self.target)
-            .release(true)
-            .multimodule(false)
-            .shader_panic_strategy(spirv_builder::ShaderPanicStrategy::SilentExit)
-            .preserve_bindings(true);
-
-        for capability in &self.capabilities {
-            builder = builder.capability(*capability);
+/// Builds the shader crate at `path`, returning the SPIR-V bytes and the names
+/// of all of its entry points.
+fn build_spirv(
+    path: &Path,
+    target: &str,
+    capabilities: &[spirv_builder::Capability],
+) -> anyhow::Result<(Vec<u8>, Vec<String>)> {
+    let mut builder = SpirvBuilder::new(path, target)
+        .release(true)
+        .multimodule(false)
+        .shader_panic_strategy(spirv_builder::ShaderPanicStrategy::SilentExit)
+        .preserve_bindings(true);
+
+    for capability in capabilities {
+        builder = builder.capability(*capability);
+    }
+
+    let artifact = builder.build().context("SpirvBuilder::build() failed")?;
+
+    let shader_bytes = match artifact.module {
+        ModuleResult::SingleModule(path) => fs::read(&path)
+            .with_context(|| format!("reading spv file '{}' failed", path.display()))?,
+        ModuleResult::MultiModule(_modules) => {
+            anyhow::bail!("MultiModule modules produced");
         }
+    };
 
-        let artifact = builder.build().context("SpirvBuilder::build() failed")?;
+    Ok((shader_bytes, artifact.entry_points))
+}
+
+fn create_spirv_module(
+    device: &wgpu::Device,
+    shader_bytes: &[u8],
+    label: &str,
+) -> anyhow::Result<wgpu::ShaderModule> {
+    if !shader_bytes.len().is_multiple_of(4) {
+        anyhow::bail!("SPIR-V binary length is not a multiple of 4");
+    }
+    let shader_words: Vec<u32> = bytemuck::cast_slice(shader_bytes).to_vec();
+    Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::SpirV(Cow::Owned(shader_words)),
+    }))
+}
+
+impl SpirvShader for RustComputeShader {
+    fn spirv_bytes(&self) -> anyhow::Result<(Vec<u8>, String)> {
+        let (shader_bytes, entry_points) =
+            build_spirv(&self.path, &self.target, &self.capabilities)?;
 
-        if artifact.entry_points.len() != 1 {
+        if entry_points.len() != 1 {
             anyhow::bail!(
                 "Expected exactly one entry point, found {}",
-                artifact.entry_points.len()
+                entry_points.len()
             );
         }
-        let entry_point = artifact.entry_points.into_iter().next().unwrap();
-
-        let shader_bytes = match artifact.module {
-            ModuleResult::SingleModule(path) => fs::read(&path)
-                .with_context(|| format!("reading spv file '{}' failed", path.display()))?,
-            ModuleResult::MultiModule(_modules) => {
-                anyhow::bail!("MultiModule modules produced");
-            }
-        };
+        let entry_point = entry_points.into_iter().next().unwrap();
 
         Ok((shader_bytes, entry_point))
     }
@@ -75,17 +110,13 @@ impl WgpuShader for RustComputeShader {
         device: &wgpu::Device,
     ) -> anyhow::Result<(wgpu::ShaderModule, Option<String>)> {
         let (shader_bytes, entry_point) = self.spirv_bytes()?;
-
-        if !shader_bytes.len().is_multiple_of(4) {
-            anyhow::bail!("SPIR-V binary length is not a multiple of 4");
-        }
-        let shader_words: Vec<u32> = bytemuck::cast_slice(&shader_bytes).to_vec();
-        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Compute Shader"),
-            source: wgpu::ShaderSource::SpirV(Cow::Owned(shader_words)),
-        });
+        let module = create_spirv_module(device, &shader_bytes, "Compute Shader")?;
         Ok((module, Some(entry_point)))
     }
+
+    fn spirv_shader(&self) -> Option<&dyn SpirvShader> {
+        Some(self)
+    }
 }
 
 /// For the SPIR-V shader, the manifest directory is used as the build path.
@@ -95,3 +126,74 @@ impl Default for RustComputeShader {
         Self::new(PathBuf::from(manifest_dir))
     }
 }
+
+/// A vertex and fragment shader pair written in Rust (as two entry-points of
+/// the same crate) compiled with spirv-builder.
+pub struct RustRenderShader {
+    pub path: PathBuf,
+    pub target: String,
+    pub capabilities: Vec<spirv_builder::Capability>,
+    pub vertex_entry_point: String,
+    pub fragment_entry_point: String,
+}
+
+impl RustRenderShader {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            target: "spirv-unknown-vulkan1.1".to_string(),
+            capabilities: Vec::new(),
+            vertex_entry_point: "main_vs".to_string(),
+            fragment_entry_point: "main_fs".to_string(),
+        }
+    }
+
+    pub fn with_entry_points(
+        mut self,
+        vertex: impl Into<String>,
+        fragment: impl Into<String>,
+    ) -> Self {
+        self.vertex_entry_point = vertex.into();
+        self.fragment_entry_point = fragment.into();
+        self
+    }
+
+    pub fn with_capability(mut self, capability: spirv_builder::Capability) -> Self {
+        self.capabilities.push(capability);
+        self
+    }
+}
+
+impl WgpuRenderShader for RustRenderShader {
+    fn create_module(
+        &self,
+        device: &wgpu::Device,
+    ) -> anyhow::Result<(wgpu::ShaderModule, String, String)> {
+        let (shader_bytes, entry_points) =
+            build_spirv(&self.path, &self.target, &self.capabilities)?;
+
+        for entry_point in [&self.vertex_entry_point, &self.fragment_entry_point] {
+            if !entry_points.contains(entry_point) {
+                anyhow::bail!(
+                    "Entry point `{entry_point}` not found, found {}",
+                    entry_points.join(", ")
+                );
+            }
+        }
+
+        let module = create_spirv_module(device, &shader_bytes, "Render Shader")?;
+        Ok((
+            module,
+            self.vertex_entry_point.clone(),
+            self.fragment_entry_point.clone(),
+        ))
+    }
+}
+
+/// For the SPIR-V shader, the manifest directory is used as the build path.
+impl Default for RustRenderShader {
+    fn default() -> Self {
+        let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+        Self::new(PathBuf::from(manifest_dir))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::scaffold::shader::WgpuShader;
+use crate::scaffold::shader::{WgpuRenderShader, WgpuShader};
 use anyhow::Context;
 use std::borrow::Cow;
 use std::path::PathBuf;
@@ -67,3 +67,55 @@ impl Default for WgslComputeShader {
         Self::new(file, entry_point)
     }
 }
+
+/// A WGSL vertex and fragment shader pair (as two entry points of the same file).
+pub struct WgslRenderShader {
+    pub path: PathBuf,
+    pub vertex_entry_point: String,
+    pub fragment_entry_point: String,
+}
+
+impl WgslRenderShader {
+    pub fn new<P: Into<PathBuf>>(
+        path: P,
+        vertex_entry_point: impl Into<String>,
+        fragment_entry_point: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            vertex_entry_point: vertex_entry_point.into(),
+            fragment_entry_point: fragment_entry_point.into(),
+        }
+    }
+}
+
+impl WgpuRenderShader for WgslRenderShader {
+    fn create_module(
+        &self,
+        device: &wgpu::Device,
+    ) -> anyhow::Result<(wgpu::ShaderModule, String, String)> {
+        let shader_source = fs::read_to_string(&self.path)
+            .with_context(|| format!("reading wgsl source file '{}'", &self.path.display()))?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Render Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(shader_source)),
+        });
+        Ok((
+            module,
+            self.vertex_entry_point.clone(),
+            self.fragment_entry_point.clone(),
+        ))
+    }
+}
+
+/// For WGSL, the code uses "shader.wgsl", with `main_vs` and `main_fs` entry points.
+impl Default for WgslRenderShader {
+    fn default() -> Self {
+        let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+        Self::new(
+            PathBuf::from(manifest_dir).join("shader.wgsl"),
+            "main_vs",
+            "main_fs",
+        )
+    }
+}
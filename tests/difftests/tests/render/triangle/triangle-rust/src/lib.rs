@@ -0,0 +1,31 @@
+#![no_std]
+
+use spirv_std::glam::{Vec2, Vec3, Vec4};
+use spirv_std::spirv;
+
+#[spirv(vertex)]
+pub fn main_vs(
+    position: Vec2,
+    color: Vec3,
+    #[spirv(vertex_index)] vertex_index: u32,
+    #[spirv(position)] out_position: &mut Vec4,
+    out_color: &mut Vec3,
+    #[spirv(flat)] out_vertex_index: &mut u32,
+) {
+    *out_position = position.extend(0.0).extend(1.0);
+    *out_color = color;
+    *out_vertex_index = vertex_index;
+}
+
+#[spirv(fragment)]
+pub fn main_fs(
+    #[spirv(frag_coord)] frag_coord: Vec4,
+    color: Vec3,
+    #[spirv(flat)] vertex_index: u32,
+    output: &mut Vec4,
+) {
+    // Checkerboard the alpha channel, to also cover `frag_coord`.
+    let checker = ((frag_coord.x as u32 / 8) + (frag_coord.y as u32 / 8)) % 2;
+    let alpha = 1.0 - 0.25 * (vertex_index + checker) as f32;
+    *output = color.extend(alpha);
+}
@@ -0,0 +1,30 @@
+use difftest::config::Config;
+use difftest::scaffold::render::{
+    ImageFormat, RustRenderShader, VertexBufferConfig, WgpuRenderTest,
+};
+
+fn main() {
+    let config = Config::from_path(std::env::args().nth(1).unwrap()).unwrap();
+
+    // Position and color of each vertex.
+    let vertices: [[f32; 5]; 3] = [
+        [-0.75, -0.75, 1.0, 0.0, 0.0],
+        [0.75, -0.5, 0.0, 1.0, 0.0],
+        [0.0, 0.75, 0.0, 0.0, 1.0],
+    ];
+
+    let test = WgpuRenderTest::new(
+        RustRenderShader::default(),
+        [64, 64],
+        ImageFormat::Rgba8Unorm,
+        3,
+    )
+    .with_vertex_buffer(VertexBufferConfig::new(
+        &vertices,
+        difftest::wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3],
+    ))
+    // Allow for rounding differences in the interpolated colors.
+    .with_epsilon(1.0 / 255.0);
+
+    test.run_test(&config).unwrap();
+}
@@ -0,0 +1,26 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) @interpolate(flat) vertex_index: u32,
+}
+
+@vertex
+fn main_vs(
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.color = color;
+    out.vertex_index = vertex_index;
+    return out;
+}
+
+@fragment
+fn main_fs(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Checkerboard the alpha channel, to also cover `frag_coord`.
+    let checker = ((u32(in.position.x) / 8u) + (u32(in.position.y) / 8u)) % 2u;
+    let alpha = 1.0 - 0.25 * f32(in.vertex_index + checker);
+    return vec4<f32>(in.color, alpha);
+}
@@ -0,0 +1,760 @@
+//! The (tiny) subset of Rust that generated kernels are written in, and its
+//! rendering to source code.
+//!
+//! Everything is constructed to be well-typed, and free of panics and UB, so
+//! that any difference between the host and SPIR-V results is a bug: integer
+//! arithmetic wraps, divisors are forced to be non-zero, shift amounts are
+//! masked, array indices are masked to the (power of two) array length, and
+//! every loop has a constant trip count.
+
+use std::fmt::{self, Write};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Ty {
+    U32,
+    I32,
+    F32,
+    Bool,
+}
+
+impl Ty {
+    pub const ALL: [Self; 4] = [Self::U32, Self::I32, Self::F32, Self::Bool];
+    pub const NUMERIC: [Self; 3] = [Self::U32, Self::I32, Self::F32];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::U32 => "u32",
+            Self::I32 => "i32",
+            Self::F32 => "f32",
+            Self::Bool => "bool",
+        }
+    }
+
+    pub fn is_int(self) -> bool {
+        matches!(self, Self::U32 | Self::I32)
+    }
+}
+
+/// Number of input words per case (a power of two, as indices get masked).
+pub const INPUT_LEN: u32 = 8;
+
+/// Variables are only ever referred to by their (unique) id, rendered as `v{id}`.
+pub type VarId = usize;
+
+/// The variants of the `Choice` enum every kernel declares, and their fields
+/// (if enabled, see `Program::enum_payloads`).
+pub const CHOICE_VARIANTS: [(&str, &[Ty]); 3] =
+    [("A", &[Ty::U32]), ("B", &[Ty::I32, Ty::F32]), ("C", &[])];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum UnaryOp {
+    /// `!x`, for integers and `bool`s.
+    Not,
+    /// `x.wrapping_neg()` for `i32`, `-x` for `f32`.
+    Neg,
+    /// `x.wrapping_abs()`, for `i32`.
+    Abs,
+    /// `x.count_ones()`, for integers (always `u32`).
+    CountOnes,
+    /// `x.leading_zeros()`, for integers (always `u32`).
+    LeadingZeros,
+    /// `x.trailing_zeros()`, for integers (always `u32`).
+    TrailingZeros,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    /// Integer division uses `| 1` on the divisor to keep it non-zero.
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// Shifts and rotates take a `u32` amount, and use the wrapping variants.
+    Shl,
+    Shr,
+    RotateLeft,
+    Min,
+    Max,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `&&`/`||`, for `bool`s.
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub const INT: [Self; 13] = [
+        Self::Add,
+        Self::Sub,
+        Self::Mul,
+        Self::Div,
+        Self::Rem,
+        Self::BitAnd,
+        Self::BitOr,
+        Self::BitXor,
+        Self::Shl,
+        Self::Shr,
+        Self::RotateLeft,
+        Self::Min,
+        Self::Max,
+    ];
+    pub const FLOAT: [Self; 7] = [
+        Self::Add,
+        Self::Sub,
+        Self::Mul,
+        Self::Div,
+        Self::Rem,
+        Self::Min,
+        Self::Max,
+    ];
+    pub const BOOL: [Self; 5] = [Self::And, Self::Or, Self::BitXor, Self::Eq, Self::Ne];
+    pub const CMP: [Self; 6] = [Self::Eq, Self::Ne, Self::Lt, Self::Le, Self::Gt, Self::Ge];
+
+    pub fn is_cmp(self) -> bool {
+        Self::CMP.contains(&self)
+    }
+
+    /// Whether the right-hand side is a `u32` amount, instead of having the
+    /// same type as the left-hand side.
+    pub fn has_amount_rhs(self) -> bool {
+        matches!(self, Self::Shl | Self::Shr | Self::RotateLeft)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    /// Literal, holding the bits of its value (`0` or `1` for `bool`s).
+    Lit(Ty, u32),
+    Var(Ty, VarId),
+    /// A word of the kernel input (with a `u32` index, masked to the input
+    /// length), reinterpreted as `Ty`.
+    Input(Ty, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// `as` conversion between numeric types (or from `bool`).
+    Cast(Ty, Box<Expr>),
+    /// `if cond { a } else { b }`.
+    Select(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Read of an array variable, with a `u32` index masked to its length.
+    Index {
+        elem: Ty,
+        array: VarId,
+        len: u32,
+        index: Box<Expr>,
+    },
+    /// `match` on a `Choice` variable, with one arm per variant (binding the
+    /// variables in `bindings` to its fields).
+    Match {
+        ty: Ty,
+        scrutinee: VarId,
+        arms: Vec<(Vec<VarId>, Expr)>,
+    },
+}
+
+impl Expr {
+    pub fn ty(&self) -> Ty {
+        match self {
+            Self::Lit(ty, _)
+            | Self::Var(ty, _)
+            | Self::Input(ty, _)
+            | Self::Cast(ty, _)
+            | Self::Index { elem: ty, .. }
+            | Self::Match { ty, .. } => *ty,
+            Self::Unary(op, x) => match op {
+                UnaryOp::CountOnes | UnaryOp::LeadingZeros | UnaryOp::TrailingZeros => Ty::U32,
+                UnaryOp::Not | UnaryOp::Neg | UnaryOp::Abs => x.ty(),
+            },
+            Self::Binary(op, a, _) => {
+                if op.is_cmp() {
+                    Ty::Bool
+                } else {
+                    a.ty()
+                }
+            }
+            Self::Select(_, a, _) => a.ty(),
+        }
+    }
+
+    /// The zero (or `false`) literal of type `ty`.
+    pub fn zero(ty: Ty) -> Self {
+        Self::Lit(ty, 0)
+    }
+
+    pub fn children(&self) -> Vec<&Self> {
+        match self {
+            Self::Lit(..) | Self::Var(..) => vec![],
+            Self::Input(_, x) | Self::Unary(_, x) | Self::Cast(_, x) => vec![x],
+            Self::Index { index, .. } => vec![index],
+            Self::Binary(_, a, b) => vec![a, b],
+            Self::Select(c, a, b) => vec![c, a, b],
+            Self::Match { arms, .. } => arms.iter().map(|(_, arm)| arm).collect(),
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            Self::Lit(..) | Self::Var(..) => vec![],
+            Self::Input(_, x) | Self::Unary(_, x) | Self::Cast(_, x) => vec![x],
+            Self::Index { index, .. } => vec![index],
+            Self::Binary(_, a, b) => vec![a, b],
+            Self::Select(c, a, b) => vec![c, a, b],
+            Self::Match { arms, .. } => arms.iter_mut().map(|(_, arm)| arm).collect(),
+        }
+    }
+
+    /// Number of nodes in this expression.
+    pub fn size(&self) -> usize {
+        1 + self.children().into_iter().map(Self::size).sum::<usize>()
+    }
+
+    pub fn uses_var(&self, var: VarId) -> bool {
+        match self {
+            Self::Var(_, v) => *v == var,
+            Self::Index { array, .. } if *array == var => true,
+            Self::Match { scrutinee, .. } if *scrutinee == var => true,
+            _ => self.children().into_iter().any(|child| child.uses_var(var)),
+        }
+    }
+}
+
+/// A `Choice` enum value, i.e. the variant index and its field values.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Variant {
+    pub index: usize,
+    pub fields: Vec<Expr>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Stmt {
+    Let {
+        var: VarId,
+        mutable: bool,
+        init: Expr,
+    },
+    Assign {
+        var: VarId,
+        value: Expr,
+    },
+    /// `let mut v = [..];`, with a power of two number of elements.
+    LetArray {
+        var: VarId,
+        elem: Ty,
+        init: Vec<Expr>,
+    },
+    /// Write to an array variable, with a `u32` index masked to its length.
+    Store {
+        array: VarId,
+        len: u32,
+        index: Expr,
+        value: Expr,
+    },
+    /// `let v = if cond { Choice::.. } else { Choice::.. };`.
+    LetChoice {
+        var: VarId,
+        cond: Expr,
+        then_variant: Variant,
+        else_variant: Variant,
+    },
+    If {
+        cond: Expr,
+        then_body: Vec<Stmt>,
+        else_body: Vec<Stmt>,
+    },
+    /// `for v in 0..count { .. }`.
+    For {
+        var: VarId,
+        count: u32,
+        body: Vec<Stmt>,
+    },
+    /// `let mut v = 0; while v < count { v += 1; .. }`.
+    While {
+        var: VarId,
+        count: u32,
+        body: Vec<Stmt>,
+    },
+    /// `if cond { break; }`, only inside loops.
+    Break(Expr),
+    /// `if cond { continue; }`, only inside loops.
+    Continue(Expr),
+    /// Write to the kernel output (converting the value to its bits).
+    Output {
+        slot: usize,
+        value: Expr,
+    },
+}
+
+impl Stmt {
+    /// The blocks nested in this statement.
+    pub fn bodies(&self) -> Vec<&Vec<Self>> {
+        match self {
+            Self::If {
+                then_body,
+                else_body,
+                ..
+            } => vec![then_body, else_body],
+            Self::For { body, .. } | Self::While { body, .. } => vec![body],
+            _ => vec![],
+        }
+    }
+
+    pub fn bodies_mut(&mut self) -> Vec<&mut Vec<Self>> {
+        match self {
+            Self::If {
+                then_body,
+                else_body,
+                ..
+            } => vec![then_body, else_body],
+            Self::For { body, .. } | Self::While { body, .. } => vec![body],
+            _ => vec![],
+        }
+    }
+
+    /// The expressions directly in this statement (i.e. not in nested blocks).
+    pub fn exprs_mut(&mut self) -> Vec<&mut Expr> {
+        match self {
+            Self::Let { init: e, .. }
+            | Self::Assign { value: e, .. }
+            | Self::Break(e)
+            | Self::Continue(e)
+            | Self::Output { value: e, .. }
+            | Self::If { cond: e, .. } => vec![e],
+            Self::LetArray { init, .. } => init.iter_mut().collect(),
+            Self::Store { index, value, .. } => vec![index, value],
+            Self::LetChoice {
+                cond,
+                then_variant,
+                else_variant,
+                ..
+            } => std::iter::once(cond)
+                .chain(&mut then_variant.fields)
+                .chain(&mut else_variant.fields)
+                .collect(),
+            Self::For { .. } | Self::While { .. } => vec![],
+        }
+    }
+
+    pub fn exprs(&self) -> Vec<&Expr> {
+        match self {
+            Self::Let { init: e, .. }
+            | Self::Assign { value: e, .. }
+            | Self::Break(e)
+            | Self::Continue(e)
+            | Self::Output { value: e, .. }
+            | Self::If { cond: e, .. } => vec![e],
+            Self::LetArray { init, .. } => init.iter().collect(),
+            Self::Store { index, value, .. } => vec![index, value],
+            Self::LetChoice {
+                cond,
+                then_variant,
+                else_variant,
+                ..
+            } => std::iter::once(cond)
+                .chain(&then_variant.fields)
+                .chain(&else_variant.fields)
+                .collect(),
+            Self::For { .. } | Self::While { .. } => vec![],
+        }
+    }
+
+    /// Number of statements and expression nodes in this statement.
+    pub fn size(&self) -> usize {
+        1 + self.exprs().into_iter().map(Expr::size).sum::<usize>()
+            + self
+                .bodies()
+                .into_iter()
+                .flatten()
+                .map(Self::size)
+                .sum::<usize>()
+    }
+
+    /// The variable declared by this statement (visible to the statements
+    /// following it in the same block), if any.
+    pub fn declared_var(&self) -> Option<VarId> {
+        match self {
+            Self::Let { var, .. } | Self::LetArray { var, .. } | Self::LetChoice { var, .. } => {
+                Some(*var)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn uses_var(&self, var: VarId) -> bool {
+        let uses_directly = match self {
+            Self::Assign { var: v, .. } | Self::Store { array: v, .. } => *v == var,
+            _ => false,
+        };
+        uses_directly
+            || self.exprs().into_iter().any(|e| e.uses_var(var))
+            || self
+                .bodies()
+                .into_iter()
+                .flatten()
+                .any(|stmt| stmt.uses_var(var))
+    }
+
+    /// Whether this statement has a `break` or `continue` for the innermost
+    /// loop around it (i.e. not counting those inside nested loops).
+    pub fn has_loop_control(&self) -> bool {
+        match self {
+            Self::Break(_) | Self::Continue(_) => true,
+            Self::If {
+                then_body,
+                else_body,
+                ..
+            } => then_body
+                .iter()
+                .chain(else_body)
+                .any(Self::has_loop_control),
+            _ => false,
+        }
+    }
+}
+
+/// A generated kernel, and the input it's run with.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Program {
+    pub seed: u64,
+    pub output_len: usize,
+    /// Whether `Choice` variants are declared with fields.
+    pub enum_payloads: bool,
+    /// The cases the kernel is run for, each with `INPUT_LEN` input words.
+    pub inputs: Vec<Vec<u32>>,
+    pub body: Vec<Stmt>,
+}
+
+impl Program {
+    pub fn size(&self) -> usize {
+        self.body.iter().map(Stmt::size).sum()
+    }
+
+    /// Renders the `lib.rs` of the kernel crate, containing the kernel and its
+    /// SPIR-V entry point (which runs one case per workgroup).
+    pub fn render_lib(&self) -> String {
+        let mut out = String::new();
+        let Self {
+            seed, output_len, ..
+        } = self;
+        writeln!(
+            out,
+            "//! Kernel generated by `cargo fuzztest` (seed {seed}).\n\
+             \n\
+             #![cfg_attr(target_arch = \"spirv\", no_std)]\n\
+             #![allow(unused, clippy::all)]\n\
+             \n\
+             use spirv_std::glam::UVec3;\n\
+             use spirv_std::spirv;\n\
+             \n\
+             pub const INPUT_LEN: usize = {INPUT_LEN};\n\
+             pub const OUTPUT_LEN: usize = {output_len};\n"
+        )
+        .unwrap();
+
+        // NOTE without `repr(u32)`, the discriminant would be an `u8`,
+        // which needs `OpCapability Int8` (not supported by e.g. wgpu).
+        out += "#[derive(Copy, Clone)]\n#[repr(u32)]\npub enum Choice {\n";
+        for (name, fields) in CHOICE_VARIANTS {
+            if fields.is_empty() || !self.enum_payloads {
+                writeln!(out, "    {name},").unwrap();
+            } else {
+                let fields: Vec<_> = fields.iter().map(|ty| ty.name()).collect();
+                writeln!(out, "    {name}({}),", fields.join(", ")).unwrap();
+            }
+        }
+        out += "}\n\n";
+
+        out += "/// NaNs are canonicalized, as their payload isn't consistent across targets,\n\
+                /// and so are zeros, as Vulkan doesn't require preserving their sign by default.\n\
+                fn f32_bits(x: f32) -> u32 {\n    \
+                    if x.is_nan() {\n        \
+                        0x7fc0_0000\n    \
+                    } else if x == 0.0 {\n        \
+                        0\n    \
+                    } else {\n        \
+                        x.to_bits()\n    \
+                    }\n\
+                }\n\n";
+
+        out += "pub fn kernel(input: &[u32; INPUT_LEN], output: &mut [u32; OUTPUT_LEN]) {\n";
+        render_block(&mut out, &self.body, self, 1);
+        out += "}\n\n";
+
+        out += "#[spirv(compute(threads(1)))]\n\
+                pub fn main_cs(\n    \
+                    #[spirv(workgroup_id)] id: UVec3,\n    \
+                    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] input: &[[u32; INPUT_LEN]],\n    \
+                    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] output: &mut [[u32; OUTPUT_LEN]],\n\
+                ) {\n    \
+                    let case = id.x as usize;\n    \
+                    kernel(&input[case], &mut output[case]);\n\
+                }\n";
+        out
+    }
+
+    /// Renders the `main.rs` of the kernel crate, which runs all cases on the
+    /// CPU, printing the flattened input and output words (one line each).
+    pub fn render_main(&self) -> String {
+        let mut out = String::new();
+        out += "//! Runs the kernel on the CPU, printing its input and output words.\n\n";
+        out += "use fuzz_kernel::{INPUT_LEN, OUTPUT_LEN, kernel};\n\n";
+        writeln!(
+            out,
+            "const INPUTS: [[u32; INPUT_LEN]; {}] = [",
+            self.inputs.len()
+        )
+        .unwrap();
+        for input in &self.inputs {
+            let words: Vec<_> = input.iter().map(|w| format!("{w:#010x}")).collect();
+            writeln!(out, "    [{}],", words.join(", ")).unwrap();
+        }
+        out += "];\n\n";
+        out += "fn main() {\n    \
+                    let mut outputs = [[0; OUTPUT_LEN]; INPUTS.len()];\n    \
+                    for (input, output) in INPUTS.iter().zip(&mut outputs) {\n        \
+                        kernel(input, output);\n    \
+                    }\n    \
+                    let input: Vec<_> = INPUTS.iter().flatten().map(u32::to_string).collect();\n    \
+                    let output: Vec<_> = outputs.iter().flatten().map(u32::to_string).collect();\n    \
+                    println!(\"input {}\", input.join(\" \"));\n    \
+                    println!(\"output {}\", output.join(\" \"));\n\
+                }\n";
+        out
+    }
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("    ");
+    }
+}
+
+fn render_block(out: &mut String, body: &[Stmt], program: &Program, depth: usize) {
+    for stmt in body {
+        render_stmt(out, stmt, program, depth);
+    }
+}
+
+fn render_stmt(out: &mut String, stmt: &Stmt, program: &Program, depth: usize) {
+    indent(out, depth);
+    match stmt {
+        Stmt::Let { var, mutable, init } => {
+            let mutability = if *mutable { "mut " } else { "" };
+            writeln!(
+                out,
+                "let {mutability}v{var}: {} = {init};",
+                init.ty().name()
+            )
+            .unwrap();
+        }
+        Stmt::Assign { var, value } => writeln!(out, "v{var} = {value};").unwrap(),
+        Stmt::LetArray { var, elem, init } => {
+            let init: Vec<_> = init.iter().map(|e| e.to_string()).collect();
+            writeln!(
+                out,
+                "let mut v{var}: [{}; {}] = [{}];",
+                elem.name(),
+                init.len(),
+                init.join(", ")
+            )
+            .unwrap();
+        }
+        Stmt::Store {
+            array,
+            len,
+            index,
+            value,
+        } => writeln!(out, "v{array}[{}] = {value};", MaskedIndex(index, *len)).unwrap(),
+        Stmt::LetChoice {
+            var,
+            cond,
+            then_variant,
+            else_variant,
+        } => writeln!(
+            out,
+            "let v{var} = if {cond} {{ {then_variant} }} else {{ {else_variant} }};"
+        )
+        .unwrap(),
+        Stmt::If {
+            cond,
+            then_body,
+            else_body,
+        } => {
+            writeln!(out, "if {cond} {{").unwrap();
+            render_block(out, then_body, program, depth + 1);
+            if !else_body.is_empty() {
+                indent(out, depth);
+                out.push_str("} else {\n");
+                render_block(out, else_body, program, depth + 1);
+            }
+            indent(out, depth);
+            out.push_str("}\n");
+        }
+        Stmt::For { var, count, body } => {
+            writeln!(out, "for v{var} in 0..{count}u32 {{").unwrap();
+            render_block(out, body, program, depth + 1);
+            indent(out, depth);
+            out.push_str("}\n");
+        }
+        Stmt::While { var, count, body } => {
+            writeln!(out, "let mut v{var} = 0u32;").unwrap();
+            indent(out, depth);
+            writeln!(out, "while v{var} < {count} {{").unwrap();
+            indent(out, depth + 1);
+            writeln!(out, "v{var} += 1;").unwrap();
+            render_block(out, body, program, depth + 1);
+            indent(out, depth);
+            out.push_str("}\n");
+        }
+        Stmt::Break(cond) => writeln!(out, "if {cond} {{ break; }}").unwrap(),
+        Stmt::Continue(cond) => writeln!(out, "if {cond} {{ continue; }}").unwrap(),
+        Stmt::Output { slot, value } => {
+            let bits = match value.ty() {
+                Ty::U32 => value.to_string(),
+                Ty::I32 | Ty::Bool => format!("({value} as u32)"),
+                Ty::F32 => format!("f32_bits({value})"),
+            };
+            writeln!(out, "output[{slot}] = {bits};").unwrap();
+        }
+    }
+}
+
+/// Renders `(index & (len - 1)) as usize`.
+struct MaskedIndex<'a>(&'a Expr, u32);
+
+impl fmt::Display for MaskedIndex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(index, len) = *self;
+        match index {
+            Expr::Lit(_, i) => write!(f, "{}", i & (len - 1)),
+            _ => write!(f, "({index} & {}) as usize", len - 1),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = CHOICE_VARIANTS[self.index];
+        write!(f, "Choice::{name}")?;
+        if !self.fields.is_empty() {
+            let fields: Vec<_> = self.fields.iter().map(|e| e.to_string()).collect();
+            write!(f, "({})", fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders `e` as a method call receiver, i.e. in parentheses unless it's a
+/// variable (to avoid e.g. `-1i32.wrapping_abs()` parsing as `-(1i32.wrapping_abs())`).
+struct Receiver<'a>(&'a Expr);
+
+impl fmt::Display for Receiver<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            e @ Expr::Var(..) => write!(f, "{e}"),
+            e => write!(f, "({e})"),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lit(ty, bits) => match ty {
+                Ty::U32 => write!(f, "{bits}u32"),
+                Ty::I32 => write!(f, "{}i32", *bits as i32),
+                Ty::F32 => write!(f, "{:?}f32", f32::from_bits(*bits)),
+                Ty::Bool => write!(f, "{}", *bits != 0),
+            },
+            Self::Var(_, var) => write!(f, "v{var}"),
+            Self::Input(ty, index) => {
+                let word = format!("input[{}]", MaskedIndex(index, INPUT_LEN));
+                match ty {
+                    Ty::U32 => write!(f, "{word}"),
+                    Ty::I32 => write!(f, "({word} as i32)"),
+                    Ty::F32 => write!(f, "f32::from_bits({word})"),
+                    Ty::Bool => write!(f, "({word} & 1 != 0)"),
+                }
+            }
+            Self::Unary(op, x) => match (op, x.ty()) {
+                (UnaryOp::Not, _) => write!(f, "!{}", Receiver(x)),
+                (UnaryOp::Neg, Ty::F32) => write!(f, "-{}", Receiver(x)),
+                (UnaryOp::Neg, _) => write!(f, "{}.wrapping_neg()", Receiver(x)),
+                (UnaryOp::Abs, _) => write!(f, "{}.wrapping_abs()", Receiver(x)),
+                (UnaryOp::CountOnes, _) => write!(f, "{}.count_ones()", Receiver(x)),
+                (UnaryOp::LeadingZeros, _) => write!(f, "{}.leading_zeros()", Receiver(x)),
+                (UnaryOp::TrailingZeros, _) => write!(f, "{}.trailing_zeros()", Receiver(x)),
+            },
+            Self::Binary(op, a, b) => {
+                let ty = a.ty();
+                let (ra, int) = (Receiver(a), ty.is_int());
+                match op {
+                    BinaryOp::Add if int => write!(f, "{ra}.wrapping_add({b})"),
+                    BinaryOp::Sub if int => write!(f, "{ra}.wrapping_sub({b})"),
+                    BinaryOp::Mul if int => write!(f, "{ra}.wrapping_mul({b})"),
+                    BinaryOp::Div if int => write!(f, "{ra}.wrapping_div({} | 1)", Receiver(b)),
+                    BinaryOp::Rem if int => write!(f, "{ra}.wrapping_rem({} | 1)", Receiver(b)),
+                    BinaryOp::Shl => write!(f, "{ra}.wrapping_shl({b})"),
+                    BinaryOp::Shr => write!(f, "{ra}.wrapping_shr({b})"),
+                    BinaryOp::RotateLeft => write!(f, "{ra}.rotate_left({b})"),
+                    BinaryOp::Min => write!(f, "{ra}.min({b})"),
+                    BinaryOp::Max => write!(f, "{ra}.max({b})"),
+                    _ => {
+                        let op = match op {
+                            BinaryOp::Add => "+",
+                            BinaryOp::Sub => "-",
+                            BinaryOp::Mul => "*",
+                            BinaryOp::Div => "/",
+                            BinaryOp::Rem => "%",
+                            BinaryOp::BitAnd => "&",
+                            BinaryOp::BitOr => "|",
+                            BinaryOp::BitXor => "^",
+                            BinaryOp::Eq => "==",
+                            BinaryOp::Ne => "!=",
+                            BinaryOp::Lt => "<",
+                            BinaryOp::Le => "<=",
+                            BinaryOp::Gt => ">",
+                            BinaryOp::Ge => ">=",
+                            BinaryOp::And => "&&",
+                            BinaryOp::Or => "||",
+                            BinaryOp::Shl
+                            | BinaryOp::Shr
+                            | BinaryOp::RotateLeft
+                            | BinaryOp::Min
+                            | BinaryOp::Max => unreachable!(),
+                        };
+                        write!(f, "({a} {op} {b})")
+                    }
+                }
+            }
+            Self::Cast(ty, x) => match x.ty() {
+                Ty::Bool if *ty == Ty::F32 => write!(f, "(({x} as u32) as f32)"),
+                _ => write!(f, "({x} as {})", ty.name()),
+            },
+            Self::Select(c, a, b) => write!(f, "(if {c} {{ {a} }} else {{ {b} }})"),
+            Self::Index {
+                array, len, index, ..
+            } => write!(f, "v{array}[{}]", MaskedIndex(index, *len)),
+            Self::Match {
+                scrutinee, arms, ..
+            } => {
+                write!(f, "(match v{scrutinee} {{ ")?;
+                for ((name, _), (bindings, arm)) in CHOICE_VARIANTS.iter().zip(arms) {
+                    write!(f, "Choice::{name}")?;
+                    if !bindings.is_empty() {
+                        let bindings: Vec<_> = bindings.iter().map(|v| format!("v{v}")).collect();
+                        write!(f, "({})", bindings.join(", "))?;
+                    }
+                    write!(f, " => {arm}, ")?;
+                }
+                write!(f, "}})")
+            }
+        }
+    }
+}
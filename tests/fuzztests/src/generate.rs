@@ -0,0 +1,565 @@
+//! Random generation of (well-typed) kernels.
+
+use crate::ast::{
+    BinaryOp, CHOICE_VARIANTS, Expr, INPUT_LEN, Program, Stmt, Ty, UnaryOp, VarId, Variant,
+};
+
+/// Small (`xorshift64*`) PRNG, so that a seed always generates the same kernel
+/// (regardless of any dependency's version).
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as u32
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        self.next_u32() as usize % n
+    }
+
+    /// `true` with probability `1 / n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+/// Options controlling what (and how much) gets generated.
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    /// Maximum number of statements per block (the top-level block gets twice as many).
+    pub max_stmts: usize,
+    /// Maximum nesting of `if`s and loops.
+    pub max_block_depth: usize,
+    /// Maximum nesting of expressions.
+    pub max_expr_depth: usize,
+    /// Maximum trip count of each loop.
+    pub max_trip_count: u32,
+    /// Whether `Choice` variants have fields, which rust-gpu doesn't support yet.
+    pub enum_payloads: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            max_stmts: 6,
+            max_block_depth: 3,
+            max_expr_depth: 4,
+            max_trip_count: 8,
+            enum_payloads: false,
+        }
+    }
+}
+
+/// Number of cases (i.e. different inputs) each kernel is run with.
+pub const CASES: usize = 4;
+/// Number of output words per case.
+pub const OUTPUT_LEN: usize = 8;
+
+/// "Interesting" `u32` input words, which are mixed with random ones.
+const SPECIAL_WORDS: [u32; 10] = [
+    0,
+    1,
+    2,
+    31,
+    32,
+    0x7fff_ffff,
+    0x8000_0000,
+    0xffff_ffff,
+    0xffff_fffe,
+    0x0001_0000,
+];
+
+/// Finite floats, used both for literals and input words. Subnormals are left
+/// out, as GPUs commonly flush them to zero.
+const SPECIAL_FLOATS: [f32; 12] = [
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    0.5,
+    2.0,
+    -2.5,
+    3.75,
+    100.0,
+    -1e6,
+    1e-7,
+    16_777_216.0,
+];
+
+#[derive(Copy, Clone)]
+enum VarKind {
+    Scalar { ty: Ty, mutable: bool },
+    Array { elem: Ty, len: u32 },
+    Choice,
+}
+
+#[derive(Copy, Clone)]
+struct Var {
+    id: VarId,
+    kind: VarKind,
+}
+
+pub struct Generator {
+    rng: Rng,
+    options: Options,
+    next_var: VarId,
+    /// Variables in scope, innermost scope last.
+    scopes: Vec<Vec<Var>>,
+    block_depth: usize,
+    loop_depth: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64, options: Options) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            options,
+            next_var: 0,
+            scopes: vec![],
+            block_depth: 0,
+            loop_depth: 0,
+        }
+    }
+
+    /// Generates a kernel, which writes all of its outputs at the end (in
+    /// addition to any other output writes in its body).
+    pub fn generate(seed: u64, options: Options) -> Program {
+        let mut this = Self::new(seed, options);
+        let inputs = (0..CASES)
+            .map(|_| (0..INPUT_LEN).map(|_| this.input_word()).collect())
+            .collect();
+
+        this.scopes.push(vec![]);
+        let mut body = this.block(2 * options.max_stmts);
+        for slot in 0..OUTPUT_LEN {
+            let ty = this.rng.pick(&Ty::ALL);
+            let value = this.expr(ty, 1);
+            body.push(Stmt::Output { slot, value });
+        }
+        this.scopes.pop();
+
+        Program {
+            seed,
+            output_len: OUTPUT_LEN,
+            enum_payloads: options.enum_payloads,
+            inputs,
+            body,
+        }
+    }
+
+    fn input_word(&mut self) -> u32 {
+        match self.rng.below(4) {
+            0 => self.rng.pick(&SPECIAL_WORDS),
+            1 => self.rng.pick(&SPECIAL_FLOATS).to_bits(),
+            2 => self.rng.next_u32() % 64,
+            _ => self.rng.next_u32(),
+        }
+    }
+
+    fn fresh_var(&mut self, kind: VarKind) -> VarId {
+        let id = self.next_var;
+        self.next_var += 1;
+        self.scopes.last_mut().unwrap().push(Var { id, kind });
+        id
+    }
+
+    fn vars(&self) -> impl Iterator<Item = Var> + '_ {
+        self.scopes.iter().flatten().copied()
+    }
+
+    fn pick_var(&mut self, filter: impl Fn(VarKind) -> bool) -> Option<Var> {
+        let candidates: Vec<_> = self.vars().filter(|var| filter(var.kind)).collect();
+        (!candidates.is_empty()).then(|| self.rng.pick(&candidates))
+    }
+
+    fn block(&mut self, max_stmts: usize) -> Vec<Stmt> {
+        let len = 1 + self.rng.below(max_stmts);
+        (0..len).map(|_| self.stmt()).collect()
+    }
+
+    /// Generates a nested block, with its own scope.
+    fn nested_block(&mut self) -> Vec<Stmt> {
+        self.scopes.push(vec![]);
+        self.block_depth += 1;
+        let body = self.block(self.options.max_stmts);
+        self.block_depth -= 1;
+        self.scopes.pop();
+        body
+    }
+
+    fn stmt(&mut self) -> Stmt {
+        let can_nest = self.block_depth < self.options.max_block_depth;
+        loop {
+            match self.rng.below(12) {
+                0..=2 => {
+                    let ty = self.rng.pick(&Ty::ALL);
+                    let init = self.expr(ty, 0);
+                    let mutable = self.rng.one_in(2);
+                    let var = self.fresh_var(VarKind::Scalar { ty, mutable });
+                    return Stmt::Let { var, mutable, init };
+                }
+                3 => {
+                    let Some(var) =
+                        self.pick_var(|kind| matches!(kind, VarKind::Scalar { mutable: true, .. }))
+                    else {
+                        continue;
+                    };
+                    let VarKind::Scalar { ty, .. } = var.kind else {
+                        unreachable!()
+                    };
+                    let value = self.expr(ty, 0);
+                    return Stmt::Assign { var: var.id, value };
+                }
+                4 => {
+                    let elem = self.rng.pick(&Ty::ALL);
+                    let len = 1 << (1 + self.rng.below(3));
+                    let init = (0..len).map(|_| self.expr(elem, 2)).collect();
+                    let var = self.fresh_var(VarKind::Array { elem, len });
+                    return Stmt::LetArray { var, elem, init };
+                }
+                5 => {
+                    let Some(var) = self.pick_var(|kind| matches!(kind, VarKind::Array { .. }))
+                    else {
+                        continue;
+                    };
+                    let VarKind::Array { elem, len } = var.kind else {
+                        unreachable!()
+                    };
+                    let index = self.expr(Ty::U32, 1);
+                    let value = self.expr(elem, 0);
+                    return Stmt::Store {
+                        array: var.id,
+                        len,
+                        index,
+                        value,
+                    };
+                }
+                6 => {
+                    let cond = self.expr(Ty::Bool, 1);
+                    let then_variant = self.variant();
+                    let else_variant = self.variant();
+                    let var = self.fresh_var(VarKind::Choice);
+                    return Stmt::LetChoice {
+                        var,
+                        cond,
+                        then_variant,
+                        else_variant,
+                    };
+                }
+                7 if can_nest => {
+                    let cond = self.expr(Ty::Bool, 0);
+                    let then_body = self.nested_block();
+                    let else_body = if self.rng.one_in(2) {
+                        self.nested_block()
+                    } else {
+                        vec![]
+                    };
+                    return Stmt::If {
+                        cond,
+                        then_body,
+                        else_body,
+                    };
+                }
+                8 if can_nest => {
+                    let count = self.rng.below(self.options.max_trip_count as usize + 1) as u32;
+                    let is_for = self.rng.one_in(2);
+
+                    // The loop variable is only in scope in the body.
+                    self.scopes.push(vec![]);
+                    let var = self.fresh_var(VarKind::Scalar {
+                        ty: Ty::U32,
+                        mutable: false,
+                    });
+                    self.loop_depth += 1;
+                    let body = self.nested_block();
+                    self.loop_depth -= 1;
+                    self.scopes.pop();
+
+                    return if is_for {
+                        Stmt::For { var, count, body }
+                    } else {
+                        Stmt::While { var, count, body }
+                    };
+                }
+                9 if self.loop_depth > 0 => {
+                    let cond = self.expr(Ty::Bool, 1);
+                    return if self.rng.one_in(2) {
+                        Stmt::Break(cond)
+                    } else {
+                        Stmt::Continue(cond)
+                    };
+                }
+                10 | 11 => {
+                    let slot = self.rng.below(OUTPUT_LEN);
+                    let ty = self.rng.pick(&Ty::ALL);
+                    let value = self.expr(ty, 0);
+                    return Stmt::Output { slot, value };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The field types of the `index`-th `Choice` variant.
+    fn choice_fields(&self, index: usize) -> &'static [Ty] {
+        if self.options.enum_payloads {
+            CHOICE_VARIANTS[index].1
+        } else {
+            &[]
+        }
+    }
+
+    fn variant(&mut self) -> Variant {
+        let index = self.rng.below(CHOICE_VARIANTS.len());
+        let fields = self
+            .choice_fields(index)
+            .iter()
+            .map(|&ty| self.expr(ty, 2))
+            .collect();
+        Variant { index, fields }
+    }
+
+    fn literal(&mut self, ty: Ty) -> Expr {
+        let bits = match ty {
+            Ty::U32 => match self.rng.below(3) {
+                0 => self.rng.pick(&SPECIAL_WORDS),
+                1 => self.rng.next_u32() % 16,
+                _ => self.rng.next_u32(),
+            },
+            Ty::I32 => match self.rng.below(3) {
+                0 => self.rng.pick(&SPECIAL_WORDS),
+                1 => (self.rng.next_u32() % 16).wrapping_sub(8),
+                _ => self.rng.next_u32(),
+            },
+            Ty::F32 => self.rng.pick(&SPECIAL_FLOATS).to_bits(),
+            Ty::Bool => self.rng.next_u32() & 1,
+        };
+        Expr::Lit(ty, bits)
+    }
+
+    /// A literal, variable or input word of type `ty`.
+    fn leaf(&mut self, ty: Ty) -> Expr {
+        match self.rng.below(3) {
+            0 => self.literal(ty),
+            1 => {
+                let var =
+                    self.pick_var(|kind| matches!(kind, VarKind::Scalar { ty: t, .. } if t == ty));
+                match var {
+                    Some(var) => Expr::Var(ty, var.id),
+                    None => self.literal(ty),
+                }
+            }
+            _ => {
+                let index = if self.rng.one_in(4) {
+                    self.leaf(Ty::U32)
+                } else {
+                    Expr::Lit(Ty::U32, self.rng.below(INPUT_LEN as usize) as u32)
+                };
+                Expr::Input(ty, Box::new(index))
+            }
+        }
+    }
+
+    fn expr(&mut self, ty: Ty, depth: usize) -> Expr {
+        if depth >= self.options.max_expr_depth || self.rng.one_in(4) {
+            return self.leaf(ty);
+        }
+        let depth = depth + 1;
+        let boxed = |this: &mut Self, ty| Box::new(this.expr(ty, depth));
+        loop {
+            match self.rng.below(10) {
+                // Unary operations.
+                0 => {
+                    let (op, operand_ty) = match ty {
+                        Ty::U32 => match self.rng.below(4) {
+                            0 => (UnaryOp::Not, Ty::U32),
+                            n => {
+                                let op = [
+                                    UnaryOp::CountOnes,
+                                    UnaryOp::LeadingZeros,
+                                    UnaryOp::TrailingZeros,
+                                ][n - 1];
+                                (op, self.rng.pick(&[Ty::U32, Ty::I32]))
+                            }
+                        },
+                        Ty::I32 => (
+                            self.rng.pick(&[UnaryOp::Not, UnaryOp::Neg, UnaryOp::Abs]),
+                            Ty::I32,
+                        ),
+                        Ty::F32 => (UnaryOp::Neg, Ty::F32),
+                        Ty::Bool => (UnaryOp::Not, Ty::Bool),
+                    };
+                    return Expr::Unary(op, boxed(self, operand_ty));
+                }
+                // Binary operations.
+                1..=3 => {
+                    let (op, operand_ty) = match ty {
+                        Ty::U32 | Ty::I32 => (self.rng.pick(&BinaryOp::INT), ty),
+                        Ty::F32 => (self.rng.pick(&BinaryOp::FLOAT), ty),
+                        Ty::Bool => {
+                            if self.rng.one_in(2) {
+                                (self.rng.pick(&BinaryOp::BOOL), Ty::Bool)
+                            } else {
+                                (self.rng.pick(&BinaryOp::CMP), self.rng.pick(&Ty::NUMERIC))
+                            }
+                        }
+                    };
+                    let rhs_ty = if op.has_amount_rhs() {
+                        Ty::U32
+                    } else {
+                        operand_ty
+                    };
+                    return Expr::Binary(op, boxed(self, operand_ty), boxed(self, rhs_ty));
+                }
+                // Casts.
+                4 if ty != Ty::Bool => {
+                    let from = self.rng.pick(&Ty::ALL);
+                    if from == ty {
+                        continue;
+                    }
+                    return Expr::Cast(ty, boxed(self, from));
+                }
+                5 => {
+                    return Expr::Select(boxed(self, Ty::Bool), boxed(self, ty), boxed(self, ty));
+                }
+                6 => {
+                    let Some(var) = self
+                        .pick_var(|kind| matches!(kind, VarKind::Array { elem, .. } if elem == ty))
+                    else {
+                        continue;
+                    };
+                    let VarKind::Array { elem, len } = var.kind else {
+                        unreachable!()
+                    };
+                    return Expr::Index {
+                        elem,
+                        array: var.id,
+                        len,
+                        index: boxed(self, Ty::U32),
+                    };
+                }
+                7 => {
+                    let Some(var) = self.pick_var(|kind| matches!(kind, VarKind::Choice)) else {
+                        continue;
+                    };
+                    let arms = (0..CHOICE_VARIANTS.len())
+                        .map(|index| {
+                            // The bindings are only in scope in their arm.
+                            self.scopes.push(vec![]);
+                            let bindings = self
+                                .choice_fields(index)
+                                .iter()
+                                .map(|&ty| self.fresh_var(VarKind::Scalar { ty, mutable: false }))
+                                .collect();
+                            let arm = self.expr(ty, depth);
+                            self.scopes.pop();
+                            (bindings, arm)
+                        })
+                        .collect();
+                    return Expr::Match {
+                        ty,
+                        scrutinee: var.id,
+                        arms,
+                    };
+                }
+                _ => return self.leaf(ty),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let options = Options::default();
+        for seed in 0..20 {
+            assert_eq!(
+                Generator::generate(seed, options),
+                Generator::generate(seed, options)
+            );
+        }
+        assert_ne!(
+            Generator::generate(0, options),
+            Generator::generate(1, options)
+        );
+    }
+
+    #[test]
+    fn test_generated_kernels_are_plausible() {
+        for seed in 0..50 {
+            let program = Generator::generate(seed, Options::default());
+            assert_eq!(program.inputs.len(), CASES);
+            assert!(
+                program
+                    .inputs
+                    .iter()
+                    .all(|input| input.len() == INPUT_LEN as usize)
+            );
+
+            let lib = program.render_lib();
+            assert!(lib.contains("pub fn kernel("));
+            assert!(lib.contains("#[spirv(compute(threads(1)))]"));
+            for slot in 0..OUTPUT_LEN {
+                assert!(lib.contains(&format!("output[{slot}] = ")));
+            }
+            let balanced = |open, close| lib.matches(open).count() == lib.matches(close).count();
+            assert!(balanced('(', ')') && balanced('{', '}') && balanced('[', ']'));
+
+            let main = program.render_main();
+            assert_eq!(main.matches("    [0x").count(), CASES);
+        }
+    }
+
+    #[test]
+    fn test_enum_payloads() {
+        let has_payloads = |enum_payloads| {
+            let options = Options {
+                enum_payloads,
+                ..Options::default()
+            };
+            (0..50).any(|seed| {
+                let lib = Generator::generate(seed, options).render_lib();
+                lib.contains("Choice::A(") || lib.contains("Choice::B(")
+            })
+        };
+        assert!(has_payloads(true));
+        assert!(!has_payloads(false));
+    }
+
+    #[test]
+    fn test_limits_are_respected() {
+        fn max_depth(body: &[Stmt]) -> usize {
+            body.iter()
+                .flat_map(|stmt| stmt.bodies())
+                .map(|body| 1 + max_depth(body))
+                .max()
+                .unwrap_or(0)
+        }
+        let options = Options {
+            max_stmts: 3,
+            max_block_depth: 1,
+            max_expr_depth: 2,
+            max_trip_count: 2,
+            enum_payloads: false,
+        };
+        for seed in 0..50 {
+            let program = Generator::generate(seed, options);
+            assert!(max_depth(&program.body) <= 1);
+            assert!(program.body.len() <= 2 * options.max_stmts + OUTPUT_LEN);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Generative fuzzing of rust-gpu: random (well-typed, and free of UB) kernels
+//! are compiled both natively and with rust-gpu, run on the CPU and with a
+//! SPIR-V backend, and any kernel with different results (or which rust-gpu,
+//! or the backend, fails on) is minimized into a reproducer crate.
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod ast;
+mod generate;
+mod minimize;
+mod run;
+
+use ast::Program;
+use generate::{Generator, Options};
+use run::{BackendKind, Outcome, Runner};
+
+#[derive(Parser)]
+#[command(bin_name = "cargo fuzztest")]
+struct Opt {
+    /// Seed of the first kernel to generate (the rest use consecutive seeds).
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of kernels to generate.
+    #[arg(long, default_value_t = 100)]
+    count: u64,
+
+    /// The backend to run the SPIR-V on.
+    #[arg(long, value_enum, default_value_t = BackendKind::Interpreter)]
+    backend: BackendKind,
+
+    /// Maximum number of candidates to try when minimizing a failing kernel
+    /// (`0` disables minimization).
+    #[arg(long, default_value_t = 500)]
+    max_minimize_attempts: usize,
+
+    /// Maximum number of statements per block.
+    #[arg(long, default_value_t = Options::default().max_stmts)]
+    max_stmts: usize,
+
+    /// Maximum nesting of `if`s and loops.
+    #[arg(long, default_value_t = Options::default().max_block_depth)]
+    max_block_depth: usize,
+
+    /// Generate enums with fields (which rust-gpu doesn't support yet).
+    #[arg(long)]
+    enum_payloads: bool,
+
+    /// Directory to build kernels in, and write reproducers to (defaults to
+    /// `target/fuzztests`).
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Print the kernel generated from `--seed`, instead of running anything.
+    #[arg(long)]
+    print: bool,
+
+    /// Rerun a reproducer crate, instead of generating kernels.
+    #[arg(long, conflicts_with = "print")]
+    reproduce: Option<PathBuf>,
+}
+
+impl Opt {
+    fn options(&self) -> Options {
+        Options {
+            max_stmts: self.max_stmts,
+            max_block_depth: self.max_block_depth,
+            enum_payloads: self.enum_payloads,
+            ..Options::default()
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::parse();
+
+    if opt.print {
+        let program = Generator::generate(opt.seed, opt.options());
+        print!("{}", program.render_lib());
+        return Ok(());
+    }
+
+    let out_dir = opt
+        .out_dir
+        .clone()
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target/fuzztests"));
+    fs::create_dir_all(&out_dir)?;
+    let out_dir = out_dir.canonicalize()?;
+    let runner = Runner::new(out_dir.join("work"), opt.backend)?;
+
+    if let Some(dir) = &opt.reproduce {
+        let outcome = runner.run_crate(dir)?;
+        println!("{}", outcome.summary());
+        print!("{}", details(&outcome));
+        if outcome.is_bug() {
+            bail!("the bug still reproduces");
+        }
+        return Ok(());
+    }
+
+    let mut failures = vec![];
+    for seed in opt.seed..opt.seed + opt.count {
+        let program = Generator::generate(seed, opt.options());
+        let outcome = runner.run(&program)?;
+        println!("seed {seed}: {}", outcome.summary());
+        match outcome {
+            Outcome::Pass => {}
+            Outcome::Invalid(_) => {
+                // Keep the kernel around, to fix the generator with.
+                let dir = out_dir.join(format!("invalid-{seed}"));
+                run::write_crate(&dir, &program)?;
+                println!("  written to {}", dir.display());
+                failures.push(seed);
+            }
+            _ => {
+                let minimized = if opt.max_minimize_attempts > 0 {
+                    println!("  minimizing (size {})", program.size());
+                    minimize::minimize(&program, &outcome, opt.max_minimize_attempts, |p| {
+                        runner.run(p)
+                    })?
+                } else {
+                    program
+                };
+                let dir = out_dir.join(format!("repro-{seed}"));
+                write_reproducer(&dir, &minimized, &runner)?;
+                println!("  reproducer written to {}", dir.display());
+                failures.push(seed);
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} kernels failed (seeds {failures:?})",
+            failures.len(),
+            opt.count
+        );
+    }
+    Ok(())
+}
+
+/// Writes the reproducer crate, with a `FAILURE.txt` describing what went
+/// wrong (after rerunning it, so it matches the minimized kernel).
+fn write_reproducer(dir: &Path, program: &Program, runner: &Runner) -> Result<()> {
+    run::write_crate(dir, program)?;
+    let outcome = runner.run_crate(dir)?;
+    let mut failure = format!(
+        "{}\n\n\
+         Reproduce with `cargo fuzztest --reproduce {}`\n\
+         (or `cargo run` in this directory, for the native output).\n",
+        outcome.summary(),
+        dir.display()
+    );
+    let details = details(&outcome);
+    if !details.is_empty() {
+        failure += "\n";
+        failure += &details;
+    }
+    fs::write(dir.join("FAILURE.txt"), failure)?;
+    Ok(())
+}
+
+/// The full error, or which output words differ.
+fn details(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Pass => String::new(),
+        Outcome::Invalid(err) | Outcome::CompileError(err) | Outcome::ExecError(err) => {
+            format!("{err}\n")
+        }
+        Outcome::Mismatch { native, spirv } => {
+            let mut out = String::new();
+            let words_per_case = native.len() / generate::CASES;
+            for (i, (a, b)) in native.iter().zip(spirv).enumerate() {
+                if a != b {
+                    let (case, slot) = (i / words_per_case, i % words_per_case);
+                    write!(
+                        out,
+                        "case {case}, output[{slot}]: native {a:#010x}, SPIR-V {b:#010x}"
+                    )
+                    .unwrap();
+                    if is_float_special(*a) || is_float_special(*b) {
+                        out += " (infinity or NaN, see the caveats in the fuzztests README)";
+                    }
+                    out += "\n";
+                }
+            }
+            out
+        }
+    }
+}
+
+/// Whether `word` is an infinite or (canonical) NaN `f32` (as it could also be
+/// an integer, this is just a hint).
+fn is_float_special(word: u32) -> bool {
+    matches!(word, 0x7f80_0000 | 0xff80_0000 | 0x7fc0_0000)
+}
@@ -0,0 +1,245 @@
+//! Greedy minimization of kernels which trigger a bug.
+//!
+//! Each pass can apply one of a number of edits to a kernel (numbered in a
+//! fixed order, from the outermost statements to the innermost expressions),
+//! and every edit that keeps the same bug (see `Outcome::same_bug`) is kept,
+//! until no edit of any pass does.
+//!
+//! Edits try to keep the kernel valid, but aren't guaranteed to, and any
+//! invalid kernel is simply rejected when the native build fails.
+
+use crate::ast::{Expr, Program, Stmt, Ty};
+use crate::run::Outcome;
+use anyhow::Result;
+
+/// Applies the `k`-th edit of a pass to `program`, returning `false` if there
+/// are at most `k` edits.
+type Pass = fn(&mut Program, usize) -> bool;
+
+const PASSES: [(&str, Pass); 3] = [
+    ("remove statements", |p, k| {
+        remove_stmt(&mut p.body, &mut { k })
+    }),
+    ("simplify statements", |p, k| {
+        simplify_stmt(&mut p.body, &mut { k })
+    }),
+    ("simplify expressions", |p, k| {
+        simplify_exprs(&mut p.body, &mut { k })
+    }),
+];
+
+/// Minimizes `program`, which has the bug `outcome`, by repeatedly calling
+/// `test` on smaller candidates (at most `max_attempts` times).
+pub fn minimize(
+    program: &Program,
+    outcome: &Outcome,
+    max_attempts: usize,
+    mut test: impl FnMut(&Program) -> Result<Outcome>,
+) -> Result<Program> {
+    let mut best = program.clone();
+    let mut attempts = 0;
+    loop {
+        let mut progress = false;
+        for (name, pass) in PASSES {
+            let mut k = 0;
+            loop {
+                let mut candidate = best.clone();
+                if !pass(&mut candidate, k) {
+                    break;
+                }
+                if attempts == max_attempts {
+                    return Ok(best);
+                }
+                attempts += 1;
+                if test(&candidate)?.same_bug(outcome) {
+                    if candidate.size() < best.size() {
+                        println!("  {name}: size {} -> {}", best.size(), candidate.size());
+                    }
+                    // The `k`-th edit is now a different one, so isn't skipped.
+                    best = candidate;
+                    progress = true;
+                } else {
+                    k += 1;
+                }
+            }
+        }
+        if !progress {
+            return Ok(best);
+        }
+    }
+}
+
+/// Whether `body[i]` can be removed without leaving any uses of its variable.
+fn is_removable(body: &[Stmt], i: usize) -> bool {
+    match body[i].declared_var() {
+        Some(var) => !body[i + 1..].iter().any(|stmt| stmt.uses_var(var)),
+        None => true,
+    }
+}
+
+fn remove_stmt(body: &mut Vec<Stmt>, k: &mut usize) -> bool {
+    for i in 0..body.len() {
+        if is_removable(body, i) {
+            if *k == 0 {
+                body.remove(i);
+                return true;
+            }
+            *k -= 1;
+        }
+    }
+    body.iter_mut()
+        .flat_map(Stmt::bodies_mut)
+        .any(|body| remove_stmt(body, k))
+}
+
+/// Simpler statements `stmt` can be replaced with.
+fn stmt_replacements(stmt: &Stmt) -> Vec<Vec<Stmt>> {
+    match stmt {
+        Stmt::If {
+            then_body,
+            else_body,
+            ..
+        } => [then_body, else_body]
+            .into_iter()
+            .filter(|body| !body.is_empty())
+            .cloned()
+            .collect(),
+        Stmt::For { var, count, body } | Stmt::While { var, count, body } => {
+            let mut replacements = vec![];
+            if !body
+                .iter()
+                .any(|stmt| stmt.uses_var(*var) || stmt.has_loop_control())
+            {
+                replacements.push(body.clone());
+            }
+            if *count > 1 {
+                let mut fewer_iterations = stmt.clone();
+                if let Stmt::For { count, .. } | Stmt::While { count, .. } = &mut fewer_iterations {
+                    *count /= 2;
+                }
+                replacements.push(vec![fewer_iterations]);
+            }
+            replacements
+        }
+        _ => vec![],
+    }
+}
+
+fn simplify_stmt(body: &mut Vec<Stmt>, k: &mut usize) -> bool {
+    for i in 0..body.len() {
+        let mut replacements = stmt_replacements(&body[i]);
+        if *k < replacements.len() {
+            body.splice(i..=i, replacements.swap_remove(*k));
+            return true;
+        }
+        *k -= replacements.len();
+        if body[i]
+            .bodies_mut()
+            .into_iter()
+            .any(|body| simplify_stmt(body, k))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Simpler expressions `expr` can be replaced with, i.e. a zero literal, or
+/// one of its operands of the same type.
+fn expr_replacements(expr: &Expr) -> Vec<Expr> {
+    let ty = expr.ty();
+    let mut replacements = vec![];
+    if *expr != Expr::zero(ty) {
+        replacements.push(Expr::zero(ty));
+    }
+    if let Expr::Lit(Ty::U32 | Ty::I32, bits) = *expr
+        && bits > 1
+    {
+        replacements.push(Expr::Lit(ty, 1));
+    }
+    let operands: Vec<&Expr> = match expr {
+        Expr::Match { arms, .. } => arms
+            .iter()
+            .filter(|(bindings, arm)| !bindings.iter().any(|&var| arm.uses_var(var)))
+            .map(|(_, arm)| arm)
+            .collect(),
+        _ => expr.children(),
+    };
+    replacements.extend(
+        operands
+            .into_iter()
+            .filter(|operand| operand.ty() == ty)
+            .cloned(),
+    );
+    replacements
+}
+
+fn simplify_expr(expr: &mut Expr, k: &mut usize) -> bool {
+    let mut replacements = expr_replacements(expr);
+    if *k < replacements.len() {
+        *expr = replacements.swap_remove(*k);
+        return true;
+    }
+    *k -= replacements.len();
+    expr.children_mut()
+        .into_iter()
+        .any(|child| simplify_expr(child, k))
+}
+
+fn simplify_exprs(body: &mut [Stmt], k: &mut usize) -> bool {
+    body.iter_mut().any(|stmt| {
+        stmt.exprs_mut()
+            .into_iter()
+            .any(|expr| simplify_expr(expr, k))
+            || stmt
+                .bodies_mut()
+                .into_iter()
+                .any(|body| simplify_exprs(body, k))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{Generator, Options};
+
+    /// Kernels using integer multiplication "fail".
+    fn has_mul(program: &Program) -> Outcome {
+        if program.render_lib().contains("wrapping_mul") {
+            Outcome::Mismatch {
+                native: vec![],
+                spirv: vec![],
+            }
+        } else {
+            Outcome::Pass
+        }
+    }
+
+    #[test]
+    fn test_minimize_keeps_bug() {
+        let failing = (0..)
+            .map(|seed| Generator::generate(seed, Options::default()))
+            .find(|program| has_mul(program).is_bug())
+            .unwrap();
+        let minimized =
+            minimize(&failing, &has_mul(&failing), usize::MAX, |p| Ok(has_mul(p))).unwrap();
+        assert!(has_mul(&minimized).is_bug());
+        assert!(minimized.size() < failing.size());
+        // Only the multiplication should be left (and a declaration it needs).
+        assert!(minimized.body.len() <= 2, "{}", minimized.render_lib());
+    }
+
+    #[test]
+    fn test_edits_are_exhaustive() {
+        let program = Generator::generate(7, Options::default());
+        for (_, pass) in PASSES {
+            let edits = (0..).take_while(|&k| pass(&mut program.clone(), k)).count();
+            // Every edit changes the kernel.
+            for k in 0..edits {
+                let mut candidate = program.clone();
+                pass(&mut candidate, k);
+                assert_ne!(candidate, program);
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Building and running a kernel, both natively and as SPIR-V.
+
+use crate::ast::{INPUT_LEN, Program};
+use crate::generate::CASES;
+use anyhow::{Context, Result, bail};
+use difftest::scaffold::compute::{
+    BufferConfig, ComputeBackend, InterpreterBackend, RustComputeShader, SpirvShader, WgpuBackend,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum BackendKind {
+    /// The SPIR-V interpreter from `difftest`, which doesn't need a GPU.
+    Interpreter,
+    /// The first GPU found by `wgpu`.
+    Wgpu,
+}
+
+enum Backend {
+    Interpreter(InterpreterBackend),
+    Wgpu(WgpuBackend),
+}
+
+impl Backend {
+    fn run_compute(
+        &self,
+        spirv_bytes: &[u8],
+        entry_point: &str,
+        dispatch: [u32; 3],
+        buffers: Vec<BufferConfig>,
+    ) -> Result<Vec<Vec<u8>>> {
+        match self {
+            Self::Interpreter(backend) => {
+                backend.run_compute(spirv_bytes, entry_point, dispatch, buffers)
+            }
+            Self::Wgpu(backend) => backend.run_compute(spirv_bytes, entry_point, dispatch, buffers),
+        }
+    }
+}
+
+/// The result of running a kernel (see `Runner::run`).
+#[derive(Debug)]
+pub enum Outcome {
+    /// The native and SPIR-V outputs are the same.
+    Pass,
+    /// The kernel failed to build or run natively, i.e. it's not actually a
+    /// valid kernel (which is a bug in the generator, or in the minimizer).
+    Invalid(String),
+    /// rust-gpu failed to compile the kernel.
+    CompileError(String),
+    /// The SPIR-V backend failed to run the kernel.
+    ExecError(String),
+    /// The native and SPIR-V outputs differ.
+    Mismatch { native: Vec<u32>, spirv: Vec<u32> },
+}
+
+impl Outcome {
+    pub fn is_bug(&self) -> bool {
+        matches!(
+            self,
+            Self::CompileError(_) | Self::ExecError(_) | Self::Mismatch { .. }
+        )
+    }
+
+    /// Whether `other` is (likely) the same bug, to keep the minimizer from
+    /// wandering off into a different one.
+    pub fn same_bug(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::CompileError(_), Self::CompileError(_))
+            | (Self::Mismatch { .. }, Self::Mismatch { .. }) => true,
+            (Self::ExecError(a), Self::ExecError(b)) => first_line(a) == first_line(b),
+            _ => false,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Pass => "pass".to_string(),
+            Self::Invalid(err) => format!("invalid kernel: {}", first_line(err)),
+            Self::CompileError(err) => format!("rust-gpu compile error: {}", first_line(err)),
+            Self::ExecError(err) => format!("SPIR-V execution error: {}", first_line(err)),
+            Self::Mismatch { native, spirv } => {
+                let diffs = native.iter().zip(spirv).filter(|(a, b)| a != b).count();
+                format!("output mismatch ({diffs} of {} words differ)", native.len())
+            }
+        }
+    }
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or_default()
+}
+
+/// Builds and runs kernels, reusing the same crate (in `work_dir`) for all of
+/// them, to avoid rebuilding its dependencies every time.
+pub struct Runner {
+    work_dir: PathBuf,
+    backend: Backend,
+}
+
+impl Runner {
+    pub fn new(work_dir: PathBuf, backend: BackendKind) -> Result<Self> {
+        let backend = match backend {
+            BackendKind::Interpreter => Backend::Interpreter(InterpreterBackend::init()?),
+            BackendKind::Wgpu => Backend::Wgpu(WgpuBackend::init()?),
+        };
+        Ok(Self { work_dir, backend })
+    }
+
+    pub fn run(&self, program: &Program) -> Result<Outcome> {
+        write_crate(&self.work_dir, program)?;
+        build_and_run(&self.work_dir, &self.backend)
+    }
+
+    /// Runs a crate written by `write_crate` (e.g. a reproducer).
+    pub fn run_crate(&self, dir: &Path) -> Result<Outcome> {
+        build_and_run(dir, &self.backend)
+    }
+}
+
+/// Writes `program` as a crate in `dir`, with a library containing the kernel
+/// and its SPIR-V entry point, and a binary running it on the CPU.
+pub fn write_crate(dir: &Path, program: &Program) -> Result<()> {
+    let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let spirv_std = workspace_root.join("crates/spirv-std");
+    let spirv_std = spirv_std
+        .canonicalize()
+        .with_context(|| format!("failed to find spirv-std at {}", spirv_std.display()))?;
+
+    fs::create_dir_all(dir.join("src"))?;
+
+    // Start from the workspace's lockfile, to use the same versions of
+    // spirv-std's dependencies as everything else.
+    let lockfile = dir.join("Cargo.lock");
+    let workspace_lockfile = workspace_root.join("Cargo.lock");
+    if !lockfile.exists() && workspace_lockfile.exists() {
+        fs::copy(workspace_lockfile, lockfile)?;
+    }
+
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\n\
+             name = \"fuzz-kernel\"\n\
+             version = \"0.0.0\"\n\
+             edition = \"2021\"\n\
+             publish = false\n\
+             \n\
+             [dependencies]\n\
+             spirv-std = {{ path = {:?} }}\n\
+             \n\
+             # Not part of any enclosing workspace.\n\
+             [workspace]\n",
+            spirv_std.display().to_string()
+        ),
+    )?;
+    fs::write(dir.join("src/lib.rs"), program.render_lib())?;
+    fs::write(dir.join("src/main.rs"), program.render_main())?;
+    Ok(())
+}
+
+fn build_and_run(dir: &Path, backend: &Backend) -> Result<Outcome> {
+    // Run natively first, which also checks that the kernel is valid, and gets
+    // the input (which is only embedded in the binary).
+    let output = Command::new(env!("CARGO"))
+        .args(["run", "--release", "--quiet", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .env_remove("CARGO_TARGET_DIR")
+        .output()
+        .context("failed to run cargo")?;
+    if !output.status.success() {
+        return Ok(Outcome::Invalid(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let words = |prefix: &str| -> Result<Vec<u32>> {
+        let line = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(prefix))
+            .with_context(|| format!("missing `{prefix}` line in the native output"))?;
+        Ok(line
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?)
+    };
+    let input = words("input ")?;
+    let native = words("output ")?;
+    if input.len() != CASES * INPUT_LEN as usize || native.len() % CASES != 0 {
+        bail!("unexpected number of input/output words in the native output");
+    }
+
+    let shader = RustComputeShader::with_target(dir, "spirv-unknown-vulkan1.2");
+    let (spirv_bytes, entry_point) = match shader.spirv_bytes() {
+        Ok(spirv) => spirv,
+        Err(err) => return Ok(Outcome::CompileError(format!("{err:#}"))),
+    };
+    let buffers = vec![
+        BufferConfig::read_only(&input),
+        BufferConfig::writeback(native.len() * 4),
+    ];
+    let spirv = match backend.run_compute(&spirv_bytes, &entry_point, [CASES as u32, 1, 1], buffers)
+    {
+        Ok(outputs) => outputs[1]
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect(),
+        Err(err) => return Ok(Outcome::ExecError(format!("{err:#}"))),
+    };
+
+    Ok(if native == spirv {
+        Outcome::Pass
+    } else {
+        Outcome::Mismatch { native, spirv }
+    })
+}
@@ -0,0 +1,128 @@
+//! Comparing results against the reference, as precisely as the intrinsic is
+//! specified (or as precisely as GPUs can be expected to implement it).
+
+/// How a result (of any number of words) has to match the reference.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// The words have to be the same.
+    Exact,
+    /// Every word is an `f32`, and may differ by up to `ulps` units in the
+    /// last place, while all NaNs are considered equal, as are `0.0` and
+    /// `-0.0`, and subnormals may be flushed to zero.
+    F32 { ulps: u32 },
+    /// Every word is two `f16`s, which may differ by one unit in the last
+    /// place (as converting to `f16` may round either to nearest, or towards
+    /// zero), and are otherwise compared like with `F32`.
+    F16x2,
+    /// Every word is made up of fields of this many bits, which may differ by
+    /// one (as `snorm`/`unorm` packing may round ties either way).
+    Fields(u32),
+}
+
+impl Check {
+    pub fn matches(self, expected: &[u32], actual: &[u32]) -> bool {
+        expected.len() == actual.len()
+            && expected
+                .iter()
+                .zip(actual)
+                .all(|(&expected, &actual)| self.word_matches(expected, actual))
+    }
+
+    fn word_matches(self, expected: u32, actual: u32) -> bool {
+        match self {
+            Self::Exact => expected == actual,
+            Self::F32 { ulps } => float_matches::<32>(expected, actual, ulps),
+            Self::F16x2 => (0..2).all(|i| {
+                let half = |word: u32| (word >> (i * 16)) & 0xffff;
+                float_matches::<16>(half(expected), half(actual), 1)
+            }),
+            Self::Fields(bits) => (0..32 / bits).all(|i| {
+                let mask = (1 << bits) - 1;
+                let field = |word: u32| (word >> (i * bits)) & mask;
+                let diff = field(expected).wrapping_sub(field(actual)) & mask;
+                diff <= 1 || diff == mask
+            }),
+        }
+    }
+}
+
+/// Whether the IEEE 754 floats with the given `BITS` (`16` or `32`) match,
+/// as described by `Check::F32`.
+fn float_matches<const BITS: u32>(expected: u32, actual: u32, ulps: u32) -> bool {
+    let mantissa_bits = if BITS == 16 { 10 } else { 23 };
+    let sign = 1 << (BITS - 1);
+    let mantissa_mask = (1 << mantissa_bits) - 1;
+    let exponent_mask = sign - 1 - mantissa_mask;
+    let is_nan = |x: u32| x & exponent_mask == exponent_mask && x & mantissa_mask != 0;
+    // NOTE zeros and subnormals are all flushed to (positive) zero.
+    let flush = |x: u32| if x & exponent_mask == 0 { 0 } else { x };
+
+    if is_nan(expected) || is_nan(actual) {
+        return is_nan(expected) && is_nan(actual);
+    }
+    let (expected, actual) = (flush(expected), flush(actual));
+    if expected == actual {
+        return true;
+    }
+    // Floats of the same sign are ordered like their bits, so the difference
+    // between their bits is the number of floats between them.
+    let is_infinite = |x: u32| x & !sign == exponent_mask;
+    expected & sign == actual & sign
+        && !is_infinite(expected)
+        && !is_infinite(actual)
+        && expected.abs_diff(actual) <= ulps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f32_matches(expected: f32, actual: f32, ulps: u32) -> bool {
+        Check::F32 { ulps }.matches(&[expected.to_bits()], &[actual.to_bits()])
+    }
+
+    #[test]
+    fn test_f32() {
+        assert!(f32_matches(1.0, 1.0, 0));
+        assert!(!f32_matches(1.0, 1.0 + f32::EPSILON, 0));
+        assert!(f32_matches(1.0, 1.0 + f32::EPSILON, 1));
+        assert!(!f32_matches(1.0, -1.0, 1000));
+
+        assert!(f32_matches(f32::NAN, -f32::NAN, 0));
+        assert!(f32_matches(f32::NAN, f32::from_bits(0x7f80_0001), 0));
+        assert!(!f32_matches(f32::NAN, f32::INFINITY, 1));
+        assert!(!f32_matches(f32::INFINITY, f32::MAX, 1));
+        assert!(f32_matches(f32::NEG_INFINITY, f32::NEG_INFINITY, 0));
+
+        assert!(f32_matches(0.0, -0.0, 0));
+        assert!(f32_matches(f32::from_bits(1), 0.0, 0));
+        assert!(f32_matches(-0.0, f32::from_bits(0x807f_ffff), 0));
+        assert!(!f32_matches(0.0, f32::MIN_POSITIVE, 0));
+    }
+
+    #[test]
+    fn test_f16x2() {
+        let check = |expected: u32, actual: u32| Check::F16x2.matches(&[expected], &[actual]);
+        // `1.0` and `2.0`.
+        assert!(check(0x4000_3c00, 0x4000_3c00));
+        assert!(check(0x4000_3c00, 0x4000_3c01));
+        assert!(check(0x4000_3c00, 0x3fff_3c00));
+        assert!(!check(0x4000_3c00, 0x4000_3c02));
+        // NaNs, infinities, and subnormals.
+        assert!(check(0x7e00, 0xfc01));
+        assert!(!check(0x7c00, 0x7bff));
+        assert!(check(0x0001_8000, 0x8000_03ff));
+        assert!(!check(0x0400, 0x0000));
+    }
+
+    #[test]
+    fn test_fields() {
+        let check =
+            |bits, expected: u32, actual: u32| Check::Fields(bits).matches(&[expected], &[actual]);
+        assert!(check(8, 0x7f00_ff01, 0x7e00_0000));
+        assert!(!check(8, 0x7f00_ff01, 0x7d00_0000));
+        assert!(!check(8, 0x0000_0000, 0x0000_0080));
+        assert!(check(16, 0xffff_0000, 0x0000_ffff));
+        assert!(!check(16, 0x0000_0000, 0x0002_0000));
+    }
+}
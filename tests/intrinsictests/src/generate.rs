@@ -0,0 +1,665 @@
+//! Generating the kernel crate (one entry point per intrinsic), and random
+//! inputs for it.
+
+use crate::intrinsics::{Arg, Gen, Intrinsic, Ty, arg_name};
+use std::fmt::Write as _;
+
+/// The number of invocations per workgroup, i.e. per case.
+pub const WORKGROUP_SIZE: usize = 64;
+
+/// The number of output words per invocation (enough for any result).
+pub const OUTPUT_WORDS: usize = 4;
+
+/// A small, deterministic, PRNG (xorshift64*), as seeds have to reproduce the
+/// same inputs everywhere.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as u32
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        self.next_u32() as usize % n
+    }
+
+    /// `true` with probability `1 / n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    /// A float uniformly distributed in `-max..max`.
+    fn float_in(&mut self, max: f32) -> f32 {
+        (self.next_u32() as f32 / u32::MAX as f32 * 2.0 - 1.0) * max
+    }
+}
+
+const INT_EDGE_CASES: &[u32] = &[
+    0,
+    1,
+    2,
+    u32::MAX,
+    u32::MAX - 1,
+    i32::MAX as u32,
+    i32::MIN as u32,
+];
+
+/// Special `f32`s, and ones which are special when converted to `f16`.
+const F32_EDGE_CASES: &[u32] = &[
+    // `±0.0`, `±1.0`, `±inf`, and NaNs (quiet, and signaling with a payload).
+    0x0000_0000,
+    0x8000_0000,
+    0x3f80_0000,
+    0xbf80_0000,
+    0x7f80_0000,
+    0xff80_0000,
+    0x7fc0_0000,
+    0xffc0_0000,
+    0x7fa0_0001,
+    // The smallest and largest subnormals, and the smallest and largest normals.
+    0x0000_0001,
+    0x807f_ffff,
+    0x0080_0000,
+    0x7f7f_ffff,
+    // `65504.0` (the largest `f16`), and values which round to it, and to infinity.
+    0x477f_e000,
+    0x477f_efff,
+    0x477f_f000,
+    // `2^-24` (the smallest `f16` subnormal), `2^-25` and `1.5 * 2^-25` (which
+    // round to zero, and to `2^-24`), and the largest `f16` subnormal.
+    0x3380_0000,
+    0x3300_0000,
+    0x3340_0000,
+    0x387f_c000,
+    // `2^-14` (the smallest normal `f16`).
+    0x3880_0000,
+    // `1.0 + 2^-11` and `1.0 + 3 * 2^-11`, which are ties when rounded to `f16`.
+    0x3f80_1000,
+    0x3f80_3000,
+];
+
+/// Edge cases for `Gen::Summable` and `Gen::Multipliable`.
+const F32_SPECIAL_CASES: &[u32] = &[
+    0x0000_0000,
+    0x8000_0000,
+    0x7f80_0000,
+    0xff80_0000,
+    0x7fc0_0000,
+];
+
+const F32_NORMALIZED_EDGE_CASES: &[f32] = &[
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    0.5,
+    -0.5,
+    1.000_000_1,
+    -1.000_000_1,
+    2.0,
+    -2.0,
+    f32::INFINITY,
+    f32::NEG_INFINITY,
+    1e-40,
+    -1e-40,
+];
+
+const F16_EDGE_CASES: &[u32] = &[
+    0x0000, 0x8000, 0x3c00, 0xbc00, 0x7c00, 0xfc00, 0x7e00, 0xfe00, 0x7c01, 0x0001, 0x83ff, 0x0400,
+    0x7bff,
+];
+
+fn any_int(rng: &mut Rng) -> u32 {
+    match rng.below(4) {
+        0 => rng.pick(INT_EDGE_CASES),
+        1 => rng.below(16) as u32,
+        _ => rng.next_u32(),
+    }
+}
+
+fn any_float(rng: &mut Rng) -> u32 {
+    match rng.below(4) {
+        0 => rng.pick(F32_EDGE_CASES),
+        1 => rng.next_u32(),
+        _ => rng.float_in(4.0).to_bits(),
+    }
+}
+
+/// A scalar component for `generator` (which must not depend on the lane).
+fn component(rng: &mut Rng, ty: Ty, generator: Gen, bool_mode: usize) -> u32 {
+    match (ty, generator) {
+        (Ty::Bool, _) => match bool_mode {
+            0 => 1,
+            1 => 0,
+            2 => rng.below(2) as u32,
+            _ => (!rng.one_in(16)).into(),
+        },
+        (Ty::U32 | Ty::I32 | Ty::UVec4, Gen::HalfBits) => {
+            let mut half = || {
+                if rng.one_in(2) {
+                    rng.pick(F16_EDGE_CASES)
+                } else {
+                    rng.next_u32() & 0xffff
+                }
+            };
+            half() | (half() << 16)
+        }
+        (Ty::U32 | Ty::I32 | Ty::UVec4, _) => any_int(rng),
+        (_, Gen::NotNan) => loop {
+            let x = any_float(rng);
+            if !f32::from_bits(x).is_nan() {
+                break x;
+            }
+        },
+        (_, Gen::Summable) => {
+            if rng.one_in(8) {
+                rng.pick(&[F32_SPECIAL_CASES, &[0x0000_0001, 0x807f_ffff]].concat())
+            } else {
+                (rng.below(64) as f32 / 2.0 - 16.0).to_bits()
+            }
+        }
+        (_, Gen::Multipliable) => {
+            if rng.one_in(16) {
+                rng.pick(F32_SPECIAL_CASES)
+            } else {
+                rng.pick(&[0.5f32, 1.0, 2.0, -0.5, -1.0, -2.0]).to_bits()
+            }
+        }
+        (_, Gen::Normalized) => match rng.below(4) {
+            0 => rng.pick(F32_NORMALIZED_EDGE_CASES).to_bits(),
+            1 => {
+                // Close to a rounding tie, once scaled.
+                let scale = rng.pick(&[127.0f32, 255.0, 32767.0, 65535.0]);
+                let sign = rng.pick(&[1.0f32, -1.0]);
+                ((rng.below(scale as usize) as f32 + 0.5) / scale * sign).to_bits()
+            }
+            _ => rng.float_in(1.25).to_bits(),
+        },
+        _ => any_float(rng),
+    }
+}
+
+/// Generates the values of `arg` for every invocation of a workgroup, given
+/// which invocations are `active`.
+fn generate_arg(rng: &mut Rng, arg: Arg, active: &[bool], subgroup_size: usize) -> Vec<Vec<u32>> {
+    let bool_mode = rng.below(4);
+    let value = |rng: &mut Rng| -> Vec<u32> {
+        (0..arg.ty.words())
+            .map(|_| component(rng, arg.ty, arg.generator, bool_mode))
+            .collect()
+    };
+
+    let mut values = Vec::with_capacity(active.len());
+    for (subgroup, active) in active.chunks(subgroup_size).enumerate() {
+        let active_lanes: Vec<usize> = (0..active.len()).filter(|&lane| active[lane]).collect();
+        let active_lane = |rng: &mut Rng, lanes: &mut dyn Iterator<Item = usize>| {
+            let lanes: Vec<_> = lanes.collect();
+            if lanes.is_empty() {
+                0
+            } else {
+                rng.pick(&lanes)
+            }
+        };
+        let lane_bits = if subgroup_size >= 128 {
+            u128::MAX
+        } else {
+            (1 << subgroup_size) - 1
+        };
+
+        match arg.generator {
+            Gen::MostlyUniform => {
+                let (mode, uniform) = (rng.below(3), value(rng));
+                for _ in 0..active.len() {
+                    let differs = match mode {
+                        0 => false,
+                        1 => rng.one_in(16),
+                        _ => true,
+                    };
+                    values.push(if differs { value(rng) } else { uniform.clone() });
+                }
+            }
+            Gen::UniformMask => {
+                let mask = loop {
+                    let mask = match rng.below(4) {
+                        0 => {
+                            (u128::from(rng.next_u32()) | u128::from(rng.next_u32()) << 32)
+                                & lane_bits
+                        }
+                        1 => 1 << rng.below(subgroup_size),
+                        2 => lane_bits,
+                        _ => active_lanes.iter().map(|&lane| 1 << lane).sum(),
+                    };
+                    if mask != 0 {
+                        break mask;
+                    }
+                };
+                let words: Vec<u32> = (0..4).map(|i| (mask >> (i * 32)) as u32).collect();
+                values.extend((0..active.len()).map(|_| words.clone()));
+            }
+            Gen::UniformActiveLane => {
+                let lane = active_lane(rng, &mut active_lanes.iter().copied()) as u32;
+                values.extend((0..active.len()).map(|_| vec![lane]));
+            }
+            Gen::Lane => {
+                values.extend((0..active.len()).map(|_| vec![rng.below(subgroup_size) as u32]));
+            }
+            Gen::ActiveLane
+            | Gen::XorToActiveLane
+            | Gen::DeltaToActiveLaneBelow
+            | Gen::DeltaToActiveLaneAbove => {
+                for lane in 0..active.len() {
+                    let mut candidates =
+                        active_lanes
+                            .iter()
+                            .copied()
+                            .filter(|&other| match arg.generator {
+                                Gen::DeltaToActiveLaneBelow => other <= lane,
+                                Gen::DeltaToActiveLaneAbove => other >= lane,
+                                _ => true,
+                            });
+                    let other = active_lane(rng, &mut candidates);
+                    let word = match arg.generator {
+                        Gen::ActiveLane => other,
+                        Gen::XorToActiveLane => lane ^ other,
+                        Gen::DeltaToActiveLaneBelow => lane.saturating_sub(other),
+                        _ => other.saturating_sub(lane),
+                    };
+                    values.push(vec![word as u32]);
+                }
+            }
+            _ => values.extend((0..active.len()).map(|_| value(rng))),
+        }
+        debug_assert_eq!(values.len(), (subgroup + 1) * active.len());
+    }
+    values
+}
+
+/// Which invocations of a workgroup are active, for subgroup operations.
+fn active_invocations(rng: &mut Rng) -> Vec<bool> {
+    match rng.below(5) {
+        0 => vec![true; WORKGROUP_SIZE],
+        1 => (0..WORKGROUP_SIZE).map(|_| !rng.one_in(4)).collect(),
+        2 => (0..WORKGROUP_SIZE).map(|_| rng.one_in(4)).collect(),
+        3 => (0..WORKGROUP_SIZE).map(|i| i % 2 == 0).collect(),
+        _ => {
+            let first = rng.below(WORKGROUP_SIZE);
+            (0..WORKGROUP_SIZE).map(|i| i >= first).collect()
+        }
+    }
+}
+
+/// FNV-1a, to derive a different seed for every intrinsic, which doesn't
+/// depend on which other intrinsics are being tested.
+fn hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Generates the input words of `intrinsic`, for `cases` workgroups, with
+/// `input_words` words per invocation.
+pub fn generate_input(
+    intrinsic: &Intrinsic,
+    seed: u64,
+    cases: usize,
+    subgroup_size: usize,
+    input_words: usize,
+) -> Vec<u32> {
+    let mut rng = Rng::new(seed ^ hash(&intrinsic.name));
+    let mut input = vec![0; cases * WORKGROUP_SIZE * input_words];
+    for case in input.chunks_mut(WORKGROUP_SIZE * input_words) {
+        let active = if intrinsic.subgroup {
+            active_invocations(&mut rng)
+        } else {
+            vec![true; WORKGROUP_SIZE]
+        };
+        for (invocation, &active) in active.iter().enumerate() {
+            case[invocation * input_words] = active.into();
+        }
+        let mut offset = 1;
+        for &arg in &intrinsic.args {
+            let values = generate_arg(&mut rng, arg, &active, subgroup_size);
+            for (invocation, value) in values.iter().enumerate() {
+                let at = invocation * input_words + offset;
+                case[at..at + value.len()].copy_from_slice(value);
+            }
+            offset += arg.ty.words();
+        }
+    }
+    input
+}
+
+/// The number of input words per invocation, for all of `intrinsics`.
+pub fn input_words(intrinsics: &[Intrinsic]) -> usize {
+    intrinsics
+        .iter()
+        .map(Intrinsic::input_words)
+        .max()
+        .unwrap_or(1)
+}
+
+/// Renders the kernel crate's library, with an entry point per intrinsic
+/// (named after it), plus `probe_subgroup_size`.
+pub fn render_lib(intrinsics: &[Intrinsic]) -> String {
+    let mut out = String::new();
+    out += "//! Kernels generated by `cargo intrinsictest`, calling one intrinsic each.\n\
+            //!\n\
+            //! Every invocation has `INPUT_WORDS` input words: whether it's active (i.e.\n\
+            //! doesn't return early), followed by the arguments, and writes the result to\n\
+            //! the start of its `OUTPUT_WORDS` output words.\n\n\
+            #![cfg_attr(target_arch = \"spirv\", no_std)]\n\
+            // Not every kernel crate uses both `arch` and `float`, or needs `unsafe`.\n\
+            #![allow(unused_imports, unused_unsafe)]\n\n\
+            use spirv_std::glam::{UVec3, UVec4, Vec2, Vec4};\n\
+            use spirv_std::{arch, float, spirv};\n\n";
+    writeln!(out, "pub const WORKGROUP_SIZE: u32 = {WORKGROUP_SIZE};").unwrap();
+    writeln!(
+        out,
+        "pub const INPUT_WORDS: usize = {};",
+        input_words(intrinsics)
+    )
+    .unwrap();
+    writeln!(out, "pub const OUTPUT_WORDS: usize = {OUTPUT_WORDS};\n").unwrap();
+    out += WORDS_TRAIT;
+
+    out += "\n#[spirv(compute(threads(64)))]\n\
+            pub fn probe_subgroup_size(\n    \
+                #[spirv(global_invocation_id)] id: UVec3,\n    \
+                #[spirv(subgroup_size)] subgroup_size: u32,\n    \
+                #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] _input: &[u32],\n    \
+                #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] output: &mut [u32],\n\
+            ) {\n    \
+                output[id.x as usize] = subgroup_size;\n\
+            }\n";
+
+    for intrinsic in intrinsics {
+        writeln!(
+            out,
+            "\n#[spirv(compute(threads({WORKGROUP_SIZE})))]\n\
+             pub fn {}(\n    \
+                 #[spirv(global_invocation_id)] id: UVec3,\n    \
+                 #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] input: &[u32],\n    \
+                 #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] output: &mut [u32],\n\
+             ) {{\n    \
+                 let at = id.x as usize * INPUT_WORDS;\n    \
+                 if input[at] == 0 {{\n        \
+                     return;\n    \
+                 }}",
+            intrinsic.name
+        )
+        .unwrap();
+        let mut offset = 1;
+        for (n, arg) in intrinsic.args.iter().enumerate() {
+            writeln!(
+                out,
+                "    let {}: {ty} = <{ty} as Words>::read(input, at + {offset});",
+                arg_name(n),
+                ty = arg.ty.name()
+            )
+            .unwrap();
+            offset += arg.ty.words();
+        }
+        writeln!(
+            out,
+            "    let result: {} = unsafe {{ {} }};\n    \
+                 result.write(output, id.x as usize * OUTPUT_WORDS);\n\
+             }}",
+            intrinsic.result.name(),
+            intrinsic.call
+        )
+        .unwrap();
+    }
+    out
+}
+
+const WORDS_TRAIT: &str = "\
+/// Values stored in consecutive words (of the input and output).
+pub trait Words {
+    fn read(words: &[u32], at: usize) -> Self;
+    fn write(self, words: &mut [u32], at: usize);
+}
+
+impl Words for u32 {
+    fn read(words: &[u32], at: usize) -> Self {
+        words[at]
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        words[at] = self;
+    }
+}
+
+impl Words for i32 {
+    fn read(words: &[u32], at: usize) -> Self {
+        words[at] as i32
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        words[at] = self as u32;
+    }
+}
+
+impl Words for f32 {
+    fn read(words: &[u32], at: usize) -> Self {
+        f32::from_bits(words[at])
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        words[at] = self.to_bits();
+    }
+}
+
+impl Words for bool {
+    fn read(words: &[u32], at: usize) -> Self {
+        words[at] != 0
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        words[at] = self as u32;
+    }
+}
+
+impl Words for Vec2 {
+    fn read(words: &[u32], at: usize) -> Self {
+        Vec2::new(f32::read(words, at), f32::read(words, at + 1))
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        self.x.write(words, at);
+        self.y.write(words, at + 1);
+    }
+}
+
+impl Words for Vec4 {
+    fn read(words: &[u32], at: usize) -> Self {
+        Vec4::new(
+            f32::read(words, at),
+            f32::read(words, at + 1),
+            f32::read(words, at + 2),
+            f32::read(words, at + 3),
+        )
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        self.x.write(words, at);
+        self.y.write(words, at + 1);
+        self.z.write(words, at + 2);
+        self.w.write(words, at + 3);
+    }
+}
+
+impl Words for UVec4 {
+    fn read(words: &[u32], at: usize) -> Self {
+        UVec4::new(words[at], words[at + 1], words[at + 2], words[at + 3])
+    }
+    fn write(self, words: &mut [u32], at: usize) {
+        self.x.write(words, at);
+        self.y.write(words, at + 1);
+        self.z.write(words, at + 2);
+        self.w.write(words, at + 3);
+    }
+}
+";
+
+/// Renders the kernel crate's binary, which runs the kernels with the CPU
+/// emulation in `spirv-std`, as the reference.
+pub fn render_main(intrinsics: &[Intrinsic]) -> String {
+    let mut out = String::new();
+    out += "//! Runs the kernels with `spirv-std`'s CPU emulation: for every\n\
+            //! `<kernel>.input` file in the given directory, its output is written to\n\
+            //! `<kernel>.output`.\n\n\
+            use intrinsic_kernels::{INPUT_WORDS, OUTPUT_WORDS, WORKGROUP_SIZE};\n\
+            use spirv_std::cpu_emulation::{ComputeDispatch, Shared};\n\
+            use spirv_std::glam::UVec3;\n\
+            use std::fs;\n\
+            use std::path::Path;\n\n\
+            type Kernel = fn(UVec3, &[u32], &mut [u32]);\n\n\
+            const KERNELS: &[(&str, Kernel)] = &[\n";
+    for intrinsic in intrinsics {
+        writeln!(
+            out,
+            "    (\"{0}\", intrinsic_kernels::{0}),",
+            intrinsic.name
+        )
+        .unwrap();
+    }
+    out += "];\n\n\
+            fn main() {\n    \
+                let args: Vec<String> = std::env::args().collect();\n    \
+                let [_, subgroup_size, dir] = &args[..] else {\n        \
+                    panic!(\"usage: intrinsic-kernels <subgroup size> <dir>\");\n    \
+                };\n    \
+                let subgroup_size = subgroup_size.parse().unwrap();\n    \
+                let dir = Path::new(dir);\n    \
+                for &(name, kernel) in KERNELS {\n        \
+                    let Ok(input) = fs::read(dir.join(format!(\"{name}.input\"))) else {\n            \
+                        continue;\n        \
+                    };\n        \
+                    let input: Vec<u32> = input\n            \
+                        .chunks_exact(4)\n            \
+                        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))\n            \
+                        .collect();\n        \
+                    let invocations = input.len() / INPUT_WORDS;\n        \
+                    let output = Shared::new(vec![0u32; invocations * OUTPUT_WORDS]);\n        \
+                    ComputeDispatch::new(\n            \
+                        UVec3::new(invocations as u32 / WORKGROUP_SIZE, 1, 1),\n            \
+                        UVec3::new(WORKGROUP_SIZE, 1, 1),\n        \
+                    )\n        \
+                    .subgroup_size(subgroup_size)\n        \
+                    .run(|invocation| {\n            \
+                        // SAFETY: every invocation only writes its own output words.\n            \
+                        kernel(invocation.global_invocation_id, &input, unsafe { output.get_mut() })\n        \
+                    });\n        \
+                    let output: Vec<u8> = (output.into_inner().iter())\n            \
+                        .flat_map(|word| word.to_le_bytes())\n            \
+                        .collect();\n        \
+                    fs::write(dir.join(format!(\"{name}.output\")), output).unwrap();\n    \
+                }\n\
+            }\n";
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intrinsics;
+
+    fn intrinsic(name: &str) -> Intrinsic {
+        intrinsics::all()
+            .into_iter()
+            .find(|intrinsic| intrinsic.name == name)
+            .unwrap()
+    }
+
+    /// The active flag and the `n`th argument word of every invocation.
+    fn arg_words(input: &[u32], input_words: usize, n: usize) -> Vec<(bool, u32)> {
+        input
+            .chunks(input_words)
+            .map(|words| (words[0] != 0, words[1 + n]))
+            .collect()
+    }
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        for intrinsic in intrinsics::all() {
+            let words = intrinsic.input_words();
+            let input = generate_input(&intrinsic, 7, 4, 32, words);
+            assert_eq!(input.len(), 4 * WORKGROUP_SIZE * words);
+            assert_eq!(input, generate_input(&intrinsic, 7, 4, 32, words));
+        }
+    }
+
+    #[test]
+    fn test_lanes_are_active() {
+        for subgroup_size in [4, 16, 32, 64] {
+            for (name, to_lane) in [
+                (
+                    "subgroup_shuffle_u32",
+                    (|_, arg| arg) as fn(usize, usize) -> usize,
+                ),
+                ("subgroup_shuffle_xor_u32", |lane, arg| lane ^ arg),
+                ("subgroup_shuffle_up_u32", |lane, arg| lane - arg),
+                ("subgroup_shuffle_down_u32", |lane, arg| lane + arg),
+                ("subgroup_broadcast_u32", |_, arg| arg),
+            ] {
+                let intrinsic = intrinsic(name);
+                let words = intrinsic.input_words();
+                let input = generate_input(&intrinsic, 0, 16, subgroup_size, words);
+                let lanes = arg_words(&input, words, 1);
+                for (i, &(active, arg)) in lanes.iter().enumerate() {
+                    if active {
+                        let lane = i % subgroup_size;
+                        let other = i - lane + to_lane(lane, arg as usize);
+                        assert!(other / subgroup_size == i / subgroup_size, "{name}");
+                        assert!(lanes[other].0, "{name}: lane {other} is inactive");
+                    }
+                }
+                if name == "subgroup_broadcast_u32" {
+                    for subgroup in lanes.chunks(subgroup_size) {
+                        assert!(subgroup.iter().all(|&(_, arg)| arg == subgroup[0].1));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_masks_are_uniform() {
+        let intrinsic = intrinsic("subgroup_ballot_find_msb");
+        let words = intrinsic.input_words();
+        let input = generate_input(&intrinsic, 0, 16, 16, words);
+        for subgroup in input.chunks(16 * words) {
+            let mask = &subgroup[1..5];
+            assert!(mask.iter().any(|&word| word != 0));
+            // Only lanes of the subgroup are set.
+            assert_eq!(mask[0] >> 16, 0);
+            assert_eq!(&mask[1..], [0, 0, 0]);
+            for invocation in subgroup.chunks(words) {
+                assert_eq!(&invocation[1..5], mask);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rendered_kernels() {
+        let intrinsics = intrinsics::all();
+        let lib = render_lib(&intrinsics);
+        let main = render_main(&intrinsics);
+        for intrinsic in &intrinsics {
+            assert!(lib.contains(&format!("pub fn {}(", intrinsic.name)));
+            assert!(lib.contains(&intrinsic.call));
+            assert!(main.contains(&format!("intrinsic_kernels::{}),", intrinsic.name)));
+        }
+        assert_eq!(
+            lib.matches("#[spirv(compute(").count(),
+            intrinsics.len() + 1
+        );
+        let balanced = |open, close| lib.matches(open).count() == lib.matches(close).count();
+        assert!(balanced('(', ')') && balanced('{', '}') && balanced('[', ']'));
+    }
+}
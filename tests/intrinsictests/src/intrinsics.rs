@@ -0,0 +1,444 @@
+//! The intrinsics tested, with the types of their arguments and results, how
+//! to generate their arguments, and how precisely results have to match.
+
+use crate::check::Check;
+
+/// The type of an argument or result, stored in consecutive words.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Ty {
+    U32,
+    I32,
+    F32,
+    Bool,
+    Vec2,
+    Vec4,
+    /// Also used for `SubgroupMask`s.
+    UVec4,
+}
+
+impl Ty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::U32 => "u32",
+            Self::I32 => "i32",
+            Self::F32 => "f32",
+            Self::Bool => "bool",
+            Self::Vec2 => "Vec2",
+            Self::Vec4 => "Vec4",
+            Self::UVec4 => "UVec4",
+        }
+    }
+
+    pub fn words(self) -> usize {
+        match self {
+            Self::U32 | Self::I32 | Self::F32 | Self::Bool => 1,
+            Self::Vec2 => 2,
+            Self::Vec4 | Self::UVec4 => 4,
+        }
+    }
+
+    /// Whether the components are floats.
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::Vec2 | Self::Vec4)
+    }
+
+    /// Human-readable `words` (of a value of this type), for reports.
+    pub fn format(self, words: &[u32]) -> String {
+        let component = |word: u32| match self {
+            Self::U32 | Self::UVec4 => format!("{word:#x}"),
+            Self::I32 => (word as i32).to_string(),
+            Self::Bool => (word != 0).to_string(),
+            Self::F32 | Self::Vec2 | Self::Vec4 => {
+                format!("{:?} ({word:#010x})", f32::from_bits(word))
+            }
+        };
+        let components: Vec<_> = words.iter().map(|&word| component(word)).collect();
+        if components.len() == 1 {
+            components.into_iter().next().unwrap()
+        } else {
+            format!("[{}]", components.join(", "))
+        }
+    }
+}
+
+/// How the (per-invocation) values of an argument are generated.
+///
+/// Some arguments of subgroup operations must be the same for the whole
+/// subgroup, or refer to active lanes, for the result to be defined, which
+/// the generator takes care of (the subgroup size is known by then).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Gen {
+    /// Any value, with edge cases (e.g. `0`, `MAX`, NaNs, infinities and
+    /// subnormals) mixed in.
+    Any,
+    /// Like `Any`, but without NaNs (for which e.g. `FMin` is undefined).
+    NotNan,
+    /// Floats whose sums are exact (halves of small integers), so that they
+    /// don't depend on the order of the additions, plus edge cases.
+    Summable,
+    /// Floats whose products are exact (`±0.5`, `±1` and `±2`), plus edge cases.
+    Multipliable,
+    /// Floats in (or around) `-1.0..=1.0`, including values close to rounding
+    /// ties when scaled for `snorm`/`unorm` packing, without NaNs (for which
+    /// packing is undefined).
+    Normalized,
+    /// Two `f16`s (in the low and high halves), with `f16` edge cases.
+    HalfBits,
+    /// Values which are usually the same across the subgroup.
+    MostlyUniform,
+    /// A (non-zero) mask of subgroup lanes, the same across the subgroup.
+    UniformMask,
+    /// An active lane, the same across the subgroup.
+    UniformActiveLane,
+    /// Any lane of the subgroup.
+    Lane,
+    /// An active lane.
+    ActiveLane,
+    /// A mask which, XOR-ed with the lane, gives an active lane.
+    XorToActiveLane,
+    /// How far below the lane an active lane is.
+    DeltaToActiveLaneBelow,
+    /// How far above the lane an active lane is.
+    DeltaToActiveLaneAbove,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Arg {
+    pub ty: Ty,
+    pub generator: Gen,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Intrinsic {
+    /// The name of the kernel's entry point (also matched against filters).
+    pub name: String,
+    /// The call expression, with the arguments named `a`, `b`, etc.
+    pub call: String,
+    pub args: Vec<Arg>,
+    pub result: Ty,
+    pub check: Check,
+    /// Whether to make some invocations inactive (by returning early) before
+    /// calling the intrinsic, for subgroup operations.
+    pub subgroup: bool,
+}
+
+/// The name of the `n`th argument in `Intrinsic::call`.
+pub fn arg_name(n: usize) -> char {
+    (b'a' + n as u8) as char
+}
+
+impl Intrinsic {
+    fn new(
+        name: impl Into<String>,
+        call: impl Into<String>,
+        args: &[(Ty, Gen)],
+        result: Ty,
+        check: Check,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            call: call.into(),
+            args: args
+                .iter()
+                .map(|&(ty, generator)| Arg { ty, generator })
+                .collect(),
+            result,
+            check,
+            subgroup: false,
+        }
+    }
+
+    fn subgroup(
+        name: impl Into<String>,
+        call: impl Into<String>,
+        args: &[(Ty, Gen)],
+        result: Ty,
+        check: Check,
+    ) -> Self {
+        Self {
+            subgroup: true,
+            ..Self::new(name, call, args, result, check)
+        }
+    }
+
+    /// The number of input words per invocation: an "active" flag, followed
+    /// by the arguments.
+    pub fn input_words(&self) -> usize {
+        1 + self.args.iter().map(|arg| arg.ty.words()).sum::<usize>()
+    }
+}
+
+/// The check for (exact) results of type `ty`.
+fn exact(ty: Ty) -> Check {
+    if ty.is_float() {
+        Check::F32 { ulps: 0 }
+    } else {
+        Check::Exact
+    }
+}
+
+/// All the intrinsics tested, in the order they're run in.
+pub fn all() -> Vec<Intrinsic> {
+    use Gen::{
+        ActiveLane, Any, DeltaToActiveLaneAbove, DeltaToActiveLaneBelow, HalfBits, Lane,
+        MostlyUniform, Multipliable, Normalized, NotNan, Summable, UniformActiveLane, UniformMask,
+        XorToActiveLane,
+    };
+    use Ty::{Bool, F32, I32, U32, UVec4, Vec2, Vec4};
+
+    let mut all = vec![];
+
+    // `spirv_std::arch` integer min/max (`GLSL.std.450` `UMin` etc.).
+    for (f, ty) in [
+        ("unsigned_min", U32),
+        ("unsigned_max", U32),
+        ("signed_min", I32),
+        ("signed_max", I32),
+    ] {
+        all.push(Intrinsic::new(
+            f,
+            format!("arch::{f}(a, b)"),
+            &[(ty, Any), (ty, Any)],
+            ty,
+            Check::Exact,
+        ));
+    }
+
+    // `spirv_std::float` packing (`GLSL.std.450` `PackHalf2x16` etc.).
+    for (f, arg, result, check) in [
+        ("vec2_to_f16x2", (Vec2, Any), U32, Check::F16x2),
+        (
+            "f16x2_to_vec2",
+            (U32, HalfBits),
+            Vec2,
+            Check::F32 { ulps: 0 },
+        ),
+        ("f32_to_f16", (F32, Any), U32, Check::F16x2),
+        ("f16_to_f32", (U32, HalfBits), F32, Check::F32 { ulps: 0 }),
+        (
+            "vec4_to_u8x4_snorm",
+            (Vec4, Normalized),
+            U32,
+            Check::Fields(8),
+        ),
+        (
+            "vec4_to_u8x4_unorm",
+            (Vec4, Normalized),
+            U32,
+            Check::Fields(8),
+        ),
+        (
+            "vec2_to_u16x2_snorm",
+            (Vec2, Normalized),
+            U32,
+            Check::Fields(16),
+        ),
+        (
+            "vec2_to_u16x2_unorm",
+            (Vec2, Normalized),
+            U32,
+            Check::Fields(16),
+        ),
+        (
+            "u8x4_to_vec4_snorm",
+            (U32, Any),
+            Vec4,
+            Check::F32 { ulps: 1 },
+        ),
+        (
+            "u8x4_to_vec4_unorm",
+            (U32, Any),
+            Vec4,
+            Check::F32 { ulps: 1 },
+        ),
+        (
+            "u16x2_to_vec2_snorm",
+            (U32, Any),
+            Vec2,
+            Check::F32 { ulps: 1 },
+        ),
+        (
+            "u16x2_to_vec2_unorm",
+            (U32, Any),
+            Vec2,
+            Check::F32 { ulps: 1 },
+        ),
+    ] {
+        all.push(Intrinsic::new(
+            f,
+            format!("float::{f}(a)"),
+            &[arg],
+            result,
+            check,
+        ));
+    }
+
+    // `spirv_std::arch` subgroup operations.
+    all.push(Intrinsic::subgroup(
+        "subgroup_elect",
+        "arch::subgroup_elect()",
+        &[],
+        Bool,
+        Check::Exact,
+    ));
+    for f in ["subgroup_all", "subgroup_any"] {
+        all.push(Intrinsic::subgroup(
+            f,
+            format!("arch::{f}(a)"),
+            &[(Bool, Any)],
+            Bool,
+            Check::Exact,
+        ));
+    }
+    all.push(Intrinsic::subgroup(
+        "subgroup_all_equal",
+        "arch::subgroup_all_equal(a)",
+        &[(U32, MostlyUniform)],
+        Bool,
+        Check::Exact,
+    ));
+    for ty in [U32, F32, Vec4] {
+        let suffix = ty.name().to_lowercase();
+        all.push(Intrinsic::subgroup(
+            format!("subgroup_broadcast_{suffix}"),
+            "arch::subgroup_broadcast(a, b)",
+            &[(ty, Any), (U32, UniformActiveLane)],
+            ty,
+            Check::Exact,
+        ));
+        all.push(Intrinsic::subgroup(
+            format!("subgroup_broadcast_first_{suffix}"),
+            "arch::subgroup_broadcast_first(a)",
+            &[(ty, Any)],
+            ty,
+            Check::Exact,
+        ));
+        for (f, lane) in [
+            ("subgroup_shuffle", ActiveLane),
+            ("subgroup_shuffle_xor", XorToActiveLane),
+            ("subgroup_shuffle_up", DeltaToActiveLaneBelow),
+            ("subgroup_shuffle_down", DeltaToActiveLaneAbove),
+        ] {
+            all.push(Intrinsic::subgroup(
+                format!("{f}_{suffix}"),
+                format!("arch::{f}(a, b)"),
+                &[(ty, Any), (U32, lane)],
+                ty,
+                Check::Exact,
+            ));
+        }
+    }
+    all.push(Intrinsic::subgroup(
+        "subgroup_ballot",
+        "arch::subgroup_ballot(a)",
+        &[(Bool, Any)],
+        UVec4,
+        Check::Exact,
+    ));
+    all.push(Intrinsic::subgroup(
+        "subgroup_inverse_ballot",
+        "arch::subgroup_inverse_ballot(a)",
+        &[(UVec4, UniformMask)],
+        Bool,
+        Check::Exact,
+    ));
+    all.push(Intrinsic::subgroup(
+        "subgroup_ballot_bit_extract",
+        "arch::subgroup_ballot_bit_extract(a, b)",
+        &[(UVec4, UniformMask), (U32, Lane)],
+        Bool,
+        Check::Exact,
+    ));
+    for f in [
+        "subgroup_ballot_bit_count",
+        "subgroup_ballot_inclusive_bit_count",
+        "subgroup_ballot_exclusive_bit_count",
+        "subgroup_ballot_find_lsb",
+        "subgroup_ballot_find_msb",
+    ] {
+        all.push(Intrinsic::subgroup(
+            f,
+            format!("arch::{f}(a)"),
+            &[(UVec4, UniformMask)],
+            U32,
+            Check::Exact,
+        ));
+    }
+
+    // Reductions and scans, each as `subgroup_{op}`, `subgroup_inclusive_{op}`,
+    // `subgroup_exclusive_{op}` and `subgroup_clustered_{op}`.
+    for (op, ty, generator) in [
+        ("i_add", U32, Any),
+        ("i_add", I32, Any),
+        ("i_mul", U32, Any),
+        ("f_add", F32, Summable),
+        ("f_add", Vec2, Summable),
+        ("f_mul", F32, Multipliable),
+        ("s_min", I32, Any),
+        ("s_max", I32, Any),
+        ("u_min", U32, Any),
+        ("u_max", U32, Any),
+        ("f_min", F32, NotNan),
+        ("f_max", F32, NotNan),
+        ("and", U32, Any),
+        ("or", U32, Any),
+        ("xor", U32, Any),
+        ("logical_and", Bool, Any),
+        ("logical_or", Bool, Any),
+        ("logical_xor", Bool, Any),
+    ] {
+        let suffix = ty.name().to_lowercase();
+        for (f, generics) in [
+            (format!("subgroup_{op}"), ""),
+            (format!("subgroup_inclusive_{op}"), ""),
+            (format!("subgroup_exclusive_{op}"), ""),
+            (format!("subgroup_clustered_{op}"), "::<4, _>"),
+        ] {
+            all.push(Intrinsic::subgroup(
+                format!("{f}_{suffix}"),
+                format!("arch::{f}{generics}(a)"),
+                &[(ty, generator)],
+                ty,
+                exact(ty),
+            ));
+        }
+    }
+
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_unique_identifiers() {
+        let all = all();
+        let mut names: Vec<_> = all.iter().map(|intrinsic| &intrinsic.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), all.len());
+        for name in names {
+            assert!(
+                name.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_calls_use_all_args() {
+        for intrinsic in all() {
+            for n in 0..intrinsic.args.len() {
+                let name = arg_name(n);
+                assert!(
+                    intrinsic.call.contains(&format!("({name}"))
+                        || intrinsic.call.contains(&format!(", {name}")),
+                    "`{}` doesn't use `{name}`",
+                    intrinsic.call
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Property-based tests of `spirv-std` intrinsics: every intrinsic is called
+//! (from its own kernel) with random arguments, mixed with edge cases, and the
+//! results of running the kernels as SPIR-V are compared against running them
+//! natively, with `spirv-std`'s CPU emulation as the reference semantics.
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod check;
+mod generate;
+mod intrinsics;
+mod run;
+
+use generate::{OUTPUT_WORDS, WORKGROUP_SIZE};
+use intrinsics::Intrinsic;
+use run::{BackendKind, Runner};
+
+#[derive(Parser)]
+#[command(bin_name = "cargo intrinsictest")]
+struct Opt {
+    /// Seed of the random inputs.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of cases (i.e. workgroups, of 64 invocations) per intrinsic.
+    #[arg(long, default_value_t = 8)]
+    cases: usize,
+
+    /// The backend to run the SPIR-V on.
+    #[arg(long, value_enum, default_value_t = BackendKind::Interpreter)]
+    backend: BackendKind,
+
+    /// The subgroup size of the interpreter (other backends use their own).
+    #[arg(long, default_value_t = 32)]
+    subgroup_size: u32,
+
+    /// Maximum number of mismatches to print per intrinsic.
+    #[arg(long, default_value_t = 8)]
+    max_mismatches: usize,
+
+    /// Directory to build the kernels in (defaults to `target/intrinsictests`).
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Only test the intrinsics whose names contain one of these.
+    filters: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::parse();
+
+    let intrinsics: Vec<Intrinsic> = intrinsics::all()
+        .into_iter()
+        .filter(|intrinsic| {
+            opt.filters.is_empty()
+                || opt
+                    .filters
+                    .iter()
+                    .any(|filter| intrinsic.name.contains(filter.as_str()))
+        })
+        .collect();
+    if intrinsics.is_empty() {
+        bail!("no intrinsics match the filters {:?}", opt.filters);
+    }
+    if opt.subgroup_size as usize > WORKGROUP_SIZE {
+        bail!("the subgroup size can't be larger than a workgroup ({WORKGROUP_SIZE})");
+    }
+
+    let out_dir = opt.out_dir.clone().unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target/intrinsictests")
+    });
+    fs::create_dir_all(&out_dir)?;
+    let out_dir = out_dir.canonicalize()?;
+
+    println!("building {} kernels", intrinsics.len());
+    let runner = Runner::new(
+        out_dir.join("kernels"),
+        opt.backend,
+        opt.subgroup_size,
+        &intrinsics,
+    )?;
+    println!("subgroup size: {}", runner.subgroup_size);
+
+    let inputs: Vec<Vec<u32>> = intrinsics
+        .iter()
+        .map(|intrinsic| {
+            generate::generate_input(
+                intrinsic,
+                opt.seed,
+                opt.cases,
+                runner.subgroup_size,
+                runner.input_words(),
+            )
+        })
+        .collect();
+    let named_inputs = || {
+        (intrinsics.iter())
+            .zip(&inputs)
+            .map(|(intrinsic, input)| (intrinsic.name.as_str(), input.as_slice()))
+    };
+    let references = runner.run_reference(named_inputs())?;
+
+    let mut failures = vec![];
+    for ((intrinsic, input), reference) in intrinsics.iter().zip(&inputs).zip(&references) {
+        let report = match runner.run_spirv(&intrinsic.name, input) {
+            Ok(output) => compare(
+                intrinsic,
+                input,
+                runner.input_words(),
+                runner.subgroup_size,
+                reference,
+                &output,
+                opt.max_mismatches,
+            ),
+            Err(err) => Some(format!("SPIR-V execution error: {err:#}\n")),
+        };
+        match report {
+            None => println!("{}: pass", intrinsic.name),
+            Some(report) => {
+                println!("{}: FAIL", intrinsic.name);
+                print!("{report}");
+                failures.push(intrinsic.name.as_str());
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} intrinsics failed (with --seed {}): {}",
+            failures.len(),
+            intrinsics.len(),
+            opt.seed,
+            failures.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Compares the results of the active invocations, returning a report of
+/// the mismatches (if any).
+fn compare(
+    intrinsic: &Intrinsic,
+    input: &[u32],
+    input_words: usize,
+    subgroup_size: usize,
+    reference: &[u32],
+    output: &[u32],
+    max_mismatches: usize,
+) -> Option<String> {
+    let result_words = intrinsic.result.words();
+    let mut report = String::new();
+    let mut mismatches = 0;
+    for (invocation, args) in input.chunks(input_words).enumerate() {
+        // Inactive invocations return early, without writing anything.
+        if args[0] == 0 {
+            continue;
+        }
+        let at = invocation * OUTPUT_WORDS;
+        let expected = &reference[at..at + result_words];
+        let actual = &output[at..at + result_words];
+        if intrinsic.check.matches(expected, actual) {
+            continue;
+        }
+
+        mismatches += 1;
+        if mismatches > max_mismatches {
+            continue;
+        }
+        let (case, local) = (invocation / WORKGROUP_SIZE, invocation % WORKGROUP_SIZE);
+        writeln!(
+            report,
+            "  case {case}, invocation {local} (lane {}):",
+            local % subgroup_size
+        )
+        .unwrap();
+        let mut offset = 1;
+        for (n, arg) in intrinsic.args.iter().enumerate() {
+            let words = &args[offset..offset + arg.ty.words()];
+            writeln!(
+                report,
+                "    {} = {}",
+                intrinsics::arg_name(n),
+                arg.ty.format(words)
+            )
+            .unwrap();
+            offset += arg.ty.words();
+        }
+        writeln!(
+            report,
+            "    expected {}\n    actual   {}",
+            intrinsic.result.format(expected),
+            intrinsic.result.format(actual)
+        )
+        .unwrap();
+    }
+    if mismatches > max_mismatches {
+        writeln!(report, "  ... and {} more", mismatches - max_mismatches).unwrap();
+    }
+    (mismatches > 0).then_some(report)
+}
@@ -0,0 +1,246 @@
+//! Building the kernel crate, and running its kernels both as SPIR-V and with
+//! `spirv-std`'s CPU emulation (the reference).
+
+use crate::generate::{self, OUTPUT_WORDS, WORKGROUP_SIZE};
+use crate::intrinsics::Intrinsic;
+use anyhow::{Context, Result, bail};
+use difftest::scaffold::compute::{
+    BufferConfig, ComputeBackend, InterpreterBackend, RustComputeShader, WgpuBackend,
+};
+use spirv_builder::Capability;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum BackendKind {
+    /// The SPIR-V interpreter from `difftest`, which doesn't need a GPU.
+    Interpreter,
+    /// The first GPU found by `wgpu`.
+    Wgpu,
+}
+
+enum Backend {
+    Interpreter(InterpreterBackend),
+    Wgpu(WgpuBackend),
+}
+
+impl Backend {
+    fn run_compute(
+        &self,
+        spirv_bytes: &[u8],
+        entry_point: &str,
+        dispatch: [u32; 3],
+        buffers: Vec<BufferConfig>,
+    ) -> Result<Vec<Vec<u8>>> {
+        match self {
+            Self::Interpreter(backend) => {
+                backend.run_compute(spirv_bytes, entry_point, dispatch, buffers)
+            }
+            Self::Wgpu(backend) => backend.run_compute(spirv_bytes, entry_point, dispatch, buffers),
+        }
+    }
+}
+
+/// The capabilities needed by the subgroup operations.
+const CAPABILITIES: &[Capability] = &[
+    Capability::GroupNonUniform,
+    Capability::GroupNonUniformVote,
+    Capability::GroupNonUniformBallot,
+    Capability::GroupNonUniformShuffle,
+    Capability::GroupNonUniformShuffleRelative,
+    Capability::GroupNonUniformArithmetic,
+    Capability::GroupNonUniformClustered,
+];
+
+/// The compiled kernel crate, for the intrinsics being tested.
+pub struct Runner {
+    work_dir: PathBuf,
+    backend: Backend,
+    spirv_bytes: Vec<u8>,
+    input_words: usize,
+    /// The subgroup size the backend actually uses (see `probe_subgroup_size`).
+    pub subgroup_size: usize,
+}
+
+impl Runner {
+    /// Writes and builds the kernel crate (in `work_dir`), for `intrinsics`.
+    ///
+    /// The `subgroup_size` is only used by the interpreter, and the reference
+    /// always uses the size the backend reports.
+    pub fn new(
+        work_dir: PathBuf,
+        backend: BackendKind,
+        subgroup_size: u32,
+        intrinsics: &[Intrinsic],
+    ) -> Result<Self> {
+        let backend = match backend {
+            BackendKind::Interpreter => Backend::Interpreter(
+                InterpreterBackend::default().with_subgroup_size(subgroup_size),
+            ),
+            BackendKind::Wgpu => Backend::Wgpu(WgpuBackend::init()?),
+        };
+        write_crate(&work_dir, intrinsics)?;
+
+        let mut shader = RustComputeShader::with_target(&work_dir, "spirv-unknown-vulkan1.2");
+        for &capability in CAPABILITIES {
+            shader = shader.with_capability(capability);
+        }
+        let (spirv_bytes, entry_points) = shader
+            .spirv_bytes_with_entry_points()
+            .context("rust-gpu failed to compile the kernels")?;
+        for intrinsic in intrinsics {
+            if !entry_points.contains(&intrinsic.name) {
+                bail!(
+                    "the kernels are missing the `{}` entry point",
+                    intrinsic.name
+                );
+            }
+        }
+
+        let mut runner = Self {
+            work_dir,
+            backend,
+            spirv_bytes,
+            input_words: generate::input_words(intrinsics),
+            subgroup_size: 0,
+        };
+        runner.subgroup_size = runner.probe_subgroup_size()?;
+        Ok(runner)
+    }
+
+    pub fn input_words(&self) -> usize {
+        self.input_words
+    }
+
+    /// Runs the kernel named `entry_point` as SPIR-V, on `input` (a whole
+    /// number of workgroups).
+    pub fn run_spirv(&self, entry_point: &str, input: &[u32]) -> Result<Vec<u32>> {
+        let workgroups = input.len() / (WORKGROUP_SIZE * self.input_words);
+        let buffers = vec![
+            BufferConfig::read_only(input),
+            BufferConfig::writeback(workgroups * WORKGROUP_SIZE * OUTPUT_WORDS * 4),
+        ];
+        let outputs = self.backend.run_compute(
+            &self.spirv_bytes,
+            entry_point,
+            [workgroups as u32, 1, 1],
+            buffers,
+        )?;
+        Ok(words(&outputs[1]))
+    }
+
+    /// The subgroup size of the backend, which must be the same for every
+    /// invocation (and not larger than a workgroup).
+    fn probe_subgroup_size(&self) -> Result<usize> {
+        let input = vec![0; WORKGROUP_SIZE * self.input_words];
+        let sizes = self.run_spirv("probe_subgroup_size", &input)?;
+        let size = sizes[0] as usize;
+        if sizes[..WORKGROUP_SIZE].iter().any(|&s| s as usize != size) {
+            bail!("the subgroup size varies: {:?}", &sizes[..WORKGROUP_SIZE]);
+        }
+        if !size.is_power_of_two() || size > WORKGROUP_SIZE {
+            bail!("unsupported subgroup size {size}");
+        }
+        Ok(size)
+    }
+
+    /// Runs the kernels natively, with the CPU emulation, in a single process
+    /// (as building it is what takes time).
+    pub fn run_reference<'a>(
+        &self,
+        inputs: impl IntoIterator<Item = (&'a str, &'a [u32])>,
+    ) -> Result<Vec<Vec<u32>>> {
+        let io_dir = self.work_dir.join("io");
+        if io_dir.exists() {
+            fs::remove_dir_all(&io_dir)?;
+        }
+        fs::create_dir_all(&io_dir)?;
+        let names: Vec<&str> = inputs
+            .into_iter()
+            .map(|(name, input)| {
+                let bytes: Vec<u8> = input.iter().flat_map(|word| word.to_le_bytes()).collect();
+                fs::write(io_dir.join(format!("{name}.input")), bytes)?;
+                Ok(name)
+            })
+            .collect::<Result<_>>()?;
+
+        let output = Command::new(env!("CARGO"))
+            .args(["run", "--release", "--quiet", "--manifest-path"])
+            .arg(self.work_dir.join("Cargo.toml"))
+            .arg("--")
+            .arg(self.subgroup_size.to_string())
+            .arg(&io_dir)
+            .env_remove("CARGO_TARGET_DIR")
+            .output()
+            .context("failed to run cargo")?;
+        if !output.status.success() {
+            bail!(
+                "running the kernels natively failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        names
+            .iter()
+            .map(|name| {
+                let path = io_dir.join(format!("{name}.output"));
+                let bytes = fs::read(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                Ok(words(&bytes))
+            })
+            .collect()
+    }
+}
+
+fn words(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect()
+}
+
+/// Writes the kernel crate to `dir`, with a library containing an entry point
+/// per intrinsic, and a binary running them on the CPU.
+fn write_crate(dir: &Path, intrinsics: &[Intrinsic]) -> Result<()> {
+    let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let spirv_std = workspace_root.join("crates/spirv-std");
+    let spirv_std = spirv_std
+        .canonicalize()
+        .with_context(|| format!("failed to find spirv-std at {}", spirv_std.display()))?;
+
+    fs::create_dir_all(dir.join("src"))?;
+
+    // Start from the workspace's lockfile, to use the same versions of
+    // spirv-std's dependencies as everything else.
+    let lockfile = dir.join("Cargo.lock");
+    let workspace_lockfile = workspace_root.join("Cargo.lock");
+    if !lockfile.exists() && workspace_lockfile.exists() {
+        fs::copy(workspace_lockfile, lockfile)?;
+    }
+
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\n\
+             name = \"intrinsic-kernels\"\n\
+             version = \"0.0.0\"\n\
+             edition = \"2021\"\n\
+             publish = false\n\
+             \n\
+             [dependencies]\n\
+             spirv-std = {{ path = {spirv_std:?} }}\n\
+             \n\
+             # The reference uses the CPU emulation.\n\
+             [target.'cfg(not(target_arch = \"spirv\"))'.dependencies]\n\
+             spirv-std = {{ path = {spirv_std:?}, features = [\"cpu-emulation\"] }}\n\
+             \n\
+             # Not part of any enclosing workspace.\n\
+             [workspace]\n",
+            spirv_std = spirv_std.display().to_string()
+        ),
+    )?;
+    fs::write(dir.join("src/lib.rs"), generate::render_lib(intrinsics))?;
+    fs::write(dir.join("src/main.rs"), generate::render_main(intrinsics))?;
+    Ok(())
+}
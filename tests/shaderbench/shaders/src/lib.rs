@@ -0,0 +1,138 @@
+//! Shaders benchmarked by `cargo shaderbench` (alongside `examples/shaders`),
+//! covering patterns the examples don't: nested loops over buffers, arrays of
+//! structs in uniforms, and workgroup memory with barriers.
+//!
+//! Changing these shaders changes their metrics, so any baselines have to be
+//! regenerated afterwards.
+
+#![cfg_attr(target_arch = "spirv", no_std)]
+// HACK(eddyb) can't easily see warnings otherwise from `spirv-builder` builds.
+#![deny(warnings)]
+
+use spirv_std::glam::{UVec3, Vec3, Vec4, vec3};
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::Float;
+use spirv_std::spirv;
+
+pub const BLUR_WIDTH: u32 = 256;
+pub const BLUR_RADIUS: i32 = 2;
+
+/// A box blur of a `BLUR_WIDTH`-wide single channel image.
+#[spirv(compute(threads(8, 8)))]
+pub fn blur(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] input: &[f32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] output: &mut [f32],
+) {
+    let height = input.len() as u32 / BLUR_WIDTH;
+    if id.x >= BLUR_WIDTH || id.y >= height {
+        return;
+    }
+
+    let mut sum = 0.0;
+    let mut weight = 0.0;
+    for dy in -BLUR_RADIUS..=BLUR_RADIUS {
+        for dx in -BLUR_RADIUS..=BLUR_RADIUS {
+            let x = (id.x as i32 + dx).clamp(0, BLUR_WIDTH as i32 - 1) as u32;
+            let y = (id.y as i32 + dy).clamp(0, height as i32 - 1) as u32;
+            let w = 1.0 / (1.0 + (dx * dx + dy * dy) as f32);
+            sum += input[(y * BLUR_WIDTH + x) as usize] * w;
+            weight += w;
+        }
+    }
+    output[(id.y * BLUR_WIDTH + id.x) as usize] = sum / weight;
+}
+
+pub const MAX_LIGHTS: usize = 8;
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Light {
+    /// `xyz` is the position (or direction, for directional lights).
+    pub position: Vec4,
+    /// `rgb` is the color, `a` the intensity.
+    pub color: Vec4,
+    /// 0 for point lights, 1 for directional lights, anything else is off.
+    pub kind: u32,
+    // NOTE arrays (of any stride other than 16) aren't allowed here, in uniforms.
+    pub _pad0: u32,
+    pub _pad1: u32,
+    pub _pad2: u32,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Lights {
+    pub lights: [Light; MAX_LIGHTS],
+    pub count: u32,
+    pub shininess: f32,
+    pub _pad0: u32,
+    pub _pad1: u32,
+}
+
+fn shade(light: &Light, position: Vec3, normal: Vec3, view: Vec3, shininess: f32) -> Vec3 {
+    let (dir, attenuation) = match light.kind {
+        0 => {
+            let to_light = light.position.truncate() - position;
+            let distance = to_light.length();
+            (to_light / distance, 1.0 / (1.0 + distance * distance))
+        }
+        1 => (-light.position.truncate().normalize(), 1.0),
+        _ => return Vec3::ZERO,
+    };
+    let diffuse = normal.dot(dir).max(0.0);
+    let half = (dir + view).normalize();
+    let specular = normal.dot(half).max(0.0).powf(shininess);
+    light.color.truncate() * light.color.w * attenuation * (diffuse + specular)
+}
+
+/// Blinn-Phong shading, with a variable number of lights.
+#[spirv(fragment)]
+pub fn lighting(
+    position: Vec3,
+    normal: Vec3,
+    #[spirv(uniform, descriptor_set = 0, binding = 0)] lights: &Lights,
+    #[spirv(push_constant)] camera: &Vec4,
+    output: &mut Vec4,
+) {
+    let normal = normal.normalize();
+    let view = (camera.truncate() - position).normalize();
+    let mut color = vec3(0.03, 0.03, 0.03);
+    let count = (lights.count as usize).min(MAX_LIGHTS);
+    for i in 0..count {
+        color += shade(&lights.lights[i], position, normal, view, lights.shininess);
+    }
+    *output = color.extend(1.0);
+}
+
+pub const SCAN_THREADS: u32 = 64;
+
+/// An inclusive prefix sum of each `SCAN_THREADS`-sized chunk of `data`.
+#[spirv(compute(threads(64)))]
+pub fn prefix_sum(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(local_invocation_id)] local_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] data: &mut [u32],
+    #[spirv(workgroup)] shared: &mut [u32; SCAN_THREADS as usize],
+) {
+    let (global, local) = (global_id.x as usize, local_id.x as usize);
+    shared[local] = if global < data.len() { data[global] } else { 0 };
+    spirv_std::arch::workgroup_memory_barrier_with_group_sync();
+
+    let mut offset = 1;
+    while offset < SCAN_THREADS as usize {
+        let value = if local >= offset {
+            shared[local - offset]
+        } else {
+            0
+        };
+        spirv_std::arch::workgroup_memory_barrier_with_group_sync();
+        shared[local] += value;
+        spirv_std::arch::workgroup_memory_barrier_with_group_sync();
+        offset *= 2;
+    }
+
+    if global < data.len() {
+        data[global] = shared[local];
+    }
+}
@@ -0,0 +1,164 @@
+//! Benchmarks of the SPIR-V rust-gpu emits: a corpus of shader crates is built
+//! (one module per entry point), and metrics of every entry point's module are
+//! recorded (along with how long each crate took to compile), to be compared
+//! against a baseline from an earlier run (e.g. before a rust-gpu upgrade).
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use spirv_builder::{Capability, ModuleResult, SpirvBuilder};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+mod metrics;
+mod results;
+
+use metrics::Metrics;
+use results::{Results, ShaderResults};
+
+/// The shader crates benchmarked (relative to the workspace root), and the
+/// capabilities they need.
+const CORPUS: &[(&str, &[Capability])] = &[
+    ("examples/shaders/compute-shader", &[]),
+    ("examples/shaders/mouse-shader", &[]),
+    (
+        "examples/shaders/reduce",
+        &[Capability::GroupNonUniformArithmetic],
+    ),
+    ("examples/shaders/simplest-shader", &[]),
+    ("examples/shaders/sky-shader", &[]),
+    ("tests/shaderbench/shaders", &[]),
+];
+
+#[derive(Parser)]
+#[command(bin_name = "cargo shaderbench")]
+struct Opt {
+    /// The target to build the shaders for.
+    #[arg(long, default_value = "spirv-unknown-vulkan1.2")]
+    target: String,
+
+    /// Where to write the results, as JSON (defaults to
+    /// `target/shaderbench/results.json`).
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Results of an earlier run (i.e. its `--output`) to compare against.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// How much (in percent) a metric has to grow by, compared to the
+    /// `--baseline`, to count as a regression.
+    #[arg(long, default_value_t = 0.0)]
+    threshold: f64,
+
+    /// Only benchmark shader crates whose paths contain one of these filters.
+    #[arg(name = "FILTER")]
+    filters: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::parse();
+    let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let workspace_root = workspace_root.canonicalize()?;
+
+    // Load the baseline first, to not find out it's missing after benchmarking.
+    let baseline = opt.baseline.as_deref().map(Results::load).transpose()?;
+
+    let mut results = Results {
+        target: opt.target.clone(),
+        shaders: BTreeMap::new(),
+    };
+    for &(path, capabilities) in CORPUS {
+        if !opt.filters.is_empty() && !opt.filters.iter().any(|f| path.contains(f.as_str())) {
+            continue;
+        }
+        println!("Benchmarking {path}");
+        let shader_results = bench_shader(&workspace_root.join(path), &opt.target, capabilities)
+            .with_context(|| format!("failed to benchmark {path}"))?;
+        print_shader_results(&shader_results);
+        results.shaders.insert(path.to_string(), shader_results);
+    }
+
+    let output = (opt.output.clone())
+        .unwrap_or_else(|| workspace_root.join("target/shaderbench/results.json"));
+    results.save(&output)?;
+    println!("Results written to {}", output.display());
+
+    if let Some(baseline) = &baseline {
+        let comparison = results::compare(baseline, &results, opt.threshold);
+        println!("\nCompared to {}:", opt.baseline.unwrap().display());
+        print!("{}", comparison.report);
+        if comparison.regressions > 0 {
+            bail!(
+                "{} metrics regressed (by more than {}%)",
+                comparison.regressions,
+                opt.threshold
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Builds the shader crate at `path`, and measures its entry points.
+fn bench_shader(path: &Path, target: &str, capabilities: &[Capability]) -> Result<ShaderResults> {
+    let mut builder = SpirvBuilder::new(path, target)
+        .release(true)
+        .multimodule(true);
+    for &capability in capabilities {
+        builder = builder.capability(capability);
+    }
+
+    // Build everything once, then only rebuild the shader crate itself (and not
+    // e.g. `spirv-std`), for the compile time to be comparable between runs.
+    builder.build()?;
+    fs::File::options()
+        .append(true)
+        .open(path.join("src/lib.rs"))?
+        .set_modified(SystemTime::now())?;
+    let start = Instant::now();
+    let compile_result = builder.build()?;
+    let compile_time_secs = start.elapsed().as_secs_f64();
+
+    let ModuleResult::MultiModule(modules) = compile_result.module else {
+        bail!("expected one module per entry point");
+    };
+    let mut entry_points = BTreeMap::new();
+    for (entry_point, module) in modules {
+        let bytes =
+            fs::read(&module).with_context(|| format!("failed to read {}", module.display()))?;
+        let metrics = Metrics::from_spv_bytes(&bytes)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("failed to parse {}", module.display()))?;
+        entry_points.insert(entry_point, metrics);
+    }
+    Ok(ShaderResults {
+        compile_time_secs,
+        entry_points,
+    })
+}
+
+fn print_shader_results(results: &ShaderResults) {
+    println!("  compile time: {:.2}s", results.compile_time_secs);
+    let width = (results.entry_points.keys())
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max("entry point".len());
+    println!(
+        "  {:width$}  {:>8}  {:>8}  {:>9}  {:>6}  {:>8}  {:>8}",
+        "entry point", "bytes", "instrs", "functions", "blocks", "max live", "fn vars"
+    );
+    for (entry_point, metrics) in &results.entry_points {
+        println!(
+            "  {:width$}  {:>8}  {:>8}  {:>9}  {:>6}  {:>8}  {:>8}",
+            entry_point,
+            metrics.size_bytes,
+            metrics.instructions,
+            metrics.functions,
+            metrics.blocks,
+            metrics.max_live_values,
+            metrics.function_variables
+        );
+    }
+}
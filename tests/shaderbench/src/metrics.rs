@@ -0,0 +1,393 @@
+//! Metrics of a (single entry point) SPIR-V module.
+
+use rspirv::dr::{self, Operand};
+use rspirv::spirv::{Op, StorageClass, Word};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub size_bytes: usize,
+    pub instructions: usize,
+    /// Instruction counts by (roughly, the SPIR-V grammar's) opcode class, see
+    /// `opcode_class`.
+    pub instructions_by_class: BTreeMap<String, usize>,
+    pub functions: usize,
+    pub blocks: usize,
+    /// The maximum, over all functions, of the number of values live at the
+    /// same time, see `max_live_values`.
+    pub max_live_values: usize,
+    /// `OpVariable`s in the `Function` storage class, i.e. any memory that
+    /// wasn't promoted to SSA values.
+    pub function_variables: usize,
+}
+
+impl Metrics {
+    pub fn from_spv_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let module = dr::load_bytes(bytes).map_err(|e| e.to_string())?;
+
+        let mut instructions_by_class = BTreeMap::new();
+        for inst in module.all_inst_iter() {
+            *instructions_by_class
+                .entry(opcode_class(inst.class.opcode).to_string())
+                .or_insert(0) += 1;
+        }
+
+        let function_variables = (module.functions.iter())
+            .flat_map(|func| &func.blocks)
+            .flat_map(|block| &block.instructions)
+            .filter(|inst| {
+                inst.class.opcode == Op::Variable
+                    && inst.operands.first() == Some(&Operand::StorageClass(StorageClass::Function))
+            })
+            .count();
+
+        Ok(Self {
+            size_bytes: bytes.len(),
+            instructions: instructions_by_class.values().sum(),
+            instructions_by_class,
+            functions: module.functions.len(),
+            blocks: module.functions.iter().map(|func| func.blocks.len()).sum(),
+            max_live_values: module
+                .functions
+                .iter()
+                .map(max_live_values)
+                .max()
+                .unwrap_or(0),
+            function_variables,
+        })
+    }
+
+    /// All the metrics, by name (with `instructions_by_class` flattened into
+    /// `instructions.<class>`).
+    pub fn named_values(&self) -> BTreeMap<String, usize> {
+        let mut values: BTreeMap<_, _> = [
+            ("size_bytes", self.size_bytes),
+            ("instructions", self.instructions),
+            ("functions", self.functions),
+            ("blocks", self.blocks),
+            ("max_live_values", self.max_live_values),
+            ("function_variables", self.function_variables),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        for (class, &count) in &self.instructions_by_class {
+            values.insert(format!("instructions.{class}"), count);
+        }
+        values
+    }
+}
+
+/// The class of an opcode, mostly following the classes of the SPIR-V grammar
+/// (but merging some of the smaller ones).
+pub fn opcode_class(op: Op) -> &'static str {
+    let name = format!("{op:?}");
+    match op {
+        Op::Capability
+        | Op::Extension
+        | Op::ExtInstImport
+        | Op::MemoryModel
+        | Op::EntryPoint
+        | Op::ExecutionMode
+        | Op::ExecutionModeId => "mode-setting",
+        Op::Source
+        | Op::SourceContinued
+        | Op::SourceExtension
+        | Op::Name
+        | Op::MemberName
+        | Op::String
+        | Op::Line
+        | Op::NoLine
+        | Op::ModuleProcessed => "debug",
+        Op::Decorate
+        | Op::DecorateId
+        | Op::DecorateString
+        | Op::MemberDecorate
+        | Op::MemberDecorateString
+        | Op::DecorationGroup
+        | Op::GroupDecorate
+        | Op::GroupMemberDecorate => "annotation",
+        Op::Variable
+        | Op::Load
+        | Op::Store
+        | Op::CopyMemory
+        | Op::CopyMemorySized
+        | Op::AccessChain
+        | Op::InBoundsAccessChain
+        | Op::PtrAccessChain
+        | Op::InBoundsPtrAccessChain
+        | Op::ArrayLength
+        | Op::PtrEqual
+        | Op::PtrNotEqual
+        | Op::PtrDiff => "memory",
+        Op::Function | Op::FunctionParameter | Op::FunctionEnd | Op::FunctionCall => "function",
+        Op::Label
+        | Op::Phi
+        | Op::LoopMerge
+        | Op::SelectionMerge
+        | Op::Branch
+        | Op::BranchConditional
+        | Op::Switch
+        | Op::Kill
+        | Op::Return
+        | Op::ReturnValue
+        | Op::Unreachable
+        | Op::TerminateInvocation
+        | Op::DemoteToHelperInvocation => "control-flow",
+        Op::VectorExtractDynamic
+        | Op::VectorInsertDynamic
+        | Op::VectorShuffle
+        | Op::CompositeConstruct
+        | Op::CompositeExtract
+        | Op::CompositeInsert
+        | Op::CopyObject
+        | Op::CopyLogical
+        | Op::Transpose => "composite",
+        Op::UConvert
+        | Op::SConvert
+        | Op::FConvert
+        | Op::QuantizeToF16
+        | Op::SatConvertSToU
+        | Op::SatConvertUToS
+        | Op::Bitcast => "conversion",
+        Op::SNegate
+        | Op::FNegate
+        | Op::IAdd
+        | Op::FAdd
+        | Op::ISub
+        | Op::FSub
+        | Op::IMul
+        | Op::FMul
+        | Op::UDiv
+        | Op::SDiv
+        | Op::FDiv
+        | Op::UMod
+        | Op::SRem
+        | Op::SMod
+        | Op::FRem
+        | Op::FMod
+        | Op::VectorTimesScalar
+        | Op::MatrixTimesScalar
+        | Op::VectorTimesMatrix
+        | Op::MatrixTimesVector
+        | Op::MatrixTimesMatrix
+        | Op::OuterProduct
+        | Op::Dot
+        | Op::IAddCarry
+        | Op::ISubBorrow
+        | Op::UMulExtended
+        | Op::SMulExtended => "arithmetic",
+        Op::ShiftRightLogical
+        | Op::ShiftRightArithmetic
+        | Op::ShiftLeftLogical
+        | Op::BitwiseOr
+        | Op::BitwiseXor
+        | Op::BitwiseAnd
+        | Op::Not
+        | Op::BitFieldInsert
+        | Op::BitFieldSExtract
+        | Op::BitFieldUExtract
+        | Op::BitReverse
+        | Op::BitCount => "bit",
+        Op::Any
+        | Op::All
+        | Op::IsNan
+        | Op::IsInf
+        | Op::IsFinite
+        | Op::IsNormal
+        | Op::SignBitSet
+        | Op::LessOrGreater
+        | Op::Ordered
+        | Op::Unordered
+        | Op::LogicalEqual
+        | Op::LogicalNotEqual
+        | Op::LogicalOr
+        | Op::LogicalAnd
+        | Op::LogicalNot
+        | Op::Select
+        | Op::IEqual
+        | Op::INotEqual
+        | Op::UGreaterThan
+        | Op::SGreaterThan
+        | Op::UGreaterThanEqual
+        | Op::SGreaterThanEqual
+        | Op::ULessThan
+        | Op::SLessThan
+        | Op::ULessThanEqual
+        | Op::SLessThanEqual => "relational",
+        // Mostly `GLSL.std.450` instructions.
+        Op::ExtInst => "extended",
+        Op::ControlBarrier | Op::MemoryBarrier => "barrier",
+        Op::Undef => "constant",
+        _ if name.starts_with("FOrd") || name.starts_with("FUnord") => "relational",
+        _ if name.starts_with("Convert") => "conversion",
+        _ if name.starts_with("Type") => "type",
+        _ if name.starts_with("Constant") || name.starts_with("SpecConstant") => "constant",
+        _ if name.starts_with("Atomic") => "atomic",
+        _ if name.contains("Image") => "image",
+        _ if name.starts_with("Group") || name.starts_with("Subgroup") => "group",
+        _ => "other",
+    }
+}
+
+/// Estimates the number of (SSA) values live at the same time in `func`.
+///
+/// This is a linear scan over the blocks in order, with each value live from
+/// its definition to its last use (and `OpPhi` inputs used at the end of their
+/// predecessor), so values only used before a loop's back-edge, but still live
+/// around it, are missed. It's only meant to show trends, e.g. a spike after
+/// a change to inlining, not to predict register pressure.
+pub fn max_live_values(func: &dr::Function) -> usize {
+    // Positions of definitions, and the ends of blocks (for `OpPhi`).
+    let mut defs = HashMap::<Word, usize>::new();
+    let mut block_ends = HashMap::<Word, usize>::new();
+    let mut pos = 0;
+    for param in &func.parameters {
+        defs.extend(param.result_id.map(|id| (id, pos)));
+    }
+    for block in &func.blocks {
+        for inst in &block.instructions {
+            pos += 1;
+            if inst.result_type.is_some() {
+                defs.extend(inst.result_id.map(|id| (id, pos)));
+            }
+        }
+        block_ends.extend(block.label_id().map(|id| (id, pos)));
+    }
+
+    let mut last_uses = HashMap::<Word, usize>::new();
+    let mut pos = 0;
+    for block in &func.blocks {
+        for inst in &block.instructions {
+            pos += 1;
+            let uses: Vec<(Word, usize)> = if inst.class.opcode == Op::Phi {
+                (inst.operands.chunks(2))
+                    .filter_map(|pair| match pair {
+                        [Operand::IdRef(value), Operand::IdRef(pred)] => {
+                            Some((*value, block_ends.get(pred).copied().unwrap_or(pos)))
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            } else {
+                (inst.operands.iter())
+                    .filter_map(|operand| operand.id_ref_any().map(|id| (id, pos)))
+                    .collect()
+            };
+            for (id, use_pos) in uses {
+                if defs.contains_key(&id) {
+                    let last_use = last_uses.entry(id).or_insert(use_pos);
+                    *last_use = (*last_use).max(use_pos);
+                }
+            }
+        }
+    }
+
+    // Sweep over the live ranges (inclusive of both ends).
+    let mut deltas = vec![0isize; pos + 2];
+    for (id, &def) in &defs {
+        let end = last_uses.get(id).copied().unwrap_or(def).max(def);
+        deltas[def] += 1;
+        deltas[end + 1] -= 1;
+    }
+    let mut live = 0;
+    let mut max_live = 0;
+    for delta in deltas {
+        live += delta;
+        max_live = max_live.max(live);
+    }
+    max_live as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rspirv::binary::Assemble as _;
+    use rspirv::spirv::{AddressingModel, ExecutionModel, FunctionControl, MemoryModel};
+
+    /// A module with one function, adding its two parameters (`uses_first`
+    /// times), after which the first parameter is no longer used.
+    fn module(uses_first: usize) -> Vec<u8> {
+        let mut b = dr::Builder::new();
+        b.memory_model(AddressingModel::Logical, MemoryModel::Simple);
+        let void = b.type_void();
+        let u32_ty = b.type_int(32, 0);
+        let fn_ty = b.type_function(u32_ty, [u32_ty, u32_ty]);
+        let main_ty = b.type_function(void, []);
+
+        let add = b
+            .begin_function(u32_ty, None, FunctionControl::NONE, fn_ty)
+            .unwrap();
+        let x = b.function_parameter(u32_ty).unwrap();
+        let y = b.function_parameter(u32_ty).unwrap();
+        b.begin_block(None).unwrap();
+        let ptr_ty = b.type_pointer(None, StorageClass::Function, u32_ty);
+        let var = b.variable(ptr_ty, None, StorageClass::Function, None);
+        let mut acc = y;
+        for _ in 0..uses_first {
+            acc = b.i_add(u32_ty, None, acc, x).unwrap();
+        }
+        b.store(var, acc, None, []).unwrap();
+        let doubled = b.i_add(u32_ty, None, acc, acc).unwrap();
+        b.ret_value(doubled).unwrap();
+        b.end_function().unwrap();
+
+        let main = b
+            .begin_function(void, None, FunctionControl::NONE, main_ty)
+            .unwrap();
+        b.begin_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(ExecutionModel::GLCompute, main, "main", []);
+        let _ = add;
+
+        b.module()
+            .assemble()
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .collect()
+    }
+
+    #[test]
+    fn test_metrics() {
+        let bytes = module(2);
+        let metrics = Metrics::from_spv_bytes(&bytes).unwrap();
+        assert_eq!(metrics.size_bytes, bytes.len());
+        assert_eq!(metrics.functions, 2);
+        assert_eq!(metrics.blocks, 2);
+        assert_eq!(metrics.function_variables, 1);
+        assert_eq!(metrics.instructions_by_class["arithmetic"], 3);
+        assert_eq!(metrics.instructions_by_class["memory"], 2);
+        assert_eq!(
+            metrics.instructions,
+            metrics.instructions_by_class.values().sum::<usize>()
+        );
+        // `x`, the variable, and the operand and result of a sum (as a value's
+        // last use still overlaps the definition of the result).
+        assert_eq!(metrics.max_live_values, 4);
+    }
+
+    #[test]
+    fn test_max_live_values_ends_at_last_use() {
+        // More uses of `x` don't keep more values alive.
+        let live = |uses_first| {
+            Metrics::from_spv_bytes(&module(uses_first))
+                .unwrap()
+                .max_live_values
+        };
+        assert_eq!(live(1), live(5));
+    }
+
+    #[test]
+    fn test_opcode_class() {
+        assert_eq!(opcode_class(Op::TypeInt), "type");
+        assert_eq!(opcode_class(Op::ConstantComposite), "constant");
+        assert_eq!(opcode_class(Op::FOrdLessThan), "relational");
+        assert_eq!(opcode_class(Op::ConvertFToU), "conversion");
+        assert_eq!(opcode_class(Op::AtomicIAdd), "atomic");
+        assert_eq!(opcode_class(Op::ImageSampleImplicitLod), "image");
+        assert_eq!(opcode_class(Op::GroupNonUniformIAdd), "group");
+        assert_eq!(opcode_class(Op::Nop), "other");
+    }
+}
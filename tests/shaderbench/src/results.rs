@@ -0,0 +1,214 @@
+//! The results of a benchmark run (saved as JSON), and comparing them against
+//! a baseline.
+
+use crate::metrics::Metrics;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Results {
+    pub target: String,
+    /// By shader crate path (relative to the workspace root).
+    pub shaders: BTreeMap<String, ShaderResults>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShaderResults {
+    /// Time taken to rebuild the shader crate (but not its dependencies).
+    pub compile_time_secs: f64,
+    /// By entry point name.
+    pub entry_points: BTreeMap<String, Metrics>,
+}
+
+impl Results {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("failed to read results from {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse results from {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json)
+            .with_context(|| format!("failed to write results to {}", path.display()))
+    }
+}
+
+/// The differences between two sets of results.
+pub struct Comparison {
+    /// Human-readable report of everything that changed.
+    pub report: String,
+    /// How many metrics grew by more than the threshold.
+    pub regressions: usize,
+}
+
+/// Compares `current` against `baseline`, counting every metric which grew by
+/// more than `threshold_percent` as a regression.
+///
+/// Only shaders in `current` are compared (so filtered runs can be compared
+/// against a full baseline), and compile times are reported, but never count
+/// as regressions, as they're too noisy.
+pub fn compare(baseline: &Results, current: &Results, threshold_percent: f64) -> Comparison {
+    let mut report = String::new();
+    let mut regressions = 0;
+
+    if baseline.target != current.target {
+        writeln!(
+            report,
+            "warning: comparing {} against a baseline for {}",
+            current.target, baseline.target
+        )
+        .unwrap();
+    }
+
+    for (shader, results) in &current.shaders {
+        let Some(baseline_results) = baseline.shaders.get(shader) else {
+            writeln!(report, "{shader}: not in the baseline").unwrap();
+            continue;
+        };
+
+        let (old, new) = (
+            baseline_results.compile_time_secs,
+            results.compile_time_secs,
+        );
+        writeln!(
+            report,
+            "{shader}: compile time {old:.2}s -> {new:.2}s ({})",
+            percent_change(old, new)
+        )
+        .unwrap();
+
+        for (entry_point, metrics) in &results.entry_points {
+            let Some(baseline_metrics) = baseline_results.entry_points.get(entry_point) else {
+                writeln!(report, "  {entry_point}: not in the baseline").unwrap();
+                continue;
+            };
+            let baseline_values = baseline_metrics.named_values();
+            let values = metrics.named_values();
+            // Classes can appear in either, and are `0` in the other.
+            let names = baseline_values.keys().chain(values.keys());
+            let mut names: Vec<_> = names.collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                let old = baseline_values.get(name).copied().unwrap_or(0);
+                let new = values.get(name).copied().unwrap_or(0);
+                if old == new {
+                    continue;
+                }
+                let regressed = new as f64 > old as f64 * (1.0 + threshold_percent / 100.0);
+                if regressed {
+                    regressions += 1;
+                }
+                writeln!(
+                    report,
+                    "  {entry_point}: {name} {old} -> {new} ({}){}",
+                    percent_change(old as f64, new as f64),
+                    if regressed { " REGRESSION" } else { "" }
+                )
+                .unwrap();
+            }
+        }
+        for entry_point in baseline_results.entry_points.keys() {
+            if !results.entry_points.contains_key(entry_point) {
+                writeln!(report, "  {entry_point}: removed").unwrap();
+            }
+        }
+    }
+
+    Comparison {
+        report,
+        regressions,
+    }
+}
+
+fn percent_change(old: f64, new: f64) -> String {
+    if old == 0.0 {
+        return "new".to_string();
+    }
+    format!("{:+.1}%", (new - old) / old * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(instructions: usize, blocks: usize) -> Metrics {
+        Metrics {
+            size_bytes: instructions * 16,
+            instructions,
+            instructions_by_class: [("arithmetic".to_string(), instructions)].into(),
+            functions: 1,
+            blocks,
+            max_live_values: 10,
+            function_variables: 0,
+        }
+    }
+
+    fn results(entry_points: &[(&str, Metrics)], compile_time_secs: f64) -> Results {
+        Results {
+            target: "spirv-unknown-vulkan1.2".to_string(),
+            shaders: [(
+                "shader".to_string(),
+                ShaderResults {
+                    compile_time_secs,
+                    entry_points: entry_points
+                        .iter()
+                        .map(|(name, metrics)| (name.to_string(), metrics.clone()))
+                        .collect(),
+                },
+            )]
+            .into(),
+        }
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let baseline = results(&[("main", metrics(100, 4))], 1.0);
+        // Compile times never count as regressions.
+        let current = results(&[("main", metrics(100, 4))], 2.0);
+        let comparison = compare(&baseline, &current, 0.0);
+        assert_eq!(comparison.regressions, 0);
+        assert!(
+            !comparison.report.contains("  main"),
+            "{}",
+            comparison.report
+        );
+    }
+
+    #[test]
+    fn test_regressions() {
+        let baseline = results(&[("main", metrics(100, 4)), ("old", metrics(1, 1))], 1.0);
+        let current = results(&[("main", metrics(104, 2)), ("new", metrics(1, 1))], 1.0);
+
+        // `instructions`, `instructions.arithmetic` and `size_bytes` grew.
+        let comparison = compare(&baseline, &current, 0.0);
+        assert_eq!(comparison.regressions, 3, "{}", comparison.report);
+        assert!(comparison.report.contains("main: blocks 4 -> 2 (-50.0%)\n"));
+        assert!(
+            comparison
+                .report
+                .contains("main: instructions 100 -> 104 (+4.0%) REGRESSION\n")
+        );
+        assert!(comparison.report.contains("new: not in the baseline"));
+        assert!(comparison.report.contains("old: removed"));
+
+        assert_eq!(compare(&baseline, &current, 5.0).regressions, 0);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let results = results(&[("main", metrics(100, 4))], 1.5);
+        let json = serde_json::to_string(&results).unwrap();
+        assert_eq!(serde_json::from_str::<Results>(&json).unwrap(), results);
+    }
+}